@@ -79,6 +79,28 @@ create table if not exists watermark (
     created_at timestamp default current_timestamp not null
 );
 
+create table if not exists topition_usage (
+    id int generated always as identity primary key,
+    topition int references topition (id) on delete cascade not null,
+    unique (topition),
+    records bigint not null,
+    bytes bigint not null,
+    oldest_timestamp bigint,
+    newest_timestamp bigint,
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
+create table if not exists offset_translation (
+    id int generated always as identity primary key,
+    topition int references topition (id) on delete cascade not null,
+    upstream_offset bigint not null,
+    local_offset bigint not null,
+    unique (topition, upstream_offset),
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
 create table if not exists topic_configuration (
     id int generated always as identity primary key,
     topic int references topic (id) on delete cascade,
@@ -103,6 +125,71 @@ from
     join topic t on t.cluster = c.id
     join topic_configuration tc on tc.topic = t.id;
 
+create table if not exists acl (
+    id int generated always as identity primary key,
+    cluster int references cluster (id) on delete cascade not null,
+    resource_type int not null,
+    resource_name text not null,
+    pattern_type int not null,
+    principal text not null,
+    host text not null,
+    operation int not null,
+    permission_type int not null,
+    unique (cluster, resource_type, resource_name, pattern_type, principal, host, operation, permission_type),
+    created_at timestamp default current_timestamp not null
+);
+
+create table if not exists client_quota (
+    id int generated always as identity primary key,
+    cluster int references cluster (id) on delete cascade not null,
+    entity text not null,
+    quota text not null,
+    value double precision not null,
+    unique (cluster, entity, quota),
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
+create table if not exists feature (
+    id int generated always as identity primary key,
+    cluster int references cluster (id) on delete cascade not null,
+    name text not null,
+    level smallint not null,
+    updates bigint default 1 not null,
+    unique (cluster, name),
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
+create table if not exists coordinator_lease (
+    id int generated always as identity primary key,
+    cluster int references cluster (id) on delete cascade not null,
+    key_type smallint not null,
+    key text not null,
+    unique (cluster, key_type, key),
+    node int not null,
+    host text not null,
+    port int not null,
+    epoch int not null,
+    expires_at timestamp not null,
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
+create table if not exists user_scram_credential (
+    id int generated always as identity primary key,
+    cluster int references cluster (id) on delete cascade not null,
+    username text not null,
+    mechanism int not null,
+    unique (cluster, username, mechanism),
+    salt bytea not null,
+    iterations int not null,
+    stored_key bytea not null,
+    server_key bytea not null,
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
 create table if not exists record (
     topition int references topition (id) on delete cascade,
     offset_id bigint not null,
@@ -401,6 +488,18 @@ create table if not exists txn_produce_offset (
     created_at timestamp default current_timestamp not null
 );
 
+create table if not exists txn_aborted (
+    id int generated always as identity primary key,
+    topition int references topition (id) on delete cascade not null,
+    producer bigint references producer (id) on delete cascade not null,
+    first_offset bigint not null,
+    last_offset bigint not null,
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
+create index if not exists txn_aborted_topition on txn_aborted (topition, first_offset);
+
 create
 or replace view v_txn_produce_offset as
 select
@@ -0,0 +1,101 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- Record segments, applied after 010-schema.sql to a new database, or on its
+-- own to migrate an existing database with:
+--
+--   psql --file etc/initdb.d/020-record-segment.sql
+--
+-- The records and headers of each topition are held in their own partition,
+-- sub-partitioned by range of offset into segments. Retention drops whole
+-- segments rather than deleting rows. A topition with records already in the
+-- default partition (created before segments) remains there, using deletes.
+
+begin;
+
+-- Each partition is created detached and then attached, which takes a share
+-- update exclusive lock on its parent rather than the access exclusive lock
+-- of create table ... partition of, so that appending to and reading from the
+-- other partitions of the parent continues while a segment is added. The
+-- partition of a topition is attached to the record table when its topic is
+-- created, with the next segment of each topition attached during
+-- maintenance, ahead of the produce that would otherwise attach it.
+--
+-- Concurrent creation of the segments of a topition is serialized by an
+-- advisory lock in the namespace of this function.
+create
+or replace function tansu_record_segment (tp int, segment bigint, segment_offsets bigint) returns boolean language plpgsql as $$
+declare
+    record_tp text := format('record_%s', tp);
+    header_tp text := format('header_%s', tp);
+    record_segment text := format('record_%s_%s', tp, segment);
+    header_segment text := format('header_%s_%s', tp, segment);
+begin
+    if to_regclass(record_segment) is not null then
+        return true;
+    end if;
+
+    perform pg_advisory_xact_lock(hashtext('tansu_record_segment'), tp);
+
+    if to_regclass(record_segment) is not null then
+        return true;
+    end if;
+
+    if to_regclass(record_tp) is null then
+        if exists (select 1 from record_default where topition = tp) then
+            return false;
+        end if;
+
+        execute format('create table %I (like record including defaults) partition by range (offset_id)', record_tp);
+        execute format('alter table record attach partition %I for values in (%s)', record_tp, tp);
+
+        execute format('create table %I (like header including defaults) partition by range (offset_id)', header_tp);
+        execute format('alter table header attach partition %I for values in (%s)', header_tp, tp);
+    end if;
+
+    execute format('create table %I (like record including defaults)', record_segment);
+    execute format('alter table %I attach partition %I for values from (%s) to (%s)', record_tp, record_segment, segment * segment_offsets, (segment + 1) * segment_offsets);
+
+    execute format('create table %I (like header including defaults)', header_segment);
+    execute format('alter table %I attach partition %I for values from (%s) to (%s)', header_tp, header_segment, segment * segment_offsets, (segment + 1) * segment_offsets);
+
+    return true;
+end;
+$$;
+
+create
+or replace function tansu_record_segment_drop (tp int, segment bigint) returns table (records bigint, bytes bigint) language plpgsql as $$
+declare
+    record_segment text := format('record_%s_%s', tp, segment);
+    header_segment text := format('header_%s_%s', tp, segment);
+begin
+    if to_regclass(record_segment) is null then
+        records := 0;
+        bytes := 0;
+        return next;
+        return;
+    end if;
+
+    execute format('select count(*), coalesce(sum(coalesce(length(k), 0) + coalesce(length(v), 0)), 0) from %I', record_segment) into records, bytes;
+
+    execute format('drop table if exists %I', header_segment);
+    execute format('alter table %I detach partition %I', format('record_%s', tp), record_segment);
+    execute format('drop table %I', record_segment);
+
+    return next;
+end;
+$$;
+
+commit;
@@ -0,0 +1,178 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consumers each fetching every partition, fanning out the records produced
+
+use std::{collections::BTreeMap, time::SystemTime};
+
+use bytes::Bytes;
+use tansu_client::Client;
+use tansu_sans_io::{
+    BatchAttribute, ErrorCode, FetchRequest,
+    fetch_request::{FetchPartition, FetchTopic, ReplicaState},
+    record::inflated,
+};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, Stats, workload::produced};
+
+#[derive(Clone, Debug)]
+pub(crate) struct Consumer {
+    pub(crate) id: u32,
+    pub(crate) client: Client,
+    pub(crate) topic: String,
+    pub(crate) topic_id: [u8; 16],
+    pub(crate) positions: BTreeMap<i32, i64>,
+    pub(crate) high_watermarks: watch::Receiver<Option<BTreeMap<i32, i64>>>,
+    pub(crate) token: CancellationToken,
+}
+
+impl Consumer {
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn fetch(&mut self, stats: &mut Stats) -> Result<()> {
+        let response = self
+            .client
+            .call(
+                FetchRequest::default()
+                    .cluster_id(Some("".into()))
+                    .replica_id(Some(-1))
+                    .replica_state(Some(ReplicaState::default()))
+                    .max_wait_ms(500)
+                    .min_bytes(1)
+                    .max_bytes(Some(52_428_800))
+                    .isolation_level(Some(0))
+                    .session_id(Some(-1))
+                    .session_epoch(Some(-1))
+                    .topics(Some(
+                        [FetchTopic::default()
+                            .topic(Some(self.topic.clone()))
+                            .topic_id(Some(self.topic_id))
+                            .partitions(Some(
+                                self.positions
+                                    .iter()
+                                    .map(|(partition, position)| {
+                                        FetchPartition::default()
+                                            .partition(*partition)
+                                            .fetch_offset(*position)
+                                            .log_start_offset(Some(-1))
+                                            .partition_max_bytes(1_048_576)
+                                    })
+                                    .collect(),
+                            ))]
+                        .into(),
+                    ))
+                    .forgotten_topics_data(Some([].into()))
+                    .rack_id(Some("".into())),
+            )
+            .await?;
+
+        let now = SystemTime::now();
+
+        for partition in response
+            .responses
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+        {
+            let error_code = ErrorCode::try_from(partition.error_code)?;
+
+            if error_code != ErrorCode::None {
+                return Err(Error::Api(error_code));
+            }
+
+            let Some(position) = self.positions.get_mut(&partition.partition_index) else {
+                continue;
+            };
+
+            let Some(frame) = partition.records else {
+                continue;
+            };
+
+            for batch in inflated::Frame::try_from(frame)?.batches {
+                let last_offset = batch.base_offset + i64::from(batch.last_offset_delta);
+
+                if BatchAttribute::try_from(batch.attributes)?.control {
+                    *position = (*position).max(last_offset + 1);
+                    continue;
+                }
+
+                for record in batch.records {
+                    let offset = batch.base_offset + i64::from(record.offset_delta);
+
+                    if offset < *position {
+                        continue;
+                    }
+
+                    stats.consumed.records += 1;
+                    stats.consumed.bytes += (record.key.as_ref().map_or(0, Bytes::len)
+                        + record.value.as_ref().map_or(0, Bytes::len))
+                        as u64;
+
+                    if let Some(latency) = record
+                        .value
+                        .as_deref()
+                        .and_then(produced)
+                        .and_then(|produced| now.duration_since(produced).ok())
+                    {
+                        stats.end_to_end_latency.record_duration(latency);
+                    }
+
+                    *position = offset + 1;
+                }
+
+                *position = (*position).max(last_offset + 1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether every partition has been consumed up to its final high watermark
+    fn drained(&self) -> bool {
+        self.high_watermarks
+            .borrow()
+            .as_ref()
+            .is_some_and(|high_watermarks| {
+                high_watermarks.iter().all(|(partition, high_watermark)| {
+                    self.positions
+                        .get(partition)
+                        .is_some_and(|position| position >= high_watermark)
+                })
+            })
+    }
+
+    /// Consume until drained of everything produced, or cancelled
+    pub(crate) async fn run(mut self) -> Result<Stats> {
+        let mut stats = Stats::default();
+
+        while !self.drained() {
+            let token = self.token.clone();
+
+            tokio::select! {
+                cancelled = token.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+
+                fetched = self.fetch(&mut stats) => fetched?,
+            }
+        }
+
+        debug!(id = self.id, positions = ?self.positions);
+
+        Ok(stats)
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Log linear histogram of latencies in microseconds, accurate to within 1%
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Values below `2^(SUB_BUCKET_BITS + 1)` are recorded exactly
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = (u64::BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+fn index(value: u64) -> usize {
+    let magnitude = u64::BITS - value.leading_zeros();
+
+    if magnitude <= SUB_BUCKET_BITS + 1 {
+        value as usize
+    } else {
+        let shift = magnitude - SUB_BUCKET_BITS - 1;
+        ((shift as usize + 1) << SUB_BUCKET_BITS) + ((value >> shift) as usize & (SUB_BUCKETS - 1))
+    }
+}
+
+/// The lowest value recorded in a bucket
+fn lowest(index: usize) -> u64 {
+    if index < 2 * SUB_BUCKETS {
+        index as u64
+    } else {
+        let shift = (index >> SUB_BUCKET_BITS) - 1;
+        ((SUB_BUCKETS + (index & (SUB_BUCKETS - 1))) as u64) << shift
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            max: 0,
+        }
+    }
+}
+
+impl Histogram {
+    pub(crate) fn record(&mut self, value: u64) {
+        self.counts[index(value)] += 1;
+        self.total += 1;
+        self.max = self.max.max(value);
+    }
+
+    pub(crate) fn record_duration(&mut self, duration: Duration) {
+        self.record(u64::try_from(duration.as_micros()).unwrap_or(u64::MAX))
+    }
+
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    /// The value at a percentile (0.0 to 100.0) of the recorded values
+    pub(crate) fn percentile(&self, percentile: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+
+        let rank = ((percentile / 100.0 * self.total as f64).ceil() as u64).clamp(1, self.total);
+
+        let mut cumulative = 0;
+
+        for (index, count) in self.counts.iter().enumerate() {
+            cumulative += count;
+
+            if cumulative >= rank {
+                return lowest(index).min(self.max);
+            }
+        }
+
+        self.max
+    }
+
+    pub(crate) fn latency(&self) -> Latency {
+        let ms = |micros: u64| micros as f64 / 1_000.0;
+
+        Latency {
+            count: self.total,
+            p50_ms: ms(self.percentile(50.0)),
+            p90_ms: ms(self.percentile(90.0)),
+            p99_ms: ms(self.percentile(99.0)),
+            p999_ms: ms(self.percentile(99.9)),
+            max_ms: ms(self.max),
+        }
+    }
+}
+
+/// Latency percentiles in milliseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize)]
+pub(crate) struct Latency {
+    count: u64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    p999_ms: f64,
+    max_ms: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        for value in [0, 1, 255, 256, 257, 1_000, 65_535, 1 << 40, u64::MAX] {
+            let lowest = lowest(index(value));
+
+            assert!(lowest <= value, "{lowest} > {value}");
+            assert!(value - lowest <= value / SUB_BUCKETS as u64, "{value}");
+        }
+
+        assert_eq!(BUCKETS - 1, index(u64::MAX));
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut histogram = Histogram::default();
+
+        for value in 1..=1_000 {
+            histogram.record(value);
+        }
+
+        assert_eq!(0, Histogram::default().percentile(99.0));
+        assert_eq!(1, histogram.percentile(0.0));
+        assert_eq!(1_000, histogram.percentile(100.0));
+
+        let p50 = histogram.percentile(50.0);
+        assert!((496..=500).contains(&p50), "{p50}");
+
+        let p99 = histogram.percentile(99.0);
+        assert!((984..=990).contains(&p99), "{p99}");
+    }
+
+    #[test]
+    fn merge() {
+        let mut left = Histogram::default();
+        left.record(10);
+
+        let mut right = Histogram::default();
+        right.record(20);
+
+        left.merge(&right);
+
+        assert_eq!(2, left.total);
+        assert_eq!(20, left.max);
+        assert_eq!(10, left.percentile(50.0));
+    }
+}
@@ -0,0 +1,674 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Workload generator for performance comparison
+//!
+//! Producers send batches of records with keys drawn from a Zipfian
+//! distribution over a configurable cardinality, values of a uniformly
+//! distributed size and an optional compression, while each consumer fetches
+//! every partition, fanning out the records produced. Every value starts with
+//! the time it was produced, so that consumers can measure the end-to-end
+//! latency of each record. A JSON report of throughput and latency
+//! percentiles is written once producing has stopped and the consumers have
+//! drained the topic, labelled so that runs can be compared between releases
+//! and storage engines.
+
+use core::{
+    fmt::{self, Display},
+    result,
+};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write, stdout},
+    marker::PhantomData,
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::Serialize;
+use tansu_client::{Client, ConnectionManager};
+use tansu_sans_io::{
+    Compression, ErrorCode, ListOffsetsRequest, MetadataRequest, NULL_TOPIC_ID,
+    list_offsets_request::{ListOffsetsPartition, ListOffsetsTopic},
+    metadata_request::MetadataRequestTopic,
+};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::watch,
+    task::{JoinError, JoinSet},
+    time::{Instant, sleep, timeout},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    consumer::Consumer,
+    histogram::{Histogram, Latency},
+    producer::Producer,
+    workload::{Keys, Values},
+};
+
+mod consumer;
+mod histogram;
+mod producer;
+mod workload;
+
+pub type Result<T, E = Error> = result::Result<T, E>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    Api(ErrorCode),
+    Client(#[from] tansu_client::Error),
+    Io(Arc<io::Error>),
+    Json(#[from] serde_json::Error),
+    Protocol(#[from] tansu_sans_io::Error),
+    UnknownTopic(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(Arc::new(value))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Count {
+    pub(crate) records: u64,
+    pub(crate) bytes: u64,
+}
+
+/// Counts and latencies of a producer or consumer
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Stats {
+    pub(crate) produced: Count,
+    pub(crate) consumed: Count,
+    pub(crate) failed: u64,
+    pub(crate) produce_latency: Histogram,
+    pub(crate) end_to_end_latency: Histogram,
+}
+
+impl Stats {
+    fn merge(&mut self, other: &Self) {
+        self.produced.records += other.produced.records;
+        self.produced.bytes += other.produced.bytes;
+        self.consumed.records += other.consumed.records;
+        self.consumed.bytes += other.consumed.bytes;
+        self.failed += other.failed;
+        self.produce_latency.merge(&other.produce_latency);
+        self.end_to_end_latency.merge(&other.end_to_end_latency);
+    }
+
+    /// Merge the stats of a finished producer or consumer
+    fn tally(&mut self, joined: result::Result<Result<Self>, JoinError>) -> Result<()> {
+        match joined {
+            Ok(Ok(stats)) => {
+                self.merge(&stats);
+                Ok(())
+            }
+
+            Ok(Err(err)) => Err(err),
+
+            Err(err) => {
+                warn!(?err);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize)]
+struct Throughput {
+    records: u64,
+    bytes: u64,
+    records_per_second: f64,
+    bytes_per_second: f64,
+}
+
+impl Throughput {
+    fn new(count: Count, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+
+        Self {
+            records: count.records,
+            bytes: count.bytes,
+            records_per_second: count.records as f64 / seconds,
+            bytes_per_second: count.bytes as f64 / seconds,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize)]
+struct Report {
+    label: Option<String>,
+    broker: String,
+    topic: String,
+    partitions: i32,
+    producers: u32,
+    consumers: u32,
+    batch_size: u32,
+    min_record_size: usize,
+    max_record_size: usize,
+    key_cardinality: u64,
+    key_skew: f64,
+    compression: Compression,
+    acks: i16,
+    throughput: Option<u32>,
+    producing_secs: f64,
+    draining_secs: f64,
+    drained: bool,
+    produced: Throughput,
+    consumed: Throughput,
+    failed: u64,
+    produce_latency: Latency,
+    end_to_end_latency: Latency,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bench {
+    broker: Url,
+    topic: String,
+    label: Option<String>,
+    producers: u32,
+    consumers: u32,
+    batch_size: u32,
+    record_size: RangeInclusive<usize>,
+    key_cardinality: u64,
+    key_skew: f64,
+    compression: Compression,
+    acks: i16,
+    throughput: Option<u32>,
+    duration: Duration,
+    drain_timeout: Duration,
+    report: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Builder<B, T> {
+    broker: B,
+    topic: T,
+    label: Option<String>,
+    producers: u32,
+    consumers: u32,
+    batch_size: u32,
+    record_size: RangeInclusive<usize>,
+    key_cardinality: u64,
+    key_skew: f64,
+    compression: Compression,
+    acks: i16,
+    throughput: Option<u32>,
+    duration: Duration,
+    drain_timeout: Duration,
+    report: Option<PathBuf>,
+}
+
+impl Default for Builder<PhantomData<Url>, PhantomData<String>> {
+    fn default() -> Self {
+        Self {
+            broker: Default::default(),
+            topic: Default::default(),
+            label: None,
+            producers: 1,
+            consumers: 1,
+            batch_size: 100,
+            record_size: 1024..=1024,
+            key_cardinality: 0,
+            key_skew: 0.99,
+            compression: Compression::None,
+            acks: -1,
+            throughput: None,
+            duration: Duration::from_secs(60),
+            drain_timeout: Duration::from_secs(30),
+            report: None,
+        }
+    }
+}
+
+impl<B, T> Builder<B, T> {
+    pub fn broker(self, broker: impl Into<Url>) -> Builder<Url, T> {
+        Builder {
+            broker: broker.into(),
+            topic: self.topic,
+            label: self.label,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            record_size: self.record_size,
+            key_cardinality: self.key_cardinality,
+            key_skew: self.key_skew,
+            compression: self.compression,
+            acks: self.acks,
+            throughput: self.throughput,
+            duration: self.duration,
+            drain_timeout: self.drain_timeout,
+            report: self.report,
+        }
+    }
+
+    pub fn topic(self, topic: impl Into<String>) -> Builder<B, String> {
+        Builder {
+            broker: self.broker,
+            topic: topic.into(),
+            label: self.label,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            record_size: self.record_size,
+            key_cardinality: self.key_cardinality,
+            key_skew: self.key_skew,
+            compression: self.compression,
+            acks: self.acks,
+            throughput: self.throughput,
+            duration: self.duration,
+            drain_timeout: self.drain_timeout,
+            report: self.report,
+        }
+    }
+
+    /// Identify this run in the report, e.g., the release and storage engine
+    pub fn label(self, label: Option<String>) -> Self {
+        Self { label, ..self }
+    }
+
+    pub fn producers(self, producers: u32) -> Self {
+        Self { producers, ..self }
+    }
+
+    /// The number of consumers, each fetching every record produced
+    pub fn consumers(self, consumers: u32) -> Self {
+        Self { consumers, ..self }
+    }
+
+    pub fn batch_size(self, batch_size: u32) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            ..self
+        }
+    }
+
+    /// Record values are uniformly distributed over this range of sizes
+    pub fn record_size(self, record_size: RangeInclusive<usize>) -> Self {
+        Self {
+            record_size,
+            ..self
+        }
+    }
+
+    /// The number of distinct keys, with records unkeyed when zero
+    pub fn key_cardinality(self, key_cardinality: u64) -> Self {
+        Self {
+            key_cardinality,
+            ..self
+        }
+    }
+
+    /// The Zipfian exponent of the key distribution, with zero being uniform
+    pub fn key_skew(self, key_skew: f64) -> Self {
+        Self {
+            key_skew: key_skew.max(0.0),
+            ..self
+        }
+    }
+
+    pub fn compression(self, compression: Compression) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    /// Acknowledgement from the leader (1) or all in sync replicas (-1)
+    pub fn acks(self, acks: i16) -> Self {
+        Self { acks, ..self }
+    }
+
+    /// Pace producers to this total number of records per second
+    pub fn throughput(self, throughput: Option<u32>) -> Self {
+        Self {
+            throughput: throughput.filter(|throughput| *throughput > 0),
+            ..self
+        }
+    }
+
+    pub fn duration(self, duration: Duration) -> Self {
+        Self { duration, ..self }
+    }
+
+    /// Consumers must drain the topic within this time once producing stops
+    pub fn drain_timeout(self, drain_timeout: Duration) -> Self {
+        Self {
+            drain_timeout,
+            ..self
+        }
+    }
+
+    pub fn report(self, report: Option<PathBuf>) -> Self {
+        Self { report, ..self }
+    }
+}
+
+impl Builder<Url, String> {
+    pub fn build(self) -> Bench {
+        Bench {
+            broker: self.broker,
+            topic: self.topic,
+            label: self.label,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            record_size: self.record_size,
+            key_cardinality: self.key_cardinality,
+            key_skew: self.key_skew,
+            compression: self.compression,
+            acks: self.acks,
+            throughput: self.throughput,
+            duration: self.duration,
+            drain_timeout: self.drain_timeout,
+            report: self.report,
+        }
+    }
+}
+
+impl Bench {
+    pub fn builder() -> Builder<PhantomData<Url>, PhantomData<String>> {
+        Builder::default()
+    }
+
+    /// The topic id and number of partitions of the topic
+    async fn describe(&self, client: &Client) -> Result<([u8; 16], i32)> {
+        let response = client
+            .call(
+                MetadataRequest::default()
+                    .allow_auto_topic_creation(Some(false))
+                    .include_cluster_authorized_operations(Some(false))
+                    .include_topic_authorized_operations(Some(false))
+                    .topics(Some(
+                        [MetadataRequestTopic::default()
+                            .name(Some(self.topic.clone()))
+                            .topic_id(Some(NULL_TOPIC_ID))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        response
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .find(|topic| {
+                topic.error_code == i16::from(ErrorCode::None)
+                    && topic.name.as_deref() == Some(self.topic.as_str())
+            })
+            .and_then(|topic| {
+                topic.topic_id.map(|topic_id| {
+                    (
+                        topic_id,
+                        topic
+                            .partitions
+                            .map_or(0, |partitions| partitions.len() as i32),
+                    )
+                })
+            })
+            .ok_or(Error::UnknownTopic(self.topic.clone()))
+    }
+
+    /// The high watermark of each partition of the topic
+    async fn high_watermarks(
+        &self,
+        client: &Client,
+        partitions: i32,
+    ) -> Result<BTreeMap<i32, i64>> {
+        let response = client
+            .call(
+                ListOffsetsRequest::default()
+                    .replica_id(-1)
+                    .isolation_level(Some(0))
+                    .topics(Some(
+                        [ListOffsetsTopic::default()
+                            .name(self.topic.clone())
+                            .partitions(Some(
+                                (0..partitions)
+                                    .map(|partition_index| {
+                                        ListOffsetsPartition::default()
+                                            .partition_index(partition_index)
+                                            .current_leader_epoch(Some(-1))
+                                            .timestamp(-1)
+                                            .max_num_offsets(None)
+                                    })
+                                    .collect(),
+                            ))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        response
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+            .map(|partition| {
+                ErrorCode::try_from(partition.error_code)
+                    .map_err(Into::into)
+                    .and_then(|error_code| {
+                        if error_code == ErrorCode::None {
+                            Ok((
+                                partition.partition_index,
+                                partition.offset.unwrap_or_default(),
+                            ))
+                        } else {
+                            Err(Error::Api(error_code))
+                        }
+                    })
+            })
+            .collect()
+    }
+
+    /// The interval between batches of each producer to meet the throughput
+    fn pacing(&self) -> Option<Duration> {
+        self.throughput.map(|throughput| {
+            Duration::from_secs_f64(
+                f64::from(self.batch_size) * f64::from(self.producers.max(1))
+                    / f64::from(throughput),
+            )
+        })
+    }
+
+    fn write(&self, report: &Report) -> Result<()> {
+        if let Some(ref path) = self.report {
+            File::create(path)
+                .map_err(Into::into)
+                .and_then(|file| serde_json::to_writer_pretty(file, report).map_err(Into::into))
+        } else {
+            let mut stdout = stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, report)?;
+            writeln!(stdout).map_err(Into::into)
+        }
+    }
+
+    pub async fn main(self) -> Result<ErrorCode> {
+        let producing = CancellationToken::new();
+        let token = CancellationToken::new();
+
+        let mut interrupt_signal = signal(SignalKind::interrupt())?;
+        let mut terminate_signal = signal(SignalKind::terminate())?;
+
+        let client = ConnectionManager::builder(self.broker.clone())
+            .client_id(Some(env!("CARGO_PKG_NAME").into()))
+            .build()
+            .await
+            .inspect(|pool| debug!(?pool))
+            .map(Client::new)?;
+
+        let (topic_id, partitions) = self.describe(&client).await?;
+        let positions = self.high_watermarks(&client, partitions).await?;
+        debug!(topic = self.topic, partitions, ?positions);
+
+        let (drain, high_watermarks) = watch::channel(None);
+
+        let mut consumers = JoinSet::new();
+
+        for id in 0..self.consumers {
+            let consumer = Consumer {
+                id,
+                client: client.clone(),
+                topic: self.topic.clone(),
+                topic_id,
+                positions: positions.clone(),
+                high_watermarks: high_watermarks.clone(),
+                token: token.clone(),
+            };
+
+            _ = consumers.spawn(consumer.run());
+        }
+
+        let keys = Arc::new(Keys::zipf(self.key_cardinality, self.key_skew));
+        let values = Arc::new(Values::new(self.record_size.clone()));
+
+        let mut producers = JoinSet::new();
+
+        for id in 0..self.producers {
+            let producer = Producer {
+                id,
+                client: client.clone(),
+                topic: self.topic.clone(),
+                partitions,
+                batch_size: self.batch_size,
+                acks: self.acks,
+                compression: self.compression.clone(),
+                keys: keys.clone(),
+                values: values.clone(),
+                pacing: self.pacing(),
+                producing: producing.clone(),
+            };
+
+            _ = producers.spawn(producer.run());
+        }
+
+        let mut stats = Stats::default();
+        let started = Instant::now();
+
+        let producers_until = sleep(self.duration);
+        tokio::pin!(producers_until);
+
+        let outcome = loop {
+            tokio::select! {
+                _ = &mut producers_until => break Ok(true),
+
+                Some(joined) = producers.join_next() => {
+                    if let Err(err) = stats.tally(joined) {
+                        break Err(err);
+                    }
+                }
+
+                Some(joined) = consumers.join_next() => {
+                    if let Err(err) = stats.tally(joined) {
+                        break Err(err);
+                    }
+                }
+
+                interrupt = interrupt_signal.recv() => {
+                    debug!(?interrupt);
+                    break Ok(false);
+                }
+
+                terminate = terminate_signal.recv() => {
+                    debug!(?terminate);
+                    break Ok(false);
+                }
+            }
+        };
+
+        debug!(?outcome);
+
+        producing.cancel();
+
+        let producing_secs = started.elapsed();
+
+        while let Some(joined) = producers.join_next().await {
+            if let Err(err) = stats.tally(joined) {
+                warn!(?err);
+            }
+        }
+
+        let draining = Instant::now();
+
+        let drained = if matches!(outcome, Ok(true)) {
+            let high_watermarks = self.high_watermarks(&client, partitions).await?;
+            debug!(?high_watermarks);
+
+            _ = drain.send(Some(high_watermarks));
+
+            timeout(self.drain_timeout, async {
+                while let Some(joined) = consumers.join_next().await {
+                    if let Err(err) = stats.tally(joined) {
+                        warn!(?err);
+                    }
+                }
+            })
+            .await
+            .is_ok()
+        } else {
+            false
+        };
+
+        token.cancel();
+
+        while let Some(joined) = consumers.join_next().await {
+            if let Err(err) = stats.tally(joined) {
+                warn!(?err);
+            }
+        }
+
+        let draining_secs = draining.elapsed();
+
+        let report = Report {
+            label: self.label.clone(),
+            broker: self.broker.to_string(),
+            topic: self.topic.clone(),
+            partitions,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            min_record_size: *self.record_size.start(),
+            max_record_size: *self.record_size.end(),
+            key_cardinality: self.key_cardinality,
+            key_skew: self.key_skew,
+            compression: self.compression.clone(),
+            acks: self.acks,
+            throughput: self.throughput,
+            producing_secs: producing_secs.as_secs_f64(),
+            draining_secs: draining_secs.as_secs_f64(),
+            drained,
+            produced: Throughput::new(stats.produced, producing_secs),
+            consumed: Throughput::new(stats.consumed, producing_secs + draining_secs),
+            failed: stats.failed,
+            produce_latency: stats.produce_latency.latency(),
+            end_to_end_latency: stats.end_to_end_latency.latency(),
+        };
+
+        self.write(&report)?;
+
+        outcome.map(|_| ErrorCode::None)
+    }
+}
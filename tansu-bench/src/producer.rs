@@ -0,0 +1,218 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Producers sending batches of keyed records, optionally paced to a throughput
+
+use std::{
+    collections::{BTreeMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use tansu_client::Client;
+use tansu_sans_io::{
+    BatchAttribute, Compression, ErrorCode, ProduceRequest,
+    produce_request::{PartitionProduceData, TopicProduceData},
+    record::{Record, deflated, inflated},
+};
+use tokio::time::{Instant, MissedTickBehavior, interval};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    Result, Stats,
+    workload::{Keys, Values},
+};
+
+type KeyValue = (Option<Bytes>, Bytes);
+
+#[derive(Clone, Debug)]
+pub(crate) struct Producer {
+    pub(crate) id: u32,
+    pub(crate) client: Client,
+    pub(crate) topic: String,
+    pub(crate) partitions: i32,
+    pub(crate) batch_size: u32,
+    pub(crate) acks: i16,
+    pub(crate) compression: Compression,
+    pub(crate) keys: Arc<Keys>,
+    pub(crate) values: Arc<Values>,
+    pub(crate) pacing: Option<Duration>,
+    pub(crate) producing: CancellationToken,
+}
+
+impl Producer {
+    /// The partition of a keyed record, with unkeyed records sticking to a partition per batch
+    fn partition(&self, key: Option<&Bytes>, sticky: i32) -> i32 {
+        key.map_or(sticky, |key| {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            (hasher.finish() % self.partitions as u64) as i32
+        })
+    }
+
+    fn frame(&self, records: &[KeyValue], timestamp: i64) -> Result<deflated::Frame> {
+        records
+            .iter()
+            .zip(0..)
+            .fold(
+                inflated::Batch::builder()
+                    .attributes(
+                        BatchAttribute::default()
+                            .compression(self.compression.clone())
+                            .into(),
+                    )
+                    .base_timestamp(timestamp)
+                    .max_timestamp(timestamp),
+                |batch, ((key, value), offset_delta)| {
+                    batch.record(
+                        Record::builder()
+                            .key(key.clone())
+                            .value(Some(value.clone()))
+                            .offset_delta(offset_delta),
+                    )
+                },
+            )
+            .last_offset_delta(i32::try_from(records.len()).unwrap_or(i32::MAX) - 1)
+            .build()
+            .map(|batch| inflated::Frame {
+                batches: vec![batch],
+            })
+            .and_then(deflated::Frame::try_from)
+            .map_err(Into::into)
+    }
+
+    /// Produce records grouped by partition, returning the records and bytes acknowledged
+    #[instrument(skip(self, partitioned), fields(id = self.id))]
+    async fn produce(&self, partitioned: BTreeMap<i32, Vec<KeyValue>>) -> Result<(u64, u64)> {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |elapsed| {
+                i64::try_from(elapsed.as_millis()).unwrap_or(i64::MAX)
+            });
+
+        let req = ProduceRequest::default()
+            .acks(self.acks)
+            .timeout_ms(5_000)
+            .topic_data(Some(
+                [TopicProduceData::default()
+                    .name(self.topic.clone())
+                    .partition_data(Some(
+                        partitioned
+                            .iter()
+                            .map(|(partition, records)| {
+                                self.frame(&records[..], timestamp).map(|frame| {
+                                    PartitionProduceData::default()
+                                        .index(*partition)
+                                        .records(Some(frame))
+                                })
+                            })
+                            .collect::<Result<Vec<_>>>()?,
+                    ))]
+                .into(),
+            ));
+
+        let response = self.client.call(req).await?;
+
+        Ok(response
+            .responses
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+            .filter(|response| {
+                ErrorCode::try_from(response.error_code)
+                    .inspect_err(|err| warn!(partition = response.index, ?err))
+                    .is_ok_and(|error_code| {
+                        debug!(partition = response.index, ?error_code);
+                        error_code == ErrorCode::None
+                    })
+            })
+            .filter_map(|response| partitioned.get(&response.index))
+            .fold((0, 0), |(records, bytes), acknowledged| {
+                (
+                    records + acknowledged.len() as u64,
+                    bytes
+                        + acknowledged
+                            .iter()
+                            .map(|(key, value)| {
+                                (key.as_ref().map_or(0, Bytes::len) + value.len()) as u64
+                            })
+                            .sum::<u64>(),
+                )
+            }))
+    }
+
+    /// Produce batches until producing stops
+    pub(crate) async fn run(self) -> Result<Stats> {
+        let mut stats = Stats::default();
+
+        let mut pacing = self.pacing.map(|period| {
+            let mut pacing = interval(period);
+            pacing.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            pacing
+        });
+
+        for sticky in (0..self.partitions).cycle() {
+            if let Some(ref mut pacing) = pacing {
+                tokio::select! {
+                    cancelled = self.producing.cancelled() => {
+                        debug!(?cancelled);
+                        break;
+                    }
+
+                    _ = pacing.tick() => (),
+                }
+            }
+
+            let produced = SystemTime::now();
+
+            let partitioned = (0..self.batch_size).fold(
+                BTreeMap::<i32, Vec<KeyValue>>::new(),
+                |mut partitioned, _| {
+                    let key = self.keys.sample();
+                    let value = self.values.sample(produced);
+
+                    partitioned
+                        .entry(self.partition(key.as_ref(), sticky))
+                        .or_default()
+                        .push((key, value));
+
+                    partitioned
+                },
+            );
+
+            let started = Instant::now();
+
+            tokio::select! {
+                cancelled = self.producing.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+
+                acknowledged = self.produce(partitioned) => {
+                    let (records, bytes) = acknowledged?;
+
+                    stats.produced.records += records;
+                    stats.produced.bytes += bytes;
+                    stats.failed += u64::from(self.batch_size) - records;
+                    stats.produce_latency.record_duration(started.elapsed());
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+}
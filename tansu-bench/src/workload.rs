@@ -0,0 +1,158 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record keys drawn from a Zipfian distribution, and values of a uniformly
+//! distributed size starting with the time they were produced
+
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, SystemTime},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+const PRODUCED_SIZE: usize = size_of::<u64>();
+
+/// Minimum size of the pool that values are filled from
+const POOL_SIZE: usize = 1_048_576;
+
+/// Keys ranked by popularity, where the key of rank `k` is drawn with a
+/// probability proportional to `1 / k^skew`
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+pub(crate) struct Keys {
+    cdf: Vec<f64>,
+}
+
+impl Keys {
+    /// Without any keys when the cardinality is zero, and uniform when the skew is zero
+    pub(crate) fn zipf(cardinality: u64, skew: f64) -> Self {
+        let mut total = 0.0;
+
+        let mut cdf = (1..=cardinality)
+            .map(|rank| {
+                total += 1.0 / (rank as f64).powf(skew);
+                total
+            })
+            .collect::<Vec<_>>();
+
+        for probability in cdf.iter_mut() {
+            *probability /= total;
+        }
+
+        Self { cdf }
+    }
+
+    /// The rank of the key corresponding to a uniform sample in `[0, 1)`
+    fn rank(&self, uniform: f64) -> Option<usize> {
+        (!self.cdf.is_empty()).then(|| {
+            self.cdf
+                .partition_point(|probability| *probability <= uniform)
+                .min(self.cdf.len() - 1)
+        })
+    }
+
+    pub(crate) fn sample(&self) -> Option<Bytes> {
+        self.rank(rand::random())
+            .map(|rank| Bytes::from(format!("key-{rank}")))
+    }
+}
+
+/// Values filled with random lowercase ASCII, giving compression something to work with
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Values {
+    size: RangeInclusive<usize>,
+    pool: Bytes,
+}
+
+impl Values {
+    pub(crate) fn new(size: RangeInclusive<usize>) -> Self {
+        let size = (*size.start()).max(PRODUCED_SIZE)
+            ..=(*size.end()).max(*size.start()).max(PRODUCED_SIZE);
+
+        let pool = (0..POOL_SIZE.max(2 * size.end()))
+            .map(|_| rand::random_range(b'a'..=b'z'))
+            .collect::<Vec<u8>>()
+            .into();
+
+        Self { size, pool }
+    }
+
+    pub(crate) fn sample(&self, produced: SystemTime) -> Bytes {
+        let size = rand::random_range(self.size.clone());
+        let filler = size - PRODUCED_SIZE;
+        let offset = rand::random_range(0..=self.pool.len() - filler);
+
+        let mut value = BytesMut::with_capacity(size);
+        value.put_u64(
+            produced
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |elapsed| {
+                    u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX)
+                }),
+        );
+        value.put_slice(&self.pool[offset..offset + filler]);
+        value.freeze()
+    }
+}
+
+/// The time that a value was produced
+pub(crate) fn produced(value: &[u8]) -> Option<SystemTime> {
+    value
+        .first_chunk::<PRODUCED_SIZE>()
+        .map(|micros| SystemTime::UNIX_EPOCH + Duration::from_micros(u64::from_be_bytes(*micros)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_keys() {
+        assert_eq!(None, Keys::zipf(0, 1.0).sample());
+    }
+
+    #[test]
+    fn uniform() {
+        let keys = Keys::zipf(4, 0.0);
+
+        assert_eq!(Some(0), keys.rank(0.0));
+        assert_eq!(Some(1), keys.rank(0.25));
+        assert_eq!(Some(2), keys.rank(0.6));
+        assert_eq!(Some(3), keys.rank(0.99));
+    }
+
+    #[test]
+    fn skewed() {
+        let keys = Keys::zipf(1_000, 1.0);
+
+        let popular = (0..10_000)
+            .filter(|sample| keys.rank(f64::from(*sample) / 10_000.0) == Some(0))
+            .count();
+
+        // H(1000) ~ 7.485, with the most popular key drawn ~ 13.4% of the time
+        assert!((1_300..1_370).contains(&popular), "{popular}");
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let values = Values::new(16..=64);
+        let produced_at = SystemTime::UNIX_EPOCH + Duration::from_micros(1_723_456_789_012_345);
+
+        for _ in 0..100 {
+            let value = values.sample(produced_at);
+            assert!((16..=64).contains(&value.len()));
+            assert_eq!(Some(produced_at), produced(&value[..]));
+        }
+    }
+}
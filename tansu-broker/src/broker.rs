@@ -12,31 +12,87 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod admin;
+pub mod audit;
+pub mod authorizer;
+#[cfg(feature = "dynostore")]
+pub mod bucket;
+#[cfg(feature = "postgres")]
+pub mod cdc;
+pub mod chaos;
+#[cfg(any(feature = "dynostore", feature = "postgres"))]
+mod checkpoint;
+pub mod claim_check;
+pub mod compaction;
+pub mod feature;
 pub mod group;
+pub mod latency;
+pub mod lineage;
+pub mod linger;
+pub mod link;
+pub mod logger;
+pub mod message_size;
+pub mod oauth;
+#[cfg(feature = "postgres")]
+pub mod outbox;
+#[cfg(feature = "postgres")]
+pub mod platform;
+mod postgres;
+pub mod preflight;
+pub mod quota;
+pub mod read_only;
+pub mod recompress;
+pub mod replay;
+pub mod retention;
+pub mod sasl;
+pub mod scaling;
+pub mod sequence;
+pub mod sink;
+pub mod tag;
+pub mod throttle;
+pub mod tls;
+pub mod topic_config;
+pub mod txn_timeout;
+pub mod watch;
 
 use crate::{
     CancelKind, Error, Result,
-    coordinator::group::{Coordinator, administrator::Controller},
-    otel,
-    service::services,
+    broker::{
+        authorizer::{AclAuthorizer, Authorization, Authorizer},
+        link::ClusterLink,
+        oauth::OAuthBearer,
+        sasl::{Credentials, SaslSession},
+        scaling::PartitionAnalyzer,
+        tls::Tls,
+        watch::TopicWatch,
+    },
+    clock::{Clock, SystemClock},
+    coordinator::group::{Coordinator, administrator::Controller, lease::Leased},
+    otel::{self, LatencyHistogram, ResourceAttributes},
+    service::{TcpRouteFrame, services},
 };
 use rama::{Context, Service};
 use std::{
-    io::ErrorKind,
+    collections::BTreeMap,
+    io::{self, ErrorKind},
     marker::PhantomData,
     net::{IpAddr, Ipv6Addr, SocketAddr},
+    path::Path,
     str::FromStr,
-    time::{Duration, SystemTime},
+    sync::Arc,
+    time::Duration,
 };
 use tansu_sans_io::{ErrorCode, RootMessageMeta};
 use tansu_schema::{Registry, lake::House};
-use tansu_storage::{BrokerRegistrationRequest, Storage, StorageContainer};
+use tansu_service::{Connection, Peer};
+use tansu_storage::{BrokerRegistrationRequest, FetchService, Storage, StorageContainer};
 use tokio::{
-    net::TcpListener,
+    net::{TcpListener, TcpStream, UnixListener, UnixStream},
     signal::unix::{SignalKind, signal},
     task::JoinSet,
     time::{self, sleep},
 };
+use tokio_rustls::TlsAcceptor;
 use tokio_util::sync::CancellationToken;
 use tracing::{Instrument, Level, debug, error, span};
 use url::Url;
@@ -49,11 +105,37 @@ pub struct Broker<G, S> {
     incarnation_id: Uuid,
     listener: Url,
     advertised_listener: Url,
+    admin_listener: Option<Url>,
+    admin_token: Option<Arc<str>>,
     storage: S,
     groups: G,
+    fetch: FetchService,
+    schema_registry: Option<Registry>,
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+    authorizer: Option<Arc<dyn Authorizer>>,
+    bucket_interval: Option<Duration>,
+    cluster_link_interval: Option<Duration>,
+    offsets_retention: Option<Duration>,
+    sink_interval: Option<Duration>,
+    produce_linger: Option<Duration>,
+    transaction_abort_interval: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    compaction_interval: Option<Duration>,
+    cdc_interval: Option<Duration>,
+    outbox_interval: Option<Duration>,
+    topic_watch_interval: Option<Duration>,
+    partition_analysis_interval: Option<Duration>,
+    direct_read_expiry: Option<Duration>,
+    maximum_frame_size: Option<usize>,
+    maximum_in_flight: Option<usize>,
+    incremental_decode: Option<usize>,
+    chaos: bool,
+    tls: Option<Tls>,
+    rack: Option<String>,
 
     #[allow(dead_code)]
-    otlp_endpoint_url: Option<Url>,
+    otlp_endpoint_urls: Vec<Url>,
 
     cancellation: CancellationToken,
 }
@@ -79,9 +161,35 @@ where
             incarnation_id,
             listener,
             advertised_listener,
+            admin_listener: None,
+            admin_token: None,
             storage,
             groups,
-            otlp_endpoint_url: None,
+            fetch: FetchService::default(),
+            schema_registry: None,
+            credentials: None,
+            oauth_bearer: None,
+            authorizer: None,
+            bucket_interval: None,
+            cluster_link_interval: None,
+            offsets_retention: None,
+            sink_interval: None,
+            produce_linger: None,
+            transaction_abort_interval: None,
+            clock: Arc::new(SystemClock),
+            compaction_interval: None,
+            cdc_interval: None,
+            outbox_interval: None,
+            topic_watch_interval: None,
+            partition_analysis_interval: None,
+            direct_read_expiry: None,
+            maximum_frame_size: None,
+            maximum_in_flight: None,
+            incremental_decode: None,
+            chaos: false,
+            tls: None,
+            rack: None,
+            otlp_endpoint_urls: vec![],
 
             cancellation: CancellationToken::new(),
         }
@@ -104,6 +212,8 @@ where
             );
         }
 
+        self.preflight().await?;
+
         let mut set = JoinSet::new();
 
         let mut interrupt_signal = signal(SignalKind::interrupt()).unwrap();
@@ -114,6 +224,225 @@ where
 
         let token = self.cancellation.clone();
 
+        if let Some(admin_listener) = self.admin_listener.as_ref() {
+            let listener = platform::listen_tcp(
+                admin::bind_addr(admin_listener, self.admin_token.is_some())?,
+                "admin listener",
+            )
+            .await
+            .inspect_err(|err| error!(?err, %admin_listener))?;
+
+            let topic_changes = self.topic_watch_interval.map(|interval| {
+                let watch =
+                    TopicWatch::new(self.storage.clone(), interval, self.cancellation.clone());
+                let changes = watch.changes();
+
+                _ = set.spawn(async move {
+                    watch.serve().await.inspect_err(|err| error!(?err)).unwrap();
+                });
+
+                changes
+            });
+
+            let recommendations = self.partition_analysis_interval.map(|interval| {
+                let analyzer = PartitionAnalyzer::new(
+                    self.storage.clone(),
+                    interval,
+                    self.cancellation.clone(),
+                )
+                .clock(self.clock.clone());
+                let recommendations = analyzer.recommendations();
+
+                _ = set.spawn(async move {
+                    analyzer
+                        .serve()
+                        .await
+                        .inspect_err(|err| error!(?err))
+                        .unwrap();
+                });
+
+                recommendations
+            });
+
+            let schema_registry = self.schema_registry.clone();
+            let storage = self.storage.clone();
+            let direct_read_expiry = self.direct_read_expiry;
+            let linger = self
+                .produce_linger
+                .map(|linger| linger::Linger::new(self.storage.clone(), linger));
+            let admin_token = self.admin_token.clone();
+            let fetch_hints = self.fetch.has_hints();
+            let chaos = self.chaos;
+            let cancellation = self.cancellation.clone();
+
+            _ = set.spawn(async move {
+                admin::serve(
+                    listener,
+                    admin_token,
+                    schema_registry,
+                    storage,
+                    topic_changes,
+                    recommendations,
+                    direct_read_expiry,
+                    linger,
+                    fetch_hints,
+                    chaos,
+                    cancellation,
+                )
+                .await
+                .inspect_err(|err| error!(?err))
+                .unwrap();
+            });
+        }
+
+        if let Some(tls) = self.tls.clone() {
+            let cancellation = self.cancellation.clone();
+
+            _ = set.spawn(async move {
+                tls.serve(cancellation)
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        if let Some(interval) = self.cluster_link_interval {
+            let link = ClusterLink::new(self.storage.clone(), interval, self.cancellation.clone());
+
+            _ = set.spawn(async move {
+                link.serve().await.inspect_err(|err| error!(?err)).unwrap();
+            });
+        }
+
+        if let Some(retention) = self.offsets_retention {
+            let retention = retention::OffsetRetention::new(
+                self.storage.clone(),
+                retention,
+                self.cancellation.clone(),
+            );
+
+            _ = set.spawn(async move {
+                retention
+                    .serve()
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        if let Some(interval) = self.sink_interval {
+            let sink =
+                sink::SinkConnector::new(self.storage.clone(), interval, self.cancellation.clone());
+
+            _ = set.spawn(async move {
+                sink.serve().await.inspect_err(|err| error!(?err)).unwrap();
+            });
+        }
+
+        if let Some(interval) = self.transaction_abort_interval {
+            let timeout = txn_timeout::TxnTimeout::new(
+                self.storage.clone(),
+                interval,
+                self.cancellation.clone(),
+            )
+            .clock(self.clock.clone());
+
+            _ = set.spawn(async move {
+                timeout
+                    .serve()
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        if let Some(interval) = self.compaction_interval {
+            let compactor = compaction::Compactor::new(
+                self.storage.clone(),
+                interval,
+                self.cancellation.clone(),
+            )
+            .clock(self.clock.clone());
+
+            _ = set.spawn(async move {
+                compactor
+                    .serve()
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        #[cfg(feature = "postgres")]
+        if let Some(interval) = self.outbox_interval {
+            let outbox = outbox::OutboxPoller::new(
+                self.storage.clone(),
+                interval,
+                self.cancellation.clone(),
+            );
+
+            _ = set.spawn(async move {
+                outbox
+                    .serve()
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        #[cfg(not(feature = "postgres"))]
+        if let Some(interval) = self.outbox_interval {
+            tracing::warn!(?interval, "outbox ingestion requires the postgres feature");
+        }
+
+        #[cfg(feature = "postgres")]
+        if let Some(interval) = self.cdc_interval {
+            let capture = cdc::ChangeCapture::new(
+                self.storage.clone(),
+                self.schema_registry.clone(),
+                interval,
+                self.cancellation.clone(),
+            );
+
+            _ = set.spawn(async move {
+                capture
+                    .serve()
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        #[cfg(not(feature = "postgres"))]
+        if let Some(interval) = self.cdc_interval {
+            tracing::warn!(
+                ?interval,
+                "change data capture requires the postgres feature"
+            );
+        }
+
+        #[cfg(feature = "dynostore")]
+        if let Some(interval) = self.bucket_interval {
+            let bucket = bucket::BucketSource::new(
+                self.storage.clone(),
+                interval,
+                self.cancellation.clone(),
+            );
+
+            _ = set.spawn(async move {
+                bucket
+                    .serve()
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap();
+            });
+        }
+
+        #[cfg(not(feature = "dynostore"))]
+        if let Some(interval) = self.bucket_interval {
+            tracing::warn!(?interval, "bucket ingestion requires the dynostore feature");
+        }
+
         _ = set.spawn(async move {
             self.serve().await.inspect_err(|err| error!(?err)).unwrap();
         });
@@ -167,6 +496,33 @@ where
         Ok(ErrorCode::None)
     }
 
+    /// Run the preflight checks, failing with their report when any check has failed
+    pub async fn preflight(&self) -> Result<preflight::Report> {
+        let mut listeners = vec![];
+
+        // a unix:// listener is a socket bound on startup rather than a port
+        if self.listener.scheme() != "unix" {
+            listeners.push(("listener", bind_addr(&self.listener, 9092)));
+        }
+
+        if let Some(admin_listener) = self.admin_listener.as_ref() {
+            listeners.push((
+                "admin listener",
+                admin::bind_addr(admin_listener, self.admin_token.is_some())?,
+            ));
+        }
+
+        let report = preflight::run(&self.storage, &listeners, self.tls.as_ref()).await;
+
+        if report.passed() {
+            Ok(report)
+        } else {
+            Err(Error::Message(format!(
+                "preflight checks failed:\n{report}"
+            )))
+        }
+    }
+
     pub async fn serve(&mut self) -> Result<()> {
         self.register().await?;
         self.listen().await
@@ -178,7 +534,7 @@ where
                 broker_id: self.node_id,
                 cluster_id: self.cluster_id.clone(),
                 incarnation_id: self.incarnation_id,
-                rack: None,
+                rack: self.rack.clone(),
             })
             .await
             .map_err(Into::into)
@@ -187,28 +543,10 @@ where
     pub async fn listen(&self) -> Result<()> {
         debug!(%self.listener, %self.advertised_listener);
 
-        let listener = TcpListener::bind(self.listener.host().map_or_else(
-            || {
-                SocketAddr::from((
-                    IpAddr::V6(Ipv6Addr::UNSPECIFIED),
-                    self.listener.port().unwrap_or(9092),
-                ))
-            },
-            |host| {
-                let port = self.listener.port().unwrap_or(9092);
-                debug!(?host, port);
-
-                match host {
-                    url::Host::Domain(domain) => SocketAddr::from_str(&format!("{domain}:{port}"))
-                        .unwrap_or(SocketAddr::from((IpAddr::V6(Ipv6Addr::UNSPECIFIED), port))),
-                    url::Host::Ipv4(ipv4_addr) => SocketAddr::from((IpAddr::V4(ipv4_addr), port)),
-                    url::Host::Ipv6(ipv6_addr) => SocketAddr::from((IpAddr::V6(ipv6_addr), port)),
-                }
-            },
-        ))
-        .await
-        .inspect(|listener| debug!(listener = ?listener.local_addr().ok()))
-        .inspect_err(|err| error!(?err, %self.advertised_listener))?;
+        let listener = Listener::bind(&self.listener)
+            .await
+            .inspect(|listener| debug!(?listener))
+            .inspect_err(|err| error!(?err, %self.advertised_listener))?;
 
         let mut interval = time::interval(Duration::from_millis(600_000));
 
@@ -216,33 +554,57 @@ where
 
         let service = services(
             self.cluster_id.as_str(),
+            self.maximum_frame_size,
+            self.maximum_in_flight,
+            self.incremental_decode,
             self.groups.clone(),
             self.storage.clone(),
+            self.fetch.clone(),
+            self.schema_registry.clone(),
+            self.credentials.clone(),
+            self.oauth_bearer.clone(),
+            self.authorizer.clone(),
         )?;
 
         loop {
             tokio::select! {
-                Ok((stream, _addr)) = listener.accept() => {
-                    stream.set_nodelay(true)?;
-
+                Ok(accepted) = listener.accept() => {
                     let service = service.clone();
+                    let acceptor = self.tls.as_ref().map(|tls| tls.acceptor().clone());
 
-                    let handle = set.spawn(async move {
-                            match service.serve(Context::default(), stream).await {
-                                Err(Error::Io(ref io))
-                                    if io.kind() == ErrorKind::UnexpectedEof
-                                        || io.kind() == ErrorKind::BrokenPipe
-                                        || io.kind() == ErrorKind::ConnectionReset => {}
-
-                                Err(error) => {
-                                    error!(?error);
-                                },
-
-                                Ok(response) => {
-                                    debug!(?response)
-                                }
+                    let handle = match accepted {
+                        Accepted::Tcp(stream, addr) => {
+                            stream.set_nodelay(true)?;
+
+                            let mut ctx = Context::default();
+                            _ = ctx.insert(SaslSession::default());
+                            _ = ctx.insert(Peer::from(addr));
+
+                            set.spawn(connection(service, acceptor, ctx, stream))
                         }
-                    });
+
+                        Accepted::Unix { stream, peer_credentials } => {
+                            // when enabled on the listener, the peer credentials
+                            // of a local client are its identity
+                            let session = if peer_credentials {
+                                stream.peer_cred().map_or_else(
+                                    |err| {
+                                        debug!(?err);
+                                        SaslSession::default()
+                                    },
+                                    |credentials| SaslSession::authenticated(credentials.uid().to_string()),
+                                )
+                            } else {
+                                SaslSession::default()
+                            };
+
+                            let mut ctx = Context::default();
+                            _ = ctx.insert(session);
+                            _ = ctx.insert(Connection::peer_addr(&stream)?);
+
+                            set.spawn(connection(service, acceptor, ctx, stream))
+                        }
+                    };
 
                     debug!(?handle);
 
@@ -251,13 +613,14 @@ where
 
                 _ = interval.tick() => {
                     let storage = self.storage.clone();
+                    let now = self.clock.now();
 
 
                     let handle = set.spawn(async move {
                         let span = span!(Level::DEBUG, "maintenance");
 
                         async move {
-                            _ = storage.maintain(SystemTime::now()).await.inspect(|maintain|debug!(?maintain)).inspect_err(|err|debug!(?err)).ok();
+                            _ = storage.maintain(now).await.inspect(|maintain|debug!(?maintain)).inspect_err(|err|debug!(?err)).ok();
 
                         }.instrument(span).await
 
@@ -287,6 +650,140 @@ where
     }
 }
 
+/// A bound Kafka listener, on a TCP port or a Unix domain socket
+#[derive(Debug)]
+enum Listener {
+    Tcp(TcpListener),
+    Unix {
+        listener: UnixListener,
+        peer_credentials: bool,
+    },
+}
+
+/// An accepted connection to the Kafka listener
+#[derive(Debug)]
+enum Accepted {
+    Tcp(TcpStream, SocketAddr),
+    Unix {
+        stream: UnixStream,
+        peer_credentials: bool,
+    },
+}
+
+impl Listener {
+    /// Bind a `unix://` listener to the socket at its path, otherwise to a TCP port
+    ///
+    /// A client of a `unix://` listener must authenticate as any other,
+    /// unless `peer_credentials=true` is in the query of the URL, when the
+    /// uid of its peer credentials is its identity.
+    ///
+    /// Windows named pipes (`npipe://`) are not implemented: the broker
+    /// builds for Unix only, relying on Unix signals and peer credentials.
+    async fn bind(listener: &Url) -> Result<Self> {
+        if listener.scheme() == "npipe" {
+            return Err(Error::Message(format!(
+                "named pipe listeners are not supported: {listener}"
+            )));
+        }
+
+        if listener.scheme() != "unix" {
+            return platform::listen_tcp(bind_addr(listener, 9092), "kafka listener")
+                .await
+                .map(Self::Tcp)
+                .map_err(Into::into);
+        }
+
+        let peer_credentials = listener
+            .query_pairs()
+            .any(|(name, value)| name == "peer_credentials" && value == "true");
+
+        platform::listen_unix(Path::new(listener.path()), "kafka listener")
+            .map(|listener| Self::Unix {
+                listener,
+                peer_credentials,
+            })
+            .map_err(Into::into)
+    }
+
+    async fn accept(&self) -> io::Result<Accepted> {
+        match self {
+            Self::Tcp(listener) => listener
+                .accept()
+                .await
+                .map(|(stream, addr)| Accepted::Tcp(stream, addr)),
+
+            Self::Unix {
+                listener,
+                peer_credentials,
+            } => listener.accept().await.map(|(stream, _)| Accepted::Unix {
+                stream,
+                peer_credentials: *peer_credentials,
+            }),
+        }
+    }
+}
+
+/// Serve the Kafka protocol on an accepted connection, terminating TLS when configured
+async fn connection<S, C>(
+    service: TcpRouteFrame<S>,
+    acceptor: Option<TlsAcceptor>,
+    ctx: Context<()>,
+    stream: C,
+) where
+    S: Storage,
+    C: Connection,
+{
+    let served = match acceptor {
+        Some(acceptor) => {
+            let peer = stream.peer_addr().ok();
+
+            match acceptor.accept(stream).await {
+                Ok(stream) => service.serve(ctx, stream).await,
+
+                Err(err) => {
+                    debug!(?peer, ?err);
+                    return;
+                }
+            }
+        }
+
+        None => service.serve(ctx, stream).await,
+    };
+
+    match served {
+        Err(Error::Io(ref io))
+            if io.kind() == ErrorKind::UnexpectedEof
+                || io.kind() == ErrorKind::BrokenPipe
+                || io.kind() == ErrorKind::ConnectionReset => {}
+
+        Err(error) => {
+            error!(?error);
+        }
+
+        Ok(response) => {
+            debug!(?response)
+        }
+    }
+}
+
+fn bind_addr(listener: &Url, default_port: u16) -> SocketAddr {
+    let port = listener.port().unwrap_or(default_port);
+
+    listener.host().map_or_else(
+        || SocketAddr::from((IpAddr::V6(Ipv6Addr::UNSPECIFIED), port)),
+        |host| {
+            debug!(?host, port);
+
+            match host {
+                url::Host::Domain(domain) => SocketAddr::from_str(&format!("{domain}:{port}"))
+                    .unwrap_or(SocketAddr::from((IpAddr::V6(Ipv6Addr::UNSPECIFIED), port))),
+                url::Host::Ipv4(ipv4_addr) => SocketAddr::from((IpAddr::V4(ipv4_addr), port)),
+                url::Host::Ipv6(ipv6_addr) => SocketAddr::from((IpAddr::V6(ipv6_addr), port)),
+            }
+        },
+    )
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Builder<N, C, I, A, S, L> {
     node_id: N,
@@ -294,8 +791,37 @@ pub struct Builder<N, C, I, A, S, L> {
     incarnation_id: I,
     advertised_listener: A,
     storage: S,
+    storage_tiers: BTreeMap<String, Url>,
     listener: L,
-    otlp_endpoint_url: Option<Url>,
+    admin_listener: Option<Url>,
+    admin_token: Option<Arc<str>>,
+    sasl_credentials: Option<Url>,
+    sasl_oauth_bearer: Option<OAuthBearer>,
+    authorization: Option<Authorization>,
+    authorizer: Option<Arc<dyn Authorizer>>,
+    bucket_interval: Option<Duration>,
+    cluster_link_interval: Option<Duration>,
+    offsets_retention: Option<Duration>,
+    sink_interval: Option<Duration>,
+    produce_linger: Option<Duration>,
+    transaction_abort_interval: Option<Duration>,
+    clock: Option<Arc<dyn Clock>>,
+    compaction_interval: Option<Duration>,
+    cdc_interval: Option<Duration>,
+    outbox_interval: Option<Duration>,
+    topic_watch_interval: Option<Duration>,
+    partition_analysis_interval: Option<Duration>,
+    direct_read_expiry: Option<Duration>,
+    maximum_frame_size: Option<usize>,
+    maximum_in_flight: Option<usize>,
+    incremental_decode: Option<usize>,
+    chaos: bool,
+    tls: Option<Tls>,
+    rack: Option<String>,
+    fetch: FetchService,
+    otlp_endpoint_urls: Vec<Url>,
+    resource_attributes: ResourceAttributes,
+    latency_histogram: LatencyHistogram,
     schema_registry: Option<Registry>,
     lake_house: Option<House>,
 
@@ -319,9 +845,38 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             incarnation_id: self.incarnation_id,
             advertised_listener: self.advertised_listener,
             storage: self.storage,
+            storage_tiers: self.storage_tiers,
             listener: self.listener,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
+            sasl_credentials: self.sasl_credentials,
+            sasl_oauth_bearer: self.sasl_oauth_bearer,
+            authorization: self.authorization,
+            authorizer: self.authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock: self.clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
+            fetch: self.fetch,
             schema_registry: self.schema_registry,
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            resource_attributes: self.resource_attributes,
+            latency_histogram: self.latency_histogram,
             lake_house: self.lake_house,
 
             cancellation: self.cancellation,
@@ -335,8 +890,37 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             incarnation_id: self.incarnation_id,
             advertised_listener: self.advertised_listener,
             storage: self.storage,
+            storage_tiers: self.storage_tiers,
             listener: self.listener,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
+            sasl_credentials: self.sasl_credentials,
+            sasl_oauth_bearer: self.sasl_oauth_bearer,
+            authorization: self.authorization,
+            authorizer: self.authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock: self.clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
+            fetch: self.fetch,
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            resource_attributes: self.resource_attributes,
+            latency_histogram: self.latency_histogram,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
 
@@ -351,8 +935,37 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             incarnation_id: incarnation_id.into(),
             advertised_listener: self.advertised_listener,
             storage: self.storage,
+            storage_tiers: self.storage_tiers,
             listener: self.listener,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
+            sasl_credentials: self.sasl_credentials,
+            sasl_oauth_bearer: self.sasl_oauth_bearer,
+            authorization: self.authorization,
+            authorizer: self.authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock: self.clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
+            fetch: self.fetch,
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            resource_attributes: self.resource_attributes,
+            latency_histogram: self.latency_histogram,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
 
@@ -370,8 +983,37 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             incarnation_id: self.incarnation_id,
             advertised_listener: advertised_listener.into(),
             storage: self.storage,
+            storage_tiers: self.storage_tiers,
             listener: self.listener,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
+            sasl_credentials: self.sasl_credentials,
+            sasl_oauth_bearer: self.sasl_oauth_bearer,
+            authorization: self.authorization,
+            authorizer: self.authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock: self.clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
+            fetch: self.fetch,
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            resource_attributes: self.resource_attributes,
+            latency_histogram: self.latency_histogram,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
 
@@ -388,8 +1030,37 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             incarnation_id: self.incarnation_id,
             advertised_listener: self.advertised_listener,
             storage,
+            storage_tiers: self.storage_tiers,
             listener: self.listener,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
+            sasl_credentials: self.sasl_credentials,
+            sasl_oauth_bearer: self.sasl_oauth_bearer,
+            authorization: self.authorization,
+            authorizer: self.authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock: self.clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
+            fetch: self.fetch,
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            resource_attributes: self.resource_attributes,
+            latency_histogram: self.latency_histogram,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
 
@@ -406,8 +1077,37 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             incarnation_id: self.incarnation_id,
             advertised_listener: self.advertised_listener,
             storage: self.storage,
+            storage_tiers: self.storage_tiers,
             listener,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
+            sasl_credentials: self.sasl_credentials,
+            sasl_oauth_bearer: self.sasl_oauth_bearer,
+            authorization: self.authorization,
+            authorizer: self.authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock: self.clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
+            fetch: self.fetch,
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            resource_attributes: self.resource_attributes,
+            latency_histogram: self.latency_histogram,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
 
@@ -415,6 +1115,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
         }
     }
 
+    /// Storage tiers by name, holding the topics created with a matching
+    /// `tansu.storage.tier` config
+    pub fn storage_tiers(self, storage_tiers: BTreeMap<String, Url>) -> Self {
+        Self {
+            storage_tiers,
+            ..self
+        }
+    }
+
     pub fn schema_registry(self, schema_registry: Option<Registry>) -> Self {
         Self {
             schema_registry,
@@ -430,46 +1139,347 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
         Self { lake_house, ..self }
     }
 
-    pub fn otlp_endpoint_url(self, otlp_endpoint_url: Option<Url>) -> Self {
+    pub fn admin_listener(self, admin_listener: Option<Url>) -> Self {
+        Self {
+            admin_listener,
+            ..self
+        }
+    }
+
+    /// Require this bearer token of each request to the admin listener, which
+    /// may then listen on a non-loopback address
+    pub fn admin_token(self, admin_token: Option<String>) -> Self {
+        Self {
+            admin_token: admin_token.map(Arc::from),
+            ..self
+        }
+    }
+
+    /// Require SASL authentication, with PLAIN using these credentials, a `file://` or `postgres://` URL,
+    /// and SCRAM using the user credentials held in storage
+    pub fn sasl_credentials(self, sasl_credentials: Option<Url>) -> Self {
+        Self {
+            sasl_credentials,
+            ..self
+        }
+    }
+
+    /// Accept SASL/OAUTHBEARER authentication, validating each JWT with this bearer
+    pub fn sasl_oauth_bearer(self, sasl_oauth_bearer: Option<OAuthBearer>) -> Self {
+        Self {
+            sasl_oauth_bearer,
+            ..self
+        }
+    }
+
+    /// Authorize requests with the ACL bindings held in storage
+    pub fn authorization(self, authorization: Option<Authorization>) -> Self {
         Self {
-            otlp_endpoint_url,
+            authorization,
+            ..self
+        }
+    }
+
+    /// Read the time from this clock in place of the system clock, expiring
+    /// sessions, transactions and records in virtual time
+    pub fn clock(self, clock: Option<Arc<dyn Clock>>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Authorize requests with this authorizer, in place of the ACL bindings held in storage
+    pub fn authorizer(self, authorizer: Option<Arc<dyn Authorizer>>) -> Self {
+        Self { authorizer, ..self }
+    }
+
+    /// Replicate linked topics from their upstream cluster at this interval
+    pub fn cluster_link_interval(self, cluster_link_interval: Option<Duration>) -> Self {
+        Self {
+            cluster_link_interval,
+            ..self
+        }
+    }
+
+    /// Capture the changes of tables from their replication slots at this interval
+    pub fn cdc_interval(self, cdc_interval: Option<Duration>) -> Self {
+        Self {
+            cdc_interval,
+            ..self
+        }
+    }
+
+    /// Delete the committed offsets of empty groups that have not been committed for this long
+    pub fn offsets_retention(self, offsets_retention: Option<Duration>) -> Self {
+        Self {
+            offsets_retention,
+            ..self
+        }
+    }
+
+    /// Deliver the new records of topics to their sink connectors at this interval
+    pub fn sink_interval(self, sink_interval: Option<Duration>) -> Self {
+        Self {
+            sink_interval,
+            ..self
+        }
+    }
+
+    /// Produce records posted to the admin listener in batches coalesced over this linger
+    pub fn produce_linger(self, produce_linger: Option<Duration>) -> Self {
+        Self {
+            produce_linger,
+            ..self
+        }
+    }
+
+    /// Abort the ongoing transactions that have exceeded their timeout at this interval
+    pub fn transaction_abort_interval(self, transaction_abort_interval: Option<Duration>) -> Self {
+        Self {
+            transaction_abort_interval,
+            ..self
+        }
+    }
+
+    /// Compact the topitions of compacted topics that are dirty enough at this interval
+    pub fn compaction_interval(self, compaction_interval: Option<Duration>) -> Self {
+        Self {
+            compaction_interval,
+            ..self
+        }
+    }
+
+    /// Produce the new rows of outbox tables into their topics at this interval
+    pub fn outbox_interval(self, outbox_interval: Option<Duration>) -> Self {
+        Self {
+            outbox_interval,
+            ..self
+        }
+    }
+
+    /// Produce the objects created in buckets into their topics at this interval
+    pub fn bucket_interval(self, bucket_interval: Option<Duration>) -> Self {
+        Self {
+            bucket_interval,
+            ..self
+        }
+    }
+
+    /// Record changes to topic metadata at this interval, for subscribers of the admin listener
+    pub fn topic_watch_interval(self, topic_watch_interval: Option<Duration>) -> Self {
+        Self {
+            topic_watch_interval,
+            ..self
+        }
+    }
+
+    /// Recommend partition counts from the load of each partition sampled at this interval, served by the admin listener
+    pub fn partition_analysis_interval(
+        self,
+        partition_analysis_interval: Option<Duration>,
+    ) -> Self {
+        Self {
+            partition_analysis_interval,
+            ..self
+        }
+    }
+
+    /// Presign URLs valid for this long, for consumers reading sealed batches directly from object storage
+    pub fn direct_read_expiry(self, direct_read_expiry: Option<Duration>) -> Self {
+        Self {
+            direct_read_expiry,
+            ..self
+        }
+    }
+
+    /// Close a connection sending a request larger than this many bytes
+    pub fn maximum_frame_size(self, maximum_frame_size: Option<usize>) -> Self {
+        Self {
+            maximum_frame_size,
+            ..self
+        }
+    }
+
+    /// The requests processed concurrently on each connection
+    pub fn maximum_in_flight(self, maximum_in_flight: Option<usize>) -> Self {
+        Self {
+            maximum_in_flight,
+            ..self
+        }
+    }
+
+    /// Decode produce requests of at least this many bytes as they are read
+    pub fn incremental_decode(self, incremental_decode: Option<usize>) -> Self {
+        Self {
+            incremental_decode,
+            ..self
+        }
+    }
+
+    /// Allow partitions to be made unavailable with `/partition-unavailable` on the admin listener
+    pub fn chaos(self, chaos: bool) -> Self {
+        Self { chaos, ..self }
+    }
+
+    /// Terminate TLS on the listener, with the advertised listener using the `tls` scheme
+    pub fn tls(self, tls: Option<Tls>) -> Self {
+        Self { tls, ..self }
+    }
+
+    /// The rack of this broker, directing clients in other racks to a replica in their own
+    pub fn rack(self, rack: Option<String>) -> Self {
+        Self { rack, ..self }
+    }
+
+    /// The client request timeout used to bound long-poll fetches
+    pub fn fetch_request_timeout(self, request_timeout: Option<Duration>) -> Self {
+        Self {
+            fetch: match request_timeout {
+                Some(request_timeout) => self.fetch.request_timeout(request_timeout),
+                None => self.fetch,
+            },
+            ..self
+        }
+    }
+
+    /// Include the end offset and consumer lag of each partition in fetch responses as a tagged field
+    pub fn fetch_hints(self, hints: bool) -> Self {
+        Self {
+            fetch: self.fetch.hints(hints),
+            ..self
+        }
+    }
+
+    /// Respond to a long-poll fetch this long before the client request timeout
+    pub fn fetch_safety_margin(self, safety_margin: Option<Duration>) -> Self {
+        Self {
+            fetch: match safety_margin {
+                Some(safety_margin) => self.fetch.safety_margin(safety_margin),
+                None => self.fetch,
+            },
+            ..self
+        }
+    }
+
+    /// Export metrics to each of these OTLP endpoints
+    pub fn otlp_endpoint_urls(self, otlp_endpoint_urls: Vec<Url>) -> Self {
+        Self {
+            otlp_endpoint_urls,
+            ..self
+        }
+    }
+
+    /// The resource attributes of exported metrics
+    pub fn resource_attributes(self, resource_attributes: ResourceAttributes) -> Self {
+        Self {
+            resource_attributes,
+            ..self
+        }
+    }
+
+    /// The aggregation of latency histograms exported with OTLP
+    pub fn latency_histogram(self, latency_histogram: LatencyHistogram) -> Self {
+        Self {
+            latency_histogram,
             ..self
         }
     }
 }
 
 impl Builder<i32, String, Uuid, Url, Url, Url> {
-    pub async fn build(self) -> Result<Broker<Controller<StorageContainer>, StorageContainer>> {
-        if let Some(otlp_endpoint_url) = self
-            .otlp_endpoint_url
-            .clone()
-            .inspect(|otlp_endpoint_url| debug!(%otlp_endpoint_url))
-        {
-            otel::metric_exporter(otlp_endpoint_url)?;
+    pub async fn build(
+        self,
+    ) -> Result<Broker<Leased<Controller<StorageContainer>, StorageContainer>, StorageContainer>>
+    {
+        if !self.otlp_endpoint_urls.is_empty() {
+            debug!(
+                otlp_endpoint_urls = ?self.otlp_endpoint_urls,
+                resource_attributes = ?self.resource_attributes
+            );
+
+            otel::metric_exporter(
+                &self.otlp_endpoint_urls,
+                &self.resource_attributes,
+                self.latency_histogram.clone(),
+            )?;
         }
 
-        let storage = StorageContainer::builder()
-            .cluster_id(self.cluster_id.clone())
-            .node_id(self.node_id)
-            .advertised_listener(self.advertised_listener.clone())
-            .schema_registry(self.schema_registry.clone())
-            .lake_house(self.lake_house.clone())
-            .storage(self.storage.clone())
-            .cancellation(self.cancellation.clone())
+        let mut advertised_listener = self.advertised_listener;
+
+        if self.tls.is_some() && advertised_listener.scheme() == "tcp" {
+            _ = advertised_listener.set_scheme("tls");
+        }
+
+        debug!(%advertised_listener);
+
+        let storage = self
+            .storage_tiers
+            .iter()
+            .fold(
+                StorageContainer::builder()
+                    .cluster_id(self.cluster_id.clone())
+                    .node_id(self.node_id)
+                    .advertised_listener(advertised_listener.clone())
+                    .schema_registry(self.schema_registry.clone())
+                    .lake_house(self.lake_house.clone())
+                    .storage(self.storage.clone())
+                    .cancellation(self.cancellation.clone()),
+                |builder, (name, storage)| builder.storage_tier(name, storage.clone()),
+            )
             .build()
             .await?;
 
-        let groups = Controller::with_storage(storage.clone())?;
+        let clock = self.clock.unwrap_or_else(|| Arc::new(SystemClock));
+
+        let groups = Controller::with_storage(storage.clone())
+            .map(|controller| controller.clock(clock.clone()))
+            .map(|controller| Leased::new(controller, storage.clone()))?;
+
+        let credentials = if let Some(sasl_credentials) = self.sasl_credentials.as_ref() {
+            Some(Credentials::from_url(sasl_credentials).await?)
+        } else {
+            None
+        };
+
+        let authorizer = self.authorizer.or_else(|| {
+            self.authorization.map(|authorization| {
+                Arc::new(AclAuthorizer::new(storage.clone(), authorization)) as Arc<dyn Authorizer>
+            })
+        });
 
         Ok(Broker {
             node_id: self.node_id,
             cluster_id: self.cluster_id.clone(),
             incarnation_id: self.incarnation_id,
             listener: self.listener,
-            advertised_listener: self.advertised_listener,
+            advertised_listener,
+            admin_listener: self.admin_listener,
+            admin_token: self.admin_token,
             storage,
             groups,
-            otlp_endpoint_url: self.otlp_endpoint_url,
+            fetch: self.fetch.rack(self.rack.clone()),
+            otlp_endpoint_urls: self.otlp_endpoint_urls,
+            schema_registry: self.schema_registry,
+            credentials,
+            oauth_bearer: self.sasl_oauth_bearer,
+            authorizer,
+            bucket_interval: self.bucket_interval,
+            cluster_link_interval: self.cluster_link_interval,
+            offsets_retention: self.offsets_retention,
+            sink_interval: self.sink_interval,
+            produce_linger: self.produce_linger,
+            transaction_abort_interval: self.transaction_abort_interval,
+            clock,
+            compaction_interval: self.compaction_interval,
+            cdc_interval: self.cdc_interval,
+            outbox_interval: self.outbox_interval,
+            topic_watch_interval: self.topic_watch_interval,
+            partition_analysis_interval: self.partition_analysis_interval,
+            direct_read_expiry: self.direct_read_expiry,
+            maximum_frame_size: self.maximum_frame_size,
+            maximum_in_flight: self.maximum_in_flight,
+            incremental_decode: self.incremental_decode,
+            chaos: self.chaos,
+            tls: self.tls,
+            rack: self.rack,
             cancellation: self.cancellation,
         })
     }
@@ -0,0 +1,1738 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Broker administration over HTTP
+//!
+//! - `GET /log-filter` returns the active tracing filter directives
+//! - `PUT /log-filter` replaces the active tracing filter with the directives in the body
+//! - `GET /schema-usage` returns the schema versions observed on produce for each topic as JSON
+//! - `GET /schema-quarantine` returns topics with audited fetched batches that no longer match their current schema as JSON
+//! - `GET /offset-translation?topic=..&partition=..&offset=..` translates an upstream offset of a linked topic to a local offset as JSON
+//! - `GET /topic-config?topic=..` returns each config of a topic as JSON, whether it is overridden or inherited from the broker default, and what would change were the override removed
+//! - `GET /storage-usage[?topic=..]` returns the records, bytes and oldest/newest timestamps stored by each partition as JSON
+//! - `GET /topic-changes?since=..[&timeout_ms=..]` long-polls for topics created, deleted, repartitioned or reconfigured after a change sequence as JSON
+//! - `GET /partition-recommendations[?topic=..]` returns the topics recommended more partitions or rebalancing from their sampled load, or the analysis of a topic, as JSON
+//! - `GET /direct-reads?topic=..&partition=..&offset=..[&limit=..]` returns presigned URLs for reading sealed batches of a partition directly from object storage as JSON
+//! - `GET /lineage?topic=..&partition=..&offset=..` traces the lineage of a record back to the original record that caused it as JSON
+//! - `GET /topic-read-only?topic=..` returns whether a topic is read only as JSON
+//! - `PUT /topic-read-only?topic=..` makes a topic read only, with an optional reason in the body
+//! - `DELETE /topic-read-only?topic=..` makes a read only topic writable again
+//! - `GET /records?topic=..&partition=..&offset=..[&max_bytes=..]` streams the record batches of a partition from an offset, as stored with `Accept: application/octet-stream`, or decoded with the schema of the topic with `Accept: application/json`, fetching each chunk from storage only as the client reads. With fetch hints enabled, the `tansu-end-offset` and `tansu-lag` headers carry the last stable offset of the partition and the records between the requested offset and it
+//! - `POST /records?topic=..[&partition=..]` produces the body as the value of a record to a partition (by default 0), with an optional base64 encoded `tansu-key` header, returning its offset as JSON once its lingering batch has been produced. A `multipart/form-data` body produces each part as a record (with its own optional `tansu-key` header) as it arrives, returning their offsets as JSON. A body (or part) larger than the `max.message.bytes` of the topic is rejected with a `413 Payload Too Large` without being read further
+//! - `GET /producer-replays[?producer_id=..]` returns the duplicate and out of order sequences detected for each (or the named) idempotent producer as JSON
+//! - `GET /partition-unavailable` returns the topitions made unavailable, with their error and remaining duration as JSON
+//! - `PUT /partition-unavailable?topic=..&partition=..` makes a topition answer produce and fetch with a retriable error, with an optional JSON body of the `error` (`not_leader_or_follower` or `kafka_storage_error`) and `duration_ms`
+//! - `DELETE /partition-unavailable?topic=..&partition=..` makes a topition available again
+//! - `GET /group-export[?group=..]` returns the state of each (or the named) consumer group as a record batch in the `__consumer_offsets` format
+//! - `GET /sink-connector?topic=..` returns the sink connector of a topic as JSON
+//! - `PUT /sink-connector?topic=..` replaces the sink connector of a topic with the JSON body
+//! - `DELETE /sink-connector?topic=..` removes the sink connector of a topic
+//! - `GET /sink-connector-status?topic=..` returns the state, error count, last failure and committed offsets of the sink connector of a topic as JSON
+//! - `PUT /sink-connector-pause?topic=..` pauses delivery to the sink connector of a topic
+//! - `DELETE /sink-connector-pause?topic=..` resumes delivery to the sink connector of a topic
+//! - `GET /subject-versions?topic=..` returns every registered version of the schema for a topic, including those soft deleted, as JSON
+//! - `POST /subject-versions?topic=..&schema_type=..` registers the body as a new version of the schema (`avro`, `json` or `proto`) for a topic, returning its version as JSON
+//! - `DELETE /subject-versions?topic=..[&version=..][&permanent=true]` soft deletes a version (or all versions), or permanently deletes a soft deleted version (or all soft deleted versions, unless the topic exists), returning the deleted versions as JSON
+//! - `PUT /subject-restore?topic=..[&version=..]` restores a soft deleted version (or all soft deleted versions), returning the restored versions as JSON
+//! - `PUT /subject-rollback?topic=..&version=..` promotes an older registered version as the latest schema for a topic
+//!
+//! The listener binds to a loopback address unless an admin token is
+//! configured, in which case every request must carry it with
+//! `Authorization: Bearer <token>`, otherwise receiving a `401 Unauthorized`.
+//!
+//! The `/partition-unavailable` routes inject faults into produce and fetch,
+//! and are only served (otherwise `404 Not Found`) when chaos is enabled,
+//! which it is not by default.
+//!
+//! Each successful `GET` (other than the streamed `/records`) has an `ETag` of
+//! its body, so that a polling client may revalidate with `If-None-Match`,
+//! receiving a `304 Not Modified` without a body when nothing has changed.
+
+use std::{
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::ParseIntError,
+    str::FromStr as _,
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use bytes::Bytes;
+use futures::{TryStreamExt as _, stream};
+use http_body_util::{
+    BodyExt as _, Full, LengthLimitError, Limited, StreamBody, combinators::UnsyncBoxBody,
+};
+use hyper::{
+    Method, Request, Response, StatusCode,
+    body::{Frame, Incoming},
+    header::{
+        ACCEPT, AUTHORIZATION, CONTENT_TYPE, ETAG, HeaderValue, IF_NONE_MATCH, WWW_AUTHENTICATE,
+    },
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use serde_json::Value;
+use tansu_client::lineage::Location;
+use tansu_sans_io::{
+    ConfigResource, ErrorCode, IsolationLevel,
+    record::{deflated, inflated},
+};
+use tansu_schema::{AsJsonValue as _, Registry, Schema, SchemaType, subject::Associations};
+use tansu_storage::{OffsetTranslation, Storage, Topition, consumer_offsets, inheritance};
+use tokio::{net::TcpListener, task::JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+use url::{Url, form_urlencoded};
+
+use crate::{
+    Error, Result,
+    broker::{
+        chaos::{self, Injection},
+        lineage,
+        linger::Linger,
+        message_size::MAX_MESSAGE_BYTES,
+        read_only::ReadOnly,
+        replay,
+        sasl::matches,
+        scaling::Recommendations,
+        sink::{SinkConfig, SinkStatus},
+        topic_config::TopicConfig,
+        watch::TopicChanges,
+    },
+    otel,
+};
+
+const LOG_FILTER: &str = "/log-filter";
+const SCHEMA_USAGE: &str = "/schema-usage";
+const SCHEMA_QUARANTINE: &str = "/schema-quarantine";
+const OFFSET_TRANSLATION: &str = "/offset-translation";
+const TOPIC_CONFIG: &str = "/topic-config";
+const STORAGE_USAGE: &str = "/storage-usage";
+const TOPIC_CHANGES: &str = "/topic-changes";
+const PARTITION_RECOMMENDATIONS: &str = "/partition-recommendations";
+const DIRECT_READS: &str = "/direct-reads";
+const LINEAGE: &str = "/lineage";
+const TOPIC_READ_ONLY: &str = "/topic-read-only";
+const GROUP_EXPORT: &str = "/group-export";
+const SINK_CONNECTOR: &str = "/sink-connector";
+const SINK_CONNECTOR_STATUS: &str = "/sink-connector-status";
+const SINK_CONNECTOR_PAUSE: &str = "/sink-connector-pause";
+const RECORDS: &str = "/records";
+const PRODUCER_REPLAYS: &str = "/producer-replays";
+const PARTITION_UNAVAILABLE: &str = "/partition-unavailable";
+const SUBJECT_VERSIONS: &str = "/subject-versions";
+const SUBJECT_RESTORE: &str = "/subject-restore";
+const SUBJECT_ROLLBACK: &str = "/subject-rollback";
+
+/// How long a topic changes long-poll waits when no timeout is requested
+const TOPIC_CHANGES_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of direct reads returned when no limit is requested
+const DIRECT_READS_LIMIT: usize = 100;
+
+/// The bytes of records fetched when no maximum is requested, which is also
+/// the largest record produced to a topic without a `max.message.bytes`
+const RECORDS_MAX_BYTES: u32 = 1_048_576;
+
+/// The bytes of records fetched from storage for each chunk of a streamed
+/// response
+const RECORDS_CHUNK_BYTES: u32 = 65_536;
+
+const APPLICATION_JSON: &str = "application/json";
+const APPLICATION_OCTET_STREAM: &str = "application/octet-stream";
+
+/// The base64 encoded key of a record produced with `POST /records`
+const TANSU_KEY: &str = "tansu-key";
+
+/// The last stable offset of a partition fetched with `GET /records`
+const TANSU_END_OFFSET: &str = "tansu-end-offset";
+
+/// The records between the requested offset and the end offset of a
+/// partition fetched with `GET /records`
+const TANSU_LAG: &str = "tansu-lag";
+
+/// The local offset of an upstream offset of a linked topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct TranslatedOffset {
+    topic: String,
+    partition: i32,
+    upstream: i64,
+    local: i64,
+    checkpoint: OffsetTranslation,
+}
+
+/// The offset of a record produced with `POST /records`
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct ProducedOffset {
+    partition: i32,
+    offset: i64,
+}
+
+/// The body of a response, either buffered or streamed
+type Body = UnsyncBoxBody<Bytes, io::Error>;
+
+/// The version of a schema registered with `POST /subject-versions`
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct RegisteredVersion {
+    version: u32,
+}
+
+/// A subject is associated while its topic exists in storage
+struct TopicAssociations<'a, S>(&'a S);
+
+#[async_trait]
+impl<S> Associations for TopicAssociations<'_, S>
+where
+    S: Storage,
+{
+    async fn associated(&self, topic: &str) -> tansu_schema::Result<bool> {
+        self.0
+            .describe_config(topic, ConfigResource::Topic, None)
+            .await
+            .map(|result| result.error_code == i16::from(ErrorCode::None))
+            .map_err(|err| tansu_schema::Error::Message(err.to_string()))
+    }
+}
+
+/// The topition from a query string
+fn topition(query: Option<&str>) -> Option<Topition> {
+    let (mut topic, mut partition) = (None, None);
+
+    for (name, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        match name.as_ref() {
+            "topic" => topic = Some(value.into_owned()),
+            "partition" => partition = value.parse::<i32>().ok(),
+            _ => (),
+        }
+    }
+
+    Some(Topition::new(topic?, partition?))
+}
+
+/// The topition and offset from a query string
+fn topition_offset(query: Option<&str>) -> Option<(Topition, i64)> {
+    let (mut topic, mut partition, mut offset) = (None, None, None);
+
+    for (name, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        match name.as_ref() {
+            "topic" => topic = Some(value.into_owned()),
+            "partition" => partition = value.parse::<i32>().ok(),
+            "offset" => offset = value.parse::<i64>().ok(),
+            _ => (),
+        }
+    }
+
+    Some((Topition::new(topic?, partition?), offset?))
+}
+
+async fn offset_translation<S>(storage: &S, query: Option<&str>) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some((topition, upstream)) = topition_offset(query) else {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            "expecting topic, partition and offset",
+        );
+    };
+
+    match storage.offset_translation(&topition, upstream).await {
+        Ok(Some(checkpoint)) => serde_json::to_vec(&TranslatedOffset {
+            topic: topition.topic().to_owned(),
+            partition: topition.partition(),
+            upstream,
+            local: checkpoint.translate(upstream),
+            checkpoint,
+        })
+        .map_err(Into::into)
+        .map_or_else(
+            |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            |body| respond(StatusCode::OK, body),
+        ),
+
+        Ok(None) => respond(StatusCode::NOT_FOUND, ""),
+
+        Err(err) => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// The topic from a query string
+fn topic(query: Option<&str>) -> Option<String> {
+    form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .find(|(name, _)| name == "topic")
+        .map(|(_, value)| value.into_owned())
+}
+
+async fn topic_config<S>(storage: &S, query: Option<&str>) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some(topic) = topic(query) else {
+        return respond(StatusCode::BAD_REQUEST, "expecting topic");
+    };
+
+    match storage
+        .describe_config(&topic, ConfigResource::Topic, None)
+        .await
+    {
+        Ok(result) if result.error_code == i16::from(ErrorCode::None) => {
+            serde_json::to_vec(&inheritance(
+                result
+                    .configs
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|config| (config.name, config.value)),
+            ))
+            .map_err(Into::into)
+            .map_or_else(
+                |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                |body| respond(StatusCode::OK, body),
+            )
+        }
+
+        Ok(_) => respond(StatusCode::NOT_FOUND, ""),
+
+        Err(err) => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+async fn storage_usage<S>(storage: &S, query: Option<&str>) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let topics = topic(query).map(|topic| vec![topic]);
+
+    match storage.topition_usage(topics.as_deref()).await {
+        Ok(usage) => serde_json::to_vec(&usage).map_err(Into::into).map_or_else(
+            |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            |body| respond(StatusCode::OK, body),
+        ),
+
+        Err(err) => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+async fn direct_reads<S>(
+    storage: &S,
+    expires_in: Duration,
+    query: Option<&str>,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some((topition, offset)) = topition_offset(query) else {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            "expecting topic, partition and offset",
+        );
+    };
+
+    let limit = form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .find(|(name, _)| name == "limit")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(DIRECT_READS_LIMIT);
+
+    match storage
+        .direct_reads(&topition, offset, limit, expires_in)
+        .await
+    {
+        Ok(direct_reads) => serde_json::to_vec(&direct_reads)
+            .map_err(Into::into)
+            .map_or_else(
+                |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                |body| respond(StatusCode::OK, body),
+            ),
+
+        Err(err) => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// Describe whether a topic is read only, or when altering, make it read only
+/// with `Some(reason)` or writable again with `None`
+async fn topic_read_only<S>(
+    storage: &S,
+    query: Option<&str>,
+    alteration: Option<Option<String>>,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some(topic) = topic(query) else {
+        return respond(StatusCode::BAD_REQUEST, "expecting topic");
+    };
+
+    let read_only = match alteration {
+        Some(reason) => {
+            let read_only = ReadOnly {
+                topic,
+                read_only: reason.is_some(),
+                reason: reason.filter(|reason| !reason.is_empty()),
+            };
+
+            match storage.incremental_alter_resource(read_only.alter()).await {
+                Ok(response) if response.error_code == i16::from(ErrorCode::None) => read_only,
+
+                Ok(response)
+                    if response.error_code == i16::from(ErrorCode::UnknownTopicOrPartition) =>
+                {
+                    return respond(StatusCode::NOT_FOUND, "");
+                }
+
+                Ok(response) => {
+                    return respond(
+                        StatusCode::BAD_REQUEST,
+                        response.error_message.unwrap_or_default(),
+                    );
+                }
+
+                Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+        }
+
+        None => match ReadOnly::describe(storage, &topic).await {
+            Ok(read_only) => read_only,
+            Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        },
+    };
+
+    serde_json::to_vec(&read_only)
+        .map_err(Into::into)
+        .map_or_else(
+            |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            |body| respond(StatusCode::OK, body),
+        )
+}
+
+/// Describe the sink connector of a topic, or when altering, replace it
+/// with `Some(sink)` or remove it with `None`
+async fn sink_connector<S>(
+    storage: &S,
+    query: Option<&str>,
+    alteration: Option<Option<SinkConfig>>,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some(topic) = topic(query) else {
+        return respond(StatusCode::BAD_REQUEST, "expecting topic");
+    };
+
+    let sink = match alteration {
+        Some(sink) => {
+            if let Some(Err(err)) = sink.as_ref().map(SinkConfig::validate) {
+                return respond(StatusCode::BAD_REQUEST, err.to_string());
+            }
+
+            match storage
+                .incremental_alter_resource(SinkConfig::alter(&topic, sink.as_ref()))
+                .await
+            {
+                Ok(response) if response.error_code == i16::from(ErrorCode::None) => sink,
+
+                Ok(response)
+                    if response.error_code == i16::from(ErrorCode::UnknownTopicOrPartition) =>
+                {
+                    return respond(StatusCode::NOT_FOUND, "");
+                }
+
+                Ok(response) => {
+                    return respond(
+                        StatusCode::BAD_REQUEST,
+                        response.error_message.unwrap_or_default(),
+                    );
+                }
+
+                Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+        }
+
+        None => match SinkConfig::describe(storage, &topic).await {
+            Ok(sink) => sink,
+            Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        },
+    };
+
+    let Some(sink) = sink else {
+        return respond(StatusCode::NOT_FOUND, "");
+    };
+
+    serde_json::to_vec(&sink).map_err(Into::into).map_or_else(
+        |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        |body| respond(StatusCode::OK, body),
+    )
+}
+
+/// Describe the status of the sink connector of a topic, after pausing it
+/// with `Some(true)` or resuming it with `Some(false)`
+async fn sink_connector_status<S>(
+    storage: &S,
+    query: Option<&str>,
+    pause: Option<bool>,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some(topic) = topic(query) else {
+        return respond(StatusCode::BAD_REQUEST, "expecting topic");
+    };
+
+    let status = match SinkStatus::describe(storage, &topic).await {
+        Ok(Some(status)) => status,
+        Ok(None) => return respond(StatusCode::NOT_FOUND, ""),
+        Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+
+    let status = match pause {
+        Some(paused) => {
+            match storage
+                .incremental_alter_resource(SinkStatus::pause(&topic, paused))
+                .await
+            {
+                Ok(response) if response.error_code == i16::from(ErrorCode::None) => {}
+
+                Ok(response) => {
+                    return respond(
+                        StatusCode::BAD_REQUEST,
+                        response.error_message.unwrap_or_default(),
+                    );
+                }
+
+                Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+
+            match SinkStatus::describe(storage, &topic).await {
+                Ok(Some(status)) => status,
+                Ok(None) => return respond(StatusCode::NOT_FOUND, ""),
+                Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+        }
+
+        None => status,
+    };
+
+    serde_json::to_vec(&status).map_err(Into::into).map_or_else(
+        |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        |body| respond(StatusCode::OK, body),
+    )
+}
+
+/// The content type of records, negotiated from the media ranges of an
+/// accept header in order of preference, defaulting to the stored encoding
+fn records_content_type(accept: Option<&str>) -> Option<&'static str> {
+    let Some(accept) = accept else {
+        return Some(APPLICATION_OCTET_STREAM);
+    };
+
+    accept
+        .split(',')
+        .filter_map(|media_range| media_range.split(';').next())
+        .map(str::trim)
+        .find_map(|media_type| match media_type {
+            APPLICATION_JSON => Some(APPLICATION_JSON),
+            APPLICATION_OCTET_STREAM | "application/*" | "*/*" => Some(APPLICATION_OCTET_STREAM),
+            _ => None,
+        })
+}
+
+/// Decode fetched batches into JSON with the schema of a topic, each record
+/// including its offset
+fn records_as_json(schema: &Schema, batches: Vec<deflated::Batch>) -> Result<Vec<Value>> {
+    let mut records = vec![];
+
+    for batch in batches {
+        let batch = inflated::Batch::try_from(batch)?;
+
+        let Value::Array(decoded) = schema.as_json_value(&batch)? else {
+            continue;
+        };
+
+        for (record, mut decoded) in batch.records.iter().zip(decoded) {
+            if let Value::Object(ref mut decoded) = decoded {
+                _ = decoded.insert(
+                    "offset".into(),
+                    (batch.base_offset + i64::from(record.offset_delta)).into(),
+                );
+            }
+
+            records.push(decoded);
+        }
+    }
+
+    Ok(records)
+}
+
+/// A fetch of a topition streamed in chunks, from an offset until the end of
+/// the topition or its maximum bytes
+struct Streamed<S> {
+    storage: S,
+    topition: Topition,
+    offset: i64,
+    remaining: u32,
+}
+
+impl<S> Streamed<S>
+where
+    S: Storage,
+{
+    /// The next chunk of batches, which is empty once the stream has ended
+    async fn next(&mut self) -> Result<Vec<deflated::Batch>> {
+        if self.remaining == 0 {
+            return Ok(vec![]);
+        }
+
+        let batches = self
+            .storage
+            .fetch(
+                &self.topition,
+                self.offset,
+                1,
+                self.remaining.min(RECORDS_CHUNK_BYTES),
+                IsolationLevel::ReadCommitted,
+            )
+            .await?;
+
+        for batch in &batches {
+            self.offset = batch.base_offset + i64::from(batch.last_offset_delta) + 1;
+            self.remaining = self
+                .remaining
+                .saturating_sub(u32::try_from(batch.batch_length).unwrap_or(u32::MAX));
+        }
+
+        debug!(
+            topition = ?self.topition,
+            offset = self.offset,
+            remaining = self.remaining,
+            batches = batches.len()
+        );
+
+        Ok(batches)
+    }
+}
+
+/// A streamed body of chunks, each only fetched once the connection is ready
+/// for more, aborting the response on an error
+fn streamed<T>(chunks: T) -> Body
+where
+    T: futures::Stream<Item = Result<Bytes>> + Send + 'static,
+{
+    StreamBody::new(
+        chunks
+            .map_ok(Frame::data)
+            .map_err(|err| io::Error::other(err.to_string())),
+    )
+    .boxed_unsync()
+}
+
+async fn records<S>(
+    storage: &S,
+    schema_registry: Option<&Registry>,
+    fetch_hints: bool,
+    accept: Option<&str>,
+    query: Option<&str>,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some((topition, offset)) = topition_offset(query) else {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            "expecting topic, partition and offset",
+        );
+    };
+
+    let Some(content_type) = records_content_type(accept) else {
+        return respond(
+            StatusCode::NOT_ACCEPTABLE,
+            format!("expecting {APPLICATION_JSON} or {APPLICATION_OCTET_STREAM}"),
+        );
+    };
+
+    let max_bytes = form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .find(|(name, _)| name == "max_bytes")
+        .and_then(|(_, value)| value.parse::<u32>().ok())
+        .unwrap_or(RECORDS_MAX_BYTES);
+
+    let schema = if content_type == APPLICATION_JSON {
+        let Some(schema_registry) = schema_registry else {
+            return respond(StatusCode::NOT_ACCEPTABLE, "no schema registry");
+        };
+
+        match schema_registry.schema(topition.topic()).await {
+            Ok(Some(schema)) => Some(schema),
+
+            Ok(None) => {
+                return respond(
+                    StatusCode::NOT_ACCEPTABLE,
+                    format!("topic {} has no schema", topition.topic()),
+                );
+            }
+
+            Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        }
+    } else {
+        None
+    };
+
+    let end_offset = if fetch_hints {
+        match storage.offset_stage(&topition).await {
+            Ok(stage) => Some(stage.last_stable()),
+            Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        }
+    } else {
+        None
+    };
+
+    let mut fetch = Streamed {
+        storage: storage.clone(),
+        topition,
+        offset,
+        remaining: max_bytes,
+    };
+
+    // the first chunk is fetched before responding, so that a storage error
+    // is reported with a status rather than by aborting the stream
+    let first = match fetch.next().await {
+        Ok(batches) => batches,
+        Err(err) => return respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+
+    let body = if let Some(schema) = schema {
+        // records are streamed as a JSON array, opened by the first record
+        // and closed once the fetch has ended
+        let chunks = stream::try_unfold(
+            (Some(first), fetch, schema, false, false),
+            |(first, mut fetch, schema, opened, closed)| async move {
+                if closed {
+                    return Ok(None);
+                }
+
+                let batches = match first {
+                    Some(batches) => batches,
+                    None => fetch.next().await?,
+                };
+
+                if batches.is_empty() {
+                    let chunk = Bytes::from_static(if opened { b"]" } else { b"[]" });
+                    return Ok(Some((chunk, (None, fetch, schema, opened, true))));
+                }
+
+                let mut chunk = vec![];
+                let mut opened = opened;
+
+                for record in records_as_json(&schema, batches)? {
+                    chunk.push(if opened { b',' } else { b'[' });
+                    serde_json::to_writer(&mut chunk, &record)?;
+                    opened = true;
+                }
+
+                Ok(Some((
+                    Bytes::from(chunk),
+                    (None, fetch, schema, opened, false),
+                )))
+            },
+        );
+
+        streamed(chunks)
+    } else {
+        let chunks = stream::try_unfold((Some(first), fetch), |(first, mut fetch)| async move {
+            let batches = match first {
+                Some(batches) => batches,
+                None => fetch.next().await?,
+            };
+
+            if batches.is_empty() {
+                return Ok(None);
+            }
+
+            let chunk = batches
+                .into_iter()
+                .map(Bytes::from)
+                .collect::<Vec<_>>()
+                .concat();
+
+            Ok(Some((Bytes::from(chunk), (None, fetch))))
+        });
+
+        streamed(chunks)
+    };
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, content_type);
+
+    if let Some(end_offset) = end_offset {
+        response = response
+            .header(TANSU_END_OFFSET, end_offset)
+            .header(TANSU_LAG, end_offset.saturating_sub(offset).max(0));
+    }
+
+    response.body(body).map_err(Into::into)
+}
+
+/// The largest record that may be produced to a topic
+async fn max_message_bytes<S>(storage: &S, topic: &str) -> usize
+where
+    S: Storage,
+{
+    TopicConfig::describe(storage, topic)
+        .await
+        .inspect_err(|err| debug!(topic, ?err))
+        .ok()
+        .and_then(|config| {
+            config
+                .get(MAX_MESSAGE_BYTES)
+                .and_then(|value| value.parse::<usize>().ok())
+        })
+        .unwrap_or(RECORDS_MAX_BYTES as usize)
+}
+
+/// Produce each part of a multipart body as a record as soon as it has been
+/// received, with the optional base64 encoded key of a part in its
+/// `tansu-key` header
+async fn produce_parts<S>(
+    linger: &Linger<S>,
+    topition: &Topition,
+    body: Incoming,
+    boundary: String,
+    limit: usize,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let constraints =
+        multer::Constraints::new().size_limit(multer::SizeLimit::new().per_field(limit as u64));
+
+    let mut multipart =
+        multer::Multipart::with_constraints(body.into_data_stream(), boundary, constraints);
+
+    let mut produced = vec![];
+
+    loop {
+        let part = match multipart.next_field().await {
+            Ok(Some(part)) => part,
+            Ok(None) => break,
+            Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+        };
+
+        let key = match part
+            .headers()
+            .get(TANSU_KEY)
+            .and_then(|key| key.to_str().ok())
+            .map(|key| STANDARD.decode(key))
+            .transpose()
+        {
+            Ok(key) => key.map(Bytes::from),
+            Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+        };
+
+        let value = match part.bytes().await {
+            Ok(value) => value,
+
+            Err(err @ multer::Error::FieldSizeExceeded { .. }) => {
+                return respond(StatusCode::PAYLOAD_TOO_LARGE, err.to_string());
+            }
+
+            Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+        };
+
+        match linger.produce(topition, key, Some(value)).await {
+            Ok(offset) => produced.push(ProducedOffset {
+                partition: topition.partition(),
+                offset,
+            }),
+
+            Err(err) => return produce_failed(err),
+        }
+    }
+
+    serde_json::to_vec(&produced).map_or_else(
+        |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        |body| respond(StatusCode::OK, body),
+    )
+}
+
+fn produce_failed(err: Error) -> Result<Response<Body>> {
+    match err {
+        Error::Storage(tansu_storage::Error::Api(ErrorCode::UnknownTopicOrPartition)) => {
+            respond(StatusCode::NOT_FOUND, "")
+        }
+
+        err => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// Produce the body to the topition of a query string, coalesced with other
+/// records posted to the same topition by the linger. A multipart body is
+/// produced as a record for each part, otherwise the body is a single record,
+/// with the body (or each part) read no further than the `max.message.bytes`
+/// of the topic.
+async fn produce<S>(
+    storage: &S,
+    linger: &Linger<S>,
+    query: Option<&str>,
+    key: Option<&str>,
+    content_type: Option<&str>,
+    body: Incoming,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some(topic) = topic(query) else {
+        return respond(StatusCode::BAD_REQUEST, "expecting topic");
+    };
+
+    let partition = match form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .find(|(name, _)| name == "partition")
+        .map(|(_, value)| value.parse::<i32>())
+        .transpose()
+    {
+        Ok(partition) => partition.unwrap_or_default(),
+        Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+    };
+
+    let key = match key.map(|key| STANDARD.decode(key)).transpose() {
+        Ok(key) => key.map(Bytes::from),
+        Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+    };
+
+    let limit = max_message_bytes(storage, &topic).await;
+    let topition = Topition::new(topic, partition);
+
+    if let Some(boundary) =
+        content_type.and_then(|content_type| multer::parse_boundary(content_type).ok())
+    {
+        return produce_parts(linger, &topition, body, boundary, limit).await;
+    }
+
+    let value = match Limited::new(body, limit).collect().await {
+        Ok(value) => value.to_bytes(),
+
+        Err(err) if err.is::<LengthLimitError>() => {
+            return respond(StatusCode::PAYLOAD_TOO_LARGE, err.to_string());
+        }
+
+        Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+    };
+
+    linger
+        .produce(&topition, key, Some(value))
+        .await
+        .and_then(|offset| {
+            serde_json::to_vec(&ProducedOffset { partition, offset }).map_err(Into::into)
+        })
+        .map_or_else(produce_failed, |body| respond(StatusCode::OK, body))
+}
+
+/// The version from a query string, which is absent when not supplied
+fn version(query: Option<&str>) -> std::result::Result<Option<u32>, ParseIntError> {
+    form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .find(|(name, _)| name == "version")
+        .map(|(_, value)| value.parse::<u32>())
+        .transpose()
+}
+
+fn subject_respond<T>(result: tansu_schema::Result<T>) -> Result<Response<Body>>
+where
+    T: Serialize,
+{
+    match result {
+        Ok(body) => serde_json::to_vec(&body).map_or_else(
+            |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            |body| respond(StatusCode::OK, body),
+        ),
+
+        Err(err @ tansu_schema::Error::SchemaVersionNotFound { .. }) => {
+            respond(StatusCode::NOT_FOUND, err.to_string())
+        }
+
+        Err(
+            err @ (tansu_schema::Error::SchemaVersionNotSoftDeleted { .. }
+            | tansu_schema::Error::SubjectInUse(_)),
+        ) => respond(StatusCode::CONFLICT, err.to_string()),
+
+        Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+    }
+}
+
+/// Manage the lifecycle of the versions of the schema for a topic
+async fn subject<S>(
+    schema_registry: &Registry,
+    storage: &S,
+    method: &Method,
+    path: &str,
+    query: Option<&str>,
+    body: Bytes,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let Some(topic) = topic(query) else {
+        return respond(StatusCode::BAD_REQUEST, "expecting topic");
+    };
+
+    let version = match version(query) {
+        Ok(version) => version,
+        Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+    };
+
+    let parameter = |parameter: &str| {
+        form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+            .find(|(name, _)| name == parameter)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    match (method, path) {
+        (&Method::GET, SUBJECT_VERSIONS) => subject_respond(schema_registry.versions(&topic).await),
+
+        (&Method::POST, SUBJECT_VERSIONS) => {
+            let Some(schema_type) = parameter("schema_type") else {
+                return respond(StatusCode::BAD_REQUEST, "expecting schema_type");
+            };
+
+            let schema_type = match SchemaType::from_str(&schema_type) {
+                Ok(schema_type) => schema_type,
+                Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+            };
+
+            subject_respond(
+                schema_registry
+                    .register(&topic, schema_type, body)
+                    .await
+                    .map(|version| RegisteredVersion { version }),
+            )
+        }
+
+        (&Method::DELETE, SUBJECT_VERSIONS) => {
+            if parameter("permanent").is_some_and(|permanent| permanent == "true") {
+                subject_respond(
+                    schema_registry
+                        .permanent_delete(&topic, version, &TopicAssociations(storage))
+                        .await,
+                )
+            } else {
+                subject_respond(schema_registry.soft_delete(&topic, version).await)
+            }
+        }
+
+        (&Method::PUT, SUBJECT_RESTORE) => {
+            subject_respond(schema_registry.restore(&topic, version).await)
+        }
+
+        (&Method::PUT, SUBJECT_ROLLBACK) => {
+            let Some(version) = version else {
+                return respond(StatusCode::BAD_REQUEST, "expecting version");
+            };
+
+            subject_respond(schema_registry.rollback(&topic, version).await)
+        }
+
+        _ => respond(StatusCode::NOT_FOUND, ""),
+    }
+}
+
+async fn group_export<S>(storage: &S, query: Option<&str>) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    let groups = form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .filter(|(name, _)| name == "group")
+        .map(|(_, value)| value.into_owned())
+        .collect::<Vec<_>>();
+
+    consumer_offsets::export(storage, (!groups.is_empty()).then_some(&groups[..]))
+        .await
+        .map_or_else(
+            |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            |batch| respond(StatusCode::OK, batch),
+        )
+}
+
+async fn watch_topics(changes: &TopicChanges, query: Option<&str>) -> Result<Response<Body>> {
+    let (mut since, mut timeout) = (0, TOPIC_CHANGES_TIMEOUT);
+
+    for (name, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        match name.as_ref() {
+            "since" => match value.parse::<u64>() {
+                Ok(value) => since = value,
+                Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+            },
+
+            "timeout_ms" => match value.parse::<u64>() {
+                Ok(value) => timeout = Duration::from_millis(value),
+                Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+            },
+
+            _ => (),
+        }
+    }
+
+    changes
+        .since(since, timeout)
+        .await
+        .and_then(|changes| serde_json::to_vec(&changes).map_err(Into::into))
+        .map_or_else(
+            |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            |body| respond(StatusCode::OK, body),
+        )
+}
+
+/// The address of the admin listener, which must be a loopback address unless
+/// requests are authenticated with an admin token
+pub(crate) fn bind_addr(listener: &Url, authenticated: bool) -> Result<SocketAddr> {
+    let addr = match listener.host() {
+        Some(url::Host::Domain("localhost")) => SocketAddr::from((
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            listener.port().unwrap_or(9093),
+        )),
+
+        _ => super::bind_addr(listener, 9093),
+    };
+
+    if authenticated || addr.ip().is_loopback() {
+        Ok(addr)
+    } else {
+        Err(Error::Message(format!(
+            "admin listener: {addr} is not a loopback address, an admin token is required"
+        )))
+    }
+}
+
+/// Whether the `Authorization` header of a request carries the admin token
+fn authorized(header: Option<&HeaderValue>, token: &str) -> bool {
+    header
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .is_some_and(|bearer| matches(token.as_bytes(), bearer.trim().as_bytes()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn serve<S>(
+    listener: TcpListener,
+    token: Option<Arc<str>>,
+    schema_registry: Option<Registry>,
+    storage: S,
+    topic_changes: Option<TopicChanges>,
+    recommendations: Option<Recommendations>,
+    direct_read_expiry: Option<Duration>,
+    linger: Option<Linger<S>>,
+    fetch_hints: bool,
+    chaos: bool,
+    cancellation: CancellationToken,
+) -> Result<()>
+where
+    S: Storage,
+{
+    debug!(listener = ?listener.local_addr().ok());
+
+    let mut set = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            Ok((stream, addr)) = listener.accept() => {
+                debug!(%addr);
+
+                let schema_registry = schema_registry.clone();
+                let storage = storage.clone();
+                let topic_changes = topic_changes.clone();
+                let recommendations = recommendations.clone();
+                let linger = linger.clone();
+                let token = token.clone();
+
+                _ = set.spawn(async move {
+                    if let Err(err) = http1::Builder::new()
+                        .serve_connection(
+                            TokioIo::new(stream),
+                            service_fn(|req| {
+                                handle(
+                                    req,
+                                    token.as_deref(),
+                                    schema_registry.as_ref(),
+                                    &storage,
+                                    topic_changes.as_ref(),
+                                    recommendations.as_ref(),
+                                    direct_read_expiry,
+                                    linger.as_ref(),
+                                    fetch_hints,
+                                    chaos,
+                                )
+                            }),
+                        )
+                        .await
+                    {
+                        error!(?err);
+                    }
+                });
+            }
+
+            v = set.join_next(), if !set.is_empty() => {
+                debug!(?v);
+            }
+
+            message = cancellation.cancelled() => {
+                debug!(?message);
+                break;
+            }
+        }
+    }
+
+    set.abort_all();
+    Ok(())
+}
+
+/// The entity tag of a response body
+fn etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Whether an `If-None-Match` header matches an entity tag, using the weak
+/// comparison of RFC 9110
+fn if_none_match(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// Handle an authorized request, tagging the body of each successful `GET`
+#[allow(clippy::too_many_arguments)]
+async fn handle<S>(
+    req: Request<Incoming>,
+    token: Option<&str>,
+    schema_registry: Option<&Registry>,
+    storage: &S,
+    topic_changes: Option<&TopicChanges>,
+    recommendations: Option<&Recommendations>,
+    direct_read_expiry: Option<Duration>,
+    linger: Option<&Linger<S>>,
+    fetch_hints: bool,
+    chaos: bool,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    if let Some(token) = token
+        && !authorized(req.headers().get(AUTHORIZATION), token)
+    {
+        debug!(method = %req.method(), uri = %req.uri());
+
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(WWW_AUTHENTICATE, "Bearer")
+            .body(full(Bytes::new()))
+            .map_err(Into::into);
+    }
+
+    // streamed records are not buffered to tag their body
+    let revalidate = (req.method() == Method::GET && req.uri().path() != RECORDS).then(|| {
+        req.headers()
+            .get(IF_NONE_MATCH)
+            .and_then(|header| header.to_str().ok())
+            .map(str::to_owned)
+    });
+
+    let response = route(
+        req,
+        schema_registry,
+        storage,
+        topic_changes,
+        recommendations,
+        direct_read_expiry,
+        linger,
+        fetch_hints,
+        chaos,
+    )
+    .await?;
+
+    let Some(revalidate) = revalidate.filter(|_| response.status() == StatusCode::OK) else {
+        return Ok(response);
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let body = body.collect().await.map(|collected| collected.to_bytes())?;
+
+    let etag = etag(&body);
+    debug!(etag, ?revalidate);
+
+    if let Ok(value) = HeaderValue::try_from(etag.as_str()) {
+        _ = parts.headers.insert(ETAG, value);
+    }
+
+    if revalidate.is_some_and(|header| if_none_match(&header, &etag)) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        Ok(Response::from_parts(parts, full(Bytes::new())))
+    } else {
+        Ok(Response::from_parts(parts, full(body)))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn route<S>(
+    req: Request<Incoming>,
+    schema_registry: Option<&Registry>,
+    storage: &S,
+    topic_changes: Option<&TopicChanges>,
+    recommendations: Option<&Recommendations>,
+    direct_read_expiry: Option<Duration>,
+    linger: Option<&Linger<S>>,
+    fetch_hints: bool,
+    chaos: bool,
+) -> Result<Response<Body>>
+where
+    S: Storage,
+{
+    debug!(method = %req.method(), uri = %req.uri());
+
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, LOG_FILTER) => otel::log_filter().map_or_else(
+            || respond(StatusCode::SERVICE_UNAVAILABLE, ""),
+            |filter| respond(StatusCode::OK, filter),
+        ),
+
+        (&Method::PUT, LOG_FILTER) => match req.into_body().collect().await {
+            Ok(body) => String::from_utf8(body.to_bytes().to_vec())
+                .map_err(Into::into)
+                .and_then(|directives| otel::set_log_filter(directives.trim()))
+                .map_or_else(
+                    |err| respond(StatusCode::BAD_REQUEST, err.to_string()),
+                    |()| respond(StatusCode::OK, otel::log_filter().unwrap_or_default()),
+                ),
+
+            Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+        },
+
+        (&Method::GET, SCHEMA_USAGE) => {
+            let Some(schema_registry) = schema_registry else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            schema_registry
+                .usage()
+                .map_err(Into::into)
+                .and_then(|usage| serde_json::to_vec(&usage).map_err(Into::into))
+                .map_or_else(
+                    |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                    |body| respond(StatusCode::OK, body),
+                )
+        }
+
+        (&Method::GET, SCHEMA_QUARANTINE) => {
+            let Some(schema_registry) = schema_registry else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            schema_registry
+                .quarantine()
+                .map_err(Into::into)
+                .and_then(|quarantine| serde_json::to_vec(&quarantine).map_err(Into::into))
+                .map_or_else(
+                    |err: Error| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                    |body| respond(StatusCode::OK, body),
+                )
+        }
+
+        (&Method::GET, OFFSET_TRANSLATION) => offset_translation(storage, req.uri().query()).await,
+
+        (&Method::GET, TOPIC_CONFIG) => topic_config(storage, req.uri().query()).await,
+
+        (&Method::GET, STORAGE_USAGE) => storage_usage(storage, req.uri().query()).await,
+
+        (&Method::GET, TOPIC_CHANGES) => {
+            let Some(changes) = topic_changes else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            watch_topics(changes, req.uri().query()).await
+        }
+
+        (&Method::GET, PARTITION_RECOMMENDATIONS) => {
+            let Some(recommendations) = recommendations else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            recommendations
+                .get(topic(req.uri().query()).as_deref())
+                .and_then(|recommended| serde_json::to_vec(&recommended).map_err(Into::into))
+                .map_or_else(
+                    |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                    |body| respond(StatusCode::OK, body),
+                )
+        }
+
+        (&Method::GET, DIRECT_READS) => {
+            let Some(expires_in) = direct_read_expiry else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            direct_reads(storage, expires_in, req.uri().query()).await
+        }
+
+        (&Method::GET, LINEAGE) => {
+            let Some((topition, offset)) = topition_offset(req.uri().query()) else {
+                return respond(
+                    StatusCode::BAD_REQUEST,
+                    "expecting topic, partition and offset",
+                );
+            };
+
+            let location = Location::new(topition.topic(), topition.partition(), offset);
+
+            lineage::trace(storage, location)
+                .await
+                .and_then(|traced| serde_json::to_vec(&traced).map_err(Into::into))
+                .map_or_else(
+                    |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                    |body| respond(StatusCode::OK, body),
+                )
+        }
+
+        (&Method::GET, TOPIC_READ_ONLY) => topic_read_only(storage, req.uri().query(), None).await,
+
+        (&Method::PUT, TOPIC_READ_ONLY) => {
+            let query = req.uri().query().map(str::to_owned);
+
+            match req.into_body().collect().await {
+                Ok(body) => match String::from_utf8(body.to_bytes().to_vec()) {
+                    Ok(reason) => {
+                        topic_read_only(
+                            storage,
+                            query.as_deref(),
+                            Some(Some(reason.trim().to_owned())),
+                        )
+                        .await
+                    }
+
+                    Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+                },
+
+                Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+            }
+        }
+
+        (&Method::DELETE, TOPIC_READ_ONLY) => {
+            topic_read_only(storage, req.uri().query(), Some(None)).await
+        }
+
+        (&Method::POST, RECORDS) => {
+            let Some(linger) = linger else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            let query = req.uri().query().map(str::to_owned);
+
+            let key = req
+                .headers()
+                .get(TANSU_KEY)
+                .and_then(|key| key.to_str().ok())
+                .map(str::to_owned);
+
+            let content_type = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .map(str::to_owned);
+
+            produce(
+                storage,
+                linger,
+                query.as_deref(),
+                key.as_deref(),
+                content_type.as_deref(),
+                req.into_body(),
+            )
+            .await
+        }
+
+        (&Method::GET, PRODUCER_REPLAYS) => {
+            let producer_id =
+                form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+                    .find(|(name, _)| name == "producer_id")
+                    .map(|(_, value)| value.parse::<i64>());
+
+            let Ok(producer_id) = producer_id.transpose() else {
+                return respond(StatusCode::BAD_REQUEST, "expecting a numeric producer_id");
+            };
+
+            replay::report(producer_id)
+                .and_then(|replays| serde_json::to_vec(&replays).map_err(Into::into))
+                .map_or_else(
+                    |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                    |body| respond(StatusCode::OK, body),
+                )
+        }
+
+        (&Method::GET, PARTITION_UNAVAILABLE) if chaos => chaos::faults()
+            .and_then(|faults| serde_json::to_vec(&faults).map_err(Into::into))
+            .map_or_else(
+                |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                |body| respond(StatusCode::OK, body),
+            ),
+
+        (&Method::PUT, PARTITION_UNAVAILABLE) if chaos => {
+            let Some(topition) = topition(req.uri().query()) else {
+                return respond(StatusCode::BAD_REQUEST, "expecting topic and partition");
+            };
+
+            let body = match req.into_body().collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+            };
+
+            let injection = if body.is_empty() {
+                Injection::default()
+            } else {
+                match serde_json::from_slice::<Injection>(&body) {
+                    Ok(injection) => injection,
+                    Err(err) => return respond(StatusCode::BAD_REQUEST, err.to_string()),
+                }
+            };
+
+            match chaos::inject(topition, injection) {
+                Ok(fault) => serde_json::to_vec(&fault).map_or_else(
+                    |err| respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+                    |body| respond(StatusCode::OK, body),
+                ),
+
+                Err(Error::Message(message)) => respond(StatusCode::BAD_REQUEST, message),
+                Err(err) => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+        }
+
+        (&Method::DELETE, PARTITION_UNAVAILABLE) if chaos => {
+            let Some(topition) = topition(req.uri().query()) else {
+                return respond(StatusCode::BAD_REQUEST, "expecting topic and partition");
+            };
+
+            match chaos::remove(&topition) {
+                Ok(true) => respond(StatusCode::OK, ""),
+                Ok(false) => respond(StatusCode::NOT_FOUND, ""),
+                Err(err) => respond(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+        }
+
+        (&Method::GET, GROUP_EXPORT) => group_export(storage, req.uri().query()).await,
+
+        (_, SUBJECT_VERSIONS | SUBJECT_RESTORE | SUBJECT_ROLLBACK) => {
+            let Some(schema_registry) = schema_registry else {
+                return respond(StatusCode::NOT_FOUND, "");
+            };
+
+            let method = req.method().clone();
+            let path = req.uri().path().to_owned();
+            let query = req.uri().query().map(str::to_owned);
+
+            match req.into_body().collect().await {
+                Ok(body) => {
+                    subject(
+                        schema_registry,
+                        storage,
+                        &method,
+                        &path,
+                        query.as_deref(),
+                        body.to_bytes(),
+                    )
+                    .await
+                }
+
+                Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+            }
+        }
+
+        (&Method::GET, SINK_CONNECTOR) => sink_connector(storage, req.uri().query(), None).await,
+
+        (&Method::PUT, SINK_CONNECTOR) => {
+            let query = req.uri().query().map(str::to_owned);
+
+            match req.into_body().collect().await {
+                Ok(body) => match serde_json::from_slice::<SinkConfig>(&body.to_bytes()) {
+                    Ok(sink) => sink_connector(storage, query.as_deref(), Some(Some(sink))).await,
+                    Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+                },
+
+                Err(err) => respond(StatusCode::BAD_REQUEST, err.to_string()),
+            }
+        }
+
+        (&Method::DELETE, SINK_CONNECTOR) => {
+            sink_connector(storage, req.uri().query(), Some(None)).await
+        }
+
+        (&Method::GET, SINK_CONNECTOR_STATUS) => {
+            sink_connector_status(storage, req.uri().query(), None).await
+        }
+
+        (&Method::PUT, SINK_CONNECTOR_PAUSE) => {
+            sink_connector_status(storage, req.uri().query(), Some(true)).await
+        }
+
+        (&Method::DELETE, SINK_CONNECTOR_PAUSE) => {
+            sink_connector_status(storage, req.uri().query(), Some(false)).await
+        }
+
+        (&Method::GET, RECORDS) => {
+            records(
+                storage,
+                schema_registry,
+                fetch_hints,
+                req.headers()
+                    .get(ACCEPT)
+                    .and_then(|accept| accept.to_str().ok()),
+                req.uri().query(),
+            )
+            .await
+        }
+
+        _ => respond(StatusCode::NOT_FOUND, ""),
+    }
+}
+
+/// A buffered body
+fn full(body: impl Into<Bytes>) -> Body {
+    Full::new(body.into())
+        .map_err(|never| match never {})
+        .boxed_unsync()
+}
+
+fn respond(status: StatusCode, body: impl Into<Bytes>) -> Result<Response<Body>> {
+    Response::builder()
+        .status(status)
+        .body(full(body))
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revalidate() {
+        let etag = etag(b"abc");
+
+        assert_eq!(etag, super::etag(b"abc"));
+        assert_ne!(etag, super::etag(b"abd"));
+
+        assert!(if_none_match(&etag, &etag));
+        assert!(if_none_match(&format!("W/{etag}"), &etag));
+        assert!(if_none_match(&format!("\"xyz\", {etag}"), &etag));
+        assert!(if_none_match("*", &etag));
+        assert!(!if_none_match("\"xyz\"", &etag));
+    }
+
+    #[test]
+    fn bearer() {
+        let token = "s3cr3t";
+
+        assert!(authorized(
+            Some(&HeaderValue::from_static("Bearer s3cr3t")),
+            token
+        ));
+        assert!(!authorized(
+            Some(&HeaderValue::from_static("Bearer s3cr3")),
+            token
+        ));
+        assert!(!authorized(
+            Some(&HeaderValue::from_static("Basic s3cr3t")),
+            token
+        ));
+        assert!(!authorized(None, token));
+    }
+
+    #[cfg(feature = "dynostore")]
+    #[tokio::test]
+    async fn streamed_until_max_bytes() -> Result<()> {
+        use tansu_sans_io::{
+            create_topics_request::CreatableTopic,
+            record::{Record, inflated},
+        };
+        use tansu_storage::StorageContainer;
+
+        let storage = StorageContainer::builder()
+            .cluster_id("tansu")
+            .node_id(111)
+            .advertised_listener(Url::parse("tcp://localhost:9092")?)
+            .storage(Url::parse("memory://")?)
+            .build()
+            .await?;
+
+        _ = storage
+            .create_topic(
+                CreatableTopic::default()
+                    .name("abc".into())
+                    .num_partitions(1)
+                    .replication_factor(0)
+                    .assignments(Some([].into()))
+                    .configs(Some([].into())),
+                false,
+            )
+            .await?;
+
+        let topition = Topition::new("abc", 0);
+
+        let mut lengths = vec![];
+
+        for value in [&b"pqr"[..], b"stu", b"vwx"] {
+            let batch = inflated::Batch::builder()
+                .record(Record::builder().value(Some(Bytes::copy_from_slice(value))))
+                .build()
+                .and_then(deflated::Batch::try_from)?;
+
+            lengths.push(u32::try_from(batch.batch_length).unwrap_or(u32::MAX));
+            _ = storage.produce(None, &topition, batch).await?;
+        }
+
+        let mut fetch = Streamed {
+            storage: storage.clone(),
+            topition: topition.clone(),
+            offset: 1,
+            remaining: lengths[1] + lengths[2],
+        };
+
+        let mut offsets = vec![];
+
+        loop {
+            let batches = fetch.next().await?;
+
+            if batches.is_empty() {
+                break;
+            }
+
+            offsets.extend(batches.iter().map(|batch| batch.base_offset));
+        }
+
+        assert_eq!(vec![1, 2], offsets);
+        assert_eq!(0, fetch.remaining);
+
+        Ok(())
+    }
+
+    #[test]
+    fn loopback_unless_authenticated() -> Result<()> {
+        let localhost = Url::parse("tcp://localhost:9093")?;
+        assert!(bind_addr(&localhost, false)?.ip().is_loopback());
+
+        let loopback = Url::parse("tcp://[::1]:9093")?;
+        assert!(bind_addr(&loopback, false)?.ip().is_loopback());
+
+        let any = Url::parse("tcp://[::]:9093")?;
+        assert!(bind_addr(&any, false).is_err());
+        assert!(!bind_addr(&any, true)?.ip().is_loopback());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fetch audit
+//!
+//! A sample of fetched batches are validated against the current schema of
+//! their topic, off the fetch path, with mismatches reported by the schema
+//! registry quarantine.
+
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    FetchRequest, FetchResponse,
+    record::{deflated::Batch, inflated},
+};
+use tansu_schema::Registry;
+use tansu_storage::{Storage, TopicId};
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+/// Sample fetched batches for audit, using the schema registry audit sample rate
+#[derive(Clone, Debug, Default)]
+pub struct FetchAuditLayer {
+    registry: Option<Registry>,
+}
+
+impl FetchAuditLayer {
+    pub fn new(registry: Option<Registry>) -> Self {
+        Self {
+            registry: registry.filter(|registry| registry.audit_sample_rate() > 0.0),
+        }
+    }
+}
+
+impl<S> Layer<S> for FetchAuditLayer {
+    type Service = FetchAuditService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FetchAuditService {
+            registry: self.registry.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] auditing a sample of the batches returned by the inner fetch service
+#[derive(Clone, Debug)]
+pub struct FetchAuditService<S> {
+    registry: Option<Registry>,
+    inner: S,
+}
+
+/// Resolve the topic name and audit a sampled batch against its current schema
+async fn audit<G>(storage: &G, registry: &Registry, topic: TopicId, partition: i32, batch: Batch)
+where
+    G: Storage,
+{
+    let name = match topic {
+        TopicId::Name(name) => name,
+
+        id @ TopicId::Id(_) => match storage.metadata(Some(&[id])).await.map(|metadata| {
+            metadata
+                .topics()
+                .first()
+                .and_then(|topic| topic.name.clone())
+        }) {
+            Ok(Some(name)) => name,
+
+            otherwise => {
+                debug!(?otherwise);
+                return;
+            }
+        },
+    };
+
+    let Ok(inflated) =
+        inflated::Batch::try_from(batch).inspect_err(|err| warn!(topic = name, partition, ?err))
+    else {
+        return;
+    };
+
+    if let Err(err) = registry.audit(name.as_str(), partition, &inflated).await {
+        warn!(topic = name, partition, ?err);
+    }
+}
+
+impl<S, State> Service<State, FetchRequest> for FetchAuditService<S>
+where
+    S: Service<State, FetchRequest, Response = FetchResponse>,
+    State: Storage,
+{
+    type Response = FetchResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: FetchRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(registry) = self.registry.clone() else {
+            return self.inner.serve(ctx, req).await;
+        };
+
+        let storage = ctx.state().clone();
+        let sample_rate = registry.audit_sample_rate();
+
+        self.inner.serve(ctx, req).await.inspect(|response| {
+            let sampled = response
+                .responses
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|topic| {
+                    topic
+                        .topic
+                        .clone()
+                        .map(TopicId::Name)
+                        .or(topic.topic_id.map(|id| TopicId::Id(Uuid::from_bytes(id))))
+                        .map(|id| (id, topic.partitions.as_deref().unwrap_or_default()))
+                })
+                .flat_map(|(id, partitions)| {
+                    partitions.iter().flat_map(move |partition| {
+                        let id = id.clone();
+
+                        partition
+                            .records
+                            .as_ref()
+                            .map(|frame| &frame.batches[..])
+                            .unwrap_or_default()
+                            .iter()
+                            .map(move |batch| (id.clone(), partition.partition_index, batch))
+                    })
+                })
+                .filter(|_| rand::random_bool(sample_rate))
+                .map(|(id, partition, batch)| (id, partition, batch.clone()))
+                .collect::<Vec<_>>();
+
+            if sampled.is_empty() {
+                return;
+            }
+
+            _ = tokio::spawn(async move {
+                for (id, partition, batch) in sampled {
+                    audit(&storage, &registry, id, partition, batch).await
+                }
+            });
+        })
+    }
+}
@@ -0,0 +1,2818 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ACL authorization
+//!
+//! When enabled, the principal, operation and resource of each request is
+//! authorized by an [`Authorizer`]. The built in [`AclAuthorizer`] uses the ACL
+//! bindings held in storage, maintained with `CreateAcls` and `DeleteAcls`,
+//! while an operator may supply their own implementation. The principal of a
+//! connection is `User:<name>` once authenticated with SASL, otherwise
+//! `User:ANONYMOUS`, from the IP address of its peer. A peer connected over a
+//! Unix domain socket has no IP address, with the host `unix:`, which is only
+//! matched by a binding for that host (or any host).
+//!
+//! As with Kafka, super users are allowed everything, a matching `Deny` takes
+//! precedence over any `Allow`, `Describe` is implied by `Read`, `Write`,
+//! `Delete` or `Alter`, and `DescribeConfigs` is implied by `AlterConfigs`. A
+//! resource without any applicable binding is only accessible when
+//! `allow_everyone_if_no_acl_found` is set.
+//!
+//! Denied topics, groups and resources are answered with the appropriate
+//! authorization failure without reaching the inner service, while any
+//! remaining items of the request are served as usual. Topics listed by
+//! `Metadata` and groups listed by `ListGroups` are limited to those the
+//! principal may describe. When requested, the authorized operations of each
+//! group described by `DescribeGroups` are those allowed to the principal.
+//!
+//! Every API is denied unless it is known to this layer: an API without an
+//! authorization check of its own (e.g., `ApiVersions`, `SaslHandshake` or
+//! `FindCoordinator`) is listed explicitly, while any other API key fails
+//! with a cluster authorization failure rather than reaching the inner
+//! service unchecked.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Debug,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opentelemetry::{KeyValue, metrics::Counter};
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    AclOperation, AclPermissionType, AclResourceType, AddOffsetsToTxnResponse,
+    AddPartitionsToTxnResponse, AlterClientQuotasResponse, AlterUserScramCredentialsResponse,
+    ApiKey, Body, ConfigResource, ConsumerGroupDescribeResponse, ConsumerGroupHeartbeatResponse,
+    CreateAclsRequest, CreateAclsResponse, CreatePartitionsResponse, CreateTopicsResponse,
+    DeleteAclsRequest, DeleteAclsResponse, DeleteGroupsResponse, DeleteRecordsResponse,
+    DeleteTopicsResponse, DescribeAclsResponse, DescribeClientQuotasResponse,
+    DescribeConfigsResponse, DescribeGroupsRequest, DescribeGroupsResponse,
+    DescribeLogDirsResponse, DescribeQuorumResponse, DescribeTopicPartitionsResponse,
+    DescribeTransactionsResponse, DescribeUserScramCredentialsResponse, ElectLeadersResponse,
+    EndTxnResponse, ErrorCode, FetchResponse, Frame, Header, HeartbeatResponse,
+    IncrementalAlterConfigsResponse, InitProducerIdResponse, JoinGroupResponse, LeaveGroupResponse,
+    ListGroupsResponse, ListOffsetsResponse, ListPartitionReassignmentsResponse,
+    ListTransactionsResponse, MetadataResponse, OffsetCommitResponse, OffsetFetchResponse,
+    ProduceResponse, ShareAcknowledgeRequest, ShareAcknowledgeResponse, ShareFetchRequest,
+    ShareFetchResponse, ShareGroupHeartbeatResponse, SyncGroupResponse, TxnOffsetCommitResponse,
+    UpdateFeaturesResponse,
+    add_partitions_to_txn_request::AddPartitionsToTxnTopic,
+    add_partitions_to_txn_response::{
+        AddPartitionsToTxnPartitionResult, AddPartitionsToTxnResult, AddPartitionsToTxnTopicResult,
+    },
+    alter_client_quotas_response,
+    alter_user_scram_credentials_response::AlterUserScramCredentialsResult,
+    consumer_group_describe_response,
+    create_acls_response::AclCreationResult,
+    create_partitions_request::CreatePartitionsTopic,
+    create_partitions_response::CreatePartitionsTopicResult,
+    create_topics_request::CreatableTopic,
+    create_topics_response::CreatableTopicResult,
+    delete_acls_response::DeleteAclsFilterResult,
+    delete_groups_response::DeletableGroupResult,
+    delete_records_request::DeleteRecordsTopic,
+    delete_records_response::{DeleteRecordsPartitionResult, DeleteRecordsTopicResult},
+    delete_topics_response::DeletableTopicResult,
+    describe_configs_response::DescribeConfigsResult,
+    describe_groups_response::DescribedGroup,
+    describe_topic_partitions_response::DescribeTopicPartitionsResponseTopic,
+    describe_transactions_response::TransactionState,
+    elect_leaders_request::TopicPartitions,
+    elect_leaders_response::{PartitionResult, ReplicaElectionResult},
+    fetch_request::FetchTopic,
+    fetch_response::{
+        EpochEndOffset, FetchableTopicResponse, LeaderIdAndEpoch, PartitionData, SnapshotId,
+    },
+    incremental_alter_configs_response::AlterConfigsResourceResponse,
+    leave_group_response::MemberResponse,
+    list_offsets_request::ListOffsetsTopic,
+    list_offsets_response::{ListOffsetsPartitionResponse, ListOffsetsTopicResponse},
+    metadata_request::MetadataRequestTopic,
+    metadata_response::MetadataResponseTopic,
+    offset_commit_request::OffsetCommitRequestTopic,
+    offset_commit_response::{OffsetCommitResponsePartition, OffsetCommitResponseTopic},
+    offset_fetch_request::OffsetFetchRequestGroup,
+    offset_fetch_response::OffsetFetchResponseGroup,
+    produce_request::TopicProduceData,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+    txn_offset_commit_request::TxnOffsetCommitRequestTopic,
+    txn_offset_commit_response::{TxnOffsetCommitResponsePartition, TxnOffsetCommitResponseTopic},
+    update_features_response::UpdatableFeatureResult,
+};
+use tansu_service::Peer;
+use tansu_storage::{AclBinding, Storage, TopicId};
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+use crate::{Error, METER, Result, broker::sasl::SaslSession};
+
+/// The resource name of the cluster
+pub const CLUSTER: &str = "kafka-cluster";
+
+/// The principal of a connection that has not authenticated
+pub const ANONYMOUS: &str = "User:ANONYMOUS";
+
+/// The principal matching every user
+const WILDCARD_PRINCIPAL: &str = "User:*";
+
+/// The host of a peer connected over a Unix domain socket
+const UNIX_HOST: &str = "unix:";
+
+/// How long ACL bindings are cached before being reloaded from storage
+const ACLS_TTL: Duration = Duration::from_secs(5);
+
+/// The operations that apply to a group
+const GROUP_OPERATIONS: [AclOperation; 3] = [
+    AclOperation::Read,
+    AclOperation::Describe,
+    AclOperation::Delete,
+];
+
+static AUTHORIZATION_DENIED: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_authorization_denied")
+        .with_description("requests with a resource denied by an ACL")
+        .build()
+});
+
+/// The principal and host of a connection
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Identity {
+    principal: String,
+    host: String,
+}
+
+impl Identity {
+    pub fn new(principal: impl Into<String>, host: impl Into<String>) -> Self {
+        Self {
+            principal: principal.into(),
+            host: host.into(),
+        }
+    }
+
+    /// The principal, e.g., `User:alice`
+    pub fn principal(&self) -> &str {
+        &self.principal
+    }
+
+    /// The IP address of the peer, or `unix:` for a Unix domain socket
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The identity of a connection, from its SASL session and peer address
+    pub(crate) fn of<State>(ctx: &Context<State>) -> Self {
+        Self {
+            principal: ctx
+                .get::<SaslSession>()
+                .and_then(SaslSession::principal)
+                .map_or_else(|| ANONYMOUS.to_owned(), |name| format!("User:{name}")),
+            host: ctx.get::<Peer>().map_or_else(
+                || AclBinding::WILDCARD.to_owned(),
+                |peer| match peer {
+                    Peer::Inet(addr) => addr.ip().to_canonical().to_string(),
+                    Peer::Unix(_) => UNIX_HOST.to_owned(),
+                },
+            ),
+        }
+    }
+}
+
+/// Whether a granted operation implies the requested operation
+fn implies(granted: AclOperation, requested: AclOperation) -> bool {
+    granted == requested
+        || granted == AclOperation::All
+        || match requested {
+            AclOperation::Describe => matches!(
+                granted,
+                AclOperation::Read
+                    | AclOperation::Write
+                    | AclOperation::Delete
+                    | AclOperation::Alter
+            ),
+            AclOperation::DescribeConfigs => granted == AclOperation::AlterConfigs,
+            _otherwise => false,
+        }
+}
+
+/// Authorization configuration
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Authorization {
+    super_users: BTreeSet<String>,
+    allow_everyone_if_no_acl_found: bool,
+}
+
+impl Authorization {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Principals allowed everything, with a name without a type being a `User`
+    pub fn super_users<I, U>(self, super_users: I) -> Self
+    where
+        I: IntoIterator<Item = U>,
+        U: Into<String>,
+    {
+        Self {
+            super_users: super_users
+                .into_iter()
+                .map(Into::into)
+                .map(|principal| {
+                    if principal.contains(':') {
+                        principal
+                    } else {
+                        format!("User:{principal}")
+                    }
+                })
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Allow access to a resource without any applicable ACL binding
+    pub fn allow_everyone_if_no_acl_found(self, allow_everyone_if_no_acl_found: bool) -> Self {
+        Self {
+            allow_everyone_if_no_acl_found,
+            ..self
+        }
+    }
+
+    /// Whether the ACL bindings allow this identity an operation on a resource
+    pub fn authorize(
+        &self,
+        acls: &[AclBinding],
+        identity: &Identity,
+        resource_type: AclResourceType,
+        resource_name: &str,
+        operation: AclOperation,
+    ) -> bool {
+        if self.super_users.contains(&identity.principal) {
+            return true;
+        }
+
+        let applicable = acls
+            .iter()
+            .filter(|binding| binding.applies_to(resource_type, resource_name))
+            .collect::<Vec<_>>();
+
+        if applicable.is_empty() {
+            return self.allow_everyone_if_no_acl_found;
+        }
+
+        let matching = applicable
+            .into_iter()
+            .filter(|binding| {
+                (binding.principal == identity.principal || binding.principal == WILDCARD_PRINCIPAL)
+                    && (binding.host == identity.host || binding.host == AclBinding::WILDCARD)
+            })
+            .collect::<Vec<_>>();
+
+        let denied = matching.iter().any(|binding| {
+            binding.permission_type == AclPermissionType::Deny
+                && (binding.operation == operation || binding.operation == AclOperation::All)
+        });
+
+        !denied
+            && matching.iter().any(|binding| {
+                binding.permission_type == AclPermissionType::Allow
+                    && implies(binding.operation, operation)
+            })
+    }
+}
+
+/// Decide whether an identity may perform an operation on a resource
+///
+/// The built in [`AclAuthorizer`] uses the ACL bindings held in storage, while
+/// an operator may plug in their own implementation (e.g., LDAP, OPA or RBAC)
+/// with [`Builder::authorizer`](crate::broker::Builder::authorizer).
+#[async_trait]
+pub trait Authorizer: Debug + Send + Sync {
+    /// Whether the identity making a request with this API key is allowed
+    /// the operation on a resource
+    async fn authorize(
+        &self,
+        identity: &Identity,
+        api_key: i16,
+        operation: AclOperation,
+        resource_type: AclResourceType,
+        resource_name: &str,
+    ) -> Result<bool>;
+
+    /// The ACL bindings in storage have been changed by a `CreateAcls` or
+    /// `DeleteAcls` request
+    fn acls_changed(&self) {}
+}
+
+type Cache = Arc<Mutex<Option<(Instant, Arc<[AclBinding]>)>>>;
+
+/// An [`Authorizer`] using the ACL bindings held in storage, cached by every
+/// connection using this authorizer
+#[derive(Clone, Debug)]
+pub struct AclAuthorizer<G> {
+    storage: G,
+    authorization: Authorization,
+    cache: Cache,
+}
+
+impl<G> AclAuthorizer<G> {
+    pub fn new(storage: G, authorization: Authorization) -> Self {
+        Self {
+            storage,
+            authorization,
+            cache: Cache::default(),
+        }
+    }
+}
+
+impl<G> AclAuthorizer<G>
+where
+    G: Storage,
+{
+    fn cached(&self, now: Instant) -> Option<Arc<[AclBinding]>> {
+        self.cache.lock().ok().and_then(|guard| {
+            guard
+                .as_ref()
+                .filter(|(loaded, _)| now.saturating_duration_since(*loaded) < ACLS_TTL)
+                .map(|(_, acls)| acls.clone())
+        })
+    }
+
+    async fn acls(&self) -> Result<Arc<[AclBinding]>> {
+        let now = Instant::now();
+
+        if let Some(acls) = self.cached(now) {
+            return Ok(acls);
+        }
+
+        let acls = Arc::<[AclBinding]>::from(self.storage.acls().await?);
+
+        if let Ok(mut guard) = self.cache.lock() {
+            *guard = Some((now, acls.clone()));
+        }
+
+        Ok(acls)
+    }
+}
+
+#[async_trait]
+impl<G> Authorizer for AclAuthorizer<G>
+where
+    G: Storage,
+{
+    async fn authorize(
+        &self,
+        identity: &Identity,
+        _api_key: i16,
+        operation: AclOperation,
+        resource_type: AclResourceType,
+        resource_name: &str,
+    ) -> Result<bool> {
+        self.acls().await.map(|acls| {
+            self.authorization
+                .authorize(&acls, identity, resource_type, resource_name, operation)
+        })
+    }
+
+    fn acls_changed(&self) {
+        if let Ok(mut guard) = self.cache.lock() {
+            *guard = None;
+        }
+    }
+}
+
+/// An authorization decision for the identity of a request
+#[derive(Clone, Copy, Debug)]
+struct Decision<'a> {
+    authorizer: &'a dyn Authorizer,
+    identity: &'a Identity,
+    api_key: i16,
+}
+
+impl Decision<'_> {
+    /// Whether the operation is allowed, with an authorizer error denying it
+    async fn allows(
+        &self,
+        resource_type: AclResourceType,
+        resource_name: &str,
+        operation: AclOperation,
+    ) -> bool {
+        self.authorizer
+            .authorize(
+                self.identity,
+                self.api_key,
+                operation,
+                resource_type,
+                resource_name,
+            )
+            .await
+            .inspect_err(|err| warn!(api_key = self.api_key, ?self.identity, ?err))
+            .unwrap_or(false)
+    }
+
+    async fn cluster(&self, operation: AclOperation) -> bool {
+        self.allows(AclResourceType::Cluster, CLUSTER, operation)
+            .await
+    }
+
+    async fn group(&self, group_id: &str, operation: AclOperation) -> bool {
+        self.allows(AclResourceType::Group, group_id, operation)
+            .await
+    }
+
+    async fn topic(&self, name: &str, operation: AclOperation) -> bool {
+        self.allows(AclResourceType::Topic, name, operation).await
+    }
+
+    /// The operations allowed on a group, as a bit field with a bit set for
+    /// each allowed operation (`1 << operation`)
+    async fn group_operations(&self, group_id: &str) -> i32 {
+        let mut operations = 0;
+
+        for operation in GROUP_OPERATIONS {
+            if self.group(group_id, operation).await {
+                operations |= 1 << i8::from(operation);
+            }
+        }
+
+        operations
+    }
+
+    async fn transactional_id(&self, transactional_id: &str, operation: AclOperation) -> bool {
+        self.allows(
+            AclResourceType::TransactionalId,
+            transactional_id,
+            operation,
+        )
+        .await
+    }
+
+    /// The authorization failure of a transaction committing the offsets of
+    /// a group, which needs `Write` on the transactional id and `Read` on the
+    /// group
+    async fn transaction(&self, transactional_id: &str, group_id: &str) -> Option<ErrorCode> {
+        if !self
+            .transactional_id(transactional_id, AclOperation::Write)
+            .await
+        {
+            Some(ErrorCode::TransactionalIdAuthorizationFailed)
+        } else if !self.group(group_id, AclOperation::Read).await {
+            Some(ErrorCode::GroupAuthorizationFailed)
+        } else {
+            None
+        }
+    }
+
+    /// The results of adding partitions to a transaction when denied, which
+    /// needs `Write` on the transactional id and on each topic
+    async fn add_partitions(
+        &self,
+        transactional_id: &str,
+        topics: &[AddPartitionsToTxnTopic],
+    ) -> Option<Vec<AddPartitionsToTxnTopicResult>> {
+        let transaction = self
+            .transactional_id(transactional_id, AclOperation::Write)
+            .await;
+
+        let mut denied = BTreeSet::new();
+
+        for topic in topics {
+            if !transaction || !self.topic(&topic.name, AclOperation::Write).await {
+                _ = denied.insert(topic.name.as_str());
+            }
+        }
+
+        if denied.is_empty() {
+            return None;
+        }
+
+        Some(
+            topics
+                .iter()
+                .map(|topic| {
+                    let error_code = if !transaction {
+                        ErrorCode::TransactionalIdAuthorizationFailed
+                    } else if denied.contains(topic.name.as_str()) {
+                        ErrorCode::TopicAuthorizationFailed
+                    } else {
+                        ErrorCode::OperationNotAttempted
+                    };
+
+                    AddPartitionsToTxnTopicResult::default()
+                        .name(topic.name.clone())
+                        .results_by_partition(Some(
+                            topic
+                                .partitions
+                                .as_deref()
+                                .unwrap_or_default()
+                                .iter()
+                                .map(|partition_index| {
+                                    AddPartitionsToTxnPartitionResult::default()
+                                        .partition_index(*partition_index)
+                                        .partition_error_code(error_code.into())
+                                })
+                                .collect(),
+                        ))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The outcome of authorizing a request
+#[derive(Clone, Debug)]
+enum Authorized {
+    /// Serve the remaining request, merging the denied items into its response
+    Forward { body: Body, denied: Option<Body> },
+
+    /// Respond without reaching the inner service
+    Deny(Body),
+
+    /// Fail an API that is unknown to this layer without reaching the inner
+    /// service
+    Reject(ErrorCode),
+}
+
+impl Authorized {
+    fn forward<D>(body: impl Into<Body>, denied: Option<D>) -> Self
+    where
+        D: Into<Body>,
+    {
+        Self::Forward {
+            body: body.into(),
+            denied: denied.map(Into::into),
+        }
+    }
+
+    fn deny(body: impl Into<Body>) -> Self {
+        Self::Deny(body.into())
+    }
+}
+
+/// The authorization failure for a resource type
+fn failure(resource_type: AclResourceType) -> ErrorCode {
+    match resource_type {
+        AclResourceType::Topic => ErrorCode::TopicAuthorizationFailed,
+        AclResourceType::Group => ErrorCode::GroupAuthorizationFailed,
+        _otherwise => ErrorCode::ClusterAuthorizationFailed,
+    }
+}
+
+/// The ACL resource of a config resource
+fn config_resource(resource_type: i8, resource_name: &str) -> (AclResourceType, &str) {
+    match ConfigResource::from(resource_type) {
+        ConfigResource::Topic => (AclResourceType::Topic, resource_name),
+        ConfigResource::Group => (AclResourceType::Group, resource_name),
+        _otherwise => (AclResourceType::Cluster, CLUSTER),
+    }
+}
+
+fn produce_denied(topic: TopicProduceData) -> TopicProduceResponse {
+    TopicProduceResponse::default()
+        .name(topic.name)
+        .partition_responses(Some(
+            topic
+                .partition_data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    PartitionProduceResponse::default()
+                        .index(partition.index)
+                        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+                        .base_offset(-1)
+                        .log_append_time_ms(Some(-1))
+                        .log_start_offset(Some(0))
+                        .record_errors(Some([].into()))
+                        .error_message(None)
+                        .current_leader(None)
+                })
+                .collect(),
+        ))
+}
+
+fn fetch_denied(topic: FetchTopic) -> FetchableTopicResponse {
+    FetchableTopicResponse::default()
+        .topic(topic.topic)
+        .topic_id(topic.topic_id)
+        .partitions(Some(
+            topic
+                .partitions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    PartitionData::default()
+                        .partition_index(partition.partition)
+                        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+                        .high_watermark(-1)
+                        .last_stable_offset(Some(-1))
+                        .log_start_offset(Some(-1))
+                        .diverging_epoch(Some(EpochEndOffset::default().epoch(-1).end_offset(-1)))
+                        .current_leader(Some(
+                            LeaderIdAndEpoch::default().leader_id(-1).leader_epoch(-1),
+                        ))
+                        .snapshot_id(Some(SnapshotId::default().end_offset(-1).epoch(-1)))
+                        .aborted_transactions(Some([].into()))
+                        .preferred_read_replica(Some(-1))
+                        .records(None)
+                })
+                .collect(),
+        ))
+}
+
+fn create_topic_denied(topic: CreatableTopic) -> CreatableTopicResult {
+    CreatableTopicResult::default()
+        .name(topic.name)
+        .topic_id(Some([0; 16]))
+        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+        .error_message(Some(ErrorCode::TopicAuthorizationFailed.to_string()))
+        .topic_config_error_code(Some(ErrorCode::TopicAuthorizationFailed.into()))
+        .num_partitions(Some(-1))
+        .replication_factor(Some(-1))
+        .configs(Some([].into()))
+}
+
+fn create_partitions_denied(topic: CreatePartitionsTopic) -> CreatePartitionsTopicResult {
+    CreatePartitionsTopicResult::default()
+        .name(topic.name)
+        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+        .error_message(Some(ErrorCode::TopicAuthorizationFailed.to_string()))
+}
+
+fn elect_leaders_denied(topic: TopicPartitions) -> ReplicaElectionResult {
+    ReplicaElectionResult::default()
+        .partition_result(Some(
+            topic
+                .partitions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition_id| {
+                    PartitionResult::default()
+                        .partition_id(partition_id)
+                        .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                        .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                })
+                .collect(),
+        ))
+        .topic(topic.topic)
+}
+
+fn delete_topic_denied(name: Option<String>, topic_id: Option<[u8; 16]>) -> DeletableTopicResult {
+    DeletableTopicResult::default()
+        .name(name)
+        .topic_id(topic_id)
+        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+        .error_message(Some(ErrorCode::TopicAuthorizationFailed.to_string()))
+}
+
+fn offset_commit_denied(topic: OffsetCommitRequestTopic) -> OffsetCommitResponseTopic {
+    OffsetCommitResponseTopic::default()
+        .name(topic.name)
+        .partitions(Some(
+            topic
+                .partitions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    OffsetCommitResponsePartition::default()
+                        .partition_index(partition.partition_index)
+                        .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                })
+                .collect(),
+        ))
+}
+
+fn offset_fetch_denied(group: OffsetFetchRequestGroup) -> OffsetFetchResponseGroup {
+    OffsetFetchResponseGroup::default()
+        .group_id(group.group_id)
+        .topics(Some([].into()))
+        .error_code(ErrorCode::GroupAuthorizationFailed.into())
+}
+
+fn delete_records_denied(topic: DeleteRecordsTopic) -> DeleteRecordsTopicResult {
+    DeleteRecordsTopicResult::default()
+        .name(topic.name)
+        .partitions(Some(
+            topic
+                .partitions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    DeleteRecordsPartitionResult::default()
+                        .partition_index(partition.partition_index)
+                        .low_watermark(-1)
+                        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+                })
+                .collect(),
+        ))
+}
+
+fn txn_offset_commit_denied(
+    topic: TxnOffsetCommitRequestTopic,
+    error_code: ErrorCode,
+) -> TxnOffsetCommitResponseTopic {
+    TxnOffsetCommitResponseTopic::default()
+        .name(topic.name)
+        .partitions(Some(
+            topic
+                .partitions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    TxnOffsetCommitResponsePartition::default()
+                        .partition_index(partition.partition_index)
+                        .error_code(error_code.into())
+                })
+                .collect(),
+        ))
+}
+
+fn metadata_denied(topic: MetadataRequestTopic) -> MetadataResponseTopic {
+    MetadataResponseTopic::default()
+        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+        .name(topic.name)
+        .topic_id(topic.topic_id.or(Some([0; 16])))
+        .is_internal(Some(false))
+        .partitions(Some([].into()))
+        .topic_authorized_operations(Some(i32::MIN))
+}
+
+fn list_offsets_denied(topic: ListOffsetsTopic) -> ListOffsetsTopicResponse {
+    ListOffsetsTopicResponse::default()
+        .name(topic.name)
+        .partitions(Some(
+            topic
+                .partitions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    ListOffsetsPartitionResponse::default()
+                        .partition_index(partition.partition_index)
+                        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+                        .old_style_offsets(None)
+                        .timestamp(Some(-1))
+                        .offset(Some(-1))
+                        .leader_epoch(Some(-1))
+                })
+                .collect(),
+        ))
+}
+
+fn describe_group_denied(group_id: String) -> DescribedGroup {
+    DescribedGroup::default()
+        .error_code(ErrorCode::GroupAuthorizationFailed.into())
+        .group_id(group_id)
+        .group_state("".into())
+        .protocol_type("".into())
+        .protocol_data("".into())
+        .members(Some([].into()))
+        .authorized_operations(Some(i32::MIN))
+}
+
+fn consumer_group_describe_denied(
+    group_id: String,
+) -> consumer_group_describe_response::DescribedGroup {
+    consumer_group_describe_response::DescribedGroup::default()
+        .error_code(ErrorCode::GroupAuthorizationFailed.into())
+        .error_message(Some(ErrorCode::GroupAuthorizationFailed.to_string()))
+        .group_id(group_id)
+        .group_state("".into())
+        .group_epoch(-1)
+        .assignment_epoch(-1)
+        .assignor_name("".into())
+        .members(Some([].into()))
+        .authorized_operations(i32::MIN)
+}
+
+fn describe_topic_partitions_denied(name: String) -> DescribeTopicPartitionsResponseTopic {
+    DescribeTopicPartitionsResponseTopic::default()
+        .error_code(ErrorCode::TopicAuthorizationFailed.into())
+        .name(Some(name))
+        .topic_id([0; 16])
+        .is_internal(false)
+        .partitions(Some([].into()))
+        .topic_authorized_operations(i32::MIN)
+}
+
+/// Append the denied items to those of the response
+fn extend<T>(items: &mut Option<Vec<T>>, denied: Option<Vec<T>>) {
+    if let Some(denied) = denied.filter(|denied| !denied.is_empty()) {
+        items.get_or_insert_default().extend(denied)
+    }
+}
+
+/// Merge the denied items of a request into the response of the inner service
+fn describe_transactions_denied(transactional_id: String) -> TransactionState {
+    TransactionState::default()
+        .error_code(ErrorCode::TransactionalIdAuthorizationFailed.into())
+        .transactional_id(transactional_id)
+        .transaction_state("".into())
+        .transaction_timeout_ms(0)
+        .transaction_start_time_ms(-1)
+        .producer_id(-1)
+        .producer_epoch(-1)
+        .topics(Some([].into()))
+}
+
+fn merge(response: &mut Body, denied: Body) {
+    match (response, denied) {
+        (Body::ProduceResponse(response), Body::ProduceResponse(denied)) => {
+            extend(&mut response.responses, denied.responses)
+        }
+
+        (Body::FetchResponse(response), Body::FetchResponse(denied)) => {
+            extend(&mut response.responses, denied.responses)
+        }
+
+        (Body::CreateTopicsResponse(response), Body::CreateTopicsResponse(denied)) => {
+            extend(&mut response.topics, denied.topics)
+        }
+
+        (Body::CreatePartitionsResponse(response), Body::CreatePartitionsResponse(denied)) => {
+            extend(&mut response.results, denied.results)
+        }
+
+        (Body::DeleteTopicsResponse(response), Body::DeleteTopicsResponse(denied)) => {
+            extend(&mut response.responses, denied.responses)
+        }
+
+        (Body::DeleteGroupsResponse(response), Body::DeleteGroupsResponse(denied)) => {
+            extend(&mut response.results, denied.results)
+        }
+
+        (
+            Body::IncrementalAlterConfigsResponse(response),
+            Body::IncrementalAlterConfigsResponse(denied),
+        ) => extend(&mut response.responses, denied.responses),
+
+        (Body::OffsetFetchResponse(response), Body::OffsetFetchResponse(denied)) => {
+            extend(&mut response.groups, denied.groups)
+        }
+
+        (
+            Body::DescribeTransactionsResponse(response),
+            Body::DescribeTransactionsResponse(denied),
+        ) => extend(&mut response.transaction_states, denied.transaction_states),
+
+        (Body::DeleteRecordsResponse(response), Body::DeleteRecordsResponse(denied)) => {
+            extend(&mut response.topics, denied.topics)
+        }
+
+        (Body::AddPartitionsToTxnResponse(response), Body::AddPartitionsToTxnResponse(denied)) => {
+            extend(
+                &mut response.results_by_transaction,
+                denied.results_by_transaction,
+            )
+        }
+
+        (Body::MetadataResponse(response), Body::MetadataResponse(denied)) => {
+            extend(&mut response.topics, denied.topics)
+        }
+
+        (Body::ListOffsetsResponse(response), Body::ListOffsetsResponse(denied)) => {
+            extend(&mut response.topics, denied.topics)
+        }
+
+        (Body::DescribeGroupsResponse(response), Body::DescribeGroupsResponse(denied)) => {
+            extend(&mut response.groups, denied.groups)
+        }
+
+        (
+            Body::ConsumerGroupDescribeResponse(response),
+            Body::ConsumerGroupDescribeResponse(denied),
+        ) => extend(&mut response.groups, denied.groups),
+
+        (Body::DescribeConfigsResponse(response), Body::DescribeConfigsResponse(denied)) => {
+            extend(&mut response.results, denied.results)
+        }
+
+        (
+            Body::DescribeTopicPartitionsResponse(response),
+            Body::DescribeTopicPartitionsResponse(denied),
+        ) => extend(&mut response.topics, denied.topics),
+
+        (response, denied) => debug!(?response, ?denied),
+    }
+}
+
+/// Fill the authorized operations of each described group
+async fn authorized_operations(decision: Decision<'_>, response: &mut Body) {
+    if let Body::DescribeGroupsResponse(DescribeGroupsResponse {
+        groups: Some(groups),
+        ..
+    }) = response
+    {
+        for group in groups
+            .iter_mut()
+            .filter(|group| group.error_code == i16::from(ErrorCode::None))
+        {
+            group.authorized_operations = Some(decision.group_operations(&group.group_id).await);
+        }
+    }
+}
+
+/// Limit the topics listed by `Metadata` and the groups listed by
+/// `ListGroups` to those the principal may describe
+async fn describable(decision: Decision<'_>, response: &mut Body) {
+    match response {
+        Body::MetadataResponse(MetadataResponse {
+            topics: Some(topics),
+            ..
+        }) => {
+            let mut retained = Vec::with_capacity(topics.len());
+
+            for topic in topics.drain(..) {
+                match topic.name.as_deref() {
+                    Some(name) if !decision.topic(name, AclOperation::Describe).await => {
+                        debug!(name, "not describable")
+                    }
+
+                    _otherwise => retained.push(topic),
+                }
+            }
+
+            *topics = retained;
+        }
+
+        Body::ListGroupsResponse(ListGroupsResponse {
+            groups: Some(groups),
+            ..
+        }) if !decision.cluster(AclOperation::Describe).await => {
+            let mut retained = Vec::with_capacity(groups.len());
+
+            for group in groups.drain(..) {
+                if decision
+                    .group(&group.group_id, AclOperation::Describe)
+                    .await
+                {
+                    retained.push(group);
+                } else {
+                    debug!(group.group_id, "not describable")
+                }
+            }
+
+            *groups = retained;
+        }
+
+        _otherwise => (),
+    }
+}
+
+/// A [`Layer`] authorizing requests with an [`Authorizer`]
+#[derive(Clone, Debug)]
+pub struct AuthorizerLayer<G> {
+    storage: G,
+    authorizer: Option<Arc<dyn Authorizer>>,
+}
+
+impl<G> AuthorizerLayer<G> {
+    pub fn new(storage: G, authorizer: Option<Arc<dyn Authorizer>>) -> Self {
+        Self {
+            storage,
+            authorizer,
+        }
+    }
+}
+
+impl<G, S> Layer<S> for AuthorizerLayer<G>
+where
+    G: Clone,
+{
+    type Service = AuthorizerService<G, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthorizerService {
+            storage: self.storage.clone(),
+            authorizer: self.authorizer.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] passing authorized requests to the inner service
+#[derive(Clone, Debug)]
+pub struct AuthorizerService<G, S> {
+    storage: G,
+    authorizer: Option<Arc<dyn Authorizer>>,
+    inner: S,
+}
+
+impl<G, S> AuthorizerService<G, S>
+where
+    G: Storage,
+{
+    /// The name of a topic, resolving a topic id from the metadata in storage
+    async fn topic_name(&self, name: Option<&str>, topic_id: Option<[u8; 16]>) -> Option<String> {
+        if let Some(name) = name {
+            return Some(name.to_owned());
+        }
+
+        let topic_id = TopicId::Id(Uuid::from_bytes(topic_id?));
+
+        self.storage
+            .metadata(Some(&[topic_id]))
+            .await
+            .inspect_err(|err| debug!(?err))
+            .ok()
+            .and_then(|metadata| {
+                metadata
+                    .topics()
+                    .first()
+                    .and_then(|topic| topic.name.clone())
+            })
+    }
+
+    async fn authorize(&self, decision: Decision<'_>, body: Body) -> Authorized {
+        let allowed = match &body {
+            Body::JoinGroupRequest(join) => {
+                decision.group(&join.group_id, AclOperation::Read).await
+            }
+            Body::SyncGroupRequest(sync) => {
+                decision.group(&sync.group_id, AclOperation::Read).await
+            }
+            Body::HeartbeatRequest(heartbeat) => {
+                decision
+                    .group(&heartbeat.group_id, AclOperation::Read)
+                    .await
+            }
+            Body::ConsumerGroupHeartbeatRequest(heartbeat) => {
+                decision
+                    .group(&heartbeat.group_id, AclOperation::Read)
+                    .await
+            }
+            Body::ShareGroupHeartbeatRequest(heartbeat) => {
+                decision
+                    .group(&heartbeat.group_id, AclOperation::Read)
+                    .await
+            }
+            Body::ShareFetchRequest(ShareFetchRequest {
+                group_id: Some(group_id),
+                ..
+            })
+            | Body::ShareAcknowledgeRequest(ShareAcknowledgeRequest {
+                group_id: Some(group_id),
+                ..
+            }) => decision.group(group_id, AclOperation::Read).await,
+            Body::LeaveGroupRequest(leave) => {
+                decision.group(&leave.group_id, AclOperation::Read).await
+            }
+            Body::OffsetCommitRequest(commit) => {
+                decision.group(&commit.group_id, AclOperation::Read).await
+            }
+
+            Body::OffsetFetchRequest(offset_fetch) => match offset_fetch.group_id.as_deref() {
+                Some(group_id) => decision.group(group_id, AclOperation::Describe).await,
+                None => true,
+            },
+
+            Body::CreateAclsRequest(_)
+            | Body::DeleteAclsRequest(_)
+            | Body::AlterUserScramCredentialsRequest(_)
+            | Body::ElectLeadersRequest(_) => decision.cluster(AclOperation::Alter).await,
+
+            Body::DescribeAclsRequest(_)
+            | Body::DescribeLogDirsRequest(_)
+            | Body::DescribeUserScramCredentialsRequest(_)
+            | Body::ListTransactionsRequest(_) => decision.cluster(AclOperation::Describe).await,
+
+            Body::AlterClientQuotasRequest(_) => decision.cluster(AclOperation::AlterConfigs).await,
+
+            Body::UpdateFeaturesRequest(_) => decision.cluster(AclOperation::Alter).await,
+
+            Body::DescribeClientQuotasRequest(_) => {
+                decision.cluster(AclOperation::DescribeConfigs).await
+            }
+
+            Body::DescribeQuorumRequest(_) | Body::ListPartitionReassignmentsRequest(_) => {
+                decision.cluster(AclOperation::Describe).await
+            }
+
+            Body::InitProducerIdRequest(init) => match init.transactional_id.as_deref() {
+                Some(transactional_id) => {
+                    decision
+                        .transactional_id(transactional_id, AclOperation::Write)
+                        .await
+                }
+
+                // an idempotent producer is authorized by each produced topic
+                None => true,
+            },
+
+            Body::EndTxnRequest(end) => {
+                decision
+                    .transactional_id(&end.transactional_id, AclOperation::Write)
+                    .await
+            }
+
+            // APIs without an authorization check of their own
+            Body::ApiVersionsRequest(_)
+            | Body::SaslHandshakeRequest(_)
+            | Body::SaslAuthenticateRequest(_)
+            | Body::FindCoordinatorRequest(_)
+            | Body::DescribeClusterRequest(_)
+            | Body::GetTelemetrySubscriptionsRequest(_)
+            | Body::PushTelemetryRequest(_) => true,
+
+            // APIs authorized item by item below, with anything else denied
+            _otherwise => false,
+        };
+
+        match body {
+            Body::JoinGroupRequest(join) if !allowed => Authorized::deny(
+                JoinGroupResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .generation_id(-1)
+                    .protocol_type(None)
+                    .protocol_name(Some("".into()))
+                    .leader("".into())
+                    .skip_assignment(Some(false))
+                    .member_id(join.member_id)
+                    .members(Some([].into())),
+            ),
+
+            Body::SyncGroupRequest(_) if !allowed => Authorized::deny(
+                SyncGroupResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .protocol_type(None)
+                    .protocol_name(None)
+                    .assignment(Bytes::new()),
+            ),
+
+            Body::HeartbeatRequest(_) if !allowed => Authorized::deny(
+                HeartbeatResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into()),
+            ),
+
+            Body::ConsumerGroupHeartbeatRequest(heartbeat) if !allowed => Authorized::deny(
+                ConsumerGroupHeartbeatResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .error_message(None)
+                    .member_id(Some(heartbeat.member_id))
+                    .member_epoch(heartbeat.member_epoch)
+                    .heartbeat_interval_ms(0)
+                    .assignment(None),
+            ),
+
+            Body::ShareGroupHeartbeatRequest(heartbeat) if !allowed => Authorized::deny(
+                ShareGroupHeartbeatResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .error_message(None)
+                    .member_id(Some(heartbeat.member_id))
+                    .member_epoch(heartbeat.member_epoch)
+                    .heartbeat_interval_ms(0)
+                    .assignment(None),
+            ),
+
+            Body::ShareFetchRequest(_) if !allowed => Authorized::deny(
+                ShareFetchResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .error_message(None)
+                    .responses(Some([].into()))
+                    .node_endpoints(Some([].into())),
+            ),
+
+            Body::ShareAcknowledgeRequest(_) if !allowed => Authorized::deny(
+                ShareAcknowledgeResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .error_message(None)
+                    .responses(Some([].into()))
+                    .node_endpoints(Some([].into())),
+            ),
+
+            Body::LeaveGroupRequest(leave) if !allowed => Authorized::deny(
+                LeaveGroupResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                    .members(Some(
+                        leave
+                            .members
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|member| {
+                                MemberResponse::default()
+                                    .member_id(member.member_id)
+                                    .group_instance_id(member.group_instance_id)
+                                    .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                            })
+                            .collect(),
+                    )),
+            ),
+
+            Body::OffsetCommitRequest(commit) if !allowed => Authorized::deny(
+                OffsetCommitResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .topics(Some(
+                        commit
+                            .topics
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(offset_commit_denied)
+                            .collect(),
+                    )),
+            ),
+
+            Body::OffsetFetchRequest(_) if !allowed => Authorized::deny(
+                OffsetFetchResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .topics(Some([].into()))
+                    .error_code(Some(ErrorCode::GroupAuthorizationFailed.into()))
+                    .groups(Some([].into())),
+            ),
+
+            Body::OffsetFetchRequest(mut offset_fetch) => {
+                let mut denied = vec![];
+
+                if let Some(groups) = offset_fetch.groups.take() {
+                    let mut allowed = vec![];
+
+                    for offsets in groups {
+                        if decision
+                            .group(&offsets.group_id, AclOperation::Describe)
+                            .await
+                        {
+                            allowed.push(offsets);
+                        } else {
+                            denied.push(offset_fetch_denied(offsets));
+                        }
+                    }
+
+                    offset_fetch.groups = Some(allowed);
+                }
+
+                Authorized::forward(
+                    offset_fetch,
+                    (!denied.is_empty())
+                        .then(|| OffsetFetchResponse::default().groups(Some(denied))),
+                )
+            }
+
+            Body::ProduceRequest(mut produce) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in produce.topic_data.take().unwrap_or_default() {
+                    if decision.topic(&topic.name, AclOperation::Write).await {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(produce_denied(topic));
+                    }
+                }
+
+                produce.topic_data = Some(allowed);
+
+                Authorized::forward(
+                    produce,
+                    (!denied.is_empty())
+                        .then(|| ProduceResponse::default().responses(Some(denied))),
+                )
+            }
+
+            Body::FetchRequest(mut fetch) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in fetch.topics.take().unwrap_or_default() {
+                    let name = self
+                        .topic_name(topic.topic.as_deref(), topic.topic_id)
+                        .await;
+
+                    let allows = match name.as_deref() {
+                        Some(name) => decision.topic(name, AclOperation::Read).await,
+                        None => true,
+                    };
+
+                    if allows {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(fetch_denied(topic));
+                    }
+                }
+
+                fetch.topics = Some(allowed);
+
+                Authorized::forward(
+                    fetch,
+                    (!denied.is_empty()).then(|| FetchResponse::default().responses(Some(denied))),
+                )
+            }
+
+            Body::CreateTopicsRequest(mut create) => {
+                let cluster = decision.cluster(AclOperation::Create).await;
+
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in create.topics.take().unwrap_or_default() {
+                    if cluster || decision.topic(&topic.name, AclOperation::Create).await {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(create_topic_denied(topic));
+                    }
+                }
+
+                create.topics = Some(allowed);
+
+                Authorized::forward(
+                    create,
+                    (!denied.is_empty())
+                        .then(|| CreateTopicsResponse::default().topics(Some(denied))),
+                )
+            }
+
+            Body::CreatePartitionsRequest(mut create) => {
+                let cluster = decision.cluster(AclOperation::Alter).await;
+
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in create.topics.take().unwrap_or_default() {
+                    if cluster || decision.topic(&topic.name, AclOperation::Alter).await {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(create_partitions_denied(topic));
+                    }
+                }
+
+                create.topics = Some(allowed);
+
+                Authorized::forward(
+                    create,
+                    (!denied.is_empty())
+                        .then(|| CreatePartitionsResponse::default().results(Some(denied))),
+                )
+            }
+
+            Body::DeleteTopicsRequest(mut delete) => {
+                let mut topics = vec![];
+                let mut denied = vec![];
+
+                for topic in delete.topics.take().unwrap_or_default() {
+                    let name = self
+                        .topic_name(topic.name.as_deref(), Some(topic.topic_id))
+                        .await;
+
+                    let allows = match name.as_deref() {
+                        Some(name) => decision.topic(name, AclOperation::Delete).await,
+                        None => true,
+                    };
+
+                    if allows {
+                        topics.push(topic);
+                    } else {
+                        denied.push(delete_topic_denied(topic.name, Some(topic.topic_id)));
+                    }
+                }
+
+                let mut topic_names = vec![];
+
+                for name in delete.topic_names.take().unwrap_or_default() {
+                    if decision.topic(&name, AclOperation::Delete).await {
+                        topic_names.push(name);
+                    } else {
+                        denied.push(delete_topic_denied(Some(name), None));
+                    }
+                }
+
+                delete.topics = Some(topics);
+                delete.topic_names = Some(topic_names);
+
+                Authorized::forward(
+                    delete,
+                    (!denied.is_empty())
+                        .then(|| DeleteTopicsResponse::default().responses(Some(denied))),
+                )
+            }
+
+            Body::DescribeTransactionsRequest(mut describe) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for transactional_id in describe.transactional_ids.take().unwrap_or_default() {
+                    if decision
+                        .transactional_id(&transactional_id, AclOperation::Describe)
+                        .await
+                    {
+                        allowed.push(transactional_id);
+                    } else {
+                        denied.push(transactional_id);
+                    }
+                }
+
+                describe.transactional_ids = Some(allowed);
+
+                Authorized::forward(
+                    describe,
+                    (!denied.is_empty()).then(|| {
+                        DescribeTransactionsResponse::default().transaction_states(Some(
+                            denied
+                                .into_iter()
+                                .map(describe_transactions_denied)
+                                .collect(),
+                        ))
+                    }),
+                )
+            }
+
+            Body::DeleteGroupsRequest(mut delete) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for group_id in delete.groups_names.take().unwrap_or_default() {
+                    if decision.group(&group_id, AclOperation::Delete).await {
+                        allowed.push(group_id);
+                    } else {
+                        denied.push(group_id);
+                    }
+                }
+
+                delete.groups_names = Some(allowed);
+
+                Authorized::forward(
+                    delete,
+                    (!denied.is_empty()).then(|| {
+                        DeleteGroupsResponse::default().results(Some(
+                            denied
+                                .into_iter()
+                                .map(|group_id| {
+                                    DeletableGroupResult::default()
+                                        .group_id(group_id)
+                                        .error_code(ErrorCode::GroupAuthorizationFailed.into())
+                                })
+                                .collect(),
+                        ))
+                    }),
+                )
+            }
+
+            Body::IncrementalAlterConfigsRequest(mut alter) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for resource in alter.resources.take().unwrap_or_default() {
+                    let (resource_type, resource_name) =
+                        config_resource(resource.resource_type, &resource.resource_name);
+
+                    if decision
+                        .allows(resource_type, resource_name, AclOperation::AlterConfigs)
+                        .await
+                    {
+                        allowed.push(resource);
+                    } else {
+                        denied.push(resource);
+                    }
+                }
+
+                alter.resources = Some(allowed);
+
+                Authorized::forward(
+                    alter,
+                    (!denied.is_empty()).then(|| {
+                        IncrementalAlterConfigsResponse::default().responses(Some(
+                            denied
+                                .into_iter()
+                                .map(|resource| {
+                                    let error_code = failure(
+                                        config_resource(
+                                            resource.resource_type,
+                                            &resource.resource_name,
+                                        )
+                                        .0,
+                                    );
+
+                                    AlterConfigsResourceResponse::default()
+                                        .error_code(error_code.into())
+                                        .error_message(Some(error_code.to_string()))
+                                        .resource_type(resource.resource_type)
+                                        .resource_name(resource.resource_name)
+                                })
+                                .collect(),
+                        ))
+                    }),
+                )
+            }
+
+            Body::CreateAclsRequest(create) if !allowed => Authorized::deny(
+                CreateAclsResponse::default()
+                    .throttle_time_ms(0)
+                    .results(Some(
+                        create
+                            .creations
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|_| {
+                                AclCreationResult::default()
+                                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                                    .error_message(Some(
+                                        ErrorCode::ClusterAuthorizationFailed.to_string(),
+                                    ))
+                            })
+                            .collect(),
+                    )),
+            ),
+
+            Body::DeleteAclsRequest(delete) if !allowed => Authorized::deny(
+                DeleteAclsResponse::default()
+                    .throttle_time_ms(0)
+                    .filter_results(Some(
+                        delete
+                            .filters
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|_| {
+                                DeleteAclsFilterResult::default()
+                                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                                    .error_message(Some(
+                                        ErrorCode::ClusterAuthorizationFailed.to_string(),
+                                    ))
+                                    .matching_acls(Some([].into()))
+                            })
+                            .collect(),
+                    )),
+            ),
+
+            Body::DescribeAclsRequest(_) if !allowed => Authorized::deny(
+                DescribeAclsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                    .resources(Some([].into())),
+            ),
+
+            Body::ListTransactionsRequest(_) if !allowed => Authorized::deny(
+                ListTransactionsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .unknown_state_filters(Some([].into()))
+                    .transaction_states(Some([].into())),
+            ),
+
+            Body::DescribeLogDirsRequest(_) if !allowed => Authorized::deny(
+                DescribeLogDirsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(Some(ErrorCode::ClusterAuthorizationFailed.into()))
+                    .results(Some([].into())),
+            ),
+
+            Body::AlterUserScramCredentialsRequest(alter) if !allowed => Authorized::deny(
+                AlterUserScramCredentialsResponse::default()
+                    .throttle_time_ms(0)
+                    .results(Some(
+                        alter
+                            .deletions
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|deletion| deletion.name)
+                            .chain(
+                                alter
+                                    .upsertions
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|upsertion| upsertion.name),
+                            )
+                            .collect::<BTreeSet<_>>()
+                            .into_iter()
+                            .map(|user| {
+                                AlterUserScramCredentialsResult::default()
+                                    .user(user)
+                                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                                    .error_message(Some(
+                                        ErrorCode::ClusterAuthorizationFailed.to_string(),
+                                    ))
+                            })
+                            .collect(),
+                    )),
+            ),
+
+            Body::ElectLeadersRequest(elect) if !allowed => Authorized::deny(
+                ElectLeadersResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(Some(ErrorCode::ClusterAuthorizationFailed.into()))
+                    .replica_election_results(Some(
+                        elect
+                            .topic_partitions
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(elect_leaders_denied)
+                            .collect(),
+                    )),
+            ),
+
+            Body::DescribeUserScramCredentialsRequest(_) if !allowed => Authorized::deny(
+                DescribeUserScramCredentialsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                    .results(Some([].into())),
+            ),
+
+            Body::AlterClientQuotasRequest(alter) if !allowed => Authorized::deny(
+                AlterClientQuotasResponse::default()
+                    .throttle_time_ms(0)
+                    .entries(Some(
+                        alter
+                            .entries
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|entry| {
+                                alter_client_quotas_response::EntryData::default()
+                                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                                    .error_message(Some(
+                                        ErrorCode::ClusterAuthorizationFailed.to_string(),
+                                    ))
+                                    .entity(entry.entity.map(|entity| {
+                                        entity
+                                            .into_iter()
+                                            .map(|data| {
+                                                alter_client_quotas_response::EntityData::default()
+                                                    .entity_type(data.entity_type)
+                                                    .entity_name(data.entity_name)
+                                            })
+                                            .collect()
+                                    }))
+                            })
+                            .collect(),
+                    )),
+            ),
+
+            Body::DescribeClientQuotasRequest(_) if !allowed => Authorized::deny(
+                DescribeClientQuotasResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                    .entries(None),
+            ),
+
+            Body::UpdateFeaturesRequest(update) if !allowed => Authorized::deny(
+                UpdateFeaturesResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                    .results(Some(
+                        update
+                            .feature_updates
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|update| {
+                                UpdatableFeatureResult::default()
+                                    .feature(update.feature)
+                                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                                    .error_message(None)
+                            })
+                            .collect(),
+                    )),
+            ),
+
+            Body::DescribeQuorumRequest(_) if !allowed => Authorized::deny(
+                DescribeQuorumResponse::default()
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                    .topics(Some([].into()))
+                    .nodes(Some([].into())),
+            ),
+
+            Body::ListPartitionReassignmentsRequest(_) if !allowed => Authorized::deny(
+                ListPartitionReassignmentsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::ClusterAuthorizationFailed.into())
+                    .error_message(Some(ErrorCode::ClusterAuthorizationFailed.to_string()))
+                    .topics(Some([].into())),
+            ),
+
+            Body::InitProducerIdRequest(_) if !allowed => Authorized::deny(
+                InitProducerIdResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::TransactionalIdAuthorizationFailed.into())
+                    .producer_id(-1)
+                    .producer_epoch(-1),
+            ),
+
+            Body::EndTxnRequest(_) if !allowed => Authorized::deny(
+                EndTxnResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::TransactionalIdAuthorizationFailed.into()),
+            ),
+
+            Body::AddOffsetsToTxnRequest(add) => match decision
+                .transaction(&add.transactional_id, &add.group_id)
+                .await
+            {
+                Some(error_code) => Authorized::deny(
+                    AddOffsetsToTxnResponse::default()
+                        .throttle_time_ms(0)
+                        .error_code(error_code.into()),
+                ),
+
+                None => Authorized::Forward {
+                    body: add.into(),
+                    denied: None,
+                },
+            },
+
+            Body::TxnOffsetCommitRequest(commit) => match decision
+                .transaction(&commit.transactional_id, &commit.group_id)
+                .await
+            {
+                Some(error_code) => Authorized::deny(
+                    TxnOffsetCommitResponse::default()
+                        .throttle_time_ms(0)
+                        .topics(Some(
+                            commit
+                                .topics
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|topic| txn_offset_commit_denied(topic, error_code))
+                                .collect(),
+                        )),
+                ),
+
+                None => Authorized::Forward {
+                    body: commit.into(),
+                    denied: None,
+                },
+            },
+
+            Body::AddPartitionsToTxnRequest(mut add) => {
+                if let Some(transactional_id) = add.v_3_and_below_transactional_id.as_deref() {
+                    if let Some(results) = decision
+                        .add_partitions(
+                            transactional_id,
+                            add.v_3_and_below_topics.as_deref().unwrap_or_default(),
+                        )
+                        .await
+                    {
+                        return Authorized::deny(
+                            AddPartitionsToTxnResponse::default()
+                                .throttle_time_ms(0)
+                                .error_code(None)
+                                .results_by_transaction(None)
+                                .results_by_topic_v_3_and_below(Some(results)),
+                        );
+                    }
+                }
+
+                let mut denied = vec![];
+
+                if let Some(transactions) = add.transactions.take() {
+                    let mut allowed = vec![];
+
+                    for transaction in transactions {
+                        match decision
+                            .add_partitions(
+                                &transaction.transactional_id,
+                                transaction.topics.as_deref().unwrap_or_default(),
+                            )
+                            .await
+                        {
+                            Some(results) => denied.push(
+                                AddPartitionsToTxnResult::default()
+                                    .transactional_id(transaction.transactional_id)
+                                    .topic_results(Some(results)),
+                            ),
+
+                            None => allowed.push(transaction),
+                        }
+                    }
+
+                    add.transactions = Some(allowed);
+                }
+
+                Authorized::forward(
+                    add,
+                    (!denied.is_empty()).then(|| {
+                        AddPartitionsToTxnResponse::default().results_by_transaction(Some(denied))
+                    }),
+                )
+            }
+
+            Body::DeleteRecordsRequest(mut delete) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in delete.topics.take().unwrap_or_default() {
+                    if decision.topic(&topic.name, AclOperation::Delete).await {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(delete_records_denied(topic));
+                    }
+                }
+
+                delete.topics = Some(allowed);
+
+                Authorized::forward(
+                    delete,
+                    (!denied.is_empty())
+                        .then(|| DeleteRecordsResponse::default().topics(Some(denied))),
+                )
+            }
+
+            Body::MetadataRequest(mut metadata) => {
+                let mut denied = vec![];
+
+                if let Some(topics) = metadata.topics.take() {
+                    let mut allowed = vec![];
+
+                    for topic in topics {
+                        let name = self.topic_name(topic.name.as_deref(), topic.topic_id).await;
+
+                        let allows = match name.as_deref() {
+                            Some(name) => decision.topic(name, AclOperation::Describe).await,
+                            None => true,
+                        };
+
+                        if allows {
+                            allowed.push(topic);
+                        } else {
+                            denied.push(metadata_denied(topic));
+                        }
+                    }
+
+                    metadata.topics = Some(allowed);
+                }
+
+                Authorized::forward(
+                    metadata,
+                    (!denied.is_empty()).then(|| MetadataResponse::default().topics(Some(denied))),
+                )
+            }
+
+            Body::ListOffsetsRequest(mut list) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in list.topics.take().unwrap_or_default() {
+                    if decision.topic(&topic.name, AclOperation::Describe).await {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(list_offsets_denied(topic));
+                    }
+                }
+
+                list.topics = Some(allowed);
+
+                Authorized::forward(
+                    list,
+                    (!denied.is_empty())
+                        .then(|| ListOffsetsResponse::default().topics(Some(denied))),
+                )
+            }
+
+            Body::DescribeTopicPartitionsRequest(mut describe) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for topic in describe.topics.take().unwrap_or_default() {
+                    if decision.topic(&topic.name, AclOperation::Describe).await {
+                        allowed.push(topic);
+                    } else {
+                        denied.push(describe_topic_partitions_denied(topic.name));
+                    }
+                }
+
+                describe.topics = Some(allowed);
+
+                Authorized::forward(
+                    describe,
+                    (!denied.is_empty())
+                        .then(|| DescribeTopicPartitionsResponse::default().topics(Some(denied))),
+                )
+            }
+
+            Body::DescribeGroupsRequest(mut describe) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for group_id in describe.groups.take().unwrap_or_default() {
+                    if decision.group(&group_id, AclOperation::Describe).await {
+                        allowed.push(group_id);
+                    } else {
+                        denied.push(describe_group_denied(group_id));
+                    }
+                }
+
+                describe.groups = Some(allowed);
+
+                Authorized::forward(
+                    describe,
+                    (!denied.is_empty())
+                        .then(|| DescribeGroupsResponse::default().groups(Some(denied))),
+                )
+            }
+
+            Body::ConsumerGroupDescribeRequest(mut describe) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for group_id in describe.group_ids.take().unwrap_or_default() {
+                    if decision.group(&group_id, AclOperation::Describe).await {
+                        allowed.push(group_id);
+                    } else {
+                        denied.push(consumer_group_describe_denied(group_id));
+                    }
+                }
+
+                describe.group_ids = Some(allowed);
+
+                Authorized::forward(
+                    describe,
+                    (!denied.is_empty())
+                        .then(|| ConsumerGroupDescribeResponse::default().groups(Some(denied))),
+                )
+            }
+
+            // the listed groups are limited to those that may be described
+            // once served, see [`describable`]
+            body @ Body::ListGroupsRequest(_) => Authorized::Forward { body, denied: None },
+
+            Body::DescribeConfigsRequest(mut describe) => {
+                let mut allowed = vec![];
+                let mut denied = vec![];
+
+                for resource in describe.resources.take().unwrap_or_default() {
+                    let (resource_type, resource_name) =
+                        config_resource(resource.resource_type, &resource.resource_name);
+
+                    if decision
+                        .allows(resource_type, resource_name, AclOperation::DescribeConfigs)
+                        .await
+                    {
+                        allowed.push(resource);
+                    } else {
+                        let error_code = failure(resource_type);
+
+                        denied.push(
+                            DescribeConfigsResult::default()
+                                .error_code(error_code.into())
+                                .error_message(Some(error_code.to_string()))
+                                .resource_type(resource.resource_type)
+                                .resource_name(resource.resource_name)
+                                .configs(Some([].into())),
+                        );
+                    }
+                }
+
+                describe.resources = Some(allowed);
+
+                Authorized::forward(
+                    describe,
+                    (!denied.is_empty())
+                        .then(|| DescribeConfigsResponse::default().results(Some(denied))),
+                )
+            }
+
+            body if allowed => Authorized::Forward { body, denied: None },
+
+            body => {
+                debug!(?body, "unknown to the authorizer");
+                Authorized::Reject(ErrorCode::ClusterAuthorizationFailed)
+            }
+        }
+    }
+}
+
+impl<G, S, State> Service<State, Frame> for AuthorizerService<G, S>
+where
+    G: Storage,
+    S: Service<State, Frame, Response = Frame, Error = Error>,
+    State: Send + Sync + 'static,
+{
+    type Response = Frame;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(&self, ctx: Context<State>, req: Frame) -> Result<Self::Response, Self::Error> {
+        let Some(authorizer) = self.authorizer.as_deref() else {
+            return self.inner.serve(ctx, req).await;
+        };
+
+        let api_key = req.api_key()?;
+        let correlation_id = req.correlation_id()?;
+        let identity = Identity::of(&ctx);
+
+        let decision = Decision {
+            authorizer,
+            identity: &identity,
+            api_key,
+        };
+
+        let Frame { size, header, body } = req;
+
+        let include_authorized_operations = matches!(
+            body,
+            Body::DescribeGroupsRequest(DescribeGroupsRequest {
+                include_authorized_operations: Some(true),
+                ..
+            })
+        );
+
+        match self.authorize(decision, body).await {
+            Authorized::Deny(body) => {
+                debug!(api_key, ?identity, "denied");
+                AUTHORIZATION_DENIED.add(1, &[KeyValue::new("api_key", i64::from(api_key))]);
+
+                Ok(Frame {
+                    size: 0,
+                    header: Header::Response { correlation_id },
+                    body,
+                })
+            }
+
+            Authorized::Reject(error_code) => {
+                warn!(api_key, ?identity, ?error_code, "rejected");
+                AUTHORIZATION_DENIED.add(1, &[KeyValue::new("api_key", i64::from(api_key))]);
+
+                Err(Error::Api(error_code))
+            }
+
+            Authorized::Forward { body, denied } => {
+                if denied.is_some() {
+                    debug!(api_key, ?identity, ?denied);
+                    AUTHORIZATION_DENIED.add(1, &[KeyValue::new("api_key", i64::from(api_key))]);
+                }
+
+                let acls_changed =
+                    [CreateAclsRequest::KEY, DeleteAclsRequest::KEY].contains(&api_key);
+
+                let mut response = self.inner.serve(ctx, Frame { size, header, body }).await?;
+
+                describable(decision, &mut response.body).await;
+
+                if let Some(denied) = denied {
+                    merge(&mut response.body, denied);
+                }
+
+                if include_authorized_operations {
+                    authorized_operations(decision, &mut response.body).await;
+                }
+
+                if acls_changed {
+                    authorizer.acls_changed();
+                }
+
+                Ok(response)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tansu_sans_io::{
+        AclPatternType, AddOffsetsToTxnRequest, AddPartitionsToTxnRequest, DeleteRecordsRequest,
+        DescribeConfigsRequest, EndTxnRequest, InitProducerIdRequest, ListOffsetsRequest,
+        MetadataRequest, ProduceRequest, TxnOffsetCommitRequest, WriteTxnMarkersRequest,
+        delete_records_request::DeleteRecordsPartition,
+        describe_configs_request::DescribeConfigsResource, list_groups_response::ListedGroup,
+        list_offsets_request::ListOffsetsPartition,
+        txn_offset_commit_request::TxnOffsetCommitRequestPartition,
+    };
+    use tansu_storage::StorageContainer;
+    use url::Url;
+
+    use super::*;
+
+    fn binding(
+        resource_name: &str,
+        pattern_type: AclPatternType,
+        principal: &str,
+        operation: AclOperation,
+        permission_type: AclPermissionType,
+    ) -> AclBinding {
+        AclBinding {
+            resource_type: AclResourceType::Topic,
+            resource_name: resource_name.into(),
+            pattern_type,
+            principal: principal.into(),
+            host: AclBinding::WILDCARD.into(),
+            operation,
+            permission_type,
+        }
+    }
+
+    #[test]
+    fn no_acl_found() {
+        let alice = Identity::new("User:alice", "127.0.0.1");
+
+        assert!(!Authorization::new().authorize(
+            &[],
+            &alice,
+            AclResourceType::Topic,
+            "orders",
+            AclOperation::Read
+        ));
+
+        assert!(
+            Authorization::new()
+                .allow_everyone_if_no_acl_found(true)
+                .authorize(
+                    &[],
+                    &alice,
+                    AclResourceType::Topic,
+                    "orders",
+                    AclOperation::Read
+                )
+        );
+    }
+
+    #[test]
+    fn unix_peer_host() {
+        let mut ctx = Context::default();
+        _ = ctx.insert(Peer::Unix(None));
+        assert_eq!(UNIX_HOST, Identity::of(&ctx).host());
+
+        let mut ctx = Context::default();
+        _ = ctx.insert(Peer::from(SocketAddr::from(([127, 0, 0, 1], 9092))));
+        assert_eq!("127.0.0.1", Identity::of(&ctx).host());
+    }
+
+    #[test]
+    fn super_user() {
+        let acls = [binding(
+            "orders",
+            AclPatternType::Literal,
+            "User:*",
+            AclOperation::All,
+            AclPermissionType::Deny,
+        )];
+
+        assert!(Authorization::new().super_users(["admin"]).authorize(
+            &acls,
+            &Identity::new("User:admin", "127.0.0.1"),
+            AclResourceType::Topic,
+            "orders",
+            AclOperation::Write
+        ));
+    }
+
+    #[test]
+    fn read_implies_describe() {
+        let acls = [binding(
+            "orders",
+            AclPatternType::Literal,
+            "User:alice",
+            AclOperation::Read,
+            AclPermissionType::Allow,
+        )];
+
+        let authorization = Authorization::new();
+        let alice = Identity::new("User:alice", "127.0.0.1");
+        let bob = Identity::new("User:bob", "127.0.0.1");
+
+        for operation in [AclOperation::Read, AclOperation::Describe] {
+            assert!(authorization.authorize(
+                &acls,
+                &alice,
+                AclResourceType::Topic,
+                "orders",
+                operation
+            ));
+        }
+
+        assert!(!authorization.authorize(
+            &acls,
+            &alice,
+            AclResourceType::Topic,
+            "orders",
+            AclOperation::Write
+        ));
+
+        assert!(!authorization.authorize(
+            &acls,
+            &bob,
+            AclResourceType::Topic,
+            "orders",
+            AclOperation::Read
+        ));
+    }
+
+    #[test]
+    fn deny_takes_precedence() {
+        let acls = [
+            binding(
+                "order",
+                AclPatternType::Prefixed,
+                "User:*",
+                AclOperation::All,
+                AclPermissionType::Allow,
+            ),
+            binding(
+                "orders",
+                AclPatternType::Literal,
+                "User:bob",
+                AclOperation::Write,
+                AclPermissionType::Deny,
+            ),
+        ];
+
+        let authorization = Authorization::new();
+        let bob = Identity::new("User:bob", "10.0.0.1");
+
+        assert!(authorization.authorize(
+            &acls,
+            &bob,
+            AclResourceType::Topic,
+            "orders",
+            AclOperation::Read
+        ));
+
+        assert!(!authorization.authorize(
+            &acls,
+            &bob,
+            AclResourceType::Topic,
+            "orders",
+            AclOperation::Write
+        ));
+
+        assert!(authorization.authorize(
+            &acls,
+            &bob,
+            AclResourceType::Topic,
+            "order-history",
+            AclOperation::Write
+        ));
+    }
+
+    #[derive(Debug)]
+    struct Orders;
+
+    #[async_trait]
+    impl Authorizer for Orders {
+        async fn authorize(
+            &self,
+            identity: &Identity,
+            _api_key: i16,
+            operation: AclOperation,
+            resource_type: AclResourceType,
+            resource_name: &str,
+        ) -> Result<bool> {
+            Ok(identity.principal() == "User:alice"
+                && resource_type == AclResourceType::Topic
+                && resource_name == "orders"
+                && operation == AclOperation::Write)
+        }
+    }
+
+    #[tokio::test]
+    async fn pluggable() {
+        let alice = Identity::new("User:alice", "127.0.0.1");
+
+        let decision = Decision {
+            authorizer: &Orders,
+            identity: &alice,
+            api_key: ProduceRequest::KEY,
+        };
+
+        assert!(decision.topic("orders", AclOperation::Write).await);
+        assert!(!decision.topic("payments", AclOperation::Write).await);
+        assert!(!decision.cluster(AclOperation::Alter).await);
+    }
+
+    #[derive(Debug)]
+    struct Readers;
+
+    #[async_trait]
+    impl Authorizer for Readers {
+        async fn authorize(
+            &self,
+            _identity: &Identity,
+            _api_key: i16,
+            operation: AclOperation,
+            resource_type: AclResourceType,
+            resource_name: &str,
+        ) -> Result<bool> {
+            Ok(resource_type == AclResourceType::Group
+                && resource_name == "readers"
+                && [AclOperation::Read, AclOperation::Describe].contains(&operation))
+        }
+    }
+
+    #[tokio::test]
+    async fn describe_groups_authorized_operations() {
+        let alice = Identity::new("User:alice", "127.0.0.1");
+
+        let decision = Decision {
+            authorizer: &Readers,
+            identity: &alice,
+            api_key: DescribeGroupsRequest::KEY,
+        };
+
+        let described = |group_id: &str, error_code: ErrorCode| {
+            DescribedGroup::default()
+                .error_code(error_code.into())
+                .group_id(group_id.into())
+                .authorized_operations(Some(-1))
+        };
+
+        let mut response = Body::from(DescribeGroupsResponse::default().groups(Some(vec![
+            described("readers", ErrorCode::None),
+            described("writers", ErrorCode::None),
+            described("readers", ErrorCode::CoordinatorNotAvailable),
+        ])));
+
+        authorized_operations(decision, &mut response).await;
+
+        let Body::DescribeGroupsResponse(DescribeGroupsResponse {
+            groups: Some(groups),
+            ..
+        }) = response
+        else {
+            panic!("{response:?}");
+        };
+
+        assert_eq!(Some((1 << 3) | (1 << 8)), groups[0].authorized_operations);
+        assert_eq!(Some(0), groups[1].authorized_operations);
+        assert_eq!(Some(-1), groups[2].authorized_operations);
+    }
+
+    /// Allow only these operations on a type of resource
+    #[derive(Debug)]
+    struct Allow(&'static [(AclResourceType, AclOperation)]);
+
+    #[async_trait]
+    impl Authorizer for Allow {
+        async fn authorize(
+            &self,
+            _identity: &Identity,
+            _api_key: i16,
+            operation: AclOperation,
+            resource_type: AclResourceType,
+            _resource_name: &str,
+        ) -> Result<bool> {
+            Ok(self.0.contains(&(resource_type, operation)))
+        }
+    }
+
+    const NOTHING: Allow = Allow(&[]);
+
+    async fn authorized<R>(authorizer: &Allow, request: R) -> Result<Authorized>
+    where
+        R: ApiKey + Into<Body>,
+    {
+        let storage = StorageContainer::builder()
+            .cluster_id("tansu")
+            .node_id(111)
+            .advertised_listener(Url::parse("tcp://localhost:9092")?)
+            .storage(Url::parse("null://tansu/")?)
+            .build()
+            .await?;
+
+        let service = AuthorizerService {
+            storage,
+            authorizer: None,
+            inner: (),
+        };
+
+        let alice = Identity::new("User:alice", "127.0.0.1");
+
+        let decision = Decision {
+            authorizer,
+            identity: &alice,
+            api_key: R::KEY,
+        };
+
+        Ok(service.authorize(decision, request.into()).await)
+    }
+
+    #[tokio::test]
+    async fn unknown_api_rejected() -> Result<()> {
+        let authorized = authorized(
+            &Allow(&[(AclResourceType::Cluster, AclOperation::All)]),
+            WriteTxnMarkersRequest::default().markers(Some([].into())),
+        )
+        .await?;
+
+        assert!(matches!(
+            authorized,
+            Authorized::Reject(ErrorCode::ClusterAuthorizationFailed)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_records_denied() -> Result<()> {
+        let authorized = authorized(
+            &Allow(&[(AclResourceType::Topic, AclOperation::Describe)]),
+            DeleteRecordsRequest::default()
+                .topics(Some(vec![
+                    DeleteRecordsTopic::default()
+                        .name("orders".into())
+                        .partitions(Some(vec![
+                            DeleteRecordsPartition::default()
+                                .partition_index(0)
+                                .offset(12),
+                        ])),
+                ]))
+                .timeout_ms(5_000),
+        )
+        .await?;
+
+        let Authorized::Forward {
+            body: Body::DeleteRecordsRequest(request),
+            denied: Some(Body::DeleteRecordsResponse(response)),
+        } = authorized
+        else {
+            panic!("{authorized:?}");
+        };
+
+        assert_eq!(Some(vec![]), request.topics);
+
+        let topics = response.topics.unwrap_or_default();
+        assert_eq!("orders", topics[0].name);
+        assert_eq!(
+            i16::from(ErrorCode::TopicAuthorizationFailed),
+            topics[0].partitions.as_deref().unwrap_or_default()[0].error_code
+        );
+
+        Ok(())
+    }
+
+    fn txn_offset_commit() -> TxnOffsetCommitRequest {
+        TxnOffsetCommitRequest::default()
+            .transactional_id("txn".into())
+            .group_id("readers".into())
+            .producer_id(6)
+            .producer_epoch(0)
+            .topics(Some(vec![
+                TxnOffsetCommitRequestTopic::default()
+                    .name("orders".into())
+                    .partitions(Some(vec![
+                        TxnOffsetCommitRequestPartition::default()
+                            .partition_index(0)
+                            .committed_offset(12),
+                    ])),
+            ]))
+    }
+
+    fn txn_offset_commit_error(authorized: Authorized) -> Option<i16> {
+        let Authorized::Deny(Body::TxnOffsetCommitResponse(response)) = authorized else {
+            return None;
+        };
+
+        response
+            .topics
+            .unwrap_or_default()
+            .first()
+            .and_then(|topic| topic.partitions.as_deref().unwrap_or_default().first())
+            .map(|partition| partition.error_code)
+    }
+
+    #[tokio::test]
+    async fn txn_offset_commit_denied() -> Result<()> {
+        assert_eq!(
+            Some(i16::from(ErrorCode::TransactionalIdAuthorizationFailed)),
+            txn_offset_commit_error(authorized(&NOTHING, txn_offset_commit()).await?)
+        );
+
+        assert_eq!(
+            Some(i16::from(ErrorCode::GroupAuthorizationFailed)),
+            txn_offset_commit_error(
+                authorized(
+                    &Allow(&[(AclResourceType::TransactionalId, AclOperation::Write)]),
+                    txn_offset_commit()
+                )
+                .await?
+            )
+        );
+
+        assert!(matches!(
+            authorized(
+                &Allow(&[
+                    (AclResourceType::TransactionalId, AclOperation::Write),
+                    (AclResourceType::Group, AclOperation::Read)
+                ]),
+                txn_offset_commit()
+            )
+            .await?,
+            Authorized::Forward { denied: None, .. }
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn init_producer_id_denied() -> Result<()> {
+        let init = |transactional_id: Option<&str>| {
+            InitProducerIdRequest::default()
+                .transactional_id(transactional_id.map(ToOwned::to_owned))
+                .transaction_timeout_ms(60_000)
+        };
+
+        let transactional = authorized(&NOTHING, init(Some("txn"))).await?;
+
+        let Authorized::Deny(Body::InitProducerIdResponse(response)) = transactional else {
+            panic!("{transactional:?}");
+        };
+
+        assert_eq!(
+            i16::from(ErrorCode::TransactionalIdAuthorizationFailed),
+            response.error_code
+        );
+
+        // an idempotent producer is authorized by each produced topic
+        assert!(matches!(
+            authorized(&NOTHING, init(None)).await?,
+            Authorized::Forward { denied: None, .. }
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn add_partitions_to_txn_denied() -> Result<()> {
+        let add = || {
+            AddPartitionsToTxnRequest::default()
+                .v_3_and_below_transactional_id(Some("txn".into()))
+                .v_3_and_below_producer_id(Some(6))
+                .v_3_and_below_producer_epoch(Some(0))
+                .v_3_and_below_topics(Some(vec![
+                    AddPartitionsToTxnTopic::default()
+                        .name("orders".into())
+                        .partitions(Some(vec![0, 1])),
+                ]))
+        };
+
+        let error_codes = |authorized: Authorized| {
+            let Authorized::Deny(Body::AddPartitionsToTxnResponse(response)) = authorized else {
+                panic!("{authorized:?}");
+            };
+
+            response
+                .results_by_topic_v_3_and_below
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|topic| topic.results_by_partition.unwrap_or_default())
+                .map(|partition| partition.partition_error_code)
+                .collect::<BTreeSet<_>>()
+        };
+
+        assert_eq!(
+            BTreeSet::from([i16::from(ErrorCode::TransactionalIdAuthorizationFailed)]),
+            error_codes(authorized(&NOTHING, add()).await?)
+        );
+
+        assert_eq!(
+            BTreeSet::from([i16::from(ErrorCode::TopicAuthorizationFailed)]),
+            error_codes(
+                authorized(
+                    &Allow(&[(AclResourceType::TransactionalId, AclOperation::Write)]),
+                    add()
+                )
+                .await?
+            )
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn add_offsets_to_txn_denied() -> Result<()> {
+        let authorized = authorized(
+            &NOTHING,
+            AddOffsetsToTxnRequest::default()
+                .transactional_id("txn".into())
+                .producer_id(6)
+                .producer_epoch(0)
+                .group_id("readers".into()),
+        )
+        .await?;
+
+        let Authorized::Deny(Body::AddOffsetsToTxnResponse(response)) = authorized else {
+            panic!("{authorized:?}");
+        };
+
+        assert_eq!(
+            i16::from(ErrorCode::TransactionalIdAuthorizationFailed),
+            response.error_code
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn end_txn_denied() -> Result<()> {
+        let authorized = authorized(
+            &NOTHING,
+            EndTxnRequest::default()
+                .transactional_id("txn".into())
+                .producer_id(6)
+                .producer_epoch(0)
+                .committed(true),
+        )
+        .await?;
+
+        let Authorized::Deny(Body::EndTxnResponse(response)) = authorized else {
+            panic!("{authorized:?}");
+        };
+
+        assert_eq!(
+            i16::from(ErrorCode::TransactionalIdAuthorizationFailed),
+            response.error_code
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metadata_denied() -> Result<()> {
+        let authorized = authorized(
+            &NOTHING,
+            MetadataRequest::default()
+                .topics(Some(vec![
+                    MetadataRequestTopic::default()
+                        .name(Some("orders".into()))
+                        .topic_id(None),
+                ]))
+                .allow_auto_topic_creation(Some(true)),
+        )
+        .await?;
+
+        let Authorized::Forward {
+            body: Body::MetadataRequest(request),
+            denied: Some(Body::MetadataResponse(response)),
+        } = authorized
+        else {
+            panic!("{authorized:?}");
+        };
+
+        // a denied topic is never auto created
+        assert_eq!(Some(vec![]), request.topics);
+
+        let topics = response.topics.unwrap_or_default();
+        assert_eq!(Some("orders"), topics[0].name.as_deref());
+        assert_eq!(
+            i16::from(ErrorCode::TopicAuthorizationFailed),
+            topics[0].error_code
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_offsets_denied() -> Result<()> {
+        let authorized = authorized(
+            &NOTHING,
+            ListOffsetsRequest::default()
+                .replica_id(-1)
+                .topics(Some(vec![
+                    ListOffsetsTopic::default()
+                        .name("orders".into())
+                        .partitions(Some(vec![
+                            ListOffsetsPartition::default()
+                                .partition_index(0)
+                                .timestamp(-1),
+                        ])),
+                ])),
+        )
+        .await?;
+
+        let Authorized::Forward {
+            denied: Some(Body::ListOffsetsResponse(response)),
+            ..
+        } = authorized
+        else {
+            panic!("{authorized:?}");
+        };
+
+        let topics = response.topics.unwrap_or_default();
+        assert_eq!(
+            i16::from(ErrorCode::TopicAuthorizationFailed),
+            topics[0].partitions.as_deref().unwrap_or_default()[0].error_code
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn describe_groups_denied() -> Result<()> {
+        let authorized = authorized(
+            &NOTHING,
+            DescribeGroupsRequest::default()
+                .groups(Some(vec!["readers".into()]))
+                .include_authorized_operations(Some(false)),
+        )
+        .await?;
+
+        let Authorized::Forward {
+            denied: Some(Body::DescribeGroupsResponse(response)),
+            ..
+        } = authorized
+        else {
+            panic!("{authorized:?}");
+        };
+
+        let groups = response.groups.unwrap_or_default();
+        assert_eq!("readers", groups[0].group_id);
+        assert_eq!(
+            i16::from(ErrorCode::GroupAuthorizationFailed),
+            groups[0].error_code
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn describe_configs_denied() -> Result<()> {
+        let authorized = authorized(
+            &NOTHING,
+            DescribeConfigsRequest::default()
+                .resources(Some(vec![
+                    DescribeConfigsResource::default()
+                        .resource_type(ConfigResource::Topic.into())
+                        .resource_name("orders".into())
+                        .configuration_keys(None),
+                ]))
+                .include_synonyms(Some(false))
+                .include_documentation(Some(false)),
+        )
+        .await?;
+
+        let Authorized::Forward {
+            denied: Some(Body::DescribeConfigsResponse(response)),
+            ..
+        } = authorized
+        else {
+            panic!("{authorized:?}");
+        };
+
+        let results = response.results.unwrap_or_default();
+        assert_eq!("orders", results[0].resource_name);
+        assert_eq!(
+            i16::from(ErrorCode::TopicAuthorizationFailed),
+            results[0].error_code
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listed_topics_and_groups_describable() {
+        let alice = Identity::new("User:alice", "127.0.0.1");
+
+        let decision = Decision {
+            authorizer: &Readers,
+            identity: &alice,
+            api_key: MetadataRequest::KEY,
+        };
+
+        let mut metadata = Body::from(MetadataResponse::default().topics(Some(vec![
+            MetadataResponseTopic::default().name(Some("orders".into())),
+        ])));
+
+        describable(decision, &mut metadata).await;
+
+        let Body::MetadataResponse(MetadataResponse {
+            topics: Some(topics),
+            ..
+        }) = metadata
+        else {
+            panic!("{metadata:?}");
+        };
+
+        assert!(topics.is_empty());
+
+        let listed = |group_id: &str| ListedGroup::default().group_id(group_id.into());
+
+        let mut groups = Body::from(
+            ListGroupsResponse::default().groups(Some(vec![listed("readers"), listed("writers")])),
+        );
+
+        describable(decision, &mut groups).await;
+
+        let Body::ListGroupsResponse(ListGroupsResponse {
+            groups: Some(groups),
+            ..
+        }) = groups
+        else {
+            panic!("{groups:?}");
+        };
+
+        assert_eq!(
+            vec!["readers"],
+            groups
+                .iter()
+                .map(|group| group.group_id.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+}
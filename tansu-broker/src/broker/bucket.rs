@@ -0,0 +1,511 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Object storage ingestion
+//!
+//! A topic with a `tansu.bucket.url` topic config, e.g.,
+//! `s3://landing/orders/`, polls the bucket for objects created under the
+//! prefix, using the `AWS_*` environment for credentials and endpoint.
+//!
+//! With `tansu.bucket.format=event` (the default), each new object is
+//! produced as an event, keyed by its location, with a JSON value:
+//!
+//! ```json
+//! {"bucket": "landing", "location": "orders/1.csv", "size": 123,
+//!  "last_modified": "2025-01-01T00:00:00+00:00", "e_tag": "..."}
+//! ```
+//!
+//! With `tansu.bucket.format=lines`, each line of a new object is produced as
+//! a record keyed by the location of the object.
+//!
+//! Objects are produced in order of their last modified time, in a
+//! transaction that also commits the last modified time (in milliseconds) of
+//! the newest object as the offset of the `tansu.bucket` group on partition
+//! 0 of the topic. A transaction ends where the last modified time changes,
+//! so that objects with the same last modified time are produced together.
+//!
+//! An object can become visible after others that are newer: S3 has a last
+//! modified time to the second, and a multipart upload has the time that the
+//! upload started. The location and entity tag of each object produced with
+//! a last modified time within a trailing window before the checkpoint are
+//! kept, with any other object in that window still being produced. A broker
+//! starting, or finding a checkpoint committed by another broker, has no
+//! window until it commits: only objects newer than the checkpoint are
+//! produced, so that no object is produced twice.
+//!
+//! S3 has no listing by time, each poll lists the whole prefix, keeping only
+//! the objects that are yet to be produced.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use futures::{TryStreamExt as _, future};
+use object_store::{
+    DynObjectStore, ObjectMeta, ObjectStore as _, aws::AmazonS3Builder, path::Path,
+};
+use serde_json::json;
+use tansu_sans_io::ConfigResource;
+use tansu_storage::Storage;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+use crate::{
+    Error, Result,
+    broker::checkpoint::{Checkpointed, Records, partition, topics},
+};
+
+pub const BUCKET_URL: &str = "tansu.bucket.url";
+pub const BUCKET_FORMAT: &str = "tansu.bucket.format";
+
+/// The group with the last modified time of the newest object produced from a bucket
+pub const BUCKET_GROUP: &str = "tansu.bucket";
+
+/// The maximum number of objects produced in each transaction, unless they
+/// share a last modified time
+const MAX_OBJECTS: usize = 100;
+
+/// The trailing window (in milliseconds) before the checkpoint in which an
+/// object becoming visible late is still produced
+const TRAILING_WINDOW: i64 = 15 * 60 * 1_000;
+
+/// How the objects of a bucket are produced
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Format {
+    /// An event describing each object
+    #[default]
+    Event,
+
+    /// Each line of each object
+    Lines,
+}
+
+impl TryFrom<&str> for Format {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "event" => Ok(Self::Event),
+            "lines" => Ok(Self::Lines),
+            otherwise => Err(Error::Message(format!(
+                "unknown bucket format: {otherwise}"
+            ))),
+        }
+    }
+}
+
+/// The bucket of a topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Bucket {
+    url: Url,
+    format: Format,
+}
+
+impl Bucket {
+    /// The bucket for a topic from its configuration, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        let configs = storage
+            .describe_config(
+                topic,
+                ConfigResource::Topic,
+                Some(&[BUCKET_URL.to_owned(), BUCKET_FORMAT.to_owned()]),
+            )
+            .await?
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|config| config.value.map(|value| (config.name, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        configs
+            .get(BUCKET_URL)
+            .map(|url| {
+                let url = Url::parse(url)?;
+
+                if url.scheme() != "s3" {
+                    return Err(Error::Message(format!("unsupported bucket: {url}")));
+                }
+
+                configs
+                    .get(BUCKET_FORMAT)
+                    .map_or(Ok(Format::default()), |format| {
+                        Format::try_from(format.as_str())
+                    })
+                    .map(|format| Self { url, format })
+            })
+            .transpose()
+    }
+
+    fn name(&self) -> &str {
+        self.url.host_str().unwrap_or_default()
+    }
+
+    fn prefix(&self) -> Option<Path> {
+        Some(self.url.path().trim_matches('/'))
+            .filter(|prefix| !prefix.is_empty())
+            .map(Path::from)
+    }
+}
+
+/// The key and value of an object created event
+fn event(bucket: &str, meta: &ObjectMeta) -> Result<(Option<Bytes>, Option<Bytes>)> {
+    serde_json::to_vec(&json!({
+        "bucket": bucket,
+        "location": meta.location.as_ref(),
+        "size": meta.size,
+        "last_modified": meta.last_modified.to_rfc3339(),
+        "e_tag": meta.e_tag,
+    }))
+    .map(|value| {
+        (
+            Some(Bytes::copy_from_slice(meta.location.as_ref().as_bytes())),
+            Some(Bytes::from(value)),
+        )
+    })
+    .map_err(Into::into)
+}
+
+/// The lines of an object, without line endings or a final empty line
+fn lines(content: &Bytes) -> impl Iterator<Item = Bytes> + '_ {
+    content
+        .strip_suffix(b"\n")
+        .unwrap_or(content)
+        .split(|byte| *byte == b'\n')
+        .filter(|_| !content.is_empty())
+        .map(|line| content.slice_ref(line.strip_suffix(b"\r").unwrap_or(line)))
+}
+
+/// The objects produced from a bucket into a topic by this broker
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Ingested {
+    /// The checkpoint when this broker started producing into the topic
+    since: i64,
+
+    /// The last checkpoint committed by this broker
+    checkpoint: i64,
+
+    /// The last modified time of the objects produced within the trailing
+    /// window, by location and entity tag
+    objects: BTreeMap<(Path, Option<String>), i64>,
+}
+
+impl Ingested {
+    /// Without a trailing window, taking every object up to the checkpoint as produced
+    fn new(checkpoint: i64) -> Self {
+        Self {
+            since: checkpoint,
+            checkpoint,
+            objects: BTreeMap::new(),
+        }
+    }
+
+    /// Objects with this last modified time, or earlier, have been produced
+    fn floor(&self) -> i64 {
+        self.since
+            .max(self.checkpoint.saturating_sub(TRAILING_WINDOW))
+    }
+
+    fn is_new(&self, meta: &ObjectMeta) -> bool {
+        meta.last_modified.timestamp_millis() > self.floor()
+            && !self
+                .objects
+                .contains_key(&(meta.location.clone(), meta.e_tag.clone()))
+    }
+
+    /// Objects produced in a transaction committing a checkpoint
+    fn produced(&mut self, objects: &[ObjectMeta], checkpoint: i64) {
+        self.checkpoint = checkpoint;
+
+        self.objects.extend(objects.iter().map(|meta| {
+            (
+                (meta.location.clone(), meta.e_tag.clone()),
+                meta.last_modified.timestamp_millis(),
+            )
+        }));
+
+        let floor = self.floor();
+        self.objects
+            .retain(|_, last_modified| *last_modified > floor);
+    }
+}
+
+/// The objects to produce in a transaction, oldest first, only ending early
+/// where the last modified time changes
+fn oldest(mut objects: Vec<ObjectMeta>) -> Vec<ObjectMeta> {
+    objects.sort_by(|a, b| {
+        a.last_modified
+            .cmp(&b.last_modified)
+            .then_with(|| a.location.cmp(&b.location))
+    });
+
+    let cutoff = (MAX_OBJECTS..objects.len())
+        .find(|i| objects[*i].last_modified != objects[i - 1].last_modified)
+        .unwrap_or(objects.len());
+
+    objects.truncate(cutoff);
+    objects
+}
+
+/// Produces the objects of buckets into their topics
+#[derive(Clone, Debug)]
+pub struct BucketSource<S> {
+    storage: S,
+    interval: Duration,
+    stores: BTreeMap<String, Arc<DynObjectStore>>,
+    ingested: BTreeMap<String, Ingested>,
+    cancellation: CancellationToken,
+}
+
+impl<S> BucketSource<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            stores: BTreeMap::new(),
+            ingested: BTreeMap::new(),
+            cancellation,
+        }
+    }
+
+    pub async fn serve(mut self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .poll()
+                        .await
+                        .inspect(|produced| debug!(produced))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn store(&mut self, bucket: &Bucket) -> Result<Arc<DynObjectStore>> {
+        if let Some(store) = self.stores.get(bucket.name()) {
+            return Ok(store.clone());
+        }
+
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket.name())
+            .build()
+            .map(|store| Arc::new(store) as Arc<DynObjectStore>)?;
+
+        _ = self.stores.insert(bucket.name().to_owned(), store.clone());
+
+        Ok(store)
+    }
+
+    /// Produce the new objects of each bucket, returning the number of records produced
+    #[instrument(skip(self))]
+    pub async fn poll(&mut self) -> Result<u64> {
+        let mut produced = 0;
+
+        for (name, partitions) in topics(&self.storage).await? {
+            let Some(bucket) = Bucket::describe(&self.storage, &name).await? else {
+                continue;
+            };
+
+            produced += self
+                .topic(&name, &bucket, partitions)
+                .await
+                .inspect_err(|err| warn!(topic = name, bucket = %bucket.url, ?err))
+                .unwrap_or_default();
+        }
+
+        Ok(produced)
+    }
+
+    async fn topic(&mut self, name: &str, bucket: &Bucket, partitions: i32) -> Result<u64> {
+        let (transaction, checkpoint) =
+            Checkpointed::begin(&self.storage, BUCKET_GROUP, name).await?;
+
+        let store = self.store(bucket)?;
+
+        let mut ingested = self
+            .ingested
+            .get(name)
+            .filter(|ingested| ingested.checkpoint == checkpoint)
+            .cloned()
+            .unwrap_or_else(|| Ingested::new(checkpoint));
+
+        let objects = oldest(
+            store
+                .list(bucket.prefix().as_ref())
+                .try_filter(|meta| future::ready(ingested.is_new(meta)))
+                .try_collect::<Vec<_>>()
+                .await?,
+        );
+
+        let Some(last_modified) = objects
+            .last()
+            .map(|meta| meta.last_modified.timestamp_millis().max(checkpoint))
+        else {
+            _ = self.ingested.insert(name.to_owned(), ingested);
+            return Ok(0);
+        };
+
+        debug!(name, last_modified, objects = objects.len());
+
+        let mut records = Records::new();
+        let mut sequence = 0;
+
+        for meta in &objects {
+            let mut produce = |key: Option<Bytes>, value: Option<Bytes>| {
+                records
+                    .entry(partition(sequence, key.as_deref(), partitions))
+                    .or_default()
+                    .push((key, value));
+
+                sequence += 1;
+            };
+
+            match bucket.format {
+                Format::Event => {
+                    let (key, value) = event(bucket.name(), meta)?;
+                    produce(key, value);
+                }
+
+                Format::Lines => {
+                    let content = store.get(&meta.location).await?.bytes().await?;
+                    let key = Bytes::copy_from_slice(meta.location.as_ref().as_bytes());
+
+                    for line in lines(&content) {
+                        produce(Some(key.clone()), Some(line));
+                    }
+                }
+            }
+        }
+
+        let produced = transaction.commit(records, last_modified).await?;
+
+        ingested.produced(&objects, last_modified);
+        _ = self.ingested.insert(name.to_owned(), ingested);
+
+        Ok(produced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+
+    fn meta(location: &str, last_modified: i64) -> ObjectMeta {
+        ObjectMeta {
+            location: Path::from(location),
+            last_modified: (SystemTime::UNIX_EPOCH + Duration::from_millis(last_modified as u64))
+                .into(),
+            size: 0,
+            e_tag: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn lines_without_final_newline() {
+        let content = Bytes::from_static(b"abc\r\ndef\n\nghi\n");
+
+        assert_eq!(
+            vec![
+                Bytes::from_static(b"abc"),
+                Bytes::from_static(b"def"),
+                Bytes::from_static(b""),
+                Bytes::from_static(b"ghi"),
+            ],
+            lines(&content).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn oldest_first() {
+        let objects = vec![meta("b", 3), meta("a", 3), meta("c", 1), meta("d", 2)];
+
+        assert_eq!(
+            vec![meta("c", 1), meta("d", 2), meta("a", 3), meta("b", 3)],
+            oldest(objects)
+        );
+    }
+
+    #[test]
+    fn oldest_without_splitting_last_modified() {
+        let objects = (0..MAX_OBJECTS + 2)
+            .map(|i| meta(&format!("{i:03}"), if i < MAX_OBJECTS - 1 { 1 } else { 2 }))
+            .chain([meta("z", 3)])
+            .collect::<Vec<_>>();
+
+        let oldest = oldest(objects);
+        assert_eq!(MAX_OBJECTS + 2, oldest.len());
+        assert!(
+            oldest
+                .iter()
+                .all(|meta| meta.last_modified.timestamp_millis() < 3)
+        );
+    }
+
+    #[test]
+    fn newer_than_checkpoint_when_started() {
+        let ingested = Ingested::new(2_000);
+
+        assert!(!ingested.is_new(&meta("a", 1_000)));
+        assert!(!ingested.is_new(&meta("b", 2_000)));
+        assert!(ingested.is_new(&meta("c", 2_001)));
+    }
+
+    #[test]
+    fn late_within_trailing_window() {
+        let mut ingested = Ingested::new(-1);
+        ingested.produced(&[meta("a", 1_000), meta("b", 2_000)], 2_000);
+
+        assert!(!ingested.is_new(&meta("a", 1_000)));
+        assert!(!ingested.is_new(&meta("b", 2_000)));
+
+        assert!(ingested.is_new(&meta("c", 1_000)));
+        assert!(ingested.is_new(&meta("d", 2_000)));
+
+        ingested.produced(&[meta("c", 1_000), meta("d", 2_000)], 2_000);
+        assert!(!ingested.is_new(&meta("c", 1_000)));
+        assert!(!ingested.is_new(&meta("d", 2_000)));
+    }
+
+    #[test]
+    fn trailing_window_is_pruned() {
+        let mut ingested = Ingested::new(-1);
+        ingested.produced(&[meta("a", 1_000)], 1_000);
+
+        let checkpoint = 2_000 + TRAILING_WINDOW;
+        ingested.produced(&[meta("b", checkpoint)], checkpoint);
+
+        assert_eq!(1, ingested.objects.len());
+        assert!(!ingested.is_new(&meta("c", 2_000)));
+        assert!(ingested.is_new(&meta("c", 2_001)));
+    }
+}
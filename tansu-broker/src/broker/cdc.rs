@@ -0,0 +1,587 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Change data capture from Postgres
+//!
+//! A topic with a `tansu.cdc.url` topic config captures the changes to a
+//! table (`tansu.cdc.table`, by default the name of the topic) in a Postgres
+//! database from a logical replication slot (`tansu.cdc.slot`) using the
+//! [wal2json](https://github.com/eulerto/wal2json) output plugin:
+//!
+//! ```sql
+//! select pg_create_logical_replication_slot('orders', 'wal2json');
+//! ```
+//!
+//! Each inserted or updated row is produced as a JSON object of its columns,
+//! keyed by a JSON object of its primary key columns. A deleted row is
+//! produced as a tombstone with the same key, so that the topic may be
+//! compacted. A keyed row is produced to the partition chosen by the default
+//! Kafka partitioner, otherwise rows are spread over the partitions by their
+//! LSN.
+//!
+//! A JSON schema inferred from the columns of the table is registered for the
+//! topic in the schema registry whenever the columns change.
+//!
+//! Changes are produced in a transaction that also commits the LSN of the
+//! last change as the offset of the `tansu.cdc` group on partition 0 of the
+//! topic, the slot is only advanced to a committed LSN. Without a committed
+//! LSN, a snapshot of the table is produced first, with changes made while the
+//! snapshot is taken produced again after it.
+//!
+//! As the URL may embed credentials, `tansu.cdc.url` is sensitive and may be
+//! a `secret://` reference to an environment variable or mounted file.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use bytes::Bytes;
+use deadpool_postgres::Client;
+use serde_json::{Map, Value, json};
+use tansu_sans_io::ConfigResource;
+use tansu_schema::{Registry, SchemaType};
+use tansu_storage::{Secret, Storage};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    Error, Result,
+    broker::{
+        checkpoint::{Checkpointed, Records, partition, topics},
+        outbox::{Pools, is_identifier},
+    },
+};
+
+pub const CDC_URL: &str = "tansu.cdc.url";
+pub const CDC_SLOT: &str = "tansu.cdc.slot";
+pub const CDC_TABLE: &str = "tansu.cdc.table";
+
+/// The group with the LSN of the last change produced from a slot
+pub const CDC_GROUP: &str = "tansu.cdc";
+
+/// The maximum number of changes peeked from a slot in each transaction
+const MAX_CHANGES: i32 = 1_000;
+
+const INSERT: &str = "I";
+const UPDATE: &str = "U";
+const DELETE: &str = "D";
+
+/// The change data capture of a topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Capture {
+    url: String,
+    slot: String,
+    table: String,
+}
+
+impl Capture {
+    /// The change data capture for a topic from its configuration, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        let configs = storage
+            .describe_config(
+                topic,
+                ConfigResource::Topic,
+                Some(&[
+                    CDC_URL.to_owned(),
+                    CDC_SLOT.to_owned(),
+                    CDC_TABLE.to_owned(),
+                ]),
+            )
+            .await?
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|config| config.value.map(|value| (config.name, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        configs
+            .get(CDC_URL)
+            .map(|url| {
+                let slot = configs
+                    .get(CDC_SLOT)
+                    .cloned()
+                    .ok_or(Error::Message(format!("{CDC_SLOT} is required")))?;
+
+                let table = configs.get(CDC_TABLE).cloned().unwrap_or(topic.to_owned());
+
+                if !is_identifier(&table) {
+                    return Err(Error::Message(format!("invalid cdc table: {table}")));
+                }
+
+                Secret::resolve(url).map_err(Into::into).map(|url| Self {
+                    url: url.into_owned(),
+                    slot,
+                    table,
+                })
+            })
+            .transpose()
+    }
+
+    /// The table in the form used by the `add-tables` option of wal2json
+    fn add_tables(&self) -> String {
+        if self.table.contains('.') {
+            self.table.clone()
+        } else {
+            format!("*.{}", self.table)
+        }
+    }
+}
+
+/// A column of a captured table
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Column {
+    name: String,
+    r#type: String,
+    primary_key: bool,
+}
+
+/// The JSON schema type of a Postgres type, as named by `format_type`
+fn json_type(r#type: &str) -> Value {
+    if r#type.ends_with("[]") {
+        return json!({"type": ["string", "null"]});
+    }
+
+    match r#type.split('(').next().unwrap_or(r#type).trim() {
+        "smallint" | "integer" | "bigint" => json!({"type": ["integer", "null"]}),
+        "real" | "double precision" | "numeric" => json!({"type": ["number", "null"]}),
+        "boolean" => json!({"type": ["boolean", "null"]}),
+        "json" | "jsonb" => json!({}),
+        _ => json!({"type": ["string", "null"]}),
+    }
+}
+
+/// A JSON schema object with a property for each column
+fn object<'a>(columns: impl Iterator<Item = &'a Column>) -> Value {
+    json!({
+        "type": "object",
+        "properties": Map::from_iter(
+            columns.map(|column| (column.name.clone(), json_type(&column.r#type)))
+        ),
+    })
+}
+
+/// A JSON schema for the keys and values produced from the columns of a table
+fn infer(columns: &[Column]) -> Value {
+    let mut properties = Map::new();
+
+    if columns.iter().any(|column| column.primary_key) {
+        _ = properties.insert(
+            "key".into(),
+            object(columns.iter().filter(|column| column.primary_key)),
+        );
+    }
+
+    _ = properties.insert("value".into(), object(columns.iter()));
+
+    json!({"type": "object", "properties": properties})
+}
+
+/// A JSON object of the named columns of a wal2json change
+fn row<'a>(columns: Option<&Value>, names: impl Iterator<Item = &'a str>) -> Option<Value> {
+    let columns = columns.and_then(Value::as_array)?;
+
+    let row = names
+        .filter_map(|name| {
+            columns
+                .iter()
+                .find(|column| column.get("name").and_then(Value::as_str) == Some(name))
+                .map(|column| {
+                    (
+                        name.to_owned(),
+                        column.get("value").cloned().unwrap_or(Value::Null),
+                    )
+                })
+        })
+        .collect::<Map<_, _>>();
+
+    (!row.is_empty()).then_some(Value::Object(row))
+}
+
+/// The key and value of a wal2json (format version 2) change, ignoring
+/// anything that isn't an insert, update or delete
+fn change(columns: &[Column], data: &Value) -> Option<(Option<Value>, Option<Value>)> {
+    let primary_key = || {
+        columns
+            .iter()
+            .filter(|column| column.primary_key)
+            .map(|column| column.name.as_str())
+    };
+
+    match data.get("action").and_then(Value::as_str)? {
+        INSERT | UPDATE => {
+            let after = data.get("columns");
+
+            Some((
+                row(after, primary_key()),
+                row(after, columns.iter().map(|column| column.name.as_str())),
+            ))
+        }
+
+        DELETE => Some((row(data.get("identity"), primary_key()), None)),
+
+        _ => None,
+    }
+}
+
+fn encode(value: Option<Value>) -> Result<Option<Bytes>> {
+    value
+        .map(|value| serde_json::to_vec(&value).map(Bytes::from))
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Produces the changes of tables into their topics
+#[derive(Clone, Debug)]
+pub struct ChangeCapture<S> {
+    storage: S,
+    schema_registry: Option<Registry>,
+    interval: Duration,
+    pools: Pools,
+    schemas: BTreeMap<String, Value>,
+    cancellation: CancellationToken,
+}
+
+impl<S> ChangeCapture<S>
+where
+    S: Storage,
+{
+    pub fn new(
+        storage: S,
+        schema_registry: Option<Registry>,
+        interval: Duration,
+        cancellation: CancellationToken,
+    ) -> Self {
+        Self {
+            storage,
+            schema_registry,
+            interval,
+            pools: Pools::default(),
+            schemas: BTreeMap::new(),
+            cancellation,
+        }
+    }
+
+    pub async fn serve(mut self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .poll()
+                        .await
+                        .inspect(|produced| debug!(produced))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Produce the new changes of each captured table, returning the number of records produced
+    #[instrument(skip(self))]
+    pub async fn poll(&mut self) -> Result<u64> {
+        let mut produced = 0;
+
+        for (name, partitions) in topics(&self.storage).await? {
+            let Some(capture) = Capture::describe(&self.storage, &name).await? else {
+                continue;
+            };
+
+            produced += self
+                .topic(&name, &capture, partitions)
+                .await
+                .inspect_err(|err| warn!(topic = name, table = %capture.table, ?err))
+                .unwrap_or_default();
+        }
+
+        Ok(produced)
+    }
+
+    async fn topic(&mut self, name: &str, capture: &Capture, partitions: i32) -> Result<u64> {
+        let (transaction, checkpoint) = Checkpointed::begin(&self.storage, CDC_GROUP, name).await?;
+
+        let client = self.pools.get(&capture.url)?.get().await?;
+
+        let columns = columns(&client, &capture.table).await?;
+        self.register(name, &columns).await?;
+
+        let mut records = Records::new();
+
+        let lsn = if checkpoint < 0 {
+            let lsn = confirmed_flush(&client, &capture.slot).await?;
+
+            for (sequence, row) in client
+                .query(
+                    &format!("select row_to_json(t)::text from {} t", capture.table),
+                    &[],
+                )
+                .await?
+                .into_iter()
+                .enumerate()
+            {
+                let data = serde_json::from_str::<Value>(row.get(0))?;
+
+                let key = row_object(&columns, &data, true);
+                let value = row_object(&columns, &data, false);
+
+                let (key, value) = (encode(key)?, encode(value)?);
+
+                records
+                    .entry(partition(sequence as i64, key.as_deref(), partitions))
+                    .or_default()
+                    .push((key, value));
+            }
+
+            debug!(
+                name,
+                lsn,
+                snapshot = records.values().map(Vec::len).sum::<usize>()
+            );
+
+            lsn
+        } else {
+            advance(&client, &capture.slot, checkpoint).await?;
+
+            let mut lsn = checkpoint;
+
+            for row in client
+                .query(
+                    "select (lsn - '0/0'::pg_lsn)::bigint, data \
+                     from pg_logical_slot_peek_changes($1, null, $2, \
+                     'format-version', '2', 'add-tables', $3)",
+                    &[&capture.slot, &MAX_CHANGES, &capture.add_tables()],
+                )
+                .await?
+            {
+                let change_lsn = row.get::<_, i64>(0);
+
+                if change_lsn <= checkpoint {
+                    continue;
+                }
+
+                lsn = lsn.max(change_lsn);
+
+                let data = serde_json::from_str::<Value>(row.get(1))?;
+
+                let Some((key, value)) = change(&columns, &data) else {
+                    continue;
+                };
+
+                let (key, value) = (encode(key)?, encode(value)?);
+
+                records
+                    .entry(partition(change_lsn, key.as_deref(), partitions))
+                    .or_default()
+                    .push((key, value));
+            }
+
+            if lsn == checkpoint {
+                return Ok(0);
+            }
+
+            lsn
+        };
+
+        let produced = transaction.commit(records, lsn).await?;
+
+        advance(&client, &capture.slot, lsn)
+            .await
+            .inspect_err(|err| warn!(slot = capture.slot, lsn, ?err))
+            .unwrap_or_default();
+
+        Ok(produced)
+    }
+
+    /// Register the schema inferred from the columns of a table, when they have changed
+    async fn register(&mut self, topic: &str, columns: &[Column]) -> Result<()> {
+        let Some(ref registry) = self.schema_registry else {
+            return Ok(());
+        };
+
+        let schema = infer(columns);
+
+        if self.schemas.get(topic) == Some(&schema) {
+            return Ok(());
+        }
+
+        let version = registry
+            .register(
+                topic,
+                SchemaType::Json,
+                serde_json::to_vec(&schema).map(Bytes::from)?,
+            )
+            .await?;
+
+        debug!(topic, version);
+
+        _ = self.schemas.insert(topic.to_owned(), schema);
+
+        Ok(())
+    }
+}
+
+/// The key (primary key columns) or value (all columns) of a snapshot row
+fn row_object(columns: &[Column], data: &Value, primary_key: bool) -> Option<Value> {
+    let row = columns
+        .iter()
+        .filter(|column| !primary_key || column.primary_key)
+        .filter_map(|column| {
+            data.get(&column.name)
+                .map(|value| (column.name.clone(), value.clone()))
+        })
+        .collect::<Map<_, _>>();
+
+    (!row.is_empty()).then_some(Value::Object(row))
+}
+
+/// The columns of a table, in order
+async fn columns(client: &Client, table: &str) -> Result<Vec<Column>> {
+    client
+        .query(
+            "select a.attname::text, format_type(a.atttypid, a.atttypmod), \
+             coalesce(a.attnum = any(i.indkey), false) \
+             from pg_attribute a \
+             left join pg_index i on i.indrelid = a.attrelid and i.indisprimary \
+             where a.attrelid = $1::text::regclass and a.attnum > 0 and not a.attisdropped \
+             order by a.attnum",
+            &[&table],
+        )
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|row| Column {
+                    name: row.get(0),
+                    r#type: row.get(1),
+                    primary_key: row.get(2),
+                })
+                .collect()
+        })
+        .map_err(Into::into)
+}
+
+/// The LSN that a slot has been confirmed to
+async fn confirmed_flush(client: &Client, slot: &str) -> Result<i64> {
+    client
+        .query_opt(
+            "select (confirmed_flush_lsn - '0/0'::pg_lsn)::bigint \
+             from pg_replication_slots where slot_name = $1",
+            &[&slot],
+        )
+        .await?
+        .map(|row| row.get(0))
+        .ok_or(Error::Message(format!("unknown replication slot: {slot}")))
+}
+
+/// Advance a slot to a committed LSN, releasing the WAL that it retains
+async fn advance(client: &Client, slot: &str, lsn: i64) -> Result<()> {
+    client
+        .execute(
+            "select pg_replication_slot_advance(slot_name, \
+             greatest(confirmed_flush_lsn, '0/0'::pg_lsn + $2::bigint::numeric)) \
+             from pg_replication_slots where slot_name = $1",
+            &[&slot, &lsn],
+        )
+        .await
+        .map(|advanced| debug!(slot, lsn, advanced))
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<Column> {
+        vec![
+            Column {
+                name: "id".into(),
+                r#type: "integer".into(),
+                primary_key: true,
+            },
+            Column {
+                name: "name".into(),
+                r#type: "character varying(64)".into(),
+                primary_key: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn infer_schema() {
+        assert_eq!(
+            json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "object",
+                        "properties": {"id": {"type": ["integer", "null"]}},
+                    },
+                    "value": {
+                        "type": "object",
+                        "properties": {
+                            "id": {"type": ["integer", "null"]},
+                            "name": {"type": ["string", "null"]},
+                        },
+                    },
+                },
+            }),
+            infer(&columns())
+        );
+    }
+
+    #[test]
+    fn insert_update_delete() {
+        let columns = columns();
+
+        let insert = json!({
+            "action": "I",
+            "schema": "public",
+            "table": "t",
+            "columns": [
+                {"name": "id", "type": "integer", "value": 1},
+                {"name": "name", "type": "character varying(64)", "value": "abc"},
+            ],
+        });
+
+        assert_eq!(
+            Some((
+                Some(json!({"id": 1})),
+                Some(json!({"id": 1, "name": "abc"}))
+            )),
+            change(&columns, &insert)
+        );
+
+        let delete = json!({
+            "action": "D",
+            "schema": "public",
+            "table": "t",
+            "identity": [{"name": "id", "type": "integer", "value": 1}],
+        });
+
+        assert_eq!(
+            Some((Some(json!({"id": 1})), None)),
+            change(&columns, &delete)
+        );
+
+        assert_eq!(None, change(&columns, &json!({"action": "B"})));
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partition unavailability injection
+//!
+//! A topition made unavailable with `/partition-unavailable` on the admin
+//! listener answers produce and fetch with a retriable error,
+//! `NotLeaderOrFollower` (the default) or `KafkaStorageError`, until the
+//! injection expires or is removed. Other partitions in the same request are
+//! unaffected. Clients are expected to refresh their metadata and retry,
+//! verifying their retry and failover handling without stopping a broker.
+//!
+//! Injection is off by default: the admin listener only serves
+//! `/partition-unavailable` for a broker started with `--chaos`, without
+//! which no topition can be made unavailable.
+//!
+//! Injections are held by this broker only, and expire after at most
+//! [`MAXIMUM_DURATION`]. Each injected error is counted by the
+//! `tansu_chaos_injected` counter.
+
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use opentelemetry::{KeyValue, metrics::Counter};
+use rama::{Context, Layer, Service};
+use serde::{Deserialize, Serialize};
+use tansu_sans_io::{
+    ApiKey as _, ErrorCode, FetchRequest, FetchResponse, ProduceRequest, ProduceResponse,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+};
+use tansu_storage::{Storage, Topition};
+use tracing::{debug, instrument, warn};
+
+use crate::{Error, METER, Result, broker::claim_check::topic_name};
+
+/// How long a topition is unavailable when no duration is requested
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(60);
+
+/// The longest a topition may be made unavailable
+pub const MAXIMUM_DURATION: Duration = Duration::from_secs(15 * 60);
+
+static INJECTED: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_chaos_injected")
+        .with_description("retriable errors injected into produce and fetch responses")
+        .build()
+});
+
+static UNAVAILABLE: LazyLock<Mutex<BTreeMap<Topition, (Unavailable, Instant)>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// The retriable error returned by an unavailable topition
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Unavailable {
+    #[default]
+    NotLeaderOrFollower,
+    KafkaStorageError,
+}
+
+impl From<Unavailable> for ErrorCode {
+    fn from(value: Unavailable) -> Self {
+        match value {
+            Unavailable::NotLeaderOrFollower => Self::NotLeaderOrFollower,
+            Unavailable::KafkaStorageError => Self::KafkaStorageError,
+        }
+    }
+}
+
+/// A request to make a topition unavailable
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct Injection {
+    pub error: Unavailable,
+    pub duration_ms: Option<u64>,
+}
+
+/// A topition that is unavailable
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Fault {
+    pub topic: String,
+    pub partition: i32,
+    pub error: Unavailable,
+    pub remaining_ms: u64,
+}
+
+/// Make a topition unavailable, replacing any earlier injection
+pub fn inject(topition: Topition, injection: Injection) -> Result<Fault> {
+    let duration = injection
+        .duration_ms
+        .map_or(DEFAULT_DURATION, Duration::from_millis);
+
+    if duration > MAXIMUM_DURATION {
+        return Err(Error::Message(format!(
+            "duration_ms: {} exceeds the maximum: {}",
+            duration.as_millis(),
+            MAXIMUM_DURATION.as_millis()
+        )));
+    }
+
+    debug!(?topition, ?injection);
+
+    let expires = Instant::now() + duration;
+
+    UNAVAILABLE
+        .lock()
+        .map(|mut injections| {
+            _ = injections.insert(topition.clone(), (injection.error, expires));
+            fault(&topition, injection.error, expires)
+        })
+        .map_err(Into::into)
+}
+
+/// Make a topition available again, returning whether it was unavailable
+pub fn remove(topition: &Topition) -> Result<bool> {
+    UNAVAILABLE
+        .lock()
+        .map(|mut injections| {
+            injections
+                .remove(topition)
+                .is_some_and(|(_, expires)| expires > Instant::now())
+        })
+        .map_err(Into::into)
+}
+
+/// The topitions that are unavailable
+pub fn faults() -> Result<Vec<Fault>> {
+    UNAVAILABLE
+        .lock()
+        .map(|mut injections| {
+            let now = Instant::now();
+            injections.retain(|_, (_, expires)| *expires > now);
+
+            injections
+                .iter()
+                .map(|(topition, (error, expires))| fault(topition, *error, *expires))
+                .collect()
+        })
+        .map_err(Into::into)
+}
+
+fn fault(topition: &Topition, error: Unavailable, expires: Instant) -> Fault {
+    Fault {
+        topic: topition.topic().to_owned(),
+        partition: topition.partition(),
+        error,
+        remaining_ms: u64::try_from(
+            expires
+                .saturating_duration_since(Instant::now())
+                .as_millis(),
+        )
+        .unwrap_or(u64::MAX),
+    }
+}
+
+/// The error of a topition that is unavailable, removing an expired injection
+fn injected_error(topition: &Topition) -> Option<ErrorCode> {
+    let mut injections = UNAVAILABLE.lock().inspect_err(|err| warn!(?err)).ok()?;
+
+    match injections.get(topition) {
+        Some((error, expires)) if *expires > Instant::now() => Some(ErrorCode::from(*error)),
+
+        Some(_) => {
+            _ = injections.remove(topition);
+            None
+        }
+
+        None => None,
+    }
+}
+
+fn is_injecting() -> bool {
+    UNAVAILABLE
+        .lock()
+        .is_ok_and(|injections| !injections.is_empty())
+}
+
+fn injected(topic: &str, api_key: i16, error_code: ErrorCode) {
+    INJECTED.add(
+        1,
+        &[
+            KeyValue::new("topic", topic.to_owned()),
+            KeyValue::new("api_key", i64::from(api_key)),
+            KeyValue::new("error", format!("{error_code:?}")),
+        ],
+    );
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChaosLayer;
+
+impl<S> Layer<S> for ChaosLayer {
+    type Service = ChaosService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ChaosService { inner }
+    }
+}
+
+/// A [`Service`] answering produce and fetch of an unavailable topition with a
+/// retriable error
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChaosService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for ChaosService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Send + Sync + 'static,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        if !is_injecting() {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        let mut rejected = vec![];
+
+        for topic in req.topic_data.as_deref_mut().unwrap_or_default() {
+            let (available, faulted): (Vec<_>, Vec<_>) = topic
+                .partition_data
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    let error_code =
+                        injected_error(&Topition::new(topic.name.as_str(), partition.index));
+                    (partition, error_code)
+                })
+                .partition(|(_, error_code)| error_code.is_none());
+
+            topic.partition_data = Some(
+                available
+                    .into_iter()
+                    .map(|(partition, _)| partition)
+                    .collect(),
+            );
+
+            if faulted.is_empty() {
+                continue;
+            }
+
+            rejected.push(
+                TopicProduceResponse::default()
+                    .name(topic.name.clone())
+                    .partition_responses(Some(
+                        faulted
+                            .into_iter()
+                            .filter_map(|(partition, error_code)| {
+                                error_code.map(|error_code| (partition.index, error_code))
+                            })
+                            .inspect(|(index, error_code)| {
+                                debug!(topic = topic.name, index, ?error_code);
+                                injected(&topic.name, ProduceRequest::KEY, *error_code);
+                            })
+                            .map(|(index, error_code)| {
+                                PartitionProduceResponse::default()
+                                    .index(index)
+                                    .error_code(error_code.into())
+                                    .base_offset(-1)
+                                    .log_append_time_ms(Some(-1))
+                                    .log_start_offset(Some(-1))
+                                    .record_errors(Some([].into()))
+                                    .error_message(None)
+                                    .current_leader(None)
+                            })
+                            .collect(),
+                    )),
+            );
+        }
+
+        self.inner.serve(ctx, req).await.map(|mut response| {
+            if !rejected.is_empty() {
+                response
+                    .responses
+                    .get_or_insert_default()
+                    .append(&mut rejected);
+            }
+
+            response
+        })
+    }
+}
+
+impl<S, State> Service<State, FetchRequest> for ChaosService<S>
+where
+    S: Service<State, FetchRequest, Response = FetchResponse>,
+    State: Storage,
+{
+    type Response = FetchResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: FetchRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        if !is_injecting() {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        let storage = ctx.state().clone();
+        let mut response = self.inner.serve(ctx, req).await?;
+
+        for topic in response.responses.as_deref_mut().unwrap_or_default() {
+            let Some(name) = topic_name(&storage, topic).await else {
+                continue;
+            };
+
+            for partition in topic.partitions.as_deref_mut().unwrap_or_default() {
+                let Some(error_code) =
+                    injected_error(&Topition::new(name.as_str(), partition.partition_index))
+                else {
+                    continue;
+                };
+
+                debug!(
+                    topic = name,
+                    partition = partition.partition_index,
+                    ?error_code
+                );
+                injected(&name, FetchRequest::KEY, error_code);
+
+                partition.error_code = error_code.into();
+                partition.high_watermark = -1;
+                partition.records = None;
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_injection() -> Result<()> {
+        let topition = Topition::new("bounded_injection", 3);
+
+        assert!(
+            inject(
+                topition.clone(),
+                Injection {
+                    error: Unavailable::KafkaStorageError,
+                    duration_ms: Some(MAXIMUM_DURATION.as_secs() * 1_000 + 1),
+                },
+            )
+            .is_err()
+        );
+        assert_eq!(None, injected_error(&topition));
+
+        let fault = inject(
+            topition.clone(),
+            Injection {
+                error: Unavailable::KafkaStorageError,
+                duration_ms: Some(60_000),
+            },
+        )?;
+        assert_eq!(Unavailable::KafkaStorageError, fault.error);
+        assert!(fault.remaining_ms <= 60_000);
+
+        assert_eq!(
+            Some(ErrorCode::KafkaStorageError),
+            injected_error(&topition)
+        );
+        assert_eq!(None, injected_error(&Topition::new("bounded_injection", 4)));
+        assert!(
+            faults()?
+                .iter()
+                .any(|fault| fault.topic == "bounded_injection"
+                    && fault.partition == 3
+                    && fault.error == Unavailable::KafkaStorageError)
+        );
+
+        assert!(remove(&topition)?);
+        assert_eq!(None, injected_error(&topition));
+
+        _ = inject(
+            topition.clone(),
+            Injection {
+                duration_ms: Some(0),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(None, injected_error(&topition));
+        assert!(!remove(&topition)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Producing records into a topic with a checkpoint
+//!
+//! Sources that produce records from outside of Tansu (e.g., an outbox table
+//! or an object store bucket) keep a checkpoint of their progress as the
+//! offset of a group on partition 0 of the topic, committed in the same
+//! transaction as the records. With each broker using the same transactional
+//! id for a source and topic, a broker that has been fenced is unable to
+//! commit, so that each record is produced exactly once.
+
+use std::{collections::BTreeMap, slice, time::SystemTime};
+
+use bytes::Bytes;
+use tansu_sans_io::{
+    BatchAttribute, ErrorCode,
+    add_partitions_to_txn_request::AddPartitionsToTxnTopic,
+    record::{Record, inflated},
+    to_timestamp,
+    txn_offset_commit_request::{TxnOffsetCommitRequestPartition, TxnOffsetCommitRequestTopic},
+};
+use tansu_storage::{Storage, Topition, TxnAddPartitionsRequest, TxnOffsetCommitRequest};
+
+use crate::{Error, Result};
+
+const TRANSACTION_TIMEOUT_MS: i32 = 30_000;
+
+/// The murmur2 hash used by the default Kafka partitioner
+fn murmur2(data: &[u8]) -> i32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut h = SEED ^ data.len() as u32;
+
+    let chunks = data.chunks_exact(4);
+    let remainder = chunks.remainder();
+
+    for chunk in chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    if !remainder.is_empty() {
+        for (i, byte) in remainder.iter().enumerate().rev() {
+            h ^= u32::from(*byte) << (8 * i);
+        }
+
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+
+    h as i32
+}
+
+/// The partition of a record, by the hash of its key, or otherwise its sequence
+pub(crate) fn partition(sequence: i64, key: Option<&[u8]>, partitions: i32) -> i32 {
+    match key {
+        Some(key) => (murmur2(key) & 0x7fff_ffff) % partitions,
+        None => sequence.rem_euclid(i64::from(partitions)) as i32,
+    }
+}
+
+/// The number of partitions of each topic
+pub(crate) async fn topics<S>(storage: &S) -> Result<Vec<(String, i32)>>
+where
+    S: Storage,
+{
+    storage
+        .metadata(None)
+        .await
+        .map(|metadata| {
+            metadata
+                .topics()
+                .iter()
+                .filter_map(|topic| {
+                    topic.name.clone().zip(
+                        topic
+                            .partitions
+                            .as_deref()
+                            .map(|partitions| partitions.len() as i32)
+                            .filter(|partitions| *partitions > 0),
+                    )
+                })
+                .collect()
+        })
+        .map_err(Into::into)
+}
+
+/// The keys and values of records to produce, by partition
+pub(crate) type Records = BTreeMap<i32, Vec<(Option<Bytes>, Option<Bytes>)>>;
+
+/// Produces records into a topic in a transaction that also commits a
+/// checkpoint as the offset of a group on partition 0 of the topic
+#[derive(Debug)]
+pub(crate) struct Checkpointed<'a, S> {
+    storage: &'a S,
+    transaction_id: String,
+    group_id: &'a str,
+    topic: &'a str,
+    producer_id: i64,
+    producer_epoch: i16,
+}
+
+impl<'a, S> Checkpointed<'a, S>
+where
+    S: Storage,
+{
+    /// Fence any earlier producer for this group and topic, returning the
+    /// last committed checkpoint, or -1 if there is none
+    pub(crate) async fn begin(
+        storage: &'a S,
+        group_id: &'a str,
+        topic: &'a str,
+    ) -> Result<(Self, i64)> {
+        let transaction_id = format!("{group_id}.{topic}");
+
+        let producer = storage
+            .init_producer(
+                Some(transaction_id.as_str()),
+                TRANSACTION_TIMEOUT_MS,
+                Some(-1),
+                Some(-1),
+            )
+            .await?;
+
+        if producer.error != ErrorCode::None {
+            return Err(Error::Api(producer.error));
+        }
+
+        let topition = Topition::new(topic, 0);
+
+        let checkpoint = storage
+            .offset_fetch(Some(group_id), slice::from_ref(&topition), Some(true))
+            .await?
+            .get(&topition)
+            .copied()
+            .unwrap_or(-1);
+
+        Ok((
+            Self {
+                storage,
+                transaction_id,
+                group_id,
+                topic,
+                producer_id: producer.id,
+                producer_epoch: producer.epoch,
+            },
+            checkpoint,
+        ))
+    }
+
+    /// Produce the records with the checkpoint, returning the number of records produced
+    pub(crate) async fn commit(self, records: Records, checkpoint: i64) -> Result<u64> {
+        let result = self.produce(records, checkpoint).await;
+
+        let error_code = self
+            .storage
+            .txn_end(
+                &self.transaction_id,
+                self.producer_id,
+                self.producer_epoch,
+                result.is_ok(),
+            )
+            .await?;
+
+        match (result, error_code) {
+            (Ok(produced), ErrorCode::None) => Ok(produced),
+            (Ok(_), error_code) => Err(Error::Api(error_code)),
+            (Err(err), _) => Err(err),
+        }
+    }
+
+    async fn produce(&self, records: Records, checkpoint: i64) -> Result<u64> {
+        if !records.is_empty() {
+            let added = self
+                .storage
+                .txn_add_partitions(TxnAddPartitionsRequest::VersionZeroToThree {
+                    transaction_id: self.transaction_id.clone(),
+                    producer_id: self.producer_id,
+                    producer_epoch: self.producer_epoch,
+                    topics: [AddPartitionsToTxnTopic::default()
+                        .name(self.topic.to_owned())
+                        .partitions(Some(records.keys().copied().collect()))]
+                    .into(),
+                })
+                .await?;
+
+            if let Some(error_code) = added
+                .zero_to_three()
+                .iter()
+                .flat_map(|topic| topic.results_by_partition.as_deref().unwrap_or_default())
+                .map(|partition| ErrorCode::try_from(partition.partition_error_code))
+                .find(|error_code| !matches!(error_code, Ok(ErrorCode::None)))
+            {
+                return Err(Error::Api(error_code?));
+            }
+        }
+
+        let timestamp = to_timestamp(&SystemTime::now())?;
+        let mut produced = 0;
+
+        for (partition, records) in records {
+            let mut batch = inflated::Batch::builder()
+                .attributes(BatchAttribute::default().transaction(true).into())
+                .producer_id(self.producer_id)
+                .producer_epoch(self.producer_epoch)
+                .base_sequence(0)
+                .base_timestamp(timestamp)
+                .max_timestamp(timestamp);
+
+            for (offset_delta, (key, value)) in records.into_iter().enumerate() {
+                let offset_delta = i32::try_from(offset_delta)?;
+
+                batch = batch
+                    .record(
+                        Record::builder()
+                            .offset_delta(offset_delta)
+                            .key(key)
+                            .value(value),
+                    )
+                    .last_offset_delta(offset_delta);
+
+                produced += 1;
+            }
+
+            let batch = batch.build().and_then(TryInto::try_into)?;
+
+            _ = self
+                .storage
+                .produce(
+                    Some(self.transaction_id.as_str()),
+                    &Topition::new(self.topic, partition),
+                    batch,
+                )
+                .await?;
+        }
+
+        let error_code = self
+            .storage
+            .txn_add_offsets(
+                &self.transaction_id,
+                self.producer_id,
+                self.producer_epoch,
+                self.group_id,
+            )
+            .await?;
+
+        if error_code != ErrorCode::None {
+            return Err(Error::Api(error_code));
+        }
+
+        for topic in self
+            .storage
+            .txn_offset_commit(TxnOffsetCommitRequest {
+                transaction_id: self.transaction_id.clone(),
+                group_id: self.group_id.to_owned(),
+                producer_id: self.producer_id,
+                producer_epoch: self.producer_epoch,
+                generation_id: None,
+                member_id: None,
+                group_instance_id: None,
+                topics: vec![
+                    TxnOffsetCommitRequestTopic::default()
+                        .name(self.topic.to_owned())
+                        .partitions(Some(vec![
+                            TxnOffsetCommitRequestPartition::default()
+                                .partition_index(0)
+                                .committed_offset(checkpoint)
+                                .committed_leader_epoch(None)
+                                .committed_metadata(None),
+                        ])),
+                ],
+            })
+            .await?
+        {
+            for partition in topic.partitions.unwrap_or_default() {
+                let error_code = ErrorCode::try_from(partition.error_code)?;
+
+                if error_code != ErrorCode::None {
+                    return Err(Error::Api(error_code));
+                }
+            }
+        }
+
+        Ok(produced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmur2_as_kafka() {
+        assert_eq!(-973_932_308, murmur2(b"21"));
+        assert_eq!(-790_332_482, murmur2(b"foobar"));
+    }
+}
@@ -0,0 +1,501 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Claim check
+//!
+//! A topic with a `tansu.claim.check.url` topic config, e.g.,
+//! `s3://claims/orders/`, offloads each produced record value larger than
+//! `tansu.claim.check.threshold` bytes (1 MiB by default) to an object under
+//! that prefix, using the `AWS_*` environment for credentials and endpoint.
+//! The stored record has an empty value, with a [`CLAIM_CHECK`] header holding
+//! the URL of the object. Offloading happens before the `max.message.bytes` of
+//! the topic is checked, so that a topic may take multi-MB values while its
+//! batches stay small.
+//!
+//! A fetched record with a [`CLAIM_CHECK`] header has its value resolved from
+//! the object, with the header removed. Only the batches of a topic with a
+//! claim check are inflated on fetch, leaving the batch path of other topics
+//! unchanged. A fetch is authorized before reaching this layer, so an object
+//! is only read for a consumer that may read its topic.
+//!
+//! Object storage requires the `dynostore` feature. Objects are not removed
+//! when their records are deleted by retention.
+
+use std::{collections::BTreeMap, mem};
+
+use bytes::Bytes;
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    BatchAttribute, FetchRequest, FetchResponse, ProduceRequest,
+    fetch_response::FetchableTopicResponse,
+    record::{self, Header, Record, deflated, inflated},
+};
+use tansu_storage::{Storage, TopicId};
+use tracing::{debug, instrument, warn};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{Error, Result, broker::topic_config::TopicConfig};
+
+pub const CLAIM_CHECK_URL: &str = "tansu.claim.check.url";
+pub const CLAIM_CHECK_THRESHOLD: &str = "tansu.claim.check.threshold";
+
+/// The header of a record holding the URL of its offloaded value
+pub const CLAIM_CHECK: &str = "tansu.claim.check";
+
+/// Values larger than this many bytes are offloaded, unless configured otherwise
+const DEFAULT_THRESHOLD: usize = 1_048_576;
+
+/// The claim check of a topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ClaimCheck {
+    url: Url,
+    threshold: usize,
+}
+
+impl ClaimCheck {
+    /// The claim check for a topic from its configuration, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        TopicConfig::describe(storage, topic)
+            .await
+            .map_err(Into::into)
+            .and_then(|config| Self::from_config(&config))
+    }
+
+    /// The claim check of a topic from its configuration, if any
+    pub fn from_config(config: &TopicConfig) -> Result<Option<Self>> {
+        config
+            .get(CLAIM_CHECK_URL)
+            .map(|url| {
+                // a prefix, so that objects are created beneath it
+                let mut url = Url::parse(url)?;
+
+                if url.scheme() != "s3" {
+                    return Err(Error::Message(format!("unsupported claim check: {url}")));
+                }
+
+                if !url.path().ends_with('/') {
+                    url.set_path(&format!("{}/", url.path()));
+                }
+
+                configs
+                    .get(CLAIM_CHECK_THRESHOLD)
+                    .map_or(Ok(DEFAULT_THRESHOLD), |threshold| {
+                        threshold.parse().map_err(|_| {
+                            Error::Message(format!("invalid claim check threshold: {threshold}"))
+                        })
+                    })
+                    .map(|threshold| Self { url, threshold })
+            })
+            .transpose()
+    }
+
+    /// The URL of a new object for a value offloaded from a topition
+    fn location(&self, topic: &str, partition: i32) -> Result<Url> {
+        self.url
+            .join(&format!("{topic}/{partition}/{}", Uuid::now_v7()))
+            .map_err(Into::into)
+    }
+
+    fn is_offloaded(&self, record: &Record) -> bool {
+        record
+            .value
+            .as_ref()
+            .is_some_and(|value| value.len() > self.threshold)
+    }
+
+    /// A batch with values larger than the threshold offloaded, or `None`
+    /// when there are none
+    async fn offload(
+        &self,
+        stores: &Stores,
+        topic: &str,
+        partition: i32,
+        batch: deflated::Batch,
+    ) -> Result<Option<deflated::Batch>> {
+        if BatchAttribute::try_from(batch.attributes)?.control {
+            return Ok(None);
+        }
+
+        let mut inflated = inflated::Batch::try_from(batch)?;
+
+        if !inflated
+            .records
+            .iter()
+            .any(|record| self.is_offloaded(record))
+        {
+            return Ok(None);
+        }
+
+        let records = mem::take(&mut inflated.records);
+        let mut builder = inflated::Builder::from(inflated);
+
+        for record in records {
+            let Some(value) = record.value.clone().filter(|_| self.is_offloaded(&record)) else {
+                builder = builder.record(record::Builder::from(record));
+                continue;
+            };
+
+            let location = self.location(topic, partition)?;
+            debug!(%location, len = value.len());
+
+            stores.put(&location, value).await?;
+
+            builder = builder.record(
+                record::Builder::from(record)
+                    .value(Some(Bytes::new()))
+                    .header(
+                        Header::builder()
+                            .key(Bytes::from_static(CLAIM_CHECK.as_bytes()))
+                            .value(Bytes::from(location.to_string())),
+                    ),
+            );
+        }
+
+        builder
+            .build()
+            .and_then(deflated::Batch::try_from)
+            .map(Some)
+            .map_err(Into::into)
+    }
+}
+
+fn is_claim_check(header: &Header) -> bool {
+    header
+        .key
+        .as_deref()
+        .is_some_and(|key| key == CLAIM_CHECK.as_bytes())
+}
+
+/// The URL of the offloaded value of a record, if any
+fn claim_check(record: &Record) -> Option<Url> {
+    record
+        .headers
+        .iter()
+        .find(|header| is_claim_check(header))
+        .and_then(|header| header.value.as_deref())
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| Url::parse(value).ok())
+}
+
+/// A batch with its offloaded values resolved, or `None` when there are none
+async fn resolve(stores: &Stores, batch: deflated::Batch) -> Result<Option<deflated::Batch>> {
+    if BatchAttribute::try_from(batch.attributes)?.control {
+        return Ok(None);
+    }
+
+    let mut inflated = inflated::Batch::try_from(batch)?;
+
+    if !inflated
+        .records
+        .iter()
+        .any(|record| claim_check(record).is_some())
+    {
+        return Ok(None);
+    }
+
+    let records = mem::take(&mut inflated.records);
+    let mut builder = inflated::Builder::from(inflated);
+
+    for mut record in records {
+        let Some(location) = claim_check(&record) else {
+            builder = builder.record(record::Builder::from(record));
+            continue;
+        };
+
+        let value = stores.get(&location).await?;
+        debug!(%location, len = value.len());
+
+        record.headers.retain(|header| !is_claim_check(header));
+        builder = builder.record(record::Builder::from(record).value(Some(value)));
+    }
+
+    builder
+        .build()
+        .and_then(deflated::Batch::try_from)
+        .map(Some)
+        .map_err(Into::into)
+}
+
+/// The object stores of claim checks, by bucket
+#[cfg(feature = "dynostore")]
+#[derive(Clone, Debug, Default)]
+struct Stores(
+    std::sync::Arc<
+        std::sync::Mutex<BTreeMap<String, std::sync::Arc<object_store::DynObjectStore>>>,
+    >,
+);
+
+#[cfg(feature = "dynostore")]
+impl Stores {
+    fn store(&self, location: &Url) -> Result<std::sync::Arc<object_store::DynObjectStore>> {
+        let bucket = location.host_str().unwrap_or_default();
+        let mut stores = self.0.lock()?;
+
+        if let Some(store) = stores.get(bucket) {
+            return Ok(store.clone());
+        }
+
+        let store = object_store::aws::AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()
+            .map(|store| {
+                std::sync::Arc::new(store) as std::sync::Arc<object_store::DynObjectStore>
+            })?;
+
+        _ = stores.insert(bucket.to_owned(), store.clone());
+
+        Ok(store)
+    }
+
+    fn path(location: &Url) -> object_store::path::Path {
+        object_store::path::Path::from(location.path().trim_start_matches('/'))
+    }
+
+    async fn put(&self, location: &Url, value: Bytes) -> Result<()> {
+        self.store(location)?
+            .put(&Self::path(location), value.into())
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    async fn get(&self, location: &Url) -> Result<Bytes> {
+        self.store(location)?
+            .get(&Self::path(location))
+            .await?
+            .bytes()
+            .await
+            .map_err(Into::into)
+    }
+}
+
+/// Without object storage, a claim check can be neither offloaded nor resolved
+#[cfg(not(feature = "dynostore"))]
+#[derive(Clone, Copy, Debug, Default)]
+struct Stores;
+
+#[cfg(not(feature = "dynostore"))]
+impl Stores {
+    async fn put(&self, location: &Url, _value: Bytes) -> Result<()> {
+        Err(Error::Message(format!(
+            "claim check requires the dynostore feature: {location}"
+        )))
+    }
+
+    async fn get(&self, location: &Url) -> Result<Bytes> {
+        Err(Error::Message(format!(
+            "claim check requires the dynostore feature: {location}"
+        )))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ClaimCheckLayer {
+    stores: Stores,
+}
+
+impl<S> Layer<S> for ClaimCheckLayer {
+    type Service = ClaimCheckService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClaimCheckService {
+            stores: self.stores.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] offloading the large values of produced records, and
+/// resolving them for fetched records, of topics with a claim check
+#[derive(Clone, Debug)]
+pub struct ClaimCheckService<S> {
+    stores: Stores,
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for ClaimCheckService<S>
+where
+    S: Service<State, ProduceRequest>,
+    State: Storage,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        for topic in req.topic_data.as_deref_mut().unwrap_or_default() {
+            let Some(claim_check) = TopicConfig::lookup(&ctx, topic.name.as_str())
+                .await
+                .map_err(Into::into)
+                .and_then(|config| ClaimCheck::from_config(&config))
+                .inspect_err(|err| warn!(topic = topic.name, ?err))
+                .ok()
+                .flatten()
+            else {
+                continue;
+            };
+
+            for partition in topic.partition_data.as_deref_mut().unwrap_or_default() {
+                let Some(frame) = partition.records.as_mut() else {
+                    continue;
+                };
+
+                for batch in frame.batches.iter_mut() {
+                    match claim_check
+                        .offload(
+                            &self.stores,
+                            topic.name.as_str(),
+                            partition.index,
+                            batch.clone(),
+                        )
+                        .await
+                    {
+                        Ok(Some(offloaded)) => *batch = offloaded,
+                        Ok(None) => (),
+                        Err(err) => warn!(topic = topic.name, partition = partition.index, ?err),
+                    }
+                }
+            }
+        }
+
+        self.inner.serve(ctx, req).await
+    }
+}
+
+/// The name of a fetched topic, which is only identified by id from v13
+pub(crate) async fn topic_name<G>(storage: &G, topic: &FetchableTopicResponse) -> Option<String>
+where
+    G: Storage,
+{
+    if let Some(name) = topic.topic.clone() {
+        return Some(name);
+    }
+
+    let id = topic.topic_id.map(|id| TopicId::Id(Uuid::from_bytes(id)))?;
+
+    storage
+        .metadata(Some(&[id]))
+        .await
+        .inspect_err(|err| debug!(?err))
+        .ok()
+        .and_then(|metadata| {
+            metadata
+                .topics()
+                .first()
+                .and_then(|topic| topic.name.clone())
+        })
+}
+
+impl<S, State> Service<State, FetchRequest> for ClaimCheckService<S>
+where
+    S: Service<State, FetchRequest, Response = FetchResponse>,
+    State: Storage,
+{
+    type Response = FetchResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: FetchRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let storage = ctx.state().clone();
+        let mut response = self.inner.serve(ctx, req).await?;
+
+        for topic in response.responses.as_deref_mut().unwrap_or_default() {
+            let Some(name) = topic_name(&storage, topic).await else {
+                continue;
+            };
+
+            if ClaimCheck::describe(&storage, name.as_str())
+                .await
+                .inspect_err(|err| warn!(topic = name, ?err))
+                .ok()
+                .flatten()
+                .is_none()
+            {
+                continue;
+            }
+
+            for partition in topic.partitions.as_deref_mut().unwrap_or_default() {
+                let Some(frame) = partition.records.as_mut() else {
+                    continue;
+                };
+
+                for batch in frame.batches.iter_mut() {
+                    match resolve(&self.stores, batch.clone()).await {
+                        Ok(Some(resolved)) => *batch = resolved,
+                        Ok(None) => (),
+                        Err(err) => {
+                            warn!(topic = name, partition = partition.partition_index, ?err)
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claim_check_header() -> Result<()> {
+        let location = "s3://claims/orders/0/0190c7d2-6a4e-7c3f-8e1a-2b4c6d8e0f12";
+
+        let record = Record::builder()
+            .value(Some(Bytes::new()))
+            .header(
+                Header::builder()
+                    .key(Bytes::from_static(CLAIM_CHECK.as_bytes()))
+                    .value(Bytes::from_static(location.as_bytes())),
+            )
+            .build()?;
+
+        assert_eq!(Some(Url::parse(location)?), claim_check(&record));
+        assert!(record.headers.iter().all(is_claim_check));
+
+        let record = Record::builder()
+            .value(Some(Bytes::from_static(b"abc")))
+            .build()?;
+        assert_eq!(None, claim_check(&record));
+
+        let claim_check = ClaimCheck {
+            url: Url::parse("s3://claims/orders/")?,
+            threshold: 2,
+        };
+
+        assert!(claim_check.is_offloaded(&record));
+        assert!(
+            claim_check
+                .location("orders", 3)?
+                .as_str()
+                .starts_with("s3://claims/orders/orders/3/")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Log compaction
+//!
+//! As with the log cleaner of Kafka, the records of a topic with a `compact`
+//! cleanup policy are compacted, retaining only the latest record of each
+//! key. A topition is compacted once the proportion of its records written
+//! since it was last compacted reaches `min.cleanable.dirty.ratio` (0.5 by
+//! default). Records written within `segment.ms` (7 days by default) are in
+//! the active segment, and are not compacted.
+//!
+//! Storage compacting during its own maintenance (PostgreSQL and SQLite)
+//! removes nothing here. Storage without compaction stops the compactor with
+//! a warning rather than compacting again.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use tansu_sans_io::{ConfigResource, IsolationLevel, ListOffset};
+use tansu_storage::{Storage, Topition};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    Error, Result,
+    clock::{Clock, SystemClock},
+};
+
+const CLEANUP_POLICY: &str = "cleanup.policy";
+const MIN_CLEANABLE_DIRTY_RATIO: &str = "min.cleanable.dirty.ratio";
+const SEGMENT_MS: &str = "segment.ms";
+
+const DEFAULT_MIN_CLEANABLE_DIRTY_RATIO: f64 = 0.5;
+const DEFAULT_SEGMENT: Duration = Duration::from_millis(604_800_000);
+
+/// The compaction configuration of a topic
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+struct Policy {
+    min_cleanable_dirty_ratio: f64,
+    segment: Duration,
+}
+
+impl Policy {
+    /// The compaction policy of a topic, or `None` when it is not compacted
+    async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        let configs = storage
+            .describe_config(
+                topic,
+                ConfigResource::Topic,
+                Some(&[
+                    CLEANUP_POLICY.to_owned(),
+                    MIN_CLEANABLE_DIRTY_RATIO.to_owned(),
+                    SEGMENT_MS.to_owned(),
+                ]),
+            )
+            .await?
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|config| config.value.map(|value| (config.name, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        if !configs
+            .get(CLEANUP_POLICY)
+            .is_some_and(|policy| policy.contains("compact"))
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            min_cleanable_dirty_ratio: configs
+                .get(MIN_CLEANABLE_DIRTY_RATIO)
+                .and_then(|ratio| ratio.parse().ok())
+                .unwrap_or(DEFAULT_MIN_CLEANABLE_DIRTY_RATIO),
+            segment: configs
+                .get(SEGMENT_MS)
+                .and_then(|segment_ms| segment_ms.parse().ok())
+                .map_or(DEFAULT_SEGMENT, Duration::from_millis),
+        }))
+    }
+}
+
+/// Whether the records between the log start and end offset are dirty enough
+/// to compact, given the offset below which they were last compacted
+fn is_cleanable(log_start: i64, cleaned: i64, end: i64, min_cleanable_dirty_ratio: f64) -> bool {
+    let cleaned = cleaned.max(log_start);
+
+    if end <= cleaned {
+        return false;
+    }
+
+    let dirty = (end - cleaned) as f64;
+    let total = (end - log_start) as f64;
+
+    dirty / total >= min_cleanable_dirty_ratio
+}
+
+/// Compacts the topitions of compacted topics
+#[derive(Clone, Debug)]
+pub struct Compactor<S> {
+    storage: S,
+    interval: Duration,
+    clock: Arc<dyn Clock>,
+    cleaned: Arc<Mutex<BTreeMap<Topition, i64>>>,
+    cancellation: CancellationToken,
+}
+
+impl<S> Compactor<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            clock: Arc::new(SystemClock),
+            cleaned: Arc::new(Mutex::new(BTreeMap::new())),
+            cancellation,
+        }
+    }
+
+    /// Read the time from this clock, rather than the system clock
+    pub fn clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub async fn serve(self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match self.compact().await {
+                        Ok(removed) => debug!(removed),
+
+                        Err(Error::Storage(tansu_storage::Error::Unsupported(operation))) => {
+                            warn!(operation, "compaction is not supported by this storage");
+                            break;
+                        }
+
+                        Err(err) => warn!(?err),
+                    }
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compact each topition of a compacted topic that is dirty enough,
+    /// returning the number of records removed
+    #[instrument(skip(self))]
+    pub async fn compact(&self) -> Result<u64> {
+        let mut removed = 0;
+
+        for topic in self.storage.metadata(None).await?.topics() {
+            let (Some(name), Some(partitions)) =
+                (topic.name.as_deref(), topic.partitions.as_deref())
+            else {
+                continue;
+            };
+
+            let Some(policy) = Policy::describe(&self.storage, name).await? else {
+                continue;
+            };
+
+            for partition in partitions {
+                let topition = Topition::new(name, partition.partition_index);
+
+                match self.topition(&topition, policy).await {
+                    Ok(compacted) => removed += compacted,
+
+                    Err(err @ Error::Storage(tansu_storage::Error::Unsupported(_))) => {
+                        return Err(err);
+                    }
+
+                    Err(err) => warn!(?topition, ?err),
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    async fn topition(&self, topition: &Topition, policy: Policy) -> Result<u64> {
+        let stage = self.storage.offset_stage(topition).await?;
+
+        // the first record of the active segment, or the last stable offset
+        let active = self
+            .clock
+            .now()
+            .checked_sub(policy.segment)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let end = self
+            .storage
+            .list_offsets(
+                IsolationLevel::ReadUncommitted,
+                &[(topition.clone(), ListOffset::Timestamp(active))],
+            )
+            .await?
+            .first()
+            .and_then(|(_, response)| response.offset())
+            .map_or(stage.last_stable(), |offset| {
+                offset.min(stage.last_stable())
+            });
+
+        let cleaned = self
+            .cleaned
+            .lock()?
+            .get(topition)
+            .copied()
+            .unwrap_or(stage.log_start());
+
+        if !is_cleanable(
+            stage.log_start(),
+            cleaned,
+            end,
+            policy.min_cleanable_dirty_ratio,
+        ) {
+            debug!(?topition, log_start = stage.log_start(), cleaned, end);
+            return Ok(0);
+        }
+
+        let removed = self.storage.compact(topition, end).await?;
+
+        if removed > 0 {
+            info!(?topition, end, removed);
+        }
+
+        _ = self.cleaned.lock()?.insert(topition.clone(), end);
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleanable() {
+        // never compacted, every record is dirty
+        assert!(is_cleanable(0, 0, 10, 0.5));
+
+        // half of the records were written since the last compaction
+        assert!(is_cleanable(0, 5, 10, 0.5));
+        assert!(!is_cleanable(0, 6, 10, 0.5));
+
+        // nothing new since the last compaction
+        assert!(!is_cleanable(0, 10, 10, 0.0));
+
+        // records below the log start have since been deleted
+        assert!(is_cleanable(8, 4, 10, 1.0));
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feature flags
+//!
+//! The finalized level of each feature (KIP-584), maintained with
+//! `UpdateFeatures`, gates the experimental protocols of a cluster. With
+//! `group.version` at 0 the consumer group protocol of KIP-848 is disabled,
+//! while with `share.version` at 0 share groups are disabled. Both are
+//! enabled by default.
+//!
+//! `ApiVersions` responses include the supported and finalized features,
+//! omitting the API keys of a disabled feature. A request for a disabled API
+//! key is rejected with `UnsupportedVersion`.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    ApiKey, ApiVersionsRequest, ApiVersionsResponse, Body, ConsumerGroupHeartbeatRequest,
+    ConsumerGroupHeartbeatResponse, ErrorCode, Frame, Header, ShareAcknowledgeRequest,
+    ShareAcknowledgeResponse, ShareFetchRequest, ShareFetchResponse, ShareGroupHeartbeatRequest,
+    ShareGroupHeartbeatResponse, UpdateFeaturesRequest,
+    api_versions_response::{FinalizedFeatureKey, SupportedFeatureKey},
+};
+use tansu_storage::{FinalizedFeatures, Storage};
+use tracing::{debug, instrument, warn};
+
+use crate::{Error, Result};
+
+/// How long finalized features are cached before being reloaded from storage
+const FEATURES_TTL: Duration = Duration::from_secs(5);
+
+/// The API keys requiring a feature to be enabled
+const GATED: [(i16, &str); 4] = [
+    (
+        ConsumerGroupHeartbeatRequest::KEY,
+        FinalizedFeatures::GROUP_VERSION,
+    ),
+    (
+        ShareGroupHeartbeatRequest::KEY,
+        FinalizedFeatures::SHARE_VERSION,
+    ),
+    (ShareFetchRequest::KEY, FinalizedFeatures::SHARE_VERSION),
+    (
+        ShareAcknowledgeRequest::KEY,
+        FinalizedFeatures::SHARE_VERSION,
+    ),
+];
+
+type Cache = Arc<Mutex<Option<(Instant, FinalizedFeatures)>>>;
+
+/// Whether an API key is disabled by its feature
+fn disabled(features: &FinalizedFeatures, api_key: i16) -> bool {
+    GATED
+        .iter()
+        .any(|(gated, feature)| *gated == api_key && features.level(feature) < 1)
+}
+
+/// Include the supported and finalized features, omitting disabled API keys
+fn api_versions(features: &FinalizedFeatures, response: &mut ApiVersionsResponse) {
+    if let Some(api_keys) = response.api_keys.as_mut() {
+        api_keys.retain(|api_version| !disabled(features, api_version.api_key));
+    }
+
+    response.supported_features = Some(
+        FinalizedFeatures::SUPPORTED
+            .into_iter()
+            .map(|supported| {
+                SupportedFeatureKey::default()
+                    .name(supported.name.into())
+                    .min_version(supported.min_level)
+                    .max_version(supported.max_level)
+            })
+            .collect(),
+    );
+
+    response.finalized_features = Some(
+        features
+            .finalized()
+            .into_iter()
+            .filter(|(_, level)| *level > 0)
+            .map(|(name, level)| {
+                FinalizedFeatureKey::default()
+                    .name(name)
+                    .max_version_level(level)
+                    .min_version_level(level)
+            })
+            .collect(),
+    );
+
+    response.finalized_features_epoch = Some(features.epoch);
+}
+
+/// The response to a request for an API key disabled by its feature
+fn unsupported(body: Body) -> Option<Body> {
+    let message = |feature: &str| Some(format!("{feature} is disabled"));
+
+    match body {
+        Body::ConsumerGroupHeartbeatRequest(heartbeat) => Some(
+            ConsumerGroupHeartbeatResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::UnsupportedVersion.into())
+                .error_message(message(FinalizedFeatures::GROUP_VERSION))
+                .member_id(Some(heartbeat.member_id))
+                .member_epoch(heartbeat.member_epoch)
+                .heartbeat_interval_ms(0)
+                .assignment(None)
+                .into(),
+        ),
+
+        Body::ShareGroupHeartbeatRequest(heartbeat) => Some(
+            ShareGroupHeartbeatResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::UnsupportedVersion.into())
+                .error_message(message(FinalizedFeatures::SHARE_VERSION))
+                .member_id(Some(heartbeat.member_id))
+                .member_epoch(heartbeat.member_epoch)
+                .heartbeat_interval_ms(0)
+                .assignment(None)
+                .into(),
+        ),
+
+        Body::ShareFetchRequest(_) => Some(
+            ShareFetchResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::UnsupportedVersion.into())
+                .error_message(message(FinalizedFeatures::SHARE_VERSION))
+                .responses(Some([].into()))
+                .node_endpoints(Some([].into()))
+                .into(),
+        ),
+
+        Body::ShareAcknowledgeRequest(_) => Some(
+            ShareAcknowledgeResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::UnsupportedVersion.into())
+                .error_message(message(FinalizedFeatures::SHARE_VERSION))
+                .responses(Some([].into()))
+                .node_endpoints(Some([].into()))
+                .into(),
+        ),
+
+        _otherwise => None,
+    }
+}
+
+/// A [`Layer`] gating API keys by the finalized features held in storage
+#[derive(Clone, Debug)]
+pub struct FeatureLayer<G> {
+    storage: G,
+    cache: Cache,
+}
+
+impl<G> FeatureLayer<G> {
+    pub fn new(storage: G) -> Self {
+        Self {
+            storage,
+            cache: Cache::default(),
+        }
+    }
+}
+
+impl<G, S> Layer<S> for FeatureLayer<G>
+where
+    G: Clone,
+{
+    type Service = FeatureService<G, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FeatureService {
+            storage: self.storage.clone(),
+            cache: self.cache.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] reporting finalized features in [`ApiVersionsResponse`], and
+/// rejecting requests for API keys of a disabled feature
+#[derive(Clone, Debug)]
+pub struct FeatureService<G, S> {
+    storage: G,
+    cache: Cache,
+    inner: S,
+}
+
+impl<G, S> FeatureService<G, S>
+where
+    G: Storage,
+{
+    fn cached(&self, now: Instant) -> Option<FinalizedFeatures> {
+        self.cache.lock().ok().and_then(|guard| {
+            guard
+                .as_ref()
+                .filter(|(loaded, _)| now.saturating_duration_since(*loaded) < FEATURES_TTL)
+                .map(|(_, features)| features.clone())
+        })
+    }
+
+    async fn features(&self) -> Result<FinalizedFeatures> {
+        let now = Instant::now();
+
+        if let Some(features) = self.cached(now) {
+            return Ok(features);
+        }
+
+        let features = self.storage.finalized_features().await?;
+
+        if let Ok(mut guard) = self.cache.lock() {
+            *guard = Some((now, features.clone()));
+        }
+
+        Ok(features)
+    }
+
+    fn features_changed(&self) {
+        if let Ok(mut guard) = self.cache.lock() {
+            *guard = None;
+        }
+    }
+}
+
+impl<G, S, State> Service<State, Frame> for FeatureService<G, S>
+where
+    G: Storage,
+    S: Service<State, Frame, Response = Frame, Error = Error>,
+    State: Send + Sync + 'static,
+{
+    type Response = Frame;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(&self, ctx: Context<State>, req: Frame) -> Result<Self::Response, Self::Error> {
+        let api_key = req.api_key()?;
+
+        if api_key == UpdateFeaturesRequest::KEY {
+            return self
+                .inner
+                .serve(ctx, req)
+                .await
+                .inspect(|_| self.features_changed());
+        }
+
+        if api_key != ApiVersionsRequest::KEY && GATED.iter().all(|(gated, _)| *gated != api_key) {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        // features are unavailable: everything is enabled as before
+        let Ok(features) = self.features().await.inspect_err(|err| warn!(?err)) else {
+            return self.inner.serve(ctx, req).await;
+        };
+
+        if disabled(&features, api_key) {
+            let correlation_id = req.correlation_id()?;
+
+            if let Some(body) = unsupported(req.body) {
+                debug!(api_key, "disabled");
+
+                return Ok(Frame {
+                    size: 0,
+                    header: Header::Response { correlation_id },
+                    body,
+                });
+            }
+
+            return Err(Error::Api(ErrorCode::UnsupportedVersion));
+        }
+
+        let mut response = self.inner.serve(ctx, req).await?;
+
+        if let Body::ApiVersionsResponse(api_versions) = &mut response.body {
+            self::api_versions(&features, api_versions);
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tansu_sans_io::api_versions_response::ApiVersion;
+
+    use super::*;
+
+    #[test]
+    fn disabled_feature_omits_api_keys() {
+        let mut features = FinalizedFeatures::default();
+        features.update(FinalizedFeatures::SHARE_VERSION, 0);
+
+        let mut response = ApiVersionsResponse::default().api_keys(Some(
+            [
+                ConsumerGroupHeartbeatRequest::KEY,
+                ShareGroupHeartbeatRequest::KEY,
+                ShareFetchRequest::KEY,
+                ShareAcknowledgeRequest::KEY,
+            ]
+            .into_iter()
+            .map(|api_key| ApiVersion::default().api_key(api_key))
+            .collect(),
+        ));
+
+        api_versions(&features, &mut response);
+
+        assert_eq!(
+            vec![ConsumerGroupHeartbeatRequest::KEY],
+            response
+                .api_keys
+                .unwrap_or_default()
+                .into_iter()
+                .map(|api_version| api_version.api_key)
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(Some(1), response.finalized_features_epoch);
+        assert_eq!(
+            Some(vec![
+                FinalizedFeatureKey::default()
+                    .name(FinalizedFeatures::GROUP_VERSION.into())
+                    .max_version_level(1)
+                    .min_version_level(1)
+            ]),
+            response.finalized_features
+        );
+        assert_eq!(2, response.supported_features.unwrap_or_default().len());
+    }
+
+    #[test]
+    fn enabled_by_default() {
+        let features = FinalizedFeatures::default();
+
+        assert!(
+            GATED
+                .iter()
+                .all(|(api_key, _)| !disabled(&features, *api_key))
+        );
+    }
+}
@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod consumer_group_heartbeat;
 pub mod heartbeat;
 pub mod join;
 pub mod leave;
 pub mod offset_commit;
 pub mod offset_fetch;
+pub mod share_acknowledge;
+pub mod share_fetch;
+pub mod share_group_heartbeat;
 pub mod sync;
@@ -0,0 +1,61 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{Context, Service};
+use tansu_sans_io::{ApiKey, Frame, Header, ShareFetchRequest};
+use tracing::instrument;
+
+use crate::{
+    Error, Result,
+    coordinator::group::{Coordinator, ShareFetch},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ShareFetchService;
+
+impl ApiKey for ShareFetchService {
+    const KEY: i16 = ShareFetchRequest::KEY;
+}
+
+impl<C> Service<C, Frame> for ShareFetchService
+where
+    C: Coordinator,
+{
+    type Response = Frame;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(&self, mut ctx: Context<C>, req: Frame) -> Result<Self::Response, Self::Error> {
+        let correlation_id = req.correlation_id()?;
+        let coordinator = ctx.state_mut();
+
+        let fetch = ShareFetchRequest::try_from(req.body)?;
+
+        coordinator
+            .share_fetch(ShareFetch {
+                group_id: fetch.group_id.as_deref().unwrap_or_default(),
+                member_id: fetch.member_id.as_deref().unwrap_or_default(),
+                share_session_epoch: fetch.share_session_epoch,
+                max_bytes: fetch.max_bytes,
+                topics: fetch.topics.as_deref(),
+                forgotten_topics_data: fetch.forgotten_topics_data.as_deref(),
+            })
+            .await
+            .map(|body| Frame {
+                size: 0,
+                header: Header::Response { correlation_id },
+                body,
+            })
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! End-to-end latency
+//!
+//! A topic with `tansu.latency.timestamp` enabled has each produced record
+//! stamped with a [`RECEIVED_AT`] header, the time at which the broker
+//! received the record. Consumers compute their end-to-end latency with
+//! [`Latencies`](tansu_client::latency::Latencies).
+//!
+//! When the broker is the consumer's gateway, the latency of each fetched
+//! record that has the header is recorded off the fetch path in the
+//! `tansu_end_to_end_latency` histogram.
+
+use std::{
+    mem,
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use opentelemetry::{KeyValue, metrics::Histogram};
+use rama::{Context, Layer, Service};
+use tansu_client::latency::{RECEIVED_AT, end_to_end};
+use tansu_sans_io::{
+    BatchAttribute, ConfigResource, FetchRequest, FetchResponse, ProduceRequest, ProduceResponse,
+    record::{self, Header, deflated, inflated},
+    to_timestamp,
+};
+use tansu_storage::{Storage, TopicId};
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+use crate::METER;
+
+pub const LATENCY_TIMESTAMP: &str = "tansu.latency.timestamp";
+
+static END_TO_END_LATENCY: LazyLock<Histogram<u64>> = LazyLock::new(|| {
+    METER
+        .u64_histogram("tansu_end_to_end_latency")
+        .with_unit("ms")
+        .with_description(
+            "The time between the broker receiving a record and fetching it in milliseconds",
+        )
+        .build()
+});
+
+/// Stamp each record of a batch with the time it was received, control
+/// batches are left unchanged
+pub fn stamp(batch: deflated::Batch, received_at: i64) -> tansu_sans_io::Result<deflated::Batch> {
+    if BatchAttribute::try_from(batch.attributes)?.control {
+        return Ok(batch);
+    }
+
+    let mut inflated = inflated::Batch::try_from(batch)?;
+    let records = mem::take(&mut inflated.records);
+    let value = Bytes::copy_from_slice(&received_at.to_be_bytes());
+
+    records
+        .into_iter()
+        .fold(inflated::Builder::from(inflated), |builder, record| {
+            builder.record(
+                record::Builder::from(record).header(
+                    Header::builder()
+                        .key(Bytes::from_static(RECEIVED_AT.as_bytes()))
+                        .value(value.clone()),
+                ),
+            )
+        })
+        .build()
+        .and_then(deflated::Batch::try_from)
+}
+
+/// The end-to-end latency of each stamped record in a batch
+fn latencies(batch: deflated::Batch, now: SystemTime) -> tansu_sans_io::Result<Vec<Duration>> {
+    if BatchAttribute::try_from(batch.attributes)?.control {
+        return Ok(vec![]);
+    }
+
+    inflated::Batch::try_from(batch).map(|inflated| {
+        inflated
+            .records
+            .iter()
+            .filter_map(|record| end_to_end(record, now))
+            .collect()
+    })
+}
+
+async fn is_stamped<State>(storage: &State, topic: &str) -> bool
+where
+    State: Storage,
+{
+    storage
+        .describe_config(
+            topic,
+            ConfigResource::Topic,
+            Some(&[LATENCY_TIMESTAMP.to_owned()]),
+        )
+        .await
+        .inspect_err(|err| debug!(topic, ?err))
+        .ok()
+        .and_then(|result| result.configs)
+        .unwrap_or_default()
+        .into_iter()
+        .any(|config| {
+            config.name == LATENCY_TIMESTAMP
+                && config
+                    .value
+                    .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+        })
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReceivedAtLayer;
+
+impl<S> Layer<S> for ReceivedAtLayer {
+    type Service = ReceivedAtService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReceivedAtService { inner }
+    }
+}
+
+/// A [`Service`] stamping the records produced to a topic with
+/// `tansu.latency.timestamp` enabled with the time they were received
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReceivedAtService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for ReceivedAtService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let received_at = to_timestamp(&SystemTime::now()).unwrap_or_default();
+
+        for topic in req.topic_data.as_deref_mut().unwrap_or_default() {
+            if !is_stamped(ctx.state(), topic.name.as_str()).await {
+                continue;
+            }
+
+            for partition in topic.partition_data.as_deref_mut().unwrap_or_default() {
+                let Some(frame) = partition.records.as_mut() else {
+                    continue;
+                };
+
+                for batch in frame.batches.iter_mut() {
+                    match stamp(batch.clone(), received_at) {
+                        Ok(deflated) => *batch = deflated,
+                        Err(err) => warn!(topic = topic.name, partition = partition.index, ?err),
+                    }
+                }
+            }
+        }
+
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FetchLatencyLayer;
+
+impl<S> Layer<S> for FetchLatencyLayer {
+    type Service = FetchLatencyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FetchLatencyService { inner }
+    }
+}
+
+/// A [`Service`] recording the end-to-end latency of the stamped records
+/// returned by the inner fetch service
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FetchLatencyService<S> {
+    inner: S,
+}
+
+/// Resolve the topic name and record the latency of its stamped records
+async fn record<G>(storage: &G, topic: TopicId, batches: Vec<deflated::Batch>, now: SystemTime)
+where
+    G: Storage,
+{
+    let name = match topic {
+        TopicId::Name(name) => name,
+
+        id @ TopicId::Id(_) => match storage.metadata(Some(&[id])).await.map(|metadata| {
+            metadata
+                .topics()
+                .first()
+                .and_then(|topic| topic.name.clone())
+        }) {
+            Ok(Some(name)) => name,
+
+            otherwise => {
+                debug!(?otherwise);
+                return;
+            }
+        },
+    };
+
+    if !is_stamped(storage, name.as_str()).await {
+        return;
+    }
+
+    let attributes = [KeyValue::new("topic", name.clone())];
+
+    for batch in batches {
+        match latencies(batch, now) {
+            Ok(latencies) => {
+                for latency in latencies {
+                    END_TO_END_LATENCY.record(
+                        u64::try_from(latency.as_millis()).unwrap_or(u64::MAX),
+                        &attributes,
+                    );
+                }
+            }
+
+            Err(err) => warn!(topic = name, ?err),
+        }
+    }
+}
+
+impl<S, State> Service<State, FetchRequest> for FetchLatencyService<S>
+where
+    S: Service<State, FetchRequest, Response = FetchResponse>,
+    State: Storage,
+{
+    type Response = FetchResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: FetchRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let storage = ctx.state().clone();
+
+        self.inner.serve(ctx, req).await.inspect(|response| {
+            let now = SystemTime::now();
+
+            let fetched = response
+                .responses
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|topic| {
+                    let batches = topic
+                        .partitions
+                        .as_deref()
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|partition| partition.records.as_ref())
+                        .flat_map(|frame| frame.batches.iter().cloned())
+                        .collect::<Vec<_>>();
+
+                    topic
+                        .topic
+                        .clone()
+                        .map(TopicId::Name)
+                        .or(topic.topic_id.map(|id| TopicId::Id(Uuid::from_bytes(id))))
+                        .filter(|_| !batches.is_empty())
+                        .map(|id| (id, batches))
+                })
+                .collect::<Vec<_>>();
+
+            if fetched.is_empty() {
+                return;
+            }
+
+            _ = tokio::spawn(async move {
+                for (id, batches) in fetched {
+                    record(&storage, id, batches, now).await
+                }
+            });
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tansu_client::latency::received_at;
+
+    use super::*;
+
+    #[test]
+    fn stamped_with_received_at() -> tansu_sans_io::Result<()> {
+        let batch = inflated::Batch::builder()
+            .record(record::Record::builder().value(Some(Bytes::from_static(b"abc"))))
+            .record(
+                record::Record::builder()
+                    .offset_delta(1)
+                    .value(Some(Bytes::from_static(b"pqr"))),
+            )
+            .last_offset_delta(1)
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        let stamped = stamp(batch, 1_000)?;
+
+        assert_eq!(
+            vec![
+                Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1)),
+                Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            ],
+            inflated::Batch::try_from(stamped.clone())?
+                .records
+                .iter()
+                .map(received_at)
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![Duration::from_millis(500), Duration::from_millis(500)],
+            latencies(
+                stamped,
+                SystemTime::UNIX_EPOCH + Duration::from_millis(1_500)
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn control_unchanged() -> tansu_sans_io::Result<()> {
+        let batch = inflated::Batch::builder()
+            .record(record::Record::builder().value(Some(Bytes::from_static(b"abc"))))
+            .attributes(BatchAttribute::default().control(true).into())
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        assert_eq!(batch.clone(), stamp(batch.clone(), 1_000)?);
+        assert!(latencies(batch, SystemTime::now())?.is_empty());
+
+        Ok(())
+    }
+}
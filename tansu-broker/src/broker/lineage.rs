@@ -0,0 +1,346 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record lineage
+//!
+//! Each record produced to a topic with `tansu.lineage` enabled that has no
+//! [`CORRELATION_ID`] header is given one:
+//!
+//! - a record with a [`CAUSATION_ID`](tansu_client::lineage::CAUSATION_ID)
+//!   header inherits the correlation id of the record that caused it
+//! - otherwise the record is an original, with a newly generated correlation id
+//!
+//! Records that already have a correlation id are unchanged. The lineage of a
+//! record is traced by [`trace`], following the causation ids back to the
+//! original record, across any topics produced through tansu.
+
+use std::{collections::BTreeSet, mem};
+
+use bytes::Bytes;
+use rama::{Context, Layer, Service};
+use serde::{Deserialize, Serialize};
+use tansu_client::lineage::{CORRELATION_ID, Location, causation_id, correlation_id};
+use tansu_sans_io::{
+    BatchAttribute, IsolationLevel, ProduceRequest, ProduceResponse,
+    record::{self, Header, Record, deflated, inflated},
+};
+use tansu_storage::{Storage, Topition};
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+use crate::{Result, broker::topic_config::TopicConfig};
+
+pub const LINEAGE: &str = "tansu.lineage";
+
+/// The most records followed when tracing a lineage
+const MAX_DEPTH: usize = 64;
+
+/// The bytes fetched when looking up a record
+const FETCH_MAX_BYTES: u32 = 1_048_576;
+
+/// A record in a lineage
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Traced {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub timestamp: i64,
+    pub correlation_id: Option<String>,
+    pub causation_id: Option<String>,
+}
+
+/// The record at a location, with its timestamp
+async fn lookup<S>(storage: &S, location: &Location) -> Result<Option<(i64, Record)>>
+where
+    S: Storage,
+{
+    let topition = Topition::new(location.topic.as_str(), location.partition);
+
+    for batch in storage
+        .fetch(
+            &topition,
+            location.offset,
+            1,
+            FETCH_MAX_BYTES,
+            IsolationLevel::ReadUncommitted,
+        )
+        .await?
+    {
+        let inflated = inflated::Batch::try_from(batch)?;
+
+        if let Some(record) = inflated
+            .records
+            .iter()
+            .find(|record| inflated.base_offset + i64::from(record.offset_delta) == location.offset)
+        {
+            return Ok(Some((
+                inflated.base_timestamp + record.timestamp_delta,
+                record.to_owned(),
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The lineage of a record, from the record to the original that caused it
+pub async fn trace<S>(storage: &S, location: Location) -> Result<Vec<Traced>>
+where
+    S: Storage,
+{
+    let mut traced = vec![];
+    let mut visited = BTreeSet::new();
+    let mut next = Some(location);
+
+    while let Some(location) = next.take() {
+        if traced.len() >= MAX_DEPTH || !visited.insert(location.clone()) {
+            break;
+        }
+
+        let Some((timestamp, record)) = lookup(storage, &location).await? else {
+            debug!(%location);
+            break;
+        };
+
+        next = causation_id(&record);
+
+        traced.push(Traced {
+            topic: location.topic,
+            partition: location.partition,
+            offset: location.offset,
+            timestamp,
+            correlation_id: correlation_id(&record).map(ToOwned::to_owned),
+            causation_id: next.as_ref().map(ToString::to_string),
+        });
+    }
+
+    Ok(traced)
+}
+
+/// Give each record of a batch without a correlation id the one from `correlation`
+fn correlate<F>(
+    mut inflated: inflated::Batch,
+    correlation: F,
+) -> tansu_sans_io::Result<deflated::Batch>
+where
+    F: Fn(&Record) -> String,
+{
+    let records = mem::take(&mut inflated.records);
+
+    records
+        .into_iter()
+        .fold(inflated::Builder::from(inflated), |builder, record| {
+            if correlation_id(&record).is_some() {
+                return builder.record(record::Builder::from(record));
+            }
+
+            let correlation_id = correlation(&record);
+
+            builder.record(
+                record::Builder::from(record).header(
+                    Header::builder()
+                        .key(Bytes::from_static(CORRELATION_ID.as_bytes()))
+                        .value(Bytes::from(correlation_id)),
+                ),
+            )
+        })
+        .build()
+        .and_then(deflated::Batch::try_from)
+}
+
+/// A batch with every record correlated, or `None` when already correlated
+async fn correlated<S>(storage: &S, batch: deflated::Batch) -> Result<Option<deflated::Batch>>
+where
+    S: Storage,
+{
+    if BatchAttribute::try_from(batch.attributes)?.control {
+        return Ok(None);
+    }
+
+    let inflated = inflated::Batch::try_from(batch)?;
+
+    if inflated
+        .records
+        .iter()
+        .all(|record| correlation_id(record).is_some())
+    {
+        return Ok(None);
+    }
+
+    let causes = inflated
+        .records
+        .iter()
+        .filter(|record| correlation_id(record).is_none())
+        .filter_map(causation_id)
+        .collect::<BTreeSet<_>>();
+
+    let mut inherited = Vec::with_capacity(causes.len());
+
+    for cause in causes {
+        let correlation_id = lookup(storage, &cause)
+            .await
+            .inspect_err(|err| debug!(%cause, ?err))
+            .ok()
+            .flatten()
+            .and_then(|(_, record)| correlation_id(&record).map(ToOwned::to_owned));
+
+        inherited.push((cause, correlation_id));
+    }
+
+    correlate(inflated, |record| {
+        causation_id(record)
+            .and_then(|cause| {
+                inherited
+                    .iter()
+                    .find(|(location, _)| *location == cause)
+                    .and_then(|(_, correlation_id)| correlation_id.clone())
+            })
+            .unwrap_or_else(|| Uuid::new_v4().to_string())
+    })
+    .map(Some)
+    .map_err(Into::into)
+}
+
+async fn is_enabled<State>(ctx: &Context<State>, topic: &str) -> bool
+where
+    State: Storage,
+{
+    TopicConfig::lookup(ctx, topic)
+        .await
+        .inspect_err(|err| debug!(topic, ?err))
+        .is_ok_and(|config| {
+            config
+                .get(LINEAGE)
+                .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+        })
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LineageLayer;
+
+impl<S> Layer<S> for LineageLayer {
+    type Service = LineageService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LineageService { inner }
+    }
+}
+
+/// A [`Service`] giving the records produced to a topic with `tansu.lineage`
+/// enabled a correlation id
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LineageService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for LineageService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        for topic in req.topic_data.as_deref_mut().unwrap_or_default() {
+            if !is_enabled(&ctx, topic.name.as_str()).await {
+                continue;
+            }
+
+            for partition in topic.partition_data.as_deref_mut().unwrap_or_default() {
+                let Some(frame) = partition.records.as_mut() else {
+                    continue;
+                };
+
+                for batch in frame.batches.iter_mut() {
+                    match correlated(ctx.state(), batch.clone()).await {
+                        Ok(Some(deflated)) => *batch = deflated,
+                        Ok(None) => (),
+                        Err(err) => warn!(topic = topic.name, partition = partition.index, ?err),
+                    }
+                }
+            }
+        }
+
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn originals_correlated() -> tansu_sans_io::Result<()> {
+        let batch = inflated::Batch::builder()
+            .record(record::Record::builder().value(Some(Bytes::from_static(b"abc"))))
+            .record(
+                record::Record::builder()
+                    .offset_delta(1)
+                    .value(Some(Bytes::from_static(b"pqr")))
+                    .header(
+                        Header::builder()
+                            .key(Bytes::from_static(CORRELATION_ID.as_bytes()))
+                            .value(Bytes::from_static(b"xyz")),
+                    ),
+            )
+            .last_offset_delta(1)
+            .build()?;
+
+        let correlated =
+            correlate(batch, |_| "generated".into()).and_then(inflated::Batch::try_from)?;
+
+        assert_eq!(
+            vec![Some("generated"), Some("xyz")],
+            correlated
+                .records
+                .iter()
+                .map(correlation_id)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn caused_correlated() -> tansu_sans_io::Result<()> {
+        let cause = Location::new("orders", 0, 32);
+
+        let batch = inflated::Batch::builder()
+            .record(
+                tansu_client::lineage::caused_by(&record::Record::builder().build()?, &cause)
+                    .into_iter()
+                    .fold(
+                        record::Record::builder().value(Some(Bytes::from_static(b"abc"))),
+                        |builder, header| builder.header(header),
+                    ),
+            )
+            .build()?;
+
+        let correlated = correlate(batch, |record| {
+            causation_id(record).map_or("generated".into(), |cause| cause.to_string())
+        })
+        .and_then(inflated::Batch::try_from)?;
+
+        assert_eq!(Some("orders/0/32"), correlation_id(&correlated.records[0]));
+        assert_eq!(Some(cause), causation_id(&correlated.records[0]));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record batching for HTTP producers
+//!
+//! Records posted one at a time to `/records` on the admin listener are
+//! coalesced into a shared batch for each topition. The first record of a
+//! batch waits for the linger (as with `linger.ms` of a Kafka producer),
+//! collecting any other records posted to the same topition meanwhile, before
+//! the batch is produced with a single call to storage. The lingering batch is
+//! a delayed operation in the storage purgatory, completed early when it
+//! reaches [`MAX_RECORDS`].
+//!
+//! Each record is acknowledged with its own offset once its batch has been
+//! produced. The number of records in each batch and how long each batch
+//! lingered are recorded as `tansu_linger_batch_records` and
+//! `tansu_linger_duration` histograms.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use bytes::Bytes;
+use opentelemetry::{KeyValue, metrics::Histogram};
+use tansu_sans_io::{
+    record::{Record, inflated},
+    to_timestamp,
+};
+use tansu_storage::{
+    Storage, Topition,
+    purgatory::{self, Delayed, Key},
+};
+use tokio::sync::oneshot;
+use tracing::{debug, instrument, warn};
+
+use crate::{Error, METER, Result};
+
+/// The maximum number of records in each batch
+pub const MAX_RECORDS: usize = 500;
+
+static BATCH_RECORDS: LazyLock<Histogram<u64>> = LazyLock::new(|| {
+    METER
+        .u64_histogram("tansu_linger_batch_records")
+        .with_description("The records in each batch coalesced from HTTP producers")
+        .build()
+});
+
+static LINGER_DURATION: LazyLock<Histogram<u64>> = LazyLock::new(|| {
+    METER
+        .u64_histogram("tansu_linger_duration")
+        .with_unit("ms")
+        .with_description(
+            "How long each batch coalesced from HTTP producers lingered in milliseconds",
+        )
+        .build()
+});
+
+/// A record waiting for its batch to be produced
+#[derive(Debug)]
+struct Pending {
+    key: Option<Bytes>,
+    value: Option<Bytes>,
+    acknowledge: oneshot::Sender<Result<i64>>,
+}
+
+/// The records waiting to be produced to a topition
+#[derive(Debug)]
+struct Lingering {
+    since: Instant,
+    records: Vec<Pending>,
+}
+
+/// Coalesces records produced to the same topition into shared batches
+#[derive(Clone, Debug)]
+pub struct Linger<S> {
+    storage: S,
+    linger: Duration,
+    lingering: Arc<Mutex<BTreeMap<Topition, Lingering>>>,
+}
+
+impl<S> Linger<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, linger: Duration) -> Self {
+        Self {
+            storage,
+            linger,
+            lingering: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Produce a record, returning its offset once its batch has been produced
+    pub async fn produce(
+        &self,
+        topition: &Topition,
+        key: Option<Bytes>,
+        value: Option<Bytes>,
+    ) -> Result<i64> {
+        let (acknowledge, acknowledged) = oneshot::channel();
+
+        let records = {
+            let mut lingering = self.lingering.lock()?;
+
+            let batch = lingering
+                .entry(topition.clone())
+                .or_insert_with(|| Lingering {
+                    since: Instant::now(),
+                    records: vec![],
+                });
+
+            batch.records.push(Pending {
+                key,
+                value,
+                acknowledge,
+            });
+
+            batch.records.len()
+        };
+
+        if records == 1 {
+            let mut delayed = Delayed::new(self.linger);
+            delayed.watch(Key::Linger(topition.clone()))?;
+
+            let linger = self.clone();
+            let topition = topition.clone();
+
+            _ = tokio::spawn(async move {
+                let wake = delayed.wait(None).await;
+                debug!(?topition, ?wake);
+                linger.flush(&topition).await
+            });
+        } else if records >= MAX_RECORDS {
+            purgatory::complete(&Key::Linger(topition.clone()));
+        }
+
+        acknowledged
+            .await
+            .map_err(|_| Error::Message(format!("batch abandoned: {topition:?}")))?
+    }
+
+    /// Produce the records lingering for a topition as one batch,
+    /// acknowledging each with its offset
+    #[instrument(skip(self))]
+    async fn flush(&self, topition: &Topition) {
+        let Some(Lingering { since, mut records }) = self
+            .lingering
+            .lock()
+            .map(|mut lingering| lingering.remove(topition))
+            .inspect_err(|err| warn!(?err))
+            .ok()
+            .flatten()
+        else {
+            return;
+        };
+
+        let attributes = [KeyValue::new("topic", topition.topic().to_owned())];
+        BATCH_RECORDS.record(records.len() as u64, &attributes);
+        LINGER_DURATION.record(since.elapsed().as_millis() as u64, &attributes);
+
+        debug!(records = records.len(), lingered = ?since.elapsed());
+
+        let produced = self.produce_batch(topition, &mut records).await;
+
+        for (delta, Pending { acknowledge, .. }) in records.into_iter().enumerate() {
+            _ = acknowledge.send(
+                produced
+                    .clone()
+                    .map(|base_offset| base_offset + delta as i64),
+            );
+        }
+    }
+
+    async fn produce_batch(&self, topition: &Topition, records: &mut [Pending]) -> Result<i64> {
+        let timestamp = to_timestamp(&SystemTime::now())?;
+
+        let mut batch = inflated::Batch::builder()
+            .base_timestamp(timestamp)
+            .max_timestamp(timestamp);
+
+        for (offset_delta, pending) in records.iter_mut().enumerate() {
+            let offset_delta = i32::try_from(offset_delta)?;
+
+            batch = batch
+                .record(
+                    Record::builder()
+                        .offset_delta(offset_delta)
+                        .key(pending.key.take())
+                        .value(pending.value.take()),
+                )
+                .last_offset_delta(offset_delta);
+        }
+
+        let batch = batch.build().and_then(TryInto::try_into)?;
+
+        self.storage
+            .produce(None, topition, batch)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,517 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cluster linking
+//!
+//! A linked topic has a `tansu.link.upstream` topic config with the URL of an
+//! upstream broker, e.g., `tcp://upstream:9092`, and is continuously
+//! replicated from the topic of the same name (or `tansu.link.topic`) in the
+//! upstream cluster. By default upstream offsets are preserved, so that
+//! consumers may fail over between clusters without offset translation. A
+//! linked topic is read only, with any local produce being rejected.
+//!
+//! As the URL may embed credentials, `tansu.link.upstream` is sensitive and
+//! may be a `secret://` reference to an environment variable or mounted file,
+//! resolved whenever the link is described.
+//!
+//! Where offsets can't be preserved, `tansu.link.offsets=translate` appends
+//! each upstream batch at the local high watermark instead, checkpointing the
+//! upstream to local offset translation in storage whenever they diverge, so
+//! that the committed offsets of a consumer group may be migrated.
+
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
+
+use rama::{Context, Layer, Service};
+use tansu_client::{Client, ConnectionManager};
+use tansu_sans_io::{
+    ErrorCode, MetadataRequest, NULL_TOPIC_ID, ProduceRequest, ProduceResponse,
+    fetch_request::{FetchPartition, FetchRequest, FetchTopic, ReplicaState},
+    metadata_request::MetadataRequestTopic,
+    produce_request::TopicProduceData,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+    record::deflated::Batch,
+};
+use tansu_storage::{OffsetTranslation, Secret, Storage, Topition};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+use crate::{Result, broker::topic_config::TopicConfig};
+
+pub const LINK_UPSTREAM: &str = "tansu.link.upstream";
+pub const LINK_TOPIC: &str = "tansu.link.topic";
+pub const LINK_OFFSETS: &str = "tansu.link.offsets";
+
+/// How the offsets of a linked topic relate to those of its upstream
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Offsets {
+    /// Upstream offsets are preserved
+    #[default]
+    Preserve,
+
+    /// Upstream batches are appended locally, with checkpointed offset translations
+    Translate,
+}
+
+impl From<&str> for Offsets {
+    fn from(value: &str) -> Self {
+        match value {
+            "translate" => Self::Translate,
+            _preserve => Self::Preserve,
+        }
+    }
+}
+
+/// The upstream of a linked topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Link {
+    upstream: Url,
+    topic: String,
+    offsets: Offsets,
+}
+
+impl Link {
+    /// The link for a topic from its configuration, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        TopicConfig::describe(storage, topic)
+            .await
+            .map_err(Into::into)
+            .and_then(|config| Self::from_config(topic, &config))
+    }
+
+    /// The link for a topic from its configuration, if any
+    pub fn from_config(topic: &str, config: &TopicConfig) -> Result<Option<Self>> {
+        config
+            .get(LINK_UPSTREAM)
+            .map(|upstream| {
+                Secret::resolve(upstream)
+                    .map_err(Into::into)
+                    .and_then(|upstream| Url::from_str(&upstream).map_err(Into::into))
+                    .map(|upstream| Self {
+                        upstream,
+                        topic: config.get(LINK_TOPIC).unwrap_or(topic).to_owned(),
+                        offsets: config
+                            .get(LINK_OFFSETS)
+                            .map(Offsets::from)
+                            .unwrap_or_default(),
+                    })
+            })
+            .transpose()
+    }
+}
+
+/// Replicates linked topics from their upstream cluster
+#[derive(Clone, Debug)]
+pub struct ClusterLink<S> {
+    storage: S,
+    interval: Duration,
+    clients: BTreeMap<Url, Client>,
+    cancellation: CancellationToken,
+}
+
+impl<S> ClusterLink<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            clients: BTreeMap::new(),
+            cancellation,
+        }
+    }
+
+    pub async fn serve(mut self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .replicate()
+                        .await
+                        .inspect(|replicated| debug!(replicated))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn client(&mut self, upstream: &Url) -> Result<Client> {
+        if let Some(client) = self.clients.get(upstream) {
+            return Ok(client.clone());
+        }
+
+        let client = ConnectionManager::builder(upstream.to_owned())
+            .client_id(Some(env!("CARGO_PKG_NAME").into()))
+            .build()
+            .await
+            .map(Client::new)?;
+
+        _ = self.clients.insert(upstream.to_owned(), client.clone());
+
+        Ok(client)
+    }
+
+    /// Checkpoint a translation unless it is implied by the previous checkpoint
+    async fn checkpoint(
+        &self,
+        topition: &Topition,
+        previous: &mut Option<OffsetTranslation>,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        if previous.is_some_and(|previous| {
+            previous.upstream <= translation.upstream
+                && previous.translate(translation.upstream) == translation.local
+        }) {
+            return Ok(());
+        }
+
+        debug!(?topition, ?translation);
+
+        self.storage
+            .checkpoint_offset_translation(topition, translation)
+            .await?;
+
+        _ = previous.replace(translation);
+
+        Ok(())
+    }
+
+    /// Append a batch at the local high watermark, checkpointing its offset translation
+    async fn translate(
+        &self,
+        topition: &Topition,
+        previous: &mut Option<OffsetTranslation>,
+        batch: Batch,
+    ) -> Result<()> {
+        if batch.is_control() {
+            let local = self.storage.offset_stage(topition).await?.high_watermark();
+
+            return self
+                .checkpoint(
+                    topition,
+                    previous,
+                    OffsetTranslation::new(batch.max_offset() + 1, local),
+                )
+                .await;
+        }
+
+        let upstream = batch.base_offset;
+
+        let local = self
+            .storage
+            .produce(None, topition, batch.without_producer()?)
+            .await?;
+
+        self.checkpoint(topition, previous, OffsetTranslation::new(upstream, local))
+            .await
+    }
+
+    /// Replicate each linked topic, returning the number of batches replicated
+    #[instrument(skip(self))]
+    pub async fn replicate(&mut self) -> Result<u64> {
+        let metadata = self.storage.metadata(None).await?;
+
+        let mut replicated = 0;
+
+        for topic in metadata.topics() {
+            let Some(name) = topic.name.as_deref() else {
+                continue;
+            };
+
+            let Some(link) = Link::describe(&self.storage, name).await? else {
+                continue;
+            };
+
+            let partitions = topic
+                .partitions
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|partition| partition.partition_index)
+                .collect::<Vec<_>>();
+
+            replicated += self
+                .topic(name, &link, &partitions[..])
+                .await
+                .inspect_err(|err| warn!(topic = name, ?link, ?err))
+                .unwrap_or_default();
+        }
+
+        Ok(replicated)
+    }
+
+    async fn topic(&mut self, name: &str, link: &Link, partitions: &[i32]) -> Result<u64> {
+        let client = self.client(&link.upstream).await?;
+
+        let topic_id = client
+            .call(
+                MetadataRequest::default()
+                    .allow_auto_topic_creation(Some(false))
+                    .include_cluster_authorized_operations(Some(false))
+                    .include_topic_authorized_operations(Some(false))
+                    .topics(Some(
+                        [MetadataRequestTopic::default()
+                            .name(Some(link.topic.clone()))
+                            .topic_id(Some(NULL_TOPIC_ID))]
+                        .into(),
+                    )),
+            )
+            .await?
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .find(|topic| topic.name.as_deref() == Some(link.topic.as_str()))
+            .and_then(|topic| topic.topic_id);
+
+        let mut fetch_partitions = Vec::with_capacity(partitions.len());
+        let mut fetch_offsets = BTreeMap::new();
+        let mut translations = BTreeMap::new();
+
+        for partition in partitions {
+            let topition = Topition::new(name, *partition);
+            let high_watermark = self.storage.offset_stage(&topition).await?.high_watermark();
+
+            let fetch_offset = match link.offsets {
+                Offsets::Preserve => high_watermark,
+
+                Offsets::Translate => {
+                    let previous = self.storage.offset_translation(&topition, i64::MAX).await?;
+                    _ = translations.insert(*partition, previous);
+
+                    previous.map_or(0, |previous| {
+                        previous.upstream + (high_watermark - previous.local)
+                    })
+                }
+            };
+
+            _ = fetch_offsets.insert(*partition, fetch_offset);
+
+            fetch_partitions.push(
+                FetchPartition::default()
+                    .partition(*partition)
+                    .current_leader_epoch(Some(-1))
+                    .fetch_offset(fetch_offset)
+                    .last_fetched_epoch(Some(-1))
+                    .log_start_offset(Some(-1))
+                    .partition_max_bytes(1_048_576),
+            );
+        }
+
+        let response = client
+            .call(
+                FetchRequest::default()
+                    .cluster_id(Some("".into()))
+                    .replica_id(Some(-1))
+                    .replica_state(Some(ReplicaState::default()))
+                    .max_wait_ms(0)
+                    .min_bytes(0)
+                    .max_bytes(Some(4_194_304))
+                    .isolation_level(Some(0))
+                    .session_id(Some(-1))
+                    .session_epoch(Some(-1))
+                    .topics(Some(vec![
+                        FetchTopic::default()
+                            .topic(Some(link.topic.clone()))
+                            .topic_id(topic_id)
+                            .partitions(Some(fetch_partitions)),
+                    ]))
+                    .forgotten_topics_data(Some([].into()))
+                    .rack_id(Some("".into())),
+            )
+            .await?;
+
+        let mut replicated = 0;
+
+        for partition in response
+            .responses
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+        {
+            let error_code = ErrorCode::try_from(partition.error_code)?;
+            let topition = Topition::new(name, partition.partition_index);
+
+            if error_code == ErrorCode::OffsetOutOfRange
+                && link.offsets == Offsets::Translate
+                && let Some(log_start_offset) = partition.log_start_offset.filter(|log_start| {
+                    fetch_offsets
+                        .get(&partition.partition_index)
+                        .is_some_and(|fetch_offset| log_start > fetch_offset)
+                })
+            {
+                debug!(?topition, log_start_offset);
+
+                let local = self.storage.offset_stage(&topition).await?.high_watermark();
+
+                self.checkpoint(
+                    &topition,
+                    translations.entry(partition.partition_index).or_default(),
+                    OffsetTranslation::new(log_start_offset, local),
+                )
+                .await?;
+
+                continue;
+            }
+
+            if error_code != ErrorCode::None {
+                warn!(
+                    topic = name,
+                    partition = partition.partition_index,
+                    ?error_code
+                );
+                continue;
+            }
+
+            for batch in partition
+                .records
+                .map(|frame| frame.batches)
+                .unwrap_or_default()
+            {
+                match link.offsets {
+                    Offsets::Preserve => {
+                        _ = self
+                            .storage
+                            .replicate(&topition, batch)
+                            .await
+                            .inspect(|offset| debug!(?topition, offset))?;
+                    }
+
+                    Offsets::Translate => {
+                        let fetch_offset =
+                            fetch_offsets.entry(partition.partition_index).or_default();
+
+                        if batch.base_offset < *fetch_offset {
+                            debug!(?topition, base_offset = batch.base_offset, fetch_offset);
+                            continue;
+                        }
+
+                        *fetch_offset = batch.max_offset() + 1;
+
+                        self.translate(
+                            &topition,
+                            translations.entry(partition.partition_index).or_default(),
+                            batch,
+                        )
+                        .await?;
+                    }
+                }
+
+                replicated += 1;
+            }
+        }
+
+        Ok(replicated)
+    }
+}
+
+fn read_only(topic: TopicProduceData) -> TopicProduceResponse {
+    TopicProduceResponse::default()
+        .name(topic.name)
+        .partition_responses(Some(
+            topic
+                .partition_data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    PartitionProduceResponse::default()
+                        .index(partition.index)
+                        .error_code(ErrorCode::PolicyViolation.into())
+                        .base_offset(-1)
+                        .log_append_time_ms(Some(-1))
+                        .log_start_offset(Some(0))
+                        .record_errors(Some([].into()))
+                        .error_message(Some("linked topics are read only".into()))
+                        .current_leader(None)
+                })
+                .collect(),
+        ))
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LinkedTopicLayer;
+
+impl<S> Layer<S> for LinkedTopicLayer {
+    type Service = LinkedTopicService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LinkedTopicService { inner }
+    }
+}
+
+/// A [`Service`] rejecting a local produce to a linked topic
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LinkedTopicService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for LinkedTopicService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut accepted = vec![];
+        let mut rejected = vec![];
+
+        for topic in req.topic_data.take().unwrap_or_default() {
+            if TopicConfig::lookup(&ctx, topic.name.as_str())
+                .await
+                .map_err(Into::into)
+                .and_then(|config| Link::from_config(topic.name.as_str(), &config))
+                .inspect_err(|err| warn!(topic = topic.name, ?err))
+                .is_ok_and(|link| link.is_some())
+            {
+                debug!(topic = topic.name, "read only");
+                rejected.push(read_only(topic));
+            } else {
+                accepted.push(topic);
+            }
+        }
+
+        req.topic_data = Some(accepted);
+
+        self.inner.serve(ctx, req).await.map(|mut response| {
+            if !rejected.is_empty() {
+                response
+                    .responses
+                    .get_or_insert_default()
+                    .append(&mut rejected);
+            }
+
+            response
+        })
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime log levels using the `BROKER_LOGGER` config resource
+//!
+//! Each config name is a tracing target (module), with the value being the level:
+//! a `SET` alters the level of that target, a `DELETE` reverts it to the root level.
+//! With `validate_only` the levels are checked without being applied.
+
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    ConfigResource, ErrorCode, IncrementalAlterConfigsRequest, IncrementalAlterConfigsResponse,
+    OpType, incremental_alter_configs_request::AlterConfigsResource,
+    incremental_alter_configs_response::AlterConfigsResourceResponse,
+};
+use tracing::{debug, instrument, warn};
+
+use crate::otel::LogLevels;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BrokerLoggerLayer;
+
+impl<S> Layer<S> for BrokerLoggerLayer {
+    type Service = BrokerLoggerService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BrokerLoggerService { inner }
+    }
+}
+
+/// A [`Service`] altering `BROKER_LOGGER` resources in process, passing any
+/// other resources to the inner service
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BrokerLoggerService<S> {
+    inner: S,
+}
+
+impl<S> BrokerLoggerService<S> {
+    fn alter(resource: AlterConfigsResource, validate_only: bool) -> AlterConfigsResourceResponse {
+        let mut levels = LogLevels::current();
+
+        let outcome = resource
+            .configs
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .try_for_each(|config| {
+                match (
+                    OpType::try_from(config.config_operation),
+                    config.value.as_deref(),
+                ) {
+                    (Ok(OpType::Set), Some(level)) => {
+                        levels.set(config.name.as_str(), level);
+                        Ok(())
+                    }
+
+                    (Ok(OpType::Delete), _) => {
+                        levels.remove(config.name.as_str());
+                        Ok(())
+                    }
+
+                    (_, value) => {
+                        debug!(
+                            name = config.name.as_str(),
+                            operation = config.config_operation,
+                            ?value
+                        );
+                        Err((
+                            ErrorCode::InvalidRequest,
+                            format!("unsupported operation for logger: {}", config.name),
+                        ))
+                    }
+                }
+            })
+            .and_then(|()| {
+                if validate_only {
+                    levels.validate()
+                } else {
+                    levels.apply()
+                }
+                .map_err(|err| {
+                    warn!(?err, %levels);
+                    (ErrorCode::InvalidConfig, err.to_string())
+                })
+            });
+
+        let (error_code, error_message) = outcome.map_or_else(
+            |(error_code, error_message)| (error_code, Some(error_message)),
+            |()| (ErrorCode::None, None),
+        );
+
+        AlterConfigsResourceResponse::default()
+            .error_code(error_code.into())
+            .error_message(error_message)
+            .resource_type(resource.resource_type)
+            .resource_name(resource.resource_name)
+    }
+}
+
+impl<S, State> Service<State, IncrementalAlterConfigsRequest> for BrokerLoggerService<S>
+where
+    S: Service<State, IncrementalAlterConfigsRequest, Response = IncrementalAlterConfigsResponse>,
+    State: Send + Sync + 'static,
+{
+    type Response = IncrementalAlterConfigsResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: IncrementalAlterConfigsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let (loggers, others): (Vec<_>, Vec<_>) = req
+            .resources
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|resource| {
+                ConfigResource::from(resource.resource_type) == ConfigResource::BrokerLogger
+            });
+
+        if loggers.is_empty() {
+            return self.inner.serve(ctx, req.resources(Some(others))).await;
+        }
+
+        let validate_only = req.validate_only;
+
+        let mut responses = loggers
+            .into_iter()
+            .map(|resource| Self::alter(resource, validate_only))
+            .collect::<Vec<_>>();
+
+        if others.is_empty() {
+            return Ok(IncrementalAlterConfigsResponse::default()
+                .throttle_time_ms(0)
+                .responses(Some(responses)));
+        }
+
+        self.inner
+            .serve(ctx, req.resources(Some(others)))
+            .await
+            .map(|response| {
+                responses.extend(response.responses.unwrap_or_default());
+                response.responses(Some(responses))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tansu_sans_io::incremental_alter_configs_request::AlterableConfig;
+
+    use super::*;
+
+    fn resource(level: &str) -> AlterConfigsResource {
+        AlterConfigsResource::default()
+            .resource_type(ConfigResource::BrokerLogger.into())
+            .resource_name("111".into())
+            .configs(Some(vec![
+                AlterableConfig::default()
+                    .name("tansu_storage".into())
+                    .config_operation(OpType::Set.into())
+                    .value(Some(level.into())),
+            ]))
+    }
+
+    #[test]
+    fn validate_only_checks_levels() {
+        let response = BrokerLoggerService::<()>::alter(resource("loud"), true);
+        assert_eq!(i16::from(ErrorCode::InvalidConfig), response.error_code);
+        assert!(response.error_message.is_some());
+
+        let response = BrokerLoggerService::<()>::alter(resource("debug"), true);
+        assert_eq!(i16::from(ErrorCode::None), response.error_code);
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Produce batch size limits
+//!
+//! A topic with a `max.message.bytes` config rejects any partition with a
+//! batch larger than that limit with `MessageTooLarge`, and an error message
+//! naming the size of the batch and the limit, before the batch is written to
+//! storage. Other partitions in the same request are unaffected.
+
+use std::sync::LazyLock;
+
+use opentelemetry::{KeyValue, metrics::Counter};
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    ErrorCode, ProduceRequest, ProduceResponse,
+    produce_request::{PartitionProduceData, TopicProduceData},
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+};
+use tansu_storage::Storage;
+use tracing::{debug, instrument, warn};
+
+use crate::{METER, broker::topic_config::TopicConfig};
+
+pub const MAX_MESSAGE_BYTES: &str = "max.message.bytes";
+
+static PRODUCE_TOO_LARGE: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_produce_too_large")
+        .with_description("produce batches rejected by the max.message.bytes of a topic")
+        .build()
+});
+
+/// The size of the largest batch being produced to a partition
+fn largest_batch(partition: &PartitionProduceData) -> usize {
+    partition
+        .records
+        .as_ref()
+        .map(|frame| frame.batches.iter())
+        .into_iter()
+        .flatten()
+        .map(|batch| usize::try_from(batch.batch_length).unwrap_or_default())
+        .max()
+        .unwrap_or_default()
+}
+
+fn too_large(index: i32, size: usize, limit: usize) -> PartitionProduceResponse {
+    PartitionProduceResponse::default()
+        .index(index)
+        .error_code(ErrorCode::MessageTooLarge.into())
+        .base_offset(-1)
+        .log_append_time_ms(Some(-1))
+        .log_start_offset(Some(0))
+        .record_errors(Some([].into()))
+        .error_message(Some(format!(
+            "batch of {size} bytes is larger than {MAX_MESSAGE_BYTES}: {limit}"
+        )))
+        .current_leader(None)
+}
+
+/// Partitions within the limit remain in the topic, returning responses for
+/// those that are not
+fn partition_by_size(topic: &mut TopicProduceData, limit: usize) -> Vec<PartitionProduceResponse> {
+    let (within, beyond): (Vec<_>, Vec<_>) = topic
+        .partition_data
+        .take()
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|partition| largest_batch(partition) <= limit);
+
+    topic.partition_data = Some(within);
+
+    beyond
+        .into_iter()
+        .map(|partition| {
+            let size = largest_batch(&partition);
+            warn!(topic = topic.name, partition = partition.index, size, limit);
+            too_large(partition.index, size, limit)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MessageSizeLayer;
+
+impl<S> Layer<S> for MessageSizeLayer {
+    type Service = MessageSizeService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MessageSizeService { inner }
+    }
+}
+
+/// A [`Service`] rejecting batches larger than the `max.message.bytes` of a topic
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MessageSizeService<S> {
+    inner: S,
+}
+
+impl<S> MessageSizeService<S> {
+    async fn limit<State>(&self, ctx: &Context<State>, topic: &str) -> Option<usize>
+    where
+        State: Storage,
+    {
+        TopicConfig::lookup(ctx, topic)
+            .await
+            .inspect_err(|err| debug!(topic, ?err))
+            .ok()
+            .and_then(|config| {
+                config
+                    .get(MAX_MESSAGE_BYTES)
+                    .and_then(|value| value.parse::<usize>().ok())
+            })
+    }
+}
+
+impl<S, State> Service<State, ProduceRequest> for MessageSizeService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut rejected = vec![];
+
+        for topic in req.topic_data.as_deref_mut().unwrap_or_default() {
+            let Some(limit) = self.limit(&ctx, topic.name.as_str()).await else {
+                continue;
+            };
+
+            let partitions = partition_by_size(topic, limit);
+
+            if !partitions.is_empty() {
+                PRODUCE_TOO_LARGE.add(
+                    partitions.len() as u64,
+                    &[KeyValue::new("topic", topic.name.clone())],
+                );
+
+                rejected.push(
+                    TopicProduceResponse::default()
+                        .name(topic.name.clone())
+                        .partition_responses(Some(partitions)),
+                );
+            }
+        }
+
+        self.inner.serve(ctx, req).await.map(|mut response| {
+            if !rejected.is_empty() {
+                response
+                    .responses
+                    .get_or_insert_default()
+                    .append(&mut rejected);
+            }
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tansu_sans_io::record::{Record, deflated::Frame, inflated};
+
+    use super::*;
+    use crate::Result;
+
+    fn partition(index: i32, value: &'static [u8]) -> Result<PartitionProduceData> {
+        inflated::Batch::builder()
+            .record(Record::builder().value(Bytes::from_static(value).into()))
+            .build()
+            .and_then(TryInto::try_into)
+            .map(|batch| {
+                PartitionProduceData::default()
+                    .index(index)
+                    .records(Some(Frame {
+                        batches: vec![batch],
+                    }))
+            })
+            .map_err(Into::into)
+    }
+
+    #[test]
+    fn oversized_partitions_are_rejected() -> Result<()> {
+        let small = partition(0, b"a")?;
+        let large = partition(1, &[0u8; 512])?;
+        let limit = largest_batch(&small);
+
+        let mut topic = TopicProduceData::default()
+            .name("abc".into())
+            .partition_data(Some(vec![small, large]));
+
+        let rejected = partition_by_size(&mut topic, limit);
+
+        assert_eq!(1, rejected.len());
+        assert_eq!(1, rejected[0].index);
+        assert_eq!(
+            i16::from(ErrorCode::MessageTooLarge),
+            rejected[0].error_code
+        );
+
+        let accepted = topic.partition_data.unwrap_or_default();
+        assert_eq!(1, accepted.len());
+        assert_eq!(0, accepted[0].index);
+
+        Ok(())
+    }
+}
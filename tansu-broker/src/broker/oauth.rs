@@ -0,0 +1,482 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SASL/OAUTHBEARER authentication
+//!
+//! The client initial response of RFC 7628 carries a bearer token, which is
+//! validated as a JWT signed by one of the keys of a JSON Web Key Set. The
+//! key set is fetched from a `https://` (or `file://`) URL, such as the
+//! `jwks_uri` of a Keycloak realm or Okta authorization server, and is
+//! fetched again when a token is signed by an unknown key. Refetches are
+//! limited to one per [`REFETCH_INTERVAL`], with a key id that is still
+//! unknown after a refetch remembered for the same interval, so that tokens
+//! carrying random key ids cannot be used to flood the identity provider.
+//! The principal of the connection is taken from a claim of the token, `sub`
+//! by default.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use jsonwebtoken::{
+    DecodingKey, Validation, decode, decode_header,
+    jwk::{Jwk, JwkSet},
+};
+use serde_json::Value;
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+use crate::{Error, Result, broker::platform};
+
+pub const OAUTHBEARER: &str = "OAUTHBEARER";
+
+const SUBJECT: &str = "sub";
+
+/// The minimum interval between fetches of the key set
+pub const REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The maximum number of unknown key ids remembered
+const UNKNOWN_KIDS: usize = 1_024;
+
+/// Parse the bearer token of an OAUTHBEARER client initial response:
+/// `gs2-header %x01 auth=Bearer <token> %x01 [key=value %x01]... %x01`
+pub(crate) fn bearer(auth_bytes: &[u8]) -> Option<&str> {
+    let message = std::str::from_utf8(auth_bytes).ok()?;
+
+    let (gs2_header, kvpairs) = message.split_once('\x01')?;
+
+    if !(gs2_header.starts_with("n,") || gs2_header.starts_with("y,")) {
+        return None;
+    }
+
+    kvpairs
+        .strip_suffix("\x01\x01")?
+        .split('\x01')
+        .find_map(|kvpair| kvpair.strip_prefix("auth="))
+        .and_then(|auth| auth.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// Validate bearer tokens against a JSON Web Key Set
+#[derive(Clone, Debug)]
+pub struct OAuthBearer {
+    jwks: Url,
+    issuer: Option<String>,
+    audience: Option<String>,
+    principal_claim: String,
+    refetch_interval: Duration,
+    keys: Arc<Mutex<Keys>>,
+}
+
+/// The fetched key set, when it was fetched, and key ids that were unknown
+/// to the set at the time
+#[derive(Debug, Default)]
+struct Keys {
+    set: Option<JwkSet>,
+    fetched: Option<Instant>,
+    unknown: BTreeMap<String, Instant>,
+}
+
+impl Keys {
+    fn replace(&mut self, set: JwkSet, now: Instant) {
+        self.set = Some(set);
+        self.fetched = Some(now);
+    }
+
+    /// Whether the set may be fetched again for `kid`
+    fn refetch(&mut self, kid: Option<&str>, interval: Duration, now: Instant) -> bool {
+        self.unknown
+            .retain(|_, since| now.saturating_duration_since(*since) < interval);
+
+        if kid.is_some_and(|kid| self.unknown.contains_key(kid)) {
+            return false;
+        }
+
+        self.fetched
+            .is_none_or(|fetched| now.saturating_duration_since(fetched) >= interval)
+    }
+
+    fn unknown(&mut self, kid: Option<&str>, now: Instant) {
+        if let Some(kid) = kid {
+            if self.unknown.len() >= UNKNOWN_KIDS {
+                self.unknown.clear();
+            }
+
+            _ = self.unknown.insert(kid.to_owned(), now);
+        }
+    }
+}
+
+impl OAuthBearer {
+    pub fn new(jwks: Url) -> Self {
+        Self {
+            jwks,
+            issuer: None,
+            audience: None,
+            principal_claim: SUBJECT.into(),
+            refetch_interval: REFETCH_INTERVAL,
+            keys: Arc::new(Mutex::new(Keys::default())),
+        }
+    }
+
+    /// The minimum interval between fetches of the key set
+    pub fn refetch_interval(self, refetch_interval: Option<Duration>) -> Self {
+        Self {
+            refetch_interval: refetch_interval.unwrap_or(REFETCH_INTERVAL),
+            ..self
+        }
+    }
+
+    /// Only accept tokens with this `iss` claim
+    pub fn issuer(self, issuer: Option<String>) -> Self {
+        Self { issuer, ..self }
+    }
+
+    /// Only accept tokens with this `aud` claim
+    pub fn audience(self, audience: Option<String>) -> Self {
+        Self { audience, ..self }
+    }
+
+    /// The claim used as the principal of an authenticated connection
+    pub fn principal_claim(self, principal_claim: Option<String>) -> Self {
+        Self {
+            principal_claim: principal_claim.unwrap_or(SUBJECT.into()),
+            ..self
+        }
+    }
+
+    async fn fetch(&self) -> Result<JwkSet> {
+        debug!(%self.jwks);
+
+        match self.jwks.scheme() {
+            "file" => {
+                let path = PathBuf::from(format!(
+                    "{}{}",
+                    self.jwks.host_str().unwrap_or_default(),
+                    self.jwks.path()
+                ));
+
+                platform::read(&path, "oauthbearer json web key set")
+                    .await
+                    .inspect_err(|err| warn!(?path, ?err))
+                    .map_err(Into::into)
+                    .and_then(|contents| serde_json::from_slice(&contents[..]).map_err(Into::into))
+            }
+
+            "http" | "https" => reqwest::get(self.jwks.as_str())
+                .await?
+                .error_for_status()?
+                .json::<JwkSet>()
+                .await
+                .map_err(Into::into),
+
+            _unsupported => Err(Error::UnsupportedJwksUrl(self.jwks.to_owned())),
+        }
+    }
+
+    /// The key identified by `kid`, fetching the key set when it is not
+    /// already known and has not been fetched within the refetch interval
+    async fn key(&self, kid: Option<&str>) -> Result<Option<Jwk>> {
+        let find = |keys: &JwkSet| match kid {
+            Some(kid) => keys.find(kid).cloned(),
+            None if keys.keys.len() == 1 => keys.keys.first().cloned(),
+            None => None,
+        };
+
+        let refetch = {
+            let mut keys = self.keys.lock()?;
+
+            if let Some(key) = keys.set.as_ref().and_then(find) {
+                return Ok(Some(key));
+            }
+
+            keys.refetch(kid, self.refetch_interval, Instant::now())
+        };
+
+        if !refetch {
+            debug!(kid, "refetch suppressed");
+            return Ok(None);
+        }
+
+        let fetched = self.fetch().await;
+        let now = Instant::now();
+
+        let mut keys = self.keys.lock()?;
+
+        match fetched {
+            Ok(set) => {
+                let key = find(&set);
+                keys.replace(set, now);
+
+                if key.is_none() {
+                    keys.unknown(kid, now);
+                }
+
+                Ok(key)
+            }
+
+            Err(err) => {
+                // a failing identity provider is also only retried once per interval
+                keys.fetched = Some(now);
+                Err(err)
+            }
+        }
+    }
+
+    /// Validate a bearer token, returning the principal when valid
+    #[instrument(skip_all)]
+    pub async fn authenticate(&self, token: &str) -> Result<Option<String>> {
+        let header = match decode_header(token) {
+            Ok(header) => header,
+            Err(err) => {
+                debug!(?err);
+                return Ok(None);
+            }
+        };
+
+        let Some(jwk) = self.key(header.kid.as_deref()).await? else {
+            debug!(kid = header.kid, "unknown key");
+            return Ok(None);
+        };
+
+        let mut validation = Validation::new(
+            jwk.common
+                .key_algorithm
+                .and_then(|algorithm| algorithm.to_string().parse().ok())
+                .unwrap_or(header.alg),
+        );
+
+        if let Some(issuer) = self.issuer.as_deref() {
+            validation.set_issuer(&[issuer]);
+        }
+
+        if let Some(audience) = self.audience.as_deref() {
+            validation.set_audience(&[audience]);
+        } else {
+            validation.validate_aud = false;
+        }
+
+        let claims = match DecodingKey::from_jwk(&jwk)
+            .and_then(|key| decode::<BTreeMap<String, Value>>(token, &key, &validation))
+        {
+            Ok(data) => data.claims,
+            Err(err) => {
+                debug!(?err);
+                return Ok(None);
+            }
+        };
+
+        Ok(claims
+            .get(self.principal_claim.as_str())
+            .and_then(Value::as_str)
+            .filter(|principal| !principal.is_empty())
+            .map(ToOwned::to_owned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+    use serde_json::json;
+
+    use super::*;
+
+    const SECRET: &[u8] = b"a secret that is at least 256 bits long";
+
+    fn bearer_with_keys() -> Result<OAuthBearer> {
+        let bearer = OAuthBearer::new(Url::parse("file://./jwks.json")?)
+            .issuer(Some("https://idp.example.com".into()))
+            .audience(Some("tansu".into()));
+
+        let keys = serde_json::from_value::<JwkSet>(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "abc",
+                "alg": "HS256",
+                "k": URL_SAFE_NO_PAD.encode(SECRET),
+            }]
+        }))?;
+
+        bearer
+            .keys
+            .lock()
+            .map(|mut guard| guard.replace(keys, Instant::now()))?;
+
+        Ok(bearer)
+    }
+
+    fn token(kid: &str, claims: Value) -> Result<String> {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(kid.into());
+
+        encode(&header, &claims, &EncodingKey::from_secret(SECRET)).map_err(Into::into)
+    }
+
+    fn expiry() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs())
+            + 3_600
+    }
+
+    #[test]
+    fn client_initial_response() {
+        assert_eq!(
+            Some("abc.def.ghi"),
+            bearer(b"n,,\x01auth=Bearer abc.def.ghi\x01\x01")
+        );
+
+        assert_eq!(
+            Some("abc.def.ghi"),
+            bearer(b"n,a=alice,\x01host=localhost\x01auth=bearer abc.def.ghi\x01\x01")
+        );
+
+        assert_eq!(None, bearer(b"n,,\x01auth=Basic YWxpY2U6c2VjcmV0\x01\x01"));
+        assert_eq!(None, bearer(b"n,,\x01auth=Bearer abc.def.ghi\x01"));
+        assert_eq!(None, bearer(b"\x01auth=Bearer abc.def.ghi\x01\x01"));
+    }
+
+    #[tokio::test]
+    async fn valid_token() -> Result<()> {
+        let bearer = bearer_with_keys()?;
+
+        let token = token(
+            "abc",
+            json!({
+                "sub": "alice",
+                "iss": "https://idp.example.com",
+                "aud": "tansu",
+                "exp": expiry(),
+            }),
+        )?;
+
+        assert_eq!(Some("alice".into()), bearer.authenticate(&token).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn principal_claim() -> Result<()> {
+        let bearer = bearer_with_keys()?.principal_claim(Some("preferred_username".into()));
+
+        let token = token(
+            "abc",
+            json!({
+                "sub": "f47ac10b-58cc-4372-a567-0e02b2c3d479",
+                "preferred_username": "alice",
+                "iss": "https://idp.example.com",
+                "aud": "tansu",
+                "exp": expiry(),
+            }),
+        )?;
+
+        assert_eq!(Some("alice".into()), bearer.authenticate(&token).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_tokens() -> Result<()> {
+        let bearer = bearer_with_keys()?;
+
+        let wrong_issuer = token(
+            "abc",
+            json!({
+                "sub": "alice",
+                "iss": "https://elsewhere.example.com",
+                "aud": "tansu",
+                "exp": expiry(),
+            }),
+        )?;
+        assert_eq!(None, bearer.authenticate(&wrong_issuer).await?);
+
+        let expired = token(
+            "abc",
+            json!({
+                "sub": "alice",
+                "iss": "https://idp.example.com",
+                "aud": "tansu",
+                "exp": 1_000_000_000,
+            }),
+        )?;
+        assert_eq!(None, bearer.authenticate(&expired).await?);
+
+        assert_eq!(None, bearer.authenticate("not.a.token").await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_kid_does_not_refetch() -> Result<()> {
+        let bearer = bearer_with_keys()?;
+
+        let claims = json!({
+            "sub": "alice",
+            "iss": "https://idp.example.com",
+            "aud": "tansu",
+            "exp": expiry(),
+        });
+
+        // the key set was fetched within the interval, so ./jwks.json (which
+        // does not exist) is never read and the token is simply rejected
+        for kid in ["pqr", "stu", "pqr"] {
+            assert_eq!(
+                None,
+                bearer.authenticate(&token(kid, claims.clone())?).await?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn refetch_limited() {
+        let interval = Duration::from_secs(30);
+        let start = Instant::now();
+
+        let mut keys = Keys::default();
+        assert!(keys.refetch(Some("abc"), interval, start));
+
+        keys.replace(JwkSet { keys: vec![] }, start);
+        keys.unknown(Some("abc"), start);
+
+        let later = start + Duration::from_secs(10);
+        assert!(!keys.refetch(Some("abc"), interval, later));
+        assert!(!keys.refetch(Some("def"), interval, later));
+
+        let after = start + interval;
+        assert!(keys.refetch(Some("abc"), interval, after));
+        assert!(keys.unknown.is_empty());
+    }
+
+    #[test]
+    fn unknown_kids_bounded() {
+        let now = Instant::now();
+        let mut keys = Keys::default();
+
+        for kid in 0..=UNKNOWN_KIDS {
+            keys.unknown(Some(&kid.to_string()), now);
+        }
+
+        assert!(keys.unknown.len() <= UNKNOWN_KIDS);
+    }
+}
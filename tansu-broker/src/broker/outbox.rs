@@ -0,0 +1,284 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transactional outbox ingestion
+//!
+//! A topic with a `tansu.outbox.url` topic config tails an outbox table in a
+//! Postgres (13 or later) database, e.g.,
+//! `postgres://postgres:postgres@db/app`, producing each new row into the
+//! topic. The table (`tansu.outbox.table`, by default `outbox`) has an
+//! increasing `id`, the id of the transaction inserting each row (`tx`), with
+//! a nullable `key` and `value`:
+//!
+//! ```sql
+//! create table outbox (
+//!     id bigserial primary key,
+//!     tx xid8 not null default pg_current_xact_id(),
+//!     key bytea,
+//!     value bytea
+//! );
+//! ```
+//!
+//! Ids are drawn when rows are inserted rather than when they are committed,
+//! so a row may become visible after rows with a greater id. Rows are instead
+//! read in the order of the transaction inserting them, once that transaction
+//! is older than every transaction still in progress
+//! (`pg_snapshot_xmin(pg_current_snapshot())`), after which no further rows
+//! of an earlier transaction can become visible.
+//!
+//! A row with a key is produced to the partition chosen by the default Kafka
+//! partitioner, otherwise rows are spread over the partitions by their id.
+//!
+//! The rows are produced in a transaction that also commits the transaction
+//! id of the last row as the offset of the `tansu.outbox` group on partition
+//! 0 of the topic. With each broker polling the outbox using the same
+//! transactional id, a broker that has been fenced is unable to commit, so
+//! that each row is produced exactly once.
+//!
+//! As the URL may embed credentials, `tansu.outbox.url` is sensitive and may
+//! be a `secret://` reference to an environment variable or mounted file.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use bytes::Bytes;
+use deadpool_postgres::Pool;
+use tansu_sans_io::ConfigResource;
+use tansu_storage::{Secret, Storage};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    Error, Result,
+    broker::{
+        checkpoint::{Checkpointed, Records, partition, topics},
+        postgres,
+    },
+};
+
+pub const OUTBOX_URL: &str = "tansu.outbox.url";
+pub const OUTBOX_TABLE: &str = "tansu.outbox.table";
+
+/// The group with the id of the last row produced from an outbox
+pub const OUTBOX_GROUP: &str = "tansu.outbox";
+
+const DEFAULT_TABLE: &str = "outbox";
+
+/// The maximum number of outbox transactions produced in each transaction
+const MAX_TRANSACTIONS: i64 = 1_000;
+
+/// The outbox of a topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Outbox {
+    url: String,
+    table: String,
+}
+
+impl Outbox {
+    /// The outbox for a topic from its configuration, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        let configs = storage
+            .describe_config(
+                topic,
+                ConfigResource::Topic,
+                Some(&[OUTBOX_URL.to_owned(), OUTBOX_TABLE.to_owned()]),
+            )
+            .await?
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|config| config.value.map(|value| (config.name, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        configs
+            .get(OUTBOX_URL)
+            .map(|url| {
+                let table = configs
+                    .get(OUTBOX_TABLE)
+                    .cloned()
+                    .unwrap_or(DEFAULT_TABLE.into());
+
+                if !is_identifier(&table) {
+                    return Err(Error::Message(format!("invalid outbox table: {table}")));
+                }
+
+                Secret::resolve(url).map_err(Into::into).map(|url| Self {
+                    url: url.into_owned(),
+                    table,
+                })
+            })
+            .transpose()
+    }
+
+    /// The rows of complete transactions after the last produced, with the
+    /// rows of each transaction read together
+    fn query(&self) -> String {
+        format!(
+            "with complete as (\
+                select distinct tx from {table} \
+                where tx > greatest($1::bigint, 0)::text::xid8 \
+                and tx < pg_snapshot_xmin(pg_current_snapshot()) \
+                order by tx \
+                limit $2\
+            ) \
+            select o.tx::text::bigint, o.id, o.key, o.value \
+            from {table} o \
+            join complete using (tx) \
+            order by o.tx, o.id",
+            table = self.table
+        )
+    }
+}
+
+/// A table name, optionally qualified by its schema
+pub(crate) fn is_identifier(table: &str) -> bool {
+    table.split('.').count() <= 2
+        && table.split('.').all(|part| {
+            part.chars()
+                .next()
+                .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Connection pools to Postgres databases, by URL
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Pools(BTreeMap<String, Pool>);
+
+impl Pools {
+    pub(crate) fn get(&mut self, url: &str) -> Result<Pool> {
+        if let Some(pool) = self.0.get(url) {
+            return Ok(pool.clone());
+        }
+
+        let pool = postgres::pool(url, 1)?;
+
+        _ = self.0.insert(url.to_owned(), pool.clone());
+
+        Ok(pool)
+    }
+}
+
+/// Produces the rows of outbox tables into their topics
+#[derive(Clone, Debug)]
+pub struct OutboxPoller<S> {
+    storage: S,
+    interval: Duration,
+    pools: Pools,
+    cancellation: CancellationToken,
+}
+
+impl<S> OutboxPoller<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            pools: Pools::default(),
+            cancellation,
+        }
+    }
+
+    pub async fn serve(mut self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .poll()
+                        .await
+                        .inspect(|produced| debug!(produced))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Produce the new rows of each outbox, returning the number of rows produced
+    #[instrument(skip(self))]
+    pub async fn poll(&mut self) -> Result<u64> {
+        let mut produced = 0;
+
+        for (name, partitions) in topics(&self.storage).await? {
+            let Some(outbox) = Outbox::describe(&self.storage, &name).await? else {
+                continue;
+            };
+
+            produced += self
+                .topic(&name, &outbox, partitions)
+                .await
+                .inspect_err(|err| warn!(topic = name, table = %outbox.table, ?err))
+                .unwrap_or_default();
+        }
+
+        Ok(produced)
+    }
+
+    async fn topic(&mut self, name: &str, outbox: &Outbox, partitions: i32) -> Result<u64> {
+        let (transaction, last_tx) = Checkpointed::begin(&self.storage, OUTBOX_GROUP, name).await?;
+
+        let client = self.pools.get(&outbox.url)?.get().await?;
+
+        let rows = client
+            .query(&outbox.query(), &[&last_tx, &MAX_TRANSACTIONS])
+            .await?;
+
+        let Some(last_tx) = rows.last().map(|row| row.get::<_, i64>(0)) else {
+            return Ok(0);
+        };
+
+        debug!(name, last_tx, rows = rows.len());
+
+        let mut records = Records::new();
+
+        for row in rows {
+            let key = row.get::<_, Option<Vec<u8>>>(2).map(Bytes::from);
+            let value = row.get::<_, Option<Vec<u8>>>(3).map(Bytes::from);
+
+            records
+                .entry(partition(row.get(1), key.as_deref(), partitions))
+                .or_default()
+                .push((key, value));
+        }
+
+        transaction.commit(records, last_tx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifier() {
+        assert!(is_identifier("outbox"));
+        assert!(is_identifier("app.outbox_events"));
+        assert!(!is_identifier("outbox; drop table users"));
+        assert!(!is_identifier("a.b.c"));
+        assert!(!is_identifier(""));
+    }
+}
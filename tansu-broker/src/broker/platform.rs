@@ -0,0 +1,177 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! File and network access made by the broker itself
+//!
+//! The listeners bound and the files read by the broker go through this
+//! module, so that a distribution running tansu under a strict seccomp or
+//! AppArmor profile has a single place to audit, with each [`Access`] logged
+//! as it is made. Connections made by a client library (storage engines,
+//! Postgres, HTTP) are described by their URL instead, which is how
+//! `tansu broker --dry-run` lists every access of a configuration without
+//! making any of them.
+
+use std::{fmt, fs, io, net::SocketAddr, os::unix::fs::FileTypeExt as _, path::Path};
+
+use tokio::net::{TcpListener, UnixListener};
+use tracing::debug;
+use url::Url;
+
+/// How the broker accesses a path or endpoint
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Mode {
+    Listen,
+    Connect,
+    Read,
+    ReadWrite,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Listen => "listen",
+            Self::Connect => "connect",
+            Self::Read => "read",
+            Self::ReadWrite => "read-write",
+        })
+    }
+}
+
+/// A path or endpoint accessed by the broker
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Access {
+    mode: Mode,
+    target: String,
+    purpose: &'static str,
+}
+
+impl Access {
+    /// Connect to an endpoint, or read (and write) a file:// or sqlite:// URL
+    pub fn url(url: &Url, writes: bool, purpose: &'static str) -> Option<Self> {
+        let (mode, target) = match url.scheme() {
+            "memory" | "storage" => return None,
+            "slatedb" if url.host_str() == Some("memory") => return None,
+
+            "file" | "sqlite" => (
+                if writes { Mode::ReadWrite } else { Mode::Read },
+                url.path().to_owned(),
+            ),
+
+            "s3" | "slatedb" => (
+                Mode::Connect,
+                format!("s3://{}", url.host_str().unwrap_or("tansu")),
+            ),
+
+            scheme => (
+                Mode::Connect,
+                format!(
+                    "{scheme}://{}:{}",
+                    url.host_str().unwrap_or("localhost"),
+                    url.port_or_known_default()
+                        .map_or_else(|| "-".into(), |port| port.to_string())
+                ),
+            ),
+        };
+
+        Some(Self {
+            mode,
+            target,
+            purpose,
+        })
+    }
+
+    /// Listen on the Unix domain socket of a `unix://` URL, otherwise on a TCP port
+    pub fn listen(url: &Url, default_port: u16, purpose: &'static str) -> Self {
+        Self {
+            mode: Mode::Listen,
+            target: if url.scheme() == "unix" {
+                format!("unix:{}", url.path())
+            } else {
+                format!(
+                    "tcp:{}:{}",
+                    url.host_str().unwrap_or("[::]"),
+                    url.port().unwrap_or(default_port)
+                )
+            },
+            purpose,
+        }
+    }
+
+    /// Read a file
+    pub fn path(path: &Path, purpose: &'static str) -> Self {
+        Self {
+            mode: Mode::Read,
+            target: path.display().to_string(),
+            purpose,
+        }
+    }
+
+    fn tcp(addr: SocketAddr, purpose: &'static str) -> Self {
+        Self {
+            mode: Mode::Listen,
+            target: format!("tcp:{addr}"),
+            purpose,
+        }
+    }
+
+    fn unix(path: &Path, purpose: &'static str) -> Self {
+        Self {
+            mode: Mode::Listen,
+            target: format!("unix:{}", path.display()),
+            purpose,
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<10} {:<48} {}", self.mode, self.target, self.purpose)
+    }
+}
+
+/// Listen on a TCP port
+pub(crate) async fn listen_tcp(addr: SocketAddr, purpose: &'static str) -> io::Result<TcpListener> {
+    debug!(access = %Access::tcp(addr, purpose));
+    TcpListener::bind(addr).await
+}
+
+/// Listen on a Unix domain socket, replacing a socket left behind by a
+/// previous broker that would otherwise prevent binding
+pub(crate) fn listen_unix(path: &Path, purpose: &'static str) -> io::Result<UnixListener> {
+    debug!(access = %Access::unix(path, purpose));
+
+    if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+
+    UnixListener::bind(path)
+}
+
+/// Read the contents of a file
+pub(crate) async fn read(path: &Path, purpose: &'static str) -> io::Result<Vec<u8>> {
+    debug!(access = %Access::path(path, purpose));
+    tokio::fs::read(path).await
+}
+
+/// Read the contents of a file synchronously
+pub(crate) fn read_blocking(path: &Path, purpose: &'static str) -> io::Result<Vec<u8>> {
+    debug!(access = %Access::path(path, purpose));
+    fs::read(path)
+}
+
+/// Read the contents of a UTF-8 file
+pub(crate) async fn read_to_string(path: &Path, purpose: &'static str) -> io::Result<String> {
+    debug!(access = %Access::path(path, purpose));
+    tokio::fs::read_to_string(path).await
+}
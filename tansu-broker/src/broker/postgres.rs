@@ -0,0 +1,64 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Postgres connections made by the broker itself
+//!
+//! The `sslmode` of a URL is honoured: `disable` connects without TLS, while
+//! `prefer` (the default) and `require` use TLS verified against the webpki
+//! roots, with `prefer` falling back to plain text when the server does not
+//! support TLS.
+
+use std::{str::FromStr, sync::Arc};
+
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use tokio_postgres::{Config, NoTls, config::SslMode};
+use tokio_postgres_rustls::MakeRustlsConnect;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto::ring};
+
+use crate::{Error, Result};
+
+fn rustls() -> Result<MakeRustlsConnect> {
+    ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map(|builder| {
+            MakeRustlsConnect::new(
+                builder
+                    .with_root_certificates(RootCertStore {
+                        roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+                    })
+                    .with_no_client_auth(),
+            )
+        })
+        .map_err(|err| Error::Message(err.to_string()))
+}
+
+/// A pool of connections to the database of a URL
+pub(crate) fn pool(url: &str, max_size: usize) -> Result<Pool> {
+    let config = Config::from_str(url)?;
+
+    let manager_config = ManagerConfig {
+        recycling_method: RecyclingMethod::Fast,
+    };
+
+    let manager = if config.get_ssl_mode() == SslMode::Disable {
+        Manager::from_config(config, NoTls, manager_config)
+    } else {
+        Manager::from_config(config, rustls()?, manager_config)
+    };
+
+    Pool::builder(manager)
+        .max_size(max_size)
+        .build()
+        .map_err(|err| Error::Message(err.to_string()))
+}
@@ -0,0 +1,230 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Startup preflight checks
+//!
+//! Before accepting requests the broker verifies the conditions that it
+//! depends on, rather than erroring on the first client request:
+//!
+//! - storage is reachable, with any conditions of the storage engine itself,
+//!   such as an applied PostgreSQL schema or an object store supporting
+//!   conditional writes
+//! - the Kafka (and admin) listeners can be bound
+//! - the TLS certificate chain and private key are readable and match
+//! - the system clock is sane
+//!
+//! Every check is reported, with a remedy for each that failed. The broker
+//! does not start while any check has failed.
+
+use std::{
+    fmt,
+    io::ErrorKind,
+    net::SocketAddr,
+    time::{Duration, SystemTime},
+};
+
+use tansu_storage::{PreflightCheck, Storage};
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+use crate::broker::tls::Tls;
+
+/// No clock showing a time earlier than this (2024-01-01) is sane
+const EARLIEST: Duration = Duration::from_secs(1_704_067_200);
+
+/// The outcome of every preflight check
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report(Vec<PreflightCheck>);
+
+impl Report {
+    /// Whether every check has passed
+    pub fn passed(&self) -> bool {
+        self.0.iter().all(|check| check.passed)
+    }
+
+    pub fn checks(&self) -> &[PreflightCheck] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.0 {
+            writeln!(
+                f,
+                "[{}] {}: {}",
+                if check.passed { "pass" } else { "fail" },
+                check.name,
+                check.detail
+            )?;
+
+            if let Some(ref remedy) = check.remedy {
+                writeln!(f, "       remedy: {remedy}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Storage is reachable, followed by the checks of the storage engine
+async fn storage<S>(storage: &S) -> Vec<PreflightCheck>
+where
+    S: Storage,
+{
+    const NAME: &str = "storage";
+
+    if let Err(err) = storage.ping().await {
+        return vec![PreflightCheck::failed(
+            NAME,
+            err.to_string(),
+            "check the storage engine URL, and that storage is running and reachable from this broker",
+        )];
+    }
+
+    let mut checks = vec![PreflightCheck::passed(NAME, "reachable")];
+
+    match storage.preflight().await {
+        Ok(engine) => checks.extend(engine),
+        Err(err) => checks.push(PreflightCheck::failed(
+            NAME,
+            err.to_string(),
+            "check that the storage engine is initialised for this version of tansu",
+        )),
+    }
+
+    checks
+}
+
+/// A listener can be bound, releasing it immediately
+async fn listener(name: &str, addr: SocketAddr) -> PreflightCheck {
+    match TcpListener::bind(addr).await {
+        Ok(_) => PreflightCheck::passed(name, format!("{addr} available")),
+
+        Err(err) => PreflightCheck::failed(
+            name,
+            format!("{addr}: {err}"),
+            match err.kind() {
+                ErrorKind::AddrInUse => {
+                    format!("stop the process already listening on {addr}, or use another port")
+                }
+                ErrorKind::PermissionDenied => {
+                    "use a port above 1023, or grant the broker CAP_NET_BIND_SERVICE".into()
+                }
+                ErrorKind::AddrNotAvailable => {
+                    "listen on an address of this host, or 0.0.0.0 for every address".into()
+                }
+                _ => "check the host and port of the listener URL".into(),
+            },
+        ),
+    }
+}
+
+fn tls(tls: &Tls) -> PreflightCheck {
+    const NAME: &str = "tls";
+
+    match tls.verify() {
+        Ok(()) => PreflightCheck::passed(NAME, "certificate and private key match"),
+        Err(err) => PreflightCheck::failed(
+            NAME,
+            err.to_string(),
+            "check that the PEM certificate chain and private key are readable and form a pair",
+        ),
+    }
+}
+
+fn clock(now: SystemTime) -> PreflightCheck {
+    const NAME: &str = "clock";
+    const REMEDY: &str = "synchronise the system clock, for example with NTP";
+
+    match now.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since_epoch) if since_epoch >= EARLIEST => {
+            PreflightCheck::passed(NAME, format!("{}s since epoch", since_epoch.as_secs()))
+        }
+
+        Ok(since_epoch) => PreflightCheck::failed(
+            NAME,
+            format!("{}s since epoch is in the past", since_epoch.as_secs()),
+            REMEDY,
+        ),
+
+        Err(err) => PreflightCheck::failed(NAME, err.to_string(), REMEDY),
+    }
+}
+
+/// Run every preflight check, logging the outcome of each
+pub async fn run<S>(storage: &S, listeners: &[(&str, SocketAddr)], tls: Option<&Tls>) -> Report
+where
+    S: Storage,
+{
+    let mut checks = vec![clock(SystemTime::now())];
+
+    checks.extend(self::storage(storage).await);
+
+    for (name, addr) in listeners {
+        checks.push(listener(name, *addr).await);
+    }
+
+    checks.extend(tls.map(self::tls));
+
+    for check in &checks {
+        if check.passed {
+            info!(check = %check.name, detail = %check.detail);
+        } else {
+            error!(
+                check = %check.name,
+                detail = %check.detail,
+                remedy = ?check.remedy
+            );
+        }
+    }
+
+    Report(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_sanity() {
+        assert!(clock(SystemTime::now()).passed);
+        assert!(!clock(SystemTime::UNIX_EPOCH).passed);
+        assert!(!clock(SystemTime::UNIX_EPOCH + Duration::from_secs(86_400)).passed);
+    }
+
+    #[tokio::test]
+    async fn listener_in_use() {
+        let bound = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = bound.local_addr().unwrap();
+
+        let check = listener("listener", addr).await;
+        assert!(!check.passed);
+        assert!(check.remedy.is_some_and(|remedy| remedy.contains("stop")));
+    }
+
+    #[test]
+    fn report() {
+        let report = Report(vec![
+            PreflightCheck::passed("clock", "ok"),
+            PreflightCheck::failed("storage", "refused", "start it"),
+        ]);
+
+        assert!(!report.passed());
+        assert_eq!(
+            "[pass] clock: ok\n[fail] storage: refused\n       remedy: start it\n",
+            report.to_string()
+        );
+    }
+}
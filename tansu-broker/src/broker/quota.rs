@@ -0,0 +1,461 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client quotas
+//!
+//! The `producer_byte_rate` and `consumer_byte_rate` client quotas, maintained
+//! with `AlterClientQuotas`, are enforced for the user and client id of each
+//! produce and fetch. As with Kafka, the most specific quota for the user and
+//! client id applies, with a default entity standing in for any user or client
+//! id without its own quota.
+//!
+//! Each quota is a token bucket holding up to one second of bytes, shared by
+//! every connection of the same user and/or client id. Produced bytes are
+//! withdrawn from the bucket of the producer, while fetched bytes are withdrawn
+//! from the bucket of the consumer. A request overdrawing its bucket is served
+//! as usual, with a `throttle_time_ms` in its response for the client to back
+//! off until the bucket is no longer overdrawn.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use opentelemetry::{KeyValue, metrics::Counter};
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    AlterClientQuotasRequest, ApiKey, Body, FetchRequest, FetchResponse, Frame, ProduceRequest,
+};
+use tansu_storage::{ClientQuota, ClientQuotaEntity, Storage};
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    Error, METER, Result,
+    broker::{authorizer::Identity, throttle::ingress},
+};
+
+pub const PRODUCER_BYTE_RATE: &str = "producer_byte_rate";
+pub const CONSUMER_BYTE_RATE: &str = "consumer_byte_rate";
+
+/// How long client quotas are cached before being reloaded from storage
+const QUOTAS_TTL: Duration = Duration::from_secs(5);
+
+static CLIENT_QUOTA_THROTTLED: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_client_quota_throttled")
+        .with_description("requests throttled by a client quota")
+        .build()
+});
+
+/// The most specific quota for a user and client id, in the precedence used by Kafka
+fn resolve<'a>(
+    quotas: &'a [ClientQuota],
+    key: &str,
+    user: &str,
+    client_id: &str,
+) -> Option<&'a ClientQuota> {
+    let precedence = [
+        (Some(Some(user)), Some(Some(client_id))),
+        (Some(Some(user)), Some(None)),
+        (Some(Some(user)), None),
+        (Some(None), Some(Some(client_id))),
+        (Some(None), Some(None)),
+        (Some(None), None),
+        (None, Some(Some(client_id))),
+        (None, Some(None)),
+    ];
+
+    precedence.into_iter().find_map(|(user, client_id)| {
+        let entity = user
+            .map(|name| (ClientQuotaEntity::USER, name))
+            .into_iter()
+            .chain(client_id.map(|name| (ClientQuotaEntity::CLIENT_ID, name)))
+            .map(|(entity_type, name)| (entity_type.to_owned(), name.map(str::to_owned)))
+            .collect::<ClientQuotaEntity>();
+
+        quotas
+            .iter()
+            .find(|quota| quota.key == key && quota.entity == entity)
+    })
+}
+
+/// Tokens available to a quota, which become negative when overdrawn
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self {
+            tokens: rate,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, rate: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + rate * elapsed).min(rate);
+        self.updated = now;
+    }
+
+    /// Withdraw tokens, returning the time until this bucket is no longer overdrawn
+    fn withdraw(&mut self, rate: f64, bytes: u64, now: Instant) -> Duration {
+        self.refill(rate, now);
+        self.tokens -= bytes as f64;
+
+        if rate > 0.0 && self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// The bucket of a quota, with the user and client id that share it
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct BucketKey {
+    key: String,
+    user: Option<String>,
+    client_id: Option<String>,
+}
+
+impl BucketKey {
+    fn new(quota: &ClientQuota, user: &str, client_id: &str) -> Self {
+        let shared = |entity_type: &str, name: &str| {
+            quota
+                .entity
+                .0
+                .contains_key(entity_type)
+                .then(|| name.to_owned())
+        };
+
+        Self {
+            key: quota.key.clone(),
+            user: shared(ClientQuotaEntity::USER, user),
+            client_id: shared(ClientQuotaEntity::CLIENT_ID, client_id),
+        }
+    }
+}
+
+type Buckets = Arc<Mutex<BTreeMap<BucketKey, Bucket>>>;
+
+type Cache = Arc<Mutex<Option<(Instant, Arc<[ClientQuota]>)>>>;
+
+/// Bytes being fetched by a consumer
+fn egress(fetch: &FetchResponse) -> u64 {
+    fetch
+        .responses
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|topic| topic.partitions.as_deref().unwrap_or_default())
+        .filter_map(|partition| partition.records.as_ref())
+        .flat_map(|frame| frame.batches.iter())
+        .map(|batch| u64::try_from(batch.batch_length).unwrap_or_default())
+        .sum()
+}
+
+fn throttle_time_ms(existing: Option<i32>, throttle: Duration) -> Option<i32> {
+    let throttle = i32::try_from(throttle.as_millis()).unwrap_or(i32::MAX);
+    existing.map(|existing| existing.max(throttle))
+}
+
+/// A [`Layer`] enforcing the client quotas held in storage
+#[derive(Clone, Debug)]
+pub struct ClientQuotaLayer<G> {
+    storage: G,
+    cache: Cache,
+    buckets: Buckets,
+}
+
+impl<G> ClientQuotaLayer<G> {
+    pub fn new(storage: G) -> Self {
+        Self {
+            storage,
+            cache: Cache::default(),
+            buckets: Buckets::default(),
+        }
+    }
+}
+
+impl<G, S> Layer<S> for ClientQuotaLayer<G>
+where
+    G: Clone,
+{
+    type Service = ClientQuotaService<G, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientQuotaService {
+            storage: self.storage.clone(),
+            cache: self.cache.clone(),
+            buckets: self.buckets.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] throttling produce and fetch requests exceeding a client
+/// quota, with quotas and buckets shared by every connection using this service
+#[derive(Clone, Debug)]
+pub struct ClientQuotaService<G, S> {
+    storage: G,
+    cache: Cache,
+    buckets: Buckets,
+    inner: S,
+}
+
+impl<G, S> ClientQuotaService<G, S>
+where
+    G: Storage,
+{
+    fn cached(&self, now: Instant) -> Option<Arc<[ClientQuota]>> {
+        self.cache.lock().ok().and_then(|guard| {
+            guard
+                .as_ref()
+                .filter(|(loaded, _)| now.saturating_duration_since(*loaded) < QUOTAS_TTL)
+                .map(|(_, quotas)| quotas.clone())
+        })
+    }
+
+    async fn quotas(&self) -> Result<Arc<[ClientQuota]>> {
+        let now = Instant::now();
+
+        if let Some(quotas) = self.cached(now) {
+            return Ok(quotas);
+        }
+
+        let quotas = Arc::<[ClientQuota]>::from(self.storage.client_quotas().await?);
+
+        if let Ok(mut guard) = self.cache.lock() {
+            *guard = Some((now, quotas.clone()));
+        }
+
+        Ok(quotas)
+    }
+
+    fn quotas_changed(&self) {
+        if let Ok(mut guard) = self.cache.lock() {
+            *guard = None;
+        }
+    }
+
+    /// Withdraw bytes from the bucket of the applicable quota, returning the throttle time
+    async fn throttle(&self, key: &str, user: &str, client_id: &str, bytes: u64) -> Duration {
+        let Ok(quotas) = self.quotas().await.inspect_err(|err| warn!(?err)) else {
+            return Duration::ZERO;
+        };
+
+        let Some(quota) = resolve(&quotas, key, user, client_id) else {
+            return Duration::ZERO;
+        };
+
+        let now = Instant::now();
+
+        let throttle = self.buckets.lock().map_or(Duration::ZERO, |mut guard| {
+            guard
+                .entry(BucketKey::new(quota, user, client_id))
+                .or_insert_with(|| Bucket::new(quota.value, now))
+                .withdraw(quota.value, bytes, now)
+        });
+
+        debug!(key, user, client_id, bytes, ?quota.entity, quota.value, ?throttle);
+
+        if !throttle.is_zero() {
+            CLIENT_QUOTA_THROTTLED.add(1, &[KeyValue::new("quota", key.to_owned())]);
+        }
+
+        throttle
+    }
+}
+
+impl<G, S, State> Service<State, Frame> for ClientQuotaService<G, S>
+where
+    G: Storage,
+    S: Service<State, Frame, Response = Frame, Error = Error>,
+    State: Send + Sync + 'static,
+{
+    type Response = Frame;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(&self, ctx: Context<State>, req: Frame) -> Result<Self::Response, Self::Error> {
+        let api_key = req.api_key()?;
+
+        if api_key == AlterClientQuotasRequest::KEY {
+            return self
+                .inner
+                .serve(ctx, req)
+                .await
+                .inspect(|_| self.quotas_changed());
+        }
+
+        if api_key != ProduceRequest::KEY && api_key != FetchRequest::KEY {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        let identity = Identity::of(&ctx);
+        let principal = identity.principal();
+        let user = principal
+            .strip_prefix("User:")
+            .unwrap_or(principal)
+            .to_owned();
+        let client_id = req.client_id()?.unwrap_or_default().to_owned();
+
+        let produced = if let Body::ProduceRequest(produce) = &req.body {
+            produce
+                .topic_data
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|topic| ingress(topic).1)
+                .sum()
+        } else {
+            0
+        };
+
+        let mut response = self.inner.serve(ctx, req).await?;
+
+        match &mut response.body {
+            Body::ProduceResponse(produce) => {
+                let throttle = self
+                    .throttle(PRODUCER_BYTE_RATE, &user, &client_id, produced)
+                    .await;
+
+                produce.throttle_time_ms = throttle_time_ms(produce.throttle_time_ms, throttle);
+            }
+
+            Body::FetchResponse(fetch) => {
+                let throttle = self
+                    .throttle(CONSUMER_BYTE_RATE, &user, &client_id, egress(fetch))
+                    .await;
+
+                fetch.throttle_time_ms = throttle_time_ms(fetch.throttle_time_ms, throttle);
+            }
+
+            _otherwise => (),
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(entity: &[(&str, Option<&str>)], key: &str, value: f64) -> ClientQuota {
+        ClientQuota {
+            entity: entity
+                .iter()
+                .map(|(entity_type, name)| ((*entity_type).to_owned(), name.map(str::to_owned)))
+                .collect(),
+            key: key.to_owned(),
+            value,
+        }
+    }
+
+    #[test]
+    fn most_specific_quota_applies() {
+        let quotas = [
+            quota(
+                &[(ClientQuotaEntity::CLIENT_ID, None)],
+                PRODUCER_BYTE_RATE,
+                1.0,
+            ),
+            quota(&[(ClientQuotaEntity::USER, None)], PRODUCER_BYTE_RATE, 2.0),
+            quota(
+                &[(ClientQuotaEntity::USER, Some("alice"))],
+                PRODUCER_BYTE_RATE,
+                3.0,
+            ),
+            quota(
+                &[
+                    (ClientQuotaEntity::USER, Some("alice")),
+                    (ClientQuotaEntity::CLIENT_ID, Some("console")),
+                ],
+                PRODUCER_BYTE_RATE,
+                4.0,
+            ),
+        ];
+
+        let value = |user, client_id| {
+            resolve(&quotas, PRODUCER_BYTE_RATE, user, client_id).map(|quota| quota.value)
+        };
+
+        assert_eq!(Some(4.0), value("alice", "console"));
+        assert_eq!(Some(3.0), value("alice", "other"));
+        assert_eq!(Some(2.0), value("bob", "console"));
+        assert_eq!(
+            None,
+            resolve(&quotas, CONSUMER_BYTE_RATE, "alice", "console").map(|quota| quota.value)
+        );
+    }
+
+    #[test]
+    fn default_client_id_quota() {
+        let quotas = [quota(
+            &[(ClientQuotaEntity::CLIENT_ID, None)],
+            CONSUMER_BYTE_RATE,
+            1_024.0,
+        )];
+
+        let quota = resolve(&quotas, CONSUMER_BYTE_RATE, "ANONYMOUS", "console").unwrap();
+        assert_eq!(1_024.0, quota.value);
+
+        assert_eq!(
+            BucketKey {
+                key: CONSUMER_BYTE_RATE.to_owned(),
+                user: None,
+                client_id: Some("console".to_owned()),
+            },
+            BucketKey::new(quota, "ANONYMOUS", "console")
+        );
+    }
+
+    #[test]
+    fn overdrawn_is_throttled() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(1_000.0, now);
+
+        assert_eq!(Duration::ZERO, bucket.withdraw(1_000.0, 1_000, now));
+        assert_eq!(
+            Duration::from_millis(500),
+            bucket.withdraw(1_000.0, 500, now)
+        );
+        assert_eq!(
+            Duration::from_millis(250),
+            bucket.withdraw(1_000.0, 0, now + Duration::from_millis(250))
+        );
+        assert_eq!(
+            Duration::ZERO,
+            bucket.withdraw(1_000.0, 0, now + Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn throttle_time_is_the_larger() {
+        assert_eq!(
+            Some(100),
+            throttle_time_ms(Some(100), Duration::from_millis(50))
+        );
+        assert_eq!(
+            Some(200),
+            throttle_time_ms(Some(100), Duration::from_millis(200))
+        );
+        assert_eq!(None, throttle_time_ms(None, Duration::from_millis(200)));
+    }
+}
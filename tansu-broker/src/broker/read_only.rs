@@ -0,0 +1,247 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read only topics
+//!
+//! A topic with `tansu.read_only` set to `true` rejects produce with
+//! `PolicyViolation`, while fetch continues to work, e.g., during maintenance
+//! or a migration. The error message includes `tansu.read_only.reason` when
+//! set. Both are topic configs, altered with IncrementalAlterConfigs or the
+//! `/topic-read-only` endpoint of the admin listener.
+
+use rama::{Context, Layer, Service};
+use serde::Serialize;
+use tansu_sans_io::{
+    ConfigResource, ErrorCode, OpType, ProduceRequest, ProduceResponse,
+    incremental_alter_configs_request::{AlterConfigsResource, AlterableConfig},
+    produce_request::TopicProduceData,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+};
+use tansu_storage::Storage;
+use tracing::{debug, instrument, warn};
+
+use crate::{Result, broker::topic_config::TopicConfig};
+
+pub const READ_ONLY: &str = "tansu.read_only";
+pub const READ_ONLY_REASON: &str = "tansu.read_only.reason";
+
+/// Whether a topic is read only, and why
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ReadOnly {
+    pub topic: String,
+    pub read_only: bool,
+    pub reason: Option<String>,
+}
+
+impl ReadOnly {
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Self>
+    where
+        S: Storage,
+    {
+        TopicConfig::describe(storage, topic)
+            .await
+            .map(|config| Self::from_config(topic, &config))
+            .map_err(Into::into)
+    }
+
+    /// Whether a topic is read only from its configuration
+    pub fn from_config(topic: &str, config: &TopicConfig) -> Self {
+        Self {
+            topic: topic.to_owned(),
+            read_only: config
+                .get(READ_ONLY)
+                .is_some_and(|value| value.eq_ignore_ascii_case("true")),
+            reason: config
+                .get(READ_ONLY_REASON)
+                .filter(|reason| !reason.is_empty())
+                .map(str::to_owned),
+        }
+    }
+
+    /// The config alterations making this topic read only, or writable again
+    pub fn alter(&self) -> AlterConfigsResource {
+        let set = |name: &str, value: String| {
+            AlterableConfig::default()
+                .name(name.into())
+                .config_operation(OpType::Set.into())
+                .value(Some(value))
+        };
+
+        let delete = |name: &str| {
+            AlterableConfig::default()
+                .name(name.into())
+                .config_operation(OpType::Delete.into())
+                .value(None)
+        };
+
+        AlterConfigsResource::default()
+            .resource_type(ConfigResource::Topic.into())
+            .resource_name(self.topic.clone())
+            .configs(Some(if self.read_only {
+                vec![
+                    set(READ_ONLY, "true".into()),
+                    self.reason.clone().map_or_else(
+                        || delete(READ_ONLY_REASON),
+                        |reason| set(READ_ONLY_REASON, reason),
+                    ),
+                ]
+            } else {
+                vec![delete(READ_ONLY), delete(READ_ONLY_REASON)]
+            }))
+    }
+
+    fn message(&self) -> String {
+        self.reason.as_deref().map_or_else(
+            || format!("topic {} is read only", self.topic),
+            |reason| format!("topic {} is read only: {reason}", self.topic),
+        )
+    }
+}
+
+fn read_only(topic: TopicProduceData, message: String) -> TopicProduceResponse {
+    TopicProduceResponse::default()
+        .name(topic.name)
+        .partition_responses(Some(
+            topic
+                .partition_data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    PartitionProduceResponse::default()
+                        .index(partition.index)
+                        .error_code(ErrorCode::PolicyViolation.into())
+                        .base_offset(-1)
+                        .log_append_time_ms(Some(-1))
+                        .log_start_offset(Some(0))
+                        .record_errors(Some([].into()))
+                        .error_message(Some(message.clone()))
+                        .current_leader(None)
+                })
+                .collect(),
+        ))
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReadOnlyTopicLayer;
+
+impl<S> Layer<S> for ReadOnlyTopicLayer {
+    type Service = ReadOnlyTopicService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReadOnlyTopicService { inner }
+    }
+}
+
+/// A [`Service`] rejecting a produce to a read only topic
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReadOnlyTopicService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for ReadOnlyTopicService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut accepted = vec![];
+        let mut rejected = vec![];
+
+        for topic in req.topic_data.take().unwrap_or_default() {
+            match TopicConfig::lookup(&ctx, topic.name.as_str())
+                .await
+                .map(|config| ReadOnly::from_config(topic.name.as_str(), &config))
+                .inspect_err(|err| warn!(topic = topic.name, ?err))
+            {
+                Ok(read_only) if read_only.read_only => {
+                    debug!(?read_only);
+                    rejected.push(self::read_only(topic, read_only.message()));
+                }
+
+                _ => accepted.push(topic),
+            }
+        }
+
+        req.topic_data = Some(accepted);
+
+        self.inner.serve(ctx, req).await.map(|mut response| {
+            if !rejected.is_empty() {
+                response
+                    .responses
+                    .get_or_insert_default()
+                    .append(&mut rejected);
+            }
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs(read_only: &ReadOnly) -> Vec<(String, i8, Option<String>)> {
+        read_only
+            .alter()
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .map(|config| (config.name, config.config_operation, config.value))
+            .collect()
+    }
+
+    #[test]
+    fn alter() {
+        let frozen = ReadOnly {
+            topic: "abc".into(),
+            read_only: true,
+            reason: Some("migrating".into()),
+        };
+
+        assert_eq!(
+            vec![
+                (READ_ONLY.into(), i8::from(OpType::Set), Some("true".into())),
+                (
+                    READ_ONLY_REASON.into(),
+                    i8::from(OpType::Set),
+                    Some("migrating".into())
+                ),
+            ],
+            configs(&frozen)
+        );
+        assert_eq!("topic abc is read only: migrating", frozen.message());
+
+        let thawed = ReadOnly {
+            topic: "abc".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vec![
+                (READ_ONLY.into(), i8::from(OpType::Delete), None),
+                (READ_ONLY_REASON.into(), i8::from(OpType::Delete), None),
+            ],
+            configs(&thawed)
+        );
+    }
+}
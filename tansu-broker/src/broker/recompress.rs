@@ -0,0 +1,366 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Broker side recompression using the topic `compression.type`
+//!
+//! As with a Kafka broker, `producer` (the default) retains the codec chosen by
+//! the producer, while `uncompressed`, `gzip`, `snappy`, `lz4` or `zstd`
+//! re-encode each produced batch with that codec before it is stored. A
+//! partition with a batch that cannot be re-encoded is rejected with
+//! `CORRUPT_MESSAGE`, rather than being stored with the producer codec.
+
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    BatchAttribute, Compression, ErrorCode, ProduceRequest, ProduceResponse,
+    produce_request::PartitionProduceData,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+    record::{deflated, inflated},
+};
+use tansu_storage::Storage;
+use tracing::{debug, instrument, warn};
+
+use crate::broker::topic_config::TopicConfig;
+
+const COMPRESSION_TYPE: &str = "compression.type";
+
+/// The codec for a `compression.type`, with `None` retaining the producer codec
+fn compression_type(value: &str) -> Option<Compression> {
+    match value {
+        "uncompressed" => Some(Compression::None),
+        "gzip" => Some(Compression::Gzip),
+        "snappy" => Some(Compression::Snappy),
+        "lz4" => Some(Compression::Lz4),
+        "zstd" => Some(Compression::Zstd),
+        _producer => None,
+    }
+}
+
+/// Re-encode a batch with the compression, control batches are left unchanged
+pub fn recompress(
+    batch: deflated::Batch,
+    compression: &Compression,
+) -> tansu_sans_io::Result<deflated::Batch> {
+    let attributes = BatchAttribute::try_from(batch.attributes)?;
+
+    if attributes.control || attributes.compression == *compression {
+        return Ok(batch);
+    }
+
+    inflated::Batch::try_from(batch).and_then(|mut inflated| {
+        inflated.attributes = attributes.compression(compression.to_owned()).into();
+        deflated::Batch::try_from(inflated)
+    })
+}
+
+fn corrupt(index: i32, err: &tansu_sans_io::Error) -> PartitionProduceResponse {
+    PartitionProduceResponse::default()
+        .index(index)
+        .error_code(ErrorCode::CorruptMessage.into())
+        .base_offset(-1)
+        .log_append_time_ms(Some(-1))
+        .log_start_offset(Some(0))
+        .record_errors(Some([].into()))
+        .error_message(Some(format!("unable to recompress batch: {err}")))
+        .current_leader(None)
+}
+
+/// Re-encode every batch of a partition with the compression
+fn recompress_partition(
+    partition: &mut PartitionProduceData,
+    compression: &Compression,
+) -> tansu_sans_io::Result<()> {
+    let Some(frame) = partition.records.as_mut() else {
+        return Ok(());
+    };
+
+    frame.batches = frame
+        .batches
+        .drain(..)
+        .map(|batch| recompress(batch, compression))
+        .collect::<tansu_sans_io::Result<Vec<_>>>()?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RecompressLayer;
+
+impl<S> Layer<S> for RecompressLayer {
+    type Service = RecompressService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecompressService { inner }
+    }
+}
+
+/// A [`Service`] re-encoding produced batches with the topic `compression.type`
+/// before passing the request to the inner service
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RecompressService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for RecompressService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut rejected = vec![];
+
+        for topic in req.topic_data.as_deref_mut().unwrap_or_default() {
+            let Some(compression) = TopicConfig::lookup(&ctx, topic.name.as_str())
+                .await
+                .inspect_err(|err| debug!(topic = topic.name, ?err))
+                .ok()
+                .and_then(|config| config.get(COMPRESSION_TYPE).and_then(compression_type))
+            else {
+                continue;
+            };
+
+            debug!(topic = topic.name, ?compression);
+
+            let mut accepted = vec![];
+            let mut corrupted = vec![];
+
+            for mut partition in topic.partition_data.take().unwrap_or_default() {
+                match recompress_partition(&mut partition, &compression) {
+                    Ok(()) => accepted.push(partition),
+
+                    Err(err) => {
+                        warn!(topic = topic.name, partition = partition.index, ?err);
+                        corrupted.push(corrupt(partition.index, &err));
+                    }
+                }
+            }
+
+            topic.partition_data = Some(accepted);
+
+            if !corrupted.is_empty() {
+                rejected.push(
+                    TopicProduceResponse::default()
+                        .name(topic.name.clone())
+                        .partition_responses(Some(corrupted)),
+                );
+            }
+        }
+
+        self.inner.serve(ctx, req).await.map(|mut response| {
+            if !rejected.is_empty() {
+                merge(response.responses.get_or_insert_default(), rejected);
+            }
+
+            response
+        })
+    }
+}
+
+/// Merge the rejected partitions into the response of each topic
+fn merge(responses: &mut Vec<TopicProduceResponse>, rejected: Vec<TopicProduceResponse>) {
+    for topic in rejected {
+        if let Some(response) = responses
+            .iter_mut()
+            .find(|response| response.name == topic.name)
+        {
+            response
+                .partition_responses
+                .get_or_insert_default()
+                .extend(topic.partition_responses.unwrap_or_default());
+        } else {
+            responses.push(topic);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tansu_sans_io::record::Record;
+
+    use super::*;
+
+    #[test]
+    fn producer_retains_codec() {
+        assert_eq!(None, compression_type("producer"));
+        assert_eq!(Some(Compression::None), compression_type("uncompressed"));
+        assert_eq!(Some(Compression::Zstd), compression_type("zstd"));
+    }
+
+    #[test]
+    fn gzip_to_zstd() -> tansu_sans_io::Result<()> {
+        let original = inflated::Batch::builder()
+            .attributes(
+                BatchAttribute::default()
+                    .compression(Compression::Gzip)
+                    .into(),
+            )
+            .record(
+                Record::builder().value(Bytes::from_static(b"Lorem ipsum dolor sit amet").into()),
+            )
+            .build()?;
+
+        let recompressed = deflated::Batch::try_from(original.clone())
+            .and_then(|deflated| recompress(deflated, &Compression::Zstd))?;
+
+        assert_eq!(
+            Compression::Zstd,
+            Compression::try_from(recompressed.attributes)?
+        );
+
+        let inflated = inflated::Batch::try_from(recompressed)?;
+        assert_eq!(original.records, inflated.records);
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_batch_rejected() -> tansu_sans_io::Result<()> {
+        let mut corrupt = deflated::Batch::try_from(
+            inflated::Batch::builder()
+                .attributes(
+                    BatchAttribute::default()
+                        .compression(Compression::Gzip)
+                        .into(),
+                )
+                .record(Record::builder().value(Bytes::from_static(b"abc").into()))
+                .build()?,
+        )?;
+        corrupt.record_data = Bytes::from_static(b"not gzip");
+
+        let mut partition =
+            PartitionProduceData::default()
+                .index(3)
+                .records(Some(deflated::Frame {
+                    batches: vec![corrupt],
+                }));
+
+        assert!(recompress_partition(&mut partition, &Compression::Zstd).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "dynostore")]
+    #[tokio::test]
+    async fn rejected_merged_into_topic() -> crate::Result<()> {
+        use tansu_sans_io::{
+            create_topics_request::{CreatableTopic, CreatableTopicConfig},
+            produce_request::TopicProduceData,
+        };
+        use tansu_storage::{ProduceService, StorageContainer};
+        use url::Url;
+
+        let storage = StorageContainer::builder()
+            .cluster_id("tansu")
+            .node_id(111)
+            .advertised_listener(Url::parse("tcp://localhost:9092")?)
+            .storage(Url::parse("memory://")?)
+            .build()
+            .await?;
+
+        _ = storage
+            .create_topic(
+                CreatableTopic::default()
+                    .name("abc".into())
+                    .num_partitions(2)
+                    .replication_factor(0)
+                    .assignments(Some([].into()))
+                    .configs(Some(
+                        [CreatableTopicConfig::default()
+                            .name(COMPRESSION_TYPE.into())
+                            .value(Some("zstd".into()))]
+                        .into(),
+                    )),
+                false,
+            )
+            .await?;
+
+        let batch = || {
+            inflated::Batch::builder()
+                .attributes(
+                    BatchAttribute::default()
+                        .compression(Compression::Gzip)
+                        .into(),
+                )
+                .record(Record::builder().value(Bytes::from_static(b"abc").into()))
+                .build()
+                .and_then(deflated::Batch::try_from)
+        };
+
+        let mut corrupt = batch()?;
+        corrupt.record_data = Bytes::from_static(b"not gzip");
+
+        let response = RecompressLayer
+            .layer(ProduceService)
+            .serve(
+                Context::with_state(storage),
+                ProduceRequest::default().topic_data(Some(
+                    [TopicProduceData::default()
+                        .name("abc".into())
+                        .partition_data(Some(
+                            [
+                                PartitionProduceData::default().index(0).records(Some(
+                                    deflated::Frame {
+                                        batches: vec![batch()?],
+                                    },
+                                )),
+                                PartitionProduceData::default().index(1).records(Some(
+                                    deflated::Frame {
+                                        batches: vec![corrupt],
+                                    },
+                                )),
+                            ]
+                            .into(),
+                        ))]
+                    .into(),
+                )),
+            )
+            .await?;
+
+        let topics = response.responses.as_deref().unwrap_or_default();
+        assert_eq!(1, topics.len());
+        assert_eq!("abc", topics[0].name);
+
+        let mut partitions = topics[0]
+            .partition_responses
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|partition| {
+                (
+                    partition.index,
+                    ErrorCode::try_from(partition.error_code).ok(),
+                )
+            })
+            .collect::<Vec<_>>();
+        partitions.sort_by_key(|(index, _)| *index);
+
+        assert_eq!(
+            vec![
+                (0, Some(ErrorCode::None)),
+                (1, Some(ErrorCode::CorruptMessage))
+            ],
+            partitions
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Produce replay detection
+//!
+//! An idempotent producer numbers each batch it sends to a partition. Storage
+//! rejects a batch that has already been written with
+//! `DuplicateSequenceNumber`, and one that skips a sequence with
+//! `OutOfOrderSequenceNumber`. Both are counted by the
+//! `tansu_produce_sequence_errors` counter for each topic and error, while a
+//! report of the producers responsible is served by `/producer-replays` on the
+//! admin listener.
+//!
+//! A stream of duplicates from a producer is usually a retry storm, e.g., a
+//! `delivery.timeout.ms` or `request.timeout.ms` that is too short for the
+//! latency of the broker. Out of order sequences usually follow batches that
+//! were lost or expired on the client, or a `max.in.flight.requests.per.connection`
+//! greater than 5.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use opentelemetry::{KeyValue, metrics::Counter};
+use rama::{Context, Layer, Service};
+use serde::{Deserialize, Serialize};
+use tansu_sans_io::{ErrorCode, ProduceRequest, ProduceResponse, to_timestamp};
+use tansu_storage::Topition;
+use tracing::{debug, instrument, warn};
+
+use crate::{METER, Result};
+
+/// The number of producers kept in the report, evicting the least recently
+/// detected
+pub const MAXIMUM_PRODUCERS: usize = 1_000;
+
+static SEQUENCE_ERRORS: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_produce_sequence_errors")
+        .with_description("produce batches rejected as a duplicate or out of order sequence")
+        .build()
+});
+
+static REPLAYS: LazyLock<Mutex<BTreeMap<i64, ProducerReplays>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// The duplicate and out of order sequences detected for a producer
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ProducerReplays {
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub duplicates: u64,
+    pub out_of_order: u64,
+
+    /// The topics that a sequence error was detected on
+    pub topics: BTreeSet<String>,
+
+    /// The base sequence of the most recently rejected batch
+    pub last_sequence: i32,
+
+    /// When the most recent sequence error was detected, in milliseconds since the epoch
+    pub last_detected_ms: i64,
+}
+
+/// The producers with a detected sequence error, or a single producer
+pub fn report(producer_id: Option<i64>) -> Result<Vec<ProducerReplays>> {
+    REPLAYS
+        .lock()
+        .map(|replays| match producer_id {
+            Some(producer_id) => replays.get(&producer_id).cloned().into_iter().collect(),
+            None => replays.values().cloned().collect(),
+        })
+        .map_err(Into::into)
+}
+
+/// The idempotent batch produced to a topition
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Sequence {
+    producer_id: i64,
+    producer_epoch: i16,
+    base_sequence: i32,
+}
+
+/// The first idempotent batch of each topition in a request
+fn sequences(req: &ProduceRequest) -> BTreeMap<Topition, Sequence> {
+    req.topic_data
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|topic| {
+            topic
+                .partition_data
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|partition| {
+                    partition
+                        .records
+                        .as_ref()
+                        .and_then(|frame| frame.batches.iter().find(|batch| batch.producer_id >= 0))
+                        .map(|batch| {
+                            (
+                                Topition::new(topic.name.as_str(), partition.index),
+                                Sequence {
+                                    producer_id: batch.producer_id,
+                                    producer_epoch: batch.producer_epoch,
+                                    base_sequence: batch.base_sequence,
+                                },
+                            )
+                        })
+                })
+        })
+        .collect()
+}
+
+/// Record the sequence errors of a response against the producers of the request
+fn detect(
+    sequences: &BTreeMap<Topition, Sequence>,
+    response: &ProduceResponse,
+    detected_at: SystemTime,
+) -> Result<()> {
+    if sequences.is_empty() {
+        return Ok(());
+    }
+
+    let last_detected_ms = to_timestamp(&detected_at).unwrap_or_default();
+
+    let mut replays = REPLAYS.lock()?;
+
+    for topic in response.responses.as_deref().unwrap_or_default() {
+        for partition in topic.partition_responses.as_deref().unwrap_or_default() {
+            let error = match ErrorCode::try_from(partition.error_code) {
+                Ok(ErrorCode::DuplicateSequenceNumber) => "duplicate",
+                Ok(ErrorCode::OutOfOrderSequenceNumber) => "out_of_order",
+                _ => continue,
+            };
+
+            let Some(sequence) =
+                sequences.get(&Topition::new(topic.name.as_str(), partition.index))
+            else {
+                continue;
+            };
+
+            warn!(
+                topic = topic.name,
+                partition = partition.index,
+                producer_id = sequence.producer_id,
+                producer_epoch = sequence.producer_epoch,
+                base_sequence = sequence.base_sequence,
+                error
+            );
+
+            SEQUENCE_ERRORS.add(
+                1,
+                &[
+                    KeyValue::new("topic", topic.name.clone()),
+                    KeyValue::new("error", error),
+                ],
+            );
+
+            let replay = replays
+                .entry(sequence.producer_id)
+                .or_insert_with(|| ProducerReplays {
+                    producer_id: sequence.producer_id,
+                    ..Default::default()
+                });
+
+            if error == "duplicate" {
+                replay.duplicates += 1;
+            } else {
+                replay.out_of_order += 1;
+            }
+
+            _ = replay.topics.insert(topic.name.clone());
+            replay.producer_epoch = sequence.producer_epoch;
+            replay.last_sequence = sequence.base_sequence;
+            replay.last_detected_ms = last_detected_ms;
+        }
+    }
+
+    while replays.len() > MAXIMUM_PRODUCERS {
+        let Some(producer_id) = replays
+            .values()
+            .min_by_key(|replay| replay.last_detected_ms)
+            .map(|replay| replay.producer_id)
+        else {
+            break;
+        };
+
+        debug!(evicted = producer_id);
+        _ = replays.remove(&producer_id);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReplayLayer;
+
+impl<S> Layer<S> for ReplayLayer {
+    type Service = ReplayService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReplayService { inner }
+    }
+}
+
+/// A [`Service`] reporting the producers of batches rejected as a duplicate or
+/// out of order sequence by the inner service
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReplayService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for ReplayService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Send + Sync + 'static,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let sequences = sequences(&req);
+
+        self.inner.serve(ctx, req).await.inspect(|response| {
+            _ = detect(&sequences, response, SystemTime::now()).inspect_err(|err| warn!(?err));
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tansu_sans_io::{
+        produce_request::{PartitionProduceData, TopicProduceData},
+        produce_response::{PartitionProduceResponse, TopicProduceResponse},
+        record::{Record, deflated, inflated},
+    };
+
+    use super::*;
+
+    fn produce(topic: &str, producer_id: i64, base_sequence: i32) -> Result<ProduceRequest> {
+        let batch = inflated::Batch::builder()
+            .producer_id(producer_id)
+            .producer_epoch(3)
+            .base_sequence(base_sequence)
+            .record(Record::builder().value(Bytes::from_static(b"Lorem ipsum").into()))
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        Ok(ProduceRequest::default().topic_data(Some(vec![
+            TopicProduceData::default()
+                .name(topic.into())
+                .partition_data(Some(vec![
+                    PartitionProduceData::default()
+                        .index(0)
+                        .records(Some(deflated::Frame {
+                            batches: vec![batch],
+                        })),
+                ])),
+        ])))
+    }
+
+    fn response(topic: &str, error_code: ErrorCode) -> ProduceResponse {
+        ProduceResponse::default().responses(Some(vec![
+            TopicProduceResponse::default()
+                .name(topic.into())
+                .partition_responses(Some(vec![
+                    PartitionProduceResponse::default()
+                        .index(0)
+                        .error_code(error_code.into()),
+                ])),
+        ]))
+    }
+
+    #[test]
+    fn duplicate_and_out_of_order() -> Result<()> {
+        let topic = "duplicate_and_out_of_order";
+        let producer_id = 6_502;
+
+        let detected_at = SystemTime::now();
+
+        for (base_sequence, error_code) in [
+            (5, ErrorCode::None),
+            (5, ErrorCode::DuplicateSequenceNumber),
+            (5, ErrorCode::DuplicateSequenceNumber),
+            (9, ErrorCode::OutOfOrderSequenceNumber),
+        ] {
+            let sequences = sequences(&produce(topic, producer_id, base_sequence)?);
+            detect(&sequences, &response(topic, error_code), detected_at)?;
+        }
+
+        let replays = report(Some(producer_id))?;
+        assert_eq!(1, replays.len());
+        assert_eq!(2, replays[0].duplicates);
+        assert_eq!(1, replays[0].out_of_order);
+        assert_eq!(3, replays[0].producer_epoch);
+        assert_eq!(9, replays[0].last_sequence);
+        assert!(replays[0].topics.contains(topic));
+
+        // a producer without an idempotent batch is never reported
+        let sequences = sequences(&produce(topic, -1, -1)?);
+        detect(
+            &sequences,
+            &response(topic, ErrorCode::DuplicateSequenceNumber),
+            detected_at,
+        )?;
+        assert!(report(Some(-1))?.is_empty());
+
+        Ok(())
+    }
+}
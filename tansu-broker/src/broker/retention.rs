@@ -0,0 +1,188 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consumer offset retention
+//!
+//! As with `offsets.retention.minutes` in Kafka, the committed offsets of a
+//! group without any members are deleted once they have not been committed
+//! for the retention period, so that groups that are no longer used do not
+//! grow the offsets kept by storage without bound. The offsets of a group
+//! with members are kept, however long ago they were committed.
+//!
+//! Groups used by Tansu itself to checkpoint sources and sinks (with a
+//! `tansu.` prefix) have no members, and are never expired.
+//!
+//! Storage that does not record when each offset was committed cannot expire
+//! them, and the check stops with a warning rather than running again.
+
+use std::time::Duration;
+
+use tansu_sans_io::{ErrorCode, describe_groups_response::DescribedGroup};
+use tansu_storage::{ConsumerGroupState, Storage};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+use crate::{Error, Result};
+
+/// How often the offsets of empty groups are checked for expiry
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// The prefix of groups used by Tansu itself
+const INTERNAL_GROUP_PREFIX: &str = "tansu.";
+
+/// Whether the offsets of a described group may expire
+fn is_expirable(group: &DescribedGroup) -> bool {
+    if group.group_id.starts_with(INTERNAL_GROUP_PREFIX) {
+        return false;
+    }
+
+    match ErrorCode::try_from(group.error_code) {
+        Ok(ErrorCode::None) => group.group_state == ConsumerGroupState::Empty.to_string(),
+        Ok(ErrorCode::GroupIdNotFound) => true,
+        _ => false,
+    }
+}
+
+/// Deletes the expired offsets of empty groups
+#[derive(Clone, Debug)]
+pub struct OffsetRetention<S> {
+    storage: S,
+    retention: Duration,
+    cancellation: CancellationToken,
+}
+
+impl<S> OffsetRetention<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, retention: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            retention,
+            cancellation,
+        }
+    }
+
+    pub async fn serve(self) -> Result<()> {
+        let mut interval = time::interval(CHECK_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match self.expire().await {
+                        Ok(expired) => debug!(expired),
+
+                        Err(Error::Storage(tansu_storage::Error::Unsupported(operation))) => {
+                            warn!(operation, "offset retention is not supported by this storage");
+                            break;
+                        }
+
+                        Err(err) => warn!(?err),
+                    }
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the expired offsets of each empty group, returning the number of
+    /// offsets deleted
+    #[instrument(skip(self))]
+    pub async fn expire(&self) -> Result<usize> {
+        let group_ids = self
+            .storage
+            .list_groups(None)
+            .await?
+            .into_iter()
+            .map(|group| group.group_id)
+            .collect::<Vec<_>>();
+
+        if group_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut expired = 0;
+
+        for group in self
+            .storage
+            .describe_groups(Some(&group_ids), false)
+            .await?
+            .iter()
+            .map(DescribedGroup::from)
+            .filter(is_expirable)
+        {
+            let topitions = self
+                .storage
+                .delete_expired_offsets(&group.group_id, self.retention)
+                .await?;
+
+            if !topitions.is_empty() {
+                info!(group_id = group.group_id, ?topitions);
+                expired += topitions.len();
+            }
+        }
+
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn described(
+        group_id: &str,
+        error_code: ErrorCode,
+        state: ConsumerGroupState,
+    ) -> DescribedGroup {
+        DescribedGroup::default()
+            .error_code(error_code.into())
+            .group_id(group_id.into())
+            .group_state(state.to_string())
+    }
+
+    #[test]
+    fn expirable() {
+        assert!(is_expirable(&described(
+            "abc",
+            ErrorCode::None,
+            ConsumerGroupState::Empty
+        )));
+
+        assert!(is_expirable(&described(
+            "abc",
+            ErrorCode::GroupIdNotFound,
+            ConsumerGroupState::Unknown
+        )));
+
+        assert!(!is_expirable(&described(
+            "abc",
+            ErrorCode::None,
+            ConsumerGroupState::Stable
+        )));
+
+        assert!(!is_expirable(&described(
+            "tansu.sink.abc",
+            ErrorCode::None,
+            ConsumerGroupState::Empty
+        )));
+    }
+}
@@ -0,0 +1,1150 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SASL/PLAIN and SASL/SCRAM authentication
+//!
+//! When credentials are configured, a client must complete a `SaslHandshake`
+//! (v1+) followed by a `SaslAuthenticate` before any API other than
+//! `ApiVersions` is accepted. PLAIN credentials are verified by one of:
+//!
+//! - `file://./etc/sasl.txt` is a static file of `username:password` lines, ignoring blank lines and those starting with `#`
+//! - `storage://` uses the SCRAM credentials of each user held in [`Storage`], the same credentials used by SCRAM
+//!
+//! `SCRAM-SHA-256` and `SCRAM-SHA-512` (RFC 5802) verify the client proof
+//! against the salted credentials held in [`Storage`], which are maintained
+//! with `AlterUserScramCredentials`, and are offered whenever the storage
+//! holds SCRAM credentials. An unknown user is answered with a salt derived
+//! from the username and a secret of this broker, failing only at the client
+//! final message, so that usernames cannot be enumerated.
+//!
+//! `OAUTHBEARER` (RFC 7628) validates a JWT with an [`OAuthBearer`].
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use bytes::Bytes;
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    ApiKey, ApiVersionsRequest, ErrorCode, Frame, SaslAuthenticateRequest,
+    SaslAuthenticateResponse, SaslHandshakeRequest, SaslHandshakeResponse,
+};
+use tansu_storage::{ScramCredential, ScramMechanism, Storage};
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+use crate::{
+    Error, Result,
+    broker::{
+        oauth::{OAUTHBEARER, OAuthBearer, bearer},
+        platform,
+    },
+};
+
+pub const PLAIN: &str = "PLAIN";
+
+/// The secret of this broker used to derive the salts of unknown SCRAM users
+static FAKE_SALT_SECRET: LazyLock<[u8; 32]> = LazyLock::new(rand::random);
+
+/// A credential for an unknown SCRAM user that no proof will verify, with a
+/// salt that is the same for every attempt with the same username
+fn unknown_credential(mechanism: ScramMechanism, username: &str) -> Result<ScramCredential> {
+    mechanism
+        .hmac(&FAKE_SALT_SECRET[..], username.as_bytes())
+        .map(|salt| ScramCredential {
+            salt: salt.slice(..16),
+            iterations: ScramMechanism::MIN_ITERATIONS,
+            stored_key: Bytes::from(rand::random::<[u8; 32]>().to_vec()),
+            server_key: Bytes::from(rand::random::<[u8; 32]>().to_vec()),
+        })
+        .map_err(Into::into)
+}
+
+/// Verify the username and password of a SASL/PLAIN authentication
+#[async_trait]
+pub trait CredentialStore: Debug + Send + Sync {
+    async fn authenticate(&self, username: &str, password: &str) -> Result<bool>;
+}
+
+/// Compare without exiting early on the first difference
+pub(crate) fn matches(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .fold(0, |difference, (expected, actual)| {
+                difference | (expected ^ actual)
+            })
+            == 0
+}
+
+/// Credentials from a static file of `username:password` lines
+#[derive(Clone, Debug, Default)]
+pub struct FileCredentials {
+    users: Arc<BTreeMap<String, String>>,
+}
+
+impl FileCredentials {
+    pub async fn load(path: PathBuf) -> Result<Self> {
+        platform::read_to_string(&path, "sasl credentials")
+            .await
+            .inspect_err(|err| warn!(?path, ?err))
+            .map_err(Into::into)
+            .and_then(|contents| Self::from_str(contents.as_str()))
+    }
+}
+
+impl FromStr for FileCredentials {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !(line.is_empty() || line.starts_with('#')))
+            .map(|(number, line)| {
+                line.split_once(':')
+                    .filter(|(username, _)| !username.is_empty())
+                    .map(|(username, password)| (username.to_owned(), password.to_owned()))
+                    .ok_or(Error::Message(format!(
+                        "expecting username:password on line: {}",
+                        number + 1
+                    )))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()
+            .map(|users| Self {
+                users: Arc::new(users),
+            })
+    }
+}
+
+#[async_trait]
+impl CredentialStore for FileCredentials {
+    async fn authenticate(&self, username: &str, password: &str) -> Result<bool> {
+        Ok(self
+            .users
+            .get(username)
+            .is_some_and(|expected| matches(expected.as_bytes(), password.as_bytes())))
+    }
+}
+
+/// Verify a password against a SCRAM verifier, without the password being stored
+fn verify(mechanism: ScramMechanism, credential: &ScramCredential, password: &str) -> Result<bool> {
+    mechanism
+        .salted_password(
+            password.as_bytes(),
+            &credential.salt[..],
+            credential.iterations,
+        )
+        .and_then(|salted| {
+            mechanism.credential(credential.salt.clone(), credential.iterations, &salted[..])
+        })
+        .map(|derived| matches(&credential.stored_key[..], &derived.stored_key[..]))
+        .map_err(Into::into)
+}
+
+/// The configured verifier of SASL/PLAIN passwords
+#[derive(Clone, Debug)]
+pub enum Credentials {
+    File(FileCredentials),
+
+    /// The SCRAM credentials of each user held in [`Storage`]
+    Storage,
+}
+
+impl Credentials {
+    pub async fn from_url(url: &Url) -> Result<Self> {
+        match url.scheme() {
+            "file" => FileCredentials::load(PathBuf::from(format!(
+                "{}{}",
+                url.host_str().unwrap_or_default(),
+                url.path()
+            )))
+            .await
+            .map(Self::File),
+
+            "storage" => Ok(Self::Storage),
+
+            _unsupported => Err(Error::UnsupportedCredentialsUrl(url.to_owned())),
+        }
+    }
+
+    /// Verify the username and password of a SASL/PLAIN authentication
+    pub async fn authenticate<G>(&self, storage: &G, username: &str, password: &str) -> Result<bool>
+    where
+        G: Storage,
+    {
+        match self {
+            Self::File(inner) => inner.authenticate(username, password).await,
+
+            Self::Storage => {
+                for mechanism in [ScramMechanism::Sha512, ScramMechanism::Sha256] {
+                    let Some(credential) =
+                        storage.user_scram_credential(username, mechanism).await?
+                    else {
+                        continue;
+                    };
+
+                    let password = password.to_owned();
+
+                    // deriving the salted password is deliberately expensive
+                    return tokio::task::spawn_blocking(move || {
+                        verify(mechanism, &credential, &password)
+                    })
+                    .await?;
+                }
+
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// The `gs2-header` and `client-first-message-bare` of a SCRAM client first message
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct ClientFirst {
+    gs2_header: String,
+    username: String,
+    nonce: String,
+    bare: String,
+}
+
+/// Decode a `saslname`, where `=2C` is `,` and `=3D` is `=`
+fn saslname(encoded: &str) -> Option<String> {
+    let mut decoded = String::with_capacity(encoded.len());
+    let mut chars = encoded.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '=' => match (chars.next(), chars.next()) {
+                (Some('2'), Some('C')) => decoded.push(','),
+                (Some('3'), Some('D')) => decoded.push('='),
+                _ => return None,
+            },
+
+            ',' => return None,
+
+            c => decoded.push(c),
+        }
+    }
+
+    Some(decoded)
+}
+
+/// Parse a SCRAM client first message, without channel binding or impersonation
+fn client_first(auth_bytes: &[u8]) -> Option<ClientFirst> {
+    let message = std::str::from_utf8(auth_bytes).ok()?;
+
+    let (cbind_flag, remainder) = message.split_once(',')?;
+    let (authzid, bare) = remainder.split_once(',')?;
+
+    if !["n", "y"].contains(&cbind_flag) {
+        return None;
+    }
+
+    let mut attributes = bare.split(',');
+
+    let username = attributes
+        .next()
+        .and_then(|attribute| attribute.strip_prefix("n="))
+        .and_then(saslname)
+        .filter(|username| !username.is_empty())?;
+
+    let nonce = attributes
+        .next()
+        .and_then(|attribute| attribute.strip_prefix("r="))
+        .filter(|nonce| !nonce.is_empty())?;
+
+    if !(authzid.is_empty()
+        || authzid
+            .strip_prefix("a=")
+            .and_then(saslname)
+            .is_some_and(|authzid| authzid == username))
+    {
+        return None;
+    }
+
+    Some(ClientFirst {
+        gs2_header: format!("{cbind_flag},{authzid},"),
+        username,
+        nonce: nonce.to_owned(),
+        bare: bare.to_owned(),
+    })
+}
+
+/// A SCRAM exchange waiting for the client final message
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct ScramExchange {
+    mechanism: ScramMechanism,
+    client_first: ClientFirst,
+    nonce: String,
+    server_first: String,
+    credential: ScramCredential,
+}
+
+impl ScramExchange {
+    fn new(
+        mechanism: ScramMechanism,
+        client_first: ClientFirst,
+        server_nonce: &str,
+        credential: ScramCredential,
+    ) -> Self {
+        let nonce = format!("{}{server_nonce}", client_first.nonce);
+
+        let server_first = format!(
+            "r={nonce},s={},i={}",
+            STANDARD.encode(&credential.salt[..]),
+            credential.iterations
+        );
+
+        Self {
+            mechanism,
+            client_first,
+            nonce,
+            server_first,
+            credential,
+        }
+    }
+
+    /// Verify the proof of the client final message, returning the server final message
+    fn verify(&self, auth_bytes: &[u8]) -> Result<Option<String>> {
+        let mechanism = self.mechanism;
+
+        let Some((without_proof, proof)) = std::str::from_utf8(auth_bytes)
+            .ok()
+            .and_then(|message| message.rsplit_once(",p="))
+        else {
+            return Ok(None);
+        };
+
+        let mut attributes = without_proof.split(',');
+
+        let channel_binding = attributes
+            .next()
+            .and_then(|attribute| attribute.strip_prefix("c="))
+            .and_then(|encoded| STANDARD.decode(encoded).ok());
+
+        let nonce = attributes
+            .next()
+            .and_then(|attribute| attribute.strip_prefix("r="));
+
+        let Ok(proof) = STANDARD.decode(proof) else {
+            return Ok(None);
+        };
+
+        if channel_binding.as_deref() != Some(self.client_first.gs2_header.as_bytes())
+            || nonce != Some(self.nonce.as_str())
+        {
+            return Ok(None);
+        }
+
+        let auth_message = format!(
+            "{},{},{without_proof}",
+            self.client_first.bare, self.server_first
+        );
+
+        let client_signature =
+            mechanism.hmac(&self.credential.stored_key[..], auth_message.as_bytes())?;
+
+        if proof.len() != client_signature.len() {
+            return Ok(None);
+        }
+
+        let client_key = proof
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(proof, signature)| proof ^ signature)
+            .collect::<Vec<_>>();
+
+        if !matches(
+            &self.credential.stored_key[..],
+            &mechanism.hash(&client_key[..])[..],
+        ) {
+            return Ok(None);
+        }
+
+        mechanism
+            .hmac(&self.credential.server_key[..], auth_message.as_bytes())
+            .map(|server_signature| Some(format!("v={}", STANDARD.encode(&server_signature[..]))))
+            .map_err(Into::into)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum SessionState {
+    #[default]
+    Start,
+    Handshake(String),
+    Scram(Box<ScramExchange>),
+    Authenticated(String),
+}
+
+/// The SASL state of a connection, inserted into the [`Context`] extensions of each connection
+#[derive(Clone, Debug, Default)]
+pub struct SaslSession(Arc<Mutex<SessionState>>);
+
+impl SaslSession {
+    /// A session already authenticated by other means, such as the peer
+    /// credentials of a Unix domain socket
+    pub fn authenticated(principal: impl Into<String>) -> Self {
+        Self(Arc::new(Mutex::new(SessionState::Authenticated(
+            principal.into(),
+        ))))
+    }
+
+    fn state(&self) -> Result<SessionState> {
+        self.0.lock().map(|guard| guard.clone()).map_err(Into::into)
+    }
+
+    fn transition(&self, state: SessionState) -> Result<()> {
+        self.0
+            .lock()
+            .map(|mut guard| {
+                debug!(from = ?guard, to = ?state);
+                *guard = state
+            })
+            .map_err(Into::into)
+    }
+
+    /// The authenticated username of this connection
+    pub fn principal(&self) -> Option<String> {
+        self.state().ok().and_then(|state| match state {
+            SessionState::Authenticated(principal) => Some(principal),
+            _ => None,
+        })
+    }
+}
+
+/// Parse the `[authzid] NUL authcid NUL passwd` of a SASL/PLAIN message, without impersonation
+fn plain(auth_bytes: &[u8]) -> Option<(&str, &str)> {
+    let mut fields = auth_bytes.split(|byte| *byte == 0);
+
+    let authzid = fields.next()?;
+    let authcid = fields.next()?;
+    let passwd = fields.next()?;
+
+    if fields.next().is_some() || authcid.is_empty() || !(authzid.is_empty() || authzid == authcid)
+    {
+        return None;
+    }
+
+    std::str::from_utf8(authcid)
+        .ok()
+        .zip(std::str::from_utf8(passwd).ok())
+}
+
+/// A [`Layer`] rejecting requests on a connection that has not authenticated
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SaslAuthenticationLayer {
+    enabled: bool,
+}
+
+impl SaslAuthenticationLayer {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+impl<S> Layer<S> for SaslAuthenticationLayer {
+    type Service = SaslAuthenticationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SaslAuthenticationService {
+            enabled: self.enabled,
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] only passing `ApiVersions`, `SaslHandshake` and `SaslAuthenticate`
+/// to the inner service until the connection has authenticated
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SaslAuthenticationService<S> {
+    enabled: bool,
+    inner: S,
+}
+
+impl<S, State> Service<State, Frame> for SaslAuthenticationService<S>
+where
+    S: Service<State, Frame, Response = Frame, Error = Error>,
+    State: Send + Sync + 'static,
+{
+    type Response = Frame;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(&self, ctx: Context<State>, req: Frame) -> Result<Self::Response, Self::Error> {
+        if !self.enabled {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        let api_key = req.api_key()?;
+
+        if [
+            ApiVersionsRequest::KEY,
+            SaslHandshakeRequest::KEY,
+            SaslAuthenticateRequest::KEY,
+        ]
+        .contains(&api_key)
+            || ctx
+                .get::<SaslSession>()
+                .and_then(SaslSession::principal)
+                .is_some()
+        {
+            self.inner.serve(ctx, req).await
+        } else {
+            warn!(api_key, "unauthenticated");
+            Err(Error::Api(ErrorCode::SaslAuthenticationFailed))
+        }
+    }
+}
+
+/// A [`Service`] negotiating the SASL mechanism of a connection, using
+/// [`Storage`] as [`Context`] to offer SCRAM
+#[derive(Clone, Debug, Default)]
+pub struct SaslHandshakeService {
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+}
+
+impl SaslHandshakeService {
+    pub fn new(credentials: Option<Credentials>) -> Self {
+        Self {
+            credentials,
+            oauth_bearer: None,
+        }
+    }
+
+    pub fn oauth_bearer(self, oauth_bearer: Option<OAuthBearer>) -> Self {
+        Self {
+            oauth_bearer,
+            ..self
+        }
+    }
+}
+
+impl ApiKey for SaslHandshakeService {
+    const KEY: i16 = SaslHandshakeRequest::KEY;
+}
+
+impl<G> Service<G, SaslHandshakeRequest> for SaslHandshakeService
+where
+    G: Storage,
+{
+    type Response = SaslHandshakeResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: SaslHandshakeRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut mechanisms = self
+            .credentials
+            .as_ref()
+            .map_or(vec![], |_| vec![PLAIN.into()]);
+
+        if ctx.state().scram_credentials() {
+            mechanisms.extend(
+                [ScramMechanism::Sha256, ScramMechanism::Sha512]
+                    .into_iter()
+                    .map(|mechanism| mechanism.name().into()),
+            );
+        }
+
+        if self.oauth_bearer.is_some() {
+            mechanisms.push(OAUTHBEARER.into());
+        }
+
+        let error_code = match ctx.get::<SaslSession>() {
+            Some(session) if mechanisms.contains(&req.mechanism) => session
+                .transition(SessionState::Handshake(req.mechanism))
+                .map(|()| ErrorCode::None)?,
+
+            Some(_) => ErrorCode::UnsupportedSaslMechanism,
+
+            None => ErrorCode::IllegalSaslState,
+        };
+
+        Ok(SaslHandshakeResponse::default()
+            .error_code(error_code.into())
+            .mechanisms(Some(mechanisms)))
+    }
+}
+
+/// A [`Service`] authenticating a connection with SASL/PLAIN or SASL/SCRAM,
+/// using [`Storage`] as [`Context`] for SCRAM credentials
+#[derive(Clone, Debug, Default)]
+pub struct SaslAuthenticateService {
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+}
+
+impl SaslAuthenticateService {
+    pub fn new(credentials: Option<Credentials>) -> Self {
+        Self {
+            credentials,
+            oauth_bearer: None,
+        }
+    }
+
+    pub fn oauth_bearer(self, oauth_bearer: Option<OAuthBearer>) -> Self {
+        Self {
+            oauth_bearer,
+            ..self
+        }
+    }
+}
+
+impl ApiKey for SaslAuthenticateService {
+    const KEY: i16 = SaslAuthenticateRequest::KEY;
+}
+
+impl SaslAuthenticateService {
+    fn response(&self, error_code: ErrorCode) -> SaslAuthenticateResponse {
+        SaslAuthenticateResponse::default()
+            .error_code(error_code.into())
+            .error_message((error_code != ErrorCode::None).then(|| {
+                if error_code == ErrorCode::SaslAuthenticationFailed {
+                    "Authentication failed: Invalid username or password".into()
+                } else {
+                    error_code.to_string()
+                }
+            }))
+            .auth_bytes(Bytes::new())
+            .session_lifetime_ms(Some(0))
+    }
+}
+
+impl SaslAuthenticateService {
+    async fn plain<G>(
+        &self,
+        storage: &G,
+        session: &SaslSession,
+        credentials: &Credentials,
+        auth_bytes: &[u8],
+    ) -> Result<SaslAuthenticateResponse>
+    where
+        G: Storage,
+    {
+        let authenticated = match plain(auth_bytes) {
+            Some((username, password)) => credentials
+                .authenticate(storage, username, password)
+                .await?
+                .then(|| username.to_owned()),
+
+            None => None,
+        };
+
+        debug!(?authenticated);
+
+        self.complete(session, authenticated)
+    }
+
+    async fn oauth_bearer(
+        &self,
+        session: &SaslSession,
+        oauth_bearer: &OAuthBearer,
+        auth_bytes: &[u8],
+    ) -> Result<SaslAuthenticateResponse> {
+        let authenticated = match bearer(auth_bytes) {
+            Some(token) => oauth_bearer.authenticate(token).await?,
+            None => None,
+        };
+
+        debug!(?authenticated);
+
+        self.complete(session, authenticated)
+    }
+
+    /// Reply to a SCRAM client first message with the salt, iterations and nonce
+    async fn scram_first<G>(
+        &self,
+        storage: &G,
+        session: &SaslSession,
+        mechanism: ScramMechanism,
+        auth_bytes: &[u8],
+    ) -> Result<SaslAuthenticateResponse>
+    where
+        G: Storage,
+    {
+        let Some(client_first) = client_first(auth_bytes) else {
+            return self.complete(session, None);
+        };
+
+        let credential = match storage
+            .user_scram_credential(client_first.username.as_str(), mechanism)
+            .await?
+        {
+            Some(credential) => credential,
+
+            None => {
+                debug!(username = client_first.username, ?mechanism, "unknown");
+                unknown_credential(mechanism, client_first.username.as_str())?
+            }
+        };
+
+        let server_nonce = STANDARD.encode(rand::random::<[u8; 24]>());
+        let exchange = ScramExchange::new(mechanism, client_first, &server_nonce, credential);
+        let server_first = Bytes::from(exchange.server_first.clone());
+
+        session
+            .transition(SessionState::Scram(Box::new(exchange)))
+            .map(|()| self.response(ErrorCode::None).auth_bytes(server_first))
+    }
+
+    /// Verify the proof of a SCRAM client final message
+    fn scram_final(
+        &self,
+        session: &SaslSession,
+        exchange: &ScramExchange,
+        auth_bytes: &[u8],
+    ) -> Result<SaslAuthenticateResponse> {
+        match exchange.verify(auth_bytes)? {
+            Some(server_final) => self
+                .complete(session, Some(exchange.client_first.username.clone()))
+                .map(|response| response.auth_bytes(Bytes::from(server_final))),
+
+            None => self.complete(session, None),
+        }
+    }
+
+    fn complete(
+        &self,
+        session: &SaslSession,
+        authenticated: Option<String>,
+    ) -> Result<SaslAuthenticateResponse> {
+        if let Some(principal) = authenticated {
+            session
+                .transition(SessionState::Authenticated(principal))
+                .map(|()| self.response(ErrorCode::None))
+        } else {
+            session
+                .transition(SessionState::Start)
+                .map(|()| self.response(ErrorCode::SaslAuthenticationFailed))
+        }
+    }
+}
+
+impl<G> Service<G, SaslAuthenticateRequest> for SaslAuthenticateService
+where
+    G: Storage,
+{
+    type Response = SaslAuthenticateResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: SaslAuthenticateRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(session) = ctx.get::<SaslSession>() else {
+            return Ok(self.response(ErrorCode::IllegalSaslState));
+        };
+
+        match (session.state()?, &self.credentials, &self.oauth_bearer) {
+            (SessionState::Handshake(mechanism), Some(credentials), _) if mechanism == PLAIN => {
+                self.plain(ctx.state(), session, credentials, &req.auth_bytes[..])
+                    .await
+            }
+
+            (SessionState::Handshake(mechanism), _, Some(oauth_bearer))
+                if mechanism == OAUTHBEARER =>
+            {
+                self.oauth_bearer(session, oauth_bearer, &req.auth_bytes[..])
+                    .await
+            }
+
+            (SessionState::Handshake(mechanism), _, _) if ctx.state().scram_credentials() => {
+                let mechanism = ScramMechanism::from_str(mechanism.as_str())?;
+
+                self.scram_first(ctx.state(), session, mechanism, &req.auth_bytes[..])
+                    .await
+            }
+
+            (SessionState::Scram(exchange), _, _) => {
+                self.scram_final(session, &exchange, &req.auth_bytes[..])
+            }
+
+            _otherwise => Ok(self.response(ErrorCode::IllegalSaslState)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tansu_storage::StorageContainer;
+
+    use super::*;
+
+    async fn storage() -> Result<StorageContainer> {
+        StorageContainer::builder()
+            .cluster_id("tansu")
+            .node_id(111)
+            .advertised_listener(Url::parse("tcp://localhost:9092")?)
+            .storage(Url::parse("memory://tansu/")?)
+            .build()
+            .await
+            .map_err(Into::into)
+    }
+
+    #[test]
+    fn plain_message() {
+        assert_eq!(Some(("alice", "secret")), plain(b"\0alice\0secret"));
+        assert_eq!(Some(("alice", "secret")), plain(b"alice\0alice\0secret"));
+        assert_eq!(None, plain(b"bob\0alice\0secret"));
+        assert_eq!(None, plain(b"\0\0secret"));
+        assert_eq!(None, plain(b"alice:secret"));
+    }
+
+    #[tokio::test]
+    async fn file_credentials() -> Result<()> {
+        let credentials = FileCredentials::from_str(
+            r"
+            # username:password
+            alice:secret
+            bob:pass:word
+            ",
+        )?;
+
+        assert!(credentials.authenticate("alice", "secret").await?);
+        assert!(credentials.authenticate("bob", "pass:word").await?);
+        assert!(!credentials.authenticate("alice", "secrets").await?);
+        assert!(!credentials.authenticate("carol", "secret").await?);
+
+        assert!(FileCredentials::from_str("alice").is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn authenticate() -> Result<()> {
+        let credentials = Credentials::File(FileCredentials::from_str("alice:secret")?);
+        let session = SaslSession::default();
+
+        let mut ctx = Context::with_state(storage().await?);
+        _ = ctx.insert(session.clone());
+
+        let handshake = SaslHandshakeService::new(Some(credentials.clone()))
+            .serve(
+                ctx.clone(),
+                SaslHandshakeRequest::default().mechanism(PLAIN.into()),
+            )
+            .await?;
+        assert_eq!(i16::from(ErrorCode::None), handshake.error_code);
+
+        let authenticate = SaslAuthenticateService::new(Some(credentials))
+            .serve(
+                ctx,
+                SaslAuthenticateRequest::default()
+                    .auth_bytes(Bytes::from_static(b"\0alice\0secret")),
+            )
+            .await?;
+        assert_eq!(i16::from(ErrorCode::None), authenticate.error_code);
+
+        assert_eq!(Some("alice".into()), session.principal());
+
+        Ok(())
+    }
+
+    #[test]
+    fn client_first_message() {
+        assert_eq!(
+            Some(ClientFirst {
+                gs2_header: "n,,".into(),
+                username: "user".into(),
+                nonce: "rOprNGfwEbeRWgbNEkqO".into(),
+                bare: "n=user,r=rOprNGfwEbeRWgbNEkqO".into(),
+            }),
+            client_first(b"n,,n=user,r=rOprNGfwEbeRWgbNEkqO")
+        );
+
+        assert_eq!(
+            Some("a,b=c".into()),
+            client_first(b"n,,n=a=2Cb=3Dc,r=abc").map(|first| first.username)
+        );
+
+        assert!(client_first(b"n,a=alice,n=alice,r=abc").is_some());
+        assert!(client_first(b"n,a=bob,n=alice,r=abc").is_none());
+        assert!(client_first(b"p=tls-unique,,n=alice,r=abc").is_none());
+        assert!(client_first(b"n,,n=,r=abc").is_none());
+        assert!(client_first(b"n,,n=alice").is_none());
+    }
+
+    /// The SCRAM-SHA-256 example exchange of RFC 7677
+    #[test]
+    fn scram_sha_256_exchange() -> Result<()> {
+        let mechanism = ScramMechanism::Sha256;
+        let salt = Bytes::from(STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==")?);
+        let salted_password = mechanism.salted_password(b"pencil", &salt[..], 4_096)?;
+        let credential = mechanism.credential(salt, 4_096, &salted_password[..])?;
+
+        let exchange = ScramExchange::new(
+            mechanism,
+            client_first(b"n,,n=user,r=rOprNGfwEbeRWgbNEkqO").expect("client first"),
+            "%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0",
+            credential,
+        );
+
+        assert_eq!(
+            "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096",
+            exchange.server_first
+        );
+
+        assert_eq!(
+            Some("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=".into()),
+            exchange.verify(
+                b"c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+            )?
+        );
+
+        assert_eq!(
+            None,
+            exchange.verify(
+                b"c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=AHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn plain_against_verifier() -> Result<()> {
+        let mechanism = ScramMechanism::Sha512;
+        let salt = Bytes::from_static(b"pepper");
+        let salted_password = mechanism.salted_password(b"pencil", &salt[..], 4_096)?;
+        let credential = mechanism.credential(salt, 4_096, &salted_password[..])?;
+
+        assert!(verify(mechanism, &credential, "pencil")?);
+        assert!(!verify(mechanism, &credential, "pen")?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plain_against_storage() -> Result<()> {
+        let storage = storage().await?;
+
+        let mechanism = ScramMechanism::Sha256;
+        let salt = Bytes::from_static(b"pepper");
+        let iterations = ScramMechanism::MIN_ITERATIONS;
+        let salted_password = mechanism.salted_password(b"secret", &salt[..], iterations)?;
+
+        storage
+            .upsert_user_scram_credential(
+                "alice",
+                mechanism,
+                mechanism.credential(salt, iterations, &salted_password[..])?,
+            )
+            .await?;
+
+        let credentials = Credentials::from_url(&Url::parse("storage://")?).await?;
+
+        assert!(
+            credentials
+                .authenticate(&storage, "alice", "secret")
+                .await?
+        );
+        assert!(
+            !credentials
+                .authenticate(&storage, "alice", "secrets")
+                .await?
+        );
+        assert!(!credentials.authenticate(&storage, "bob", "secret").await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scram_without_plain_credentials() -> Result<()> {
+        let mut ctx = Context::with_state(storage().await?);
+        _ = ctx.insert(SaslSession::default());
+
+        let handshake = SaslHandshakeService::new(None)
+            .serve(
+                ctx,
+                SaslHandshakeRequest::default().mechanism(ScramMechanism::Sha512.name().into()),
+            )
+            .await?;
+
+        assert_eq!(i16::from(ErrorCode::None), handshake.error_code);
+        assert_eq!(
+            Some(vec![
+                ScramMechanism::Sha256.name().into(),
+                ScramMechanism::Sha512.name().into()
+            ]),
+            handshake.mechanisms
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scram_authenticate() -> Result<()> {
+        let storage = storage().await?;
+
+        let mechanism = ScramMechanism::Sha512;
+        let salt = Bytes::from_static(b"pepper");
+        let iterations = ScramMechanism::MIN_ITERATIONS;
+        let salted_password = mechanism.salted_password(b"secret", &salt[..], iterations)?;
+
+        storage
+            .upsert_user_scram_credential(
+                "alice",
+                mechanism,
+                mechanism.credential(salt, iterations, &salted_password[..])?,
+            )
+            .await?;
+
+        let credentials = Credentials::File(FileCredentials::default());
+        let session = SaslSession::default();
+
+        let mut ctx = Context::with_state(storage);
+        _ = ctx.insert(session.clone());
+
+        let handshake = SaslHandshakeService::new(Some(credentials.clone()))
+            .serve(
+                ctx.clone(),
+                SaslHandshakeRequest::default().mechanism(mechanism.name().into()),
+            )
+            .await?;
+        assert_eq!(i16::from(ErrorCode::None), handshake.error_code);
+
+        let service = SaslAuthenticateService::new(Some(credentials));
+
+        let client_first_bare = "n=alice,r=fyko+d2lbbFgONRv9qkxdawL";
+
+        let server_first = service
+            .serve(
+                ctx.clone(),
+                SaslAuthenticateRequest::default()
+                    .auth_bytes(Bytes::from(format!("n,,{client_first_bare}"))),
+            )
+            .await?;
+        assert_eq!(i16::from(ErrorCode::None), server_first.error_code);
+        assert_eq!(None, session.principal());
+
+        let server_first = String::from_utf8(server_first.auth_bytes.to_vec())?;
+        let nonce = server_first
+            .split(',')
+            .find_map(|attribute| attribute.strip_prefix("r="))
+            .expect("nonce");
+        assert!(nonce.starts_with("fyko+d2lbbFgONRv9qkxdawL"));
+
+        let without_proof = format!("c=biws,r={nonce}");
+        let auth_message = format!("{client_first_bare},{server_first},{without_proof}");
+
+        let client_key = mechanism.hmac(&salted_password[..], b"Client Key")?;
+        let stored_key = mechanism.hash(&client_key[..]);
+        let client_signature = mechanism.hmac(&stored_key[..], auth_message.as_bytes())?;
+        let proof = client_key
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(key, signature)| key ^ signature)
+            .collect::<Vec<_>>();
+
+        let server_final = service
+            .serve(
+                ctx,
+                SaslAuthenticateRequest::default().auth_bytes(Bytes::from(format!(
+                    "{without_proof},p={}",
+                    STANDARD.encode(proof)
+                ))),
+            )
+            .await?;
+        assert_eq!(i16::from(ErrorCode::None), server_final.error_code);
+
+        let server_key = mechanism.hmac(&salted_password[..], b"Server Key")?;
+        let server_signature = mechanism.hmac(&server_key[..], auth_message.as_bytes())?;
+
+        assert_eq!(
+            format!("v={}", STANDARD.encode(server_signature)),
+            String::from_utf8(server_final.auth_bytes.to_vec())?
+        );
+
+        assert_eq!(Some("alice".into()), session.principal());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scram_unknown_user() -> Result<()> {
+        let storage = storage().await?;
+
+        let mechanism = ScramMechanism::Sha256;
+        let credentials = Credentials::File(FileCredentials::default());
+        let session = SaslSession::default();
+
+        let mut ctx = Context::with_state(storage);
+        _ = ctx.insert(session.clone());
+
+        let server_first = async || {
+            let handshake = SaslHandshakeService::new(Some(credentials.clone()))
+                .serve(
+                    ctx.clone(),
+                    SaslHandshakeRequest::default().mechanism(mechanism.name().into()),
+                )
+                .await?;
+            assert_eq!(i16::from(ErrorCode::None), handshake.error_code);
+
+            let server_first = SaslAuthenticateService::new(Some(credentials.clone()))
+                .serve(
+                    ctx.clone(),
+                    SaslAuthenticateRequest::default().auth_bytes(Bytes::from_static(
+                        b"n,,n=mallory,r=fyko+d2lbbFgONRv9qkxdawL",
+                    )),
+                )
+                .await?;
+            assert_eq!(i16::from(ErrorCode::None), server_first.error_code);
+
+            String::from_utf8(server_first.auth_bytes.to_vec())
+                .map(|server_first| {
+                    server_first
+                        .split(',')
+                        .filter(|attribute| !attribute.starts_with("r="))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .map_err(Error::from)
+        };
+
+        // the salt and iterations of an unknown user do not change between attempts
+        let salt_iterations = server_first().await?;
+        assert_eq!(salt_iterations, server_first().await?);
+
+        let server_final = SaslAuthenticateService::new(Some(credentials.clone()))
+            .serve(
+                ctx,
+                SaslAuthenticateRequest::default().auth_bytes(Bytes::from_static(
+                    b"c=biws,r=fyko+d2lbbFgONRv9qkxdawL,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=",
+                )),
+            )
+            .await?;
+        assert_eq!(
+            i16::from(ErrorCode::SaslAuthenticationFailed),
+            server_final.error_code
+        );
+        assert_eq!(None, session.principal());
+
+        Ok(())
+    }
+}
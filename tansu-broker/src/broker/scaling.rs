@@ -0,0 +1,535 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partition scaling recommendations
+//!
+//! A [`PartitionAnalyzer`] samples the high watermark, stored bytes and
+//! consumer backlog of every partition at an interval, keeping the most recent
+//! [`WINDOW`] samples of each. Over that window, a topic is recommended:
+//!
+//! - more partitions, when its throughput exceeds [`TARGET_THROUGHPUT`]
+//!   records per second for each partition;
+//! - more partitions, when its backlog is growing while consumers are making
+//!   progress, in proportion to the rate at which they are falling behind;
+//! - rebalancing, when its busiest partition has at least [`MAX_SKEW`] times
+//!   the mean throughput of its partitions, usually the result of a hot key.
+//!
+//! The backlog of a partition is the lag of the consumer group that is
+//! furthest behind. Recommendations are served by `/partition-recommendations`
+//! on the admin listener, with the recommended partition count and skew of
+//! every topic recorded in the `tansu_partition_recommended` and
+//! `tansu_partition_skew` gauges.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use opentelemetry::{KeyValue, metrics::Gauge};
+use serde::{Deserialize, Serialize};
+use tansu_sans_io::ErrorCode;
+use tansu_storage::{Storage, Topition};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    METER, Result,
+    clock::{Clock, SystemClock},
+};
+
+/// The number of samples of each partition used for a recommendation
+pub const WINDOW: usize = 60;
+
+/// The records per second that a single partition is expected to sustain
+pub const TARGET_THROUGHPUT: f64 = 1_000.0;
+
+/// The ratio of the busiest partition to the mean, at which a topic is imbalanced
+pub const MAX_SKEW: f64 = 2.0;
+
+static RECOMMENDED: LazyLock<Gauge<u64>> = LazyLock::new(|| {
+    METER
+        .u64_gauge("tansu_partition_recommended")
+        .with_description("The recommended partition count of each topic")
+        .build()
+});
+
+static SKEW: LazyLock<Gauge<f64>> = LazyLock::new(|| {
+    METER
+        .f64_gauge("tansu_partition_skew")
+        .with_description(
+            "The throughput of the busiest partition of each topic relative to the mean",
+        )
+        .build()
+});
+
+/// The state of a partition at a point in time
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Sample {
+    at: SystemTime,
+    high_watermark: i64,
+    bytes: i64,
+    backlog: i64,
+}
+
+/// Why a topic has a recommendation
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// The throughput of each partition exceeds the target
+    Throughput,
+
+    /// Consumers are falling behind
+    Backlog,
+
+    /// The throughput of the partitions is severely imbalanced
+    Imbalanced,
+}
+
+/// The load of a partition over the window
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct PartitionLoad {
+    pub partition: i32,
+    pub records_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub backlog: i64,
+    pub backlog_per_sec: f64,
+}
+
+/// The analysis of a topic over the window
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct Recommendation {
+    pub topic: String,
+    pub partitions: i32,
+    pub recommended_partitions: i32,
+    pub records_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub backlog: i64,
+    pub skew: f64,
+    pub reasons: Vec<Reason>,
+    pub loads: Vec<PartitionLoad>,
+}
+
+/// The load of a partition from the first and last samples of its window
+fn load(partition: i32, samples: &VecDeque<Sample>) -> Option<PartitionLoad> {
+    let (first, last) = (samples.front()?, samples.back()?);
+
+    let elapsed = last
+        .at
+        .duration_since(first.at)
+        .ok()
+        .filter(|elapsed| !elapsed.is_zero())?
+        .as_secs_f64();
+
+    Some(PartitionLoad {
+        partition,
+        records_per_sec: (last.high_watermark - first.high_watermark).max(0) as f64 / elapsed,
+        bytes_per_sec: (last.bytes - first.bytes).max(0) as f64 / elapsed,
+        backlog: last.backlog,
+        backlog_per_sec: (last.backlog - first.backlog) as f64 / elapsed,
+    })
+}
+
+/// Recommend a partition count for a topic from the samples of its partitions
+fn recommend(topic: &str, samples: &BTreeMap<i32, VecDeque<Sample>>) -> Option<Recommendation> {
+    let loads = samples
+        .iter()
+        .map(|(partition, samples)| load(*partition, samples))
+        .collect::<Option<Vec<_>>>()
+        .filter(|loads| !loads.is_empty())?;
+
+    let partitions = i32::try_from(loads.len()).unwrap_or(i32::MAX);
+
+    let records_per_sec = loads.iter().map(|load| load.records_per_sec).sum::<f64>();
+    let bytes_per_sec = loads.iter().map(|load| load.bytes_per_sec).sum::<f64>();
+    let backlog = loads.iter().map(|load| load.backlog).sum::<i64>();
+    let backlog_per_sec = loads.iter().map(|load| load.backlog_per_sec).sum::<f64>();
+
+    let mut reasons = vec![];
+    let mut recommended_partitions = partitions;
+
+    let for_throughput = (records_per_sec / TARGET_THROUGHPUT).ceil() as i32;
+
+    if for_throughput > partitions {
+        reasons.push(Reason::Throughput);
+        recommended_partitions = recommended_partitions.max(for_throughput);
+    }
+
+    // consumers that are making progress keep up with proportionally more
+    // partitions, while stalled consumers are not helped by more partitions
+    let consumed_per_sec = records_per_sec - backlog_per_sec;
+
+    if backlog > 0 && backlog_per_sec > 0.0 && consumed_per_sec > 0.0 {
+        let for_backlog =
+            (f64::from(partitions) * records_per_sec / consumed_per_sec).ceil() as i32;
+
+        if for_backlog > partitions {
+            reasons.push(Reason::Backlog);
+            recommended_partitions = recommended_partitions.max(for_backlog);
+        }
+    }
+
+    let mean = records_per_sec / f64::from(partitions);
+
+    let skew = if mean > 0.0 {
+        loads
+            .iter()
+            .map(|load| load.records_per_sec)
+            .fold(0.0, f64::max)
+            / mean
+    } else {
+        0.0
+    };
+
+    if partitions > 1 && skew >= MAX_SKEW {
+        reasons.push(Reason::Imbalanced);
+    }
+
+    Some(Recommendation {
+        topic: topic.to_owned(),
+        partitions,
+        recommended_partitions,
+        records_per_sec,
+        bytes_per_sec,
+        backlog,
+        skew,
+        reasons,
+        loads,
+    })
+}
+
+/// Recommendations made by a [`PartitionAnalyzer`], shared with the admin listener
+#[derive(Clone, Debug, Default)]
+pub struct Recommendations {
+    analyzed: Arc<Mutex<BTreeMap<String, Recommendation>>>,
+}
+
+impl Recommendations {
+    /// The analysis of a topic, or every topic with a recommendation
+    pub fn get(&self, topic: Option<&str>) -> Result<Vec<Recommendation>> {
+        self.analyzed
+            .lock()
+            .map(|analyzed| match topic {
+                Some(topic) => analyzed.get(topic).cloned().into_iter().collect(),
+
+                None => analyzed
+                    .values()
+                    .filter(|recommendation| !recommendation.reasons.is_empty())
+                    .cloned()
+                    .collect(),
+            })
+            .map_err(Into::into)
+    }
+
+    fn replace(&self, recommendations: BTreeMap<String, Recommendation>) -> Result<()> {
+        for recommendation in recommendations.values() {
+            let attributes = [KeyValue::new("topic", recommendation.topic.clone())];
+
+            RECOMMENDED.record(
+                u64::try_from(recommendation.recommended_partitions).unwrap_or_default(),
+                &attributes,
+            );
+            SKEW.record(recommendation.skew, &attributes);
+        }
+
+        self.analyzed
+            .lock()
+            .map(|mut analyzed| *analyzed = recommendations)
+            .map_err(Into::into)
+    }
+}
+
+/// Samples the load of every partition at an interval, recommending topics
+/// that need more partitions or are imbalanced
+#[derive(Clone, Debug)]
+pub struct PartitionAnalyzer<S> {
+    storage: S,
+    interval: Duration,
+    clock: Arc<dyn Clock>,
+    samples: Arc<Mutex<BTreeMap<Topition, VecDeque<Sample>>>>,
+    recommendations: Recommendations,
+    cancellation: CancellationToken,
+}
+
+impl<S> PartitionAnalyzer<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            clock: Arc::new(SystemClock),
+            samples: Arc::new(Mutex::new(BTreeMap::new())),
+            recommendations: Recommendations::default(),
+            cancellation,
+        }
+    }
+
+    /// Read the time from this clock, rather than the system clock
+    pub fn clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// The recommendations made by this analyzer
+    pub fn recommendations(&self) -> Recommendations {
+        self.recommendations.clone()
+    }
+
+    pub async fn serve(self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .analyze()
+                        .await
+                        .inspect(|recommended| debug!(recommended))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sample every partition, returning the number of topics with a recommendation
+    #[instrument(skip(self))]
+    pub async fn analyze(&self) -> Result<usize> {
+        let sampled = self.sample().await?;
+
+        let by_topic = self.samples.lock().map(|mut samples| {
+            samples.retain(|topition, _| sampled.contains_key(topition));
+
+            for (topition, sample) in sampled {
+                let window = samples.entry(topition).or_default();
+                window.push_back(sample);
+
+                while window.len() > WINDOW {
+                    _ = window.pop_front();
+                }
+            }
+
+            samples.iter().fold(
+                BTreeMap::<String, BTreeMap<i32, VecDeque<Sample>>>::new(),
+                |mut by_topic, (topition, window)| {
+                    _ = by_topic
+                        .entry(topition.topic().to_owned())
+                        .or_default()
+                        .insert(topition.partition(), window.clone());
+                    by_topic
+                },
+            )
+        })?;
+
+        let recommendations = by_topic
+            .iter()
+            .filter_map(|(topic, samples)| {
+                recommend(topic, samples).map(|recommendation| (topic.to_owned(), recommendation))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let recommended = recommendations
+            .values()
+            .filter(|recommendation| !recommendation.reasons.is_empty())
+            .count();
+
+        self.recommendations.replace(recommendations)?;
+
+        Ok(recommended)
+    }
+
+    /// The high watermark, stored bytes and backlog of every partition
+    async fn sample(&self) -> Result<BTreeMap<Topition, Sample>> {
+        let at = self.clock.now();
+
+        let bytes = self
+            .storage
+            .topition_usage(None)
+            .await?
+            .into_iter()
+            .map(|usage| (usage.topition, usage.usage.bytes))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut high_watermarks = BTreeMap::new();
+
+        for topic in self.storage.metadata(None).await?.topics() {
+            let (Some(name), Some(partitions)) =
+                (topic.name.as_deref(), topic.partitions.as_deref())
+            else {
+                continue;
+            };
+
+            if topic.error_code != i16::from(ErrorCode::None) {
+                continue;
+            }
+
+            for partition in partitions {
+                let topition = Topition::new(name, partition.partition_index);
+                let stage = self.storage.offset_stage(&topition).await?;
+                _ = high_watermarks.insert(topition, stage.high_watermark());
+            }
+        }
+
+        let topitions = high_watermarks.keys().cloned().collect::<Vec<_>>();
+        let mut backlogs = BTreeMap::new();
+
+        for group in self
+            .storage
+            .list_groups(None)
+            .await?
+            .into_iter()
+            .map(|group| group.group_id)
+            .collect::<BTreeSet<_>>()
+        {
+            let committed = self
+                .storage
+                .offset_fetch(Some(group.as_str()), &topitions, Some(false))
+                .await
+                .inspect_err(|err| debug!(group, ?err))
+                .unwrap_or_default();
+
+            for (topition, offset) in committed.into_iter().filter(|(_, offset)| *offset >= 0) {
+                let Some(high_watermark) = high_watermarks.get(&topition) else {
+                    continue;
+                };
+
+                let lag = (high_watermark - offset).max(0);
+
+                _ = backlogs
+                    .entry(topition)
+                    .and_modify(|backlog: &mut i64| *backlog = (*backlog).max(lag))
+                    .or_insert(lag);
+            }
+        }
+
+        Ok(high_watermarks
+            .into_iter()
+            .map(|(topition, high_watermark)| {
+                let sample = Sample {
+                    at,
+                    high_watermark,
+                    bytes: bytes.get(&topition).copied().unwrap_or_default(),
+                    backlog: backlogs.get(&topition).copied().unwrap_or_default(),
+                };
+
+                (topition, sample)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples a minute apart of each partition from its high watermarks and backlogs
+    fn window(samples: &[(i64, i64)]) -> VecDeque<Sample> {
+        samples
+            .iter()
+            .enumerate()
+            .map(|(i, (high_watermark, backlog))| Sample {
+                at: SystemTime::UNIX_EPOCH + Duration::from_secs(60 * i as u64),
+                high_watermark: *high_watermark,
+                bytes: high_watermark * 100,
+                backlog: *backlog,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn healthy() {
+        let samples = BTreeMap::from([
+            (0, window(&[(0, 0), (6_000, 0)])),
+            (1, window(&[(0, 0), (6_000, 0)])),
+        ]);
+
+        let recommendation = recommend("abc", &samples).expect("recommendation");
+
+        assert_eq!(2, recommendation.partitions);
+        assert_eq!(2, recommendation.recommended_partitions);
+        assert_eq!(200.0, recommendation.records_per_sec);
+        assert_eq!(20_000.0, recommendation.bytes_per_sec);
+        assert_eq!(1.0, recommendation.skew);
+        assert!(recommendation.reasons.is_empty());
+    }
+
+    #[test]
+    fn throughput() {
+        let samples = BTreeMap::from([
+            (0, window(&[(0, 0), (150_000, 0)])),
+            (1, window(&[(0, 0), (150_000, 0)])),
+        ]);
+
+        let recommendation = recommend("abc", &samples).expect("recommendation");
+
+        assert_eq!(5_000.0, recommendation.records_per_sec);
+        assert_eq!(5, recommendation.recommended_partitions);
+        assert_eq!(vec![Reason::Throughput], recommendation.reasons);
+    }
+
+    #[test]
+    fn backlog() {
+        // producing 100 records/s with consumers falling behind at 50 records/s
+        let samples = BTreeMap::from([
+            (0, window(&[(0, 0), (6_000, 3_000)])),
+            (1, window(&[(0, 0), (6_000, 3_000)])),
+        ]);
+
+        let recommendation = recommend("abc", &samples).expect("recommendation");
+
+        assert_eq!(6_000, recommendation.backlog);
+        assert_eq!(4, recommendation.recommended_partitions);
+        assert_eq!(vec![Reason::Backlog], recommendation.reasons);
+    }
+
+    #[test]
+    fn stalled_consumers() {
+        let samples = BTreeMap::from([(0, window(&[(0, 0), (6_000, 6_000)]))]);
+
+        let recommendation = recommend("abc", &samples).expect("recommendation");
+
+        assert_eq!(1, recommendation.recommended_partitions);
+        assert!(recommendation.reasons.is_empty());
+    }
+
+    #[test]
+    fn imbalanced() {
+        let samples = BTreeMap::from([
+            (0, window(&[(0, 0), (12_000, 0)])),
+            (1, window(&[(0, 0), (0, 0)])),
+            (2, window(&[(0, 0), (0, 0)])),
+            (3, window(&[(0, 0), (0, 0)])),
+        ]);
+
+        let recommendation = recommend("abc", &samples).expect("recommendation");
+
+        assert_eq!(4.0, recommendation.skew);
+        assert_eq!(4, recommendation.recommended_partitions);
+        assert_eq!(vec![Reason::Imbalanced], recommendation.reasons);
+    }
+
+    #[test]
+    fn single_sample() {
+        let samples = BTreeMap::from([(0, window(&[(0, 0)]))]);
+        assert_eq!(None, recommend("abc", &samples));
+    }
+}
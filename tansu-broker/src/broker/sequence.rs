@@ -0,0 +1,367 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Global ordering topics
+//!
+//! A topic with `tansu.global.sequence` enabled has a single logical sequence
+//! across its partitions. Each produced record is stamped with a
+//! [`GLOBAL_SEQUENCE`] header from a sequence shared by every partition of the
+//! topic, allowing a consumer to merge the partitions deterministically with
+//! an [`OrderedMerge`](tansu_client::ordered::OrderedMerge).
+//!
+//! Produce requests to a global ordering topic are appended one at a time, so
+//! that the sequence follows the order in which records are stored. The
+//! sequence is seeded from the most recently stored record of each partition
+//! when first used by a broker. Only one broker should accept produce
+//! requests for a global ordering topic, as each broker holds its own
+//! sequence.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use rama::{Context, Layer, Service};
+use tansu_client::ordered::{GLOBAL_SEQUENCE, global_sequence};
+use tansu_sans_io::{
+    BatchAttribute, ErrorCode, IsolationLevel, ProduceRequest, ProduceResponse,
+    produce_request::TopicProduceData,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+    record::{self, Header, deflated, inflated},
+};
+use tansu_storage::{Storage, TopicId, Topition};
+use tracing::{debug, instrument, warn};
+
+use crate::broker::topic_config::TopicConfig;
+
+pub const GLOBAL_ORDERING: &str = "tansu.global.sequence";
+
+/// The next sequence of each global ordering topic, `None` until seeded
+type Sequences = Arc<Mutex<BTreeMap<String, Arc<tokio::sync::Mutex<Option<i64>>>>>>;
+
+/// Stamp each record of a batch with the next sequence, control batches are
+/// left unchanged
+pub fn stamp(batch: deflated::Batch, next: &mut i64) -> tansu_sans_io::Result<deflated::Batch> {
+    if BatchAttribute::try_from(batch.attributes)?.control {
+        return Ok(batch);
+    }
+
+    let mut inflated = inflated::Batch::try_from(batch)?;
+    let records = mem::take(&mut inflated.records);
+
+    records
+        .into_iter()
+        .fold(inflated::Builder::from(inflated), |builder, record| {
+            let sequence = *next;
+            *next += 1;
+
+            builder.record(
+                record::Builder::from(record).header(
+                    Header::builder()
+                        .key(Bytes::from_static(GLOBAL_SEQUENCE.as_bytes()))
+                        .value(Bytes::copy_from_slice(&sequence.to_be_bytes())),
+                ),
+            )
+        })
+        .build()
+        .and_then(deflated::Batch::try_from)
+}
+
+fn unavailable(topic: TopicProduceData) -> TopicProduceResponse {
+    TopicProduceResponse::default()
+        .name(topic.name)
+        .partition_responses(Some(
+            topic
+                .partition_data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    PartitionProduceResponse::default()
+                        .index(partition.index)
+                        .error_code(ErrorCode::KafkaStorageError.into())
+                        .base_offset(-1)
+                        .log_append_time_ms(Some(-1))
+                        .log_start_offset(Some(0))
+                        .record_errors(Some([].into()))
+                        .error_message(Some("global sequence unavailable".into()))
+                        .current_leader(None)
+                })
+                .collect(),
+        ))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GlobalSequenceLayer {
+    sequences: Sequences,
+}
+
+impl<S> Layer<S> for GlobalSequenceLayer {
+    type Service = GlobalSequenceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GlobalSequenceService {
+            sequences: self.sequences.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] stamping the records produced to a global ordering topic
+/// with the next sequence of that topic, serializing produce requests to the
+/// topic through the inner service
+#[derive(Clone, Debug)]
+pub struct GlobalSequenceService<S> {
+    sequences: Sequences,
+    inner: S,
+}
+
+impl<S> GlobalSequenceService<S> {
+    async fn is_global<State>(&self, ctx: &Context<State>, topic: &str) -> bool
+    where
+        State: Storage,
+    {
+        TopicConfig::lookup(ctx, topic)
+            .await
+            .inspect_err(|err| debug!(topic, ?err))
+            .is_ok_and(|config| {
+                config
+                    .get(GLOBAL_ORDERING)
+                    .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+            })
+    }
+
+    fn sequence(&self, topic: &str) -> Arc<tokio::sync::Mutex<Option<i64>>> {
+        self.sequences
+            .lock()
+            .map(|mut sequences| sequences.entry(topic.to_owned()).or_default().clone())
+            .unwrap_or_default()
+    }
+
+    /// The sequence following the most recently stored record of any
+    /// partition of the topic
+    async fn seed<State>(&self, storage: &State, topic: &str) -> tansu_storage::Result<i64>
+    where
+        State: Storage,
+    {
+        let metadata = storage.metadata(Some(&[TopicId::from(topic)])).await?;
+
+        let partitions = metadata
+            .topics()
+            .iter()
+            .flat_map(|topic| topic.partitions.as_deref().unwrap_or_default())
+            .map(|partition| partition.partition_index)
+            .collect::<Vec<_>>();
+
+        let mut next = 0;
+
+        for partition in partitions {
+            let topition = Topition::new(topic, partition);
+            let stage = storage.offset_stage(&topition).await?;
+
+            let mut offset = stage.high_watermark() - 1;
+
+            while offset >= stage.log_start() {
+                let last = storage
+                    .fetch(
+                        &topition,
+                        offset,
+                        0,
+                        u32::MAX,
+                        IsolationLevel::ReadUncommitted,
+                    )
+                    .await?
+                    .into_iter()
+                    .filter_map(|batch| inflated::Batch::try_from(batch).ok())
+                    .flat_map(|batch| batch.records)
+                    .filter_map(|record| global_sequence(&record))
+                    .max();
+
+                if let Some(last) = last {
+                    next = next.max(last + 1);
+                    break;
+                }
+
+                offset -= 1;
+            }
+        }
+
+        debug!(topic, next);
+
+        Ok(next)
+    }
+}
+
+impl<S, State> Service<State, ProduceRequest> for GlobalSequenceService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut topics = vec![];
+
+        for topic in req.topic_data.take().unwrap_or_default() {
+            let global = self.is_global(&ctx, topic.name.as_str()).await;
+            topics.push((topic, global));
+        }
+
+        // locks are taken in topic order, and held until the inner service
+        // has stored the stamped records
+        let mut guards = BTreeMap::new();
+
+        for name in topics
+            .iter()
+            .filter(|(_, global)| *global)
+            .map(|(topic, _)| topic.name.clone())
+            .collect::<BTreeSet<_>>()
+        {
+            let mut guard = self.sequence(name.as_str()).lock_owned().await;
+
+            if guard.is_none() {
+                match self.seed(ctx.state(), name.as_str()).await {
+                    Ok(next) => _ = guard.replace(next),
+                    Err(err) => {
+                        warn!(topic = name, ?err);
+                        continue;
+                    }
+                }
+            }
+
+            _ = guards.insert(name, guard);
+        }
+
+        let mut accepted = vec![];
+        let mut rejected = vec![];
+
+        for (mut topic, global) in topics {
+            if !global {
+                accepted.push(topic);
+                continue;
+            }
+
+            let Some(next) = guards.get_mut(&topic.name).and_then(|guard| guard.as_mut()) else {
+                rejected.push(unavailable(topic));
+                continue;
+            };
+
+            for partition in topic.partition_data.as_deref_mut().unwrap_or_default() {
+                let Some(frame) = partition.records.as_mut() else {
+                    continue;
+                };
+
+                for batch in frame.batches.iter_mut() {
+                    let mut stamped = *next;
+
+                    match stamp(batch.clone(), &mut stamped) {
+                        Ok(deflated) => {
+                            *batch = deflated;
+                            *next = stamped;
+                        }
+
+                        Err(err) => {
+                            warn!(topic = topic.name, partition = partition.index, ?err)
+                        }
+                    }
+                }
+            }
+
+            accepted.push(topic);
+        }
+
+        req.topic_data = Some(accepted);
+
+        let response = self.inner.serve(ctx, req).await;
+
+        drop(guards);
+
+        response.map(|mut response| {
+            if !rejected.is_empty() {
+                response
+                    .responses
+                    .get_or_insert_default()
+                    .append(&mut rejected);
+            }
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamped_in_order() -> tansu_sans_io::Result<()> {
+        let batch = inflated::Batch::builder()
+            .record(record::Record::builder().value(Some(Bytes::from_static(b"abc"))))
+            .record(
+                record::Record::builder()
+                    .offset_delta(1)
+                    .value(Some(Bytes::from_static(b"pqr")))
+                    .header(
+                        Header::builder()
+                            .key(Bytes::from_static(b"x"))
+                            .value(Bytes::from_static(b"y")),
+                    ),
+            )
+            .last_offset_delta(1)
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        let mut next = 5;
+
+        let stamped = stamp(batch, &mut next).and_then(inflated::Batch::try_from)?;
+        assert_eq!(7, next);
+
+        assert_eq!(
+            vec![Some(5), Some(6)],
+            stamped
+                .records
+                .iter()
+                .map(global_sequence)
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(2, stamped.records[1].headers.len());
+        assert_eq!(Some(Bytes::from_static(b"pqr")), stamped.records[1].value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn control_unchanged() -> tansu_sans_io::Result<()> {
+        let batch = inflated::Batch::builder()
+            .record(record::Record::builder().value(Some(Bytes::from_static(b"abc"))))
+            .attributes(BatchAttribute::default().control(true).into())
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        let mut next = 5;
+
+        assert_eq!(batch.clone(), stamp(batch, &mut next)?);
+        assert_eq!(5, next);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1006 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sink connectors
+//!
+//! A topic with a `tansu.sink.url` topic config delivers each of its records
+//! to an external system, configured with the `/sink-connector` endpoint of
+//! the admin listener (or IncrementalAlterConfigs):
+//!
+//! - an `http://` or `https://` URL POSTs the value of each record, with its
+//!   topic, partition, offset and (base64 encoded) key as `tansu-topic`,
+//!   `tansu-partition`, `tansu-offset` and `tansu-key` headers
+//! - a `postgres://` URL (with the `postgres` feature) inserts each record
+//!   into a table (`tansu.sink.table`, by default `sink`):
+//!
+//! ```sql
+//! create table sink (
+//!     partition integer,
+//!     "offset" bigint,
+//!     key bytea,
+//!     value bytea,
+//!     primary key (partition, "offset")
+//! );
+//! ```
+//!
+//! Delivery to an HTTP sink is at least once: the offset of each partition is
+//! committed to the `tansu.sink.<topic>` consumer group after its records are
+//! delivered, so that a record may be delivered again after a failure.
+//!
+//! Delivery to a Postgres sink is exactly once, with the next offset of the
+//! partition committed in the same Postgres transaction as each record, to a
+//! table that must also exist in the database:
+//!
+//! ```sql
+//! create table tansu_sink_offset (
+//!     topic text,
+//!     partition integer,
+//!     "offset" bigint,
+//!     primary key (topic, partition)
+//! );
+//! ```
+//!
+//! The offset is then committed to the consumer group, so that the lag of the
+//! sink may be monitored as any other group. Should a broker fail after the
+//! Postgres transaction commits, but before the group commit, delivery
+//! resumes from the offset recorded in Postgres rather than the group.
+//!
+//! A record that cannot be delivered
+//! after `tansu.sink.retries` retries (by default 3) is produced to partition
+//! 0 of `tansu.sink.dead_letter_topic` with a `tansu.sink.error` header,
+//! otherwise delivery of the partition stops until the next poll.
+//!
+//! The status of each sink connector survives a broker restart, persisted in
+//! the topic configuration with the committed offsets of its group:
+//! `tansu.sink.paused` stops delivery until it is removed,
+//! `tansu.sink.errors` counts the records that could not be delivered and
+//! `tansu.sink.failure` has the reason delivery last stopped, until it next
+//! succeeds. The status is returned by the `/sink-connector-status` endpoint
+//! of the admin listener, with `/sink-connector-pause` pausing (`PUT`) and
+//! resuming (`DELETE`) delivery.
+//!
+//! As the URL may embed credentials, `tansu.sink.url` is sensitive and may be
+//! a `secret://` reference to an environment variable or mounted file.
+
+use std::{collections::BTreeMap, slice, time::Duration};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tansu_sans_io::{
+    BatchAttribute, ConfigResource, ErrorCode, IsolationLevel, OpType,
+    incremental_alter_configs_request::{AlterConfigsResource, AlterableConfig},
+    record::{Header, Record, deflated, inflated},
+};
+use tansu_storage::{OffsetCommitRequest, Secret, Storage, Topition};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+use url::Url;
+
+#[cfg(feature = "postgres")]
+use crate::broker::outbox::{Pools, is_identifier};
+use crate::{Error, Result};
+
+pub const SINK_URL: &str = "tansu.sink.url";
+pub const SINK_TABLE: &str = "tansu.sink.table";
+pub const SINK_RETRIES: &str = "tansu.sink.retries";
+pub const SINK_DEAD_LETTER_TOPIC: &str = "tansu.sink.dead_letter_topic";
+
+/// Whether delivery to the sink of a topic is paused
+pub const SINK_PAUSED: &str = "tansu.sink.paused";
+
+/// The number of records that could not be delivered to the sink of a topic
+pub const SINK_ERRORS: &str = "tansu.sink.errors";
+
+/// Why delivery to the sink of a topic last stopped, until it next succeeds
+pub const SINK_FAILURE: &str = "tansu.sink.failure";
+
+/// The header of a dead letter with the reason that it could not be delivered
+pub const SINK_ERROR: &str = "tansu.sink.error";
+
+#[cfg(feature = "postgres")]
+const DEFAULT_TABLE: &str = "sink";
+const DEFAULT_RETRIES: u32 = 3;
+
+/// The next offset of each partition delivered to a Postgres sink
+#[cfg(feature = "postgres")]
+const SINK_OFFSET_TABLE: &str = "tansu_sink_offset";
+
+/// The delay before the first retry, doubling with each subsequent retry
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The bytes of records fetched from each partition in each poll
+const MAX_BYTES: u32 = 1_048_576;
+
+/// The consumer group of the sink connector of a topic
+pub fn group_id(topic: &str) -> String {
+    format!("tansu.sink.{topic}")
+}
+
+/// Set a topic config to a value, or delete it with `None`
+fn alter(name: &str, value: Option<String>) -> AlterableConfig {
+    AlterableConfig::default()
+        .name(name.into())
+        .config_operation(
+            if value.is_some() {
+                OpType::Set
+            } else {
+                OpType::Delete
+            }
+            .into(),
+        )
+        .value(value)
+}
+
+/// The sink connector of a topic, as configured
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SinkConfig {
+    pub url: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_letter_topic: Option<String>,
+}
+
+impl SinkConfig {
+    /// The sink connector of a topic from its configuration, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        let mut configs = storage
+            .describe_config(
+                topic,
+                ConfigResource::Topic,
+                Some(&[
+                    SINK_URL.to_owned(),
+                    SINK_TABLE.to_owned(),
+                    SINK_RETRIES.to_owned(),
+                    SINK_DEAD_LETTER_TOPIC.to_owned(),
+                ]),
+            )
+            .await?
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|config| config.value.map(|value| (config.name, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        configs
+            .remove(SINK_URL)
+            .map(|url| {
+                configs
+                    .remove(SINK_RETRIES)
+                    .map(|retries| retries.parse::<u32>())
+                    .transpose()
+                    .map_err(|err| Error::Message(format!("invalid {SINK_RETRIES}: {err}")))
+                    .map(|retries| Self {
+                        url,
+                        table: configs.remove(SINK_TABLE),
+                        retries,
+                        dead_letter_topic: configs.remove(SINK_DEAD_LETTER_TOPIC),
+                    })
+            })
+            .transpose()
+    }
+
+    /// The config alterations replacing the sink connector of a topic, or
+    /// removing it (and its status) with `None`
+    pub fn alter(topic: &str, sink: Option<&Self>) -> AlterConfigsResource {
+        let mut configs = vec![
+            alter(SINK_URL, sink.map(|sink| sink.url.clone())),
+            alter(SINK_TABLE, sink.and_then(|sink| sink.table.clone())),
+            alter(
+                SINK_RETRIES,
+                sink.and_then(|sink| sink.retries.map(|retries| retries.to_string())),
+            ),
+            alter(
+                SINK_DEAD_LETTER_TOPIC,
+                sink.and_then(|sink| sink.dead_letter_topic.clone()),
+            ),
+        ];
+
+        if sink.is_none() {
+            configs.extend(
+                [SINK_PAUSED, SINK_ERRORS, SINK_FAILURE]
+                    .into_iter()
+                    .map(|name| alter(name, None)),
+            );
+        }
+
+        AlterConfigsResource::default()
+            .resource_type(ConfigResource::Topic.into())
+            .resource_name(topic.to_owned())
+            .configs(Some(configs))
+    }
+
+    /// Validate this configuration, without resolving any secret
+    pub fn validate(&self) -> Result<()> {
+        if !Secret::is_reference(&self.url) {
+            _ = Sink::new(&self.url, self.table.as_deref())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The state of a sink connector
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkState {
+    #[default]
+    Running,
+    Paused,
+    Failed,
+}
+
+/// The health of a sink connector, as persisted in the topic configuration
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Health {
+    paused: bool,
+    errors: u64,
+    failure: Option<String>,
+}
+
+impl Health {
+    async fn describe<S>(storage: &S, topic: &str) -> Result<Self>
+    where
+        S: Storage,
+    {
+        storage
+            .describe_config(
+                topic,
+                ConfigResource::Topic,
+                Some(&[
+                    SINK_PAUSED.to_owned(),
+                    SINK_ERRORS.to_owned(),
+                    SINK_FAILURE.to_owned(),
+                ]),
+            )
+            .await
+            .map(|result| {
+                result.configs.unwrap_or_default().into_iter().fold(
+                    Self::default(),
+                    |health, config| match config.name.as_str() {
+                        SINK_PAUSED => Self {
+                            paused: config
+                                .value
+                                .is_some_and(|value| value.eq_ignore_ascii_case("true")),
+                            ..health
+                        },
+
+                        SINK_ERRORS => Self {
+                            errors: config
+                                .value
+                                .and_then(|value| value.parse().ok())
+                                .unwrap_or_default(),
+                            ..health
+                        },
+
+                        SINK_FAILURE => Self {
+                            failure: config.value.filter(|failure| !failure.is_empty()),
+                            ..health
+                        },
+
+                        _ => health,
+                    },
+                )
+            })
+            .map_err(Into::into)
+    }
+
+    fn state(&self) -> SinkState {
+        if self.paused {
+            SinkState::Paused
+        } else if self.failure.is_some() {
+            SinkState::Failed
+        } else {
+            SinkState::Running
+        }
+    }
+
+    /// The config alterations recording the outcome of a poll
+    fn alter(topic: &str, errors: u64, failure: Option<&str>) -> AlterConfigsResource {
+        AlterConfigsResource::default()
+            .resource_type(ConfigResource::Topic.into())
+            .resource_name(topic.to_owned())
+            .configs(Some(vec![
+                alter(SINK_ERRORS, Some(errors.to_string())),
+                alter(SINK_FAILURE, failure.map(str::to_owned)),
+            ]))
+    }
+}
+
+/// The status of the sink connector of a topic
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SinkStatus {
+    pub state: SinkState,
+
+    /// The records that could not be delivered, including dead letters
+    pub errors: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+
+    /// The committed offset of each partition
+    pub offsets: BTreeMap<i32, i64>,
+}
+
+impl SinkStatus {
+    /// The status of the sink connector of a topic, if any
+    pub async fn describe<S>(storage: &S, topic: &str) -> Result<Option<Self>>
+    where
+        S: Storage,
+    {
+        if SinkConfig::describe(storage, topic).await?.is_none() {
+            return Ok(None);
+        }
+
+        let health = Health::describe(storage, topic).await?;
+
+        let offsets = storage
+            .committed_offset_topitions(&group_id(topic))
+            .await?
+            .into_iter()
+            .filter(|(topition, _)| topition.topic() == topic)
+            .map(|(topition, offset)| (topition.partition(), offset))
+            .collect();
+
+        Ok(Some(Self {
+            state: health.state(),
+            errors: health.errors,
+            failure: health.failure,
+            offsets,
+        }))
+    }
+
+    /// The config alterations pausing delivery to the sink of a topic, or
+    /// resuming it
+    pub fn pause(topic: &str, paused: bool) -> AlterConfigsResource {
+        AlterConfigsResource::default()
+            .resource_type(ConfigResource::Topic.into())
+            .resource_name(topic.to_owned())
+            .configs(Some(vec![alter(
+                SINK_PAUSED,
+                Some("true".to_owned()).filter(|_| paused),
+            )]))
+    }
+}
+
+/// Where the records of a topic are delivered
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Sink {
+    Http(Url),
+
+    #[cfg(feature = "postgres")]
+    Postgres {
+        url: String,
+        table: String,
+    },
+}
+
+impl Sink {
+    #[cfg_attr(not(feature = "postgres"), allow(unused_variables))]
+    fn new(url: &str, table: Option<&str>) -> Result<Self> {
+        match Url::parse(url)?.scheme() {
+            "http" | "https" => Url::parse(url).map(Self::Http).map_err(Into::into),
+
+            #[cfg(feature = "postgres")]
+            "postgres" | "postgresql" => {
+                let table = table.unwrap_or(DEFAULT_TABLE);
+
+                if is_identifier(table) {
+                    Ok(Self::Postgres {
+                        url: url.to_owned(),
+                        table: table.to_owned(),
+                    })
+                } else {
+                    Err(Error::Message(format!("invalid sink table: {table}")))
+                }
+            }
+
+            otherwise => Err(Error::Message(format!("unsupported sink: {otherwise}"))),
+        }
+    }
+}
+
+/// A sink connector, with its secrets resolved
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Connector {
+    sink: Sink,
+    retries: u32,
+    dead_letter_topic: Option<String>,
+}
+
+impl TryFrom<&SinkConfig> for Connector {
+    type Error = Error;
+
+    fn try_from(config: &SinkConfig) -> Result<Self, Self::Error> {
+        let url = Secret::resolve(&config.url)?;
+
+        Sink::new(&url, config.table.as_deref()).map(|sink| Self {
+            sink,
+            retries: config.retries.unwrap_or(DEFAULT_RETRIES),
+            dead_letter_topic: config.dead_letter_topic.clone(),
+        })
+    }
+}
+
+/// A record being delivered to a sink
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Delivery<'a> {
+    topic: &'a str,
+    partition: i32,
+    offset: i64,
+    key: Option<Bytes>,
+    value: Option<Bytes>,
+}
+
+/// The records of a fetch from an offset, excluding control batches
+fn deliveries(
+    topition: &Topition,
+    offset: i64,
+    batches: Vec<deflated::Batch>,
+) -> Result<Vec<Delivery<'_>>> {
+    let mut deliveries = vec![];
+
+    for batch in batches {
+        if BatchAttribute::try_from(batch.attributes)?.control {
+            continue;
+        }
+
+        let batch = inflated::Batch::try_from(batch)?;
+
+        for record in batch.records {
+            let record_offset = batch.base_offset + i64::from(record.offset_delta);
+
+            if record_offset >= offset {
+                deliveries.push(Delivery {
+                    topic: topition.topic(),
+                    partition: topition.partition(),
+                    offset: record_offset,
+                    key: record.key,
+                    value: record.value,
+                });
+            }
+        }
+    }
+
+    Ok(deliveries)
+}
+
+/// The offset to resume delivery from: the offset recorded by a sink that
+/// commits it with each record, otherwise the offset committed by the group
+fn resume_offset(committed: Option<i64>, recorded: Option<i64>) -> i64 {
+    recorded
+        .or(committed)
+        .filter(|offset| *offset >= 0)
+        .unwrap_or_default()
+}
+
+/// The outcome of delivering the new records of a partition
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Progress {
+    delivered: u64,
+    errors: u64,
+    failure: Option<String>,
+}
+
+/// Upsert the next offset of a partition delivered to a Postgres sink
+#[cfg(feature = "postgres")]
+fn record_offset() -> String {
+    format!(
+        "insert into {SINK_OFFSET_TABLE} (topic, partition, \"offset\") values ($1, $2, $3) \
+         on conflict (topic, partition) do update set \"offset\" = excluded.\"offset\""
+    )
+}
+
+/// Delivers the records of topics to their sinks
+#[derive(Clone, Debug)]
+pub struct SinkConnector<S> {
+    storage: S,
+    interval: Duration,
+    client: reqwest::Client,
+
+    #[cfg(feature = "postgres")]
+    pools: Pools,
+
+    cancellation: CancellationToken,
+}
+
+impl<S> SinkConnector<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            client: reqwest::Client::new(),
+
+            #[cfg(feature = "postgres")]
+            pools: Pools::default(),
+
+            cancellation,
+        }
+    }
+
+    pub async fn serve(mut self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .poll()
+                        .await
+                        .inspect(|delivered| debug!(delivered))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deliver the new records of each topic with a sink, returning the
+    /// number of records delivered
+    #[instrument(skip(self))]
+    pub async fn poll(&mut self) -> Result<u64> {
+        let mut delivered = 0;
+
+        for topic in self.storage.metadata(None).await?.topics() {
+            let (Some(name), Some(partitions)) =
+                (topic.name.as_deref(), topic.partitions.as_deref())
+            else {
+                continue;
+            };
+
+            let Some(config) = SinkConfig::describe(&self.storage, name).await? else {
+                continue;
+            };
+
+            let health = Health::describe(&self.storage, name).await?;
+
+            if health.paused {
+                debug!(topic = name, paused = health.paused);
+                continue;
+            }
+
+            let connector = match Connector::try_from(&config) {
+                Ok(connector) => connector,
+                Err(err) => {
+                    warn!(topic = name, ?err);
+                    self.report(name, &health, 0, Some(err.to_string())).await?;
+                    continue;
+                }
+            };
+
+            let mut errors = 0;
+            let mut failure = None;
+
+            for partition in partitions {
+                let topition = Topition::new(name, partition.partition_index);
+
+                match self.partition(&connector, &topition).await {
+                    Ok(progress) => {
+                        delivered += progress.delivered;
+                        errors += progress.errors;
+                        failure = failure.or(progress.failure);
+                    }
+
+                    Err(err) => {
+                        warn!(?topition, ?err);
+                        _ = failure.get_or_insert(err.to_string());
+                    }
+                }
+            }
+
+            self.report(name, &health, errors, failure).await?;
+        }
+
+        Ok(delivered)
+    }
+
+    /// Record the outcome of a poll of a topic, when its health has changed
+    async fn report(
+        &self,
+        topic: &str,
+        health: &Health,
+        errors: u64,
+        failure: Option<String>,
+    ) -> Result<()> {
+        if errors == 0 && failure == health.failure {
+            return Ok(());
+        }
+
+        let response = self
+            .storage
+            .incremental_alter_resource(Health::alter(
+                topic,
+                health.errors + errors,
+                failure.as_deref(),
+            ))
+            .await?;
+
+        if response.error_code == i16::from(ErrorCode::None) {
+            Ok(())
+        } else {
+            warn!(topic, ?response);
+            Err(Error::Api(ErrorCode::try_from(response.error_code)?))
+        }
+    }
+
+    async fn partition(&mut self, connector: &Connector, topition: &Topition) -> Result<Progress> {
+        let group_id = group_id(topition.topic());
+
+        let committed = self
+            .storage
+            .offset_fetch(Some(&group_id), slice::from_ref(topition), Some(true))
+            .await?
+            .get(topition)
+            .copied();
+
+        let recorded = self.recorded(&connector.sink, topition).await?;
+
+        let offset = resume_offset(committed, recorded);
+        debug!(?topition, committed, recorded, offset);
+
+        let batches = self
+            .storage
+            .fetch(
+                topition,
+                offset,
+                1,
+                MAX_BYTES,
+                IsolationLevel::ReadCommitted,
+            )
+            .await?;
+
+        let mut next = offset;
+        let mut progress = Progress::default();
+
+        for delivery in deliveries(topition, offset, batches)? {
+            let offset = delivery.offset;
+
+            match self.deliver(connector, &delivery).await {
+                Ok(()) => progress.delivered += 1,
+
+                Err(err) => {
+                    progress.errors += 1;
+
+                    let Some(ref dead_letter_topic) = connector.dead_letter_topic else {
+                        warn!(?topition, offset, ?err);
+                        progress.failure = Some(err.to_string());
+                        break;
+                    };
+
+                    self.dead_letter(dead_letter_topic, &delivery, &err).await?;
+                    self.skip(&connector.sink, &delivery).await?;
+                }
+            }
+
+            next = offset + 1;
+        }
+
+        if next > offset {
+            debug!(?topition, next, delivered = progress.delivered);
+
+            for (topition, error_code) in self
+                .storage
+                .offset_commit(
+                    &group_id,
+                    None,
+                    &[(
+                        topition.clone(),
+                        OffsetCommitRequest::default().offset(next),
+                    )],
+                )
+                .await?
+            {
+                if error_code != ErrorCode::None {
+                    warn!(?topition, ?error_code);
+                    return Err(Error::Api(error_code));
+                }
+            }
+        }
+
+        Ok(progress)
+    }
+
+    /// Deliver a record, retrying with an exponential backoff
+    async fn deliver(&mut self, connector: &Connector, delivery: &Delivery<'_>) -> Result<()> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 0;
+
+        loop {
+            match self.send(&connector.sink, delivery).await {
+                Ok(()) => return Ok(()),
+
+                Err(err) if attempt < connector.retries => {
+                    debug!(
+                        topic = delivery.topic,
+                        offset = delivery.offset,
+                        attempt,
+                        ?err
+                    );
+
+                    time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    async fn send(&mut self, sink: &Sink, delivery: &Delivery<'_>) -> Result<()> {
+        match sink {
+            Sink::Http(url) => {
+                let mut request = self
+                    .client
+                    .post(url.clone())
+                    .header("tansu-topic", delivery.topic)
+                    .header("tansu-partition", delivery.partition)
+                    .header("tansu-offset", delivery.offset);
+
+                if let Some(ref key) = delivery.key {
+                    request = request.header("tansu-key", STANDARD.encode(key));
+                }
+
+                _ = request
+                    .body(delivery.value.clone().unwrap_or_default())
+                    .send()
+                    .await?
+                    .error_for_status()?;
+
+                Ok(())
+            }
+
+            #[cfg(feature = "postgres")]
+            Sink::Postgres { url, table } => {
+                let mut client = self.pools.get(url)?.get().await?;
+                let transaction = client.transaction().await?;
+
+                _ = transaction
+                    .execute(
+                        &format!(
+                            "insert into {table} (partition, \"offset\", key, value) \
+                             values ($1, $2, $3, $4) on conflict do nothing"
+                        ),
+                        &[
+                            &delivery.partition,
+                            &delivery.offset,
+                            &delivery.key.as_deref(),
+                            &delivery.value.as_deref(),
+                        ],
+                    )
+                    .await?;
+
+                _ = transaction
+                    .execute(
+                        &record_offset(),
+                        &[&delivery.topic, &delivery.partition, &(delivery.offset + 1)],
+                    )
+                    .await?;
+
+                transaction.commit().await.map_err(Into::into)
+            }
+        }
+    }
+
+    /// The next offset of a partition recorded by a sink that commits it with
+    /// each record, if any
+    #[cfg_attr(not(feature = "postgres"), allow(unused_variables))]
+    async fn recorded(&mut self, sink: &Sink, topition: &Topition) -> Result<Option<i64>> {
+        match sink {
+            Sink::Http(_) => Ok(None),
+
+            #[cfg(feature = "postgres")]
+            Sink::Postgres { url, .. } => {
+                let client = self.pools.get(url)?.get().await?;
+
+                client
+                    .query_opt(
+                        &format!(
+                            "select \"offset\" from {SINK_OFFSET_TABLE} \
+                             where topic = $1 and partition = $2"
+                        ),
+                        &[&topition.topic(), &topition.partition()],
+                    )
+                    .await
+                    .map(|row| row.map(|row| row.get(0)))
+                    .map_err(Into::into)
+            }
+        }
+    }
+
+    /// Record that a dead letter has been skipped, with a sink that commits
+    /// the offset with each record
+    #[cfg_attr(not(feature = "postgres"), allow(unused_variables))]
+    async fn skip(&mut self, sink: &Sink, delivery: &Delivery<'_>) -> Result<()> {
+        match sink {
+            Sink::Http(_) => Ok(()),
+
+            #[cfg(feature = "postgres")]
+            Sink::Postgres { url, .. } => {
+                let client = self.pools.get(url)?.get().await?;
+
+                client
+                    .execute(
+                        &record_offset(),
+                        &[&delivery.topic, &delivery.partition, &(delivery.offset + 1)],
+                    )
+                    .await
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+        }
+    }
+
+    /// Produce a record that could not be delivered to the dead letter topic
+    async fn dead_letter(
+        &self,
+        dead_letter_topic: &str,
+        delivery: &Delivery<'_>,
+        err: &Error,
+    ) -> Result<()> {
+        warn!(
+            topic = delivery.topic,
+            partition = delivery.partition,
+            offset = delivery.offset,
+            dead_letter_topic,
+            ?err
+        );
+
+        let batch = inflated::Batch::builder()
+            .record(
+                Record::builder()
+                    .key(delivery.key.clone())
+                    .value(delivery.value.clone())
+                    .header(
+                        Header::builder()
+                            .key(Bytes::from_static(SINK_ERROR.as_bytes()))
+                            .value(Bytes::from(err.to_string())),
+                    ),
+            )
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        self.storage
+            .produce(None, &Topition::new(dead_letter_topic, 0), batch)
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trip() -> Result<()> {
+        let config = SinkConfig {
+            url: "https://example.com/events".into(),
+            retries: Some(5),
+            dead_letter_topic: Some("dlq".into()),
+            ..Default::default()
+        };
+
+        let encoded = serde_json::to_string(&config)?;
+        assert_eq!(
+            r#"{"url":"https://example.com/events","retries":5,"dead_letter_topic":"dlq"}"#,
+            encoded
+        );
+        assert_eq!(config, serde_json::from_str(&encoded)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn alter_removes_sink() {
+        let resource = SinkConfig::alter("abc", None);
+
+        assert!(
+            resource
+                .configs
+                .unwrap_or_default()
+                .iter()
+                .all(|config| matches!(
+                    OpType::try_from(config.config_operation),
+                    Ok(OpType::Delete)
+                ) && config.value.is_none())
+        );
+    }
+
+    #[test]
+    fn health_state() {
+        assert_eq!(SinkState::Running, Health::default().state());
+
+        assert_eq!(
+            SinkState::Failed,
+            Health {
+                failure: Some("connection refused".into()),
+                ..Default::default()
+            }
+            .state()
+        );
+
+        assert_eq!(
+            SinkState::Paused,
+            Health {
+                paused: true,
+                failure: Some("connection refused".into()),
+                ..Default::default()
+            }
+            .state()
+        );
+    }
+
+    #[test]
+    fn status_as_json() -> Result<()> {
+        let status = SinkStatus {
+            state: SinkState::Failed,
+            errors: 3,
+            failure: Some("connection refused".into()),
+            offsets: [(0, 12), (1, 4)].into(),
+        };
+
+        assert_eq!(
+            r#"{"state":"failed","errors":3,"failure":"connection refused","offsets":{"0":12,"1":4}}"#,
+            serde_json::to_string(&status)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resume_after_crash() {
+        // nothing delivered yet
+        assert_eq!(0, resume_offset(None, None));
+
+        // at least once, from the group
+        assert_eq!(5, resume_offset(Some(5), None));
+
+        // failed before the sink transaction committed
+        assert_eq!(5, resume_offset(Some(5), Some(5)));
+
+        // failed after the sink transaction committed, before the group commit
+        assert_eq!(8, resume_offset(Some(5), Some(8)));
+
+        // the group was deleted, or its offsets expired
+        assert_eq!(8, resume_offset(None, Some(8)));
+    }
+
+    #[test]
+    fn unsupported_sink() {
+        assert!(Sink::new("https://example.com", None).is_ok());
+        assert!(Sink::new("ftp://example.com", None).is_err());
+    }
+}
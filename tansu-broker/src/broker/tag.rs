@@ -0,0 +1,57 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage tagging
+//!
+//! Each request is served with a storage [`Tag`] holding its API key, so that
+//! the SQL issued on behalf of the request can be attributed to it.
+
+use rama::{Context, Layer, Service};
+use tansu_sans_io::Frame;
+use tansu_storage::Tag;
+use tracing::instrument;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct StorageTagLayer;
+
+impl<S> Layer<S> for StorageTagLayer {
+    type Service = StorageTagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StorageTagService { inner }
+    }
+}
+
+/// A [`Service`] serving each [`Frame`] within the storage [`Tag`] of its API key
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct StorageTagService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, Frame> for StorageTagService<S>
+where
+    S: Service<State, Frame, Response = Frame>,
+    State: Send + Sync + 'static,
+{
+    type Response = Frame;
+    type Error = S::Error;
+
+    #[instrument(skip_all, fields(api_key = req.api_key().ok()))]
+    async fn serve(&self, ctx: Context<State>, req: Frame) -> Result<Self::Response, Self::Error> {
+        Tag::default()
+            .api_key(req.api_key().ok())
+            .scope(self.inner.serve(ctx, req))
+            .await
+    }
+}
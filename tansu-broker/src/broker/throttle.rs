@@ -0,0 +1,361 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per topic produce rate limits
+//!
+//! Independent of any client quota, a topic may limit ingress with the
+//! `tansu.produce.records_per_second` and `tansu.produce.bytes_per_second`
+//! topic configs. Each limit is a token bucket holding up to one second of
+//! ingress. A produce that overdraws the bucket is accepted with a throttle
+//! time for the client to back off, while a produce to a topic that is still
+//! overdrawn is rejected with `ThrottlingQuotaExceeded`.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use opentelemetry::{KeyValue, metrics::Counter};
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    ErrorCode, ProduceRequest, ProduceResponse,
+    produce_request::TopicProduceData,
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+};
+use tansu_storage::Storage;
+use tracing::{debug, instrument};
+
+use crate::{METER, broker::topic_config::TopicConfig};
+
+pub const RECORDS_PER_SECOND: &str = "tansu.produce.records_per_second";
+pub const BYTES_PER_SECOND: &str = "tansu.produce.bytes_per_second";
+
+static PRODUCE_THROTTLED: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_produce_throttled")
+        .with_description("produce requests throttled by a topic rate limit")
+        .build()
+});
+
+/// The ingress limits of a topic
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Limit {
+    records: Option<f64>,
+    bytes: Option<f64>,
+}
+
+impl Limit {
+    fn is_unlimited(&self) -> bool {
+        self.records.is_none() && self.bytes.is_none()
+    }
+}
+
+/// Tokens available for each limit, which become negative when overdrawn
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    records: f64,
+    bytes: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: &Limit, now: Instant) -> Self {
+        Self {
+            records: limit.records.unwrap_or_default(),
+            bytes: limit.bytes.unwrap_or_default(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &Limit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+
+        if let Some(rate) = limit.records {
+            self.records = (self.records + rate * elapsed).min(rate);
+        }
+
+        if let Some(rate) = limit.bytes {
+            self.bytes = (self.bytes + rate * elapsed).min(rate);
+        }
+
+        self.updated = now;
+    }
+
+    /// The time until this bucket is no longer overdrawn
+    fn overdrawn(&self, limit: &Limit) -> Duration {
+        let wait = |tokens: f64, rate: Option<f64>| {
+            rate.filter(|rate| *rate > 0.0 && tokens < 0.0)
+                .map_or(0.0, |rate| -tokens / rate)
+        };
+
+        Duration::from_secs_f64(
+            wait(self.records, limit.records).max(wait(self.bytes, limit.bytes)),
+        )
+    }
+
+    /// Withdraw tokens, returning the throttle time, or an error when already overdrawn
+    fn withdraw(
+        &mut self,
+        limit: &Limit,
+        records: u64,
+        bytes: u64,
+        now: Instant,
+    ) -> Result<Duration, Duration> {
+        self.refill(limit, now);
+
+        let overdrawn = self.overdrawn(limit);
+
+        if !overdrawn.is_zero() {
+            return Err(overdrawn);
+        }
+
+        if limit.records.is_some() {
+            self.records -= records as f64;
+        }
+
+        if limit.bytes.is_some() {
+            self.bytes -= bytes as f64;
+        }
+
+        Ok(self.overdrawn(limit))
+    }
+}
+
+type Buckets = Arc<Mutex<BTreeMap<String, Bucket>>>;
+
+/// Records and bytes being produced to a topic
+pub(crate) fn ingress(topic: &TopicProduceData) -> (u64, u64) {
+    topic
+        .partition_data
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|partition| partition.records.as_ref())
+        .flat_map(|frame| frame.batches.iter())
+        .fold((0, 0), |(records, bytes), batch| {
+            (
+                records + u64::from(batch.record_count),
+                bytes + u64::try_from(batch.batch_length).unwrap_or_default(),
+            )
+        })
+}
+
+fn throttled(topic: TopicProduceData) -> TopicProduceResponse {
+    TopicProduceResponse::default()
+        .name(topic.name)
+        .partition_responses(Some(
+            topic
+                .partition_data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|partition| {
+                    PartitionProduceResponse::default()
+                        .index(partition.index)
+                        .error_code(ErrorCode::ThrottlingQuotaExceeded.into())
+                        .base_offset(-1)
+                        .log_append_time_ms(Some(-1))
+                        .log_start_offset(Some(0))
+                        .record_errors(Some([].into()))
+                        .error_message(None)
+                        .current_leader(None)
+                })
+                .collect(),
+        ))
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProduceThrottleLayer {
+    buckets: Buckets,
+}
+
+impl<S> Layer<S> for ProduceThrottleLayer {
+    type Service = ProduceThrottleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProduceThrottleService {
+            buckets: self.buckets.clone(),
+            inner,
+        }
+    }
+}
+
+/// A [`Service`] enforcing the produce rate limits of each topic, with
+/// buckets shared by every connection using this service
+#[derive(Clone, Debug)]
+pub struct ProduceThrottleService<S> {
+    buckets: Buckets,
+    inner: S,
+}
+
+impl<S> ProduceThrottleService<S> {
+    async fn limit<State>(&self, ctx: &Context<State>, topic: &str) -> Limit
+    where
+        State: Storage,
+    {
+        TopicConfig::lookup(ctx, topic)
+            .await
+            .inspect_err(|err| debug!(topic, ?err))
+            .ok()
+            .map_or_else(Limit::default, |config| {
+                let rate = |name| {
+                    config
+                        .get(name)
+                        .and_then(|value| value.parse::<f64>().ok())
+                        .filter(|rate| *rate > 0.0)
+                };
+
+                Limit {
+                    records: rate(RECORDS_PER_SECOND),
+                    bytes: rate(BYTES_PER_SECOND),
+                }
+            })
+    }
+}
+
+impl<S, State> Service<State, ProduceRequest> for ProduceThrottleService<S>
+where
+    S: Service<State, ProduceRequest, Response = ProduceResponse>,
+    State: Storage,
+{
+    type Response = ProduceResponse;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut throttle_time = Duration::ZERO;
+        let mut rejected = vec![];
+        let mut accepted = vec![];
+
+        for topic in req.topic_data.take().unwrap_or_default() {
+            let limit = self.limit(&ctx, topic.name.as_str()).await;
+
+            if limit.is_unlimited() {
+                accepted.push(topic);
+                continue;
+            }
+
+            let (records, bytes) = ingress(&topic);
+            let now = Instant::now();
+
+            let withdrawn = self.buckets.lock().map_or(Ok(Duration::ZERO), |mut guard| {
+                let bucket = guard
+                    .entry(topic.name.clone())
+                    .or_insert_with(|| Bucket::new(&limit, now));
+
+                bucket.withdraw(&limit, records, bytes, now)
+            });
+
+            debug!(topic = topic.name, ?limit, records, bytes, ?withdrawn);
+
+            match withdrawn {
+                Ok(throttle) => {
+                    throttle_time = throttle_time.max(throttle);
+                    accepted.push(topic);
+                }
+
+                Err(throttle) => {
+                    PRODUCE_THROTTLED.add(1, &[KeyValue::new("topic", topic.name.clone())]);
+
+                    throttle_time = throttle_time.max(throttle);
+                    rejected.push(throttled(topic));
+                }
+            }
+        }
+
+        req.topic_data = Some(accepted);
+
+        let throttle_time_ms = i32::try_from(throttle_time.as_millis()).unwrap_or(i32::MAX);
+
+        self.inner.serve(ctx, req).await.map(|mut response| {
+            if !rejected.is_empty() {
+                response
+                    .responses
+                    .get_or_insert_default()
+                    .append(&mut rejected);
+            }
+
+            if throttle_time_ms > 0 {
+                response.throttle_time_ms = response
+                    .throttle_time_ms
+                    .map(|existing| existing.max(throttle_time_ms));
+            }
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn within_limit() {
+        let limit = Limit {
+            records: Some(10.0),
+            bytes: None,
+        };
+
+        let now = Instant::now();
+        let mut bucket = Bucket::new(&limit, now);
+
+        assert_eq!(Ok(Duration::ZERO), bucket.withdraw(&limit, 5, 1_024, now));
+        assert_eq!(Ok(Duration::ZERO), bucket.withdraw(&limit, 5, 1_024, now));
+    }
+
+    #[test]
+    fn overdrawn_is_throttled_then_rejected() {
+        let limit = Limit {
+            records: None,
+            bytes: Some(1_000.0),
+        };
+
+        let now = Instant::now();
+        let mut bucket = Bucket::new(&limit, now);
+
+        assert_eq!(
+            Ok(Duration::from_millis(500)),
+            bucket.withdraw(&limit, 1, 1_500, now)
+        );
+
+        assert_eq!(
+            Err(Duration::from_millis(250)),
+            bucket.withdraw(&limit, 1, 1, now + Duration::from_millis(250))
+        );
+
+        assert_eq!(
+            Ok(Duration::ZERO),
+            bucket.withdraw(&limit, 1, 0, now + Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn refill_is_capped_at_one_second() {
+        let limit = Limit {
+            records: Some(100.0),
+            bytes: None,
+        };
+
+        let now = Instant::now();
+        let mut bucket = Bucket::new(&limit, now);
+        bucket.refill(&limit, now + Duration::from_secs(60));
+
+        assert_eq!(100.0, bucket.records);
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TLS termination on the Kafka listener
+//!
+//! The certificate chain and private key are PEM files that are read on
+//! startup and again on `SIGHUP`, so that a renewed certificate is used by new
+//! connections without restarting the broker. A failed reload is logged,
+//! leaving the previous certificate in use.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use tokio::signal::unix::{SignalKind, signal};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        InconsistentKeys, ServerConfig,
+        crypto::{CryptoProvider, ring},
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject as _},
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
+    },
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::{Error, Result, broker::platform};
+
+/// Resolves the most recently loaded certificate for every client hello
+#[derive(Debug)]
+struct Reloadable(RwLock<Arc<CertifiedKey>>);
+
+impl ResolvesServerCert for Reloadable {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.0
+            .read()
+            .ok()
+            .map(|certified_key| certified_key.clone())
+    }
+}
+
+/// TLS configuration of the Kafka listener
+#[derive(Clone)]
+pub struct Tls {
+    certificate: PathBuf,
+    private_key: PathBuf,
+    provider: Arc<CryptoProvider>,
+    resolver: Arc<Reloadable>,
+    acceptor: TlsAcceptor,
+}
+
+impl fmt::Debug for Tls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(stringify!(Tls))
+            .field("certificate", &self.certificate)
+            .field("private_key", &self.private_key)
+            .finish()
+    }
+}
+
+fn certified_key(
+    provider: &CryptoProvider,
+    certificate: &Path,
+    private_key: &Path,
+) -> Result<CertifiedKey> {
+    let chain = platform::read_blocking(certificate, "tls certificate")
+        .map_err(Error::from)
+        .and_then(|pem| {
+            CertificateDer::pem_slice_iter(&pem[..])
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Into::into)
+        })?;
+
+    if chain.is_empty() {
+        return Err(Error::Message(format!(
+            "no certificates in: {}",
+            certificate.display()
+        )));
+    }
+
+    let key = platform::read_blocking(private_key, "tls private key")
+        .map_err(Error::from)
+        .and_then(|pem| PrivateKeyDer::from_pem_slice(&pem[..]).map_err(Into::into))?;
+
+    provider
+        .key_provider
+        .load_private_key(key)
+        .map(|signing_key| CertifiedKey::new(chain, signing_key))
+        .map_err(Into::into)
+}
+
+impl Tls {
+    /// Load the PEM certificate chain and private key
+    pub fn new(certificate: impl Into<PathBuf>, private_key: impl Into<PathBuf>) -> Result<Self> {
+        let certificate = certificate.into();
+        let private_key = private_key.into();
+        let provider = Arc::new(ring::default_provider());
+
+        let resolver = certified_key(&provider, &certificate, &private_key)
+            .map(|certified_key| Arc::new(Reloadable(RwLock::new(Arc::new(certified_key)))))?;
+
+        let config = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map(|builder| {
+                builder
+                    .with_no_client_auth()
+                    .with_cert_resolver(resolver.clone())
+            })?;
+
+        Ok(Self {
+            certificate,
+            private_key,
+            provider,
+            resolver,
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    pub(crate) fn acceptor(&self) -> &TlsAcceptor {
+        &self.acceptor
+    }
+
+    /// Reload the certificate chain and private key, used by subsequent handshakes
+    pub fn reload(&self) -> Result<()> {
+        let certified_key = certified_key(&self.provider, &self.certificate, &self.private_key)?;
+
+        self.resolver
+            .0
+            .write()
+            .map(|mut guard| *guard = Arc::new(certified_key))
+            .map_err(Into::into)
+    }
+
+    /// Verify that the certificate chain and private key are still readable
+    /// and that the private key belongs to the certificate
+    pub fn verify(&self) -> Result<()> {
+        match certified_key(&self.provider, &self.certificate, &self.private_key)?.keys_match() {
+            Err(tokio_rustls::rustls::Error::InconsistentKeys(InconsistentKeys::KeyMismatch)) => {
+                Err(Error::Message(format!(
+                    "private key: {} does not match certificate: {}",
+                    self.private_key.display(),
+                    self.certificate.display()
+                )))
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Reload on every `SIGHUP` until cancelled
+    pub async fn serve(self, cancellation: CancellationToken) -> Result<()> {
+        let mut hangup = signal(SignalKind::hangup())?;
+
+        loop {
+            tokio::select! {
+                hangup = hangup.recv() => {
+                    debug!(?hangup);
+
+                    match self.reload() {
+                        Ok(()) => info!(certificate = %self.certificate.display(), "reloaded"),
+                        Err(err) => warn!(certificate = %self.certificate.display(), ?err),
+                    }
+                }
+
+                cancelled = cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
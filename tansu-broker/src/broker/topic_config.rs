@@ -0,0 +1,134 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Topic configuration resolved once per produce request
+//!
+//! Many layers of the produce stack depend on a topic config (linking,
+//! claim checks, message size, throttling, recompression, global ordering).
+//! [`TopicConfigLayer`] describes each produced topic once, inserting the
+//! result into the [`Context`] so that the layers beneath it share a single
+//! lookup rather than each describing the topic again.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use rama::{Context, Layer, Service};
+use tansu_sans_io::{ConfigResource, ProduceRequest};
+use tansu_storage::Storage;
+use tracing::{debug, instrument};
+
+/// The configuration of a topic by name, including inherited defaults
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TopicConfig(BTreeMap<String, String>);
+
+impl TopicConfig {
+    /// Describe the configuration of a topic from storage
+    pub async fn describe<S>(storage: &S, topic: &str) -> tansu_storage::Result<Self>
+    where
+        S: Storage,
+    {
+        storage
+            .describe_config(topic, ConfigResource::Topic, None)
+            .await
+            .map(|result| {
+                Self(
+                    result
+                        .configs
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|config| config.value.map(|value| (config.name, value)))
+                        .collect(),
+                )
+            })
+    }
+
+    /// The configuration of a topic resolved for this request, otherwise
+    /// described from storage
+    pub async fn lookup<State>(
+        ctx: &Context<State>,
+        topic: &str,
+    ) -> tansu_storage::Result<Arc<Self>>
+    where
+        State: Storage,
+    {
+        if let Some(config) = ctx
+            .get::<TopicConfigs>()
+            .and_then(|configs| configs.0.get(topic))
+        {
+            return Ok(config.clone());
+        }
+
+        Self::describe(ctx.state(), topic).await.map(Arc::new)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+/// The configuration of each topic of a produce request
+#[derive(Clone, Debug, Default)]
+pub struct TopicConfigs(Arc<BTreeMap<String, Arc<TopicConfig>>>);
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TopicConfigLayer;
+
+impl<S> Layer<S> for TopicConfigLayer {
+    type Service = TopicConfigService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TopicConfigService { inner }
+    }
+}
+
+/// A [`Service`] describing the configuration of each produced topic once,
+/// before passing the request to the inner service
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TopicConfigService<S> {
+    inner: S,
+}
+
+impl<S, State> Service<State, ProduceRequest> for TopicConfigService<S>
+where
+    S: Service<State, ProduceRequest>,
+    State: Storage,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: ProduceRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut configs = BTreeMap::new();
+
+        for topic in req.topic_data.as_deref().unwrap_or_default() {
+            if configs.contains_key(&topic.name) {
+                continue;
+            }
+
+            // a topic that cannot be described is left to each layer, which
+            // will describe it again and handle the error as it would otherwise
+            match TopicConfig::describe(ctx.state(), topic.name.as_str()).await {
+                Ok(config) => _ = configs.insert(topic.name.clone(), Arc::new(config)),
+                Err(err) => debug!(topic = topic.name, ?err),
+            }
+        }
+
+        _ = ctx.insert(TopicConfigs(Arc::new(configs)));
+
+        self.inner.serve(ctx, req).await
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transaction timeouts
+//!
+//! As with `transaction.timeout.ms` in Kafka, an ongoing transaction that
+//! has not ended within the timeout of its producer is aborted by the broker
+//! coordinating its transactional id, so that a producer that has gone away
+//! does not leave a transaction open (and read committed consumers behind
+//! its last stable offset) forever.
+//!
+//! The transaction is aborted by initialising its producer again, writing
+//! abort markers to the partitions of the transaction and bumping the
+//! producer epoch, fencing the previous producer should it return.
+//!
+//! Storage without queryable transaction state has no transactions to
+//! describe, and none are aborted.
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use tansu_sans_io::ErrorCode;
+use tansu_storage::{Storage, TxnDescription, TxnState};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    Result,
+    clock::{Clock, SystemClock},
+};
+
+/// The key type of a transactional id in a coordinator lease
+const TRANSACTION: i8 = 1;
+
+/// Whether an ongoing transaction has exceeded its timeout
+fn has_timed_out(txn: &TxnDescription, now: SystemTime) -> bool {
+    txn.state == Some(TxnState::Begin)
+        && txn.started_at.is_some_and(|started_at| {
+            started_at + Duration::from_millis(u64::try_from(txn.timeout_ms).unwrap_or_default())
+                < now
+        })
+}
+
+/// Aborts the ongoing transactions that have exceeded their timeout
+#[derive(Clone, Debug)]
+pub struct TxnTimeout<S> {
+    storage: S,
+    interval: Duration,
+    clock: Arc<dyn Clock>,
+    cancellation: CancellationToken,
+}
+
+impl<S> TxnTimeout<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            clock: Arc::new(SystemClock),
+            cancellation,
+        }
+    }
+
+    /// Read the time from this clock, rather than the system clock
+    pub fn clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub async fn serve(self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    _ = self
+                        .abort()
+                        .await
+                        .inspect(|aborted| debug!(aborted))
+                        .inspect_err(|err| warn!(?err));
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Abort each timed out transaction coordinated by this broker,
+    /// returning the number of transactions aborted
+    #[instrument(skip(self))]
+    pub async fn abort(&self) -> Result<usize> {
+        let now = self.clock.now();
+        let node_id = self.storage.node().await?;
+
+        let mut aborted = 0;
+
+        for txn in self
+            .storage
+            .describe_txns(None)
+            .await?
+            .into_iter()
+            .filter(|txn| has_timed_out(txn, now))
+        {
+            let lease = self
+                .storage
+                .coordinator_lease(TRANSACTION, &txn.transactional_id)
+                .await?;
+
+            if lease.node_id != node_id {
+                debug!(transactional_id = txn.transactional_id, ?lease);
+                continue;
+            }
+
+            let response = self
+                .storage
+                .init_producer(
+                    Some(&txn.transactional_id),
+                    txn.timeout_ms,
+                    Some(-1),
+                    Some(-1),
+                )
+                .await?;
+
+            if response.error == ErrorCode::None {
+                info!(
+                    transactional_id = txn.transactional_id,
+                    producer_id = txn.producer_id,
+                    producer_epoch = txn.producer_epoch,
+                    fenced_by = response.epoch,
+                    timeout_ms = txn.timeout_ms
+                );
+
+                aborted += 1;
+            } else {
+                warn!(transactional_id = txn.transactional_id, ?response);
+            }
+        }
+
+        Ok(aborted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txn(state: Option<TxnState>, started_at: Option<SystemTime>) -> TxnDescription {
+        TxnDescription {
+            transactional_id: "abc".into(),
+            state,
+            timeout_ms: 60_000,
+            started_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn timed_out() {
+        let now = SystemTime::now();
+        let ago = |secs| now - Duration::from_secs(secs);
+
+        assert!(has_timed_out(
+            &txn(Some(TxnState::Begin), Some(ago(61))),
+            now
+        ));
+
+        assert!(!has_timed_out(
+            &txn(Some(TxnState::Begin), Some(ago(59))),
+            now
+        ));
+
+        assert!(!has_timed_out(
+            &txn(Some(TxnState::Committed), Some(ago(61))),
+            now
+        ));
+
+        assert!(!has_timed_out(&txn(None, None), now));
+    }
+}
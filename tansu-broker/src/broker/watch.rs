@@ -0,0 +1,423 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Topic metadata changes
+//!
+//! A [`TopicWatch`] periodically compares the topics held in storage with
+//! those it previously saw, recording each topic that has been created or
+//! deleted, has a different partition count or a changed config. As the
+//! comparison is with storage, changes made through any broker sharing that
+//! storage are observed.
+//!
+//! Subscribers long-poll [`TopicChanges::since`] with the sequence of the last
+//! change that they have seen, and are answered as soon as a later change is
+//! recorded. Only the most recent [`RETAINED`] changes are kept. A subscriber
+//! that has fallen further behind, or is ahead following a broker restart, is
+//! told that changes were missed, so that it can resynchronize with a Metadata
+//! request.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tansu_sans_io::{ConfigResource, ErrorCode};
+use tansu_storage::Storage;
+use tokio::{sync::watch, time};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::Result;
+
+/// The number of recorded changes kept for subscribers
+pub const RETAINED: usize = 1_024;
+
+/// The partition count and configs of a topic
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct TopicState {
+    partitions: i32,
+    configs: BTreeMap<String, Option<String>>,
+}
+
+type Snapshot = BTreeMap<String, TopicState>;
+
+/// A change to the metadata of a topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Created {
+        partitions: i32,
+    },
+
+    Deleted,
+
+    Partitions {
+        from: i32,
+        to: i32,
+    },
+
+    Config {
+        name: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+/// A recorded change to a topic
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct TopicChange {
+    pub sequence: u64,
+    pub topic: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// The changes recorded after a sequence
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Changes {
+    /// The sequence of the most recently recorded change
+    pub sequence: u64,
+
+    /// Whether changes following the requested sequence are no longer retained
+    pub missed: bool,
+
+    pub changes: Vec<TopicChange>,
+}
+
+/// The changes between two snapshots of the topics in storage
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<(String, Change)> {
+    let mut changes = vec![];
+
+    for (topic, state) in after {
+        let Some(previous) = before.get(topic) else {
+            changes.push((
+                topic.to_owned(),
+                Change::Created {
+                    partitions: state.partitions,
+                },
+            ));
+            continue;
+        };
+
+        if previous.partitions != state.partitions {
+            changes.push((
+                topic.to_owned(),
+                Change::Partitions {
+                    from: previous.partitions,
+                    to: state.partitions,
+                },
+            ));
+        }
+
+        for name in previous
+            .configs
+            .keys()
+            .chain(state.configs.keys())
+            .collect::<BTreeSet<_>>()
+        {
+            let from = previous.configs.get(name).cloned().flatten();
+            let to = state.configs.get(name).cloned().flatten();
+
+            if from != to {
+                changes.push((
+                    topic.to_owned(),
+                    Change::Config {
+                        name: name.to_owned(),
+                        from,
+                        to,
+                    },
+                ));
+            }
+        }
+    }
+
+    for topic in before.keys().filter(|topic| !after.contains_key(*topic)) {
+        changes.push((topic.to_owned(), Change::Deleted));
+    }
+
+    changes
+}
+
+/// Changes recorded by a [`TopicWatch`], shared with subscribers
+#[derive(Clone, Debug)]
+pub struct TopicChanges {
+    recorded: Arc<Mutex<VecDeque<TopicChange>>>,
+    sequence: Arc<watch::Sender<u64>>,
+}
+
+impl Default for TopicChanges {
+    fn default() -> Self {
+        Self {
+            recorded: Arc::new(Mutex::new(VecDeque::with_capacity(RETAINED))),
+            sequence: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl TopicChanges {
+    fn record(&self, changes: Vec<(String, Change)>) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut sequence = *self.sequence.borrow();
+
+        self.recorded.lock().map(|mut recorded| {
+            for (topic, change) in changes {
+                sequence += 1;
+                debug!(sequence, topic, ?change);
+
+                recorded.push_back(TopicChange {
+                    sequence,
+                    topic,
+                    change,
+                });
+
+                if recorded.len() > RETAINED {
+                    _ = recorded.pop_front();
+                }
+            }
+        })?;
+
+        _ = self.sequence.send_replace(sequence);
+        Ok(())
+    }
+
+    fn after(&self, since: u64) -> Result<Changes> {
+        let sequence = *self.sequence.borrow();
+
+        self.recorded
+            .lock()
+            .map(|recorded| {
+                let missed = since > sequence
+                    || recorded
+                        .front()
+                        .is_some_and(|oldest| oldest.sequence > since + 1);
+
+                Changes {
+                    sequence,
+                    missed,
+                    changes: recorded
+                        .iter()
+                        .filter(|change| missed || change.sequence > since)
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    /// The changes recorded after since, waiting up to timeout for a change
+    pub async fn since(&self, since: u64, timeout: Duration) -> Result<Changes> {
+        let mut receiver = self.sequence.subscribe();
+
+        _ = time::timeout(timeout, receiver.wait_for(|sequence| *sequence != since)).await;
+
+        self.after(since)
+    }
+}
+
+/// Records changes to the topics held in storage at an interval
+#[derive(Clone, Debug)]
+pub struct TopicWatch<S> {
+    storage: S,
+    interval: Duration,
+    changes: TopicChanges,
+    cancellation: CancellationToken,
+}
+
+impl<S> TopicWatch<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S, interval: Duration, cancellation: CancellationToken) -> Self {
+        Self {
+            storage,
+            interval,
+            changes: TopicChanges::default(),
+            cancellation,
+        }
+    }
+
+    /// The changes recorded by this watch
+    pub fn changes(&self) -> TopicChanges {
+        self.changes.clone()
+    }
+
+    pub async fn serve(self) -> Result<()> {
+        let mut interval = time::interval(self.interval);
+        let mut previous = None;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match self.snapshot().await {
+                        Ok(snapshot) => {
+                            if let Some(previous) = previous.as_ref() {
+                                self.changes.record(diff(previous, &snapshot))?;
+                            }
+
+                            previous = Some(snapshot);
+                        }
+
+                        Err(err) => warn!(?err),
+                    }
+                }
+
+                cancelled = self.cancellation.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new();
+
+        for topic in self.storage.metadata(None).await?.topics() {
+            let Some(name) = topic.name.as_deref() else {
+                continue;
+            };
+
+            if topic.error_code != i16::from(ErrorCode::None) {
+                continue;
+            }
+
+            let configs = self
+                .storage
+                .describe_config(name, ConfigResource::Topic, None)
+                .await?
+                .configs
+                .unwrap_or_default()
+                .into_iter()
+                .map(|config| (config.name, config.value))
+                .collect();
+
+            _ = snapshot.insert(
+                name.to_owned(),
+                TopicState {
+                    partitions: topic
+                        .partitions
+                        .as_ref()
+                        .map_or(0, |partitions| partitions.len() as i32),
+                    configs,
+                },
+            );
+        }
+
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(partitions: i32, configs: &[(&str, &str)]) -> TopicState {
+        TopicState {
+            partitions,
+            configs: configs
+                .iter()
+                .map(|(name, value)| (name.to_string(), Some(value.to_string())))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn changes_between_snapshots() {
+        let before = Snapshot::from([
+            ("abc".into(), state(1, &[("cleanup.policy", "delete")])),
+            ("pqr".into(), state(3, &[])),
+        ]);
+
+        let after = Snapshot::from([
+            ("abc".into(), state(3, &[("cleanup.policy", "compact")])),
+            ("xyz".into(), state(2, &[])),
+        ]);
+
+        assert_eq!(
+            vec![
+                ("abc".into(), Change::Partitions { from: 1, to: 3 }),
+                (
+                    "abc".into(),
+                    Change::Config {
+                        name: "cleanup.policy".into(),
+                        from: Some("delete".into()),
+                        to: Some("compact".into()),
+                    }
+                ),
+                ("xyz".into(), Change::Created { partitions: 2 }),
+                ("pqr".into(), Change::Deleted),
+            ],
+            diff(&before, &after)
+        );
+
+        assert!(diff(&after, &after).is_empty());
+    }
+
+    #[tokio::test]
+    async fn long_poll() -> Result<()> {
+        let changes = TopicChanges::default();
+
+        let waiting = {
+            let changes = changes.clone();
+            tokio::spawn(async move { changes.since(0, Duration::from_secs(5)).await })
+        };
+
+        changes.record(vec![("abc".into(), Change::Created { partitions: 1 })])?;
+
+        let answered = waiting.await??;
+        assert_eq!(1, answered.sequence);
+        assert!(!answered.missed);
+        assert_eq!(1, answered.changes.len());
+        assert_eq!(1, answered.changes[0].sequence);
+
+        let idle = changes.since(1, Duration::from_millis(10)).await?;
+        assert_eq!(1, idle.sequence);
+        assert!(idle.changes.is_empty());
+
+        let restarted = changes.since(7, Duration::from_millis(10)).await?;
+        assert!(restarted.missed);
+        assert_eq!(1, restarted.changes.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn retained() -> Result<()> {
+        let changes = TopicChanges::default();
+
+        changes.record(
+            (0..RETAINED + 2)
+                .map(|i| (format!("t{i}"), Change::Deleted))
+                .collect(),
+        )?;
+
+        let behind = changes.after(0)?;
+        assert!(behind.missed);
+        assert_eq!(RETAINED, behind.changes.len());
+        assert_eq!(3, behind.changes[0].sequence);
+
+        let current = changes.after(2)?;
+        assert!(!current.missed);
+        assert_eq!(RETAINED, current.changes.len());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Time source
+//!
+//! Time driven behaviour (retention, compaction, group session timeouts and
+//! transaction expiry) reads the time from a [`Clock`] rather than directly
+//! from [`SystemTime`]. The broker uses the [`SystemClock`], while a test can
+//! use a [`VirtualClock`], advancing it deterministically rather than
+//! sleeping. Combined with a paused tokio runtime (`tokio::time::pause`), the
+//! interval of a background subsystem can be advanced too.
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// A source of the current time
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The time of the system
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A time that only changes when set or advanced, shared by each clone
+#[derive(Clone, Debug)]
+pub struct VirtualClock(Arc<Mutex<SystemTime>>);
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new(SystemTime::now())
+    }
+}
+
+impl VirtualClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self(Arc::new(Mutex::new(now)))
+    }
+
+    /// Move the time forward
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut now) = self.0.lock() {
+            *now += duration;
+        }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = now;
+        }
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> SystemTime {
+        self.0
+            .lock()
+            .map(|now| *now)
+            .unwrap_or_else(|poisoned| *poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance() {
+        let clock = VirtualClock::new(SystemTime::UNIX_EPOCH);
+        let shared = clock.clone();
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+            shared.now()
+        );
+
+        shared.set(SystemTime::UNIX_EPOCH);
+        assert_eq!(SystemTime::UNIX_EPOCH, clock.now());
+    }
+}
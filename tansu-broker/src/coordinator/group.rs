@@ -14,16 +14,22 @@
 
 pub mod administrator;
 pub mod consumer;
+mod cooperative;
+pub mod lease;
+pub mod share;
 
 use crate::Result;
 use async_trait::async_trait;
 use std::fmt::Debug;
 use tansu_sans_io::{
     Body,
+    consumer_group_heartbeat_request::TopicPartitions,
     join_group_request::JoinGroupRequestProtocol,
     leave_group_request::MemberIdentity,
     offset_commit_request::OffsetCommitRequestTopic,
     offset_fetch_request::{OffsetFetchRequestGroup, OffsetFetchRequestTopic},
+    share_acknowledge_request::AcknowledgeTopic,
+    share_fetch_request::{FetchTopic, ForgottenTopic},
     sync_group_request::SyncGroupRequestAssignment,
 };
 
@@ -37,6 +43,48 @@ pub struct OffsetCommit<'a> {
     pub topics: Option<&'a [OffsetCommitRequestTopic]>,
 }
 
+#[derive(Debug)]
+pub struct ConsumerGroupHeartbeat<'a> {
+    pub client_id: Option<&'a str>,
+    pub group_id: &'a str,
+    pub member_id: &'a str,
+    pub member_epoch: i32,
+    pub instance_id: Option<&'a str>,
+    pub rack_id: Option<&'a str>,
+    pub rebalance_timeout_ms: i32,
+    pub subscribed_topic_names: Option<&'a [String]>,
+    pub server_assignor: Option<&'a str>,
+    pub topic_partitions: Option<&'a [TopicPartitions]>,
+}
+
+#[derive(Debug)]
+pub struct ShareGroupHeartbeat<'a> {
+    pub client_id: Option<&'a str>,
+    pub group_id: &'a str,
+    pub member_id: &'a str,
+    pub member_epoch: i32,
+    pub rack_id: Option<&'a str>,
+    pub subscribed_topic_names: Option<&'a [String]>,
+}
+
+#[derive(Debug)]
+pub struct ShareFetch<'a> {
+    pub group_id: &'a str,
+    pub member_id: &'a str,
+    pub share_session_epoch: i32,
+    pub max_bytes: i32,
+    pub topics: Option<&'a [FetchTopic]>,
+    pub forgotten_topics_data: Option<&'a [ForgottenTopic]>,
+}
+
+#[derive(Debug)]
+pub struct ShareAcknowledge<'a> {
+    pub group_id: &'a str,
+    pub member_id: &'a str,
+    pub share_session_epoch: i32,
+    pub topics: Option<&'a [AcknowledgeTopic]>,
+}
+
 #[async_trait]
 pub trait Coordinator: Clone + Debug + Send + Sync + 'static {
     #[allow(clippy::too_many_arguments)]
@@ -89,4 +137,15 @@ pub trait Coordinator: Clone + Debug + Send + Sync + 'static {
         groups: Option<&[OffsetFetchRequestGroup]>,
         require_stable: Option<bool>,
     ) -> Result<Body>;
+
+    async fn consumer_group_heartbeat(
+        &mut self,
+        detail: ConsumerGroupHeartbeat<'_>,
+    ) -> Result<Body>;
+
+    async fn share_group_heartbeat(&mut self, detail: ShareGroupHeartbeat<'_>) -> Result<Body>;
+
+    async fn share_fetch(&mut self, detail: ShareFetch<'_>) -> Result<Body>;
+
+    async fn share_acknowledge(&mut self, detail: ShareAcknowledge<'_>) -> Result<Body>;
 }
@@ -18,7 +18,7 @@ use std::{
     hash::{Hash, Hasher},
     marker::PhantomData,
     ops::Deref,
-    sync::LazyLock,
+    sync::{Arc, LazyLock},
     time::SystemTime,
 };
 
@@ -44,19 +44,37 @@ use tansu_sans_io::{
     sync_group_response::SyncGroupResponse,
 };
 use tansu_storage::{
-    GroupDetail, GroupMember, GroupState, OffsetCommitRequest, Storage, Topition, UpdateError,
-    Version,
+    ConsumerGroup, GroupDetail, GroupMember, GroupState, OffsetCommitRequest, ShareGroup, Storage,
+    Topition, UpdateError, Version,
+    purgatory::{Delayed, Key},
 };
-use tokio::time::{Duration, sleep};
+use tokio::time::Duration;
 use tracing::{debug, error, info};
 use uuid::Uuid;
 
-use crate::{Error, METER, Result};
+use crate::{
+    Error, METER, Result,
+    clock::{Clock, SystemClock},
+};
 
-use super::{Coordinator, OffsetCommit};
+use super::{
+    ConsumerGroupHeartbeat, Coordinator, OffsetCommit, ShareAcknowledge, ShareFetch,
+    ShareGroupHeartbeat, consumer, cooperative, share,
+};
 
 const PAUSE_MS: u128 = 3_000;
 
+/// Pause until the group is next updated by this broker, or the pause expires
+async fn pause(group_id: &str, pause_ms: u128) -> Result<()> {
+    let mut delayed = Delayed::new(Duration::from_millis(u64::try_from(pause_ms)?));
+    delayed.watch(Key::Group(group_id.to_owned()))?;
+
+    let wake = delayed.wait(None).await;
+    debug!(group_id, pause_ms, ?wake);
+
+    Ok(())
+}
+
 static COORDINATOR_REQUESTS: LazyLock<Counter<u64>> = LazyLock::new(|| {
     METER
         .u64_counter("tansu_group_coordinator_requests")
@@ -201,6 +219,8 @@ where
                 state,
                 skip_assignment,
                 inception,
+                consumer,
+                share,
                 ..
             }) => GroupDetail {
                 session_timeout_ms: *session_timeout_ms,
@@ -220,6 +240,8 @@ where
                 generation_id: *generation_id,
                 skip_assignment: *skip_assignment,
                 inception: *inception,
+                consumer: consumer.clone(),
+                share: share.clone(),
                 state: GroupState::Forming {
                     protocol_type: state.protocol_type.clone(),
                     protocol_name: state.protocol_name.clone(),
@@ -234,6 +256,8 @@ where
                 state,
                 skip_assignment,
                 inception,
+                consumer,
+                share,
                 ..
             }) => GroupDetail {
                 session_timeout_ms: *session_timeout_ms,
@@ -253,6 +277,8 @@ where
                 generation_id: *generation_id,
                 skip_assignment: *skip_assignment,
                 inception: *inception,
+                consumer: consumer.clone(),
+                share: share.clone(),
                 state: GroupState::Formed {
                     protocol_type: state.protocol_type.clone(),
                     protocol_name: state.protocol_name.clone(),
@@ -309,6 +335,8 @@ where
                     storage,
                     skip_assignment: gd.skip_assignment,
                     inception: gd.inception,
+                    consumer: gd.consumer,
+                    share: gd.share,
                 })
             }
             GroupState::Formed {
@@ -342,6 +370,8 @@ where
                 storage,
                 skip_assignment: gd.skip_assignment,
                 inception: gd.inception,
+                consumer: gd.consumer,
+                share: gd.share,
             }),
         }
     }
@@ -360,6 +390,22 @@ where
         }
     }
 
+    /// The state of a group using the consumer rebalance protocol
+    pub fn consumer(&self) -> Option<&ConsumerGroup> {
+        match self {
+            Self::Forming(inner) => inner.consumer.as_ref(),
+            Self::Formed(inner) => inner.consumer.as_ref(),
+        }
+    }
+
+    /// The state of a share group
+    pub fn share(&self) -> Option<&ShareGroup> {
+        match self {
+            Self::Forming(inner) => inner.share.as_ref(),
+            Self::Formed(inner) => inner.share.as_ref(),
+        }
+    }
+
     pub fn session_timeout_ms(&self) -> i32 {
         match self {
             Self::Forming(inner) => inner.session_timeout_ms,
@@ -457,6 +503,8 @@ where
                         storage: inner.storage,
                         skip_assignment: inner.skip_assignment,
                         inception: inner.inception,
+                        consumer: inner.consumer,
+                        share: inner.share,
                     })
                 } else {
                     Self::Formed(inner)
@@ -676,6 +724,7 @@ where
 pub struct Controller<O> {
     storage: O,
     wrappers: BTreeMap<String, (Wrapper<O>, Option<Version>)>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<O> Controller<O>
@@ -686,8 +735,83 @@ where
         Ok(Self {
             storage,
             wrappers: BTreeMap::new(),
+            clock: Arc::new(SystemClock),
         })
     }
+
+    /// Expire sessions and locks using the time of this clock, rather than the system clock
+    pub fn clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Apply a request to a share group, retrying with the latest state of
+    /// the group when outdated
+    async fn share(&mut self, method: &'static str, request: share::Request<'_>) -> Result<Body> {
+        debug!(?request);
+        COORDINATOR_REQUESTS.add(1, &[KeyValue::new("method", method)]);
+
+        let group_id = request.group_id();
+        let mut iteration = 0;
+
+        let (mut current, mut version) = self
+            .wrappers
+            .remove(group_id)
+            .map_or((GroupDetail::default(), None), |(wrapper, version)| {
+                (GroupDetail::from(&wrapper), version)
+            });
+
+        loop {
+            debug!(?group_id, ?current, ?version, ?iteration);
+
+            let body =
+                share::apply(&self.storage, &mut current, self.clock.now(), &request).await?;
+
+            match self
+                .storage
+                .update_group(group_id, current.clone(), version)
+                .await
+            {
+                Ok(version) => {
+                    debug!(?group_id, ?version);
+
+                    _ = self.wrappers.insert(
+                        group_id.to_owned(),
+                        (
+                            Wrapper::with_storage_group_detail(self.storage.clone(), current),
+                            Some(version),
+                        ),
+                    );
+
+                    return Ok(body);
+                }
+
+                Err(UpdateError::Outdated {
+                    current: latest,
+                    version: latest_version,
+                }) => {
+                    debug!(?group_id, ?latest, ?latest_version);
+                    COORDINATOR_REQUESTS.add(1, &[KeyValue::new("method", "share_outdated")]);
+
+                    current = latest;
+                    version = Some(latest_version);
+                    iteration += 1;
+                    continue;
+                }
+
+                Err(UpdateError::Error(error)) => return Err(error.into()),
+
+                Err(UpdateError::SerdeJson(error)) => return Err(error.into()),
+
+                Err(UpdateError::MissingEtag) => {
+                    return Err(Error::Message(String::from("missing e-tag")));
+                }
+
+                Err(UpdateError::Uuid(uuid)) => {
+                    return Err(Error::Message(format!("uuid: {uuid}")));
+                }
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -728,7 +852,7 @@ where
         loop {
             COORDINATOR_REQUESTS.add(1, &[KeyValue::new("method", "join_loop")]);
 
-            let now = SystemTime::now();
+            let now = self.clock.now();
 
             let (mut original, version) = self.wrappers.remove(group_id).unwrap_or_else(|| {
                 debug!(?iteration, ?group_id);
@@ -741,7 +865,9 @@ where
                     state: Forming::default(),
                     skip_assignment: Some(false),
                     storage: self.storage.clone(),
-                    inception: SystemTime::now(),
+                    inception: now,
+                    consumer: None,
+                    share: None,
                 };
 
                 (Wrapper::Forming(inner), None)
@@ -751,6 +877,32 @@ where
                 original = original.missed_heartbeat(group_id, now);
             }
 
+            if original
+                .consumer()
+                .is_some_and(|consumer| !consumer.members.is_empty())
+                || original
+                    .share()
+                    .is_some_and(|share| !share.members.is_empty())
+            {
+                debug!(group_id, "consumer protocol or share group");
+
+                _ = self
+                    .wrappers
+                    .insert(group_id.to_owned(), (original, version));
+
+                return Ok(JoinGroupResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::InconsistentGroupProtocol.into())
+                    .generation_id(-1)
+                    .protocol_type(None)
+                    .protocol_name(None)
+                    .leader("".into())
+                    .skip_assignment(Some(false))
+                    .member_id(member_id.into())
+                    .members(Some([].into()))
+                    .into());
+            }
+
             if iteration == 0
                 && !member_id.is_empty()
                 && original.leader().is_some_and(|leader| leader != member_id)
@@ -758,7 +910,7 @@ where
             {
                 debug!(?member_id);
                 COORDINATOR_REQUESTS.add(1, &[KeyValue::new("method", "join_follower_pause")]);
-                sleep(Duration::from_millis(PAUSE_MS as u64)).await;
+                pause(group_id, PAUSE_MS).await?;
             }
 
             debug!(?group_id, ?original, ?version, ?iteration);
@@ -809,7 +961,7 @@ where
 
                         COORDINATOR_REQUESTS
                             .add(1, &[KeyValue::new("method", "join_group_instance_pause")]);
-                        sleep(Duration::from_millis(pause as u64)).await;
+                        self::pause(group_id, pause).await?;
 
                         iteration += 1;
                         continue;
@@ -879,7 +1031,7 @@ where
         loop {
             COORDINATOR_REQUESTS.add(1, &[KeyValue::new("method", "sync_loop")]);
 
-            let now = SystemTime::now();
+            let now = self.clock.now();
 
             let (mut original, version) = self
                 .wrappers
@@ -935,7 +1087,7 @@ where
 
                         COORDINATOR_REQUESTS
                             .add(1, &[KeyValue::new("method", "sync_group_instance_pause")]);
-                        sleep(Duration::from_millis(pause as u64)).await;
+                        self::pause(group_id, pause).await?;
 
                         iteration += 1;
                         continue;
@@ -997,7 +1149,7 @@ where
 
             debug!(?group_id, ?wrapper, ?version, ?iteration);
 
-            let now = SystemTime::now();
+            let now = self.clock.now();
             let wrapper = wrapper.missed_heartbeat(group_id, now);
 
             let (wrapper, body) = wrapper.leave(now, group_id, member_id, members).await;
@@ -1066,7 +1218,7 @@ where
 
             debug!(?group_id, ?wrapper, ?version, ?iteration);
 
-            let now = SystemTime::now();
+            let now = self.clock.now();
 
             let (wrapper, body) = wrapper.offset_commit(now, &offset_commit).await;
             debug!(group_id, ?wrapper, ?version, iteration,);
@@ -1130,7 +1282,7 @@ where
 
         let wrapper = Wrapper::Forming(Inner::new(self.storage.clone()));
 
-        let now = SystemTime::now();
+        let now = self.clock.now();
         let (_wrapper, body) = wrapper
             .offset_fetch(now, group_id, topics, groups, require_stable)
             .await;
@@ -1159,7 +1311,7 @@ where
 
             debug!(?group_id, ?wrapper, ?version, ?iteration);
 
-            let now = SystemTime::now();
+            let now = self.clock.now();
 
             let (mut wrapper, body) = wrapper
                 .heartbeat(now, group_id, generation_id, member_id, group_instance_id)
@@ -1216,6 +1368,115 @@ where
             }
         }
     }
+
+    async fn consumer_group_heartbeat(
+        &mut self,
+        detail: ConsumerGroupHeartbeat<'_>,
+    ) -> Result<Body> {
+        debug!(?detail);
+        COORDINATOR_REQUESTS.add(1, &[KeyValue::new("method", "consumer_group_heartbeat")]);
+
+        let group_id = detail.group_id;
+        let mut iteration = 0;
+
+        let (mut current, mut version) = self
+            .wrappers
+            .remove(group_id)
+            .map_or((GroupDetail::default(), None), |(wrapper, version)| {
+                (GroupDetail::from(&wrapper), version)
+            });
+
+        loop {
+            COORDINATOR_REQUESTS.add(
+                1,
+                &[KeyValue::new("method", "consumer_group_heartbeat_loop")],
+            );
+
+            debug!(?group_id, ?current, ?version, ?iteration);
+
+            if (current.consumer.is_none() && !current.members.is_empty())
+                || current
+                    .share
+                    .as_ref()
+                    .is_some_and(|share| !share.members.is_empty())
+            {
+                return Ok(consumer::error(
+                    &detail,
+                    ErrorCode::GroupIdNotFound,
+                    format!("{group_id} is not a consumer group"),
+                )
+                .into());
+            }
+
+            let mut group = current.consumer.take().unwrap_or_default();
+            let topics = consumer::subscribed(&self.storage, &group, &detail).await?;
+            let body = consumer::heartbeat(&mut group, self.clock.now(), &detail, &topics);
+            current.consumer = Some(group);
+
+            match self
+                .storage
+                .update_group(group_id, current.clone(), version)
+                .await
+            {
+                Ok(version) => {
+                    debug!(?group_id, ?version);
+
+                    _ = self.wrappers.insert(
+                        group_id.to_owned(),
+                        (
+                            Wrapper::with_storage_group_detail(self.storage.clone(), current),
+                            Some(version),
+                        ),
+                    );
+
+                    return Ok(body.into());
+                }
+
+                Err(UpdateError::Outdated {
+                    current: latest,
+                    version: latest_version,
+                }) => {
+                    debug!(?group_id, ?latest, ?latest_version);
+                    COORDINATOR_REQUESTS.add(
+                        1,
+                        &[KeyValue::new("method", "consumer_group_heartbeat_outdated")],
+                    );
+
+                    current = latest;
+                    version = Some(latest_version);
+                    iteration += 1;
+                    continue;
+                }
+
+                Err(UpdateError::Error(error)) => return Err(error.into()),
+
+                Err(UpdateError::SerdeJson(error)) => return Err(error.into()),
+
+                Err(UpdateError::MissingEtag) => {
+                    return Err(Error::Message(String::from("missing e-tag")));
+                }
+
+                Err(UpdateError::Uuid(uuid)) => {
+                    return Err(Error::Message(format!("uuid: {uuid}")));
+                }
+            }
+        }
+    }
+
+    async fn share_group_heartbeat(&mut self, detail: ShareGroupHeartbeat<'_>) -> Result<Body> {
+        self.share("share_group_heartbeat", share::Request::Heartbeat(detail))
+            .await
+    }
+
+    async fn share_fetch(&mut self, detail: ShareFetch<'_>) -> Result<Body> {
+        self.share("share_fetch", share::Request::Fetch(detail))
+            .await
+    }
+
+    async fn share_acknowledge(&mut self, detail: ShareAcknowledge<'_>) -> Result<Body> {
+        self.share("share_acknowledge", share::Request::Acknowledge(detail))
+            .await
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -1243,6 +1504,8 @@ pub struct Inner<O, S> {
     storage: O,
     skip_assignment: Option<bool>,
     inception: SystemTime,
+    consumer: Option<ConsumerGroup>,
+    share: Option<ShareGroup>,
 }
 
 impl<O, S> PartialEq for Inner<O, S>
@@ -1257,6 +1520,8 @@ where
             && self.state == other.state
             && self.skip_assignment == other.skip_assignment
             && self.inception == other.inception
+            && self.consumer == other.consumer
+            && self.share == other.share
     }
 }
 
@@ -1272,6 +1537,8 @@ where
         self.state.hash(state);
         self.skip_assignment.hash(state);
         self.inception.hash(state);
+        self.consumer.hash(state);
+        self.share.hash(state);
     }
 }
 
@@ -1289,6 +1556,8 @@ where
             skip_assignment: Some(false),
             storage,
             inception: SystemTime::now(),
+            consumer: None,
+            share: None,
         }
     }
 }
@@ -1552,7 +1821,87 @@ where
             .into())
     }
 
+    /// The member id of the static member with this group instance id, if any
+    fn static_member_id(&self, group_instance_id: &str) -> Option<&str> {
+        self.members.iter().find_map(|(member_id, member)| {
+            (member.join_response.group_instance_id.as_deref() == Some(group_instance_id))
+                .then_some(member_id.as_str())
+        })
+    }
+
+    /// Whether this member has been fenced by a later instance joining with the
+    /// same group instance id
+    fn is_fenced(&self, member_id: &str, group_instance_id: Option<&str>) -> bool {
+        group_instance_id
+            .and_then(|group_instance_id| self.static_member_id(group_instance_id))
+            .is_some_and(|existing| existing != member_id)
+    }
+
+    /// Replace the member id of a static member that has rejoined without one,
+    /// fencing its previous instance, returning the previous member id
+    fn replace_static_member(
+        &mut self,
+        group_instance_id: &str,
+        member_id: &str,
+    ) -> Option<String> {
+        let previous = self.static_member_id(group_instance_id)?.to_owned();
+
+        self.members.remove(&previous).map(|mut member| {
+            member.join_response.member_id = member_id.to_owned();
+            _ = self.members.insert(member_id.to_owned(), member);
+            previous
+        })
+    }
+
+    /// Remove a leaving member, identified by its member id, or by its group
+    /// instance id when it is static
+    fn remove_member(&mut self, member_id: &str, group_instance_id: Option<&str>) -> ErrorCode {
+        let member_id = match group_instance_id
+            .and_then(|group_instance_id| self.static_member_id(group_instance_id))
+        {
+            Some(existing) if member_id.is_empty() || member_id == existing => existing.to_owned(),
+            Some(_) => return ErrorCode::FencedInstanceId,
+            None => member_id.to_owned(),
+        };
+
+        if self.members.remove(&member_id).is_some() {
+            ErrorCode::None
+        } else {
+            ErrorCode::UnknownMemberId
+        }
+    }
+
     async fn commit_offset(&mut self, detail: &OffsetCommit<'_>) -> Result<Body> {
+        if self.is_fenced(
+            detail.member_id.unwrap_or_default(),
+            detail.group_instance_id,
+        ) {
+            debug!(?detail.member_id, ?detail.group_instance_id, commit_outcome = ?ErrorCode::FencedInstanceId);
+
+            return Ok(OffsetCommitResponse::default()
+                .throttle_time_ms(Some(0))
+                .topics(detail.topics.map(|topics| {
+                    topics
+                        .iter()
+                        .map(|topic| {
+                            OffsetCommitResponseTopic::default()
+                                .name(topic.name.clone())
+                                .partitions(topic.partitions.as_ref().map(|partitions| {
+                                    partitions
+                                        .iter()
+                                        .map(|partition| {
+                                            OffsetCommitResponsePartition::default()
+                                                .partition_index(partition.partition_index)
+                                                .error_code(ErrorCode::FencedInstanceId.into())
+                                        })
+                                        .collect()
+                                }))
+                        })
+                        .collect()
+                }))
+                .into());
+        }
+
         let retention_time_ms = detail.retention_time_ms.map_or(Ok(None), |ms| {
             u64::try_from(ms)
                 .map(Duration::from_millis)
@@ -1773,19 +2122,40 @@ where
             return (self, join_group_response.into());
         }
 
-        let member_id = group_instance_id.map_or(member_id.to_owned(), |group_instance_id| {
-            if member_id.is_empty() {
-                if let Some((member_id, _)) = self.members.iter().find(|(_, member)| {
-                    member.join_response.group_instance_id.as_deref() == Some(group_instance_id)
-                }) {
-                    member_id.into()
-                } else {
-                    format!("{group_instance_id}-{}", Uuid::new_v4())
+        if !member_id.is_empty() && self.is_fenced(member_id, group_instance_id) {
+            debug!(member_id, group_instance_id, join_outcome = ?ErrorCode::FencedInstanceId);
+
+            let join_group_response = JoinGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::FencedInstanceId.into())
+                .generation_id(-1)
+                .protocol_type(self.state.protocol_type.clone())
+                .protocol_name(Some("".into()))
+                .leader("".into())
+                .skip_assignment(self.skip_assignment)
+                .member_id(member_id.into())
+                .members(Some([].into()));
+
+            return (self, join_group_response.into());
+        }
+
+        let member_id = match group_instance_id {
+            Some(group_instance_id) if member_id.is_empty() => {
+                let member_id = format!("{group_instance_id}-{}", Uuid::new_v4());
+
+                if let Some(previous) = self.replace_static_member(group_instance_id, &member_id) {
+                    debug!(previous, member_id, group_instance_id);
+
+                    if self.state.leader.as_deref() == Some(previous.as_str()) {
+                        _ = self.state.leader.replace(member_id.clone());
+                    }
                 }
-            } else {
-                member_id.into()
+
+                member_id
             }
-        });
+
+            _ => member_id.to_owned(),
+        };
 
         debug!(?member_id, ?self.members);
 
@@ -1906,6 +2276,19 @@ where
             ?assignments
         );
 
+        if self.is_fenced(member_id, group_instance_id) {
+            debug!(member_id, group_instance_id, sync_outcome = ?ErrorCode::FencedInstanceId);
+
+            let sync_group_response = SyncGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::FencedInstanceId.into())
+                .protocol_type(self.state.protocol_type.clone())
+                .protocol_name(self.state.protocol_name.clone())
+                .assignment(Bytes::from_static(b""));
+
+            return (self.into(), sync_group_response.into());
+        }
+
         if !self.members.contains_key(member_id) {
             debug!(?self.members, sync_outcome = ?ErrorCode::UnknownMemberId);
 
@@ -1987,6 +2370,24 @@ where
 
         debug!(?assignments);
 
+        let assignments = if cooperative::is_cooperative(
+            self.state.protocol_type.as_deref(),
+            self.state.protocol_name.as_deref(),
+        ) {
+            cooperative::withhold_owned(
+                &self
+                    .members
+                    .iter()
+                    .map(|(member_id, member)| {
+                        (member_id.clone(), member.join_response.metadata.clone())
+                    })
+                    .collect(),
+                assignments,
+            )
+        } else {
+            assignments
+        };
+
         let sync_group_response = SyncGroupResponse::default()
             .throttle_time_ms(Some(0))
             .error_code(ErrorCode::None.into())
@@ -2016,6 +2417,8 @@ where
             storage: self.storage,
             skip_assignment: self.skip_assignment,
             inception: self.inception,
+            consumer: self.consumer,
+            share: self.share,
         };
 
         (state.into(), sync_group_response.into())
@@ -2037,7 +2440,17 @@ where
             ?group_instance_id
         );
 
-        let _ = group_instance_id;
+        if self.is_fenced(member_id, group_instance_id) {
+            debug!(member_id, group_instance_id);
+
+            return (
+                self,
+                HeartbeatResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::FencedInstanceId.into())
+                    .into(),
+            );
+        }
 
         if !self.members.contains_key(member_id) {
             debug!(?self.members);
@@ -2105,13 +2518,7 @@ where
                 MemberResponse::default()
                     .member_id(member_id.to_owned())
                     .group_instance_id(None)
-                    .error_code({
-                        if self.members.remove(member_id).is_some() {
-                            ErrorCode::None.into()
-                        } else {
-                            ErrorCode::UnknownMemberId.into()
-                        }
-                    }),
+                    .error_code(self.remove_member(member_id, None).into()),
             ]
         } else {
             members.map_or(vec![], |members| {
@@ -2121,13 +2528,13 @@ where
                         MemberResponse::default()
                             .member_id(member.member_id.clone())
                             .group_instance_id(member.group_instance_id.clone())
-                            .error_code({
-                                if self.members.remove(&member.member_id).is_some() {
-                                    ErrorCode::None.into()
-                                } else {
-                                    ErrorCode::UnknownMemberId.into()
-                                }
-                            })
+                            .error_code(
+                                self.remove_member(
+                                    &member.member_id,
+                                    member.group_instance_id.as_deref(),
+                                )
+                                .into(),
+                            )
                     })
                     .collect::<Vec<MemberResponse>>()
             })
@@ -2336,25 +2743,52 @@ where
                     storage: self.storage,
                     skip_assignment: self.skip_assignment,
                     inception: self.inception,
+                    consumer: self.consumer,
+                    share: self.share,
                 }
                 .into(),
                 join_group_response.into(),
             );
         }
 
-        let member_id = group_instance_id.map_or(member_id.to_owned(), |group_instance_id| {
-            if member_id.is_empty() {
-                if let Some((member_id, _)) = self.members.iter().find(|(_, member)| {
-                    member.join_response.group_instance_id.as_deref() == Some(group_instance_id)
-                }) {
-                    member_id.into()
-                } else {
-                    format!("{group_instance_id}-{}", Uuid::new_v4())
+        if !member_id.is_empty() && self.is_fenced(member_id, group_instance_id) {
+            debug!(member_id, group_instance_id, join_outcome = ?ErrorCode::FencedInstanceId);
+
+            let join_group_response = JoinGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::FencedInstanceId.into())
+                .generation_id(-1)
+                .protocol_type(Some(self.state.protocol_type.clone()))
+                .protocol_name(Some("".into()))
+                .leader("".into())
+                .skip_assignment(self.skip_assignment)
+                .member_id(member_id.into())
+                .members(Some([].into()));
+
+            return (self.into(), join_group_response.into());
+        }
+
+        let member_id = match group_instance_id {
+            Some(group_instance_id) if member_id.is_empty() => {
+                let member_id = format!("{group_instance_id}-{}", Uuid::new_v4());
+
+                if let Some(previous) = self.replace_static_member(group_instance_id, &member_id) {
+                    debug!(previous, member_id, group_instance_id);
+
+                    if self.state.leader == previous {
+                        self.state.leader = member_id.clone();
+                    }
+
+                    if let Some(assignment) = self.state.assignments.remove(&previous) {
+                        _ = self.state.assignments.insert(member_id.clone(), assignment);
+                    }
                 }
-            } else {
-                member_id.into()
+
+                member_id
             }
-        });
+
+            _ => member_id.to_owned(),
+        };
 
         debug!(?member_id, ?self.members);
 
@@ -2436,6 +2870,8 @@ where
                     storage: self.storage,
                     skip_assignment: self.skip_assignment,
                     inception: self.inception,
+                    consumer: self.consumer,
+                    share: self.share,
                 }
                 .into();
 
@@ -2505,6 +2941,8 @@ where
                     storage: self.storage,
                     skip_assignment: self.skip_assignment,
                     inception: self.inception,
+                    consumer: self.consumer,
+                    share: self.share,
                 }
                 .into();
 
@@ -2557,11 +2995,24 @@ where
         assignments: Option<&[SyncGroupRequestAssignment]>,
     ) -> (Self::SyncState, Body) {
         let _ = group_id;
-        let _ = group_instance_id;
         let _ = protocol_type;
         let _ = protocol_name;
         let _ = assignments;
 
+        if self.is_fenced(member_id, group_instance_id) {
+            debug!(member_id, group_instance_id, sync_outcome = ?ErrorCode::FencedInstanceId);
+
+            let body = SyncGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::FencedInstanceId.into())
+                .protocol_type(Some(self.state.protocol_type.clone()))
+                .protocol_name(Some(self.state.protocol_name.clone()))
+                .assignment(Bytes::from_static(b""))
+                .into();
+
+            return (self, body);
+        }
+
         if !self.members.contains_key(member_id) {
             debug!(sync_outcome = ?ErrorCode::UnknownMemberId);
 
@@ -2635,6 +3086,16 @@ where
     ) -> (Self::HeartbeatState, Body) {
         debug!(?group_id, ?generation_id, ?member_id, ?group_instance_id);
 
+        if self.is_fenced(member_id, group_instance_id) {
+            return (
+                self,
+                HeartbeatResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::FencedInstanceId.into())
+                    .into(),
+            );
+        }
+
         if !self.members.contains_key(member_id) {
             return (
                 self,
@@ -2693,13 +3154,7 @@ where
                 MemberResponse::default()
                     .member_id(member_id.to_owned())
                     .group_instance_id(None)
-                    .error_code({
-                        if self.members.remove(member_id).is_some() {
-                            ErrorCode::None.into()
-                        } else {
-                            ErrorCode::UnknownMemberId.into()
-                        }
-                    }),
+                    .error_code(self.remove_member(member_id, None).into()),
             ]
         } else {
             members.map_or(vec![], |members| {
@@ -2709,13 +3164,13 @@ where
                         MemberResponse::default()
                             .member_id(member.member_id.clone())
                             .group_instance_id(member.group_instance_id.clone())
-                            .error_code({
-                                if self.members.remove(&member.member_id).is_some() {
-                                    ErrorCode::None.into()
-                                } else {
-                                    ErrorCode::UnknownMemberId.into()
-                                }
-                            })
+                            .error_code(
+                                self.remove_member(
+                                    &member.member_id,
+                                    member.group_instance_id.as_deref(),
+                                )
+                                .into(),
+                            )
                     })
                     .collect::<Vec<MemberResponse>>()
             })
@@ -2745,6 +3200,8 @@ where
                 storage: self.storage,
                 skip_assignment: self.skip_assignment,
                 inception: self.inception,
+                consumer: self.consumer,
+                share: self.share,
             }
             .into()
         } else {
@@ -4173,4 +4630,145 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn static_member_rejoins_without_rebalance() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        const CLIENT_ID: &str = "console-consumer";
+        const GROUP_ID: &str = "test-consumer-group";
+        const GROUP_INSTANCE_ID: &str = "instance-1";
+        const RANGE: &str = "range";
+        const PROTOCOL_TYPE: &str = "consumer";
+
+        let session_timeout_ms = 45_000;
+        let rebalance_timeout_ms = Some(300_000);
+        let generation_id = 3;
+        let previous = format!("{GROUP_INSTANCE_ID}-previous");
+        let metadata = Bytes::from_static(b"range_meta_01");
+        let assignment = Bytes::from_static(b"assignment_01");
+
+        let storage = StorageContainer::builder()
+            .cluster_id("abc")
+            .node_id(12321)
+            .advertised_listener(Url::parse("tcp://127.0.0.1:9092/")?)
+            .schema_registry(None)
+            .storage(Url::parse("memory://")?)
+            .build()
+            .await?;
+
+        let s = Wrapper::with_storage_group_detail(
+            storage,
+            GroupDetail {
+                session_timeout_ms,
+                rebalance_timeout_ms,
+                generation_id,
+                members: BTreeMap::from([(
+                    previous.clone(),
+                    GroupMember {
+                        join_response: JoinGroupResponseMember::default()
+                            .member_id(previous.clone())
+                            .group_instance_id(Some(GROUP_INSTANCE_ID.into()))
+                            .metadata(metadata.clone()),
+                        last_contact: Some(SystemTime::now()),
+                    },
+                )]),
+                state: GroupState::Formed {
+                    protocol_type: PROTOCOL_TYPE.into(),
+                    protocol_name: RANGE.into(),
+                    leader: previous.clone(),
+                    assignments: BTreeMap::from([(previous.clone(), assignment.clone())]),
+                },
+                ..Default::default()
+            },
+        );
+
+        let protocols = [JoinGroupRequestProtocol::default()
+            .name(RANGE.into())
+            .metadata(metadata)];
+
+        let (s, member_id) = match s
+            .join(
+                SystemTime::now(),
+                Some(CLIENT_ID),
+                GROUP_ID,
+                session_timeout_ms,
+                rebalance_timeout_ms,
+                "",
+                Some(GROUP_INSTANCE_ID),
+                PROTOCOL_TYPE,
+                Some(&protocols[..]),
+                None,
+            )
+            .await
+        {
+            (
+                s,
+                Body::JoinGroupResponse(JoinGroupResponse {
+                    error_code,
+                    generation_id: joined,
+                    leader,
+                    member_id,
+                    ..
+                }),
+            ) => {
+                assert_eq!(i16::from(ErrorCode::None), error_code);
+                assert_eq!(generation_id, joined);
+                assert_eq!(member_id, leader);
+                assert_ne!(previous, member_id);
+                assert!(member_id.starts_with(GROUP_INSTANCE_ID));
+                assert!(!s.is_forming());
+
+                (s, member_id)
+            }
+
+            otherwise => panic!("{otherwise:?}"),
+        };
+
+        let (s, body) = s
+            .heartbeat(
+                SystemTime::now(),
+                GROUP_ID,
+                generation_id,
+                &previous,
+                Some(GROUP_INSTANCE_ID),
+            )
+            .await;
+
+        assert_eq!(
+            Body::from(
+                HeartbeatResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::FencedInstanceId.into())
+            ),
+            body
+        );
+
+        let (_, body) = s
+            .sync(
+                SystemTime::now(),
+                GROUP_ID,
+                generation_id,
+                &member_id,
+                Some(GROUP_INSTANCE_ID),
+                Some(PROTOCOL_TYPE),
+                Some(RANGE),
+                None,
+            )
+            .await;
+
+        assert_eq!(
+            Body::from(
+                SyncGroupResponse::default()
+                    .throttle_time_ms(Some(0))
+                    .error_code(ErrorCode::None.into())
+                    .protocol_type(Some(PROTOCOL_TYPE.into()))
+                    .protocol_name(Some(RANGE.into()))
+                    .assignment(assignment)
+            ),
+            body
+        );
+
+        Ok(())
+    }
 }
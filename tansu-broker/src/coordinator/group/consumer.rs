@@ -12,35 +12,681 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+//! Consumer rebalance protocol (KIP-848)
+//!
+//! A member joins a group with a ConsumerGroupHeartbeat having an epoch of 0,
+//! and leaves with -1 (or -2 for a static member that will rejoin). A change
+//! in membership, subscription or subscribed partitions advances the group
+//! epoch, with the coordinator computing a target assignment for every member
+//! using the server side assignor (`uniform` or `range`).
+//!
+//! On each heartbeat a member is given those partitions of its target that are
+//! not still owned by another member, and told to release those that are no
+//! longer part of its target. Once a member has released them, its epoch
+//! advances to the assignment epoch of the group.
 
-use bytes::Bytes;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, SystemTime},
+};
 
-#[allow(dead_code)]
-struct Group {
-    id: String,
-    session_timeout_ms: i32,
-    rebalance_timeout_ms: i32,
-    members: HashMap<String, Member>,
+use tansu_sans_io::{
+    ErrorCode,
+    consumer_group_heartbeat_response::{
+        Assignment, ConsumerGroupHeartbeatResponse, TopicPartitions,
+    },
+};
+use tansu_storage::{
+    ConsumerGroup, ConsumerGroupAssignment, ConsumerGroupMember, Storage, TopicId,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::Result;
+
+use super::ConsumerGroupHeartbeat;
+
+pub const UNIFORM: &str = "uniform";
+pub const RANGE: &str = "range";
+
+const HEARTBEAT_INTERVAL_MS: i32 = 5_000;
+const SESSION_TIMEOUT: Duration = Duration::from_secs(45);
+
+const JOIN_GROUP_MEMBER_EPOCH: i32 = 0;
+const LEAVE_GROUP_MEMBER_EPOCH: i32 = -1;
+const LEAVE_GROUP_STATIC_MEMBER_EPOCH: i32 = -2;
+
+/// A topic subscribed to by a member of a group
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Subscribed {
+    pub id: Uuid,
+    pub partitions: i32,
+}
+
+/// The topics subscribed to by the members of a group, or by the member heartbeating
+pub async fn subscribed<S>(
+    storage: &S,
+    group: &ConsumerGroup,
+    detail: &ConsumerGroupHeartbeat<'_>,
+) -> Result<BTreeMap<String, Subscribed>>
+where
+    S: Storage,
+{
+    topics(
+        storage,
+        group
+            .members
+            .values()
+            .flat_map(|member| member.subscribed_topic_names.iter())
+            .chain(detail.subscribed_topic_names.unwrap_or_default())
+            .cloned()
+            .collect(),
+    )
+    .await
+}
+
+/// The id and number of partitions of each topic that exists
+pub async fn topics<S>(storage: &S, names: BTreeSet<String>) -> Result<BTreeMap<String, Subscribed>>
+where
+    S: Storage,
+{
+    let topics = names.into_iter().map(TopicId::Name).collect::<Vec<_>>();
+
+    if topics.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    storage
+        .metadata(Some(&topics[..]))
+        .await
+        .map(|metadata| {
+            metadata
+                .topics()
+                .iter()
+                .filter(|topic| topic.error_code == i16::from(ErrorCode::None))
+                .filter_map(|topic| {
+                    topic.name.clone().map(|name| {
+                        (
+                            name,
+                            Subscribed {
+                                id: topic.topic_id.map(Uuid::from_bytes).unwrap_or_default(),
+                                partitions: topic
+                                    .partitions
+                                    .as_ref()
+                                    .map_or(0, |partitions| partitions.len() as i32),
+                            },
+                        )
+                    })
+                })
+                .collect()
+        })
+        .map_err(Into::into)
+}
+
+pub fn error(
+    detail: &ConsumerGroupHeartbeat<'_>,
+    error_code: ErrorCode,
+    message: String,
+) -> ConsumerGroupHeartbeatResponse {
+    debug!(?detail, ?error_code, message);
+
+    ConsumerGroupHeartbeatResponse::default()
+        .throttle_time_ms(0)
+        .error_code(error_code.into())
+        .error_message(Some(message))
+        .member_id(Some(detail.member_id.into()))
+        .member_epoch(detail.member_epoch)
+        .heartbeat_interval_ms(HEARTBEAT_INTERVAL_MS)
+        .assignment(None)
 }
 
-#[allow(dead_code)]
-struct Member {
-    metadata: Bytes,
+fn union(a: &ConsumerGroupAssignment, b: &ConsumerGroupAssignment) -> ConsumerGroupAssignment {
+    let mut union = a.clone();
+
+    for (topic, partitions) in b {
+        union
+            .entry(topic.clone())
+            .or_default()
+            .extend(partitions.iter().copied());
+    }
+
+    union
+}
+
+fn filter(
+    a: &ConsumerGroupAssignment,
+    predicate: impl Fn(&str, &i32) -> bool,
+) -> ConsumerGroupAssignment {
+    a.iter()
+        .filter_map(|(topic, partitions)| {
+            let partitions = partitions
+                .iter()
+                .filter(|partition| predicate(topic, partition))
+                .copied()
+                .collect::<BTreeSet<_>>();
+
+            (!partitions.is_empty()).then(|| (topic.clone(), partitions))
+        })
+        .collect()
 }
 
-#[allow(dead_code)]
-struct MemberMetadata {
-    version: i16,
-    topics: Vec<String>,
-    user_data: Option<Bytes>,
-    owned_partitions: Vec<TopicPartition>,
-    generation_id: Option<i32>,
-    rack_id: Option<String>,
+fn contains(a: &ConsumerGroupAssignment, topic: &str, partition: &i32) -> bool {
+    a.get(topic)
+        .is_some_and(|partitions| partitions.contains(partition))
+}
+
+/// The target assignment of each member of a group
+fn target_assignment(
+    assignor: &str,
+    members: &BTreeMap<String, ConsumerGroupMember>,
+    topics: &BTreeMap<String, Subscribed>,
+) -> BTreeMap<String, ConsumerGroupAssignment> {
+    let mut targets = members
+        .keys()
+        .map(|member_id| (member_id.clone(), ConsumerGroupAssignment::new()))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut next = 0;
+
+    for (topic, subscribed) in topics {
+        let subscribers = members
+            .iter()
+            .filter(|(_, member)| member.subscribed_topic_names.contains(topic))
+            .map(|(member_id, _)| member_id)
+            .collect::<Vec<_>>();
+
+        if subscribers.is_empty() {
+            continue;
+        }
+
+        for partition in 0..subscribed.partitions {
+            let subscriber = if assignor == RANGE {
+                let quotient = subscribed.partitions as usize / subscribers.len();
+                let remainder = subscribed.partitions as usize % subscribers.len();
+                let partition = partition as usize;
+
+                if partition < remainder * (quotient + 1) {
+                    partition / (quotient + 1)
+                } else {
+                    remainder + (partition - remainder * (quotient + 1)) / quotient
+                }
+            } else {
+                next += 1;
+                (next - 1) % subscribers.len()
+            };
+
+            _ = targets
+                .get_mut(subscribers[subscriber])
+                .map(|target| target.entry(topic.clone()).or_default().insert(partition));
+        }
+    }
+
+    targets
+}
+
+/// The most popular server side assignor of the members, defaulting to uniform
+fn assignor(members: &BTreeMap<String, ConsumerGroupMember>) -> String {
+    members
+        .values()
+        .filter_map(|member| member.server_assignor.as_deref())
+        .fold(
+            BTreeMap::<&str, usize>::new(),
+            |mut popularity, assignor| {
+                *popularity.entry(assignor).or_default() += 1;
+                popularity
+            },
+        )
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, popularity)| *popularity)
+        .map_or(UNIFORM, |(assignor, _)| assignor)
+        .into()
+}
+
+/// Compute the target assignment of every member, advancing the group epoch when it changes
+fn assign(group: &mut ConsumerGroup, topics: &BTreeMap<String, Subscribed>) {
+    let assignor = assignor(&group.members);
+    let mut targets = target_assignment(&assignor, &group.members, topics);
+
+    if group.group_epoch == group.assignment_epoch
+        && group
+            .members
+            .iter()
+            .any(|(member_id, member)| targets.get(member_id) != Some(&member.target_assignment))
+    {
+        group.group_epoch += 1;
+    }
+
+    for (member_id, member) in group.members.iter_mut() {
+        member.target_assignment = targets.remove(member_id).unwrap_or_default();
+    }
+
+    group.assignment_epoch = group.group_epoch;
+    group.assignor_name = assignor;
+    group.topics = topics
+        .iter()
+        .map(|(topic, subscribed)| (topic.clone(), subscribed.id))
+        .collect();
+}
+
+/// Move a member toward its target assignment, returning whether its assignment changed
+fn reconcile(
+    group: &mut ConsumerGroup,
+    member_id: &str,
+    owned: Option<&ConsumerGroupAssignment>,
+) -> bool {
+    let others = group
+        .members
+        .iter()
+        .filter(|(id, _)| id.as_str() != member_id)
+        .fold(ConsumerGroupAssignment::new(), |others, (_, member)| {
+            union(&union(&others, &member.assignment), &member.revoking)
+        });
+
+    let assignment_epoch = group.assignment_epoch;
+
+    let Some(member) = group.members.get_mut(member_id) else {
+        return false;
+    };
+
+    if let Some(owned) = owned {
+        member.revoking = filter(&member.revoking, |topic, partition| {
+            contains(owned, topic, partition)
+        });
+    }
+
+    let keep = filter(&member.assignment, |topic, partition| {
+        contains(&member.target_assignment, topic, partition)
+    });
+
+    let revoke = filter(&member.assignment, |topic, partition| {
+        !contains(&member.target_assignment, topic, partition)
+    });
+
+    member.revoking = union(&member.revoking, &revoke);
+
+    let free = filter(&member.target_assignment, |topic, partition| {
+        !contains(&keep, topic, partition)
+            && !contains(&others, topic, partition)
+            && !contains(&member.revoking, topic, partition)
+    });
+
+    let assignment = union(&keep, &free);
+    let changed = assignment != member.assignment;
+    member.assignment = assignment;
+
+    if member.revoking.is_empty() {
+        member.member_epoch = assignment_epoch;
+    }
+
+    changed
+}
+
+/// Fence members that have not heartbeat within the session timeout
+fn expire(group: &mut ConsumerGroup, now: SystemTime) {
+    let expired = group
+        .members
+        .iter()
+        .filter(|(_, member)| {
+            member.last_contact.is_some_and(|last_contact| {
+                now.duration_since(last_contact)
+                    .is_ok_and(|elapsed| elapsed > SESSION_TIMEOUT)
+            })
+        })
+        .map(|(member_id, _)| member_id.clone())
+        .collect::<Vec<_>>();
+
+    for member_id in expired {
+        debug!(member_id, "expired");
+        _ = group.members.remove(&member_id);
+        group.group_epoch += 1;
+    }
 }
 
-#[allow(dead_code)]
-struct TopicPartition {
-    topic: String,
-    partitions: Vec<i32>,
+/// Apply the changes in a heartbeat to a member, returning whether its
+/// subscription or assignor changed
+fn update(member: &mut ConsumerGroupMember, detail: &ConsumerGroupHeartbeat<'_>) -> bool {
+    let mut changed = false;
+
+    if let Some(rack_id) = detail.rack_id {
+        member.rack_id = Some(rack_id.into());
+    }
+
+    if detail.rebalance_timeout_ms != -1 {
+        member.rebalance_timeout_ms = detail.rebalance_timeout_ms;
+    }
+
+    if let Some(subscribed) = detail.subscribed_topic_names
+        && subscribed != member.subscribed_topic_names.as_slice()
+    {
+        member.subscribed_topic_names = subscribed.to_vec();
+        changed = true;
+    }
+
+    if let Some(server_assignor) = detail.server_assignor
+        && member.server_assignor.as_deref() != Some(server_assignor)
+    {
+        member.server_assignor = Some(server_assignor.into());
+        changed = true;
+    }
+
+    changed
+}
+
+/// Add a member to the group, replacing a static member with the same
+/// instance id that has left, returning the member id
+fn join(
+    group: &mut ConsumerGroup,
+    detail: &ConsumerGroupHeartbeat<'_>,
+) -> Result<String, ConsumerGroupHeartbeatResponse> {
+    if detail.subscribed_topic_names.is_none() {
+        return Err(error(
+            detail,
+            ErrorCode::InvalidRequest,
+            "SubscribedTopicNames must be set when joining".into(),
+        ));
+    }
+
+    let member_id = if detail.member_id.is_empty() {
+        Uuid::new_v4().to_string()
+    } else {
+        detail.member_id.to_owned()
+    };
+
+    let mut member = ConsumerGroupMember {
+        instance_id: detail.instance_id.map(Into::into),
+        client_id: detail.client_id.unwrap_or_default().into(),
+        ..Default::default()
+    };
+
+    if let Some(instance_id) = detail.instance_id
+        && let Some(previous) = group
+            .members
+            .iter()
+            .find(|(id, existing)| {
+                **id != member_id && existing.instance_id.as_deref() == Some(instance_id)
+            })
+            .map(|(id, _)| id.clone())
+    {
+        if group.members[&previous].member_epoch != LEAVE_GROUP_STATIC_MEMBER_EPOCH {
+            return Err(error(
+                detail,
+                ErrorCode::UnreleasedInstanceId,
+                format!("instance {instance_id} is in use by {previous}"),
+            ));
+        }
+
+        if let Some(previous) = group.members.remove(&previous) {
+            member.assignment = previous.assignment;
+            member.revoking = previous.revoking;
+            member.target_assignment = previous.target_assignment;
+        }
+    }
+
+    _ = update(&mut member, detail);
+    _ = group.members.insert(member_id.clone(), member);
+    group.group_epoch += 1;
+
+    Ok(member_id)
+}
+
+/// Apply a heartbeat to the group, returning the response to the member
+pub fn heartbeat(
+    group: &mut ConsumerGroup,
+    now: SystemTime,
+    detail: &ConsumerGroupHeartbeat<'_>,
+    topics: &BTreeMap<String, Subscribed>,
+) -> ConsumerGroupHeartbeatResponse {
+    expire(group, now);
+
+    if let Some(server_assignor) = detail.server_assignor
+        && server_assignor != UNIFORM
+        && server_assignor != RANGE
+    {
+        return error(
+            detail,
+            ErrorCode::UnsupportedAssignor,
+            format!("assignor {server_assignor} is not supported"),
+        );
+    }
+
+    let member_id = match detail.member_epoch {
+        JOIN_GROUP_MEMBER_EPOCH => match join(group, detail) {
+            Ok(member_id) => member_id,
+            Err(response) => return response,
+        },
+
+        LEAVE_GROUP_MEMBER_EPOCH | LEAVE_GROUP_STATIC_MEMBER_EPOCH => {
+            if detail.member_epoch == LEAVE_GROUP_STATIC_MEMBER_EPOCH
+                && let Some(member) = group.members.get_mut(detail.member_id)
+                && member.instance_id.is_some()
+            {
+                member.member_epoch = LEAVE_GROUP_STATIC_MEMBER_EPOCH;
+                member.last_contact = Some(now);
+            } else if group.members.remove(detail.member_id).is_some() {
+                group.group_epoch += 1;
+                assign(group, topics);
+            }
+
+            return ConsumerGroupHeartbeatResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::None.into())
+                .error_message(None)
+                .member_id(Some(detail.member_id.into()))
+                .member_epoch(detail.member_epoch)
+                .heartbeat_interval_ms(0)
+                .assignment(None);
+        }
+
+        member_epoch => {
+            let Some(member) = group.members.get_mut(detail.member_id) else {
+                return error(
+                    detail,
+                    ErrorCode::UnknownMemberId,
+                    format!("member {} is not in the group", detail.member_id),
+                );
+            };
+
+            if member_epoch != member.member_epoch {
+                return error(
+                    detail,
+                    ErrorCode::FencedMemberEpoch,
+                    format!("member epoch {member_epoch} is not {}", member.member_epoch),
+                );
+            }
+
+            if update(member, detail) {
+                group.group_epoch += 1;
+            }
+
+            detail.member_id.to_owned()
+        }
+    };
+
+    assign(group, topics);
+
+    let names = topics
+        .iter()
+        .map(|(topic, subscribed)| (subscribed.id, topic.as_str()))
+        .collect::<BTreeMap<_, _>>();
+
+    let owned = detail.topic_partitions.map(|topic_partitions| {
+        topic_partitions
+            .iter()
+            .filter_map(|topic| {
+                names.get(&Uuid::from_bytes(topic.topic_id)).map(|name| {
+                    (
+                        (*name).to_owned(),
+                        topic
+                            .partitions
+                            .iter()
+                            .flatten()
+                            .copied()
+                            .collect::<BTreeSet<_>>(),
+                    )
+                })
+            })
+            .collect::<ConsumerGroupAssignment>()
+    });
+
+    let changed = reconcile(group, &member_id, owned.as_ref());
+
+    let member = group
+        .members
+        .get_mut(&member_id)
+        .expect("a member that has just heartbeat");
+
+    member.last_contact = Some(now);
+
+    let assignment = (changed
+        || detail.member_epoch == JOIN_GROUP_MEMBER_EPOCH
+        || owned.is_some_and(|owned| owned != member.assignment))
+    .then(|| {
+        Assignment::default().topic_partitions(Some(
+            member
+                .assignment
+                .iter()
+                .map(|(topic, partitions)| {
+                    TopicPartitions::default()
+                        .topic_id(
+                            group
+                                .topics
+                                .get(topic)
+                                .copied()
+                                .unwrap_or_default()
+                                .into_bytes(),
+                        )
+                        .partitions(Some(partitions.iter().copied().collect()))
+                })
+                .collect(),
+        ))
+    });
+
+    ConsumerGroupHeartbeatResponse::default()
+        .throttle_time_ms(0)
+        .error_code(ErrorCode::None.into())
+        .error_message(None)
+        .member_id(Some(member_id))
+        .member_epoch(member.member_epoch)
+        .heartbeat_interval_ms(HEARTBEAT_INTERVAL_MS)
+        .assignment(assignment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topics() -> BTreeMap<String, Subscribed> {
+        BTreeMap::from([(
+            "abc".into(),
+            Subscribed {
+                id: Uuid::from_u128(1),
+                partitions: 3,
+            },
+        )])
+    }
+
+    fn detail<'a>(
+        member_id: &'a str,
+        member_epoch: i32,
+        subscribed_topic_names: Option<&'a [String]>,
+    ) -> ConsumerGroupHeartbeat<'a> {
+        ConsumerGroupHeartbeat {
+            client_id: None,
+            group_id: "g",
+            member_id,
+            member_epoch,
+            instance_id: None,
+            rack_id: None,
+            rebalance_timeout_ms: -1,
+            subscribed_topic_names,
+            server_assignor: None,
+            topic_partitions: None,
+        }
+    }
+
+    fn partitions(response: &ConsumerGroupHeartbeatResponse) -> Vec<i32> {
+        response
+            .assignment
+            .as_ref()
+            .and_then(|assignment| assignment.topic_partitions.as_deref())
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|topic| topic.partitions.clone().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn range() {
+        let member = |subscribed: &[&str]| ConsumerGroupMember {
+            subscribed_topic_names: subscribed.iter().map(|topic| (*topic).into()).collect(),
+            ..Default::default()
+        };
+
+        let members = BTreeMap::from([
+            ("a".into(), member(&["abc"])),
+            ("b".into(), member(&["abc"])),
+        ]);
+
+        let targets = target_assignment(RANGE, &members, &topics());
+        assert_eq!(Some(&BTreeSet::from([0, 1])), targets["a"].get("abc"));
+        assert_eq!(Some(&BTreeSet::from([2])), targets["b"].get("abc"));
+
+        let targets = target_assignment(UNIFORM, &members, &topics());
+        assert_eq!(Some(&BTreeSet::from([0, 2])), targets["a"].get("abc"));
+        assert_eq!(Some(&BTreeSet::from([1])), targets["b"].get("abc"));
+    }
+
+    #[test]
+    fn partitions_move_once_released() {
+        let subscribed = ["abc".to_owned()];
+        let topics = topics();
+        let now = SystemTime::now();
+        let mut group = ConsumerGroup::default();
+
+        let first = heartbeat(&mut group, now, &detail("a", 0, Some(&subscribed)), &topics);
+        assert_eq!(i16::from(ErrorCode::None), first.error_code);
+        assert_eq!(1, first.member_epoch);
+        assert_eq!(vec![0, 1, 2], partitions(&first));
+
+        let second = heartbeat(&mut group, now, &detail("b", 0, Some(&subscribed)), &topics);
+        assert_eq!(2, second.member_epoch);
+        assert!(partitions(&second).is_empty());
+
+        // a is told to release a partition, remaining at its epoch until it has
+        let revoke = heartbeat(&mut group, now, &detail("a", 1, None), &topics);
+        assert_eq!(1, revoke.member_epoch);
+        assert_eq!(vec![0, 2], partitions(&revoke));
+
+        let waiting = heartbeat(&mut group, now, &detail("b", 2, None), &topics);
+        assert!(waiting.assignment.is_none());
+
+        let owned = [
+            tansu_sans_io::consumer_group_heartbeat_request::TopicPartitions::default()
+                .topic_id(Uuid::from_u128(1).into_bytes())
+                .partitions(Some(vec![0, 2])),
+        ];
+
+        let released = heartbeat(
+            &mut group,
+            now,
+            &ConsumerGroupHeartbeat {
+                topic_partitions: Some(&owned),
+                ..detail("a", 1, None)
+            },
+            &topics,
+        );
+        assert_eq!(2, released.member_epoch);
+
+        let assigned = heartbeat(&mut group, now, &detail("b", 2, None), &topics);
+        assert_eq!(vec![1], partitions(&assigned));
+
+        let fenced = heartbeat(&mut group, now, &detail("a", 1, None), &topics);
+        assert_eq!(i16::from(ErrorCode::FencedMemberEpoch), fenced.error_code);
+
+        let left = heartbeat(&mut group, now, &detail("b", -1, None), &topics);
+        assert_eq!(-1, left.member_epoch);
+        assert_eq!(1, group.members.len());
+        assert_eq!(3, group.group_epoch);
+    }
 }
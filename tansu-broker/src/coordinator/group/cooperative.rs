@@ -0,0 +1,315 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cooperative rebalancing of classic consumer groups
+//!
+//! With a cooperative protocol (e.g., `cooperative-sticky`) consumers keep
+//! their partitions during a rebalance, with each member reporting the
+//! partitions that it owns in its subscription. A partition moving between
+//! members is revoked by its owner in a first rebalance, and only assigned to
+//! its new owner in a second rebalance, that the owner triggers by rejoining
+//! once it has been revoked.
+//!
+//! Rather than relying on the assignor of the group leader alone, the
+//! coordinator enforces this: any partition that the leader assigns to a
+//! member while it is still owned by another is withheld from the assignment
+//! until the owner has rejoined without it.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bytes::{Buf as _, BufMut as _, Bytes, BytesMut};
+use tracing::debug;
+
+/// The protocol type of consumer groups
+const CONSUMER: &str = "consumer";
+
+/// Assignors that rebalance cooperatively
+const COOPERATIVE_PROTOCOLS: [&str; 1] = ["cooperative-sticky"];
+
+/// Whether the protocol of a group rebalances cooperatively
+pub(crate) fn is_cooperative(protocol_type: Option<&str>, protocol_name: Option<&str>) -> bool {
+    protocol_type == Some(CONSUMER)
+        && protocol_name.is_some_and(|protocol_name| COOPERATIVE_PROTOCOLS.contains(&protocol_name))
+}
+
+fn get_i16(encoded: &mut &[u8]) -> Option<i16> {
+    (encoded.remaining() >= 2).then(|| encoded.get_i16())
+}
+
+fn get_i32(encoded: &mut &[u8]) -> Option<i32> {
+    (encoded.remaining() >= 4).then(|| encoded.get_i32())
+}
+
+fn get_string(encoded: &mut &[u8]) -> Option<String> {
+    let length = usize::try_from(get_i16(encoded)?).ok()?;
+
+    (encoded.remaining() >= length).then(|| {
+        let s = String::from_utf8_lossy(&encoded[..length]).into_owned();
+        encoded.advance(length);
+        s
+    })
+}
+
+fn get_nullable_bytes(encoded: &mut &[u8]) -> Option<Option<Bytes>> {
+    let length = get_i32(encoded)?;
+
+    let Ok(length) = usize::try_from(length) else {
+        return Some(None);
+    };
+
+    (encoded.remaining() >= length).then(|| {
+        let bytes = Bytes::copy_from_slice(&encoded[..length]);
+        encoded.advance(length);
+        Some(bytes)
+    })
+}
+
+fn get_array<T>(
+    encoded: &mut &[u8],
+    mut get: impl FnMut(&mut &[u8]) -> Option<T>,
+) -> Option<Vec<T>> {
+    let length = get_i32(encoded)?;
+    (0..length.max(0)).map(|_| get(encoded)).collect()
+}
+
+fn get_topic_partitions(encoded: &mut &[u8]) -> Option<Vec<(String, Vec<i32>)>> {
+    get_array(encoded, |encoded| {
+        get_string(encoded).zip(get_array(encoded, get_i32))
+    })
+}
+
+/// The partitions owned by a member from its subscription (version 1+)
+fn owned_partitions(subscription: &[u8]) -> Option<BTreeSet<(String, i32)>> {
+    let mut encoded = subscription;
+
+    let version = get_i16(&mut encoded)?;
+    _ = get_array(&mut encoded, get_string)?;
+    _ = get_nullable_bytes(&mut encoded)?;
+
+    if version < 1 {
+        return Some(BTreeSet::new());
+    }
+
+    get_topic_partitions(&mut encoded).map(|topics| {
+        topics
+            .into_iter()
+            .flat_map(|(topic, partitions)| {
+                partitions
+                    .into_iter()
+                    .map(move |partition| (topic.clone(), partition))
+            })
+            .collect()
+    })
+}
+
+/// An assignment of partitions to a member
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Assignment {
+    version: i16,
+    partitions: Vec<(String, Vec<i32>)>,
+    user_data: Option<Bytes>,
+}
+
+impl Assignment {
+    fn decode(assignment: &[u8]) -> Option<Self> {
+        let mut encoded = assignment;
+
+        let version = get_i16(&mut encoded)?;
+        let partitions = get_topic_partitions(&mut encoded)?;
+        let user_data = get_nullable_bytes(&mut encoded)?;
+
+        Some(Self {
+            version,
+            partitions,
+            user_data,
+        })
+    }
+
+    fn encode(&self) -> Bytes {
+        let mut encoded = BytesMut::new();
+
+        let put_string = |encoded: &mut BytesMut, s: &str| {
+            encoded.put_i16(s.len() as i16);
+            encoded.put(s.as_bytes());
+        };
+
+        encoded.put_i16(self.version);
+        encoded.put_i32(self.partitions.len() as i32);
+
+        for (topic, partitions) in &self.partitions {
+            put_string(&mut encoded, topic);
+            encoded.put_i32(partitions.len() as i32);
+
+            for partition in partitions {
+                encoded.put_i32(*partition);
+            }
+        }
+
+        if let Some(ref user_data) = self.user_data {
+            encoded.put_i32(user_data.len() as i32);
+            encoded.put(&user_data[..]);
+        } else {
+            encoded.put_i32(-1);
+        }
+
+        encoded.freeze()
+    }
+}
+
+/// Withhold partitions from the assignments that are still owned by another
+/// member, according to the subscription metadata of each member
+pub(crate) fn withhold_owned(
+    subscriptions: &BTreeMap<String, Bytes>,
+    assignments: BTreeMap<String, Bytes>,
+) -> BTreeMap<String, Bytes> {
+    let owners = subscriptions
+        .iter()
+        .filter_map(|(member_id, subscription)| {
+            let owned = owned_partitions(subscription);
+            debug!(member_id, ?owned);
+            owned.map(|owned| (member_id, owned))
+        })
+        .flat_map(|(member_id, owned)| {
+            owned
+                .into_iter()
+                .map(move |topition| (topition, member_id.as_str()))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    assignments
+        .into_iter()
+        .map(|(member_id, encoded)| {
+            let Some(mut assignment) = Assignment::decode(&encoded) else {
+                debug!(member_id, unparsable = ?encoded);
+                return (member_id, encoded);
+            };
+
+            let mut withheld = vec![];
+
+            for (topic, partitions) in assignment.partitions.iter_mut() {
+                partitions.retain(|partition| {
+                    let retained = owners
+                        .get(&(topic.clone(), *partition))
+                        .is_none_or(|owner| *owner == member_id);
+
+                    if !retained {
+                        withheld.push((topic.clone(), *partition));
+                    }
+
+                    retained
+                });
+            }
+
+            if withheld.is_empty() {
+                (member_id, encoded)
+            } else {
+                debug!(member_id, ?withheld);
+
+                assignment
+                    .partitions
+                    .retain(|(_, partitions)| !partitions.is_empty());
+
+                let encoded = assignment.encode();
+                (member_id, encoded)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(topics: &[&str], owned: &[(&str, &[i32])]) -> Bytes {
+        let mut encoded = BytesMut::new();
+        encoded.put_i16(1);
+
+        encoded.put_i32(topics.len() as i32);
+        for topic in topics {
+            encoded.put_i16(topic.len() as i16);
+            encoded.put(topic.as_bytes());
+        }
+
+        encoded.put_i32(-1);
+
+        encoded.put_i32(owned.len() as i32);
+        for (topic, partitions) in owned {
+            encoded.put_i16(topic.len() as i16);
+            encoded.put(topic.as_bytes());
+
+            encoded.put_i32(partitions.len() as i32);
+            for partition in *partitions {
+                encoded.put_i32(*partition);
+            }
+        }
+
+        encoded.freeze()
+    }
+
+    fn assignment(partitions: &[(&str, &[i32])]) -> Bytes {
+        Assignment {
+            version: 1,
+            partitions: partitions
+                .iter()
+                .map(|(topic, partitions)| ((*topic).into(), partitions.to_vec()))
+                .collect(),
+            user_data: None,
+        }
+        .encode()
+    }
+
+    #[test]
+    fn cooperative() {
+        assert!(is_cooperative(Some("consumer"), Some("cooperative-sticky")));
+        assert!(!is_cooperative(Some("consumer"), Some("range")));
+        assert!(!is_cooperative(Some("connect"), Some("cooperative-sticky")));
+    }
+
+    #[test]
+    fn assignment_round_trip() {
+        let encoded = assignment(&[("abc", &[0, 2]), ("pqr", &[1])]);
+
+        assert_eq!(
+            Some(encoded.clone()),
+            Assignment::decode(&encoded).map(|assignment| assignment.encode())
+        );
+    }
+
+    #[test]
+    fn partition_moves_after_revocation() {
+        let assignments = BTreeMap::from([
+            ("a".into(), assignment(&[("t", &[0])])),
+            ("b".into(), assignment(&[("t", &[1])])),
+        ]);
+
+        let subscriptions = BTreeMap::from([
+            ("a".into(), subscription(&["t"], &[("t", &[0, 1])])),
+            ("b".into(), subscription(&["t"], &[])),
+        ]);
+
+        let first = withhold_owned(&subscriptions, assignments.clone());
+        assert_eq!(assignment(&[("t", &[0])]), first["a"]);
+        assert_eq!(assignment(&[]), first["b"]);
+
+        let subscriptions = BTreeMap::from([
+            ("a".into(), subscription(&["t"], &[("t", &[0])])),
+            ("b".into(), subscription(&["t"], &[])),
+        ]);
+
+        assert_eq!(
+            assignments,
+            withhold_owned(&subscriptions, assignments.clone())
+        );
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Coordinator leases
+//!
+//! With brokers sharing storage, each group is coordinated by the broker
+//! holding its coordinator lease. A broker renews the lease of a group while
+//! coordinating it. Once a lease has expired another broker may acquire it,
+//! incrementing the epoch of the lease, and fencing the previous holder which
+//! then responds with `NotCoordinator` so that the client finds the new
+//! coordinator.
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use opentelemetry::{KeyValue, metrics::Counter};
+use tansu_sans_io::{
+    Body, ErrorCode,
+    consumer_group_heartbeat_response::ConsumerGroupHeartbeatResponse,
+    heartbeat_response::HeartbeatResponse,
+    join_group_request::JoinGroupRequestProtocol,
+    join_group_response::JoinGroupResponse,
+    leave_group_request::MemberIdentity,
+    leave_group_response::LeaveGroupResponse,
+    offset_commit_response::{
+        OffsetCommitResponse, OffsetCommitResponsePartition, OffsetCommitResponseTopic,
+    },
+    offset_fetch_request::{OffsetFetchRequestGroup, OffsetFetchRequestTopic},
+    offset_fetch_response::{OffsetFetchResponse, OffsetFetchResponseGroup},
+    share_group_heartbeat_response::ShareGroupHeartbeatResponse,
+    sync_group_request::SyncGroupRequestAssignment,
+    sync_group_response::SyncGroupResponse,
+};
+use tansu_storage::Storage;
+use tracing::{debug, instrument};
+
+use crate::{METER, Result};
+
+use super::{
+    ConsumerGroupHeartbeat, Coordinator, OffsetCommit, ShareAcknowledge, ShareFetch,
+    ShareGroupHeartbeat,
+};
+
+/// The key type of a group in a coordinator lease
+const GROUP: i8 = 0;
+
+static NOT_COORDINATOR: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_group_not_coordinator")
+        .with_description("group requests for a group coordinated by another broker")
+        .build()
+});
+
+/// A [`Coordinator`] serving only the groups with a coordinator lease held by this broker
+#[derive(Clone, Debug)]
+pub struct Leased<C, S> {
+    inner: C,
+    storage: S,
+}
+
+impl<C, S> Leased<C, S>
+where
+    C: Coordinator,
+    S: Storage,
+{
+    pub fn new(inner: C, storage: S) -> Self {
+        Self { inner, storage }
+    }
+
+    /// Whether this broker holds (or has just acquired) the coordinator lease of a group
+    #[instrument(skip(self))]
+    async fn holds(&self, method: &'static str, group_id: &str) -> Result<bool> {
+        let node_id = self.storage.node().await?;
+        let lease = self.storage.coordinator_lease(GROUP, group_id).await?;
+
+        if lease.node_id == node_id {
+            Ok(true)
+        } else {
+            debug!(group_id, node_id, ?lease);
+            NOT_COORDINATOR.add(1, &[KeyValue::new("method", method)]);
+            Ok(false)
+        }
+    }
+}
+
+#[async_trait]
+impl<C, S> Coordinator for Leased<C, S>
+where
+    C: Coordinator,
+    S: Storage,
+{
+    async fn join(
+        &mut self,
+        client_id: Option<&str>,
+        group_id: &str,
+        session_timeout_ms: i32,
+        rebalance_timeout_ms: Option<i32>,
+        member_id: &str,
+        group_instance_id: Option<&str>,
+        protocol_type: &str,
+        protocols: Option<&[JoinGroupRequestProtocol]>,
+        reason: Option<&str>,
+    ) -> Result<Body> {
+        if !self.holds("join", group_id).await? {
+            return Ok(JoinGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::NotCoordinator.into())
+                .generation_id(-1)
+                .protocol_type(None)
+                .protocol_name(None)
+                .leader("".into())
+                .skip_assignment(Some(false))
+                .member_id(member_id.into())
+                .members(Some([].into()))
+                .into());
+        }
+
+        self.inner
+            .join(
+                client_id,
+                group_id,
+                session_timeout_ms,
+                rebalance_timeout_ms,
+                member_id,
+                group_instance_id,
+                protocol_type,
+                protocols,
+                reason,
+            )
+            .await
+    }
+
+    async fn sync(
+        &mut self,
+        group_id: &str,
+        generation_id: i32,
+        member_id: &str,
+        group_instance_id: Option<&str>,
+        protocol_type: Option<&str>,
+        protocol_name: Option<&str>,
+        assignments: Option<&[SyncGroupRequestAssignment]>,
+    ) -> Result<Body> {
+        if !self.holds("sync", group_id).await? {
+            return Ok(SyncGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::NotCoordinator.into())
+                .protocol_type(protocol_type.map(Into::into))
+                .protocol_name(protocol_name.map(Into::into))
+                .assignment(Bytes::from_static(b""))
+                .into());
+        }
+
+        self.inner
+            .sync(
+                group_id,
+                generation_id,
+                member_id,
+                group_instance_id,
+                protocol_type,
+                protocol_name,
+                assignments,
+            )
+            .await
+    }
+
+    async fn heartbeat(
+        &mut self,
+        group_id: &str,
+        generation_id: i32,
+        member_id: &str,
+        group_instance_id: Option<&str>,
+    ) -> Result<Body> {
+        if !self.holds("heartbeat", group_id).await? {
+            return Ok(HeartbeatResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::NotCoordinator.into())
+                .into());
+        }
+
+        self.inner
+            .heartbeat(group_id, generation_id, member_id, group_instance_id)
+            .await
+    }
+
+    async fn leave(
+        &mut self,
+        group_id: &str,
+        member_id: Option<&str>,
+        members: Option<&[MemberIdentity]>,
+    ) -> Result<Body> {
+        if !self.holds("leave", group_id).await? {
+            return Ok(LeaveGroupResponse::default()
+                .throttle_time_ms(Some(0))
+                .error_code(ErrorCode::NotCoordinator.into())
+                .members(Some([].into()))
+                .into());
+        }
+
+        self.inner.leave(group_id, member_id, members).await
+    }
+
+    async fn offset_commit(&mut self, detail: OffsetCommit<'_>) -> Result<Body> {
+        if !self.holds("offset_commit", detail.group_id).await? {
+            return Ok(OffsetCommitResponse::default()
+                .throttle_time_ms(Some(0))
+                .topics(detail.topics.map(|topics| {
+                    topics
+                        .iter()
+                        .map(|topic| {
+                            OffsetCommitResponseTopic::default()
+                                .name(topic.name.clone())
+                                .partitions(topic.partitions.as_ref().map(|partitions| {
+                                    partitions
+                                        .iter()
+                                        .map(|partition| {
+                                            OffsetCommitResponsePartition::default()
+                                                .partition_index(partition.partition_index)
+                                                .error_code(ErrorCode::NotCoordinator.into())
+                                        })
+                                        .collect()
+                                }))
+                        })
+                        .collect()
+                }))
+                .into());
+        }
+
+        self.inner.offset_commit(detail).await
+    }
+
+    async fn offset_fetch(
+        &mut self,
+        group_id: Option<&str>,
+        topics: Option<&[OffsetFetchRequestTopic]>,
+        groups: Option<&[OffsetFetchRequestGroup]>,
+        require_stable: Option<bool>,
+    ) -> Result<Body> {
+        if let Some(group_id) = group_id
+            && !self.holds("offset_fetch", group_id).await?
+        {
+            return Ok(OffsetFetchResponse::default()
+                .throttle_time_ms(Some(0))
+                .topics(Some([].into()))
+                .error_code(Some(ErrorCode::NotCoordinator.into()))
+                .groups(None)
+                .into());
+        }
+
+        let Some(groups) = groups else {
+            return self
+                .inner
+                .offset_fetch(group_id, topics, groups, require_stable)
+                .await;
+        };
+
+        let mut held = vec![];
+        let mut not_coordinator = vec![];
+
+        for group in groups {
+            if self.holds("offset_fetch", group.group_id.as_str()).await? {
+                held.push(group.clone());
+            } else {
+                not_coordinator.push(
+                    OffsetFetchResponseGroup::default()
+                        .group_id(group.group_id.clone())
+                        .topics(Some([].into()))
+                        .error_code(ErrorCode::NotCoordinator.into()),
+                );
+            }
+        }
+
+        if not_coordinator.is_empty() {
+            return self
+                .inner
+                .offset_fetch(group_id, topics, Some(groups), require_stable)
+                .await;
+        }
+
+        let mut response = if held.is_empty() {
+            OffsetFetchResponse::default()
+                .throttle_time_ms(Some(0))
+                .topics(None)
+                .error_code(Some(ErrorCode::None.into()))
+                .groups(Some([].into()))
+        } else {
+            self.inner
+                .offset_fetch(group_id, topics, Some(&held[..]), require_stable)
+                .await
+                .and_then(|body| OffsetFetchResponse::try_from(body).map_err(Into::into))?
+        };
+
+        response
+            .groups
+            .get_or_insert_default()
+            .append(&mut not_coordinator);
+
+        Ok(response.into())
+    }
+
+    async fn consumer_group_heartbeat(
+        &mut self,
+        detail: ConsumerGroupHeartbeat<'_>,
+    ) -> Result<Body> {
+        if !self
+            .holds("consumer_group_heartbeat", detail.group_id)
+            .await?
+        {
+            return Ok(ConsumerGroupHeartbeatResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::NotCoordinator.into())
+                .error_message(None)
+                .member_id(Some(detail.member_id.into()))
+                .member_epoch(detail.member_epoch)
+                .heartbeat_interval_ms(0)
+                .assignment(None)
+                .into());
+        }
+
+        self.inner.consumer_group_heartbeat(detail).await
+    }
+
+    async fn share_group_heartbeat(&mut self, detail: ShareGroupHeartbeat<'_>) -> Result<Body> {
+        if !self.holds("share_group_heartbeat", detail.group_id).await? {
+            return Ok(ShareGroupHeartbeatResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::NotCoordinator.into())
+                .error_message(None)
+                .member_id(Some(detail.member_id.into()))
+                .member_epoch(detail.member_epoch)
+                .heartbeat_interval_ms(0)
+                .assignment(None)
+                .into());
+        }
+
+        self.inner.share_group_heartbeat(detail).await
+    }
+
+    /// Share fetches are sent to the leader of a partition rather than the
+    /// coordinator, relying on the conditional update of the group in storage
+    async fn share_fetch(&mut self, detail: ShareFetch<'_>) -> Result<Body> {
+        self.inner.share_fetch(detail).await
+    }
+
+    async fn share_acknowledge(&mut self, detail: ShareAcknowledge<'_>) -> Result<Body> {
+        self.inner.share_acknowledge(detail).await
+    }
+}
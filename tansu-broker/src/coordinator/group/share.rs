@@ -0,0 +1,1071 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Share groups (KIP-932)
+//!
+//! The members of a share group cooperatively consume the partitions of the
+//! topics they subscribe to, with a partition assigned to more than one member
+//! when there are more members than partitions. Rather than committing an
+//! offset, a member acquires records with a ShareFetch and then acknowledges
+//! each of them (with a ShareAcknowledge or its next ShareFetch) as accepted,
+//! released for redelivery or rejected.
+//!
+//! The delivery state of each in-flight record is kept with the group in
+//! storage. An acquired record that is not acknowledged before its lock
+//! expires is released, and a record delivered too many times is archived.
+//!
+//! Share sessions are not cached: a ShareFetch fetches the partitions that it
+//! names together with those assigned to the member.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, SystemTime},
+};
+
+use tansu_sans_io::{
+    Body, ErrorCode, IsolationLevel,
+    record::deflated::{Batch, Frame},
+    share_acknowledge_response::{self, ShareAcknowledgeResponse, ShareAcknowledgeTopicResponse},
+    share_fetch_response::{
+        AcquiredRecords, LeaderIdAndEpoch, PartitionData, ShareFetchResponse,
+        ShareFetchableTopicResponse,
+    },
+    share_group_heartbeat_response::{Assignment, ShareGroupHeartbeatResponse, TopicPartitions},
+};
+use tansu_storage::{
+    ConsumerGroupAssignment, GroupDetail, ShareDeliveryState, ShareGroup, ShareGroupMember,
+    SharePartition, ShareRecord, Storage, Topition,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::Result;
+
+use super::{
+    ShareAcknowledge, ShareFetch, ShareGroupHeartbeat,
+    consumer::{self, Subscribed},
+};
+
+const HEARTBEAT_INTERVAL_MS: i32 = 5_000;
+const SESSION_TIMEOUT: Duration = Duration::from_secs(45);
+
+const JOIN_GROUP_MEMBER_EPOCH: i32 = 0;
+const LEAVE_GROUP_MEMBER_EPOCH: i32 = -1;
+
+/// The share session epoch of a request closing its session
+const FINAL_SHARE_SESSION_EPOCH: i32 = -1;
+
+/// The number of deliveries of a record before it is archived
+const DELIVERY_COUNT_LIMIT: i16 = 5;
+
+/// The duration of the lock on an acquired record
+const RECORD_LOCK_DURATION: Duration = Duration::from_secs(30);
+
+/// The maximum number of in-flight records of a share partition
+const MAX_RECORD_LOCKS: usize = 200;
+
+const GAP: i8 = 0;
+const ACCEPT: i8 = 1;
+const RELEASE: i8 = 2;
+const REJECT: i8 = 3;
+
+/// A request changing the state of a share group
+#[derive(Debug)]
+pub enum Request<'a> {
+    Heartbeat(ShareGroupHeartbeat<'a>),
+    Fetch(ShareFetch<'a>),
+    Acknowledge(ShareAcknowledge<'a>),
+}
+
+impl Request<'_> {
+    pub fn group_id(&self) -> &str {
+        match self {
+            Self::Heartbeat(detail) => detail.group_id,
+            Self::Fetch(detail) => detail.group_id,
+            Self::Acknowledge(detail) => detail.group_id,
+        }
+    }
+
+    pub fn error(&self, error_code: ErrorCode, message: String) -> Body {
+        match self {
+            Self::Heartbeat(detail) => heartbeat_error(detail, error_code, message).into(),
+            Self::Fetch(_) => fetch_error(error_code, message).into(),
+            Self::Acknowledge(_) => acknowledge_error(error_code, message).into(),
+        }
+    }
+}
+
+fn heartbeat_error(
+    detail: &ShareGroupHeartbeat<'_>,
+    error_code: ErrorCode,
+    message: String,
+) -> ShareGroupHeartbeatResponse {
+    debug!(?detail, ?error_code, message);
+
+    ShareGroupHeartbeatResponse::default()
+        .throttle_time_ms(0)
+        .error_code(error_code.into())
+        .error_message(Some(message))
+        .member_id(Some(detail.member_id.into()))
+        .member_epoch(detail.member_epoch)
+        .heartbeat_interval_ms(HEARTBEAT_INTERVAL_MS)
+        .assignment(None)
+}
+
+fn fetch_error(error_code: ErrorCode, message: String) -> ShareFetchResponse {
+    debug!(?error_code, message);
+
+    ShareFetchResponse::default()
+        .throttle_time_ms(0)
+        .error_code(error_code.into())
+        .error_message(Some(message))
+        .responses(Some([].into()))
+        .node_endpoints(Some([].into()))
+}
+
+fn acknowledge_error(error_code: ErrorCode, message: String) -> ShareAcknowledgeResponse {
+    debug!(?error_code, message);
+
+    ShareAcknowledgeResponse::default()
+        .throttle_time_ms(0)
+        .error_code(error_code.into())
+        .error_message(Some(message))
+        .responses(Some([].into()))
+        .node_endpoints(Some([].into()))
+}
+
+/// Apply a request to the share group state of a group, returning the response
+pub async fn apply<S>(
+    storage: &S,
+    current: &mut GroupDetail,
+    now: SystemTime,
+    request: &Request<'_>,
+) -> Result<Body>
+where
+    S: Storage,
+{
+    if !current.members.is_empty()
+        || current
+            .consumer
+            .as_ref()
+            .is_some_and(|consumer| !consumer.members.is_empty())
+    {
+        return Ok(request.error(
+            ErrorCode::GroupIdNotFound,
+            format!("{} is not a share group", request.group_id()),
+        ));
+    }
+
+    let mut group = current.share.take().unwrap_or_default();
+
+    expire(&mut group, now);
+
+    let body = match request {
+        Request::Heartbeat(detail) => {
+            let topics = subscribed(storage, &group, detail).await?;
+            heartbeat(&mut group, now, detail, &topics).into()
+        }
+
+        Request::Fetch(detail) => fetch(storage, &mut group, now, detail).await?.into(),
+
+        Request::Acknowledge(detail) => acknowledge(&mut group, detail).into(),
+    };
+
+    current.share = Some(group);
+
+    Ok(body)
+}
+
+/// The topics subscribed to by the members of a group, or by the member heartbeating
+async fn subscribed<S>(
+    storage: &S,
+    group: &ShareGroup,
+    detail: &ShareGroupHeartbeat<'_>,
+) -> Result<BTreeMap<String, Subscribed>>
+where
+    S: Storage,
+{
+    consumer::topics(
+        storage,
+        group
+            .members
+            .values()
+            .flat_map(|member| member.subscribed_topic_names.iter())
+            .chain(detail.subscribed_topic_names.unwrap_or_default())
+            .cloned()
+            .collect(),
+    )
+    .await
+}
+
+/// Make a record available for redelivery, archiving it once it has reached
+/// the delivery count limit
+fn release(record: &mut ShareRecord) {
+    record.member_id = None;
+    record.lock_expiry = None;
+
+    record.state = if record.delivery_count >= DELIVERY_COUNT_LIMIT {
+        ShareDeliveryState::Archived
+    } else {
+        ShareDeliveryState::Available
+    };
+}
+
+/// Release the acquired records matching a predicate
+fn release_acquired(group: &mut ShareGroup, predicate: impl Fn(&ShareRecord) -> bool) {
+    for partition in group
+        .partitions
+        .values_mut()
+        .flat_map(|partitions| partitions.values_mut())
+    {
+        for record in partition.records.values_mut() {
+            if record.state == ShareDeliveryState::Acquired && predicate(record) {
+                release(record);
+            }
+        }
+
+        advance(partition);
+    }
+}
+
+/// Advance the start offset past the acknowledged or archived records
+fn advance(partition: &mut SharePartition) {
+    while let Some(entry) = partition.records.first_entry() {
+        if matches!(
+            entry.get().state,
+            ShareDeliveryState::Acknowledged | ShareDeliveryState::Archived
+        ) {
+            _ = entry.remove();
+        } else {
+            break;
+        }
+    }
+
+    partition.start_offset = partition
+        .records
+        .keys()
+        .next()
+        .copied()
+        .unwrap_or(partition.end_offset);
+}
+
+/// Fence members that have not heartbeat within the session timeout, and
+/// release any records with an expired lock
+fn expire(group: &mut ShareGroup, now: SystemTime) {
+    let expired = group
+        .members
+        .iter()
+        .filter(|(_, member)| {
+            member.last_contact.is_some_and(|last_contact| {
+                now.duration_since(last_contact)
+                    .is_ok_and(|elapsed| elapsed > SESSION_TIMEOUT)
+            })
+        })
+        .map(|(member_id, _)| member_id.clone())
+        .collect::<Vec<_>>();
+
+    for member_id in expired {
+        debug!(member_id, "expired");
+        _ = group.members.remove(&member_id);
+        group.group_epoch += 1;
+    }
+
+    let members = group.members.keys().cloned().collect::<BTreeSet<_>>();
+
+    release_acquired(group, |record| {
+        record
+            .lock_expiry
+            .is_some_and(|lock_expiry| lock_expiry <= now)
+            || record
+                .member_id
+                .as_ref()
+                .is_none_or(|member_id| !members.contains(member_id))
+    });
+}
+
+/// The assignment of each member of a group, with the partitions of a topic
+/// spread over its subscribers, and every subscriber assigned at least one
+/// partition
+fn target_assignment(
+    members: &BTreeMap<String, ShareGroupMember>,
+    topics: &BTreeMap<String, Subscribed>,
+) -> BTreeMap<String, ConsumerGroupAssignment> {
+    let mut targets = members
+        .keys()
+        .map(|member_id| (member_id.clone(), ConsumerGroupAssignment::new()))
+        .collect::<BTreeMap<_, _>>();
+
+    for (topic, subscribed) in topics {
+        let subscribers = members
+            .iter()
+            .filter(|(_, member)| member.subscribed_topic_names.contains(topic))
+            .map(|(member_id, _)| member_id)
+            .collect::<Vec<_>>();
+
+        let partitions = usize::try_from(subscribed.partitions).unwrap_or_default();
+
+        if subscribers.is_empty() || partitions == 0 {
+            continue;
+        }
+
+        for i in 0..partitions.max(subscribers.len()) {
+            _ = targets
+                .get_mut(subscribers[i % subscribers.len()])
+                .map(|target| {
+                    target
+                        .entry(topic.clone())
+                        .or_default()
+                        .insert((i % partitions) as i32)
+                });
+        }
+    }
+
+    targets
+}
+
+/// Assign partitions to every member, advancing the group epoch when any assignment changes
+fn assign(group: &mut ShareGroup, topics: &BTreeMap<String, Subscribed>) {
+    let mut targets = target_assignment(&group.members, topics);
+
+    if group
+        .members
+        .iter()
+        .any(|(member_id, member)| targets.get(member_id) != Some(&member.assignment))
+    {
+        group.group_epoch += 1;
+    }
+
+    for (member_id, member) in group.members.iter_mut() {
+        member.assignment = targets.remove(member_id).unwrap_or_default();
+    }
+
+    group.topics = topics
+        .iter()
+        .map(|(topic, subscribed)| (topic.clone(), subscribed.id))
+        .collect();
+}
+
+/// Apply a heartbeat to the group, returning the response to the member
+fn heartbeat(
+    group: &mut ShareGroup,
+    now: SystemTime,
+    detail: &ShareGroupHeartbeat<'_>,
+    topics: &BTreeMap<String, Subscribed>,
+) -> ShareGroupHeartbeatResponse {
+    let member_id = match detail.member_epoch {
+        JOIN_GROUP_MEMBER_EPOCH => {
+            let Some(subscribed) = detail.subscribed_topic_names else {
+                return heartbeat_error(
+                    detail,
+                    ErrorCode::InvalidRequest,
+                    "SubscribedTopicNames must be set when joining".into(),
+                );
+            };
+
+            let member_id = if detail.member_id.is_empty() {
+                Uuid::new_v4().to_string()
+            } else {
+                detail.member_id.to_owned()
+            };
+
+            _ = group.members.insert(
+                member_id.clone(),
+                ShareGroupMember {
+                    rack_id: detail.rack_id.map(Into::into),
+                    client_id: detail.client_id.unwrap_or_default().into(),
+                    subscribed_topic_names: subscribed.to_vec(),
+                    ..Default::default()
+                },
+            );
+
+            group.group_epoch += 1;
+            member_id
+        }
+
+        LEAVE_GROUP_MEMBER_EPOCH => {
+            if group.members.remove(detail.member_id).is_some() {
+                release_acquired(group, |record| {
+                    record.member_id.as_deref() == Some(detail.member_id)
+                });
+
+                group.group_epoch += 1;
+                assign(group, topics);
+            }
+
+            return ShareGroupHeartbeatResponse::default()
+                .throttle_time_ms(0)
+                .error_code(ErrorCode::None.into())
+                .error_message(None)
+                .member_id(Some(detail.member_id.into()))
+                .member_epoch(detail.member_epoch)
+                .heartbeat_interval_ms(0)
+                .assignment(None);
+        }
+
+        member_epoch => {
+            let Some(member) = group.members.get_mut(detail.member_id) else {
+                return heartbeat_error(
+                    detail,
+                    ErrorCode::UnknownMemberId,
+                    format!("member {} is not in the group", detail.member_id),
+                );
+            };
+
+            if member_epoch != member.member_epoch {
+                return heartbeat_error(
+                    detail,
+                    ErrorCode::FencedMemberEpoch,
+                    format!("member epoch {member_epoch} is not {}", member.member_epoch),
+                );
+            }
+
+            if let Some(rack_id) = detail.rack_id {
+                member.rack_id = Some(rack_id.into());
+            }
+
+            if let Some(subscribed) = detail.subscribed_topic_names
+                && subscribed != member.subscribed_topic_names.as_slice()
+            {
+                member.subscribed_topic_names = subscribed.to_vec();
+                group.group_epoch += 1;
+            }
+
+            detail.member_id.to_owned()
+        }
+    };
+
+    assign(group, topics);
+
+    let group_epoch = group.group_epoch;
+
+    let member = group
+        .members
+        .get_mut(&member_id)
+        .expect("a member that has just heartbeat");
+
+    member.last_contact = Some(now);
+
+    let assignment = (member.member_epoch != group_epoch).then(|| {
+        Assignment::default().topic_partitions(Some(
+            member
+                .assignment
+                .iter()
+                .map(|(topic, partitions)| {
+                    TopicPartitions::default()
+                        .topic_id(
+                            group
+                                .topics
+                                .get(topic)
+                                .copied()
+                                .unwrap_or_default()
+                                .into_bytes(),
+                        )
+                        .partitions(Some(partitions.iter().copied().collect()))
+                })
+                .collect(),
+        ))
+    });
+
+    member.member_epoch = group_epoch;
+
+    ShareGroupHeartbeatResponse::default()
+        .throttle_time_ms(0)
+        .error_code(ErrorCode::None.into())
+        .error_message(None)
+        .member_id(Some(member_id))
+        .member_epoch(group_epoch)
+        .heartbeat_interval_ms(HEARTBEAT_INTERVAL_MS)
+        .assignment(assignment)
+}
+
+/// The name of each subscribed topic by id
+fn names(group: &ShareGroup) -> BTreeMap<Uuid, String> {
+    group
+        .topics
+        .iter()
+        .map(|(name, id)| (*id, name.clone()))
+        .collect()
+}
+
+/// The first offset of a partition that could be acquired
+fn next_offset(partition: &SharePartition) -> Option<i64> {
+    partition
+        .records
+        .iter()
+        .find(|(_, record)| record.state == ShareDeliveryState::Available)
+        .map(|(offset, _)| *offset)
+        .or_else(|| (partition.records.len() < MAX_RECORD_LOCKS).then_some(partition.end_offset))
+}
+
+/// Acquire the available records of fetched batches for a member, returning
+/// the acquired ranges with the batches containing them
+fn acquire(
+    partition: &mut SharePartition,
+    member_id: &str,
+    now: SystemTime,
+    batches: Vec<Batch>,
+) -> (Vec<AcquiredRecords>, Vec<Batch>) {
+    let mut acquired = BTreeMap::<i64, i16>::new();
+    let mut delivered = vec![];
+
+    for batch in batches {
+        if batch.record_count == 0 {
+            continue;
+        }
+
+        let before = acquired.len();
+        let last_offset = batch.base_offset + i64::from(batch.last_offset_delta);
+
+        for offset in batch.base_offset.max(partition.start_offset)..=last_offset {
+            if offset >= partition.end_offset {
+                if batch.is_control() {
+                    partition.end_offset = offset + 1;
+                    continue;
+                }
+
+                if partition.records.len() >= MAX_RECORD_LOCKS {
+                    break;
+                }
+
+                _ = partition.records.insert(offset, ShareRecord::default());
+                partition.end_offset = offset + 1;
+            }
+
+            if let Some(record) = partition.records.get_mut(&offset)
+                && record.state == ShareDeliveryState::Available
+            {
+                record.state = ShareDeliveryState::Acquired;
+                record.delivery_count += 1;
+                record.member_id = Some(member_id.into());
+                record.lock_expiry = now.checked_add(RECORD_LOCK_DURATION);
+
+                _ = acquired.insert(offset, record.delivery_count);
+            }
+        }
+
+        if acquired.len() > before {
+            delivered.push(batch);
+        }
+    }
+
+    advance(partition);
+
+    let mut ranges: Vec<AcquiredRecords> = vec![];
+
+    for (offset, delivery_count) in acquired {
+        match ranges.last_mut() {
+            Some(range)
+                if range.last_offset + 1 == offset && range.delivery_count == delivery_count =>
+            {
+                range.last_offset = offset
+            }
+
+            _ => ranges.push(
+                AcquiredRecords::default()
+                    .first_offset(offset)
+                    .last_offset(offset)
+                    .delivery_count(delivery_count),
+            ),
+        }
+    }
+
+    (ranges, delivered)
+}
+
+/// Acknowledge a batch of records acquired by a member, with either a single
+/// acknowledge type for the batch, or one for each of its records
+fn acknowledge_batch(
+    partition: &mut SharePartition,
+    member_id: &str,
+    first_offset: i64,
+    last_offset: i64,
+    acknowledge_types: &[i8],
+) -> ErrorCode {
+    let Ok(length) = usize::try_from(last_offset - first_offset + 1) else {
+        return ErrorCode::InvalidRequest;
+    };
+
+    if acknowledge_types.len() != 1 && acknowledge_types.len() != length {
+        return ErrorCode::InvalidRequest;
+    }
+
+    let acknowledgements = (first_offset..=last_offset)
+        .zip(acknowledge_types.iter().copied().cycle())
+        .collect::<Vec<_>>();
+
+    for (offset, acknowledge_type) in &acknowledgements {
+        if !(GAP..=REJECT).contains(acknowledge_type) {
+            return ErrorCode::InvalidRequest;
+        }
+
+        match partition.records.get(offset) {
+            Some(record)
+                if record.state == ShareDeliveryState::Acquired
+                    && record.member_id.as_deref() == Some(member_id) => {}
+
+            None if *acknowledge_type == GAP => {}
+
+            otherwise => {
+                debug!(offset, ?otherwise);
+                return ErrorCode::InvalidRecordState;
+            }
+        }
+    }
+
+    for (offset, acknowledge_type) in acknowledgements {
+        let Some(record) = partition.records.get_mut(&offset) else {
+            continue;
+        };
+
+        match acknowledge_type {
+            ACCEPT => record.state = ShareDeliveryState::Acknowledged,
+            RELEASE => release(record),
+            _ => record.state = ShareDeliveryState::Archived,
+        }
+
+        if record.state != ShareDeliveryState::Available {
+            record.member_id = None;
+            record.lock_expiry = None;
+        }
+    }
+
+    advance(partition);
+
+    ErrorCode::None
+}
+
+/// Acknowledge batches of the records of a partition acquired by a member
+fn acknowledge_partition<'a>(
+    group: &mut ShareGroup,
+    topic: &str,
+    partition_index: i32,
+    member_id: &str,
+    batches: impl Iterator<Item = (i64, i64, &'a [i8])>,
+) -> ErrorCode {
+    let Some(partition) = group
+        .partitions
+        .get_mut(topic)
+        .and_then(|partitions| partitions.get_mut(&partition_index))
+    else {
+        return ErrorCode::InvalidRecordState;
+    };
+
+    for (first_offset, last_offset, acknowledge_types) in batches {
+        let error_code = acknowledge_batch(
+            partition,
+            member_id,
+            first_offset,
+            last_offset,
+            acknowledge_types,
+        );
+
+        if error_code != ErrorCode::None {
+            return error_code;
+        }
+    }
+
+    ErrorCode::None
+}
+
+fn partition_data(partition_index: i32) -> PartitionData {
+    PartitionData::default()
+        .partition_index(partition_index)
+        .error_code(ErrorCode::None.into())
+        .error_message(None)
+        .acknowledge_error_code(ErrorCode::None.into())
+        .acknowledge_error_message(None)
+        .current_leader(LeaderIdAndEpoch::default().leader_id(-1).leader_epoch(-1))
+        .records(None)
+        .acquired_records(Some([].into()))
+}
+
+/// Acknowledge the records in a fetch, then acquire records from the
+/// partitions of the fetch and those assigned to the member
+async fn fetch<S>(
+    storage: &S,
+    group: &mut ShareGroup,
+    now: SystemTime,
+    detail: &ShareFetch<'_>,
+) -> Result<ShareFetchResponse>
+where
+    S: Storage,
+{
+    let Some(member) = group.members.get(detail.member_id) else {
+        return Ok(fetch_error(
+            ErrorCode::UnknownMemberId,
+            format!("member {} is not in the group", detail.member_id),
+        ));
+    };
+
+    let closing = detail.share_session_epoch == FINAL_SHARE_SESSION_EPOCH;
+
+    let mut fetching = if closing {
+        ConsumerGroupAssignment::new()
+    } else {
+        member.assignment.clone()
+    };
+
+    let names = names(group);
+    let mut responses = BTreeMap::<Uuid, BTreeMap<i32, PartitionData>>::new();
+
+    for topic in detail.topics.unwrap_or_default() {
+        let topic_id = Uuid::from_bytes(topic.topic_id);
+
+        for partition in topic.partitions.as_deref().unwrap_or_default() {
+            let data = responses
+                .entry(topic_id)
+                .or_default()
+                .entry(partition.partition_index)
+                .or_insert_with(|| partition_data(partition.partition_index));
+
+            let Some(name) = names.get(&topic_id) else {
+                data.error_code = ErrorCode::UnknownTopicId.into();
+                continue;
+            };
+
+            if let Some(batches) = partition.acknowledgement_batches.as_deref()
+                && !batches.is_empty()
+            {
+                data.acknowledge_error_code = acknowledge_partition(
+                    group,
+                    name,
+                    partition.partition_index,
+                    detail.member_id,
+                    batches.iter().map(|batch| {
+                        (
+                            batch.first_offset,
+                            batch.last_offset,
+                            batch.acknowledge_types.as_deref().unwrap_or_default(),
+                        )
+                    }),
+                )
+                .into();
+            }
+
+            if !closing {
+                _ = fetching
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(partition.partition_index);
+            }
+        }
+    }
+
+    for topic in detail.forgotten_topics_data.unwrap_or_default() {
+        if let Some(name) = names.get(&Uuid::from_bytes(topic.topic_id))
+            && let Some(partitions) = fetching.get_mut(name)
+        {
+            for partition in topic.partitions.as_deref().unwrap_or_default() {
+                _ = partitions.remove(partition);
+            }
+        }
+    }
+
+    let mut max_bytes = u32::try_from(detail.max_bytes)
+        .ok()
+        .filter(|max_bytes| *max_bytes > 0)
+        .unwrap_or(i32::MAX as u32);
+
+    for (topic, partitions) in fetching {
+        let topic_id = group.topics.get(&topic).copied().unwrap_or_default();
+
+        for partition_index in partitions {
+            let topition = Topition::new(topic.clone(), partition_index);
+
+            let data = responses
+                .entry(topic_id)
+                .or_default()
+                .entry(partition_index)
+                .or_insert_with(|| partition_data(partition_index));
+
+            let initial = if group
+                .partitions
+                .get(&topic)
+                .is_some_and(|partitions| partitions.contains_key(&partition_index))
+            {
+                None
+            } else {
+                match storage.offset_stage(&topition).await {
+                    Ok(stage) => Some(stage.high_watermark()),
+
+                    Err(error) => {
+                        debug!(?topition, ?error);
+                        data.error_code = ErrorCode::UnknownTopicOrPartition.into();
+                        continue;
+                    }
+                }
+            };
+
+            let partition = group
+                .partitions
+                .entry(topic.clone())
+                .or_default()
+                .entry(partition_index)
+                .or_insert_with(|| {
+                    let offset = initial.unwrap_or_default();
+
+                    SharePartition {
+                        start_offset: offset,
+                        end_offset: offset,
+                        ..Default::default()
+                    }
+                });
+
+            let Some(offset) = next_offset(partition).filter(|_| max_bytes > 0) else {
+                continue;
+            };
+
+            let fetched = storage
+                .fetch(
+                    &topition,
+                    offset,
+                    1,
+                    max_bytes,
+                    IsolationLevel::ReadCommitted,
+                )
+                .await?;
+
+            let (acquired, batches) = acquire(partition, detail.member_id, now, fetched);
+
+            max_bytes = max_bytes.saturating_sub(
+                u32::try_from(
+                    batches
+                        .iter()
+                        .map(|batch| batch.record_data.len())
+                        .sum::<usize>(),
+                )
+                .unwrap_or(u32::MAX),
+            );
+
+            if !batches.is_empty() {
+                data.records = Some(Frame { batches });
+                data.acquired_records = Some(acquired);
+            }
+        }
+    }
+
+    Ok(ShareFetchResponse::default()
+        .throttle_time_ms(0)
+        .error_code(ErrorCode::None.into())
+        .error_message(None)
+        .responses(Some(
+            responses
+                .into_iter()
+                .map(|(topic_id, partitions)| {
+                    ShareFetchableTopicResponse::default()
+                        .topic_id(topic_id.into_bytes())
+                        .partitions(Some(partitions.into_values().collect()))
+                })
+                .collect(),
+        ))
+        .node_endpoints(Some([].into())))
+}
+
+/// Acknowledge the records acquired by a member
+fn acknowledge(group: &mut ShareGroup, detail: &ShareAcknowledge<'_>) -> ShareAcknowledgeResponse {
+    if !group.members.contains_key(detail.member_id) {
+        return acknowledge_error(
+            ErrorCode::UnknownMemberId,
+            format!("member {} is not in the group", detail.member_id),
+        );
+    }
+
+    let names = names(group);
+    let mut responses = vec![];
+
+    for topic in detail.topics.unwrap_or_default() {
+        let name = names.get(&Uuid::from_bytes(topic.topic_id));
+        let mut partitions = vec![];
+
+        for partition in topic.partitions.as_deref().unwrap_or_default() {
+            let error_code = name.map_or(ErrorCode::UnknownTopicId, |name| {
+                acknowledge_partition(
+                    group,
+                    name,
+                    partition.partition_index,
+                    detail.member_id,
+                    partition
+                        .acknowledgement_batches
+                        .as_deref()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|batch| {
+                            (
+                                batch.first_offset,
+                                batch.last_offset,
+                                batch.acknowledge_types.as_deref().unwrap_or_default(),
+                            )
+                        }),
+                )
+            });
+
+            partitions.push(
+                share_acknowledge_response::PartitionData::default()
+                    .partition_index(partition.partition_index)
+                    .error_code(error_code.into())
+                    .error_message(None)
+                    .current_leader(
+                        share_acknowledge_response::LeaderIdAndEpoch::default()
+                            .leader_id(-1)
+                            .leader_epoch(-1),
+                    ),
+            );
+        }
+
+        responses.push(
+            ShareAcknowledgeTopicResponse::default()
+                .topic_id(topic.topic_id)
+                .partitions(Some(partitions)),
+        );
+    }
+
+    ShareAcknowledgeResponse::default()
+        .throttle_time_ms(0)
+        .error_code(ErrorCode::None.into())
+        .error_message(None)
+        .responses(Some(responses))
+        .node_endpoints(Some([].into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(base_offset: i64, record_count: u32) -> Batch {
+        Batch {
+            base_offset,
+            last_offset_delta: record_count as i32 - 1,
+            record_count,
+            ..Default::default()
+        }
+    }
+
+    fn state(partition: &SharePartition, offset: i64) -> Option<ShareDeliveryState> {
+        partition.records.get(&offset).map(|record| record.state)
+    }
+
+    #[test]
+    fn more_members_than_partitions() {
+        let member = || ShareGroupMember {
+            subscribed_topic_names: vec!["abc".into()],
+            ..Default::default()
+        };
+
+        let members = BTreeMap::from([
+            ("a".into(), member()),
+            ("b".into(), member()),
+            ("c".into(), member()),
+        ]);
+
+        let topics = BTreeMap::from([(
+            "abc".into(),
+            Subscribed {
+                id: Uuid::from_u128(1),
+                partitions: 2,
+            },
+        )]);
+
+        let targets = target_assignment(&members, &topics);
+        assert_eq!(Some(&BTreeSet::from([0])), targets["a"].get("abc"));
+        assert_eq!(Some(&BTreeSet::from([1])), targets["b"].get("abc"));
+        assert_eq!(Some(&BTreeSet::from([0])), targets["c"].get("abc"));
+    }
+
+    #[test]
+    fn acquire_acknowledge_release() {
+        let now = SystemTime::now();
+        let mut partition = SharePartition::default();
+
+        let (acquired, delivered) = acquire(&mut partition, "a", now, vec![batch(0, 3)]);
+        assert_eq!(1, delivered.len());
+        assert_eq!(
+            vec![
+                AcquiredRecords::default()
+                    .first_offset(0)
+                    .last_offset(2)
+                    .delivery_count(1)
+            ],
+            acquired
+        );
+
+        // records acquired by one member cannot be acknowledged by another
+        assert_eq!(
+            ErrorCode::InvalidRecordState,
+            acknowledge_batch(&mut partition, "b", 0, 0, &[ACCEPT])
+        );
+
+        assert_eq!(
+            ErrorCode::None,
+            acknowledge_batch(&mut partition, "a", 0, 2, &[ACCEPT, RELEASE, REJECT])
+        );
+        assert_eq!(1, partition.start_offset);
+        assert_eq!(3, partition.end_offset);
+        assert_eq!(Some(ShareDeliveryState::Available), state(&partition, 1));
+        assert_eq!(Some(ShareDeliveryState::Archived), state(&partition, 2));
+        assert_eq!(Some(1), next_offset(&partition));
+
+        // only the released record is redelivered
+        let (acquired, _) = acquire(&mut partition, "b", now, vec![batch(0, 3)]);
+        assert_eq!(
+            vec![
+                AcquiredRecords::default()
+                    .first_offset(1)
+                    .last_offset(1)
+                    .delivery_count(2)
+            ],
+            acquired
+        );
+
+        assert_eq!(
+            ErrorCode::None,
+            acknowledge_batch(&mut partition, "b", 1, 1, &[ACCEPT])
+        );
+        assert!(partition.records.is_empty());
+        assert_eq!(3, partition.start_offset);
+    }
+
+    #[test]
+    fn expired_locks_are_released() {
+        let now = SystemTime::now();
+
+        let mut group = ShareGroup {
+            members: BTreeMap::from([(
+                "a".into(),
+                ShareGroupMember {
+                    last_contact: Some(now),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let mut partition = SharePartition::default();
+
+        for _ in 0..DELIVERY_COUNT_LIMIT {
+            _ = acquire(&mut partition, "a", now, vec![batch(0, 1)]);
+            _ = group
+                .partitions
+                .entry("abc".into())
+                .or_default()
+                .insert(0, partition.clone());
+
+            expire(&mut group, now + RECORD_LOCK_DURATION);
+            partition = group.partitions["abc"][&0].clone();
+        }
+
+        // archived having reached the delivery count limit
+        assert!(partition.records.is_empty());
+        assert_eq!(1, partition.start_offset);
+    }
+}
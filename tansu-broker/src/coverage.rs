@@ -0,0 +1,89 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol coverage
+//!
+//! A matrix of every API key known to the protocol model, with the version
+//! range of each message and whether the broker has a handler registered for it.
+
+use serde::{Deserialize, Serialize};
+use tansu_sans_io::{ApiKey as _, ApiVersionsRequest, RootMessageMeta};
+use tansu_storage::{FetchService, StorageContainer};
+use url::Url;
+
+use crate::{NODE_ID, Result, coordinator::group::administrator::Controller, service::routes};
+
+/// Coverage of a single API key
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ApiCoverage {
+    pub api_key: i16,
+    pub name: String,
+    pub min_version: i16,
+    pub max_version: i16,
+    pub deprecated_versions: Option<(i16, i16)>,
+    pub supported: bool,
+}
+
+/// The coverage of all API keys given the keys handled by a broker
+pub fn coverage(handled: &[i16]) -> Vec<ApiCoverage> {
+    let mut coverage = RootMessageMeta::messages()
+        .requests()
+        .values()
+        .map(|meta| ApiCoverage {
+            api_key: meta.api_key,
+            name: meta
+                .name
+                .strip_suffix("Request")
+                .unwrap_or(meta.name)
+                .to_owned(),
+            min_version: meta.version.valid.start,
+            max_version: meta.version.valid.end,
+            deprecated_versions: meta
+                .version
+                .deprecated
+                .map(|deprecated| (deprecated.start, deprecated.end)),
+            supported: handled.contains(&meta.api_key),
+        })
+        .collect::<Vec<_>>();
+
+    coverage.sort();
+    coverage
+}
+
+/// The coverage of this broker, using the handlers registered on a null storage engine
+pub async fn broker() -> Result<Vec<ApiCoverage>> {
+    let storage = StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(NODE_ID)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(Url::parse("null://tansu/")?)
+        .build()
+        .await?;
+
+    let coordinator = Controller::with_storage(storage.clone())?;
+
+    routes(
+        coordinator,
+        storage,
+        FetchService::default(),
+        None,
+        None,
+        None,
+    )
+    .map(|builder| {
+        let mut handled = builder.api_keys();
+        handled.push(ApiVersionsRequest::KEY);
+        coverage(&handled)
+    })
+}
@@ -38,7 +38,9 @@ use tracing_subscriber::filter::ParseError;
 use url::Url;
 
 pub mod broker;
+pub mod clock;
 pub mod coordinator;
+pub mod coverage;
 pub mod otel;
 pub mod service;
 
@@ -72,6 +74,8 @@ pub(crate) static METER: LazyLock<Meter> = LazyLock::new(|| {
 pub enum Error {
     AddrParse(#[from] AddrParseError),
     Api(ErrorCode),
+    Base64(#[from] base64::DecodeError),
+    Client(#[from] tansu_client::Error),
     Custom(String),
     DuplicateApiService(i16),
     EmptyCoordinatorWrapper,
@@ -83,6 +87,7 @@ pub enum Error {
     Io(Arc<io::Error>),
     Join(Arc<JoinError>),
     Json(Arc<serde_json::Error>),
+    Jwt(Arc<jsonwebtoken::errors::Error>),
     KafkaProtocol(#[from] tansu_sans_io::Error),
 
     #[cfg(feature = "libsql")]
@@ -97,6 +102,7 @@ pub enum Error {
     ParseFilter(Arc<ParseError>),
     ParseInt(#[from] std::num::ParseIntError),
     Pattern(Arc<PatternError>),
+    Pem(Arc<tokio_rustls::rustls::pki_types::pem::Error>),
     Poison,
 
     #[cfg(feature = "postgres")]
@@ -107,6 +113,8 @@ pub enum Error {
     Storage(#[from] tansu_storage::Error),
     StringUtf8(#[from] FromUtf8Error),
     Regex(#[from] regex::Error),
+    Reqwest(Arc<reqwest::Error>),
+    Rustls(Arc<tokio_rustls::rustls::Error>),
 
     #[cfg(feature = "postgres")]
     TokioPostgres(Arc<tokio_postgres::error::Error>),
@@ -116,6 +124,8 @@ pub enum Error {
     Turso(Arc<turso::Error>),
 
     UnsupportedApiService(i16),
+    UnsupportedCredentialsUrl(Url),
+    UnsupportedJwksUrl(Url),
     UnsupportedStorageUrl(Url),
     UnsupportedTracingFormat(String),
     Url(#[from] url::ParseError),
@@ -209,6 +219,30 @@ impl From<Arc<serde_json::Error>> for Error {
     }
 }
 
+impl From<jsonwebtoken::errors::Error> for Error {
+    fn from(value: jsonwebtoken::errors::Error) -> Self {
+        Self::Jwt(Arc::new(value))
+    }
+}
+
+impl From<tokio_rustls::rustls::pki_types::pem::Error> for Error {
+    fn from(value: tokio_rustls::rustls::pki_types::pem::Error) -> Self {
+        Self::Pem(Arc::new(value))
+    }
+}
+
+impl From<tokio_rustls::rustls::Error> for Error {
+    fn from(value: tokio_rustls::rustls::Error) -> Self {
+        Self::Rustls(Arc::new(value))
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Reqwest(Arc::new(value))
+    }
+}
+
 #[cfg(feature = "dynostore")]
 impl From<object_store::Error> for Error {
     fn from(value: object_store::Error) -> Self {
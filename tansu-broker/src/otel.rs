@@ -12,13 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use ::tracing::debug;
+use std::{collections::BTreeMap, env, fmt, str::FromStr as _};
+
+use ::tracing::{debug, level_filters::LevelFilter};
 use opentelemetry::{KeyValue, global};
 use opentelemetry_otlp::{Protocol, WithExportConfig as _};
-use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::{
+    Resource,
+    metrics::{Aggregation, Instrument, InstrumentKind, SdkMeterProvider, Stream},
+};
+use opentelemetry_semantic_conventions::resource::SERVICE_NAME;
 use url::Url;
 
-use crate::{Result, TracingFormat};
+use crate::{Error, Result, TracingFormat};
 
 mod tracing;
 
@@ -32,27 +38,347 @@ pub fn init(tracing_format: TracingFormat) -> Result<Guard> {
     tracing::init_tracing_subscriber(tracing_format).map(|tracer| Guard { tracer })
 }
 
-pub fn metric_exporter(endpoint: Url) -> Result<()> {
-    let endpoint = endpoint
-        .join("v1/metrics")
-        .inspect(|endpoint| debug!(%endpoint))?;
+/// The currently active tracing filter directives
+pub fn log_filter() -> Option<String> {
+    tracing::filter()
+}
+
+/// Replace the active tracing filter with new directives, e.g., `info,tansu_storage=debug`
+pub fn set_log_filter(directives: &str) -> Result<()> {
+    debug!(directives);
+    tracing::replace_filter(directives)
+}
+
+/// Tracing filter directives keyed by target (module)
+///
+/// The root (target-less) directive is held under an empty key.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LogLevels(BTreeMap<String, String>);
+
+impl LogLevels {
+    /// The levels of the currently active tracing filter
+    pub fn current() -> Self {
+        log_filter().as_deref().map(Self::from).unwrap_or_default()
+    }
+
+    /// Set the level for a target, an empty target or `root` alters the default level
+    pub fn set(&mut self, target: &str, level: &str) {
+        _ = self.0.insert(Self::key(target), level.to_lowercase());
+    }
+
+    /// Remove the level for a target, falling back to the default level
+    pub fn remove(&mut self, target: &str) {
+        _ = self.0.remove(&Self::key(target));
+    }
 
-    let exporter = opentelemetry_otlp::MetricExporter::builder()
-        .with_http()
-        .with_protocol(Protocol::HttpBinary)
-        .with_endpoint(endpoint.to_string())
-        .build()?;
+    pub fn levels(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(target, level)| (target.as_str(), level.as_str()))
+    }
 
-    let meter_provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
-        .with_periodic_exporter(exporter)
-        .with_resource(
-            Resource::builder_empty()
-                .with_attributes([KeyValue::new("service.name", env!("CARGO_PKG_NAME"))])
-                .build(),
+    /// Check that each level is a valid level filter, without applying them
+    pub fn validate(&self) -> Result<()> {
+        for (target, level) in self.levels() {
+            _ = LevelFilter::from_str(level)
+                .map_err(|err| Error::Message(format!("{target}={level}: {err}")))?;
+        }
+
+        tracing::validate_filter(&self.to_string())
+    }
+
+    /// Make these levels the active tracing filter
+    pub fn apply(&self) -> Result<()> {
+        self.validate()?;
+        set_log_filter(&self.to_string())
+    }
+
+    fn key(target: &str) -> String {
+        if target.eq_ignore_ascii_case("root") {
+            String::new()
+        } else {
+            target.to_owned()
+        }
+    }
+}
+
+impl From<&str> for LogLevels {
+    fn from(directives: &str) -> Self {
+        Self(
+            directives
+                .split(',')
+                .map(str::trim)
+                .filter(|directive| !directive.is_empty())
+                .map(|directive| {
+                    directive.rsplit_once('=').map_or_else(
+                        || (String::new(), directive.to_owned()),
+                        |(target, level)| (target.to_owned(), level.to_owned()),
+                    )
+                })
+                .collect(),
         )
-        .build();
+    }
+}
+
+impl fmt::Display for LogLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let directives = self
+            .0
+            .iter()
+            .map(|(target, level)| {
+                if target.is_empty() {
+                    level.to_owned()
+                } else {
+                    format!("{target}={level}")
+                }
+            })
+            .collect::<Vec<_>>();
+
+        f.write_str(&directives.join(","))
+    }
+}
+
+/// The aggregation of latency histograms
+///
+/// Exponential buckets keep a fixed number of buckets while covering a range
+/// wide enough to place p999 latencies, without a boundary per bucket being
+/// configured. The aggregation applies to latency histograms only, being
+/// those with a unit of [`LATENCY_UNIT`], so that a new histogram measuring
+/// latency in milliseconds uses it without being listed here.
+///
+/// Exemplars linking a measurement to its trace are not recorded, the
+/// OpenTelemetry SDK used here has no support for them.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum LatencyHistogram {
+    /// Base 2 exponential buckets, rescaled to hold at most `max_size` buckets
+    Exponential { max_size: u32, max_scale: i8 },
+
+    /// Buckets with these boundaries
+    Explicit(Vec<f64>),
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::Exponential {
+            max_size: 160,
+            max_scale: 20,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// A latency histogram with these boundaries, or exponential buckets
+    /// without any
+    pub fn boundaries(boundaries: Vec<f64>) -> Self {
+        if boundaries.is_empty() {
+            Self::default()
+        } else {
+            Self::Explicit(boundaries)
+        }
+    }
+
+    fn aggregation(&self) -> Aggregation {
+        match self {
+            Self::Exponential {
+                max_size,
+                max_scale,
+            } => Aggregation::Base2ExponentialHistogram {
+                max_size: *max_size,
+                max_scale: *max_scale,
+                record_min_max: true,
+            },
+
+            Self::Explicit(boundaries) => Aggregation::ExplicitBucketHistogram {
+                boundaries: boundaries.clone(),
+                record_min_max: true,
+            },
+        }
+    }
+
+    /// Aggregate latency histograms, leaving other instruments unchanged
+    fn view(&self, instrument: &Instrument) -> Option<Stream> {
+        if !is_latency(instrument.kind(), instrument.unit()) {
+            return None;
+        }
+
+        Stream::builder()
+            .with_aggregation(self.aggregation())
+            .build()
+            .inspect_err(|err| debug!(name = instrument.name(), ?err))
+            .ok()
+    }
+}
+
+/// The unit of every histogram measuring latency
+pub const LATENCY_UNIT: &str = "ms";
+
+fn is_latency(kind: InstrumentKind, unit: &str) -> bool {
+    kind == InstrumentKind::Histogram && unit == LATENCY_UNIT
+}
+
+/// Resource attributes describing this broker in exported telemetry, e.g.,
+/// `service.name`, `deployment.environment` or custom labels
+///
+/// The `service.name` is this crate unless set otherwise.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ResourceAttributes(BTreeMap<String, String>);
 
-    global::set_meter_provider(meter_provider);
+impl ResourceAttributes {
+    /// Attributes from the `OTEL_RESOURCE_ATTRIBUTES` and `OTEL_SERVICE_NAME`
+    /// environment variables
+    pub fn from_env() -> Self {
+        let mut attributes = env::var("OTEL_RESOURCE_ATTRIBUTES")
+            .as_deref()
+            .map(Self::from)
+            .unwrap_or_default();
+
+        if let Ok(service_name) = env::var("OTEL_SERVICE_NAME") {
+            attributes.set(SERVICE_NAME, &service_name);
+        }
+
+        attributes
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        _ = self.0.insert(key.to_owned(), value.to_owned());
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    fn resource(&self) -> Resource {
+        let mut attributes = self.0.clone();
+
+        _ = attributes
+            .entry(SERVICE_NAME.to_owned())
+            .or_insert_with(|| env!("CARGO_PKG_NAME").to_owned());
+
+        Resource::builder_empty()
+            .with_attributes(
+                attributes
+                    .into_iter()
+                    .map(|(key, value)| KeyValue::new(key, value)),
+            )
+            .build()
+    }
+}
+
+impl From<&str> for ResourceAttributes {
+    fn from(attributes: &str) -> Self {
+        Self(
+            attributes
+                .split(',')
+                .filter_map(|attribute| attribute.split_once('='))
+                .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+                .filter(|(key, _)| !key.is_empty())
+                .collect(),
+        )
+    }
+}
+
+impl FromIterator<(String, String)> for ResourceAttributes {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Export metrics to each OTLP endpoint, e.g., a local collector and a
+/// hosted service
+pub fn metric_exporter(
+    endpoints: &[Url],
+    resource_attributes: &ResourceAttributes,
+    latency_histogram: LatencyHistogram,
+) -> Result<()> {
+    let mut meter_provider = SdkMeterProvider::builder()
+        .with_view(move |instrument: &Instrument| latency_histogram.view(instrument))
+        .with_resource(resource_attributes.resource());
+
+    for endpoint in endpoints {
+        let endpoint = endpoint
+            .join("v1/metrics")
+            .inspect(|endpoint| debug!(%endpoint))?;
+
+        let exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(endpoint.to_string())
+            .build()?;
+
+        meter_provider = meter_provider.with_periodic_exporter(exporter);
+    }
+
+    global::set_meter_provider(meter_provider.build());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_instruments() {
+        assert!(is_latency(InstrumentKind::Histogram, "ms"));
+        assert!(!is_latency(InstrumentKind::Histogram, "By"));
+        assert!(!is_latency(InstrumentKind::Histogram, ""));
+        assert!(!is_latency(InstrumentKind::Counter, "ms"));
+
+        assert_eq!(
+            LatencyHistogram::default(),
+            LatencyHistogram::boundaries(vec![])
+        );
+        assert_eq!(
+            LatencyHistogram::Explicit(vec![1.0, 5.0, 25.0]),
+            LatencyHistogram::boundaries(vec![1.0, 5.0, 25.0])
+        );
+    }
+
+    #[test]
+    fn resource_attributes() {
+        let mut attributes =
+            ResourceAttributes::from("deployment.environment=staging, team = streaming,invalid");
+        attributes.set("service.name", "tansu-eu-west");
+
+        assert_eq!(
+            vec![
+                ("deployment.environment", "staging"),
+                ("service.name", "tansu-eu-west"),
+                ("team", "streaming"),
+            ],
+            attributes.attributes().collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Some(env!("CARGO_PKG_NAME").into()),
+            ResourceAttributes::default()
+                .resource()
+                .get(&SERVICE_NAME.into())
+        );
+    }
+
+    #[test]
+    fn log_levels_round_trip() {
+        let mut levels = LogLevels::from("warn,tansu_storage=debug");
+
+        levels.set("tansu_broker::broker", "TRACE");
+        levels.set("root", "info");
+        levels.remove("tansu_storage");
+
+        assert_eq!("info,tansu_broker::broker=trace", levels.to_string());
+    }
+
+    #[test]
+    fn log_levels_validated() {
+        let mut levels = LogLevels::from("warn");
+        assert!(levels.validate().is_ok());
+
+        levels.set("tansu_storage", "loud");
+        assert!(levels.validate().is_err());
+
+        levels.set("tansu_storage", "off");
+        assert!(levels.validate().is_ok());
+    }
+}
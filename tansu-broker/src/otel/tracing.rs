@@ -12,12 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{Result, TracingFormat};
-use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::{env, sync::OnceLock};
+
+use crate::{Error, Result, TracingFormat};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig as _};
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
 use tracing_subscriber::{
-    EnvFilter, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt,
+    EnvFilter, Registry, fmt::format::FmtSpan, layer::SubscriberExt, registry::LookupSpan, reload,
+    util::SubscriberInitExt,
 };
 
+use super::ResourceAttributes;
+
+/// Handle used to replace the active filter without restarting the broker
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
 #[derive(Debug)]
 pub(super) struct Guard {
     tracer: Option<SdkTracerProvider>,
@@ -33,14 +45,52 @@ impl Drop for Guard {
     }
 }
 
+/// Export spans to each endpoint in `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`
+/// (comma separated), the tracing subscriber being initialised before any
+/// command line is parsed
+fn tracer_provider() -> Result<Option<SdkTracerProvider>> {
+    let Ok(endpoints) = env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") else {
+        return Ok(None);
+    };
+
+    let mut provider =
+        SdkTracerProvider::builder().with_resource(ResourceAttributes::from_env().resource());
+
+    for endpoint in endpoints
+        .split(',')
+        .map(str::trim)
+        .filter(|endpoint| !endpoint.is_empty())
+    {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(endpoint)
+            .build()?;
+
+        provider = provider.with_batch_exporter(exporter);
+    }
+
+    Ok(Some(provider.build()))
+}
+
+fn otel_layer<S>(tracer: Option<&SdkTracerProvider>) -> Option<OpenTelemetryLayer<S, Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracer.map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+    })
+}
+
 pub(super) fn init_tracing_subscriber(tracing_format: TracingFormat) -> Result<Guard> {
-    // let provider = init_tracer_provider()?;
+    let tracer = tracer_provider()?;
 
-    // let tracer = provider.tracer(format!("{}-otel-subscriber", env!("CARGO_PKG_NAME")));
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    _ = FILTER.set(handle);
 
     match tracing_format {
         TracingFormat::Text => tracing_subscriber::registry()
-            .with(EnvFilter::from_default_env())
+            .with(filter)
             .with(
                 tracing_subscriber::fmt::layer()
                     .with_level(true)
@@ -48,15 +98,40 @@ pub(super) fn init_tracing_subscriber(tracing_format: TracingFormat) -> Result<G
                     .with_thread_ids(false)
                     .with_span_events(FmtSpan::FULL),
             )
-            // .with(OpenTelemetryLayer::new(tracer))
+            .with(otel_layer(tracer.as_ref()))
             .init(),
 
         TracingFormat::Json => tracing_subscriber::registry()
-            .with(EnvFilter::from_default_env())
+            .with(filter)
             .with(tracing_subscriber::fmt::layer().json())
-            // .with(OpenTelemetryLayer::new(tracer))
+            .with(otel_layer(tracer.as_ref()))
             .init(),
     }
 
-    Ok(Guard { tracer: None })
+    Ok(Guard { tracer })
+}
+
+pub(super) fn filter() -> Option<String> {
+    FILTER
+        .get()
+        .and_then(|handle| handle.with_current(|filter| filter.to_string()).ok())
+}
+
+pub(super) fn validate_filter(directives: &str) -> Result<()> {
+    EnvFilter::try_new(directives)
+        .map(|_| ())
+        .map_err(Into::into)
+}
+
+pub(super) fn replace_filter(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(directives)?;
+
+    FILTER
+        .get()
+        .ok_or(Error::Message("tracing subscriber not initialised".into()))
+        .and_then(|handle| {
+            handle
+                .reload(filter)
+                .map_err(|err| Error::Message(err.to_string()))
+        })
 }
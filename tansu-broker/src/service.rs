@@ -12,39 +12,131 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
 use rama::Layer;
+use tansu_schema::Registry;
 use tansu_service::{
-    BytesFrameLayer, BytesFrameService, FrameRouteService, TcpBytesLayer, TcpBytesService,
-    TcpContext, TcpContextLayer, TcpContextService,
+    BytesFrameLayer, BytesFrameService, FrameRouteBuilder, FrameRouteService, ReceivedFrame,
+    TcpBytesLayer, TcpBytesService, TcpContext, TcpContextLayer, TcpContextService,
 };
-use tansu_storage::Storage;
+use tansu_storage::{FetchService, Storage};
 use tracing::debug;
 
-use crate::{Error, Result, coordinator::group::Coordinator};
+use crate::{
+    Error, Result,
+    broker::{
+        authorizer::{Authorizer, AuthorizerLayer, AuthorizerService},
+        feature::{FeatureLayer, FeatureService},
+        oauth::OAuthBearer,
+        quota::{ClientQuotaLayer, ClientQuotaService},
+        sasl::{Credentials, SaslAuthenticationLayer, SaslAuthenticationService},
+        tag::{StorageTagLayer, StorageTagService},
+    },
+    coordinator::group::Coordinator,
+};
 
 pub mod coordinator;
+pub mod sasl;
 pub mod storage;
 
-type TcpRouteFrame =
-    TcpContextService<TcpBytesService<BytesFrameService<FrameRouteService<(), Error>>, ()>>;
+pub(crate) type TcpRouteFrame<S> = TcpContextService<
+    TcpBytesService<
+        BytesFrameService<
+            StorageTagService<
+                SaslAuthenticationService<
+                    AuthorizerService<
+                        S,
+                        ClientQuotaService<S, FeatureService<S, FrameRouteService<(), Error>>>,
+                    >,
+                >,
+            >,
+        >,
+        (),
+        ReceivedFrame,
+    >,
+>;
+
+#[allow(clippy::too_many_arguments)]
+pub fn services<C, S>(
+    cluster_id: &str,
+    maximum_frame_size: Option<usize>,
+    maximum_in_flight: Option<usize>,
+    incremental_decode: Option<usize>,
+    coordinator: C,
+    storage: S,
+    fetch: FetchService,
+    schema_registry: Option<Registry>,
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+    authorizer: Option<Arc<dyn Authorizer>>,
+) -> Result<TcpRouteFrame<S>, Error>
+where
+    S: Storage,
+    C: Coordinator,
+{
+    let authentication =
+        SaslAuthenticationLayer::new(credentials.is_some() || oauth_bearer.is_some());
+
+    let authorizer = AuthorizerLayer::new(storage.clone(), authorizer);
+    let quota = ClientQuotaLayer::new(storage.clone());
+    let feature = FeatureLayer::new(storage.clone());
+
+    routes(
+        coordinator,
+        storage,
+        fetch,
+        schema_registry,
+        credentials,
+        oauth_bearer,
+    )
+    .and_then(|builder| builder.build().map_err(Into::into))
+    .map(|route| {
+        (
+            TcpContextLayer::new(
+                TcpContext::default()
+                    .cluster_id(Some(cluster_id.into()))
+                    .maximum_frame_size(maximum_frame_size)
+                    .maximum_in_flight(maximum_in_flight)
+                    .incremental_decode(incremental_decode),
+            ),
+            TcpBytesLayer::<(), ReceivedFrame>::default(),
+            BytesFrameLayer,
+            StorageTagLayer,
+            authentication,
+            authorizer,
+            quota,
+            feature,
+        )
+            .into_layer(route)
+    })
+}
 
-pub fn services<C, S>(cluster_id: &str, coordinator: C, storage: S) -> Result<TcpRouteFrame, Error>
+/// All routes handled by the broker, prior to the implicit API versions route
+pub fn routes<C, S>(
+    coordinator: C,
+    storage: S,
+    fetch: FetchService,
+    schema_registry: Option<Registry>,
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
 where
     S: Storage,
     C: Coordinator,
 {
-    storage::services(FrameRouteService::<(), Error>::builder(), storage)
-        .inspect(|builder| debug!(?builder))
-        .and_then(|builder| {
-            coordinator::services(builder, coordinator).inspect(|builder| debug!(?builder))
-        })
-        .and_then(|builder| builder.build().map_err(Into::into))
-        .map(|route| {
-            (
-                TcpContextLayer::new(TcpContext::default().cluster_id(Some(cluster_id.into()))),
-                TcpBytesLayer::<()>::default(),
-                BytesFrameLayer,
-            )
-                .into_layer(route)
-        })
+    storage::services(
+        FrameRouteService::<(), Error>::builder(),
+        storage.clone(),
+        fetch,
+        schema_registry,
+    )
+    .inspect(|builder| debug!(?builder))
+    .and_then(|builder| {
+        coordinator::services(builder, coordinator).inspect(|builder| debug!(?builder))
+    })
+    .and_then(|builder| {
+        sasl::services(builder, storage, credentials, oauth_bearer)
+            .inspect(|builder| debug!(?builder))
+    })
 }
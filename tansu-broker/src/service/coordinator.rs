@@ -17,16 +17,19 @@ use rama::{
     layer::{MapErrLayer, MapStateLayer},
 };
 use tansu_sans_io::{
-    ApiKey as _, HeartbeatRequest, JoinGroupRequest, LeaveGroupRequest, OffsetCommitRequest,
-    OffsetFetchRequest, SyncGroupRequest,
+    ApiKey as _, ConsumerGroupHeartbeatRequest, HeartbeatRequest, JoinGroupRequest,
+    LeaveGroupRequest, OffsetCommitRequest, OffsetFetchRequest, ShareAcknowledgeRequest,
+    ShareFetchRequest, ShareGroupHeartbeatRequest, SyncGroupRequest,
 };
 use tansu_service::FrameRouteBuilder;
 
 use crate::{
     Error,
     broker::group::{
-        heartbeat::HeartbeatService, join::JoinGroupService, leave::LeaveGroupService,
-        offset_commit::OffsetCommitService, offset_fetch::OffsetFetchService,
+        consumer_group_heartbeat::ConsumerGroupHeartbeatService, heartbeat::HeartbeatService,
+        join::JoinGroupService, leave::LeaveGroupService, offset_commit::OffsetCommitService,
+        offset_fetch::OffsetFetchService, share_acknowledge::ShareAcknowledgeService,
+        share_fetch::ShareFetchService, share_group_heartbeat::ShareGroupHeartbeatService,
         sync::SyncGroupService,
     },
     coordinator::group::Coordinator,
@@ -40,11 +43,15 @@ where
     C: Coordinator,
 {
     [
+        consumer_group_heartbeat,
         heartbeat,
         join_group,
         leave_group,
         offset_commit,
         offset_fetch,
+        share_acknowledge,
+        share_fetch,
+        share_group_heartbeat,
         sync_group,
     ]
     .iter()
@@ -53,6 +60,26 @@ where
     })
 }
 
+pub fn consumer_group_heartbeat<C>(
+    builder: FrameRouteBuilder<(), Error>,
+    coordinator: C,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    C: Coordinator,
+{
+    builder
+        .with_route(
+            ConsumerGroupHeartbeatRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| coordinator),
+            )
+                .into_layer(ConsumerGroupHeartbeatService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn heartbeat<C>(
     builder: FrameRouteBuilder<(), Error>,
     coordinator: C,
@@ -153,6 +180,66 @@ where
         .map_err(Into::into)
 }
 
+pub fn share_acknowledge<C>(
+    builder: FrameRouteBuilder<(), Error>,
+    coordinator: C,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    C: Coordinator,
+{
+    builder
+        .with_route(
+            ShareAcknowledgeRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| coordinator),
+            )
+                .into_layer(ShareAcknowledgeService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn share_fetch<C>(
+    builder: FrameRouteBuilder<(), Error>,
+    coordinator: C,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    C: Coordinator,
+{
+    builder
+        .with_route(
+            ShareFetchRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| coordinator),
+            )
+                .into_layer(ShareFetchService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn share_group_heartbeat<C>(
+    builder: FrameRouteBuilder<(), Error>,
+    coordinator: C,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    C: Coordinator,
+{
+    builder
+        .with_route(
+            ShareGroupHeartbeatRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| coordinator),
+            )
+                .into_layer(ShareGroupHeartbeatService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn sync_group<C>(
     builder: FrameRouteBuilder<(), Error>,
     coordinator: C,
@@ -0,0 +1,93 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{
+    Layer as _, Service as _,
+    layer::{MapErrLayer, MapStateLayer},
+};
+use tansu_sans_io::{ApiKey as _, SaslAuthenticateRequest, SaslHandshakeRequest};
+use tansu_service::{FrameRequestLayer, FrameRouteBuilder};
+use tansu_storage::Storage;
+
+use crate::{
+    Error,
+    broker::{
+        oauth::OAuthBearer,
+        sasl::{Credentials, SaslAuthenticateService, SaslHandshakeService},
+    },
+};
+
+pub fn services<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    sasl_handshake(
+        builder,
+        storage.clone(),
+        credentials.clone(),
+        oauth_bearer.clone(),
+    )
+    .and_then(|builder| sasl_authenticate(builder, storage, credentials, oauth_bearer))
+}
+
+pub fn sasl_authenticate<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            SaslAuthenticateRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<SaslAuthenticateRequest>::new(),
+            )
+                .into_layer(SaslAuthenticateService::new(credentials).oauth_bearer(oauth_bearer))
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn sasl_handshake<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+    credentials: Option<Credentials>,
+    oauth_bearer: Option<OAuthBearer>,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            SaslHandshakeRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<SaslHandshakeRequest>::new(),
+            )
+                .into_layer(SaslHandshakeService::new(credentials).oauth_bearer(oauth_bearer))
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
@@ -17,29 +17,59 @@ use rama::{
     layer::{MapErrLayer, MapStateLayer},
 };
 use tansu_sans_io::{
-    AddOffsetsToTxnRequest, AddPartitionsToTxnRequest, ApiKey as _, ConsumerGroupDescribeRequest,
-    CreateTopicsRequest, DeleteGroupsRequest, DeleteRecordsRequest, DeleteTopicsRequest,
-    DescribeClusterRequest, DescribeConfigsRequest, DescribeGroupsRequest,
-    DescribeTopicPartitionsRequest, FetchRequest, FindCoordinatorRequest,
+    AddOffsetsToTxnRequest, AddPartitionsToTxnRequest, AlterClientQuotasRequest,
+    AlterUserScramCredentialsRequest, ApiKey as _, ConsumerGroupDescribeRequest, CreateAclsRequest,
+    CreatePartitionsRequest, CreateTopicsRequest, DeleteAclsRequest, DeleteGroupsRequest,
+    DeleteRecordsRequest, DeleteTopicsRequest, DescribeAclsRequest, DescribeClientQuotasRequest,
+    DescribeClusterRequest, DescribeConfigsRequest, DescribeGroupsRequest, DescribeLogDirsRequest,
+    DescribeQuorumRequest, DescribeTopicPartitionsRequest, DescribeTransactionsRequest,
+    DescribeUserScramCredentialsRequest, ElectLeadersRequest, FetchRequest, FindCoordinatorRequest,
     GetTelemetrySubscriptionsRequest, IncrementalAlterConfigsRequest, InitProducerIdRequest,
-    ListGroupsRequest, ListOffsetsRequest, ListPartitionReassignmentsRequest, MetadataRequest,
-    ProduceRequest, TxnOffsetCommitRequest,
+    ListGroupsRequest, ListOffsetsRequest, ListPartitionReassignmentsRequest,
+    ListTransactionsRequest, MetadataRequest, ProduceRequest, TxnOffsetCommitRequest,
+    UpdateFeaturesRequest,
 };
+use tansu_schema::Registry;
 use tansu_service::{FrameRequestLayer, FrameRouteBuilder};
 use tansu_storage::{
-    ConsumerGroupDescribeService, CreateTopicsService, DeleteGroupsService, DeleteRecordsService,
-    DeleteTopicsService, DescribeClusterService, DescribeConfigsService, DescribeGroupsService,
-    DescribeTopicPartitionsService, FetchService, FindCoordinatorService,
+    AlterClientQuotasService, AlterUserScramCredentialsService, ConsumerGroupDescribeService,
+    CreateAclsService, CreatePartitionsService, CreateTopicsService, DeleteAclsService,
+    DeleteGroupsService, DeleteRecordsService, DeleteTopicsService, DescribeAclsService,
+    DescribeClientQuotasService, DescribeClusterService, DescribeConfigsService,
+    DescribeGroupsService, DescribeLogDirsService, DescribeQuorumService,
+    DescribeTopicPartitionsService, DescribeTransactionsService,
+    DescribeUserScramCredentialsService, ElectLeadersService, FetchService, FindCoordinatorService,
     GetTelemetrySubscriptionsService, IncrementalAlterConfigsService, InitProducerIdService,
-    ListGroupsService, ListOffsetsService, ListPartitionReassignmentsService, MetadataService,
-    ProduceService, Storage, TxnAddOffsetsService, TxnAddPartitionService, TxnOffsetCommitService,
+    ListGroupsService, ListOffsetsService, ListPartitionReassignmentsService,
+    ListTransactionsService, MetadataService, ProduceService, Storage, TxnAddOffsetsService,
+    TxnAddPartitionService, TxnOffsetCommitService, UpdateFeaturesService,
 };
 
-use crate::Error;
+use crate::{
+    Error,
+    broker::{
+        audit::FetchAuditLayer,
+        chaos::ChaosLayer,
+        claim_check::ClaimCheckLayer,
+        latency::{FetchLatencyLayer, ReceivedAtLayer},
+        lineage::LineageLayer,
+        link::LinkedTopicLayer,
+        logger::BrokerLoggerLayer,
+        message_size::MessageSizeLayer,
+        read_only::ReadOnlyTopicLayer,
+        recompress::RecompressLayer,
+        replay::ReplayLayer,
+        sequence::GlobalSequenceLayer,
+        throttle::ProduceThrottleLayer,
+        topic_config::TopicConfigLayer,
+    },
+};
 
 pub fn services<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
+    fetch_service: FetchService,
+    schema_registry: Option<Registry>,
 ) -> Result<FrameRouteBuilder<(), Error>, Error>
 where
     S: Storage,
@@ -47,16 +77,27 @@ where
     [
         add_offsets_to_txn,
         add_partitions_to_txn,
+        alter_client_quotas,
+        alter_user_scram_credentials,
         consumer_group_describe,
+        create_acls,
+        create_partitions,
         create_topics,
+        delete_acls,
         delete_groups,
         delete_records,
         delete_topics,
+        describe_acls,
+        describe_client_quotas,
         describe_cluster,
         describe_configs,
         describe_groups,
+        describe_log_dirs,
+        describe_quorum,
         describe_topic_partitions,
-        fetch,
+        describe_transactions,
+        describe_user_scram_credentials,
+        elect_leaders,
         find_coordinator,
         get_telemetry_subscriptions,
         incremental_alter_configs,
@@ -64,14 +105,59 @@ where
         list_groups,
         list_offsets,
         list_partition_reassignments,
+        list_transactions,
         metadata,
         produce,
         txn_offset_commit_request,
+        update_features,
     ]
     .iter()
     .try_fold(builder, |builder, service| {
         service(builder, storage.clone())
     })
+    .and_then(|builder| fetch(builder, storage, fetch_service, schema_registry))
+}
+
+pub fn alter_client_quotas<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            AlterClientQuotasRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<AlterClientQuotasRequest>::new(),
+            )
+                .into_layer(AlterClientQuotasService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn alter_user_scram_credentials<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            AlterUserScramCredentialsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<AlterUserScramCredentialsRequest>::new(),
+            )
+                .into_layer(AlterUserScramCredentialsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
 }
 
 pub fn consumer_group_describe<S>(
@@ -95,6 +181,48 @@ where
         .map_err(Into::into)
 }
 
+pub fn create_acls<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            CreateAclsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<CreateAclsRequest>::new(),
+            )
+                .into_layer(CreateAclsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn create_partitions<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            CreatePartitionsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<CreatePartitionsRequest>::new(),
+            )
+                .into_layer(CreatePartitionsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn create_topics<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
@@ -116,6 +244,27 @@ where
         .map_err(Into::into)
 }
 
+pub fn delete_acls<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DeleteAclsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DeleteAclsRequest>::new(),
+            )
+                .into_layer(DeleteAclsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn delete_groups<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
@@ -179,6 +328,48 @@ where
         .map_err(Into::into)
 }
 
+pub fn describe_acls<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DescribeAclsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DescribeAclsRequest>::new(),
+            )
+                .into_layer(DescribeAclsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn describe_client_quotas<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DescribeClientQuotasRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DescribeClientQuotasRequest>::new(),
+            )
+                .into_layer(DescribeClientQuotasService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn describe_cluster<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
@@ -242,6 +433,48 @@ where
         .map_err(Into::into)
 }
 
+pub fn describe_log_dirs<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DescribeLogDirsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DescribeLogDirsRequest>::new(),
+            )
+                .into_layer(DescribeLogDirsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn describe_quorum<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DescribeQuorumRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DescribeQuorumRequest>::new(),
+            )
+                .into_layer(DescribeQuorumService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn describe_topic_partitions<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
@@ -263,9 +496,74 @@ where
         .map_err(Into::into)
 }
 
+pub fn describe_transactions<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DescribeTransactionsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DescribeTransactionsRequest>::new(),
+            )
+                .into_layer(DescribeTransactionsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn describe_user_scram_credentials<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            DescribeUserScramCredentialsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<DescribeUserScramCredentialsRequest>::new(),
+            )
+                .into_layer(DescribeUserScramCredentialsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
+pub fn elect_leaders<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            ElectLeadersRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<ElectLeadersRequest>::new(),
+            )
+                .into_layer(ElectLeadersService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn fetch<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
+    fetch_service: FetchService,
+    schema_registry: Option<Registry>,
 ) -> Result<FrameRouteBuilder<(), Error>, Error>
 where
     S: Storage,
@@ -277,8 +575,12 @@ where
                 MapErrLayer::new(Error::from),
                 MapStateLayer::new(|_| storage),
                 FrameRequestLayer::<FetchRequest>::new(),
+                ChaosLayer,
+                FetchAuditLayer::new(schema_registry),
+                ClaimCheckLayer::default(),
+                FetchLatencyLayer,
             )
-                .into_layer(FetchService)
+                .into_layer(fetch_service)
                 .boxed(),
         )
         .map_err(Into::into)
@@ -319,6 +621,7 @@ where
                 MapErrLayer::new(Error::from),
                 MapStateLayer::new(|_| storage),
                 FrameRequestLayer::<IncrementalAlterConfigsRequest>::new(),
+                BrokerLoggerLayer,
             )
                 .into_layer(IncrementalAlterConfigsService)
                 .boxed(),
@@ -410,6 +713,27 @@ where
         .map_err(Into::into)
 }
 
+pub fn list_transactions<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            ListTransactionsRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<ListTransactionsRequest>::new(),
+            )
+                .into_layer(ListTransactionsService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
+
 pub fn metadata<S>(
     builder: FrameRouteBuilder<(), Error>,
     storage: S,
@@ -445,6 +769,18 @@ where
                 MapErrLayer::new(Error::from),
                 MapStateLayer::new(|_| storage),
                 FrameRequestLayer::<ProduceRequest>::new(),
+                ReceivedAtLayer,
+                ChaosLayer,
+                TopicConfigLayer,
+                LineageLayer,
+                ReplayLayer,
+                ReadOnlyTopicLayer,
+                LinkedTopicLayer,
+                ClaimCheckLayer::default(),
+                MessageSizeLayer,
+                ProduceThrottleLayer::default(),
+                RecompressLayer,
+                GlobalSequenceLayer::default(),
             )
                 .into_layer(ProduceService)
                 .boxed(),
@@ -535,3 +871,24 @@ where
         )
         .map_err(Into::into)
 }
+
+pub fn update_features<S>(
+    builder: FrameRouteBuilder<(), Error>,
+    storage: S,
+) -> Result<FrameRouteBuilder<(), Error>, Error>
+where
+    S: Storage,
+{
+    builder
+        .with_route(
+            UpdateFeaturesRequest::KEY,
+            (
+                MapErrLayer::new(Error::from),
+                MapStateLayer::new(|_| storage),
+                FrameRequestLayer::<UpdateFeaturesRequest>::new(),
+            )
+                .into_layer(UpdateFeaturesService)
+                .boxed(),
+        )
+        .map_err(Into::into)
+}
@@ -0,0 +1,49 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use tansu_broker::{Result, coverage};
+use tansu_sans_io::{ApiKey as _, ApiVersionsRequest, FetchRequest, ProduceRequest};
+
+#[tokio::test]
+async fn broker() -> Result<()> {
+    let coverage = coverage::broker().await?;
+
+    assert!(
+        coverage
+            .windows(2)
+            .all(|pair| pair[0].api_key < pair[1].api_key)
+    );
+
+    for api_key in [
+        ApiVersionsRequest::KEY,
+        FetchRequest::KEY,
+        ProduceRequest::KEY,
+    ] {
+        let api = coverage
+            .iter()
+            .find(|api| api.api_key == api_key)
+            .expect("api key");
+
+        assert!(api.supported, "{api:?}");
+        assert!(api.min_version <= api.max_version, "{api:?}");
+    }
+
+    let produce = coverage
+        .iter()
+        .find(|api| api.api_key == ProduceRequest::KEY)
+        .expect("produce");
+    assert_eq!("Produce", produce.name);
+
+    Ok(())
+}
@@ -0,0 +1,266 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use common::{alphanumeric_string, register_broker};
+use rama::{Context, Service};
+use rand::{prelude::*, rng};
+use tansu_broker::Result;
+use tansu_sans_io::{
+    CreatePartitionsRequest, ErrorCode,
+    create_partitions_request::CreatePartitionsTopic,
+    create_topics_request::CreatableTopic,
+    record::{Record, inflated},
+};
+use tansu_storage::{CreatePartitionsService, Storage, StorageContainer, TopicId, Topition};
+use tracing::debug;
+use uuid::Uuid;
+
+pub mod common;
+
+async fn partitions(sc: &StorageContainer, topic_name: &str) -> Result<usize> {
+    sc.metadata(Some(&[TopicId::Name(topic_name.into())]))
+        .await
+        .map(|metadata| {
+            metadata
+                .topics()
+                .iter()
+                .find(|topic| topic.name.as_deref() == Some(topic_name))
+                .and_then(|topic| topic.partitions.as_ref())
+                .map_or(0, |partitions| partitions.len())
+        })
+        .map_err(Into::into)
+}
+
+pub async fn grow<C>(cluster_id: C, broker_id: i32, sc: StorageContainer) -> Result<()>
+where
+    C: Into<String>,
+{
+    register_broker(cluster_id, broker_id, &sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 1;
+    let replication_factor = 0;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic::default()
+                .name(topic_name.clone())
+                .num_partitions(num_partitions)
+                .replication_factor(replication_factor)
+                .assignments(Some([].into()))
+                .configs(Some([].into())),
+            false,
+        )
+        .await?;
+
+    let create_partitions = |count, validate_only| {
+        CreatePartitionsRequest::default()
+            .topics(Some(
+                [CreatePartitionsTopic::default()
+                    .name(topic_name.clone())
+                    .count(count)
+                    .assignments(None)]
+                .into(),
+            ))
+            .timeout_ms(5_000)
+            .validate_only(validate_only)
+    };
+
+    let response = CreatePartitionsService
+        .serve(Context::with_state(sc.clone()), create_partitions(3, true))
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(1, results.len());
+    assert_eq!(i16::from(ErrorCode::None), results[0].error_code);
+    assert_eq!(1, partitions(&sc, &topic_name).await?);
+
+    let response = CreatePartitionsService
+        .serve(Context::with_state(sc.clone()), create_partitions(3, false))
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(1, results.len());
+    assert_eq!(i16::from(ErrorCode::None), results[0].error_code);
+    assert_eq!(3, partitions(&sc, &topic_name).await?);
+
+    let batch = inflated::Batch::builder()
+        .record(Record::builder().value(Some(Bytes::from_static(b"pqrst"))))
+        .build()
+        .and_then(TryInto::try_into)
+        .inspect(|deflated| debug!(?deflated))?;
+
+    assert_eq!(
+        0,
+        sc.produce(None, &Topition::new(topic_name.clone(), 2), batch)
+            .await?
+    );
+
+    assert_eq!(
+        ErrorCode::InvalidPartitions,
+        sc.add_partitions(&topic_name, 2).await?
+    );
+
+    assert_eq!(
+        ErrorCode::InvalidPartitions,
+        sc.add_partitions(&topic_name, 3).await?
+    );
+
+    assert_eq!(
+        ErrorCode::UnknownTopicOrPartition,
+        sc.add_partitions(&alphanumeric_string(15), 3).await?
+    );
+
+    assert_eq!(3, partitions(&sc, &topic_name).await?);
+
+    Ok(())
+}
+
+mod pg {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::Postgres,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn grow() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::grow(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "dynostore")]
+mod in_memory {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::InMemory,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn grow() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::grow(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "libsql")]
+mod lite {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::Lite,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn grow() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::grow(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "slatedb")]
+mod slatedb {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::SlateDb,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn grow() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::grow(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
@@ -98,8 +98,8 @@ where
                         .name(cleanup_policy.into())
                         .value(Some(compact.into()))
                         .read_only(false)
-                        .is_default(None)
-                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                        .is_default(Some(false))
+                        .config_source(Some(ConfigSource::DynamicTopicConfig.into()))
                         .is_sensitive(false)
                         .synonyms(Some([].into()))
                         .config_type(Some(ConfigResource::Topic.into()))
@@ -223,8 +223,8 @@ pub async fn alter_single_topic(
                         .name(cleanup_policy.into())
                         .value(Some(compact.into()))
                         .read_only(false)
-                        .is_default(None)
-                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                        .is_default(Some(false))
+                        .config_source(Some(ConfigSource::DynamicTopicConfig.into()))
                         .is_sensitive(false)
                         .synonyms(Some([].into()))
                         .config_type(Some(ConfigResource::Topic.into()))
@@ -281,8 +281,8 @@ pub async fn alter_single_topic(
                         .name(cleanup_policy.into())
                         .value(Some(delete.into()))
                         .read_only(false)
-                        .is_default(None)
-                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                        .is_default(Some(false))
+                        .config_source(Some(ConfigSource::DynamicTopicConfig.into()))
                         .is_sensitive(false)
                         .synonyms(Some([].into()))
                         .config_type(Some(ConfigResource::Topic.into()))
@@ -0,0 +1,245 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use common::{alphanumeric_string, register_broker};
+use rama::{Context, Service};
+use rand::{prelude::*, rng};
+use tansu_broker::Result;
+use tansu_sans_io::{
+    DescribeLogDirsRequest, ErrorCode,
+    create_topics_request::CreatableTopic,
+    describe_log_dirs_request::DescribableLogDirTopic,
+    describe_log_dirs_response::{DescribeLogDirsPartition, DescribeLogDirsTopic},
+    record::{Record, inflated},
+};
+use tansu_storage::{DescribeLogDirsService, Storage, StorageContainer, Topition};
+use tracing::debug;
+use uuid::Uuid;
+
+pub mod common;
+
+pub async fn partition_size<C>(cluster_id: C, broker_id: i32, sc: StorageContainer) -> Result<()>
+where
+    C: Into<String>,
+{
+    register_broker(cluster_id, broker_id, &sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 3;
+    let replication_factor = 0;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic::default()
+                .name(topic_name.clone())
+                .num_partitions(num_partitions)
+                .replication_factor(replication_factor)
+                .assignments(Some([].into()))
+                .configs(Some([].into())),
+            false,
+        )
+        .await?;
+
+    const KEY: Bytes = Bytes::from_static(b"abc");
+    const VALUE: Bytes = Bytes::from_static(b"pqrst");
+
+    let partition = 1;
+    let topition = Topition::new(topic_name.clone(), partition);
+
+    let batch = inflated::Batch::builder()
+        .record(Record::builder().key(Some(KEY)).value(Some(VALUE)))
+        .build()
+        .and_then(TryInto::try_into)
+        .inspect(|deflated| debug!(?deflated))?;
+
+    assert_eq!(0, sc.produce(None, &topition, batch).await?);
+
+    let ctx = Context::with_state(sc);
+
+    let response = DescribeLogDirsService
+        .serve(
+            ctx,
+            DescribeLogDirsRequest::default().topics(Some(
+                [DescribableLogDirTopic::default()
+                    .topic(topic_name.clone())
+                    .partitions(Some([0, partition].into()))]
+                .into(),
+            )),
+        )
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(1, results.len());
+    assert_eq!(i16::from(ErrorCode::None), results[0].error_code);
+
+    assert_eq!(
+        Some(vec![
+            DescribeLogDirsTopic::default()
+                .name(topic_name)
+                .partitions(Some(vec![
+                    DescribeLogDirsPartition::default()
+                        .partition_index(0)
+                        .partition_size(0)
+                        .offset_lag(0)
+                        .is_future_key(false),
+                    DescribeLogDirsPartition::default()
+                        .partition_index(partition)
+                        .partition_size((KEY.len() + VALUE.len()) as i64)
+                        .offset_lag(0)
+                        .is_future_key(false),
+                ]))
+        ]),
+        results[0].topics
+    );
+
+    Ok(())
+}
+
+mod pg {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::Postgres,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn partition_size() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::partition_size(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "dynostore")]
+mod in_memory {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::InMemory,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn partition_size() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::partition_size(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "libsql")]
+mod lite {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::Lite,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn partition_size() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::partition_size(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "slatedb")]
+mod slatedb {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::SlateDb,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn partition_size() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::partition_size(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
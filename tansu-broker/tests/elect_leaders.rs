@@ -0,0 +1,260 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::{alphanumeric_string, register_broker};
+use rama::{Context, Service};
+use rand::{prelude::*, rng};
+use tansu_broker::Result;
+use tansu_sans_io::{
+    ElectLeadersRequest, ErrorCode, create_topics_request::CreatableTopic,
+    elect_leaders_request::TopicPartitions,
+};
+use tansu_storage::{ElectLeadersService, Storage, StorageContainer};
+use tracing::debug;
+use uuid::Uuid;
+
+pub mod common;
+
+pub async fn preferred_leader<C>(cluster_id: C, broker_id: i32, sc: StorageContainer) -> Result<()>
+where
+    C: Into<String>,
+{
+    register_broker(cluster_id, broker_id, &sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 3;
+    let replication_factor = 0;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic::default()
+                .name(topic_name.clone())
+                .num_partitions(num_partitions)
+                .replication_factor(replication_factor)
+                .assignments(Some([].into()))
+                .configs(Some([].into())),
+            false,
+        )
+        .await?;
+
+    let unknown_topic = alphanumeric_string(15);
+
+    let response = ElectLeadersService
+        .serve(
+            Context::with_state(sc.clone()),
+            ElectLeadersRequest::default()
+                .election_type(Some(0))
+                .topic_partitions(Some(
+                    [
+                        TopicPartitions::default()
+                            .topic(topic_name.clone())
+                            .partitions(Some([0, 2, num_partitions].into())),
+                        TopicPartitions::default()
+                            .topic(unknown_topic.clone())
+                            .partitions(Some([0].into())),
+                    ]
+                    .into(),
+                ))
+                .timeout_ms(5_000),
+        )
+        .await?;
+
+    assert_eq!(Some(i16::from(ErrorCode::None)), response.error_code);
+
+    let results = response.replica_election_results.unwrap_or_default();
+    assert_eq!(2, results.len());
+
+    assert_eq!(topic_name, results[0].topic);
+    assert_eq!(
+        vec![
+            (0, i16::from(ErrorCode::ElectionNotNeeded)),
+            (2, i16::from(ErrorCode::ElectionNotNeeded)),
+            (
+                num_partitions,
+                i16::from(ErrorCode::UnknownTopicOrPartition)
+            ),
+        ],
+        results[0]
+            .partition_result
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|partition| (partition.partition_id, partition.error_code))
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(unknown_topic, results[1].topic);
+    assert_eq!(
+        vec![(0, i16::from(ErrorCode::UnknownTopicOrPartition))],
+        results[1]
+            .partition_result
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|partition| (partition.partition_id, partition.error_code))
+            .collect::<Vec<_>>()
+    );
+
+    let response = ElectLeadersService
+        .serve(
+            Context::with_state(sc),
+            ElectLeadersRequest::default()
+                .election_type(Some(0))
+                .topic_partitions(None)
+                .timeout_ms(5_000),
+        )
+        .await?;
+
+    assert_eq!(Some(i16::from(ErrorCode::None)), response.error_code);
+    assert_eq!(Some(vec![]), response.replica_election_results);
+
+    Ok(())
+}
+
+mod pg {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::Postgres,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn preferred_leader() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::preferred_leader(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "dynostore")]
+mod in_memory {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::InMemory,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn preferred_leader() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::preferred_leader(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "libsql")]
+mod lite {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::Lite,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn preferred_leader() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::preferred_leader(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "slatedb")]
+mod slatedb {
+    use common::{StorageType, init_tracing};
+    use url::Url;
+
+    use super::*;
+
+    async fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        common::storage_container(
+            StorageType::SlateDb,
+            cluster,
+            node,
+            Url::parse("tcp://127.0.0.1/")?,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn preferred_leader() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::preferred_leader(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id).await?,
+        )
+        .await
+    }
+}
@@ -84,7 +84,7 @@ where
 
     let ctx = Context::with_state(sc);
 
-    let fetch = FetchService
+    let fetch = FetchService::default()
         .serve(
             ctx,
             FetchRequest::default()
@@ -263,7 +263,7 @@ where
 
     let ctx = Context::with_state(sc);
 
-    let fetch = FetchService
+    let fetch = FetchService::default()
         .serve(
             ctx,
             FetchRequest::default()
@@ -32,7 +32,7 @@ use tansu_service::{
     BytesFrameLayer, BytesFrameService, BytesLayer, BytesService, FrameBytesLayer,
     FrameBytesService, FrameRouteService, RequestFrameLayer, RequestFrameService,
 };
-use tansu_storage::{Storage, StorageContainer, Topition};
+use tansu_storage::{FetchService, Storage, StorageContainer, Topition};
 use tokio::time::sleep;
 use tracing::debug;
 use url::Url;
@@ -48,18 +48,23 @@ fn broker<S>(storage: S) -> Result<Broker>
 where
     S: Storage,
 {
-    storage::services(FrameRouteService::<(), Error>::builder(), storage)
-        .inspect(|builder| debug!(?builder))
-        .and_then(|builder| builder.build().map_err(Into::into))
-        .map(|frame_route| {
-            (
-                RequestFrameLayer,
-                FrameBytesLayer,
-                BytesLayer,
-                BytesFrameLayer,
-            )
-                .into_layer(frame_route)
-        })
+    storage::services(
+        FrameRouteService::<(), Error>::builder(),
+        storage,
+        FetchService::default(),
+        None,
+    )
+    .inspect(|builder| debug!(?builder))
+    .and_then(|builder| builder.build().map_err(Into::into))
+    .map(|frame_route| {
+        (
+            RequestFrameLayer,
+            FrameBytesLayer,
+            BytesLayer,
+            BytesFrameLayer,
+        )
+            .into_layer(frame_route)
+    })
 }
 
 pub async fn multiple_record(broker: Broker) -> Result<()> {
@@ -0,0 +1,112 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "postgres")]
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker, storage_container};
+
+#[cfg(feature = "postgres")]
+use rama::{Context, Service as _};
+
+#[cfg(feature = "postgres")]
+use rand::{prelude::*, rng};
+
+#[cfg(feature = "postgres")]
+use tansu_broker::{
+    Result,
+    coordinator::group::{Coordinator as _, administrator::Controller, lease::Leased},
+};
+
+#[cfg(feature = "postgres")]
+use tansu_sans_io::{ErrorCode, FindCoordinatorRequest, HeartbeatResponse};
+
+#[cfg(feature = "postgres")]
+use tansu_storage::{FindCoordinatorService, Storage as _};
+
+#[cfg(feature = "postgres")]
+use tracing::debug;
+
+#[cfg(feature = "postgres")]
+use url::Url;
+
+#[cfg(feature = "postgres")]
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+#[cfg(feature = "postgres")]
+async fn group_coordinator_is_lease_holder() -> Result<()> {
+    let _guard = init_tracing()?;
+
+    let mut rng = rng();
+
+    let cluster_id = Uuid::now_v7();
+    let holder_id = rng.random_range(0..i32::MAX / 2);
+    let other_id = holder_id + 1;
+
+    let holder = storage_container(
+        StorageType::Postgres,
+        cluster_id,
+        holder_id,
+        Url::parse("tcp://127.0.0.1:9092/")?,
+        None,
+    )
+    .await?;
+    register_broker(cluster_id, holder_id, &holder).await?;
+
+    let other = storage_container(
+        StorageType::Postgres,
+        cluster_id,
+        other_id,
+        Url::parse("tcp://127.0.0.1:9093/")?,
+        None,
+    )
+    .await?;
+    register_broker(cluster_id, other_id, &other).await?;
+
+    let group_id: String = alphanumeric_string(15);
+    debug!(?group_id);
+
+    let request = FindCoordinatorRequest::default()
+        .key_type(Some(0))
+        .coordinator_keys(Some([group_id.clone()].into()));
+
+    for sc in [&holder, &other] {
+        let response = FindCoordinatorService
+            .serve(Context::with_state(sc.clone()), request.clone())
+            .await?;
+
+        let coordinators = response.coordinators.unwrap_or_default();
+        assert_eq!(1, coordinators.len());
+        assert_eq!(group_id, coordinators[0].key);
+        assert_eq!(holder_id, coordinators[0].node_id);
+        assert_eq!(9092, coordinators[0].port);
+        assert_eq!(i16::from(ErrorCode::None), coordinators[0].error_code);
+    }
+
+    let lease = holder.coordinator_lease(0, &group_id).await?;
+    assert_eq!(holder_id, lease.node_id);
+    assert_eq!(0, lease.epoch);
+
+    let mut fenced = Leased::new(Controller::with_storage(other.clone())?, other);
+
+    let heartbeat = fenced
+        .heartbeat(&group_id, 0, &alphanumeric_string(10), None)
+        .await
+        .and_then(|body| HeartbeatResponse::try_from(body).map_err(Into::into))?;
+
+    assert_eq!(i16::from(ErrorCode::NotCoordinator), heartbeat.error_code);
+
+    Ok(())
+}
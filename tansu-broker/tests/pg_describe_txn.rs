@@ -0,0 +1,176 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "postgres")]
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker, storage_container};
+
+#[cfg(feature = "postgres")]
+use rama::{Context, Service as _};
+
+#[cfg(feature = "postgres")]
+use rand::{prelude::*, rng};
+
+#[cfg(feature = "postgres")]
+use tansu_broker::Result;
+
+#[cfg(feature = "postgres")]
+use tansu_sans_io::{
+    DescribeTransactionsRequest, ErrorCode, ListTransactionsRequest,
+    add_partitions_to_txn_request::AddPartitionsToTxnTopic, create_topics_request::CreatableTopic,
+};
+
+#[cfg(feature = "postgres")]
+use tansu_storage::{
+    DescribeTransactionsService, ListTransactionsService, Storage as _, TxnAddPartitionsRequest,
+};
+
+#[cfg(feature = "postgres")]
+use tracing::debug;
+
+#[cfg(feature = "postgres")]
+use url::Url;
+
+#[cfg(feature = "postgres")]
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+#[cfg(feature = "postgres")]
+async fn describe_and_list() -> Result<()> {
+    let _guard = init_tracing()?;
+
+    let cluster_id = Uuid::now_v7();
+    let broker_id = rng().random_range(0..i32::MAX);
+
+    let sc = storage_container(
+        StorageType::Postgres,
+        cluster_id,
+        broker_id,
+        Url::parse("tcp://127.0.0.1/")?,
+        None,
+    )
+    .await?;
+
+    register_broker(cluster_id, broker_id, &sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic::default()
+                .name(topic_name.clone())
+                .num_partitions(3)
+                .replication_factor(0)
+                .assignments(Some([].into()))
+                .configs(Some([].into())),
+            false,
+        )
+        .await?;
+
+    let transaction_timeout_ms = 10_000;
+
+    let ongoing: String = alphanumeric_string(10);
+    let producer = sc
+        .init_producer(Some(&ongoing), transaction_timeout_ms, Some(-1), Some(-1))
+        .await?;
+
+    _ = sc
+        .txn_add_partitions(TxnAddPartitionsRequest::VersionZeroToThree {
+            transaction_id: ongoing.clone(),
+            producer_id: producer.id,
+            producer_epoch: producer.epoch,
+            topics: [AddPartitionsToTxnTopic::default()
+                .name(topic_name.clone())
+                .partitions(Some([0, 2].into()))]
+            .into(),
+        })
+        .await?;
+
+    let empty: String = alphanumeric_string(10);
+    let empty_producer = sc
+        .init_producer(Some(&empty), transaction_timeout_ms, Some(-1), Some(-1))
+        .await?;
+
+    let unknown: String = alphanumeric_string(10);
+
+    let response = DescribeTransactionsService
+        .serve(
+            Context::with_state(sc.clone()),
+            DescribeTransactionsRequest::default().transactional_ids(Some(
+                [ongoing.clone(), empty.clone(), unknown.clone()].into(),
+            )),
+        )
+        .await?;
+
+    let states = response.transaction_states.unwrap_or_default();
+    assert_eq!(3, states.len());
+
+    assert_eq!(ongoing, states[0].transactional_id);
+    assert_eq!(i16::from(ErrorCode::None), states[0].error_code);
+    assert_eq!("Ongoing", states[0].transaction_state);
+    assert_eq!(transaction_timeout_ms, states[0].transaction_timeout_ms);
+    assert!(states[0].transaction_start_time_ms > 0);
+    assert_eq!(producer.id, states[0].producer_id);
+    assert_eq!(producer.epoch, states[0].producer_epoch);
+    assert_eq!(
+        vec![(topic_name.clone(), Some(vec![0, 2]))],
+        states[0]
+            .topics
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|topic| (topic.topic, topic.partitions))
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(empty, states[1].transactional_id);
+    assert_eq!(i16::from(ErrorCode::None), states[1].error_code);
+    assert_eq!("Empty", states[1].transaction_state);
+    assert_eq!(-1, states[1].transaction_start_time_ms);
+
+    assert_eq!(unknown, states[2].transactional_id);
+    assert_eq!(
+        i16::from(ErrorCode::TransactionalIdNotFound),
+        states[2].error_code
+    );
+
+    let response = ListTransactionsService
+        .serve(
+            Context::with_state(sc),
+            ListTransactionsRequest::default()
+                .state_filters(Some(["Ongoing".into(), "Unknown".into()].into()))
+                .producer_id_filters(Some([producer.id, empty_producer.id].into())),
+        )
+        .await?;
+
+    assert_eq!(i16::from(ErrorCode::None), response.error_code);
+    assert_eq!(Some(vec!["Unknown".into()]), response.unknown_state_filters);
+    assert_eq!(
+        vec![(ongoing, producer.id, "Ongoing".to_owned())],
+        response
+            .transaction_states
+            .unwrap_or_default()
+            .into_iter()
+            .map(|state| (
+                state.transactional_id,
+                state.producer_id,
+                state.transaction_state
+            ))
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
@@ -32,7 +32,7 @@ use tansu_service::{
     BytesFrameLayer, BytesFrameService, BytesLayer, BytesService, FrameBytesLayer,
     FrameBytesService, FrameRouteService, RequestFrameLayer, RequestFrameService,
 };
-use tansu_storage::{Storage, StorageContainer};
+use tansu_storage::{FetchService, Storage, StorageContainer};
 use tracing::debug;
 use url::Url;
 use uuid::Uuid;
@@ -42,6 +42,7 @@ pub mod common;
 const CLEANUP_POLICY: &str = "cleanup.policy";
 const COMPACT: &str = "compact";
 const DELETE: &str = "delete";
+const RETENTION_BYTES: &str = "retention.bytes";
 const RETENTION_MS: &str = "retention.ms";
 
 type Broker = RequestFrameService<
@@ -52,18 +53,23 @@ fn broker<S>(storage: S) -> Result<Broker>
 where
     S: Storage,
 {
-    storage::services(FrameRouteService::<(), Error>::builder(), storage)
-        .inspect(|builder| debug!(?builder))
-        .and_then(|builder| builder.build().map_err(Into::into))
-        .map(|frame_route| {
-            (
-                RequestFrameLayer,
-                FrameBytesLayer,
-                BytesLayer,
-                BytesFrameLayer,
-            )
-                .into_layer(frame_route)
-        })
+    storage::services(
+        FrameRouteService::<(), Error>::builder(),
+        storage,
+        FetchService::default(),
+        None,
+    )
+    .inspect(|builder| debug!(?builder))
+    .and_then(|builder| builder.build().map_err(Into::into))
+    .map(|frame_route| {
+        (
+            RequestFrameLayer,
+            FrameBytesLayer,
+            BytesLayer,
+            BytesFrameLayer,
+        )
+            .into_layer(frame_route)
+    })
 }
 
 pub async fn compact_only(sc: StorageContainer) -> Result<()> {
@@ -334,9 +340,31 @@ pub async fn compact_only(sc: StorageContainer) -> Result<()> {
         assert_eq!(Some(-1), partition.timestamp);
     }
 
+    debug!(phase = "pre: usage");
+    let usage = sc.topition_usage(Some(&[topic_name.into()])).await?;
+    assert_eq!(num_partitions as usize, usage.len());
+    assert_eq!(partition, usage[0].topition.partition());
+    assert_eq!(3, usage[0].usage.records);
+    assert_eq!(
+        (KEY.len() * 3 + ONE.len() + TWO.len() + THREE.len()) as i64,
+        usage[0].usage.bytes
+    );
+
     debug!(phase = "maintenance");
     sc.maintain(SystemTime::now()).await?;
 
+    debug!(phase = "post: usage");
+    let usage = sc.topition_usage(Some(&[topic_name.into()])).await?;
+    assert_eq!(num_partitions as usize, usage.len());
+    assert_eq!(1, usage[0].usage.records);
+    assert_eq!((KEY.len() + THREE.len()) as i64, usage[0].usage.bytes);
+    assert!(usage[0].usage.oldest_timestamp.is_some());
+
+    for usage in usage[1..].iter() {
+        assert_eq!(0, usage.usage.records);
+        assert_eq!(0, usage.usage.bytes);
+    }
+
     debug!(phase = "post: uncommitted earliest offset");
     let timestamp = ListOffset::Earliest.try_into()?;
 
@@ -1739,6 +1767,122 @@ pub async fn delete_no_retention_ms_only(sc: StorageContainer) -> Result<()> {
     Ok(())
 }
 
+pub async fn delete_retention_bytes(sc: StorageContainer) -> Result<()> {
+    let broker = broker(sc.clone())?;
+
+    let topic_name = &alphanumeric_string(15)[..];
+    debug!(?topic_name);
+
+    let timeout = 5_000;
+    let partition = 0;
+
+    let response = broker
+        .serve(
+            Context::default(),
+            CreateTopicsRequest::default()
+                .timeout_ms(timeout)
+                .validate_only(Some(false))
+                .topics(Some(
+                    [CreatableTopic::default()
+                        .num_partitions(1)
+                        .configs(Some(
+                            [
+                                CreatableTopicConfig::default()
+                                    .name(CLEANUP_POLICY.into())
+                                    .value(Some(DELETE.into())),
+                                CreatableTopicConfig::default()
+                                    .name(RETENTION_BYTES.into())
+                                    .value(Some("10".into())),
+                            ]
+                            .into(),
+                        ))
+                        .assignments(Some([].into()))
+                        .replication_factor(0)
+                        .name(topic_name.into())]
+                    .into(),
+                )),
+        )
+        .await?;
+
+    let topics = response.topics.as_deref().unwrap_or_default();
+    assert_eq!(1, topics.len());
+    assert_eq!(i16::from(ErrorCode::None), topics[0].error_code);
+
+    const KEY: Bytes = Bytes::from_static(b"alpha");
+
+    // each record is the length of its key and value: 8, 8 and 10 bytes
+    for (offset, value) in [&b"one"[..], b"two", b"three"].into_iter().enumerate() {
+        let frame = inflated::Batch::builder()
+            .record(
+                Record::builder()
+                    .key(Some(KEY))
+                    .value(Some(Bytes::from_static(value))),
+            )
+            .build()
+            .map(|batch| inflated::Frame {
+                batches: vec![batch],
+            })
+            .and_then(deflated::Frame::try_from)?;
+
+        let response = broker
+            .serve(
+                Context::default(),
+                ProduceRequest::default()
+                    .timeout_ms(timeout)
+                    .acks(Ack::Leader.into())
+                    .topic_data(Some(
+                        [TopicProduceData::default()
+                            .name(topic_name.into())
+                            .partition_data(Some(
+                                [PartitionProduceData::default()
+                                    .index(partition)
+                                    .records(Some(frame))]
+                                .into(),
+                            ))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        let topics = response.responses.as_deref().unwrap_or_default();
+        let partitions = topics[0].partition_responses.as_deref().unwrap_or_default();
+        assert_eq!(i16::from(ErrorCode::None), partitions[0].error_code);
+        assert_eq!(offset as i64, partitions[0].base_offset);
+    }
+
+    sc.maintain(SystemTime::now()).await?;
+
+    let response = broker
+        .serve(
+            Context::default(),
+            ListOffsetsRequest::default()
+                .isolation_level(Some(IsolationLevel::ReadUncommitted.into()))
+                .replica_id(-1)
+                .topics(Some(
+                    [ListOffsetsTopic::default()
+                        .name(topic_name.into())
+                        .partitions(Some(
+                            [ListOffsetsPartition::default()
+                                .partition_index(partition)
+                                .timestamp(ListOffset::Earliest.try_into()?)
+                                .current_leader_epoch(Some(-1))]
+                            .into(),
+                        ))]
+                    .into(),
+                )),
+        )
+        .await?;
+
+    let topics = response.topics.as_deref().unwrap_or_default();
+    let partitions = topics[0].partitions.as_deref().unwrap_or_default();
+    assert_eq!(i16::from(ErrorCode::None), partitions[0].error_code);
+
+    // only the newest record is within the retention of 10 bytes
+    assert_eq!(Some(2), partitions[0].offset);
+
+    Ok(())
+}
+
 pub async fn compact_delete_001(sc: StorageContainer) -> Result<()> {
     let broker = broker(sc.clone())?;
 
@@ -2897,6 +3041,19 @@ mod pg {
         super::delete_no_retention_ms_only(sc).await
     }
 
+    #[tokio::test]
+    async fn delete_retention_bytes() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        let sc = storage_container(cluster_id, broker_id).await?;
+        register_broker(cluster_id, broker_id, &sc).await?;
+
+        super::delete_retention_bytes(sc).await
+    }
+
     #[tokio::test]
     async fn compact_delete_001() -> Result<()> {
         let _guard = init_tracing()?;
@@ -3063,6 +3220,19 @@ mod lite {
         super::delete_no_retention_ms_only(sc).await
     }
 
+    #[tokio::test]
+    async fn delete_retention_bytes() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        let sc = storage_container(cluster_id, broker_id).await?;
+        register_broker(cluster_id, broker_id, &sc).await?;
+
+        super::delete_retention_bytes(sc).await
+    }
+
     #[tokio::test]
     async fn compact_delete_001() -> Result<()> {
         let _guard = init_tracing()?;
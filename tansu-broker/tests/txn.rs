@@ -25,6 +25,7 @@ use tansu_sans_io::{
         AddPartitionsToTxnPartitionResult, AddPartitionsToTxnTopicResult,
     },
     create_topics_request::CreatableTopic,
+    fetch_response::AbortedTransaction,
     record::{Record, inflated},
     txn_offset_commit_request::{TxnOffsetCommitRequestPartition, TxnOffsetCommitRequestTopic},
 };
@@ -866,6 +867,18 @@ pub async fn simple_txn_produce_abort(
         assert_eq!(Some(offset), list_offsets_after[0].1.offset);
     }
 
+    // the records of txn 1 are skipped by read committed consumers
+    //
+    assert_eq!(
+        vec![
+            AbortedTransaction::default()
+                .producer_id(transactions[0].1.id)
+                .first_offset(0)
+        ],
+        sc.aborted_txns(&topition, 0, i64::from(num_records * num_transactions))
+            .await?
+    );
+
     assert_eq!(
         ErrorCode::None,
         sc.delete_topic(&TopicId::from(topic_id)).await?
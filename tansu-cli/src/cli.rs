@@ -16,9 +16,13 @@
 //!
 //! The CLI is a single statically linked binary that contains:
 //! - Broker
+//! - Bench: workload generator reporting end-to-end latency percentiles
 //! - Cat: produce, validate (if backed by a schema) and fetch messages
 //! - Generator: use fake data generators to produce messages with a rate limit
+//! - Protocol: API key and version coverage of the broker
 //! - Proxy: a Kafka API proxy
+//! - Schema: import of Confluent or Apicurio schema registry exports
+//! - Soak: long running produce/consume checking broker invariants
 //! - Topic: Topic administration
 
 use std::process;
@@ -28,15 +32,33 @@ use clap::{Parser, Subcommand};
 use tansu_sans_io::ErrorCode;
 use tracing::debug;
 
+mod bench;
 mod broker;
 mod cat;
 mod generator;
+mod lineage;
+mod link;
+mod partition;
 mod perf;
+mod protocol;
 mod proxy;
+mod schema;
+mod soak;
 mod topic;
 
 const DEFAULT_BROKER: &str = "tcp://localhost:9092";
 
+/// GET a URL of the broker admin listener, with a bearer token when it requires one
+async fn admin_get(url: url::Url, token: Option<&str>) -> Result<reqwest::Response> {
+    let request = reqwest::Client::new().get(url);
+
+    token
+        .map_or(request, |token| request.bearer_auth(token))
+        .send()
+        .await
+        .map_err(Into::into)
+}
+
 fn storage_engines() -> Vec<&'static str> {
     vec![
         #[cfg(feature = "dynostore")]
@@ -91,6 +113,9 @@ enum Command {
     /// Apache Kafka compatible broker with Avro, JSON, Protobuf schema validation [default if no command supplied]
     Broker(Box<broker::Arg>),
 
+    /// Workload generator reporting throughput and end-to-end latency percentiles
+    Bench(Box<bench::Arg>),
+
     /// Easily consume or produce Avro, JSON or Protobuf messages to a topic
     Cat {
         #[command(subcommand)]
@@ -100,12 +125,45 @@ enum Command {
     /// Traffic Generator for schema backed topics
     Generator(Box<generator::Arg>),
 
+    /// Trace the lineage of records produced to topics with tansu.lineage enabled
+    Lineage {
+        #[command(subcommand)]
+        command: lineage::Command,
+    },
+
+    /// Offset translation of topics linked to an upstream cluster
+    Link {
+        #[command(subcommand)]
+        command: link::Command,
+    },
+
+    /// Partition scaling recommendations from the load sampled by the broker
+    Partition {
+        #[command(subcommand)]
+        command: partition::Command,
+    },
+
     /// Performance
     Perf(Box<perf::Arg>),
 
+    /// Kafka protocol coverage of the broker
+    Protocol {
+        #[command(subcommand)]
+        command: protocol::Command,
+    },
+
     /// Apache Kafka compatible proxy
     Proxy(Box<proxy::Arg>),
 
+    /// Schema registry administration
+    Schema {
+        #[command(subcommand)]
+        command: schema::Command,
+    },
+
+    /// Soak test producing and consuming continuously while checking invariants
+    Soak(Box<soak::Arg>),
+
     /// Create, list or delete topics managed by the broker
     Topic {
         #[command(subcommand)]
@@ -125,9 +183,14 @@ impl Cli {
 
         match cli.command.unwrap_or(Command::Broker(Box::new(cli.broker))) {
             Command::Broker(arg) => arg.main().await,
+            Command::Bench(arg) => arg.main().await,
             Command::Cat { command } => command.main().await,
             Command::Generator(arg) => arg.main().await,
+            Command::Lineage { command } => command.main().await,
+            Command::Link { command } => command.main().await,
+            Command::Partition { command } => command.main().await,
             Command::Perf(arg) => arg.main().await,
+            Command::Protocol { command } => command.main().await,
             Command::Proxy(arg) => tansu_proxy::Proxy::main(
                 arg.listener_url.into_inner(),
                 arg.advertised_listener_url.into_inner(),
@@ -137,6 +200,8 @@ impl Cli {
             )
             .await
             .map_err(Into::into),
+            Command::Schema { command } => command.main().await,
+            Command::Soak(arg) => arg.main().await,
             Command::Topic { command } => command.main().await,
         }
     }
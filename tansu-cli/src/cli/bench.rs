@@ -0,0 +1,119 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use tansu_bench::Bench;
+use tansu_sans_io::{Compression, ErrorCode};
+use url::Url;
+
+use crate::{EnvVarExp, Result, cli::DEFAULT_BROKER};
+
+#[derive(Args, Clone, Debug)]
+pub(super) struct Arg {
+    /// The URL of the broker
+    #[arg(long, default_value = DEFAULT_BROKER, env = "ADVERTISED_LISTENER_URL")]
+    broker: EnvVarExp<Url>,
+
+    /// The topic to produce into and consume from
+    #[clap(value_parser)]
+    topic: String,
+
+    /// Identify this run in the report, e.g., the release and storage engine
+    #[arg(long)]
+    label: Option<String>,
+
+    /// The number of producers
+    #[arg(long, default_value = "1")]
+    producers: u32,
+
+    /// The number of consumers, each fetching every record produced
+    #[arg(long, default_value = "1")]
+    consumers: u32,
+
+    /// Records in each produce request
+    #[arg(long, default_value = "100")]
+    batch_size: u32,
+
+    /// Minimum record value size
+    #[arg(long, default_value = "1k", value_parser=clap::value_parser!(human_units::Size))]
+    record_size: human_units::Size,
+
+    /// Maximum record value size, with sizes uniformly distributed from the minimum (default: the minimum)
+    #[arg(long, value_parser=clap::value_parser!(human_units::Size))]
+    max_record_size: Option<human_units::Size>,
+
+    /// The number of distinct record keys, with records unkeyed when zero
+    #[arg(long, default_value = "0")]
+    key_cardinality: u64,
+
+    /// The Zipfian exponent of the record key distribution, with zero being uniform
+    #[arg(long, default_value = "0.99")]
+    key_skew: f64,
+
+    /// Record batch compression: none, gzip, snappy, lz4 or zstd
+    #[arg(long, default_value = "none")]
+    compression: Compression,
+
+    /// Produce acknowledgement from the leader (1) or all in sync replicas (-1)
+    #[arg(long, default_value = "-1", allow_negative_numbers = true)]
+    acks: i16,
+
+    /// Pace producers to this total number of records per second (default: unpaced)
+    #[arg(long)]
+    throughput: Option<u32>,
+
+    /// Stop producing after this time
+    #[arg(long, default_value = "1m", value_parser=clap::value_parser!(human_units::Duration))]
+    duration: human_units::Duration,
+
+    /// Consumers must drain the topic within this time once producing stops
+    #[arg(long, default_value = "30s", value_parser=clap::value_parser!(human_units::Duration))]
+    drain_timeout: human_units::Duration,
+
+    /// Write the report as JSON to this file rather than stdout
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+impl Arg {
+    pub(super) async fn main(self) -> Result<ErrorCode> {
+        let record_size = self.record_size.0 as usize;
+        let max_record_size = self
+            .max_record_size
+            .map_or(record_size, |max_record_size| max_record_size.0 as usize);
+
+        Bench::builder()
+            .broker(self.broker.into_inner())
+            .topic(self.topic)
+            .label(self.label)
+            .producers(self.producers)
+            .consumers(self.consumers)
+            .batch_size(self.batch_size)
+            .record_size(record_size..=max_record_size)
+            .key_cardinality(self.key_cardinality)
+            .key_skew(self.key_skew)
+            .compression(self.compression)
+            .acks(self.acks)
+            .throughput(self.throughput)
+            .duration(self.duration.0)
+            .drain_timeout(self.drain_timeout.0)
+            .report(self.report)
+            .build()
+            .main()
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write as _};
+
+use super::admin_get;
+use crate::Result;
+use clap::Subcommand;
+use tansu_broker::broker::lineage::Traced;
+use tansu_sans_io::ErrorCode;
+use url::Url;
+
+const DEFAULT_ADMIN: &str = "http://localhost:9093";
+
+#[derive(Clone, Debug, Subcommand)]
+pub(super) enum Command {
+    /// Trace the lineage of a record back to the original record that caused it
+    Trace {
+        /// Broker administration URL
+        #[arg(long, env = "ADMIN_URL", default_value = DEFAULT_ADMIN)]
+        admin: Url,
+
+        /// Bearer token required by the broker administration listener
+        #[arg(long, env = "ADMIN_TOKEN")]
+        admin_token: Option<String>,
+
+        /// The topic of the record
+        #[clap(value_parser)]
+        topic: String,
+
+        /// The partition of the record
+        #[arg(long, default_value = "0")]
+        partition: i32,
+
+        /// The offset of the record
+        #[arg(long)]
+        offset: i64,
+
+        /// Write the lineage as JSON
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+}
+
+impl Command {
+    pub(super) async fn main(self) -> Result<ErrorCode> {
+        match self {
+            Command::Trace {
+                admin,
+                admin_token,
+                topic,
+                partition,
+                offset,
+                json,
+            } => {
+                let mut url = admin.join("lineage")?;
+
+                _ = url
+                    .query_pairs_mut()
+                    .append_pair("topic", topic.as_str())
+                    .append_pair("partition", partition.to_string().as_str())
+                    .append_pair("offset", offset.to_string().as_str());
+
+                let response = admin_get(url, admin_token.as_deref()).await?;
+
+                if !response.status().is_success() {
+                    return Ok(ErrorCode::UnknownServerError);
+                }
+
+                let lineage = response.json::<Vec<Traced>>().await?;
+
+                if lineage.is_empty() {
+                    return Ok(ErrorCode::OffsetOutOfRange);
+                }
+
+                let mut stdout = io::stdout().lock();
+
+                if json {
+                    serde_json::to_writer_pretty(&mut stdout, &lineage)?;
+                    writeln!(stdout)?;
+                    return Ok(ErrorCode::None);
+                }
+
+                for (depth, traced) in lineage.iter().enumerate() {
+                    writeln!(
+                        stdout,
+                        "{:indent$}{}/{}/{} timestamp: {} correlation: {}",
+                        "",
+                        traced.topic,
+                        traced.partition,
+                        traced.offset,
+                        traced.timestamp,
+                        traced.correlation_id.as_deref().unwrap_or("-"),
+                        indent = depth * 2,
+                    )?;
+                }
+
+                Ok(ErrorCode::None)
+            }
+        }
+    }
+}
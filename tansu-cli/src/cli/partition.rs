@@ -0,0 +1,132 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write as _};
+
+use super::admin_get;
+use crate::Result;
+use clap::Subcommand;
+use tansu_broker::broker::scaling::{Reason, Recommendation};
+use tansu_sans_io::ErrorCode;
+use url::Url;
+
+const DEFAULT_ADMIN: &str = "http://localhost:9093";
+
+#[derive(Clone, Debug, Subcommand)]
+pub(super) enum Command {
+    /// Report topics that need more partitions, or whose partitions are imbalanced
+    Recommend {
+        /// Broker administration URL
+        #[arg(long, env = "ADMIN_URL", default_value = DEFAULT_ADMIN)]
+        admin: Url,
+
+        /// Bearer token required by the broker administration listener
+        #[arg(long, env = "ADMIN_TOKEN")]
+        admin_token: Option<String>,
+
+        /// Report the load of each partition of this topic, whether or not it has a recommendation
+        #[arg(long)]
+        topic: Option<String>,
+
+        /// Write the recommendations as JSON
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+}
+
+fn reasons(reasons: &[Reason]) -> String {
+    reasons
+        .iter()
+        .map(|reason| match reason {
+            Reason::Throughput => "throughput",
+            Reason::Backlog => "backlog",
+            Reason::Imbalanced => "imbalanced",
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Command {
+    pub(super) async fn main(self) -> Result<ErrorCode> {
+        match self {
+            Command::Recommend {
+                admin,
+                admin_token,
+                topic,
+                json,
+            } => {
+                let mut url = admin.join("partition-recommendations")?;
+
+                if let Some(topic) = topic.as_deref() {
+                    _ = url.query_pairs_mut().append_pair("topic", topic);
+                }
+
+                let response = admin_get(url, admin_token.as_deref()).await?;
+
+                if !response.status().is_success() {
+                    return Ok(ErrorCode::UnknownServerError);
+                }
+
+                let recommendations = response.json::<Vec<Recommendation>>().await?;
+
+                let mut stdout = io::stdout().lock();
+
+                if json {
+                    serde_json::to_writer_pretty(&mut stdout, &recommendations)?;
+                    writeln!(stdout)?;
+                    return Ok(ErrorCode::None);
+                }
+
+                writeln!(
+                    stdout,
+                    "{:<32} {:>10} {:>11} {:>12} {:>14} {:>10} {:>6}  reasons",
+                    "topic", "partitions", "recommended", "records/s", "bytes/s", "backlog", "skew"
+                )?;
+
+                for recommendation in &recommendations {
+                    writeln!(
+                        stdout,
+                        "{:<32} {:>10} {:>11} {:>12.1} {:>14.1} {:>10} {:>6.2}  {}",
+                        recommendation.topic,
+                        recommendation.partitions,
+                        recommendation.recommended_partitions,
+                        recommendation.records_per_sec,
+                        recommendation.bytes_per_sec,
+                        recommendation.backlog,
+                        recommendation.skew,
+                        reasons(&recommendation.reasons),
+                    )?;
+
+                    if topic.is_none() {
+                        continue;
+                    }
+
+                    for load in &recommendation.loads {
+                        writeln!(
+                            stdout,
+                            "  partition {:<20} {:>23} {:>12.1} {:>14.1} {:>10}",
+                            load.partition,
+                            "",
+                            load.records_per_sec,
+                            load.bytes_per_sec,
+                            load.backlog,
+                        )?;
+                    }
+                }
+
+                Ok(ErrorCode::None)
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write as _};
+
+use crate::Result;
+use clap::Subcommand;
+use tansu_broker::coverage;
+use tansu_sans_io::ErrorCode;
+
+#[derive(Clone, Debug, Subcommand)]
+pub(super) enum Command {
+    /// JSON matrix of API keys and versions, with those handled by the broker
+    Coverage,
+}
+
+impl Command {
+    pub(super) async fn main(self) -> Result<ErrorCode> {
+        match self {
+            Command::Coverage => {
+                let coverage = coverage::broker().await?;
+
+                let mut stdout = io::stdout().lock();
+                serde_json::to_writer_pretty(&mut stdout, &coverage)?;
+                writeln!(stdout)?;
+
+                Ok(ErrorCode::None)
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write as _};
+
+use crate::Result;
+use clap::Subcommand;
+use tansu_sans_io::ErrorCode;
+use tansu_schema::{Registry, import};
+use tokio::io::AsyncReadExt as _;
+use url::Url;
+
+#[derive(Clone, Debug, Subcommand)]
+pub(super) enum Command {
+    /// Import the subject versions exported from a Confluent or Apicurio schema registry
+    Import {
+        /// Schema registry examples are: file://./etc/schema or s3://tansu/
+        #[arg(long, env = "SCHEMA_REGISTRY")]
+        schema_registry: Url,
+
+        /// The exported JSON array (or newline delimited JSON) of subject versions, or '-' for stdin
+        #[clap(value_parser, default_value = "-")]
+        file: String,
+    },
+}
+
+impl Command {
+    pub(super) async fn main(self) -> Result<ErrorCode> {
+        match self {
+            Command::Import {
+                schema_registry,
+                file,
+            } => {
+                let encoded = if file == "-" {
+                    let mut encoded = vec![];
+                    _ = tokio::io::stdin().read_to_end(&mut encoded).await?;
+                    encoded
+                } else {
+                    tokio::fs::read(file).await?
+                };
+
+                let registry = Registry::builder_try_from_url(&schema_registry)?.build();
+
+                let imported = registry.import(import::exported(&encoded)?).await?;
+
+                let mut stdout = io::stdout().lock();
+
+                for outcome in &imported {
+                    if let Some(ref error) = outcome.error {
+                        writeln!(
+                            stdout,
+                            "{} version {}: {error}",
+                            outcome.subject, outcome.exported_version
+                        )?;
+
+                        continue;
+                    }
+
+                    writeln!(
+                        stdout,
+                        "{} version {} -> {} version {}{}{}",
+                        outcome.subject,
+                        outcome.exported_version,
+                        outcome.topic,
+                        outcome
+                            .version
+                            .map_or(String::from("-"), |version| version.to_string()),
+                        if outcome.version_preserved || outcome.subject.ends_with("-key") {
+                            ""
+                        } else {
+                            " (version renumbered)"
+                        },
+                        match outcome.id {
+                            Some(id) if !outcome.id_preserved => format!(" (id {id} in use)"),
+                            _ => String::new(),
+                        },
+                    )?;
+                }
+
+                Ok(if imported.iter().any(|outcome| outcome.error.is_some()) {
+                    ErrorCode::InvalidRecord
+                } else {
+                    ErrorCode::None
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use tansu_sans_io::ErrorCode;
+use tansu_soak::Soak;
+use url::Url;
+
+use crate::{EnvVarExp, Result, cli::DEFAULT_BROKER};
+
+#[derive(Args, Clone, Debug)]
+pub(super) struct Arg {
+    /// The URL of the broker
+    #[arg(long, default_value = DEFAULT_BROKER, env = "ADVERTISED_LISTENER_URL")]
+    broker: EnvVarExp<Url>,
+
+    /// The topic to produce into and consume from
+    #[clap(value_parser)]
+    topic: String,
+
+    /// The consumer group used by every consumer
+    #[arg(long, default_value = "tansu-soak")]
+    group_id: String,
+
+    /// The number of producers
+    #[arg(long, default_value = "1")]
+    producers: u32,
+
+    /// The number of consumers in the group
+    #[arg(long, default_value = "3")]
+    consumers: u32,
+
+    /// Record batch size used by every producer
+    #[arg(long, default_value = "10")]
+    batch_size: u32,
+
+    /// Record size used by every producer
+    #[arg(long, default_value = "1k", value_parser=clap::value_parser!(human_units::Size))]
+    record_size: human_units::Size,
+
+    /// Stop producing after this time
+    #[arg(long, default_value = "1h", value_parser=clap::value_parser!(human_units::Duration))]
+    duration: human_units::Duration,
+
+    /// Each consumer leaves and rejoins the group after this time, forcing a rebalance
+    #[arg(long, default_value = "1m", value_parser=clap::value_parser!(human_units::Duration))]
+    rebalance_interval: human_units::Duration,
+
+    /// Consumer group session timeout
+    #[arg(long, default_value = "30s", value_parser=clap::value_parser!(human_units::Duration))]
+    session_timeout: human_units::Duration,
+
+    /// Consumers must catch up with the high watermark within this time once producing stops
+    #[arg(long, default_value = "1m", value_parser=clap::value_parser!(human_units::Duration))]
+    convergence_timeout: human_units::Duration,
+
+    /// Write diagnostics as JSON to this file rather than stdout
+    #[arg(long)]
+    diagnostics: Option<PathBuf>,
+}
+
+impl Arg {
+    pub(super) async fn main(self) -> Result<ErrorCode> {
+        Soak::builder()
+            .broker(self.broker.into_inner())
+            .topic(self.topic)
+            .group_id(self.group_id)
+            .producers(self.producers)
+            .consumers(self.consumers)
+            .batch_size(self.batch_size)
+            .record_size(self.record_size.0 as usize)
+            .duration(self.duration.0)
+            .rebalance_interval(self.rebalance_interval.0)
+            .session_timeout(self.session_timeout.0)
+            .convergence_timeout(self.convergence_timeout.0)
+            .diagnostics(self.diagnostics)
+            .build()
+            .main()
+            .await
+            .map_err(Into::into)
+    }
+}
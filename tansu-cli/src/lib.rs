@@ -21,15 +21,20 @@ use regex::{Regex, Replacer};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    Bench(#[from] tansu_bench::Error),
     Box(#[from] Box<dyn std::error::Error + Send + Sync>),
     Cat(Box<tansu_cat::Error>),
     DotEnv(#[from] dotenv::Error),
     Generate(#[from] tansu_generator::Error),
+    Io(#[from] std::io::Error),
+    Json(#[from] serde_json::Error),
     Perf(#[from] tansu_perf::Error),
     Proxy(#[from] tansu_proxy::Error),
     Regex(#[from] regex::Error),
+    Reqwest(#[from] reqwest::Error),
     Schema(Box<tansu_schema::Error>),
     Server(Box<tansu_broker::Error>),
+    Soak(#[from] tansu_soak::Error),
     Topic(#[from] tansu_topic::Error),
     Url(#[from] url::ParseError),
 }
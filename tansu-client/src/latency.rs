@@ -0,0 +1,192 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! End-to-end latency
+//!
+//! A topic with `tansu.latency.timestamp` enabled has each produced record
+//! stamped by the broker with a [`RECEIVED_AT`] header: the big endian `i64`
+//! milliseconds since the epoch at which the broker received the record.
+//!
+//! A consumer computes the end-to-end latency of each record with
+//! [`end_to_end`], the time between the broker receiving the record and the
+//! record being consumed, aggregating them into a [`Latencies`] distribution.
+//! Unlike the record timestamp, which is set by the producer, the header is
+//! unaffected by the clock of the producer.
+
+use std::time::{Duration, SystemTime};
+
+use tansu_sans_io::record::Record;
+
+/// The header holding the time the broker received a record
+pub const RECEIVED_AT: &str = "tansu-received-at";
+
+/// The time the broker received a record, if it was stamped
+pub fn received_at(record: &Record) -> Option<SystemTime> {
+    record
+        .headers
+        .iter()
+        .find(|header| header.key.as_deref() == Some(RECEIVED_AT.as_bytes()))
+        .and_then(|header| header.value.as_deref())
+        .and_then(|value| <[u8; 8]>::try_from(value).ok())
+        .map(i64::from_be_bytes)
+        .and_then(|millis| u64::try_from(millis).ok())
+        .map(|millis| SystemTime::UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+/// The time between the broker receiving a record and `now`, zero when the
+/// clock of the consumer is behind that of the broker
+pub fn end_to_end(record: &Record, now: SystemTime) -> Option<Duration> {
+    received_at(record).map(|received_at| now.duration_since(received_at).unwrap_or_default())
+}
+
+/// A distribution of end-to-end latencies
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Latencies {
+    observed: Vec<Duration>,
+    sorted: bool,
+}
+
+impl Latencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe the latency of a record, returning the latency when the
+    /// record was stamped by the broker
+    pub fn observe(&mut self, record: &Record, now: SystemTime) -> Option<Duration> {
+        end_to_end(record, now).inspect(|latency| self.push(*latency))
+    }
+
+    /// Add a latency to the distribution
+    pub fn push(&mut self, latency: Duration) {
+        self.observed.push(latency);
+        self.sorted = false;
+    }
+
+    /// The number of observed latencies
+    pub fn len(&self) -> usize {
+        self.observed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observed.is_empty()
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.observed.iter().min().copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.observed.iter().max().copied()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        u32::try_from(self.observed.len())
+            .ok()
+            .filter(|len| *len > 0)
+            .map(|len| self.observed.iter().sum::<Duration>() / len)
+    }
+
+    /// The latency at a percentile between 0 and 100, using the nearest rank
+    pub fn percentile(&mut self, percentile: f64) -> Option<Duration> {
+        if self.observed.is_empty() || !(0.0..=100.0).contains(&percentile) {
+            return None;
+        }
+
+        if !self.sorted {
+            self.observed.sort_unstable();
+            self.sorted = true;
+        }
+
+        let rank = ((percentile / 100.0) * self.observed.len() as f64).ceil() as usize;
+        self.observed.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// Remove every observed latency
+    pub fn clear(&mut self) {
+        self.observed.clear();
+        self.sorted = false;
+    }
+}
+
+impl Extend<Duration> for Latencies {
+    fn extend<T: IntoIterator<Item = Duration>>(&mut self, iter: T) {
+        self.observed.extend(iter);
+        self.sorted = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tansu_sans_io::record::Header;
+
+    use super::*;
+
+    #[test]
+    fn received_at_header() -> Result<(), tansu_sans_io::Error> {
+        let record = Record::builder()
+            .value(Some(Bytes::from_static(b"abc")))
+            .header(
+                Header::builder()
+                    .key(Bytes::from_static(RECEIVED_AT.as_bytes()))
+                    .value(Bytes::copy_from_slice(&1_000i64.to_be_bytes())),
+            )
+            .build()?;
+
+        let received = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        assert_eq!(Some(received), received_at(&record));
+
+        assert_eq!(
+            Some(Duration::from_millis(250)),
+            end_to_end(&record, received + Duration::from_millis(250))
+        );
+
+        assert_eq!(
+            Some(Duration::ZERO),
+            end_to_end(&record, SystemTime::UNIX_EPOCH)
+        );
+
+        assert_eq!(
+            None,
+            Record::builder()
+                .build()
+                .map(|record| received_at(&record))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut latencies = Latencies::new();
+        assert_eq!(None, latencies.percentile(50.0));
+        assert_eq!(None, latencies.mean());
+
+        latencies.extend((1..=100).rev().map(Duration::from_millis));
+
+        assert_eq!(100, latencies.len());
+        assert_eq!(Some(Duration::from_millis(1)), latencies.min());
+        assert_eq!(Some(Duration::from_millis(100)), latencies.max());
+        assert_eq!(Some(Duration::from_micros(50_500)), latencies.mean());
+        assert_eq!(Some(Duration::from_millis(1)), latencies.percentile(0.0));
+        assert_eq!(Some(Duration::from_millis(50)), latencies.percentile(50.0));
+        assert_eq!(Some(Duration::from_millis(99)), latencies.percentile(99.0));
+        assert_eq!(
+            Some(Duration::from_millis(100)),
+            latencies.percentile(100.0)
+        );
+        assert_eq!(None, latencies.percentile(101.0));
+    }
+}
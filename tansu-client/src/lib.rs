@@ -99,6 +99,10 @@
 //! # }
 //! ```
 
+pub mod latency;
+pub mod lineage;
+pub mod ordered;
+
 use std::{
     collections::BTreeMap,
     error, fmt, io,
@@ -0,0 +1,167 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record lineage
+//!
+//! A topic with `tansu.lineage` enabled has each produced record given a
+//! [`CORRELATION_ID`] header by the broker when it has none, shared by every
+//! record descended from the same original record.
+//!
+//! A record produced as the result of consuming another (e.g., by a stream
+//! processor) carries a [`CAUSATION_ID`] header: the [`Location`] of the
+//! record that caused it. The headers for such a record are made with
+//! [`caused_by`], or by the broker copying the correlation id of the cause
+//! when only the causation id is present. The lineage of a record is traced
+//! by following its causation ids back to the original record.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use bytes::Bytes;
+use tansu_sans_io::record::{Record, header};
+
+/// The header identifying the records descended from the same original record
+pub const CORRELATION_ID: &str = "tansu-correlation-id";
+
+/// The header holding the location of the record that caused this record
+pub const CAUSATION_ID: &str = "tansu-causation-id";
+
+/// The location of a record, as `topic/partition/offset`
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+impl Location {
+    pub fn new(topic: impl Into<String>, partition: i32, offset: i64) -> Self {
+        Self {
+            topic: topic.into(),
+            partition,
+            offset,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.topic, self.partition, self.offset)
+    }
+}
+
+impl FromStr for Location {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // a topic name cannot contain '/'
+        let mut parts = s.rsplitn(3, '/');
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(offset), Some(partition), Some(topic)) if !topic.is_empty() => Ok(Self {
+                topic: topic.to_owned(),
+                partition: partition
+                    .parse()
+                    .map_err(|_| crate::Error::Message(s.to_owned()))?,
+                offset: offset
+                    .parse()
+                    .map_err(|_| crate::Error::Message(s.to_owned()))?,
+            }),
+
+            _ => Err(crate::Error::Message(s.to_owned())),
+        }
+    }
+}
+
+fn header<'a>(record: &'a Record, key: &str) -> Option<&'a [u8]> {
+    record
+        .headers
+        .iter()
+        .find(|header| header.key.as_deref() == Some(key.as_bytes()))
+        .and_then(|header| header.value.as_deref())
+}
+
+/// The correlation id of a record, if any
+pub fn correlation_id(record: &Record) -> Option<&str> {
+    header(record, CORRELATION_ID).and_then(|value| std::str::from_utf8(value).ok())
+}
+
+/// The location of the record that caused a record, if any
+pub fn causation_id(record: &Record) -> Option<Location> {
+    header(record, CAUSATION_ID)
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// The lineage headers of a record caused by consuming the record at a location
+pub fn caused_by(consumed: &Record, location: &Location) -> Vec<header::Builder> {
+    correlation_id(consumed)
+        .map(|correlation_id| {
+            header::Header::builder()
+                .key(Bytes::from_static(CORRELATION_ID.as_bytes()))
+                .value(Bytes::copy_from_slice(correlation_id.as_bytes()))
+        })
+        .into_iter()
+        .chain(Some(
+            header::Header::builder()
+                .key(Bytes::from_static(CAUSATION_ID.as_bytes()))
+                .value(Bytes::from(location.to_string())),
+        ))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location() -> Result<(), crate::Error> {
+        let location = Location::new("orders.v1", 3, 12_321);
+        assert_eq!("orders.v1/3/12321", location.to_string());
+        assert_eq!(location, "orders.v1/3/12321".parse()?);
+
+        assert!("orders/3".parse::<Location>().is_err());
+        assert!("/3/12321".parse::<Location>().is_err());
+        assert!("orders/three/12321".parse::<Location>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn propagated() -> Result<(), tansu_sans_io::Error> {
+        let consumed = Record::builder()
+            .value(Some(Bytes::from_static(b"abc")))
+            .header(
+                header::Header::builder()
+                    .key(Bytes::from_static(CORRELATION_ID.as_bytes()))
+                    .value(Bytes::from_static(b"pqr")),
+            )
+            .build()?;
+
+        let location = Location::new("orders", 0, 6);
+
+        let derived = caused_by(&consumed, &location)
+            .into_iter()
+            .fold(Record::builder(), |builder, header| builder.header(header))
+            .build()?;
+
+        assert_eq!(Some("pqr"), correlation_id(&derived));
+        assert_eq!(Some(location), causation_id(&derived));
+        assert_eq!(None, causation_id(&consumed));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ordered consumption across the partitions of a global ordering topic
+//!
+//! A topic with `tansu.global.sequence` enabled has each produced record
+//! stamped by the broker with a [`GLOBAL_SEQUENCE`] header: a big endian
+//! `i64` taken from a single sequence shared by every partition of the topic.
+//! The sequence increases in the order that records were appended, while
+//! records continue to be stored (and fetched) in parallel by partition.
+//!
+//! A consumer needing a total order across the partitions pushes the records
+//! of each partition into an [`OrderedMerge`], which releases the record with
+//! the lowest sequence once every other partition either has a buffered
+//! record or has been fetched up to its high watermark. The resulting order is
+//! the same for every consumer, regardless of how fetches interleave.
+//!
+//! The order is paid for with latency and throughput: the slowest partition
+//! holds back every other partition, and the broker appends to a global
+//! ordering topic one produce request at a time. The sequence is not
+//! contiguous, a failed or aborted produce leaves a gap.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use tansu_sans_io::record::Record;
+
+/// The header holding the global sequence of a record
+pub const GLOBAL_SEQUENCE: &str = "tansu-global-sequence";
+
+/// The global sequence of a record, if it has one
+pub fn global_sequence(record: &Record) -> Option<i64> {
+    record
+        .headers
+        .iter()
+        .find(|header| header.key.as_deref() == Some(GLOBAL_SEQUENCE.as_bytes()))
+        .and_then(|header| header.value.as_deref())
+        .and_then(|value| <[u8; 8]>::try_from(value).ok())
+        .map(i64::from_be_bytes)
+}
+
+/// Merge the records of each partition into global sequence order
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrderedMerge<T> {
+    pending: BTreeMap<i32, VecDeque<(i64, T)>>,
+    caught_up: BTreeSet<i32>,
+}
+
+impl<T> OrderedMerge<T> {
+    /// A merge of the partitions of a topic
+    pub fn new(partitions: impl IntoIterator<Item = i32>) -> Self {
+        Self {
+            pending: partitions
+                .into_iter()
+                .map(|partition| (partition, VecDeque::new()))
+                .collect(),
+            caught_up: BTreeSet::new(),
+        }
+    }
+
+    /// Buffer an item of a partition with its global sequence, items of a
+    /// partition must be pushed in offset order
+    pub fn push(&mut self, partition: i32, sequence: i64, item: T) {
+        _ = self.caught_up.remove(&partition);
+
+        self.pending
+            .entry(partition)
+            .or_default()
+            .push_back((sequence, item));
+    }
+
+    /// The partition has been fetched up to its high watermark, so no
+    /// record with a lower sequence than those already produced can follow
+    pub fn caught_up(&mut self, partition: i32) {
+        _ = self.caught_up.insert(partition);
+    }
+
+    /// The next item in global sequence order, or `None` when a partition
+    /// without any buffered items might still hold a lower sequence
+    pub fn pop(&mut self) -> Option<(i32, T)> {
+        let mut lowest = None;
+
+        for (partition, pending) in &self.pending {
+            match pending.front() {
+                Some((sequence, _)) => {
+                    if lowest.is_none_or(|(_, lowest)| sequence < lowest) {
+                        lowest = Some((*partition, sequence));
+                    }
+                }
+
+                None if self.caught_up.contains(partition) => (),
+
+                None => return None,
+            }
+        }
+
+        let (partition, _) = lowest?;
+
+        self.pending
+            .get_mut(&partition)
+            .and_then(VecDeque::pop_front)
+            .map(|(_, item)| (partition, item))
+    }
+
+    /// The number of buffered items
+    pub fn len(&self) -> usize {
+        self.pending.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tansu_sans_io::record::Header;
+
+    use super::*;
+
+    #[test]
+    fn sequence_header() -> Result<(), tansu_sans_io::Error> {
+        let record = Record::builder()
+            .value(Some(Bytes::from_static(b"abc")))
+            .header(
+                Header::builder()
+                    .key(Bytes::from_static(GLOBAL_SEQUENCE.as_bytes()))
+                    .value(Bytes::copy_from_slice(&321i64.to_be_bytes())),
+            )
+            .build()?;
+
+        assert_eq!(Some(321), global_sequence(&record));
+        assert_eq!(
+            None,
+            Record::builder()
+                .build()
+                .map(|record| global_sequence(&record))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn waits_for_every_partition() {
+        let mut merge = OrderedMerge::new(0..3);
+
+        merge.push(0, 3, "d");
+        merge.push(1, 1, "b");
+        assert_eq!(None, merge.pop());
+
+        merge.push(2, 0, "a");
+        assert_eq!(Some((2, "a")), merge.pop());
+        assert_eq!(None, merge.pop());
+
+        merge.caught_up(2);
+        assert_eq!(Some((1, "b")), merge.pop());
+        assert_eq!(None, merge.pop());
+
+        merge.push(1, 2, "c");
+        assert_eq!(Some((1, "c")), merge.pop());
+
+        merge.caught_up(1);
+        assert_eq!(Some((0, "d")), merge.pop());
+        assert_eq!(None, merge.pop());
+        assert!(merge.is_empty());
+    }
+}
@@ -25,6 +25,8 @@ pub enum Error {
     DryPop,
     EmptyStack,
     FromUtf8(string::FromUtf8Error),
+    #[cfg(feature = "generate")]
+    Glob(Arc<glob::GlobError>),
     Io(Arc<io::Error>),
     Json(Arc<serde_json::Error>),
     Message(String),
@@ -34,6 +36,8 @@ pub enum Error {
     NoSuchMessage(&'static str),
     ParseBool(str::ParseBoolError),
     ParseInt(num::ParseIntError),
+    #[cfg(feature = "generate")]
+    Pattern(glob::PatternError),
     Syn(syn::Error),
     TryFromInt(num::TryFromIntError),
     Utf8(str::Utf8Error),
@@ -95,6 +99,20 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "generate")]
+impl From<glob::GlobError> for Error {
+    fn from(value: glob::GlobError) -> Self {
+        Self::Glob(Arc::new(value))
+    }
+}
+
+#[cfg(feature = "generate")]
+impl From<glob::PatternError> for Error {
+    fn from(value: glob::PatternError) -> Self {
+        Self::Pattern(value)
+    }
+}
+
 impl From<syn::Error> for Error {
     fn from(value: syn::Error) -> Self {
         Self::Syn(value)
@@ -18,6 +18,8 @@
 //! that can be easily used during the Tansu Sans I/O build process.
 
 pub mod error;
+#[cfg(feature = "generate")]
+pub mod generate;
 pub mod wv;
 
 use convert_case::{Case, Casing};
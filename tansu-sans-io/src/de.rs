@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{Error, Result, RootMessageMeta};
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use bytes::{Buf, Bytes};
 use serde::{
     Deserializer,
     de::{DeserializeSeed, EnumAccess, SeqAccess, VariantAccess, Visitor},
@@ -867,8 +867,17 @@ impl<'de> Deserializer<'de> for &mut Decoder<'de> {
             Some(size_in_bytes) if self.in_records => {
                 debug!(size_in_bytes);
 
-                let mut buf = vec![0u8; size_in_bytes];
-                self.reader.read_exact(&mut buf)?;
+                // grown as the records are read, rather than trusting the
+                // length of a frame that is still being received
+                let mut buf = Vec::new();
+                let read = (&mut self.reader)
+                    .take(u64::try_from(size_in_bytes)?)
+                    .read_to_end(&mut buf)?;
+
+                if read < size_in_bytes {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+
                 let outcome = visitor.visit_seq(Batch::new(Bytes::from(buf)));
                 self.in_seq_of_primitive = false;
                 self.in_records = false;
@@ -1060,17 +1069,22 @@ impl<'de> SeqAccess<'de> for Batch {
         );
 
         if self.encoded.has_remaining() {
-            let base_offset = self.encoded.try_get_i64()?;
-            let batch_length = self.encoded.try_get_i32()?;
+            let mut header = &self.encoded[..];
+            let base_offset = header.try_get_i64()?;
+            let batch_length = header.try_get_i32()?;
             debug!(base_offset, batch_length);
 
-            let mut batch = BytesMut::new();
-            batch.put_i64(base_offset);
-            batch.put_i32(batch_length);
-            batch.put(self.encoded.split_to(batch_length as usize));
+            let length = size_of_val(&base_offset)
+                + size_of_val(&batch_length)
+                + usize::try_from(batch_length)?;
+
+            if length > self.encoded.len() {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
 
+            // the batch shares the records read, rather than being copied
             let decoder = BatchDecoder {
-                encoded: Bytes::from(batch),
+                encoded: self.encoded.split_to(length),
             };
 
             seed.deserialize(decoder).map(Some)
@@ -117,6 +117,7 @@
 //! map from the [Serde Data Model](https://serde.rs/data-model.html) to the Kafka protocol or vice versa.
 
 pub mod de;
+pub mod pool;
 pub mod primitive;
 pub mod record;
 pub mod ser;
@@ -127,6 +128,7 @@ use flate2::read::GzDecoder;
 use primitive::tagged::TagBuffer;
 use record::deflated::Frame as RecordBatch;
 pub use ser::Encoder;
+use ser::{Length, Window};
 use serde::{Deserialize, Serialize};
 use std::{
     array::TryFromSliceError,
@@ -135,6 +137,7 @@ use std::{
     fmt::{self, Display, Formatter},
     io::{self, BufRead, Cursor, Read, Write},
     num,
+    ops::Range,
     process::{ExitCode, Termination},
     str, string,
     sync::{Arc, OnceLock},
@@ -403,6 +406,9 @@ pub struct Frame {
     pub body: Body,
 }
 
+/// The capacity of a buffer taken from the [`pool`] to encode a frame into
+const FRAME_CAPACITY: usize = 4_096;
+
 impl Frame {
     fn elapsed_millis(start: SystemTime) -> u64 {
         start
@@ -415,9 +421,9 @@ impl Frame {
     pub fn request(header: Header, body: Body) -> Result<Bytes> {
         let start = SystemTime::now();
 
-        let mut c = Cursor::new(vec![]);
+        let mut writer = pool::buffer(FRAME_CAPACITY).writer();
 
-        let mut serializer = Encoder::request(&mut c);
+        let mut serializer = Encoder::request(&mut writer);
 
         let frame = Frame {
             size: 0,
@@ -426,13 +432,12 @@ impl Frame {
         };
 
         frame.serialize(&mut serializer)?;
-        let size = i32::try_from(c.position()).map(|position| position - 4)?;
 
-        c.set_position(0);
-        let buf = size.to_be_bytes();
-        c.write_all(&buf)?;
+        let mut encoded = writer.into_inner();
+        let size = i32::try_from(encoded.len()).map(|length| length - 4)?;
+        encoded[..4].copy_from_slice(&size.to_be_bytes());
 
-        Ok(Bytes::from(c.into_inner())).inspect(|encoded| {
+        Ok(encoded.freeze()).inspect(|encoded| {
             debug!(
                 len = encoded.len(),
                 elapsed_millis = Self::elapsed_millis(start)
@@ -443,9 +448,15 @@ impl Frame {
     /// deserialize bytes into an API request frame
     #[instrument(skip_all)]
     pub fn request_from_bytes(encoded: impl Buf) -> Result<Frame> {
+        Self::request_from_reader(encoded.reader())
+    }
+
+    /// deserialize an API request frame as it is read, without first
+    /// reading the whole frame into memory
+    #[instrument(skip_all)]
+    pub fn request_from_reader(mut reader: impl Read) -> Result<Frame> {
         let start = SystemTime::now();
 
-        let mut reader = encoded.reader();
         let mut deserializer = Decoder::request(&mut reader);
         Frame::deserialize(&mut deserializer)
             .inspect(|_frame| debug!(elapsed_millis = Self::elapsed_millis(start)))
@@ -456,8 +467,8 @@ impl Frame {
     pub fn response(header: Header, body: Body, api_key: i16, api_version: i16) -> Result<Bytes> {
         let start = SystemTime::now();
 
-        let mut c = Cursor::new(vec![]);
-        let mut serializer = Encoder::response(&mut c, api_key, api_version);
+        let mut writer = pool::buffer(FRAME_CAPACITY).writer();
+        let mut serializer = Encoder::response(&mut writer, api_key, api_version);
 
         let frame = Frame {
             size: 0,
@@ -466,18 +477,17 @@ impl Frame {
         };
 
         frame.serialize(&mut serializer)?;
-        let size = i32::try_from(c.position())
-            .map(|position| position - 4)
+
+        let mut encoded = writer.into_inner();
+        let size = i32::try_from(encoded.len())
+            .map(|length| length - 4)
             .inspect_err(|err| {
-                let position = c.position();
-                warn!(?err, ?position, ?frame);
+                let length = encoded.len();
+                warn!(?err, ?length, ?frame);
             })?;
+        encoded[..4].copy_from_slice(&size.to_be_bytes());
 
-        c.set_position(0);
-        let buf = size.to_be_bytes();
-        c.write_all(&buf)?;
-
-        Ok(Bytes::from(c.into_inner())).inspect(|encoded| {
+        Ok(encoded.freeze()).inspect(|encoded| {
             debug!(
                 len = encoded.len(),
                 elapsed_millis = Self::elapsed_millis(start)
@@ -485,6 +495,40 @@ impl Frame {
         })
     }
 
+    /// an API response, encoded a window of its bytes at a time
+    ///
+    /// The size of the frame is found by a counting pass. Each window encodes
+    /// the frame again, retaining only the bytes within the window, so that
+    /// the record batches of a large fetch response are written from the body
+    /// rather than from a buffer holding the whole response.
+    #[instrument(skip_all)]
+    pub fn encoded_response(
+        header: Header,
+        body: Body,
+        api_key: i16,
+        api_version: i16,
+    ) -> Result<EncodedResponse> {
+        let mut frame = Frame {
+            size: 0,
+            header,
+            body,
+        };
+
+        let mut length = Length::default();
+        frame.serialize(&mut Encoder::response(&mut length, api_key, api_version))?;
+
+        frame.size = i32::try_from(length.0)
+            .map(|length| length - 4)
+            .inspect_err(|err| warn!(?err, length = length.0))?;
+
+        Ok(EncodedResponse {
+            frame,
+            api_key,
+            api_version,
+            length: length.0,
+        })
+    }
+
     /// deserialize bytes into an API response frame
     #[instrument(skip_all)]
     pub fn response_from_bytes(bytes: impl Buf, api_key: i16, api_version: i16) -> Result<Frame> {
@@ -778,6 +822,9 @@ impl TryFrom<&i16> for ErrorCode {
             117 => Ok(Self::UnknownSubscriptionId),
             118 => Ok(Self::TelemetryTooLarge),
             119 => Ok(Self::InvalidRegistration),
+            121 => Ok(Self::InvalidRecordState),
+            122 => Ok(Self::ShareSessionNotFound),
+            123 => Ok(Self::InvalidShareSessionEpoch),
             otherwise => Err(Error::UnknownApiErrorCode(*otherwise)),
         }
     }
@@ -914,6 +961,9 @@ impl From<&ErrorCode> for i16 {
             ErrorCode::UnknownSubscriptionId => 117,
             ErrorCode::TelemetryTooLarge => 118,
             ErrorCode::InvalidRegistration => 119,
+            ErrorCode::InvalidRecordState => 121,
+            ErrorCode::ShareSessionNotFound => 122,
+            ErrorCode::InvalidShareSessionEpoch => 123,
         }
     }
 }
@@ -1270,6 +1320,14 @@ impl Display for ErrorCode {
             ErrorCode::InvalidRegistration => {
                 f.write_str("The controller has considered the broker registration to be invalid.")
             }
+            ErrorCode::InvalidRecordState => f.write_str(
+                "The record state is invalid. The acknowledgement of delivery could not be \
+                 completed.",
+            ),
+            ErrorCode::ShareSessionNotFound => f.write_str("The share session was not found."),
+            ErrorCode::InvalidShareSessionEpoch => {
+                f.write_str("The share session epoch is invalid.")
+            }
         }
     }
 }
@@ -1402,6 +1460,9 @@ pub enum ErrorCode {
     UnknownSubscriptionId,
     TelemetryTooLarge,
     InvalidRegistration,
+    InvalidRecordState,
+    ShareSessionNotFound,
+    InvalidShareSessionEpoch,
 }
 
 #[derive(
@@ -1547,6 +1608,87 @@ impl From<Compression> for i16 {
     }
 }
 
+impl str::FromStr for Compression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "uncompressed" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "snappy" => Ok(Self::Snappy),
+            "lz4" => Ok(Self::Lz4),
+            "zstd" => Ok(Self::Zstd),
+            otherwise => Err(Error::Message(format!("unknown compression: {otherwise}"))),
+        }
+    }
+}
+
+// https://github.com/xerial/snappy-java/tree/master?tab=readme-ov-file#compatibility-notes
+const XERIAL_MAGIC: &[u8] = b"\x82SNAPPY\0";
+const XERIAL_VERSION: i32 = 1;
+const XERIAL_COMPATIBLE_VERSION: i32 = 1;
+const XERIAL_HEADER_SIZE: usize = XERIAL_MAGIC.len() + 8;
+const XERIAL_BLOCK_SIZE: usize = 32 * 1024;
+
+/// Decompress snappy with or without the xerial framing used by the Java client
+fn snappy_decompress(input: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = snap::raw::Decoder::new();
+
+    let Some(mut remainder) = input
+        .starts_with(XERIAL_MAGIC)
+        .then(|| &input[XERIAL_HEADER_SIZE.min(input.len())..])
+    else {
+        return decoder.decompress_vec(input).map_err(Into::into);
+    };
+
+    debug!(
+        version = ?input.get(8..12),
+        compatible_version = ?input.get(12..16)
+    );
+
+    let mut decompressed = Vec::new();
+
+    while !remainder.is_empty() {
+        let (length, block) = remainder
+            .split_first_chunk::<4>()
+            .map(|(length, block)| (i32::from_be_bytes(*length), block))
+            .ok_or(Error::Message(String::from(
+                "truncated xerial snappy block length",
+            )))?;
+
+        let (compressed, following) = usize::try_from(length)
+            .ok()
+            .and_then(|length| block.split_at_checked(length))
+            .ok_or(Error::Message(format!(
+                "truncated xerial snappy block: {length}"
+            )))?;
+
+        decompressed.extend(decoder.decompress_vec(compressed)?);
+        remainder = following;
+    }
+
+    Ok(decompressed)
+}
+
+/// Compress snappy with the xerial framing used by the Java client
+pub(crate) fn snappy_compress(input: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = snap::raw::Encoder::new();
+
+    let mut compressed =
+        Vec::with_capacity(XERIAL_HEADER_SIZE + snap::raw::max_compress_len(input.len()));
+    compressed.extend_from_slice(XERIAL_MAGIC);
+    compressed.extend_from_slice(&XERIAL_VERSION.to_be_bytes());
+    compressed.extend_from_slice(&XERIAL_COMPATIBLE_VERSION.to_be_bytes());
+
+    for block in input.chunks(XERIAL_BLOCK_SIZE) {
+        let block = encoder.compress_vec(block)?;
+        compressed.extend_from_slice(&i32::try_from(block.len())?.to_be_bytes());
+        compressed.extend(block);
+    }
+
+    Ok(compressed)
+}
+
 impl Compression {
     fn inflator(&self, mut deflated: impl BufRead + 'static) -> Result<Box<dyn Read>> {
         match self {
@@ -1557,35 +1699,7 @@ impl Compression {
                 _ = deflated.read_to_end(&mut input)?;
                 debug!(?input);
 
-                let mut decoder = snap::raw::Decoder::new();
-
-                decoder
-                    .decompress_vec(
-                        // https://github.com/xerial/snappy-java/tree/master?tab=readme-ov-file#compatibility-notes
-                        if input.starts_with(b"\x82SNAPPY\0") {
-                            if let (b"\x82SNAPPY\0", remainder) = input.split_at(8) {
-                                let (version, remainder) = remainder.split_at(4);
-                                let version: i32 = version.try_into().map(i32::from_be_bytes)?;
-
-                                let (compatible_version, remainder) = remainder.split_at(4);
-                                let compatible_version: i32 =
-                                    compatible_version.try_into().map(i32::from_be_bytes)?;
-
-                                let (block_size, _) = remainder.split_at(4);
-                                let block_size: i32 =
-                                    block_size.try_into().map(i32::from_be_bytes)?;
-
-                                debug!(version, compatible_version, block_size);
-                            }
-
-                            let skip_header = &input[20..];
-                            debug!(?skip_header);
-                            skip_header
-                        } else {
-                            &input[..]
-                        },
-                    )
-                    .map_err(Into::into)
+                snappy_decompress(&input)
                     .map(Bytes::from)
                     .map(|bytes| bytes.reader())
                     .map(Box::new)
@@ -1753,6 +1867,19 @@ pub struct EndTransactionMarker {
     pub coordinator_epoch: i32,
 }
 
+impl EndTransactionMarker {
+    pub fn version(self, version: i16) -> Self {
+        Self { version, ..self }
+    }
+
+    pub fn coordinator_epoch(self, coordinator_epoch: i32) -> Self {
+        Self {
+            coordinator_epoch,
+            ..self
+        }
+    }
+}
+
 impl TryFrom<Bytes> for EndTransactionMarker {
     type Error = Error;
 
@@ -2010,6 +2137,190 @@ impl From<OpType> for i8 {
     }
 }
 
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+/// The type of resource protected by an ACL.
+pub enum AclResourceType {
+    #[default]
+    Unknown,
+    Any,
+    Topic,
+    Group,
+    Cluster,
+    TransactionalId,
+    DelegationToken,
+    User,
+}
+
+impl From<i8> for AclResourceType {
+    fn from(value: i8) -> Self {
+        match value {
+            1 => Self::Any,
+            2 => Self::Topic,
+            3 => Self::Group,
+            4 => Self::Cluster,
+            5 => Self::TransactionalId,
+            6 => Self::DelegationToken,
+            7 => Self::User,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<AclResourceType> for i8 {
+    fn from(value: AclResourceType) -> Self {
+        match value {
+            AclResourceType::Unknown => 0,
+            AclResourceType::Any => 1,
+            AclResourceType::Topic => 2,
+            AclResourceType::Group => 3,
+            AclResourceType::Cluster => 4,
+            AclResourceType::TransactionalId => 5,
+            AclResourceType::DelegationToken => 6,
+            AclResourceType::User => 7,
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+/// How the resource name of an ACL is matched.
+pub enum AclPatternType {
+    #[default]
+    Unknown,
+    Any,
+    Match,
+    Literal,
+    Prefixed,
+}
+
+impl From<i8> for AclPatternType {
+    fn from(value: i8) -> Self {
+        match value {
+            1 => Self::Any,
+            2 => Self::Match,
+            3 => Self::Literal,
+            4 => Self::Prefixed,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<AclPatternType> for i8 {
+    fn from(value: AclPatternType) -> Self {
+        match value {
+            AclPatternType::Unknown => 0,
+            AclPatternType::Any => 1,
+            AclPatternType::Match => 2,
+            AclPatternType::Literal => 3,
+            AclPatternType::Prefixed => 4,
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+/// The operation permitted or denied by an ACL.
+pub enum AclOperation {
+    #[default]
+    Unknown,
+    Any,
+    All,
+    Read,
+    Write,
+    Create,
+    Delete,
+    Alter,
+    Describe,
+    ClusterAction,
+    DescribeConfigs,
+    AlterConfigs,
+    IdempotentWrite,
+    CreateTokens,
+    DescribeTokens,
+}
+
+impl From<i8> for AclOperation {
+    fn from(value: i8) -> Self {
+        match value {
+            1 => Self::Any,
+            2 => Self::All,
+            3 => Self::Read,
+            4 => Self::Write,
+            5 => Self::Create,
+            6 => Self::Delete,
+            7 => Self::Alter,
+            8 => Self::Describe,
+            9 => Self::ClusterAction,
+            10 => Self::DescribeConfigs,
+            11 => Self::AlterConfigs,
+            12 => Self::IdempotentWrite,
+            13 => Self::CreateTokens,
+            14 => Self::DescribeTokens,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<AclOperation> for i8 {
+    fn from(value: AclOperation) -> Self {
+        match value {
+            AclOperation::Unknown => 0,
+            AclOperation::Any => 1,
+            AclOperation::All => 2,
+            AclOperation::Read => 3,
+            AclOperation::Write => 4,
+            AclOperation::Create => 5,
+            AclOperation::Delete => 6,
+            AclOperation::Alter => 7,
+            AclOperation::Describe => 8,
+            AclOperation::ClusterAction => 9,
+            AclOperation::DescribeConfigs => 10,
+            AclOperation::AlterConfigs => 11,
+            AclOperation::IdempotentWrite => 12,
+            AclOperation::CreateTokens => 13,
+            AclOperation::DescribeTokens => 14,
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+/// Whether an ACL allows or denies an operation.
+pub enum AclPermissionType {
+    #[default]
+    Unknown,
+    Any,
+    Deny,
+    Allow,
+}
+
+impl From<i8> for AclPermissionType {
+    fn from(value: i8) -> Self {
+        match value {
+            1 => Self::Any,
+            2 => Self::Deny,
+            3 => Self::Allow,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<AclPermissionType> for i8 {
+    fn from(value: AclPermissionType) -> Self {
+        match value {
+            AclPermissionType::Unknown => 0,
+            AclPermissionType::Any => 1,
+            AclPermissionType::Deny => 2,
+            AclPermissionType::Allow => 3,
+        }
+    }
+}
+
 /// convert a Kafka timestamp into system time
 pub fn to_system_time(timestamp: i64) -> Result<SystemTime> {
     u64::try_from(timestamp)
@@ -2074,10 +2385,92 @@ pub trait Decode: Sized {
     fn decode(encoded: &mut Bytes) -> Result<Self>;
 }
 
+/// An API response [`Frame`] of a known length, encoded a window at a time
+#[derive(Clone, Debug)]
+pub struct EncodedResponse {
+    frame: Frame,
+    api_key: i16,
+    api_version: i16,
+    length: usize,
+}
+
+impl EncodedResponse {
+    /// The length of the encoded frame, including its size
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Encode the bytes of the frame within a range
+    #[instrument(skip_all, fields(start = range.start, end = range.end))]
+    pub fn window(&self, range: Range<usize>) -> Result<Bytes> {
+        let start = SystemTime::now();
+
+        let mut window = Window::new(range);
+
+        let encoded = self.frame.serialize(&mut Encoder::response(
+            &mut window,
+            self.api_key,
+            self.api_version,
+        ));
+
+        match encoded {
+            // the encoder is stopped once the window has been written
+            Err(_) if window.is_complete() => (),
+            otherwise => otherwise?,
+        }
+
+        debug!(
+            len = window.buffer.len(),
+            elapsed_millis = Frame::elapsed_millis(start)
+        );
+
+        Ok(window.buffer.freeze())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn encoded_response_in_windows() -> Result<()> {
+        let body = || {
+            MetadataResponse::default()
+                .brokers(Some([].into()))
+                .topics(Some([].into()))
+                .cluster_id(Some("abc".into()))
+                .controller_id(Some(111))
+                .throttle_time_ms(Some(0))
+                .cluster_authorized_operations(Some(-1))
+                .into()
+        };
+
+        let header = Header::Response { correlation_id: 6 };
+
+        let expected = Frame::response(header.clone(), body(), MetadataRequest::KEY, 12)?;
+
+        let encoded = Frame::encoded_response(header, body(), MetadataRequest::KEY, 12)?;
+        assert_eq!(expected.len(), encoded.len());
+        assert_eq!(expected, encoded.window(0..encoded.len())?);
+
+        for window in [1, 3, 7, expected.len() + 1] {
+            let mut written = vec![];
+
+            for start in (0..encoded.len()).step_by(window) {
+                written
+                    .extend_from_slice(&encoded.window(start..encoded.len().min(start + window))?);
+            }
+
+            assert_eq!(expected, Bytes::from(written));
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn batch_attribute() {
         assert_eq!(0, i16::from(BatchAttribute::default()));
@@ -0,0 +1,142 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Buffer pool
+//!
+//! The buffers that frames are read into, encoded into and written from are
+//! taken from a pool shared by every connection and the encoder, being
+//! returned once a frame has been decoded or written, so that steady state
+//! produce and fetch traffic reuses the same allocations rather than
+//! allocating a fresh buffer for each request.
+//!
+//! A frame that is still referenced elsewhere (e.g., records retained by
+//! storage) is left to be freed as usual. A buffer larger than
+//! [`MAXIMUM_CAPACITY`] is never pooled, with the pool holding at most
+//! [`MAXIMUM_BUFFERS`].
+
+use std::sync::{LazyLock, Mutex};
+
+use bytes::{Bytes, BytesMut};
+
+/// The largest buffer kept by the pool, larger buffers are freed
+pub const MAXIMUM_CAPACITY: usize = 1_048_576;
+
+/// The most buffers kept by the pool
+pub const MAXIMUM_BUFFERS: usize = 256;
+
+static POOL: LazyLock<Pool> = LazyLock::new(Pool::default);
+
+/// An empty buffer from the shared pool with at least the capacity
+pub fn buffer(capacity: usize) -> BytesMut {
+    POOL.buffer(capacity)
+}
+
+/// Return a buffer to the shared pool
+pub fn release(buffer: BytesMut) {
+    POOL.release(buffer)
+}
+
+/// Return the buffer of a frame to the shared pool, when nothing else refers to it
+pub fn reclaim(encoded: Bytes) {
+    POOL.reclaim(encoded)
+}
+
+/// A pool of reusable buffers
+#[derive(Debug, Default)]
+pub struct Pool {
+    free: Mutex<Vec<BytesMut>>,
+}
+
+impl Pool {
+    /// An empty buffer with at least the capacity, preferring a pooled buffer
+    /// that is already large enough
+    pub fn buffer(&self, capacity: usize) -> BytesMut {
+        let mut buffer = self
+            .free
+            .lock()
+            .ok()
+            .and_then(|mut free| {
+                free.iter()
+                    .position(|buffer| buffer.capacity() >= capacity)
+                    .map(|position| free.swap_remove(position))
+                    .or_else(|| free.pop())
+            })
+            .unwrap_or_default();
+
+        buffer.reserve(capacity);
+        buffer
+    }
+
+    pub fn release(&self, mut buffer: BytesMut) {
+        if buffer.capacity() == 0 || buffer.capacity() > MAXIMUM_CAPACITY {
+            return;
+        }
+
+        buffer.clear();
+
+        if let Ok(mut free) = self.free.lock()
+            && free.len() < MAXIMUM_BUFFERS
+        {
+            free.push(buffer);
+        }
+    }
+
+    pub fn reclaim(&self, encoded: Bytes) {
+        if let Ok(buffer) = encoded.try_into_mut() {
+            self.release(buffer)
+        }
+    }
+
+    /// The number of buffers in this pool
+    pub fn len(&self) -> usize {
+        self.free.lock().map_or(0, |free| free.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused() {
+        let pool = Pool::default();
+
+        let mut buffer = pool.buffer(1_024);
+        buffer.extend_from_slice(b"Lorem ipsum dolor sit amet");
+        let allocation = buffer.as_ptr();
+
+        let encoded = buffer.freeze();
+
+        // still referenced elsewhere
+        let retained = encoded.clone();
+        pool.reclaim(encoded);
+        assert!(pool.is_empty());
+
+        pool.reclaim(retained);
+        assert_eq!(1, pool.len());
+
+        let buffer = pool.buffer(512);
+        assert!(buffer.is_empty());
+        assert_eq!(allocation, buffer.as_ptr());
+        assert!(pool.is_empty());
+
+        // too large to be pooled
+        pool.release(BytesMut::with_capacity(MAXIMUM_CAPACITY + 1));
+        assert!(pool.is_empty());
+    }
+}
@@ -15,7 +15,7 @@
 //! Deflated (compressed) Kafka Records
 use std::{fmt::Formatter, result};
 
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut, TryGetError};
 use flate2::write::GzEncoder;
 use serde::{
     Deserialize, Deserializer, Serialize,
@@ -179,22 +179,17 @@ impl TryFrom<Vec<u8>> for Batch {
 
 impl TryFrom<Bytes> for Batch {
     type Error = Error;
-    fn try_from(mut encoded: Bytes) -> result::Result<Self, Self::Error> {
-        let base_offset = encoded.try_get_i64()?;
-        let batch_length = encoded.try_get_i32()?;
-
-        let partition_leader_epoch = encoded.try_get_i32()?;
-        let magic = encoded.try_get_i8()?;
-        let crc = encoded.try_get_u32()?;
+    fn try_from(encoded: Bytes) -> result::Result<Self, Self::Error> {
+        let batch = Self::from_encoded(encoded.clone())?;
 
-        let crc_data_size = usize::try_from(batch_length).map(|batch_length| {
+        let crc_data_size = usize::try_from(batch.batch_length).map(|batch_length| {
             batch_length
-                - size_of_val(&partition_leader_epoch)
-                - size_of_val(&magic)
-                - size_of_val(&crc)
+                - size_of_val(&batch.partition_leader_epoch)
+                - size_of_val(&batch.magic)
+                - size_of_val(&batch.crc)
         })?;
 
-        let crc_data = &encoded[..crc_data_size];
+        let crc_data = &encoded[CRC_DATA_OFFSET..CRC_DATA_OFFSET + crc_data_size];
 
         let computed = {
             let mut digest = crc_fast::Digest::new(crc_fast::CrcAlgorithm::Crc32Iscsi);
@@ -203,41 +198,10 @@ impl TryFrom<Bytes> for Batch {
             digest.finalize() as u32
         };
 
-        if computed != crc {
-            error!(crc, computed);
+        if computed != batch.crc {
+            error!(crc = batch.crc, computed);
         }
 
-        let attributes = encoded.try_get_i16()?;
-        let last_offset_delta = encoded.try_get_i32()?;
-        let base_timestamp = encoded.try_get_i64()?;
-        let max_timestamp = encoded.try_get_i64()?;
-        let producer_id = encoded.try_get_i64()?;
-        let producer_epoch = encoded.try_get_i16()?;
-        let base_sequence = encoded.try_get_i32()?;
-        let record_count = encoded.try_get_u32()?;
-
-        let record_data_size =
-            usize::try_from(batch_length).map(|batch_length| batch_length - FIXED_BATCH_LENGTH)?;
-
-        let record_data = encoded.slice(..record_data_size);
-
-        let batch = Batch {
-            base_offset,
-            batch_length,
-            partition_leader_epoch,
-            magic,
-            crc,
-            attributes,
-            last_offset_delta,
-            base_timestamp,
-            max_timestamp,
-            producer_id,
-            producer_epoch,
-            base_sequence,
-            record_count,
-            record_data,
-        };
-
         Ok(batch)
     }
 }
@@ -323,6 +287,85 @@ impl Batch {
     pub fn is_idempotent(&self) -> bool {
         self.producer_id != -1 && self.base_sequence != -1
     }
+
+    /// This batch without the producer id, epoch and sequence, recomputing the CRC
+    pub fn without_producer(self) -> Result<Self> {
+        let (base_offset, partition_leader_epoch, magic) =
+            (self.base_offset, self.partition_leader_epoch, self.magic);
+
+        CrcData {
+            producer_id: -1,
+            producer_epoch: -1,
+            base_sequence: -1,
+            ..CrcData::from(&self)
+        }
+        .into_batch(base_offset, partition_leader_epoch, magic)
+    }
+
+    /// The CRC of the content of this batch, which should equal its `crc`
+    pub fn computed_crc(&self) -> Result<u32> {
+        CrcData::from(self).crc()
+    }
+
+    /// A batch sharing its record data with the encoded bytes, without
+    /// verifying the CRC, for a batch that was verified when produced
+    pub fn from_encoded(mut encoded: Bytes) -> Result<Self> {
+        let base_offset = encoded.try_get_i64()?;
+        let batch_length = encoded.try_get_i32()?;
+        let partition_leader_epoch = encoded.try_get_i32()?;
+        let magic = encoded.try_get_i8()?;
+        let crc = encoded.try_get_u32()?;
+        let attributes = encoded.try_get_i16()?;
+        let last_offset_delta = encoded.try_get_i32()?;
+        let base_timestamp = encoded.try_get_i64()?;
+        let max_timestamp = encoded.try_get_i64()?;
+        let producer_id = encoded.try_get_i64()?;
+        let producer_epoch = encoded.try_get_i16()?;
+        let base_sequence = encoded.try_get_i32()?;
+        let record_count = encoded.try_get_u32()?;
+
+        let record_data_size =
+            usize::try_from(batch_length).map(|batch_length| batch_length - FIXED_BATCH_LENGTH)?;
+
+        if encoded.len() < record_data_size {
+            return Err(TryGetError {
+                requested: record_data_size,
+                available: encoded.len(),
+            }
+            .into());
+        }
+
+        Ok(Self {
+            base_offset,
+            batch_length,
+            partition_leader_epoch,
+            magic,
+            crc,
+            attributes,
+            last_offset_delta,
+            base_timestamp,
+            max_timestamp,
+            producer_id,
+            producer_epoch,
+            base_sequence,
+            record_count,
+            record_data: encoded.slice(..record_data_size),
+        })
+    }
+
+    /// Encoded batch bytes with this base offset, which are only copied
+    /// when their base offset differs
+    pub fn rebase_encoded(encoded: Bytes, base_offset: i64) -> Result<Bytes> {
+        let existing = (&encoded[..]).try_get_i64()?;
+
+        if existing == base_offset {
+            Ok(encoded)
+        } else {
+            let mut rebased = BytesMut::from(encoded);
+            rebased[..size_of_val(&base_offset)].copy_from_slice(&base_offset.to_be_bytes());
+            Ok(rebased.freeze())
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -419,8 +462,10 @@ fn into_record_data(records: &[Record], compression: Compression) -> Result<Byte
                 record.serialize(&mut encoder)?;
             }
 
-            let (w, _) = lz4.finish();
-            Ok(Bytes::from(w.into_inner()))
+            let (w, finished) = lz4.finish();
+            finished
+                .map(|()| Bytes::from(w.into_inner()))
+                .map_err(Into::into)
         }
 
         Compression::Zstd => {
@@ -437,7 +482,16 @@ fn into_record_data(records: &[Record], compression: Compression) -> Result<Byte
                 .map_err(Into::into)
         }
 
-        _ => todo!(),
+        Compression::Snappy => {
+            let mut record_data = BytesMut::new().writer();
+            let mut encoder = Encoder::new(&mut record_data);
+
+            for record in records {
+                record.serialize(&mut encoder)?;
+            }
+
+            crate::snappy_compress(&record_data.into_inner()).map(Bytes::from)
+        }
     }
 }
 
@@ -535,6 +589,19 @@ impl TryFrom<&Batch> for Vec<Record> {
     }
 }
 
+/// The offset of the data covered by the CRC of an encoded batch
+const CRC_DATA_OFFSET: usize =
+    // base offset
+    size_of::<i64>()
+    // batch length
+    + size_of::<i32>()
+    // partition leader epoch
+    + size_of::<i32>()
+    // magic
+    + size_of::<i8>()
+    // CRC
+    + size_of::<u32>();
+
 const FIXED_BATCH_LENGTH: usize =
     // partition leader epoch
     size_of::<i32>()
@@ -1093,4 +1160,144 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn from_encoded_shares_record_data() -> Result<()> {
+        let batch: Batch = inflated::Batch::builder()
+            .record(Record::builder().value(Bytes::from_static(b"abc").into()))
+            .base_offset(32123)
+            .build()
+            .and_then(TryInto::try_into)?;
+
+        let encoded = Bytes::from(batch.clone());
+
+        let decoded = Batch::from_encoded(encoded.clone())?;
+        assert_eq!(batch, decoded);
+        assert_eq!(
+            encoded[encoded.len() - decoded.record_data.len()..].as_ptr(),
+            decoded.record_data.as_ptr()
+        );
+
+        assert!(Batch::from_encoded(encoded.slice(..encoded.len() - 1)).is_err());
+
+        let unchanged = Batch::rebase_encoded(encoded.clone(), 32123)?;
+        assert_eq!(encoded.as_ptr(), unchanged.as_ptr());
+
+        let rebased = Batch::rebase_encoded(encoded, 45654)?;
+        assert_eq!(45654, Batch::try_from(rebased)?.base_offset);
+
+        Ok(())
+    }
+
+    #[test]
+    fn snappy_xerial_round_trip() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let attributes: i16 = BatchAttribute::default()
+            .compression(Compression::Snappy)
+            .into();
+
+        let mut builder = inflated::Batch::builder()
+            .attributes(attributes)
+            .producer_id(-1)
+            .producer_epoch(-1)
+            .base_sequence(-1);
+
+        // enough records for more than one xerial block
+        for offset_delta in 0..128 {
+            builder = builder.record(
+                Record::builder()
+                    .offset_delta(offset_delta)
+                    .value(Bytes::from_static(LOREM).into()),
+            );
+        }
+
+        let original = builder.build()?;
+
+        let deflated = Batch::try_from(original.clone())?;
+        assert_eq!(Compression::Snappy, deflated.compression()?);
+        assert!(deflated.record_data.starts_with(b"\x82SNAPPY\0"));
+
+        let encoded = Bytes::from(deflated.clone());
+        let decoded = Batch::try_from(&encoded[..])?;
+        assert_eq!(deflated, decoded);
+
+        let inflated = inflated::Batch::try_from(decoded)?;
+        assert_eq!(original.records, inflated.records);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lz4_round_trip() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let attributes: i16 = BatchAttribute::default()
+            .compression(Compression::Lz4)
+            .into();
+
+        let mut builder = inflated::Batch::builder()
+            .attributes(attributes)
+            .producer_id(-1)
+            .producer_epoch(-1)
+            .base_sequence(-1)
+            .base_timestamp(1_722_000_000_000)
+            .max_timestamp(1_722_000_000_000);
+
+        for offset_delta in 0..32 {
+            builder = builder.record(
+                Record::builder()
+                    .offset_delta(offset_delta)
+                    .key(Bytes::from(format!("key-{offset_delta}")).into())
+                    .value(Bytes::from_static(LOREM).into())
+                    .header(
+                        crate::record::header::Header::builder()
+                            .key(Bytes::from_static(b"trace"))
+                            .value(Bytes::from(offset_delta.to_string())),
+                    ),
+            );
+        }
+
+        let original = builder.build()?;
+
+        let deflated = Batch::try_from(original.clone())?;
+        assert_eq!(attributes, deflated.attributes);
+        assert_eq!(Compression::Lz4, deflated.compression()?);
+        assert!(deflated.record_data.len() < 32 * LOREM.len());
+
+        let encoded = Bytes::from(deflated.clone());
+        let decoded = Batch::try_from(&encoded[..])?;
+        assert_eq!(deflated, decoded);
+
+        let inflated = inflated::Batch::try_from(decoded)?;
+        assert_eq!(original.records, inflated.records);
+
+        Ok(())
+    }
+
+    #[test]
+    fn without_producer() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let idempotent = inflated::Batch::builder()
+            .base_offset(54_345)
+            .producer_id(6)
+            .producer_epoch(2)
+            .base_sequence(32)
+            .record(Record::builder().value(Bytes::from_static(LOREM).into()))
+            .build()
+            .and_then(Batch::try_from)?;
+        assert!(idempotent.is_idempotent());
+
+        let anonymous = idempotent.clone().without_producer()?;
+        assert!(!anonymous.is_idempotent());
+        assert_eq!(-1, anonymous.producer_id);
+        assert_eq!(idempotent.base_offset, anonymous.base_offset);
+        assert_eq!(idempotent.record_data, anonymous.record_data);
+
+        let encoded = Bytes::from(anonymous.clone());
+        assert_eq!(anonymous, Batch::try_from(&encoded[..])?);
+
+        Ok(())
+    }
 }
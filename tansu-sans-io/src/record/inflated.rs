@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{
-    Compression, Encoder, Error, Result,
+    BatchAttribute, Compression, ControlBatch, Encoder, EndTransactionMarker, Error, Result,
     primitive::ByteSize,
     record::{Record, codec::Sequence, deflated},
     to_timestamp,
@@ -162,6 +162,51 @@ impl Batch {
         self.into()
     }
 
+    /// A builder of a control batch, with a single record ending a
+    /// transaction with a commit or abort marker. The producer id and epoch
+    /// of the transaction should be set on the builder.
+    pub fn control(control: ControlBatch, marker: EndTransactionMarker) -> Result<Builder> {
+        let key = Bytes::try_from(control)?;
+        let value = Bytes::try_from(marker)?;
+
+        Ok(Builder::default()
+            .attributes(
+                BatchAttribute::default()
+                    .control(true)
+                    .transaction(true)
+                    .into(),
+            )
+            .base_sequence(-1)
+            .record(Record::builder().key(Some(key)).value(Some(value))))
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        BatchAttribute::try_from(self.attributes).is_ok_and(|attributes| attributes.transaction)
+    }
+
+    pub fn is_control(&self) -> bool {
+        BatchAttribute::try_from(self.attributes).is_ok_and(|attributes| attributes.control)
+    }
+
+    /// The marker of a control batch, or `None` for a batch of records
+    pub fn marker(&self) -> Result<Option<(ControlBatch, EndTransactionMarker)>> {
+        if !self.is_control() {
+            return Ok(None);
+        }
+
+        let Some(record) = self.records.first() else {
+            return Ok(None);
+        };
+
+        let control = record.key().map(ControlBatch::try_from).transpose()?;
+        let marker = record
+            .value()
+            .map(EndTransactionMarker::try_from)
+            .transpose()?;
+
+        Ok(control.zip(marker))
+    }
+
     pub fn max_offset(&self) -> i64 {
         self.base_offset + i64::from(self.last_offset_delta)
     }
@@ -450,6 +495,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn control_batch() -> Result<()> {
+        let deflated = Batch::control(
+            ControlBatch::default().abort(),
+            EndTransactionMarker::default().coordinator_epoch(6),
+        )?
+        .producer_id(54345)
+        .producer_epoch(3)
+        .build()
+        .and_then(deflated::Batch::try_from)?;
+
+        assert!(deflated.is_control());
+        assert!(deflated.is_transactional());
+        assert_eq!(-1, deflated.base_sequence);
+
+        let inflated = Batch::try_from(deflated)?;
+        assert_eq!(54345, inflated.producer_id);
+        assert_eq!(3, inflated.producer_epoch);
+
+        let (control, marker) = inflated.marker()?.expect("marker");
+        assert!(control.is_abort());
+        assert_eq!(6, marker.coordinator_epoch);
+
+        assert_eq!(
+            None,
+            Batch::builder()
+                .record(Record::builder().value(Some(Bytes::from_static(b"abc"))))
+                .build()?
+                .marker()?
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn batch_decode() -> Result<()> {
         let encoded = vec![
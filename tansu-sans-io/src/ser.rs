@@ -15,9 +15,11 @@
 use std::{
     collections::VecDeque,
     fmt,
-    io::{Cursor, Write},
+    io::{self, Write},
+    ops::Range,
 };
 
+use bytes::BytesMut;
 use serde::{
     Serialize, Serializer,
     ser::{
@@ -28,7 +30,7 @@ use serde::{
 use tansu_model::{FieldMeta, MessageMeta};
 use tracing::debug;
 
-use crate::{Error, Result, RootMessageMeta};
+use crate::{Error, Result, RootMessageMeta, pool};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 enum Kind {
@@ -81,6 +83,72 @@ impl FieldLookup {
 
 const PARSE_DEPTH: usize = 6;
 
+/// A [`Write`] counting the bytes written, without retaining them
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Length(pub(crate) usize);
+
+impl Write for Length {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`Write`] retaining only the bytes written within a range, stopping the
+/// encoder once the end of the range has been written
+#[derive(Debug)]
+pub(crate) struct Window {
+    range: Range<usize>,
+    position: usize,
+    pub(crate) buffer: BytesMut,
+}
+
+impl Window {
+    pub(crate) fn new(range: Range<usize>) -> Self {
+        Self {
+            buffer: pool::buffer(range.len()),
+            range,
+            position: 0,
+        }
+    }
+
+    /// Whether every byte of the range has been written
+    pub(crate) fn is_complete(&self) -> bool {
+        self.position >= self.range.end
+    }
+}
+
+impl Write for Window {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.is_complete() {
+            return Err(io::Error::from(io::ErrorKind::WriteZero));
+        }
+
+        let start = self
+            .range
+            .start
+            .clamp(self.position, self.position + buf.len());
+        let end = self
+            .range
+            .end
+            .clamp(self.position, self.position + buf.len());
+
+        self.buffer
+            .extend_from_slice(&buf[start - self.position..end - self.position]);
+        self.position += buf.len();
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Meta {
     message: Option<&'static MessageMeta>,
@@ -477,11 +545,12 @@ impl Serializer for &mut Encoder<'_> {
         if self.field.is_some_and(|field| field == "tag_buffer") && !self.is_flexible() {
             Ok(())
         } else if self.is_records() {
-            let mut c = Cursor::new(vec![]);
-            let mut e = Encoder::new(&mut c);
-            value.serialize(&mut e)?;
+            // the length prefix of the records is found by a counting pass,
+            // with the batches then written directly rather than buffered
+            let mut length = Length::default();
+            value.serialize(&mut Encoder::new(&mut length))?;
 
-            u32::try_from(c.position())
+            u32::try_from(length.0)
                 .map_err(Into::into)
                 .and_then(|length| {
                     if self.is_flexible() {
@@ -492,7 +561,7 @@ impl Serializer for &mut Encoder<'_> {
                     }
                 })?;
 
-            self.writer.write_all(&c.into_inner()).map_err(Into::into)
+            value.serialize(&mut Encoder::new(self.writer))
         } else {
             value.serialize(self)
         }
@@ -280,6 +280,41 @@ fn responses_partitions_snapshot_id() {
     assert!(epoch.is_mandatory(Some(snapshot_id.version)));
 }
 
+#[test]
+fn responses_partitions_fetch_hint() {
+    let meta = BTreeMap::from(MESSAGE_META);
+    let message = meta.get(FETCH_RESPONSE).unwrap();
+    let message_fields = BTreeMap::from_iter(message.fields.iter().copied());
+    let responses = message_fields["responses"];
+    let responses_fields = BTreeMap::from_iter(responses.fields.iter().copied());
+    let partitions = responses_fields["partitions"];
+    let partitions_fields = BTreeMap::from_iter(partitions.fields.iter().copied());
+
+    let fetch_hint = partitions_fields["fetch_hint"];
+    assert_eq!(
+        VersionRange {
+            start: 12,
+            end: i16::MAX
+        },
+        fetch_hint.version
+    );
+    assert!(!fetch_hint.is_mandatory(Some(partitions.version)));
+
+    let fetch_hint_fields = BTreeMap::from_iter(fetch_hint.fields.iter().copied());
+
+    for name in ["end_offset", "lag"] {
+        let field = fetch_hint_fields[name];
+        assert_eq!(
+            VersionRange {
+                start: 12,
+                end: i16::MAX
+            },
+            field.version
+        );
+        assert!(field.is_mandatory(Some(fetch_hint.version)));
+    }
+}
+
 #[test]
 fn node_endpoints() {
     let meta = BTreeMap::from(MESSAGE_META);
@@ -0,0 +1,187 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audit of fetched batches against the current schema
+//!
+//! Useful after enabling validation on a topic with historical data: batches
+//! sampled on the fetch path are validated, with topics containing records that
+//! no longer match their current schema reported for quarantine.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use opentelemetry::{KeyValue, metrics::Counter};
+use serde::{Deserialize, Serialize};
+use tansu_sans_io::record::inflated::Batch;
+use tracing::{debug, instrument, warn};
+
+use crate::{METER, Registry, Result, Validator as _};
+
+pub(crate) type AuditCache = Arc<Mutex<BTreeMap<String, AuditReport>>>;
+
+static AUDIT: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("registry_audit")
+        .with_description("The number of fetched batches audited against the current schema")
+        .build()
+});
+
+/// Batches audited for a topic, with the most recent that did not match the current schema
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditReport {
+    pub topic: String,
+    pub audited_batches: u64,
+    pub invalid_batches: u64,
+    pub last_invalid_partition: Option<i32>,
+    pub last_invalid_offset: Option<i64>,
+    pub last_invalid_at: Option<SystemTime>,
+}
+
+impl AuditReport {
+    fn new(topic: &str) -> Self {
+        Self {
+            topic: topic.to_owned(),
+            audited_batches: 0,
+            invalid_batches: 0,
+            last_invalid_partition: None,
+            last_invalid_offset: None,
+            last_invalid_at: None,
+        }
+    }
+}
+
+impl Registry {
+    /// The proportion of fetched batches that should be audited
+    pub fn audit_sample_rate(&self) -> f64 {
+        self.audit_sample_rate
+    }
+
+    /// Audit a fetched batch against the current schema for the topic
+    ///
+    /// Unlike [`Registry::validate`] an invalid batch is not an error, it is
+    /// recorded in the [`Registry::quarantine`] report.
+    #[instrument(skip(self, batch))]
+    pub async fn audit(&self, topic: &str, partition: i32, batch: &Batch) -> Result<()> {
+        let Some(schema) = self.schema(topic).await? else {
+            return Ok(());
+        };
+
+        let valid = schema
+            .validate(batch)
+            .inspect_err(|err| warn!(topic, partition, base_offset = batch.base_offset, ?err))
+            .is_ok();
+
+        AUDIT.add(
+            1,
+            &[
+                KeyValue::new("topic", topic.to_owned()),
+                KeyValue::new("valid", valid),
+            ],
+        );
+
+        self.audits
+            .lock()
+            .map(|mut guard| {
+                let report = guard
+                    .entry(topic.to_owned())
+                    .or_insert_with(|| AuditReport::new(topic));
+
+                report.audited_batches += 1;
+
+                if !valid {
+                    report.invalid_batches += 1;
+                    report.last_invalid_partition = Some(partition);
+                    report.last_invalid_offset = Some(batch.base_offset);
+                    report.last_invalid_at = Some(SystemTime::now());
+                }
+
+                debug!(?report);
+            })
+            .map_err(Into::into)
+    }
+
+    /// Topics with fetched batches that did not match their current schema
+    pub fn quarantine(&self) -> Result<Vec<AuditReport>> {
+        self.audits
+            .lock()
+            .map(|guard| {
+                guard
+                    .values()
+                    .filter(|report| report.invalid_batches > 0)
+                    .cloned()
+                    .collect()
+            })
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use serde_json::json;
+    use tansu_sans_io::record::Record;
+
+    use super::*;
+    use crate::SchemaType;
+
+    #[tokio::test]
+    async fn quarantine() -> Result<()> {
+        let registry = Registry::new(InMemory::new());
+
+        _ = registry
+            .register(
+                "abc",
+                SchemaType::Json,
+                serde_json::to_vec(&json!({
+                    "type": "object",
+                    "properties": {
+                        "key": {
+                            "type": "number",
+                            "multipleOf": 10
+                        }
+                    }
+                }))
+                .map(Bytes::from)?,
+            )
+            .await?;
+
+        let valid = Batch::builder()
+            .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+            .build()?;
+
+        registry.audit("abc", 0, &valid).await?;
+        assert!(registry.quarantine()?.is_empty());
+
+        let invalid = Batch::builder()
+            .base_offset(32123)
+            .record(Record::builder().key(Bytes::from_static(b"545").into()))
+            .build()?;
+
+        registry.audit("abc", 3, &invalid).await?;
+
+        let quarantine = registry.quarantine()?;
+        assert_eq!(1, quarantine.len());
+        assert_eq!("abc", quarantine[0].topic);
+        assert_eq!(2, quarantine[0].audited_batches);
+        assert_eq!(1, quarantine[0].invalid_batches);
+        assert_eq!(Some(3), quarantine[0].last_invalid_partition);
+        assert_eq!(Some(32123), quarantine[0].last_invalid_offset);
+
+        Ok(())
+    }
+}
@@ -25,7 +25,7 @@ use tansu_sans_io::{ErrorCode, record::inflated::Batch};
 use tracing::{debug, error, info};
 use uuid::Uuid;
 
-use crate::{AsJsonValue, AsKafkaRecord, Error, Generator, Result, Validator};
+use crate::{AsJsonValue, AsKafkaRecord, Error, Generator, Result, Validator, json};
 
 #[cfg(any(feature = "parquet", feature = "iceberg", feature = "delta"))]
 use apache_avro::schema::RecordSchema;
@@ -77,6 +77,21 @@ impl Schema {
     }
 }
 
+/// Fingerprint of the normalised form of an AVRO schema
+///
+/// Unlike the Parsing Canonical Form, the normalised form retains `default`,
+/// `doc` and `aliases`, so that a version adding a default to a field is not
+/// identical to the version without it.
+pub(crate) fn fingerprint(encoded: &[u8]) -> Result<String> {
+    _ = AvroSchema::parse_slice(encoded)?;
+
+    serde_json::from_slice::<JsonValue>(encoded)
+        .map_err(Into::into)
+        .and_then(|schema| json::canonical_form(&schema))
+        .inspect(|normalised| debug!(normalised))
+        .map(|normalised| crate::fingerprint(normalised.as_bytes()))
+}
+
 impl TryFrom<Bytes> for Schema {
     type Error = Error;
 
@@ -0,0 +1,584 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import of Confluent and Apicurio registry exports
+//!
+//! An export is a JSON array (or newline delimited JSON) of subject versions,
+//! as returned by `GET /subjects/{subject}/versions/{version}` of a Confluent
+//! registry, or the artifact versions of an Apicurio registry:
+//!
+//! ```json
+//! [{"subject": "orders-value", "version": 1, "id": 42, "schemaType": "JSON", "schema": "..."}]
+//! ```
+//!
+//! Subjects follow the topic name strategy: `{topic}-key` and `{topic}-value`
+//! are combined into the single schema that Tansu uses for a topic, with `key`
+//! and `value` properties (JSON) or fields (Avro). A Protobuf schema is
+//! imported as is, needing `Key` and/or `Value` messages. Any other subject is
+//! imported as the value of a topic with the same name. Soft deleted versions
+//! are not imported.
+//!
+//! Each version is imported with its exported version number, unless that
+//! version of the topic is already registered with a different schema. The id
+//! of each schema is recorded in `ids/{id}`, resolved with
+//! [`Registry::schema_id`], so that data serialized with an embedded schema id
+//! continues to resolve, unless that id is already in use by another schema.
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use object_store::{ObjectStoreExt as _, PutPayload, path::Path};
+use serde::{Deserialize, Deserializer, Serialize, de};
+use serde_json::{Value, json};
+use tracing::{debug, instrument, warn};
+
+use crate::{Error, Registry, Result, SchemaType};
+
+const IDS: &str = "ids";
+
+/// A subject version exported from a Confluent or Apicurio registry
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exported {
+    #[serde(alias = "artifactId")]
+    pub subject: String,
+
+    #[serde(deserialize_with = "version")]
+    pub version: u32,
+
+    #[serde(alias = "globalId", default)]
+    pub id: Option<u32>,
+
+    #[serde(alias = "artifactType", alias = "type", default)]
+    pub schema_type: Option<String>,
+
+    #[serde(alias = "content")]
+    pub schema: String,
+
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// A version that is either a number (Confluent) or a string (Apicurio)
+fn version<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Number(number) => number
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| de::Error::custom(format!("invalid version: {number}"))),
+
+        Value::String(version) => version
+            .parse()
+            .map_err(|_| de::Error::custom(format!("invalid version: {version}"))),
+
+        otherwise => Err(de::Error::custom(format!("invalid version: {otherwise}"))),
+    }
+}
+
+impl Exported {
+    /// The schema type, with Avro when absent as with Confluent
+    fn schema_type(&self) -> Result<SchemaType> {
+        match self
+            .schema_type
+            .as_deref()
+            .map(str::to_ascii_uppercase)
+            .as_deref()
+        {
+            None | Some("AVRO") => Ok(SchemaType::Avro),
+            Some("JSON") => Ok(SchemaType::Json),
+            Some("PROTOBUF") => Ok(SchemaType::Proto),
+            Some(otherwise) => Err(Error::Message(format!(
+                "unsupported schema type: {otherwise}"
+            ))),
+        }
+    }
+}
+
+/// The subject versions of an export
+pub fn exported(encoded: &[u8]) -> Result<Vec<Exported>> {
+    match serde_json::from_slice::<Value>(encoded) {
+        Ok(Value::Array(exported)) => exported
+            .into_iter()
+            .map(|exported| serde_json::from_value(exported).map_err(Into::into))
+            .collect(),
+
+        Ok(Value::Object(mut object)) => {
+            if let Some(exported @ Value::Array(_)) =
+                ["schemas", "subjects", "artifacts", "versions"]
+                    .into_iter()
+                    .find_map(|name| object.remove(name))
+            {
+                serde_json::from_value(exported).map_err(Into::into)
+            } else {
+                serde_json::from_value(Value::Object(object))
+                    .map(|exported| vec![exported])
+                    .map_err(Into::into)
+            }
+        }
+
+        Ok(otherwise) => Err(Error::Message(format!("unexpected export: {otherwise}"))),
+
+        Err(_) => encoded
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.trim_ascii().is_empty())
+            .map(|line| serde_json::from_slice(line).map_err(Into::into))
+            .collect(),
+    }
+}
+
+/// The topic of a schema, resolved from an exported schema id
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SchemaId {
+    pub id: u32,
+    pub topic: String,
+    pub version: u32,
+}
+
+/// The outcome of importing a subject version
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Imported {
+    pub subject: String,
+    pub exported_version: u32,
+    pub id: Option<u32>,
+    pub topic: String,
+    pub version: Option<u32>,
+    pub version_preserved: bool,
+    pub id_preserved: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Part {
+    Key,
+    Value,
+}
+
+/// The topic and part of a subject, using the topic name strategy
+fn topic_part(subject: &str) -> (&str, Part) {
+    if let Some(topic) = subject.strip_suffix("-key") {
+        (topic, Part::Key)
+    } else if let Some(topic) = subject.strip_suffix("-value") {
+        (topic, Part::Value)
+    } else {
+        (subject, Part::Value)
+    }
+}
+
+/// An Avro name for a topic
+fn avro_name(topic: &str) -> String {
+    topic
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if c.is_ascii_alphabetic() || c == '_' || (i > 0 && c.is_ascii_digit()) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Combine the key and value schemas of a topic into a single schema
+fn combine(
+    topic: &str,
+    schema_type: SchemaType,
+    key: Option<&str>,
+    value: Option<&str>,
+) -> Result<Bytes> {
+    match schema_type {
+        SchemaType::Json => {
+            let mut properties = serde_json::Map::new();
+
+            for (name, schema) in [("key", key), ("value", value)] {
+                if let Some(schema) = schema {
+                    _ = properties.insert(name.into(), serde_json::from_str(schema)?);
+                }
+            }
+
+            serde_json::to_vec(&json!({
+                "title": topic,
+                "type": "object",
+                "properties": properties,
+            }))
+            .map(Bytes::from)
+            .map_err(Into::into)
+        }
+
+        SchemaType::Avro => {
+            let mut fields = vec![];
+
+            for (name, schema) in [("key", key), ("value", value)] {
+                if let Some(schema) = schema {
+                    fields.push(json!({
+                        "name": name,
+                        "type": serde_json::from_str::<Value>(schema)?,
+                    }));
+                }
+            }
+
+            serde_json::to_vec(&json!({
+                "type": "record",
+                "name": avro_name(topic),
+                "fields": fields,
+            }))
+            .map(Bytes::from)
+            .map_err(Into::into)
+        }
+
+        SchemaType::Proto => value
+            .or(key)
+            .map(|schema| Bytes::copy_from_slice(schema.as_bytes()))
+            .ok_or(Error::Message(format!("no schema for: {topic}"))),
+    }
+}
+
+fn id_location(id: u32) -> Path {
+    Path::from(format!("{IDS}/{id}"))
+}
+
+impl Registry {
+    /// The topic and version of a schema imported with an exported schema id
+    #[instrument(skip(self), ret)]
+    pub async fn schema_id(&self, id: u32) -> Result<Option<SchemaId>> {
+        let Some(get_result) = self.get(&id_location(id)).await? else {
+            return Ok(None);
+        };
+
+        get_result
+            .bytes()
+            .await
+            .map_err(Into::into)
+            .and_then(|encoded| serde_json::from_slice(&encoded[..]).map_err(Into::into))
+            .map(Some)
+    }
+
+    /// Import the subject versions exported from a Confluent or Apicurio registry
+    #[instrument(skip(self, exported))]
+    pub async fn import(&self, exported: Vec<Exported>) -> Result<Vec<Imported>> {
+        let mut topics = BTreeMap::<String, BTreeMap<Part, Vec<Exported>>>::new();
+
+        for exported in exported.into_iter().filter(|exported| !exported.deleted) {
+            let (topic, part) = topic_part(&exported.subject);
+
+            topics
+                .entry(topic.to_owned())
+                .or_default()
+                .entry(part)
+                .or_default()
+                .push(exported);
+        }
+
+        let mut imported = vec![];
+
+        for (topic, mut parts) in topics {
+            let mut keys = parts.remove(&Part::Key).unwrap_or_default();
+            keys.sort_by_key(|exported| exported.version);
+
+            let mut values = parts.remove(&Part::Value).unwrap_or_default();
+            values.sort_by_key(|exported| exported.version);
+
+            // the latest key is combined with each value version, or each
+            // key version is imported alone when the topic has no values
+            let (latest_key, primary) = if values.is_empty() {
+                (None, keys)
+            } else {
+                (keys.pop(), values)
+            };
+
+            let mut imported_any = false;
+
+            for exported in primary {
+                let outcome = self
+                    .import_version(&topic, latest_key.as_ref(), &exported)
+                    .await;
+
+                imported_any |= outcome.version.is_some();
+                imported.push(outcome);
+            }
+
+            if let Some(latest_key) = latest_key.as_ref()
+                && let Some(version) = imported
+                    .last()
+                    .filter(|last| last.topic == topic)
+                    .and_then(|last| last.version)
+            {
+                let id_preserved = match latest_key.id {
+                    Some(id) => self.preserve_id(id, &topic, version).await?,
+                    None => false,
+                };
+
+                imported.push(Imported {
+                    subject: latest_key.subject.clone(),
+                    exported_version: latest_key.version,
+                    id: latest_key.id,
+                    topic: topic.clone(),
+                    version: Some(version),
+                    version_preserved: false,
+                    id_preserved,
+                    error: None,
+                });
+            }
+
+            if imported_any {
+                self.promote_highest(&topic).await?;
+            }
+        }
+
+        Ok(imported)
+    }
+
+    /// Import a version of a topic, recording errors in the outcome
+    async fn import_version(
+        &self,
+        topic: &str,
+        key: Option<&Exported>,
+        exported: &Exported,
+    ) -> Imported {
+        let mut imported = Imported {
+            subject: exported.subject.clone(),
+            exported_version: exported.version,
+            id: exported.id,
+            topic: topic.to_owned(),
+            ..Default::default()
+        };
+
+        let outcome = async {
+            let schema_type = exported.schema_type()?;
+
+            let key = key
+                .filter(|key| {
+                    key.schema_type()
+                        .is_ok_and(|key_type| key_type == schema_type)
+                })
+                .map(|key| key.schema.as_str());
+
+            let (key, value) = if topic_part(&exported.subject).1 == Part::Key {
+                (Some(exported.schema.as_str()), None)
+            } else {
+                (key, Some(exported.schema.as_str()))
+            };
+
+            let encoded = combine(topic, schema_type, key, value)?;
+            let (version, version_preserved) = self
+                .put_exported(topic, exported.version, schema_type, encoded)
+                .await?;
+
+            let id_preserved = match exported.id {
+                Some(id) => self.preserve_id(id, topic, version).await?,
+                None => false,
+            };
+
+            Ok::<_, Error>((version, version_preserved, id_preserved))
+        }
+        .await;
+
+        match outcome {
+            Ok((version, version_preserved, id_preserved)) => {
+                imported.version = Some(version);
+                imported.version_preserved = version_preserved;
+                imported.id_preserved = id_preserved;
+            }
+
+            Err(err) => {
+                warn!(topic, subject = exported.subject, ?err);
+                imported.error = Some(err.to_string());
+            }
+        }
+
+        imported
+    }
+
+    /// Store an imported schema as the exported version where possible,
+    /// returning the version and whether it was preserved
+    async fn put_exported(
+        &self,
+        topic: &str,
+        exported: u32,
+        schema_type: SchemaType,
+        encoded: Bytes,
+    ) -> Result<(u32, bool)> {
+        _ = schema_type.compile(encoded.clone())?;
+        let fingerprint = schema_type.fingerprint(&encoded)?;
+
+        if let Some(existing) = self.identical(topic, schema_type, &fingerprint).await? {
+            debug!(topic, existing);
+            return Ok((existing, existing == exported));
+        }
+
+        let taken = self.issued(topic).await?.contains(&exported);
+
+        let version = if taken {
+            self.next_version(topic).await?
+        } else {
+            exported
+        };
+
+        self.put_version(topic, version, schema_type, &fingerprint, encoded)
+            .await
+            .and(Ok((version, !taken)))
+    }
+
+    /// Record the topic and version of an exported schema id, returning false
+    /// when the id is already in use by another schema
+    async fn preserve_id(&self, id: u32, topic: &str, version: u32) -> Result<bool> {
+        if let Some(existing) = self.schema_id(id).await? {
+            return Ok(existing.topic == topic && existing.version == version);
+        }
+
+        let schema_id = SchemaId {
+            id,
+            topic: topic.to_owned(),
+            version,
+        };
+
+        self.object_store
+            .put(
+                &id_location(id),
+                PutPayload::from(serde_json::to_vec(&schema_id)?),
+            )
+            .await
+            .map(|put_result| debug!(?put_result))
+            .and(Ok(true))
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[test]
+    fn confluent_and_apicurio() -> Result<()> {
+        let confluent = exported(
+            br#"[{"subject": "orders-value", "version": 2, "id": 42, "schemaType": "JSON", "schema": "{}"}]"#,
+        )?;
+
+        assert_eq!(2, confluent[0].version);
+        assert_eq!(Some(42), confluent[0].id);
+        assert_eq!(SchemaType::Json, confluent[0].schema_type()?);
+
+        let apicurio = exported(
+            br#"{"artifacts": [{"artifactId": "orders-value", "version": "3", "globalId": 7, "artifactType": "AVRO", "content": "\"string\""}]}"#,
+        )?;
+
+        assert_eq!("orders-value", apicurio[0].subject);
+        assert_eq!(3, apicurio[0].version);
+        assert_eq!(Some(7), apicurio[0].id);
+        assert_eq!(SchemaType::Avro, apicurio[0].schema_type()?);
+
+        let lines = exported(
+            b"{\"subject\": \"a\", \"version\": 1, \"schema\": \"\\\"int\\\"\"}\n\n{\"subject\": \"b\", \"version\": 1, \"schema\": \"\\\"long\\\"\"}\n",
+        )?;
+
+        assert_eq!(2, lines.len());
+        assert_eq!(SchemaType::Avro, lines[1].schema_type()?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn key_and_value_combined() -> Result<()> {
+        let registry = Registry::new(InMemory::new());
+
+        let imported = registry
+            .import(exported(
+                br#"[
+                    {"subject": "orders-key", "version": 1, "id": 10, "schemaType": "JSON", "schema": "{\"type\": \"string\"}"},
+                    {"subject": "orders-value", "version": 1, "id": 11, "schemaType": "JSON", "schema": "{\"type\": \"object\"}"},
+                    {"subject": "orders-value", "version": 3, "id": 12, "schemaType": "JSON", "schema": "{\"type\": \"object\", \"required\": [\"id\"]}"},
+                    {"subject": "orders-value", "version": 4, "id": 13, "schemaType": "JSON", "schema": "{}", "deleted": true}
+                ]"#,
+            )?)
+            .await?;
+
+        assert!(imported.iter().all(|imported| imported.error.is_none()));
+        assert_eq!(
+            vec![Some(1), Some(3), Some(3)],
+            imported
+                .iter()
+                .map(|imported| imported.version)
+                .collect::<Vec<_>>()
+        );
+        assert!(imported.iter().all(|imported| imported.id_preserved));
+
+        assert_eq!(
+            vec![1, 3],
+            registry
+                .versions("orders")
+                .await?
+                .iter()
+                .map(|version| version.version)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(registry.schema("orders").await?.is_some());
+
+        assert_eq!(
+            Some(SchemaId {
+                id: 12,
+                topic: "orders".into(),
+                version: 3
+            }),
+            registry.schema_id(12).await?
+        );
+
+        assert_eq!(
+            Some(SchemaId {
+                id: 10,
+                topic: "orders".into(),
+                version: 3
+            }),
+            registry.schema_id(10).await?
+        );
+
+        assert_eq!(None, registry.schema_id(13).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conflicts_not_preserved() -> Result<()> {
+        let registry = Registry::new(InMemory::new());
+
+        _ = registry
+            .import(exported(
+                br#"[{"subject": "orders-value", "version": 1, "id": 5, "schema": "\"string\""}]"#,
+            )?)
+            .await?;
+
+        let imported = registry
+            .import(exported(
+                br#"[
+                    {"subject": "orders-value", "version": 1, "id": 5, "schema": "\"long\""},
+                    {"subject": "invalid-value", "version": 1, "id": 6, "schema": "{\"type\": \"unknown\"}"}
+                ]"#,
+            )?)
+            .await?;
+
+        assert_eq!("invalid", imported[0].topic);
+        assert!(imported[0].error.is_some());
+
+        assert_eq!(Some(2), imported[1].version);
+        assert!(!imported[1].version_preserved);
+        assert!(!imported[1].id_preserved);
+
+        Ok(())
+    }
+}
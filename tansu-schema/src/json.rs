@@ -56,6 +56,40 @@ impl AsRef<str> for MessageKind {
     }
 }
 
+/// JSON canonical form, with object members sorted by name and insignificant whitespace removed
+pub(crate) fn canonical_form(value: &Value) -> Result<String> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .map(canonical_form)
+            .collect::<Result<Vec<_>>>()
+            .map(|values| format!("[{}]", values.join(","))),
+
+        Value::Object(object) => object
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, value)| {
+                serde_json::to_string(name)
+                    .map_err(Into::into)
+                    .and_then(|name| canonical_form(value).map(|value| format!("{name}:{value}")))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|members| format!("{{{}}}", members.join(","))),
+
+        scalar => serde_json::to_string(scalar).map_err(Into::into),
+    }
+}
+
+/// Fingerprint of the canonical form of a JSON schema
+pub(crate) fn fingerprint(encoded: &[u8]) -> Result<String> {
+    serde_json::from_slice::<Value>(encoded)
+        .map_err(Into::into)
+        .and_then(|schema| canonical_form(&schema))
+        .inspect(|canonical_form| debug!(canonical_form))
+        .map(|canonical_form| crate::fingerprint(canonical_form.as_bytes()))
+}
+
 fn validate(validator: Option<&jsonschema::Validator>, encoded: Option<Bytes>) -> Result<()> {
     debug!(validator = ?validator, ?encoded);
 
@@ -163,10 +197,30 @@ impl Generator for Schema {
     }
 }
 
+/// The JSON value of an encoded key or value, or null when absent
+fn decode(encoded: Option<&Bytes>) -> Result<Value> {
+    encoded.map_or(Ok(Value::Null), |encoded| {
+        serde_json::from_slice(encoded).map_err(Into::into)
+    })
+}
+
 impl AsJsonValue for Schema {
     fn as_json_value(&self, batch: &Batch) -> Result<Value> {
-        let _ = batch;
-        todo!()
+        batch
+            .records
+            .iter()
+            .map(|record| {
+                decode(record.key.as_ref()).and_then(|key| {
+                    decode(record.value.as_ref()).map(|value| {
+                        Value::Object(serde_json::Map::from_iter([
+                            (MessageKind::Key.as_ref().to_owned(), key),
+                            (MessageKind::Value.as_ref().to_owned(), value),
+                        ]))
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array)
     }
 }
 
@@ -493,6 +547,28 @@ mod tests {
         registry.validate(topic, &batch).await
     }
 
+    #[test]
+    fn as_json_value() -> Result<()> {
+        let batch = Batch::builder()
+            .record(
+                Record::builder()
+                    .key(Bytes::from_static(b"12320").into())
+                    .value(Bytes::from_static(br#"{"name":"alice"}"#).into()),
+            )
+            .record(Record::builder().value(Bytes::from_static(b"true").into()))
+            .build()?;
+
+        assert_eq!(
+            json!([
+                {"key": 12320, "value": {"name": "alice"}},
+                {"key": null, "value": true},
+            ]),
+            Schema::default().as_json_value(&batch)?
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn no_schema() -> Result<()> {
         let _guard = init_tracing()?;
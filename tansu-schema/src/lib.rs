@@ -22,11 +22,12 @@ use std::{
     env::{self},
     fmt::{self, Display, Formatter},
     io,
-    num::TryFromIntError,
+    num::{NonZeroUsize, TryFromIntError},
     result,
     str::FromStr,
     string::FromUtf8Error,
     sync::{Arc, LazyLock, Mutex, PoisonError},
+    thread,
     time::{Duration, SystemTime},
 };
 
@@ -48,8 +49,8 @@ use iceberg::spec::DataFileBuilderError;
 
 use jsonschema::ValidationError;
 use object_store::{
-    DynObjectStore, ObjectStore, ObjectStoreExt, aws::AmazonS3Builder, local::LocalFileSystem,
-    memory::InMemory, path::Path,
+    DynObjectStore, GetResult, ObjectMeta, ObjectStore, ObjectStoreExt, PutPayload,
+    aws::AmazonS3Builder, local::LocalFileSystem, memory::InMemory, path::Path,
 };
 use opentelemetry::{
     InstrumentationScope, KeyValue, global,
@@ -61,16 +62,21 @@ use opentelemetry_semantic_conventions::SCHEMA_URL;
 use parquet::errors::ParquetError;
 
 use rhai::EvalAltResult;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tansu_sans_io::{ErrorCode, record::inflated::Batch};
-use tracing::{debug, instrument};
+use tokio::task::{self, JoinError};
+use tracing::{debug, instrument, warn};
 use tracing_subscriber::filter::ParseError;
 use url::Url;
 
+pub mod audit;
 pub mod avro;
+pub mod import;
 pub mod json;
 pub mod lake;
 pub mod proto;
+pub mod subject;
 
 #[cfg(feature = "delta")]
 pub(crate) mod sql;
@@ -123,6 +129,8 @@ pub enum Error {
 
     Io(#[from] io::Error),
 
+    Join(#[from] JoinError),
+
     JsonToAvro(Box<apache_avro::Schema>, Box<Value>),
 
     JsonToAvroFieldNotFound {
@@ -159,11 +167,23 @@ pub enum Error {
 
     SchemaValidation,
 
+    SchemaVersionNotFound {
+        topic: String,
+        version: u32,
+    },
+
+    SchemaVersionNotSoftDeleted {
+        topic: String,
+        version: u32,
+    },
+
     SerdeJson(#[from] serde_json::Error),
 
     #[cfg(any(feature = "iceberg", feature = "delta"))]
     SqlParser(#[from] datafusion::logical_expr::sqlparser::parser::ParserError),
 
+    SubjectInUse(String),
+
     TopicWithoutSchema(String),
 
     TryFromInt(#[from] TryFromIntError),
@@ -269,21 +289,97 @@ pub enum Schema {
     Proto(Box<proto::Schema>),
 }
 
+/// The supported schema types, in order of precedence when a topic has more than one
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaType {
+    Proto,
+    Json,
+    Avro,
+}
+
+impl SchemaType {
+    const ALL: [Self; 3] = [Self::Proto, Self::Json, Self::Avro];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Proto => "proto",
+            Self::Json => "json",
+            Self::Avro => "avsc",
+        }
+    }
+
+    fn location(&self, topic: &str) -> Path {
+        Path::from(format!("{topic}.{}", self.extension()))
+    }
+
+    /// Fingerprint of the canonical form of a schema, identical schemas have the same fingerprint
+    pub fn fingerprint(&self, encoded: &[u8]) -> Result<String> {
+        match self {
+            Self::Proto => proto::fingerprint(encoded),
+            Self::Json => json::fingerprint(encoded),
+            Self::Avro => avro::fingerprint(encoded),
+        }
+    }
+
+    fn compile(&self, encoded: Bytes) -> Result<Schema> {
+        match self {
+            Self::Proto => proto::Schema::try_from(encoded)
+                .map(Box::new)
+                .map(Schema::Proto),
+            Self::Json => json::Schema::try_from(encoded)
+                .map(Arc::new)
+                .map(Schema::Json),
+            Self::Avro => avro::Schema::try_from(encoded)
+                .map(Box::new)
+                .map(Schema::Avro),
+        }
+    }
+}
+
+impl FromStr for SchemaType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|schema_type| schema_type.extension() == s)
+            .ok_or(Error::Message(format!("unknown schema type: {s}")))
+    }
+}
+
+/// A compiled schema with the version of the object it was loaded from
 #[derive(Clone, Debug)]
 struct CachedSchema {
     loaded_at: SystemTime,
     schema: Schema,
+    location: Path,
+    version: Option<String>,
 }
 
 impl CachedSchema {
-    fn new(schema: Schema) -> Self {
+    fn new(schema: Schema, location: Path, version: Option<String>) -> Self {
         Self {
             schema,
             loaded_at: SystemTime::now(),
+            location,
+            version,
         }
     }
 }
 
+/// CRC-64 fingerprint of a canonical form
+pub(crate) fn fingerprint(canonical_form: &[u8]) -> String {
+    format!(
+        "{:016x}",
+        crc_fast::checksum(crc_fast::CrcAlgorithm::Crc64Nvme, canonical_form)
+    )
+}
+
+fn object_version(meta: &ObjectMeta) -> Option<String> {
+    meta.e_tag.clone().or(meta.version.clone())
+}
+
 impl AsKafkaRecord for Schema {
     fn as_kafka_record(&self, value: &Value) -> Result<tansu_sans_io::record::Builder> {
         debug!(?value);
@@ -350,12 +446,91 @@ impl Generator for Schema {
 
 type SchemaCache = Arc<Mutex<BTreeMap<String, CachedSchema>>>;
 
+/// Schema versions observed while validating, by topic and version
+type UsageCache = Arc<Mutex<BTreeMap<(String, Option<String>), SchemaUsage>>>;
+
+/// Topics with a recent failed schema lookup, with the time of failure
+type UnavailableCache = Arc<Mutex<BTreeMap<String, SystemTime>>>;
+
+const DEFAULT_UNAVAILABLE_EXPIRY: Duration = Duration::from_secs(5);
+
+const DEFAULT_PARALLEL_VALIDATION_THRESHOLD: usize = 1_024;
+
+/// Behaviour when a schema cannot be looked up because the registry is unavailable
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FailureMode {
+    /// Accept the batch without validation, logging a warning
+    Open,
+
+    /// Reject the batch with a retriable error
+    #[default]
+    Closed,
+}
+
+impl FailureMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+impl Display for FailureMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FailureMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "open" => Ok(Self::Open),
+            "closed" => Ok(Self::Closed),
+            otherwise => Err(Error::Message(format!("unknown failure mode: {otherwise}"))),
+        }
+    }
+}
+
 // Schema Registry
 #[derive(Clone, Debug)]
 pub struct Registry {
     object_store: Arc<DynObjectStore>,
     schemas: SchemaCache,
     cache_expiry_after: Option<Duration>,
+    failure_mode: FailureMode,
+    unavailable: UnavailableCache,
+    unavailable_expiry_after: Duration,
+    usage: UsageCache,
+    audits: audit::AuditCache,
+    audit_sample_rate: f64,
+    parallel_validation_threshold: usize,
+}
+
+/// The number of batches and records validated by a version of the schema for a topic
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SchemaUsage {
+    pub topic: String,
+    pub version: Option<String>,
+    pub batches: u64,
+    pub records: u64,
+    pub first_observed: SystemTime,
+    pub last_observed: SystemTime,
+}
+
+impl Default for SchemaUsage {
+    fn default() -> Self {
+        Self {
+            topic: Default::default(),
+            version: Default::default(),
+            batches: Default::default(),
+            records: Default::default(),
+            first_observed: SystemTime::UNIX_EPOCH,
+            last_observed: SystemTime::UNIX_EPOCH,
+        }
+    }
 }
 
 impl FromStr for Registry {
@@ -374,6 +549,10 @@ impl FromStr for Registry {
 pub struct Builder {
     object_store: Arc<DynObjectStore>,
     cache_expiry_after: Option<Duration>,
+    failure_mode: FailureMode,
+    unavailable_expiry_after: Duration,
+    parallel_validation_threshold: usize,
+    audit_sample_rate: f64,
 }
 
 impl TryFrom<&Url> for Builder {
@@ -426,6 +605,13 @@ impl From<Builder> for Registry {
             object_store: builder.object_store,
             schemas: Arc::new(Mutex::new(BTreeMap::new())),
             cache_expiry_after: builder.cache_expiry_after,
+            failure_mode: builder.failure_mode,
+            unavailable: Arc::new(Mutex::new(BTreeMap::new())),
+            unavailable_expiry_after: builder.unavailable_expiry_after,
+            usage: Arc::new(Mutex::new(BTreeMap::new())),
+            audits: Arc::new(Mutex::new(BTreeMap::new())),
+            audit_sample_rate: builder.audit_sample_rate,
+            parallel_validation_threshold: builder.parallel_validation_threshold,
         }
     }
 }
@@ -435,6 +621,10 @@ impl Builder {
         Self {
             object_store: Arc::new(object_store),
             cache_expiry_after: None,
+            failure_mode: FailureMode::default(),
+            unavailable_expiry_after: DEFAULT_UNAVAILABLE_EXPIRY,
+            parallel_validation_threshold: DEFAULT_PARALLEL_VALIDATION_THRESHOLD,
+            audit_sample_rate: 0.0,
         }
     }
 
@@ -445,6 +635,37 @@ impl Builder {
         }
     }
 
+    pub fn with_failure_mode(self, failure_mode: FailureMode) -> Self {
+        Self {
+            failure_mode,
+            ..self
+        }
+    }
+
+    /// Schema lookups for a topic are not retried for this duration after the registry is unavailable
+    pub fn with_unavailable_expiry_after(self, unavailable_expiry_after: Duration) -> Self {
+        Self {
+            unavailable_expiry_after,
+            ..self
+        }
+    }
+
+    /// Batches with at least this many records are validated in parallel
+    pub fn with_parallel_validation_threshold(self, parallel_validation_threshold: usize) -> Self {
+        Self {
+            parallel_validation_threshold,
+            ..self
+        }
+    }
+
+    /// The proportion (`0.0..=1.0`) of fetched batches audited against the current schema
+    pub fn with_audit_sample_rate(self, audit_sample_rate: f64) -> Self {
+        Self {
+            audit_sample_rate: audit_sample_rate.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
     pub fn build(self) -> Registry {
         Registry::from(self)
     }
@@ -474,6 +695,27 @@ static VALIDATION_ERROR: LazyLock<Counter<u64>> = LazyLock::new(|| {
         .build()
 });
 
+static SCHEMA_CACHE: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("registry_schema_cache")
+        .with_description("The registry compiled schema cache lookup count")
+        .build()
+});
+
+static SCHEMA_USAGE: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("registry_schema_usage")
+        .with_description("The number of records validated by each schema version")
+        .build()
+});
+
+static REGISTRY_UNAVAILABLE: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("registry_unavailable")
+        .with_description("The registry unavailable count during validation")
+        .build()
+});
+
 impl Registry {
     pub fn new(object_store: impl ObjectStore) -> Self {
         Builder::new(object_store).build()
@@ -489,96 +731,351 @@ impl Registry {
 
     #[instrument(skip(self), ret)]
     pub async fn schema(&self, topic: &str) -> Result<Option<Schema>> {
-        let proto = Path::from(format!("{topic}.proto"));
-        let json = Path::from(format!("{topic}.json"));
-        let avro = Path::from(format!("{topic}.avsc"));
+        self.cached(topic)
+            .await
+            .map(|cached| cached.map(|cached| cached.schema))
+    }
 
+    async fn cached(&self, topic: &str) -> Result<Option<CachedSchema>> {
         if let Some(cached) = self.schemas.lock().map(|guard| guard.get(topic).cloned())? {
-            if self.cache_expiry_after.is_some_and(|cache_expiry_after| {
+            if !self.cache_expiry_after.is_some_and(|cache_expiry_after| {
                 SystemTime::now()
                     .duration_since(cached.loaded_at)
                     .unwrap_or_default()
                     > cache_expiry_after
             }) {
-                return Ok(Some(cached.schema));
-            } else {
-                debug!(cache_expiry = topic);
+                SCHEMA_CACHE.add(1, &[KeyValue::new("outcome", "hit")]);
+                return Ok(Some(cached));
+            }
+
+            debug!(cache_expiry = topic);
+
+            if let Some(version) = cached.version.as_deref()
+                && self
+                    .head(&cached.location)
+                    .await?
+                    .is_some_and(|current| current == version)
+            {
+                SCHEMA_CACHE.add(1, &[KeyValue::new("outcome", "revalidated")]);
+
+                _ = self.schemas.lock().map(|mut guard| {
+                    guard.insert(
+                        topic.to_owned(),
+                        CachedSchema {
+                            loaded_at: SystemTime::now(),
+                            ..cached.clone()
+                        },
+                    )
+                })?;
+
+                return Ok(Some(cached));
             }
+
+            self.invalidate(topic)?;
         }
 
-        if let Ok(get_result) = self
-            .object_store
-            .get(&proto)
-            .await
-            .inspect(|get_result| debug!(?get_result))
-            .inspect_err(|err| debug!(?err))
-        {
-            get_result
-                .bytes()
-                .await
-                .map_err(Into::into)
-                .and_then(proto::Schema::try_from)
-                .map(Box::new)
-                .map(Schema::Proto)
-                .and_then(|schema| {
-                    self.schemas
-                        .lock()
-                        .map_err(Into::into)
-                        .map(|mut guard| {
-                            guard.insert(topic.to_owned(), CachedSchema::new(schema.clone()))
-                        })
-                        .and(Ok(Some(schema)))
-                })
-        } else if let Ok(get_result) = self.object_store.get(&json).await {
-            get_result
-                .bytes()
-                .await
-                .map_err(Into::into)
-                .and_then(json::Schema::try_from)
-                .map(Arc::new)
-                .map(Schema::Json)
-                .and_then(|schema| {
-                    self.schemas
-                        .lock()
-                        .map_err(Into::into)
-                        .map(|mut guard| {
-                            guard.insert(topic.to_owned(), CachedSchema::new(schema.clone()))
-                        })
-                        .and(Ok(Some(schema)))
-                })
-        } else if let Ok(get_result) = self.object_store.get(&avro).await {
-            get_result
+        SCHEMA_CACHE.add(1, &[KeyValue::new("outcome", "miss")]);
+
+        for schema_type in SchemaType::ALL {
+            let location = schema_type.location(topic);
+
+            let Some(get_result) = self.get(&location).await? else {
+                continue;
+            };
+
+            let version = object_version(&get_result.meta);
+
+            return get_result
                 .bytes()
                 .await
                 .map_err(Into::into)
-                .and_then(avro::Schema::try_from)
-                .map(Box::new)
-                .map(Schema::Avro)
-                .and_then(|schema| {
+                .and_then(|encoded| schema_type.compile(encoded))
+                .map(|schema| CachedSchema::new(schema, location, version))
+                .and_then(|cached| {
                     self.schemas
                         .lock()
                         .map_err(Into::into)
-                        .map(|mut guard| {
-                            guard.insert(topic.to_owned(), CachedSchema::new(schema.clone()))
-                        })
-                        .and(Ok(Some(schema)))
-                })
-        } else {
-            Ok(None)
+                        .map(|mut guard| guard.insert(topic.to_owned(), cached.clone()))
+                        .and(Ok(Some(cached)))
+                });
+        }
+
+        Ok(None)
+    }
+
+    /// Register a new version of the schema for a topic, replacing any cached validator
+    ///
+    /// Registering a schema identical to an existing version returns that version.
+    #[instrument(skip(self, encoded))]
+    pub async fn register(
+        &self,
+        topic: &str,
+        schema_type: SchemaType,
+        encoded: Bytes,
+    ) -> Result<u32> {
+        let schema = schema_type.compile(encoded.clone())?;
+        let fingerprint = schema_type.fingerprint(&encoded)?;
+
+        if let Some(existing) = self.identical(topic, schema_type, &fingerprint).await? {
+            debug!(topic, existing);
+            return Ok(existing);
         }
+
+        let version = self.next_version(topic).await?;
+
+        self.put_version(topic, version, schema_type, &fingerprint, encoded.clone())
+            .await?;
+        self.promote(topic, version, schema_type, schema, encoded)
+            .await?;
+
+        Ok(version)
+    }
+
+    /// Make a version of the schema the latest, used when validating batches for the topic
+    async fn promote(
+        &self,
+        topic: &str,
+        version: u32,
+        schema_type: SchemaType,
+        schema: Schema,
+        encoded: Bytes,
+    ) -> Result<()> {
+        for other in SchemaType::ALL
+            .into_iter()
+            .filter(|other| *other != schema_type)
+        {
+            self.delete(&other.location(topic)).await?;
+        }
+
+        let location = schema_type.location(topic);
+
+        let put_result = self
+            .object_store
+            .put(&location, PutPayload::from(encoded))
+            .await
+            .inspect(|put_result| debug!(?put_result))?;
+
+        self.put_latest(topic, version).await?;
+
+        _ = self.schemas.lock().map(|mut guard| {
+            guard.insert(
+                topic.to_owned(),
+                CachedSchema::new(schema, location, put_result.e_tag.or(put_result.version)),
+            )
+        })?;
+
+        _ = self
+            .unavailable
+            .lock()
+            .map(|mut guard| guard.remove(topic))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        match self.object_store.delete(location).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Remove any cached validator for a topic, the schema is reloaded on next use
+    pub fn invalidate(&self, topic: &str) -> Result<()> {
+        self.schemas
+            .lock()
+            .map(|mut guard| {
+                if guard.remove(topic).is_some() {
+                    debug!(invalidated = topic);
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    async fn head(&self, location: &Path) -> Result<Option<String>> {
+        match self.object_store.head(location).await {
+            Ok(meta) => Ok(object_version(&meta)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn get(&self, location: &Path) -> Result<Option<GetResult>> {
+        match self
+            .object_store
+            .get(location)
+            .await
+            .inspect(|get_result| debug!(?get_result))
+            .inspect_err(|err| debug!(?err))
+        {
+            Ok(get_result) => Ok(Some(get_result)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn recently_unavailable(&self, topic: &str) -> Result<bool> {
+        self.unavailable
+            .lock()
+            .map(|mut guard| {
+                if guard.get(topic).is_some_and(|failed_at| {
+                    SystemTime::now()
+                        .duration_since(*failed_at)
+                        .unwrap_or_default()
+                        < self.unavailable_expiry_after
+                }) {
+                    true
+                } else {
+                    _ = guard.remove(topic);
+                    false
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    fn unavailable(&self, topic: &str, cached: bool) -> Result<()> {
+        if !cached {
+            _ = self
+                .unavailable
+                .lock()
+                .map(|mut guard| guard.insert(topic.to_owned(), SystemTime::now()))?;
+        }
+
+        REGISTRY_UNAVAILABLE.add(
+            1,
+            &[
+                KeyValue::new("topic", topic.to_owned()),
+                KeyValue::new("failure_mode", self.failure_mode.as_str()),
+                KeyValue::new("cached", cached),
+            ],
+        );
+
+        match self.failure_mode {
+            FailureMode::Open => {
+                warn!(registry_unavailable = topic, cached, "batch not validated");
+                Ok(())
+            }
+
+            FailureMode::Closed => Err(Error::Api(ErrorCode::KafkaStorageError)),
+        }
+    }
+
+    /// Validate the records of a batch, large batches are split across the blocking pool
+    ///
+    /// Chunks are merged in record order, so the error reported is that of the
+    /// first invalid record in the batch.
+    async fn validate_records(&self, schema: &Schema, batch: &Batch) -> Result<()> {
+        let parallelism = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+        if parallelism == 1 || batch.records.len() < self.parallel_validation_threshold {
+            return schema.validate(batch);
+        }
+
+        let header = Batch {
+            base_offset: batch.base_offset,
+            batch_length: batch.batch_length,
+            partition_leader_epoch: batch.partition_leader_epoch,
+            magic: batch.magic,
+            crc: batch.crc,
+            attributes: batch.attributes,
+            last_offset_delta: batch.last_offset_delta,
+            base_timestamp: batch.base_timestamp,
+            max_timestamp: batch.max_timestamp,
+            producer_id: batch.producer_id,
+            producer_epoch: batch.producer_epoch,
+            base_sequence: batch.base_sequence,
+            records: Vec::new(),
+        };
+
+        let mut chunks = Vec::with_capacity(parallelism);
+
+        for records in batch
+            .records
+            .chunks(batch.records.len().div_ceil(parallelism))
+        {
+            let schema = schema.clone();
+            let chunk = Batch {
+                records: records.to_vec(),
+                ..header.clone()
+            };
+
+            chunks.push(task::spawn_blocking(move || schema.validate(&chunk)));
+        }
+
+        debug!(records = batch.records.len(), chunks = chunks.len());
+
+        for chunk in chunks {
+            chunk.await??;
+        }
+
+        Ok(())
+    }
+
+    fn observed(&self, topic: &str, version: Option<&str>, batch: &Batch) -> Result<()> {
+        let records = batch.records.len() as u64;
+        let now = SystemTime::now();
+
+        SCHEMA_USAGE.add(
+            records,
+            &[
+                KeyValue::new("topic", topic.to_owned()),
+                KeyValue::new("version", version.unwrap_or_default().to_owned()),
+            ],
+        );
+
+        self.usage
+            .lock()
+            .map(|mut guard| {
+                let usage = guard
+                    .entry((topic.to_owned(), version.map(ToOwned::to_owned)))
+                    .or_insert_with(|| SchemaUsage {
+                        topic: topic.to_owned(),
+                        version: version.map(ToOwned::to_owned),
+                        first_observed: now,
+                        ..Default::default()
+                    });
+
+                usage.batches += 1;
+                usage.records += records;
+                usage.last_observed = now;
+            })
+            .map_err(Into::into)
+    }
+
+    /// The schema versions observed while validating batches for each topic
+    pub fn usage(&self) -> Result<Vec<SchemaUsage>> {
+        self.usage
+            .lock()
+            .map(|guard| guard.values().cloned().collect())
+            .map_err(Into::into)
     }
 
     #[instrument(skip(self, batch), ret)]
     pub async fn validate(&self, topic: &str, batch: &Batch) -> Result<()> {
         let validation_start = SystemTime::now();
 
-        let Some(schema) = self.schema(topic).await? else {
+        if self.recently_unavailable(topic)? {
+            return self.unavailable(topic, true);
+        }
+
+        let cached = match self.cached(topic).await {
+            Ok(cached) => cached,
+
+            Err(Error::ObjectStore(err)) => {
+                debug!(topic, ?err);
+                return self.unavailable(topic, false);
+            }
+
+            Err(err) => return Err(err),
+        };
+
+        let Some(cached) = cached else {
             debug!(no_schema_for_topic = %topic);
             return Ok(());
         };
 
-        schema
-            .validate(batch)
+        self.validate_records(&cached.schema, batch)
+            .await
+            .and_then(|()| self.observed(topic, cached.version.as_deref(), batch))
             .inspect(|_| {
                 VALIDATION_DURATION.record(
                     validation_start
@@ -635,10 +1132,22 @@ impl AsArrow for Registry {
 mod tests {
     use super::*;
     use crate::Result;
+    use async_trait::async_trait;
     use bytes::Bytes;
-    use object_store::PutPayload;
+    use futures::stream::BoxStream;
+    use object_store::{
+        CopyOptions, GetOptions, ListResult, MultipartUpload, ObjectMeta, PutMultipartOptions,
+        PutOptions, PutPayload, PutResult,
+    };
     use serde_json::json;
-    use std::{fs::File, sync::Arc, thread};
+    use std::{
+        fs::File,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+        },
+        thread,
+    };
     use tansu_sans_io::record::Record;
     use tracing::subscriber::DefaultGuard;
     use tracing_subscriber::EnvFilter;
@@ -778,6 +1287,314 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn register_invalidates_cached() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let batch = Batch::builder()
+            .record(Record::builder().key(Bytes::from_static(b"545").into()))
+            .build()?;
+
+        assert!(matches!(
+            registry.validate("abc", &batch).await,
+            Err(Error::Api(ErrorCode::InvalidRecord))
+        ));
+
+        _ = registry
+            .register(
+                "abc",
+                SchemaType::Json,
+                serde_json::to_vec(&json!({
+                    "type": "object",
+                    "properties": {
+                        "key": {
+                            "type": "number",
+                            "multipleOf": 5
+                        }
+                    }
+                }))
+                .map(Bytes::from)?,
+            )
+            .await?;
+
+        registry.validate("abc", &batch).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn usage() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let batch = Batch::builder()
+            .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+            .record(Record::builder().key(Bytes::from_static(b"5460").into()))
+            .build()?;
+
+        registry.validate("abc", &batch).await?;
+        registry.validate("abc", &batch).await?;
+        registry.validate("xyz", &batch).await?;
+
+        let usage = registry.usage()?;
+        assert_eq!(1, usage.len());
+        assert_eq!("abc", usage[0].topic);
+        assert_eq!(2, usage[0].batches);
+        assert_eq!(4, usage[0].records);
+        assert!(usage[0].first_observed <= usage[0].last_observed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parallel_validation() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let registry = Registry::builder(InMemory::new())
+            .with_parallel_validation_threshold(2)
+            .build();
+
+        _ = registry
+            .register(
+                "abc",
+                SchemaType::Json,
+                serde_json::to_vec(&json!({
+                    "type": "object",
+                    "properties": {
+                        "key": {
+                            "type": "number",
+                            "multipleOf": 10
+                        }
+                    }
+                }))
+                .map(Bytes::from)?,
+            )
+            .await?;
+
+        let mut builder = Batch::builder();
+
+        for i in 0..64 {
+            builder =
+                builder.record(Record::builder().key(Bytes::from((i * 10).to_string()).into()));
+        }
+
+        registry.validate("abc", &builder.clone().build()?).await?;
+
+        let batch = builder
+            .record(Record::builder().key(Bytes::from_static(b"545").into()))
+            .build()?;
+
+        assert!(matches!(
+            registry.validate("abc", &batch).await,
+            Err(Error::Api(ErrorCode::InvalidRecord))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn without_schema_valid() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let key = Bytes::from_static(b"545");
+
+        let batch = Batch::builder()
+            .record(Record::builder().key(key.clone().into()))
+            .build()?;
+
+        registry.validate("xyz", &batch).await?;
+
+        Ok(())
+    }
+
+    /// An object store that fails each get until it is made available
+    #[derive(Debug, Default)]
+    struct Unavailable {
+        inner: InMemory,
+        available: Arc<AtomicBool>,
+        gets: Arc<AtomicUsize>,
+    }
+
+    impl Display for Unavailable {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            f.write_str("Unavailable")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for Unavailable {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            opts: PutOptions,
+        ) -> object_store::Result<PutResult> {
+            self.inner.put_opts(location, payload, opts).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            opts: PutMultipartOptions,
+        ) -> object_store::Result<Box<dyn MultipartUpload>> {
+            self.inner.put_multipart_opts(location, opts).await
+        }
+
+        async fn get_opts(
+            &self,
+            location: &Path,
+            options: GetOptions,
+        ) -> object_store::Result<GetResult> {
+            _ = self.gets.fetch_add(1, Ordering::Relaxed);
+
+            if self.available.load(Ordering::Relaxed) {
+                self.inner.get_opts(location, options).await
+            } else {
+                Err(object_store::Error::Generic {
+                    store: "Unavailable",
+                    source: "connection refused".into(),
+                })
+            }
+        }
+
+        fn delete_stream(
+            &self,
+            locations: BoxStream<'static, object_store::Result<Path>>,
+        ) -> BoxStream<'static, object_store::Result<Path>> {
+            self.inner.delete_stream(locations)
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy_opts(
+            &self,
+            from: &Path,
+            to: &Path,
+            options: CopyOptions,
+        ) -> object_store::Result<()> {
+            self.inner.copy_opts(from, to, options).await
+        }
+    }
+
+    fn unavailable(
+        failure_mode: FailureMode,
+        unavailable_expiry_after: Duration,
+    ) -> (Registry, Arc<AtomicBool>, Arc<AtomicUsize>) {
+        let object_store = Unavailable::default();
+        let available = object_store.available.clone();
+        let gets = object_store.gets.clone();
+
+        (
+            Builder::new(object_store)
+                .with_failure_mode(failure_mode)
+                .with_unavailable_expiry_after(unavailable_expiry_after)
+                .build(),
+            available,
+            gets,
+        )
+    }
+
+    fn keyed() -> Result<Batch> {
+        Batch::builder()
+            .record(Record::builder().key(Bytes::from_static(b"545").into()))
+            .build()
+            .map_err(Into::into)
+    }
+
+    #[tokio::test]
+    async fn fail_closed() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let (registry, available, gets) = unavailable(FailureMode::Closed, Duration::from_secs(60));
+
+        let batch = keyed()?;
+
+        assert!(matches!(
+            registry.validate("def", &batch).await,
+            Err(Error::Api(ErrorCode::KafkaStorageError))
+        ));
+        assert_eq!(1, gets.load(Ordering::Relaxed));
+
+        // rejected from the negative cache without another lookup
+        available.store(true, Ordering::Relaxed);
+
+        assert!(matches!(
+            registry.validate("def", &batch).await,
+            Err(Error::Api(ErrorCode::KafkaStorageError))
+        ));
+        assert_eq!(1, gets.load(Ordering::Relaxed));
+
+        // other topics are looked up
+        registry.validate("abc", &batch).await?;
+        assert!(gets.load(Ordering::Relaxed) > 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fail_open() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let (registry, _available, gets) = unavailable(FailureMode::Open, Duration::from_secs(60));
+
+        let batch = keyed()?;
+
+        registry.validate("def", &batch).await?;
+        assert_eq!(1, gets.load(Ordering::Relaxed));
+
+        registry.validate("def", &batch).await?;
+        assert_eq!(1, gets.load(Ordering::Relaxed));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unavailable_expiry() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let (registry, available, gets) = unavailable(FailureMode::Closed, Duration::ZERO);
+
+        let batch = keyed()?;
+
+        assert!(registry.validate("def", &batch).await.is_err());
+        assert_eq!(1, gets.load(Ordering::Relaxed));
+
+        // the failed lookup has expired, and the registry is available again
+        available.store(true, Ordering::Relaxed);
+
+        registry.validate("def", &batch).await?;
+        assert!(gets.load(Ordering::Relaxed) > 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failure_mode() -> Result<()> {
+        assert_eq!(FailureMode::Closed, FailureMode::default());
+
+        for mode in [FailureMode::Open, FailureMode::Closed] {
+            assert_eq!(mode, FailureMode::from_str(mode.as_str())?);
+        }
+
+        assert!(FailureMode::from_str("ajar").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn error_size_of() -> Result<()> {
         let _guard = init_tracing()?;
@@ -809,6 +1626,7 @@ mod tests {
         #[cfg(any(feature = "parquet", feature = "iceberg", feature = "delta"))]
         debug!(sql_parser = size_of::<datafusion::logical_expr::sqlparser::parser::ParserError>());
         debug!(try_from_int = size_of::<TryFromIntError>());
+        debug!(join = size_of::<JoinError>());
         debug!(url = size_of::<Url>());
         #[cfg(any(feature = "parquet", feature = "iceberg", feature = "delta"))]
         debug!(unsupported_schema_runtime_value = size_of::<(DataType, serde_json::Value)>());
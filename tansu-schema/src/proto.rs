@@ -27,7 +27,7 @@ use bytes::{BufMut, Bytes, BytesMut};
 use fake::Fake;
 
 use protobuf::{
-    CodedInputStream, MessageDyn, UnknownValueRef,
+    CodedInputStream, Message as _, MessageDyn, UnknownValueRef,
     descriptor::{self, FieldDescriptorProto},
     reflect::{
         EnumDescriptor, FileDescriptor, MessageDescriptor, ReflectValueBox, ReflectValueRef,
@@ -765,6 +765,22 @@ static WELL_KNOWN_TYPES: LazyLock<Vec<FileDescriptor>> = LazyLock::new(|| {
 static META_FILE_DESCRIPTOR: LazyLock<Option<Vec<FileDescriptor>>> =
     LazyLock::new(|| make_fd(Bytes::from_static(include_bytes!("meta.proto"))).ok());
 
+/// Fingerprint of the file descriptors of a protocol buffer schema, ignoring source formatting and comments
+pub(crate) fn fingerprint(encoded: &[u8]) -> Result<String> {
+    make_fd(Bytes::copy_from_slice(encoded)).and_then(|file_descriptors| {
+        file_descriptors
+            .iter()
+            .map(|file_descriptor| {
+                let mut proto = file_descriptor.proto().clone();
+                proto.clear_name();
+                proto.source_code_info.clear();
+                proto.write_to_bytes().map_err(Into::into)
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|canonical_form| crate::fingerprint(&canonical_form.concat()))
+    })
+}
+
 fn make_fd(proto: Bytes) -> Result<Vec<FileDescriptor>> {
     tempdir().map_err(Into::into).and_then(|temp_dir| {
         NamedTempFile::new_in(&temp_dir)
@@ -0,0 +1,710 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Subject lifecycle
+//!
+//! The subject of a schema is the topic that it validates. Every registered
+//! version is retained in the registry:
+//!
+//! - `{topic}/versions/{version}.{ext}` a registered version
+//! - `{topic}/deleted/{version}.{ext}` a soft deleted version, hidden but recoverable
+//! - `{topic}/latest` the version currently promoted to `{topic}.{ext}`
+//! - `{topic}/purged/{version}` a permanently deleted version, so that its number is never reissued
+//! - `{topic}/fingerprints/{fingerprint}.{ext}` the version registered with a fingerprint, so that an identical schema is found without reading every version
+//!
+//! Only `{topic}.{ext}` is used when validating, so a registry populated by
+//! hand without any versions continues to work as before.
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::TryStreamExt as _;
+use object_store::{ObjectStore as _, ObjectStoreExt as _, PutPayload, path::Path};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
+
+use crate::{Error, Registry, Result, SchemaType};
+
+const VERSIONS: &str = "versions";
+const DELETED: &str = "deleted";
+const LATEST: &str = "latest";
+const PURGED: &str = "purged";
+const FINGERPRINTS: &str = "fingerprints";
+
+/// Whether a subject is in use by an existing topic
+///
+/// Consulted by the registry before permanently deleting a whole subject.
+#[async_trait]
+pub trait Associations: Send + Sync {
+    async fn associated(&self, topic: &str) -> Result<bool>;
+}
+
+/// A registered version of the schema for a topic
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SchemaVersion {
+    pub version: u32,
+    pub schema_type: SchemaType,
+    pub deleted: bool,
+    pub latest: bool,
+}
+
+fn version_location(topic: &str, state: &str, version: u32, schema_type: SchemaType) -> Path {
+    Path::from(format!(
+        "{topic}/{state}/{version}.{}",
+        schema_type.extension()
+    ))
+}
+
+fn purged_location(topic: &str, version: u32) -> Path {
+    Path::from(format!("{topic}/{PURGED}/{version}"))
+}
+
+fn fingerprint_location(topic: &str, fingerprint: &str, schema_type: SchemaType) -> Path {
+    Path::from(format!(
+        "{topic}/{FINGERPRINTS}/{fingerprint}.{}",
+        schema_type.extension()
+    ))
+}
+
+fn latest_location(topic: &str) -> Path {
+    Path::from(format!("{topic}/{LATEST}"))
+}
+
+impl Registry {
+    /// All registered versions of the schema for a topic, including those soft deleted
+    #[instrument(skip(self), ret)]
+    pub async fn versions(&self, topic: &str) -> Result<Vec<SchemaVersion>> {
+        let latest = self.latest(topic).await?;
+
+        let mut versions = Vec::new();
+
+        for (state, deleted) in [(VERSIONS, false), (DELETED, true)] {
+            let prefix = Path::from(format!("{topic}/{state}"));
+
+            for meta in self
+                .object_store
+                .list(Some(&prefix))
+                .try_collect::<Vec<_>>()
+                .await?
+            {
+                let Some((version, extension)) = meta
+                    .location
+                    .filename()
+                    .and_then(|filename| filename.split_once('.'))
+                else {
+                    continue;
+                };
+
+                let (Ok(version), Ok(schema_type)) =
+                    (u32::from_str(version), SchemaType::from_str(extension))
+                else {
+                    debug!(ignoring = %meta.location);
+                    continue;
+                };
+
+                versions.push(SchemaVersion {
+                    version,
+                    schema_type,
+                    deleted,
+                    latest: !deleted && latest.is_some_and(|latest| latest == version),
+                });
+            }
+        }
+
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Soft delete a version, or all versions, of the schema for a topic
+    ///
+    /// When the latest version is deleted the highest remaining version is promoted,
+    /// if no versions remain the topic is no longer validated.
+    #[instrument(skip(self), ret)]
+    pub async fn soft_delete(&self, topic: &str, version: Option<u32>) -> Result<Vec<u32>> {
+        let candidates = self.selected(topic, version, false).await?;
+
+        let mut deleted = Vec::with_capacity(candidates.len());
+        let mut latest_deleted = false;
+
+        for candidate in candidates {
+            self.object_store
+                .rename(
+                    &version_location(topic, VERSIONS, candidate.version, candidate.schema_type),
+                    &version_location(topic, DELETED, candidate.version, candidate.schema_type),
+                )
+                .await?;
+
+            latest_deleted |= candidate.latest;
+            deleted.push(candidate.version);
+        }
+
+        if latest_deleted {
+            self.promote_highest(topic).await?;
+        }
+
+        Ok(deleted)
+    }
+
+    /// Restore a soft deleted version, or all soft deleted versions, of the schema for a topic
+    #[instrument(skip(self), ret)]
+    pub async fn restore(&self, topic: &str, version: Option<u32>) -> Result<Vec<u32>> {
+        let mut restored = Vec::new();
+
+        for candidate in self.selected(topic, version, true).await? {
+            self.object_store
+                .rename(
+                    &version_location(topic, DELETED, candidate.version, candidate.schema_type),
+                    &version_location(topic, VERSIONS, candidate.version, candidate.schema_type),
+                )
+                .await?;
+
+            restored.push(candidate.version);
+        }
+
+        if !restored.is_empty() && self.latest(topic).await?.is_none() {
+            self.promote_highest(topic).await?;
+        }
+
+        Ok(restored)
+    }
+
+    /// Permanently delete a soft deleted version, or all soft deleted versions, of the schema for a topic
+    ///
+    /// The whole subject cannot be permanently deleted while it is associated
+    /// with an existing topic. The number of a permanently deleted version is
+    /// never reissued.
+    #[instrument(skip(self, associations), ret)]
+    pub async fn permanent_delete(
+        &self,
+        topic: &str,
+        version: Option<u32>,
+        associations: &dyn Associations,
+    ) -> Result<Vec<u32>> {
+        if version.is_none() && associations.associated(topic).await? {
+            return Err(Error::SubjectInUse(topic.to_owned()));
+        }
+
+        let mut deleted = Vec::new();
+
+        for candidate in self.selected(topic, version, true).await? {
+            self.delete(&version_location(
+                topic,
+                DELETED,
+                candidate.version,
+                candidate.schema_type,
+            ))
+            .await?;
+
+            self.object_store
+                .put(
+                    &purged_location(topic, candidate.version),
+                    PutPayload::default(),
+                )
+                .await
+                .map(|put_result| debug!(?put_result))?;
+
+            deleted.push(candidate.version);
+        }
+
+        Ok(deleted)
+    }
+
+    /// Promote an older registered version of the schema as the latest for a topic
+    #[instrument(skip(self), ret)]
+    pub async fn rollback(&self, topic: &str, version: u32) -> Result<()> {
+        let Some(candidate) = self.selected(topic, Some(version), false).await?.pop() else {
+            return Err(Error::SchemaVersionNotFound {
+                topic: topic.to_owned(),
+                version,
+            });
+        };
+
+        self.promote_version(topic, candidate).await
+    }
+
+    /// A live version of the schema for a topic with the same fingerprint
+    pub(crate) async fn identical(
+        &self,
+        topic: &str,
+        schema_type: SchemaType,
+        fingerprint: &str,
+    ) -> Result<Option<u32>> {
+        let Some(get_result) = self
+            .get(&fingerprint_location(topic, fingerprint, schema_type))
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let Some(version) = get_result
+            .bytes()
+            .await
+            .map_err(Into::into)
+            .and_then(|encoded| String::from_utf8(encoded.to_vec()).map_err(Into::into))
+            .map(|version| u32::from_str(version.trim()).ok())?
+        else {
+            return Ok(None);
+        };
+
+        // a soft or permanently deleted version is no longer identical
+        match self
+            .object_store
+            .head(&version_location(topic, VERSIONS, version, schema_type))
+            .await
+        {
+            Ok(meta) => {
+                debug!(topic, version, ?meta);
+                Ok(Some(version))
+            }
+
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Every version number issued for a topic, including those permanently deleted
+    pub(crate) async fn issued(&self, topic: &str) -> Result<Vec<u32>> {
+        let mut issued = self
+            .versions(topic)
+            .await?
+            .into_iter()
+            .map(|version| version.version)
+            .collect::<Vec<_>>();
+
+        let prefix = Path::from(format!("{topic}/{PURGED}"));
+
+        for meta in self
+            .object_store
+            .list(Some(&prefix))
+            .try_collect::<Vec<_>>()
+            .await?
+        {
+            if let Some(version) = meta
+                .location
+                .filename()
+                .and_then(|filename| u32::from_str(filename).ok())
+            {
+                issued.push(version);
+            }
+        }
+
+        Ok(issued)
+    }
+
+    pub(crate) async fn next_version(&self, topic: &str) -> Result<u32> {
+        self.issued(topic)
+            .await
+            .map(|issued| issued.into_iter().max().map_or(1, |highest| highest + 1))
+    }
+
+    pub(crate) async fn put_version(
+        &self,
+        topic: &str,
+        version: u32,
+        schema_type: SchemaType,
+        fingerprint: &str,
+        encoded: Bytes,
+    ) -> Result<()> {
+        self.object_store
+            .put(
+                &version_location(topic, VERSIONS, version, schema_type),
+                PutPayload::from(encoded),
+            )
+            .await
+            .map(|put_result| debug!(?put_result))?;
+
+        self.object_store
+            .put(
+                &fingerprint_location(topic, fingerprint, schema_type),
+                PutPayload::from(Bytes::from(version.to_string())),
+            )
+            .await
+            .map(|put_result| debug!(?put_result))
+            .map_err(Into::into)
+    }
+
+    pub(crate) async fn put_latest(&self, topic: &str, version: u32) -> Result<()> {
+        self.object_store
+            .put(
+                &latest_location(topic),
+                PutPayload::from(Bytes::from(version.to_string())),
+            )
+            .await
+            .map(|put_result| debug!(?put_result))
+            .map_err(Into::into)
+    }
+
+    async fn latest(&self, topic: &str) -> Result<Option<u32>> {
+        let Some(get_result) = self.get(&latest_location(topic)).await? else {
+            return Ok(None);
+        };
+
+        get_result
+            .bytes()
+            .await
+            .map_err(Into::into)
+            .and_then(|encoded| String::from_utf8(encoded.to_vec()).map_err(Into::into))
+            .map(|latest| u32::from_str(latest.trim()).ok())
+    }
+
+    /// Versions matching the selection, either live or soft deleted
+    async fn selected(
+        &self,
+        topic: &str,
+        version: Option<u32>,
+        deleted: bool,
+    ) -> Result<Vec<SchemaVersion>> {
+        let versions = self.versions(topic).await?;
+
+        if let Some(version) = version {
+            let Some(selected) = versions
+                .iter()
+                .find(|candidate| candidate.version == version)
+            else {
+                return Err(Error::SchemaVersionNotFound {
+                    topic: topic.to_owned(),
+                    version,
+                });
+            };
+
+            if deleted && !selected.deleted {
+                return Err(Error::SchemaVersionNotSoftDeleted {
+                    topic: topic.to_owned(),
+                    version,
+                });
+            }
+
+            if !deleted && selected.deleted {
+                return Err(Error::SchemaVersionNotFound {
+                    topic: topic.to_owned(),
+                    version,
+                });
+            }
+
+            Ok(vec![*selected])
+        } else {
+            Ok(versions
+                .into_iter()
+                .filter(|candidate| candidate.deleted == deleted)
+                .collect())
+        }
+    }
+
+    async fn promote_version(&self, topic: &str, selected: SchemaVersion) -> Result<()> {
+        let encoded = self
+            .get(&version_location(
+                topic,
+                VERSIONS,
+                selected.version,
+                selected.schema_type,
+            ))
+            .await?
+            .ok_or(Error::SchemaVersionNotFound {
+                topic: topic.to_owned(),
+                version: selected.version,
+            })?
+            .bytes()
+            .await?;
+
+        let schema = selected.schema_type.compile(encoded.clone())?;
+
+        self.promote(
+            topic,
+            selected.version,
+            selected.schema_type,
+            schema,
+            encoded,
+        )
+        .await
+    }
+
+    pub(crate) async fn promote_highest(&self, topic: &str) -> Result<()> {
+        if let Some(highest) = self
+            .versions(topic)
+            .await?
+            .into_iter()
+            .filter(|candidate| !candidate.deleted)
+            .max()
+        {
+            self.promote_version(topic, highest).await
+        } else {
+            for schema_type in SchemaType::ALL {
+                self.delete(&schema_type.location(topic)).await?;
+            }
+
+            self.delete(&latest_location(topic)).await?;
+            self.invalidate(topic)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, sync::Arc, thread};
+
+    use super::*;
+    use object_store::memory::InMemory;
+    use serde_json::json;
+    use tracing::subscriber::DefaultGuard;
+    use tracing_subscriber::EnvFilter;
+
+    fn init_tracing() -> Result<DefaultGuard> {
+        Ok(tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_level(true)
+                .with_line_number(true)
+                .with_thread_names(false)
+                .with_env_filter(
+                    EnvFilter::from_default_env()
+                        .add_directive(format!("{}=debug", env!("CARGO_CRATE_NAME")).parse()?),
+                )
+                .with_writer(
+                    thread::current()
+                        .name()
+                        .ok_or(Error::Message(String::from("unnamed thread")))
+                        .and_then(|name| {
+                            File::create(format!("../logs/{}/{name}.log", env!("CARGO_PKG_NAME"),))
+                                .map_err(Into::into)
+                        })
+                        .map(Arc::new)?,
+                )
+                .finish(),
+        ))
+    }
+
+    fn multiple_of(n: u32) -> Result<Bytes> {
+        serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number",
+                    "multipleOf": n
+                }
+            }
+        }))
+        .map(Bytes::from)
+        .map_err(Into::into)
+    }
+
+    struct Associated(bool);
+
+    #[async_trait]
+    impl Associations for Associated {
+        async fn associated(&self, _topic: &str) -> Result<bool> {
+            Ok(self.0)
+        }
+    }
+
+    async fn latest(registry: &Registry, topic: &str) -> Result<Option<u32>> {
+        registry.versions(topic).await.map(|versions| {
+            versions
+                .into_iter()
+                .find(|version| version.latest)
+                .map(|version| version.version)
+        })
+    }
+
+    #[tokio::test]
+    async fn lifecycle() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let registry = Registry::new(InMemory::new());
+        let topic = "abc";
+
+        assert_eq!(
+            1,
+            registry
+                .register(topic, SchemaType::Json, multiple_of(10)?)
+                .await?
+        );
+        assert_eq!(
+            2,
+            registry
+                .register(topic, SchemaType::Json, multiple_of(5)?)
+                .await?
+        );
+        assert_eq!(Some(2), latest(&registry, topic).await?);
+
+        registry.rollback(topic, 1).await?;
+        assert_eq!(Some(1), latest(&registry, topic).await?);
+
+        assert_eq!(vec![1], registry.soft_delete(topic, Some(1)).await?);
+        assert_eq!(Some(2), latest(&registry, topic).await?);
+        assert!(registry.schema(topic).await?.is_some());
+
+        assert!(matches!(
+            registry.rollback(topic, 1).await,
+            Err(Error::SchemaVersionNotFound { version: 1, .. })
+        ));
+
+        assert!(matches!(
+            registry
+                .permanent_delete(topic, Some(2), &Associated(false))
+                .await,
+            Err(Error::SchemaVersionNotSoftDeleted { version: 2, .. })
+        ));
+
+        assert_eq!(vec![2], registry.soft_delete(topic, None).await?);
+        assert_eq!(None, latest(&registry, topic).await?);
+        assert!(registry.schema(topic).await?.is_none());
+
+        assert_eq!(vec![1], registry.restore(topic, Some(1)).await?);
+        assert_eq!(Some(1), latest(&registry, topic).await?);
+        assert!(registry.schema(topic).await?.is_some());
+
+        assert!(matches!(
+            registry
+                .permanent_delete(topic, None, &Associated(true))
+                .await,
+            Err(Error::SubjectInUse(_))
+        ));
+
+        assert_eq!(
+            vec![2],
+            registry
+                .permanent_delete(topic, None, &Associated(false))
+                .await?
+        );
+        assert_eq!(3, registry.next_version(topic).await?);
+        assert_eq!(
+            3,
+            registry
+                .register(topic, SchemaType::Json, multiple_of(3)?)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn identical_schema_existing_version() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let registry = Registry::new(InMemory::new());
+        let topic = "abc";
+
+        assert_eq!(
+            1,
+            registry
+                .register(topic, SchemaType::Json, multiple_of(10)?)
+                .await?
+        );
+
+        let reordered = Bytes::from_static(
+            br#"{
+                "properties": {"key": {"multipleOf": 10, "type": "number"}},
+                "type": "object"
+            }"#,
+        );
+
+        assert_eq!(
+            1,
+            registry
+                .register(topic, SchemaType::Json, reordered)
+                .await?
+        );
+        assert_eq!(
+            2,
+            registry
+                .register(topic, SchemaType::Json, multiple_of(5)?)
+                .await?
+        );
+        assert_eq!(
+            1,
+            registry
+                .register(topic, SchemaType::Json, multiple_of(10)?)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn avro_fingerprint() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let pretty = br#"
+            {
+                "type": "record",
+                "name": "test",
+                "fields": [
+                    {"name": "a", "type": "long", "default": 42},
+                    {"name": "b", "type": "string"}
+                ]
+            }
+        "#;
+
+        let compact = br#"{"name":"test","type":"record","fields":[{"type":"long","name":"a","default":42},{"name":"b","type":"string"}]}"#;
+
+        let without_default = br#"{"name":"test","type":"record","fields":[{"type":"long","name":"a"},{"name":"b","type":"string"}]}"#;
+
+        assert_eq!(
+            SchemaType::Avro.fingerprint(pretty)?,
+            SchemaType::Avro.fingerprint(compact)?
+        );
+
+        assert_ne!(
+            SchemaType::Avro.fingerprint(pretty)?,
+            SchemaType::Avro.fingerprint(without_default)?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn avro_default_is_new_version() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let registry = Registry::new(InMemory::new());
+        let topic = "abc";
+
+        let without_default = Bytes::from_static(
+            br#"{"type":"record","name":"test","fields":[{"name":"value","type":"long"}]}"#,
+        );
+
+        let with_default = Bytes::from_static(
+            br#"{"type":"record","name":"test","fields":[{"name":"value","type":"long","default":0}]}"#,
+        );
+
+        assert_eq!(
+            1,
+            registry
+                .register(topic, SchemaType::Avro, without_default.clone())
+                .await?
+        );
+        assert_eq!(
+            2,
+            registry
+                .register(topic, SchemaType::Avro, with_default.clone())
+                .await?
+        );
+        assert_eq!(
+            2,
+            registry
+                .register(topic, SchemaType::Avro, with_default)
+                .await?
+        );
+
+        assert_eq!(vec![1], registry.soft_delete(topic, Some(1)).await?);
+        assert_eq!(
+            3,
+            registry
+                .register(topic, SchemaType::Avro, without_default)
+                .await?
+        );
+
+        Ok(())
+    }
+}
@@ -210,6 +210,11 @@ where
             .map_or(Ok(self), |_existing| Err(Error::DuplicateRoute(api_key)))
     }
 
+    /// The API keys routed by this builder, excluding the implicit [`ApiVersionsRequest`]
+    pub fn api_keys(&self) -> Vec<i16> {
+        self.routes.keys().copied().collect()
+    }
+
     pub fn build(self) -> Result<FrameRouteService<State, E>, Error> {
         let api_key = ApiVersionsRequest::KEY;
         let mut supported = self.routes.keys().copied().collect::<Vec<_>>();
@@ -20,11 +20,25 @@ use std::{
 use bytes::Bytes;
 use opentelemetry::KeyValue;
 use rama::{Context, Layer, Service, context::Extensions, matcher::Matcher, service::BoxService};
-use tansu_sans_io::{ApiKey, Body, Frame, Header, Request, Response, RootMessageMeta};
-use tokio::task::spawn_blocking;
+use tansu_sans_io::{
+    ApiKey, Body, Frame, Header, ProduceRequest, Request, Response, RootMessageMeta, pool,
+    produce_request::{PartitionProduceData, TopicProduceData},
+    record::deflated::{self, Frame as RecordBatches},
+};
+use tokio::{
+    io::{self, AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, Take},
+    task::spawn_blocking,
+};
 use tracing::{debug, error, instrument};
 
-use crate::{API_ERRORS, API_REQUESTS};
+use crate::{API_ERRORS, API_REQUESTS, Error, Receive, Respond, frame_length, zeroed};
+
+/// Decode a request frame, returning its buffer to the [pool][`tansu_sans_io::pool`]
+fn decode(encoded: Bytes) -> tansu_sans_io::Result<Frame> {
+    let decoded = Frame::request_from_bytes(&encoded[..]);
+    pool::reclaim(encoded);
+    decoded
+}
 
 /// A [Matcher] of [`Request`]s using their [API key][`ApiKey`].
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -211,6 +225,12 @@ impl<S> Layer<S> for BytesFrameLayer {
 }
 
 /// A [`Service`] transforming [`Bytes`]s into [`Frame`]s
+///
+/// A [`ReceivedFrame`] read from a connection is answered with a
+/// [`StreamedFrame`] that is encoded as it is written to the connection: a
+/// response is never held in memory as a whole once encoded, with the memory
+/// used by a large fetch response on each connection bounded by a window of
+/// the encoded frame.
 #[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct BytesFrameService<S> {
     inner: S,
@@ -235,7 +255,7 @@ where
     async fn serve(&self, ctx: Context<State>, req: Bytes) -> Result<Self::Response, Self::Error> {
         debug!(request = ?&req[..]);
 
-        let req = spawn_blocking(|| Frame::request_from_bytes(req))
+        let req = spawn_blocking(|| decode(req))
             .await?
             .inspect(|request| debug!(?request))?;
 
@@ -275,6 +295,411 @@ where
     }
 }
 
+/// The size of each window of a [`StreamedFrame`] written to a connection
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The windows that a very large [`StreamedFrame`] is encoded in, each window
+/// encoding the frame again
+const MAXIMUM_WINDOWS: usize = 64;
+
+impl<S, State> Service<State, ReceivedFrame> for BytesFrameService<S>
+where
+    S: Service<State, Frame, Response = Frame>,
+    State: Clone + Send + Sync + 'static,
+    S::Error: From<tansu_sans_io::Error> + From<tokio::task::JoinError> + Debug,
+{
+    type Response = StreamedFrame;
+    type Error = S::Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: ReceivedFrame,
+    ) -> Result<Self::Response, Self::Error> {
+        let req = match req {
+            ReceivedFrame::Bytes(req) => spawn_blocking(|| decode(req)).await??,
+            ReceivedFrame::Decoded { frame, .. } => *frame,
+        };
+
+        debug!(?req);
+
+        let api_key = req.api_key()?;
+        let api_version = req.api_version()?;
+        let correlation_id = req.correlation_id()?;
+
+        let attributes = [
+            KeyValue::new("api_key", api_key as i64),
+            KeyValue::new("api_version", api_version as i64),
+        ];
+
+        self.inner
+            .serve(ctx, req)
+            .await
+            .map(|Frame { body, .. }| StreamedFrame {
+                correlation_id,
+                body,
+                api_key,
+                api_version,
+            })
+            .inspect(|_| API_REQUESTS.add(1, &attributes))
+            .inspect_err(|err| {
+                error!(api_key, api_version, ?err);
+                API_ERRORS.add(1, &attributes);
+            })
+    }
+}
+
+/// A request read from a connection, either as a frame of [`Bytes`], or a
+/// [`ProduceRequest`] that was decoded as it was read
+///
+/// A produce request of at least the incremental decode size is never held in
+/// memory as a whole frame: each record batch is decoded once it has been
+/// read, sharing the bytes read for its records. The maximum frame size of
+/// the connection is checked before the request is read.
+#[derive(Clone, Debug)]
+pub enum ReceivedFrame {
+    Bytes(Bytes),
+    Decoded { frame: Box<Frame>, size: usize },
+}
+
+impl Receive for ReceivedFrame {
+    async fn receive<R>(
+        reader: &mut R,
+        size: [u8; 4],
+        incremental_decode: Option<usize>,
+    ) -> Result<Self, Error>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let length = frame_length(size);
+        let mut key_version = [0u8; 4];
+
+        if incremental_decode.is_none_or(|incremental_decode| {
+            length < incremental_decode.max(size.len() + key_version.len())
+        }) {
+            return Bytes::receive(reader, size, incremental_decode)
+                .await
+                .map(Self::Bytes);
+        }
+
+        _ = reader.read_exact(&mut key_version).await?;
+
+        let api_key = i16::from_be_bytes([key_version[0], key_version[1]]);
+        let api_version = i16::from_be_bytes([key_version[2], key_version[3]]);
+
+        // record batches (magic 2) are produced from version 3
+        let Some(flexible) = RootMessageMeta::messages()
+            .requests()
+            .get(&api_key)
+            .filter(|meta| {
+                api_key == ProduceRequest::KEY
+                    && api_version >= 3
+                    && meta.version.valid.within(api_version)
+            })
+            .map(|meta| meta.is_flexible(api_version))
+        else {
+            let mut request = zeroed(length);
+            request[..size.len()].copy_from_slice(&size[..]);
+            request[size.len()..size.len() + key_version.len()].copy_from_slice(&key_version[..]);
+
+            _ = reader
+                .read_exact(&mut request[size.len() + key_version.len()..])
+                .await?;
+
+            return Ok(Self::Bytes(request.freeze()));
+        };
+
+        let remaining = length - size.len() - key_version.len();
+
+        let mut decoder = ProduceDecoder {
+            reader: reader
+                .take(u64::try_from(remaining).map_err(|err| Error::Message(err.to_string()))?),
+            flexible,
+        };
+
+        let decoded = decoder.frame(api_version).await;
+
+        // the whole frame is read even when decoding has failed
+        let unread = io::copy(&mut decoder.reader, &mut io::sink()).await?;
+
+        decoded
+            .and_then(|frame| {
+                if unread == 0 {
+                    Ok(frame)
+                } else {
+                    Err(Error::Message(format!(
+                        "{unread} bytes after produce request"
+                    )))
+                }
+            })
+            .inspect(|request| debug!(?request))
+            .map(|frame| Self::Decoded {
+                frame: Box::new(Frame {
+                    size: i32::from_be_bytes(size),
+                    ..frame
+                }),
+                size: length,
+            })
+    }
+
+    fn api_key(&self) -> Option<i16> {
+        match self {
+            Self::Bytes(encoded) => encoded.api_key(),
+            Self::Decoded { frame, .. } => frame.api_key().ok(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::Bytes(encoded) => encoded.len(),
+            Self::Decoded { size, .. } => *size,
+        }
+    }
+}
+
+/// A [`ProduceRequest`] (version 3 or later) decoded as it is read from a
+/// connection, limited to the remainder of its frame
+#[derive(Debug)]
+struct ProduceDecoder<'a, R> {
+    reader: Take<&'a mut R>,
+    flexible: bool,
+}
+
+impl<R> ProduceDecoder<'_, R>
+where
+    R: AsyncRead + Send + Unpin,
+{
+    async fn frame(&mut self, api_version: i16) -> Result<Frame, Error> {
+        let correlation_id = self.reader.read_i32().await?;
+
+        // the client id of a request header is never compact
+        let client_id = self.string(false).await?;
+        self.tagged_fields().await?;
+
+        let transactional_id = self.string(self.flexible).await?;
+        let acks = self.reader.read_i16().await?;
+        let timeout_ms = self.reader.read_i32().await?;
+
+        let topic_data = match self.length().await? {
+            Some(topics) => {
+                let mut topic_data = Vec::new();
+
+                for _ in 0..topics {
+                    topic_data.push(self.topic().await?);
+                }
+
+                Some(topic_data)
+            }
+
+            None => None,
+        };
+
+        self.tagged_fields().await?;
+
+        Ok(Frame {
+            size: 0,
+            header: Header::Request {
+                api_key: ProduceRequest::KEY,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            body: ProduceRequest::default()
+                .transactional_id(transactional_id)
+                .acks(acks)
+                .timeout_ms(timeout_ms)
+                .topic_data(topic_data)
+                .into(),
+        })
+    }
+
+    async fn topic(&mut self) -> Result<TopicProduceData, Error> {
+        let name = self
+            .string(self.flexible)
+            .await?
+            .ok_or(Error::Message(String::from("null topic name")))?;
+
+        let partition_data = match self.length().await? {
+            Some(partitions) => {
+                let mut partition_data = Vec::new();
+
+                for _ in 0..partitions {
+                    let index = self.reader.read_i32().await?;
+                    let records = self.records().await?;
+                    self.tagged_fields().await?;
+
+                    partition_data.push(
+                        PartitionProduceData::default()
+                            .index(index)
+                            .records(records),
+                    );
+                }
+
+                Some(partition_data)
+            }
+
+            None => None,
+        };
+
+        self.tagged_fields().await?;
+
+        Ok(TopicProduceData::default()
+            .name(name)
+            .partition_data(partition_data))
+    }
+
+    /// The record batches of a partition, each decoded once it has been read
+    async fn records(&mut self) -> Result<Option<RecordBatches>, Error> {
+        let Some(mut remaining) = self.length().await? else {
+            return Ok(None);
+        };
+
+        let mut batches = Vec::new();
+
+        while remaining > 0 {
+            // the base offset and batch length
+            let mut header = [0u8; 12];
+
+            if remaining < header.len() {
+                return Err(Error::Message(format!("{remaining} bytes after batches")));
+            }
+
+            _ = self.reader.read_exact(&mut header).await?;
+
+            let length = usize::try_from(i32::from_be_bytes([
+                header[8], header[9], header[10], header[11],
+            ]))
+            .ok()
+            .map(|batch_length| batch_length + header.len())
+            .filter(|length| *length <= remaining)
+            .ok_or(Error::Message(String::from(
+                "batch longer than its records",
+            )))?;
+
+            let mut batch = zeroed(length);
+            batch[..header.len()].copy_from_slice(&header[..]);
+            _ = self.reader.read_exact(&mut batch[header.len()..]).await?;
+
+            batches.push(deflated::Batch::try_from(batch.freeze())?);
+            remaining -= length;
+        }
+
+        Ok(Some(RecordBatches { batches }))
+    }
+
+    /// The length of a nullable array or bytes, compact when flexible
+    async fn length(&mut self) -> Result<Option<usize>, Error> {
+        if self.flexible {
+            self.unsigned_varint()
+                .await
+                .map(|length| length.checked_sub(1).map(|length| length as usize))
+        } else {
+            self.reader
+                .read_i32()
+                .await
+                .map(|length| usize::try_from(length).ok())
+                .map_err(Into::into)
+        }
+    }
+
+    async fn string(&mut self, compact: bool) -> Result<Option<String>, Error> {
+        let length = if compact {
+            self.unsigned_varint()
+                .await?
+                .checked_sub(1)
+                .map(|length| length as usize)
+        } else {
+            usize::try_from(self.reader.read_i16().await?).ok()
+        };
+
+        let Some(length) = length else {
+            return Ok(None);
+        };
+
+        let mut encoded = vec![0u8; length];
+        _ = self.reader.read_exact(&mut encoded).await?;
+
+        String::from_utf8(encoded)
+            .map(Some)
+            .map_err(|err| Error::Message(err.to_string()))
+    }
+
+    /// Skip the tagged fields of a flexible version, none of which are known
+    async fn tagged_fields(&mut self) -> Result<(), Error> {
+        if !self.flexible {
+            return Ok(());
+        }
+
+        for _ in 0..self.unsigned_varint().await? {
+            let tag = self.unsigned_varint().await?;
+            let size = u64::from(self.unsigned_varint().await?);
+
+            let skipped = io::copy(&mut (&mut self.reader).take(size), &mut io::sink()).await?;
+            debug!(tag, size, skipped);
+
+            if skipped < size {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn unsigned_varint(&mut self) -> Result<u32, Error> {
+        let mut value = 0;
+
+        for shift in (0..32).step_by(7) {
+            let byte = self.reader.read_u8().await?;
+            value |= u32::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(Error::Message(String::from("unsigned varint too long")))
+    }
+}
+
+/// A response [`Frame`] that is encoded as it is written to a connection
+#[derive(Clone, Debug)]
+pub struct StreamedFrame {
+    correlation_id: i32,
+    body: Body,
+    api_key: i16,
+    api_version: i16,
+}
+
+impl Respond for StreamedFrame {
+    /// Write the frame a window at a time, each window being encoded from the
+    /// body once the window before it has been written
+    async fn respond<W>(self, writer: &mut W) -> Result<usize, Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+    {
+        let encoded = Frame::encoded_response(
+            Header::Response {
+                correlation_id: self.correlation_id,
+            },
+            self.body,
+            self.api_key,
+            self.api_version,
+        )?;
+
+        let length = encoded.len();
+        let window = CHUNK_SIZE.max(length.div_ceil(MAXIMUM_WINDOWS));
+
+        for start in (0..length).step_by(window) {
+            let chunk = encoded.window(start..length.min(start + window))?;
+            writer.write_all(&chunk).await?;
+            pool::reclaim(chunk);
+        }
+
+        Ok(length)
+    }
+}
+
 /// A [`Layer`] that transforms [`Frame`]s into [`Bytes`]
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct FrameBytesLayer;
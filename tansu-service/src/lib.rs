@@ -235,12 +235,13 @@ use std::{
     time::SystemTime,
 };
 
+use bytes::BytesMut;
 use opentelemetry::{
     InstrumentationScope, KeyValue, global,
     metrics::{Counter, Histogram, Meter},
 };
 use opentelemetry_semantic_conventions::SCHEMA_URL;
-use tansu_sans_io::{Body, Frame};
+use tansu_sans_io::{Body, Frame, pool};
 use tokio::{net::lookup_host, sync::oneshot, task::JoinError};
 use tracing::debug;
 use url::Url;
@@ -259,13 +260,15 @@ pub use channel::{
 
 pub use frame::{
     BodyRequestLayer, BytesFrameLayer, BytesFrameService, FrameApiKeyMatcher, FrameBodyLayer,
-    FrameBytesLayer, FrameBytesService, FrameRequestLayer, FrameService, RequestApiKeyMatcher,
-    RequestFrameLayer, RequestFrameService, RequestLayer, ResponseService,
+    FrameBytesLayer, FrameBytesService, FrameRequestLayer, FrameService, ReceivedFrame,
+    RequestApiKeyMatcher, RequestFrameLayer, RequestFrameService, RequestLayer, ResponseService,
+    StreamedFrame,
 };
 
 pub use stream::{
-    BytesLayer, BytesService, BytesTcpService, TcpBytesLayer, TcpBytesService, TcpContext,
-    TcpContextLayer, TcpContextService, TcpListenerLayer,
+    BytesLayer, BytesService, BytesTcpService, Connection, MAXIMUM_IN_FLIGHT, PIPELINED, Peer,
+    Receive, Respond, TcpBytesLayer, TcpBytesService, TcpContext, TcpContextLayer,
+    TcpContextService, TcpListenerLayer,
 };
 
 #[derive(Clone, Debug, thiserror::Error)]
@@ -306,6 +309,13 @@ fn frame_length(encoded: [u8; 4]) -> usize {
     i32::from_be_bytes(encoded) as usize + encoded.len()
 }
 
+/// A zeroed buffer of the length from the [buffer pool][`tansu_sans_io::pool`]
+fn zeroed(length: usize) -> BytesMut {
+    let mut buffer = pool::buffer(length);
+    buffer.resize(length, 0);
+    buffer
+}
+
 pub(crate) static METER: LazyLock<Meter> = LazyLock::new(|| {
     global::meter_with_scope(
         InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
@@ -13,29 +13,179 @@
 // limitations under the License.
 
 use std::{
+    collections::BTreeSet,
     error::{self},
-    fmt::Debug,
+    fmt::{self, Debug, Display},
     io,
     marker::PhantomData,
+    net::SocketAddr,
+    path::PathBuf,
     time::SystemTime,
 };
 
 use bytes::Bytes;
+use futures::{StreamExt as _, stream::FuturesOrdered};
 use nanoid::nanoid;
 use opentelemetry::KeyValue;
 use rama::{Context, Layer, Service};
+use tansu_sans_io::{
+    ApiKey as _, ApiVersionsRequest, DescribeClusterRequest, DescribeConfigsRequest,
+    DescribeGroupsRequest, DescribeTopicPartitionsRequest, FetchRequest, FindCoordinatorRequest,
+    ListGroupsRequest, ListOffsetsRequest, MetadataRequest, OffsetFetchRequest, pool,
+};
 use tokio::{
-    io::{AsyncReadExt as _, AsyncWriteExt as _, BufWriter},
-    net::{TcpListener, TcpStream},
+    io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, BufWriter},
+    net::{TcpListener, TcpStream, UnixStream},
     task::JoinSet,
 };
+use tokio_rustls::server::TlsStream;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, instrument, warn};
 
 use crate::{
     BYTES_RECEIVED, BYTES_SENT, Error, REQUEST_DURATION, REQUEST_SIZE, RESPONSE_SIZE, frame_length,
+    zeroed,
 };
 
+/// An end of a [`Connection`], either a socket address or a Unix domain
+/// socket, which has no IP address
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Peer {
+    Inet(SocketAddr),
+
+    /// A Unix domain socket, with the path it is bound to (if any)
+    Unix(Option<PathBuf>),
+}
+
+impl Display for Peer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inet(addr) => write!(f, "{addr}"),
+            Self::Unix(Some(path)) => write!(f, "unix:{}", path.display()),
+            Self::Unix(None) => f.write_str("unix:"),
+        }
+    }
+}
+
+impl From<SocketAddr> for Peer {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Inet(addr)
+    }
+}
+
+impl From<tokio::net::unix::SocketAddr> for Peer {
+    fn from(addr: tokio::net::unix::SocketAddr) -> Self {
+        Self::Unix(addr.as_pathname().map(PathBuf::from))
+    }
+}
+
+/// A connected stream, either a [`TcpStream`], a [`UnixStream`] or a [`TlsStream`] over either
+pub trait Connection: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static {
+    fn local_addr(&self) -> io::Result<Peer>;
+
+    fn peer_addr(&self) -> io::Result<Peer>;
+}
+
+impl Connection for TcpStream {
+    fn local_addr(&self) -> io::Result<Peer> {
+        TcpStream::local_addr(self).map(Into::into)
+    }
+
+    fn peer_addr(&self) -> io::Result<Peer> {
+        TcpStream::peer_addr(self).map(Into::into)
+    }
+}
+
+impl Connection for UnixStream {
+    fn local_addr(&self) -> io::Result<Peer> {
+        UnixStream::local_addr(self).map(Into::into)
+    }
+
+    fn peer_addr(&self) -> io::Result<Peer> {
+        UnixStream::peer_addr(self).map(Into::into)
+    }
+}
+
+impl<C> Connection for TlsStream<C>
+where
+    C: Connection,
+{
+    fn local_addr(&self) -> io::Result<Peer> {
+        self.get_ref().0.local_addr()
+    }
+
+    fn peer_addr(&self) -> io::Result<Peer> {
+        self.get_ref().0.peer_addr()
+    }
+}
+
+/// A response written to a [`Connection`]
+pub trait Respond: Debug + Send + 'static {
+    /// Write this response, returning the number of bytes written
+    fn respond<W>(self, writer: &mut W) -> impl Future<Output = Result<usize, Error>> + Send
+    where
+        W: AsyncWrite + Send + Unpin;
+}
+
+impl Respond for Bytes {
+    async fn respond<W>(self, writer: &mut W) -> Result<usize, Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+    {
+        writer.write_all(&self).await?;
+
+        let length = self.len();
+        pool::reclaim(self);
+        Ok(length)
+    }
+}
+
+/// A request read from a [`Connection`]
+pub trait Receive: Debug + Send + Sync + Sized + 'static {
+    /// Read a request of the frame size that has already been read
+    fn receive<R>(
+        reader: &mut R,
+        size: [u8; 4],
+        incremental_decode: Option<usize>,
+    ) -> impl Future<Output = Result<Self, Error>> + Send
+    where
+        R: AsyncRead + Send + Unpin;
+
+    /// The API key of this request
+    fn api_key(&self) -> Option<i16>;
+
+    /// The size of this request in bytes
+    fn size(&self) -> usize;
+}
+
+impl Receive for Bytes {
+    async fn receive<R>(
+        reader: &mut R,
+        size: [u8; 4],
+        _incremental_decode: Option<usize>,
+    ) -> Result<Self, Error>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let mut request = zeroed(frame_length(size));
+
+        request[0..size.len()].copy_from_slice(&size[..]);
+
+        _ = reader.read_exact(&mut request[4..]).await?;
+
+        Ok(request.freeze())
+    }
+
+    fn api_key(&self) -> Option<i16> {
+        self.get(4..6)
+            .map(|api_key| i16::from_be_bytes([api_key[0], api_key[1]]))
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
 /// A [`Layer`] that listens for TCP connections
 #[derive(Clone, Debug, Default)]
 pub struct TcpListenerLayer {
@@ -129,12 +279,35 @@ where
     }
 }
 
+/// The requests processed concurrently on a connection, unless configured otherwise
+pub const MAXIMUM_IN_FLIGHT: usize = 5;
+
+/// The API keys of requests that may be processed while others on the same
+/// connection are in flight, unless configured otherwise: only requests that
+/// do not change state
+pub const PIPELINED: [i16; 11] = [
+    FetchRequest::KEY,
+    ListOffsetsRequest::KEY,
+    MetadataRequest::KEY,
+    OffsetFetchRequest::KEY,
+    FindCoordinatorRequest::KEY,
+    DescribeGroupsRequest::KEY,
+    ListGroupsRequest::KEY,
+    ApiVersionsRequest::KEY,
+    DescribeConfigsRequest::KEY,
+    DescribeClusterRequest::KEY,
+    DescribeTopicPartitionsRequest::KEY,
+];
+
 /// A [context state][`Context#method.state`] state used by [`TcpContextLayer`] and [`TcpContextService`]
 #[non_exhaustive]
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct TcpContext {
     cluster_id: Option<String>,
     maximum_frame_size: Option<usize>,
+    maximum_in_flight: Option<usize>,
+    incremental_decode: Option<usize>,
+    pipelined: Option<BTreeSet<i16>>,
 }
 
 impl TcpContext {
@@ -148,6 +321,30 @@ impl TcpContext {
             ..self
         }
     }
+
+    /// The requests processed concurrently on each connection, responses are
+    /// always sent in the order the requests were received
+    pub fn maximum_in_flight(self, maximum_in_flight: Option<usize>) -> Self {
+        Self {
+            maximum_in_flight: maximum_in_flight.map(|maximum_in_flight| maximum_in_flight.max(1)),
+            ..self
+        }
+    }
+
+    /// Produce requests of at least this many bytes are decoded as they are
+    /// read, rather than first reading the whole request into memory
+    pub fn incremental_decode(self, incremental_decode: Option<usize>) -> Self {
+        Self {
+            incremental_decode,
+            ..self
+        }
+    }
+
+    /// The API keys of requests that may be processed while others on the
+    /// same connection are in flight, otherwise [`PIPELINED`]
+    pub fn pipelined(self, pipelined: Option<BTreeSet<i16>>) -> Self {
+        Self { pipelined, ..self }
+    }
 }
 
 /// A [`Layer`] that injects the [`TcpContext`] into the service [`Context`] state
@@ -186,21 +383,18 @@ impl<S> Debug for TcpContextService<S> {
     }
 }
 
-impl<State, S> Service<State, TcpStream> for TcpContextService<S>
+impl<State, S, C> Service<State, C> for TcpContextService<S>
 where
-    S: Service<TcpContext, TcpStream>,
+    S: Service<TcpContext, C>,
     S::Error: From<io::Error>,
     State: Clone + Send + Sync + 'static,
+    C: Connection,
 {
     type Response = S::Response;
     type Error = S::Error;
 
     #[instrument(skip_all, fields(peer = %req.peer_addr()?))]
-    async fn serve(
-        &self,
-        ctx: Context<State>,
-        req: TcpStream,
-    ) -> Result<Self::Response, Self::Error> {
+    async fn serve(&self, ctx: Context<State>, req: C) -> Result<Self::Response, Self::Error> {
         let (ctx, _) = ctx.swap_state(self.state.clone());
 
         self.inner.serve(ctx, req).await
@@ -238,14 +432,22 @@ impl Service<TcpStream, Bytes> for BytesTcpService {
     }
 }
 
-/// A [`Layer`] receiving [`Bytes`] from a [`TcpStream`]
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct TcpBytesLayer<State = ()> {
-    _state: PhantomData<State>,
+/// A [`Layer`] receiving [`Bytes`] (or another [`Receive`]) from a [`Connection`]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TcpBytesLayer<State = (), Request = Bytes> {
+    _state: PhantomData<(State, Request)>,
+}
+
+impl<State, Request> Default for TcpBytesLayer<State, Request> {
+    fn default() -> Self {
+        Self {
+            _state: PhantomData,
+        }
+    }
 }
 
-impl<S, State> Layer<S> for TcpBytesLayer<State> {
-    type Service = TcpBytesService<S, State>;
+impl<S, State, Request> Layer<S> for TcpBytesLayer<State, Request> {
+    type Service = TcpBytesService<S, State, Request>;
 
     fn layer(&self, inner: S) -> Self::Service {
         Self::Service {
@@ -255,20 +457,20 @@ impl<S, State> Layer<S> for TcpBytesLayer<State> {
     }
 }
 
-/// A [`Service`] receiving [`Bytes`] from a [`TcpStream`], calling an inner [`Service`] and sending [`Bytes`] into the [`TcpStream`]
+/// A [`Service`] receiving [`Bytes`] (or another [`Receive`]) from a [`Connection`], calling an inner [`Service`] and sending its [`Respond`] into the [`Connection`]
 #[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct TcpBytesService<S, State> {
+pub struct TcpBytesService<S, State, Request = Bytes> {
     inner: S,
-    _state: PhantomData<State>,
+    _state: PhantomData<(State, Request)>,
 }
 
-impl<S, State> Debug for TcpBytesService<S, State> {
+impl<S, State, Request> Debug for TcpBytesService<S, State, Request> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(stringify!(TcpBytesService)).finish()
     }
 }
 
-impl<S, State> TcpBytesService<S, State> {
+impl<S, State, Request> TcpBytesService<S, State, Request> {
     fn elapsed_millis(&self, start: SystemTime) -> u64 {
         start
             .elapsed()
@@ -276,18 +478,23 @@ impl<S, State> TcpBytesService<S, State> {
     }
 }
 
-impl<S, State> TcpBytesService<S, State>
+impl<S, State, Request> TcpBytesService<S, State, Request>
 where
-    S: Service<State, Bytes, Response = Bytes>,
+    S: Service<State, Request>,
+    S::Response: Respond,
+    Request: Receive,
     S::Error: From<Error> + From<io::Error> + Debug,
     State: Clone + Default + Send + Sync + 'static,
 {
     #[instrument(skip_all)]
-    async fn wait(
+    async fn wait<R>(
         &self,
-        req: &mut TcpStream,
+        req: &mut R,
         maximum_frame_size: Option<usize>,
-    ) -> Result<[u8; 4], S::Error> {
+    ) -> Result<[u8; 4], S::Error>
+    where
+        R: AsyncRead + Unpin,
+    {
         let mut size = [0u8; 4];
 
         _ = req
@@ -296,37 +503,60 @@ where
             .inspect_err(|err| debug!(?err))?;
 
         if maximum_frame_size
-            .is_some_and(|maximum_frame_size| maximum_frame_size > frame_length(size))
+            .is_some_and(|maximum_frame_size| frame_length(size) > maximum_frame_size)
         {
-            return Err(Into::into(Error::FrameTooBig(frame_length(size))));
+            warn!(frame_length = frame_length(size), ?maximum_frame_size);
+            Err(Into::into(Error::FrameTooBig(frame_length(size))))
         } else {
             Ok(size)
         }
     }
 
     #[instrument(skip_all)]
-    async fn read(&self, req: &mut TcpStream, size: [u8; 4]) -> Result<Bytes, S::Error> {
-        let mut request: Vec<u8> = vec![0u8; frame_length(size)];
-
-        request[0..size.len()].copy_from_slice(&size[..]);
-
-        _ = req
-            .read_exact(&mut request[4..])
+    async fn read<R>(
+        &self,
+        req: &mut R,
+        size: [u8; 4],
+        incremental_decode: Option<usize>,
+    ) -> Result<Request, S::Error>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let request = Request::receive(req, size, incremental_decode)
             .await
             .inspect_err(|err| error!(?err))?;
-        BYTES_RECEIVED.add(request.len() as u64, &[]);
+        BYTES_RECEIVED.add(request.size() as u64, &[]);
 
-        Ok(Bytes::from(request))
+        Ok(request)
     }
 
-    #[instrument(skip_all)]
+    /// Read the next frame, returning the reader so that a frame is never
+    /// partially read when waiting on requests in flight
+    async fn next<R>(
+        &self,
+        mut req: R,
+        maximum_frame_size: Option<usize>,
+        incremental_decode: Option<usize>,
+    ) -> (R, Result<Request, S::Error>)
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let frame = match self.wait(&mut req, maximum_frame_size).await {
+            Ok(size) => self.read(&mut req, size, incremental_decode).await,
+            Err(err) => Err(err),
+        };
+
+        (req, frame)
+    }
+
+    #[instrument(skip_all, fields(id = nanoid!()))]
     async fn process(
         &self,
         attributes: &[KeyValue],
         ctx: Context<TcpContext>,
-        request: Bytes,
-    ) -> Result<Bytes, S::Error> {
-        REQUEST_SIZE.record(request.len() as u64, attributes);
+        request: Request,
+    ) -> Result<S::Response, S::Error> {
+        REQUEST_SIZE.record(request.size() as u64, attributes);
 
         let (ctx, _) = ctx.swap_state(State::default());
         let request_start = SystemTime::now();
@@ -335,9 +565,7 @@ where
             .serve(ctx, request)
             .await
             .inspect_err(|err| error!(?err))
-            .inspect(|response| {
-                RESPONSE_SIZE.record(response.len() as u64, attributes);
-
+            .inspect(|_| {
                 let elapsed_millis = self.elapsed_millis(request_start);
 
                 REQUEST_DURATION.record(elapsed_millis, attributes);
@@ -345,44 +573,65 @@ where
     }
 
     #[instrument(skip_all)]
-    async fn write(&self, req: &mut TcpStream, frame: Bytes) -> Result<(), S::Error> {
+    async fn write<W>(
+        &self,
+        attributes: &[KeyValue],
+        req: &mut W,
+        response: S::Response,
+    ) -> Result<(), S::Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+    {
         let mut w = BufWriter::new(req);
-        w.write_all(&frame).await.inspect_err(|err| error!(?err))?;
-        BYTES_SENT.add(frame.len() as u64, &[]);
+
+        let length = response
+            .respond(&mut w)
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        BYTES_SENT.add(length as u64, &[]);
+        RESPONSE_SIZE.record(length as u64, attributes);
+
         w.flush().await.map_err(Into::into)
     }
+}
 
-    #[instrument(skip_all, fields(id = nanoid!()))]
-    async fn req(
-        &self,
-        req: &mut TcpStream,
-        maximum_frame_size: Option<usize>,
-        attributes: &[KeyValue],
-        ctx: Context<TcpContext>,
-    ) -> Result<(), S::Error> {
-        let size = self.wait(req, maximum_frame_size).await?;
-        let request = self.read(req, size).await?;
-        let response = self.process(attributes, ctx, request).await?;
-        self.write(req, response).await
-    }
+/// Whether a request may be processed while others on the same connection
+/// are in flight
+///
+/// Any other request (e.g., produce, offset commit or SASL authentication) is
+/// processed alone, after the requests before it have completed and before any
+/// after it, so that it is applied in the order it was sent.
+fn is_pipelined(pipelined: Option<&BTreeSet<i16>>, api_key: Option<i16>) -> bool {
+    api_key.is_some_and(|api_key| {
+        pipelined.map_or_else(
+            || PIPELINED.contains(&api_key),
+            |pipelined| pipelined.contains(&api_key),
+        )
+    })
 }
 
-impl<S, State> Service<TcpContext, TcpStream> for TcpBytesService<S, State>
+impl<S, State, Request, C> Service<TcpContext, C> for TcpBytesService<S, State, Request>
 where
-    S: Service<State, Bytes, Response = Bytes>,
+    S: Service<State, Request>,
+    S::Response: Respond,
+    Request: Receive,
     S::Error: From<Error> + From<io::Error> + Debug,
     State: Clone + Default + Send + Sync + 'static,
+    C: Connection,
 {
     type Response = ();
 
     type Error = S::Error;
 
+    /// Serve requests from a connection, reading the next request while those
+    /// before it are processed, responding in the order they were received
+    ///
+    /// When the connection can no longer be read (e.g., the client has shut
+    /// down its write half), the responses to the requests in flight are
+    /// written before returning.
     #[instrument(skip(ctx, req))]
-    async fn serve(
-        &self,
-        ctx: Context<TcpContext>,
-        mut req: TcpStream,
-    ) -> Result<Self::Response, Self::Error> {
+    async fn serve(&self, ctx: Context<TcpContext>, req: C) -> Result<Self::Response, Self::Error> {
         let attributes = {
             let state = ctx.state();
 
@@ -399,13 +648,62 @@ where
         };
 
         let maximum_frame_size = ctx.state().maximum_frame_size;
+        let maximum_in_flight = ctx.state().maximum_in_flight.unwrap_or(MAXIMUM_IN_FLIGHT);
+        let incremental_decode = ctx.state().incremental_decode;
+        let pipelined = ctx.state().pipelined.clone();
+        let is_pipelined = |api_key| is_pipelined(pipelined.as_ref(), api_key);
+
+        let (reader, mut writer) = tokio::io::split(req);
+
+        let reading = self.next(reader, maximum_frame_size, incremental_decode);
+        tokio::pin!(reading);
+
+        let mut in_flight = FuturesOrdered::new();
+
+        // a request that must wait for those in flight to complete
+        let mut waiting = None;
+
+        // whether the request in flight is processed alone
+        let mut alone = false;
 
         loop {
-            let ctx = ctx.clone();
-            let attributes = attributes.clone();
+            if in_flight.is_empty()
+                && let Some(request) = waiting.take()
+            {
+                alone = !is_pipelined(request.api_key());
+                in_flight.push_back(self.process(&attributes[..], ctx.clone(), request));
+            }
+
+            tokio::select! {
+                (reader, frame) = &mut reading, if waiting.is_none() && in_flight.len() < maximum_in_flight => {
+                    reading.set(self.next(reader, maximum_frame_size, incremental_decode));
+
+                    let request = match frame {
+                        Ok(request) => request,
 
-            self.req(&mut req, maximum_frame_size, &attributes[..], ctx)
-                .await?
+                        Err(err) => {
+                            debug!(?err, in_flight = in_flight.len());
+
+                            while let Some(response) = in_flight.next().await {
+                                self.write(&attributes[..], &mut writer, response?).await?;
+                            }
+
+                            return Err(err);
+                        }
+                    };
+
+                    if in_flight.is_empty() || (!alone && is_pipelined(request.api_key())) {
+                        alone = !is_pipelined(request.api_key());
+                        in_flight.push_back(self.process(&attributes[..], ctx.clone(), request));
+                    } else {
+                        waiting = Some(request);
+                    }
+                }
+
+                Some(response) = in_flight.next(), if !in_flight.is_empty() => {
+                    self.write(&attributes[..], &mut writer, response?).await?;
+                }
+            }
         }
     }
 }
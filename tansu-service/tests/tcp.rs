@@ -12,13 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use bytes::Bytes;
 use rama::{Context, Layer as _, Service as _};
-use tansu_sans_io::{ApiKey as _, Frame, Header, MetadataRequest, MetadataResponse};
+use tansu_sans_io::{
+    ApiKey as _, Frame, Header, MetadataRequest, MetadataResponse, ProduceRequest, ProduceResponse,
+    produce_request::{PartitionProduceData, TopicProduceData},
+    produce_response::{PartitionProduceResponse, TopicProduceResponse},
+    record::{Record, deflated, inflated},
+};
 use tansu_service::{
-    BytesFrameLayer, BytesTcpService, FrameBytesLayer, FrameService, TcpBytesLayer,
-    TcpContextLayer, TcpListenerLayer,
+    BytesFrameLayer, BytesTcpService, FrameBytesLayer, FrameService, ReceivedFrame, TcpBytesLayer,
+    TcpContext, TcpContextLayer, TcpListenerLayer,
 };
 use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
     net::{TcpListener, TcpStream},
     task::JoinSet,
 };
@@ -29,10 +36,14 @@ use crate::common::{Error, init_tracing};
 
 mod common;
 
-async fn server(cancellation: CancellationToken, listener: TcpListener) -> Result<(), Error> {
+async fn server(
+    cancellation: CancellationToken,
+    listener: TcpListener,
+    maximum_frame_size: Option<usize>,
+) -> Result<(), Error> {
     let server = (
         TcpListenerLayer::new(cancellation),
-        TcpContextLayer::default(),
+        TcpContextLayer::new(TcpContext::default().maximum_frame_size(maximum_frame_size)),
         TcpBytesLayer::<()>::default(),
         BytesFrameLayer,
     )
@@ -70,7 +81,7 @@ async fn tcp_client_server() -> Result<(), Error> {
 
     let _server = {
         let cancellation = cancellation.clone();
-        join.spawn(async move { server(cancellation, listener).await })
+        join.spawn(async move { server(cancellation, listener, None).await })
     };
 
     let stream = TcpStream::connect(local_addr).await?;
@@ -78,24 +89,7 @@ async fn tcp_client_server() -> Result<(), Error> {
     let client = FrameBytesLayer.into_layer(BytesTcpService);
 
     let frame = client
-        .serve(
-            Context::with_state(stream),
-            Frame {
-                header: Header::Request {
-                    api_key: MetadataRequest::KEY,
-                    api_version: 12,
-                    correlation_id: 0,
-                    client_id: Some(env!("CARGO_PKG_NAME").into()),
-                },
-                body: MetadataRequest::default()
-                    .topics(Some([].into()))
-                    .allow_auto_topic_creation(Some(false))
-                    .include_cluster_authorized_operations(Some(false))
-                    .include_topic_authorized_operations(Some(false))
-                    .into(),
-                size: 0,
-            },
-        )
+        .serve(Context::with_state(stream), metadata_request())
         .await?;
 
     let response = MetadataResponse::try_from(frame.body)?;
@@ -109,3 +103,313 @@ async fn tcp_client_server() -> Result<(), Error> {
 
     Ok(())
 }
+
+fn metadata_request() -> Frame {
+    metadata_request_with_correlation_id(0)
+}
+
+fn metadata_request_with_correlation_id(correlation_id: i32) -> Frame {
+    Frame {
+        header: Header::Request {
+            api_key: MetadataRequest::KEY,
+            api_version: 12,
+            correlation_id,
+            client_id: Some(env!("CARGO_PKG_NAME").into()),
+        },
+        body: MetadataRequest::default()
+            .topics(Some([].into()))
+            .allow_auto_topic_creation(Some(false))
+            .include_cluster_authorized_operations(Some(false))
+            .include_topic_authorized_operations(Some(false))
+            .into(),
+        size: 0,
+    }
+}
+
+#[tokio::test]
+async fn frame_too_big() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let cancellation = CancellationToken::new();
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    let mut join = JoinSet::new();
+
+    let _server = {
+        let cancellation = cancellation.clone();
+        join.spawn(async move { server(cancellation, listener, Some(16)).await })
+    };
+
+    let client = FrameBytesLayer.into_layer(BytesTcpService);
+
+    let stream = TcpStream::connect(local_addr).await?;
+    assert!(
+        client
+            .serve(Context::with_state(stream), metadata_request())
+            .await
+            .is_err()
+    );
+
+    cancellation.cancel();
+
+    let joined = join.join_all().await;
+    debug!(?joined);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pipelined_responses_in_request_order() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let cancellation = CancellationToken::new();
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    let mut join = JoinSet::new();
+
+    let _server = {
+        let cancellation = cancellation.clone();
+        join.spawn(async move { server(cancellation, listener, None).await })
+    };
+
+    let mut stream = TcpStream::connect(local_addr).await?;
+
+    let correlation_ids = [3, 1, 4, 1, 5, 9, 2, 6];
+
+    // every request is sent before reading any response
+    for correlation_id in correlation_ids {
+        let Frame { header, body, .. } = metadata_request_with_correlation_id(correlation_id);
+        stream.write_all(&Frame::request(header, body)?).await?;
+    }
+
+    for correlation_id in correlation_ids {
+        let mut size = [0u8; 4];
+        _ = stream.read_exact(&mut size).await?;
+
+        let mut response = vec![0u8; i32::from_be_bytes(size) as usize + size.len()];
+        response[..size.len()].copy_from_slice(&size);
+        _ = stream.read_exact(&mut response[size.len()..]).await?;
+
+        let frame = Frame::response_from_bytes(&response[..], MetadataRequest::KEY, 12)?;
+        assert_eq!(correlation_id, frame.correlation_id()?);
+    }
+
+    cancellation.cancel();
+
+    let joined = join.join_all().await;
+    debug!(?joined);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn in_flight_responses_after_shutdown() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let cancellation = CancellationToken::new();
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    let mut join = JoinSet::new();
+
+    let _server = {
+        let cancellation = cancellation.clone();
+        join.spawn(async move { server(cancellation, listener, None).await })
+    };
+
+    let mut stream = TcpStream::connect(local_addr).await?;
+
+    let correlation_ids = [2, 7, 1, 8, 2, 8];
+
+    for correlation_id in correlation_ids {
+        let Frame { header, body, .. } = metadata_request_with_correlation_id(correlation_id);
+        stream.write_all(&Frame::request(header, body)?).await?;
+    }
+
+    // the write half is closed with the requests still in flight
+    stream.shutdown().await?;
+
+    for correlation_id in correlation_ids {
+        let mut size = [0u8; 4];
+        _ = stream.read_exact(&mut size).await?;
+
+        let mut response = vec![0u8; i32::from_be_bytes(size) as usize + size.len()];
+        response[..size.len()].copy_from_slice(&size);
+        _ = stream.read_exact(&mut response[size.len()..]).await?;
+
+        let frame = Frame::response_from_bytes(&response[..], MetadataRequest::KEY, 12)?;
+        assert_eq!(correlation_id, frame.correlation_id()?);
+    }
+
+    // with every response written, the server closes the connection
+    assert_eq!(0, stream.read(&mut [0u8; 1]).await?);
+
+    cancellation.cancel();
+
+    let joined = join.join_all().await;
+    debug!(?joined);
+
+    Ok(())
+}
+
+async fn produce_server(
+    cancellation: CancellationToken,
+    listener: TcpListener,
+    incremental_decode: Option<usize>,
+) -> Result<(), Error> {
+    let server = (
+        TcpListenerLayer::new(cancellation),
+        TcpContextLayer::new(TcpContext::default().incremental_decode(incremental_decode)),
+        TcpBytesLayer::<(), ReceivedFrame>::default(),
+        BytesFrameLayer,
+    )
+        .into_layer(FrameService::new(|_, req: Frame| {
+            let correlation_id = req.correlation_id()?;
+
+            // each batch received is answered with its length
+            let partition_responses = ProduceRequest::try_from(req.body)?
+                .topic_data
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|topic| topic.partition_data.unwrap_or_default())
+                .map(|partition| {
+                    PartitionProduceResponse::default()
+                        .index(partition.index)
+                        .error_code(0)
+                        .base_offset(
+                            partition
+                                .records
+                                .map(|frame| frame.batches)
+                                .unwrap_or_default()
+                                .iter()
+                                .map(|batch| i64::from(batch.batch_length))
+                                .sum(),
+                        )
+                        .log_append_time_ms(Some(-1))
+                        .log_start_offset(Some(0))
+                        .record_errors(Some([].into()))
+                        .error_message(None)
+                })
+                .collect();
+
+            Ok(Frame {
+                size: 0,
+                header: Header::Response { correlation_id },
+                body: ProduceResponse::default()
+                    .responses(Some(vec![
+                        TopicProduceResponse::default()
+                            .name("test".into())
+                            .partition_responses(Some(partition_responses)),
+                    ]))
+                    .throttle_time_ms(Some(0))
+                    .into(),
+            })
+        }));
+
+    server.serve(Context::default(), listener).await
+}
+
+#[tokio::test]
+async fn produce_decoded_incrementally() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let cancellation = CancellationToken::new();
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local_addr = listener.local_addr()?;
+
+    let mut join = JoinSet::new();
+
+    let _server = {
+        let cancellation = cancellation.clone();
+        join.spawn(async move { produce_server(cancellation, listener, Some(1_024)).await })
+    };
+
+    let mut stream = TcpStream::connect(local_addr).await?;
+
+    // large batches decoded as they are read (flexible and not), and a small
+    // batch read as a frame
+    let batches = [
+        (
+            9,
+            inflated::Batch::builder()
+                .record(Record::builder().value(Bytes::from(vec![1u8; 300_000]).into()))
+                .build()
+                .and_then(deflated::Batch::try_from)?,
+        ),
+        (
+            7,
+            inflated::Batch::builder()
+                .record(Record::builder().value(Bytes::from(vec![2u8; 200_000]).into()))
+                .build()
+                .and_then(deflated::Batch::try_from)?,
+        ),
+        (
+            9,
+            inflated::Batch::builder()
+                .record(Record::builder().value(Bytes::from_static(b"Lorem ipsum").into()))
+                .build()
+                .and_then(deflated::Batch::try_from)?,
+        ),
+    ];
+
+    for (correlation_id, (api_version, batch)) in (0..).zip(batches.iter()) {
+        let request = Frame::request(
+            Header::Request {
+                api_key: ProduceRequest::KEY,
+                api_version: *api_version,
+                correlation_id,
+                client_id: Some(env!("CARGO_PKG_NAME").into()),
+            },
+            ProduceRequest::default()
+                .transactional_id(None)
+                .acks(-1)
+                .timeout_ms(5_000)
+                .topic_data(Some(vec![
+                    TopicProduceData::default()
+                        .name("test".into())
+                        .partition_data(Some(vec![
+                            PartitionProduceData::default().index(0).records(Some(
+                                deflated::Frame {
+                                    batches: vec![batch.clone()],
+                                },
+                            )),
+                        ])),
+                ]))
+                .into(),
+        )?;
+
+        stream.write_all(&request).await?;
+    }
+
+    for (correlation_id, (api_version, batch)) in (0..).zip(batches.iter()) {
+        let mut size = [0u8; 4];
+        _ = stream.read_exact(&mut size).await?;
+
+        let mut response = vec![0u8; i32::from_be_bytes(size) as usize + size.len()];
+        response[..size.len()].copy_from_slice(&size);
+        _ = stream.read_exact(&mut response[size.len()..]).await?;
+
+        let frame = Frame::response_from_bytes(&response[..], ProduceRequest::KEY, *api_version)?;
+        assert_eq!(correlation_id, frame.correlation_id()?);
+
+        let response = ProduceResponse::try_from(frame.body)?;
+        let partitions = response
+            .responses
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        assert_eq!(1, partitions.len());
+        assert_eq!(i64::from(batch.batch_length), partitions[0].base_offset);
+    }
+
+    cancellation.cancel();
+
+    let joined = join.join_all().await;
+    debug!(?joined);
+
+    Ok(())
+}
@@ -0,0 +1,574 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consumers in a group, periodically leaving and rejoining to force rebalances
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tansu_client::Client;
+use tansu_sans_io::{
+    BatchAttribute, ErrorCode, FetchRequest, HeartbeatRequest, JoinGroupRequest, LeaveGroupRequest,
+    OffsetCommitRequest, OffsetFetchRequest, SyncGroupRequest,
+    fetch_request::{FetchPartition, FetchTopic, ReplicaState},
+    join_group_request::JoinGroupRequestProtocol,
+    leave_group_request::MemberIdentity,
+    offset_commit_request::{OffsetCommitRequestPartition, OffsetCommitRequestTopic},
+    offset_fetch_request::{
+        OffsetFetchRequestGroup, OffsetFetchRequestTopic, OffsetFetchRequestTopics,
+    },
+    record::inflated,
+    sync_group_request::SyncGroupRequestAssignment,
+};
+use tokio::time::{Instant, sleep};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument};
+
+use crate::{
+    Error, Result,
+    ledger::{Key, Ledger, Location},
+};
+
+const PROTOCOL_TYPE: &str = "tansu-soak";
+const PROTOCOL_NAME: &str = "roundrobin";
+
+/// Assign partitions round robin to the members of the group
+fn assign(members: &[String], partitions: i32) -> BTreeMap<String, Vec<i32>> {
+    let mut members = members.to_vec();
+    members.sort();
+
+    let mut assignments = members
+        .iter()
+        .map(|member| (member.to_owned(), vec![]))
+        .collect::<BTreeMap<_, _>>();
+
+    for (partition, member) in (0..partitions).zip(members.iter().cycle()) {
+        if let Some(assigned) = assignments.get_mut(member) {
+            assigned.push(partition);
+        }
+    }
+
+    assignments
+}
+
+fn encode(partitions: &[i32]) -> Bytes {
+    let mut encoded = BytesMut::with_capacity(partitions.len() * size_of::<i32>());
+
+    for partition in partitions {
+        encoded.put_i32(*partition);
+    }
+
+    encoded.freeze()
+}
+
+fn decode(mut encoded: Bytes) -> Vec<i32> {
+    let mut partitions = vec![];
+
+    while encoded.remaining() >= size_of::<i32>() {
+        partitions.push(encoded.get_i32());
+    }
+
+    partitions
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Membership {
+    generation_id: i32,
+    member_id: String,
+    leader: bool,
+    members: Vec<String>,
+}
+
+/// Why a consumer stopped consuming its assignment
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Outcome {
+    Cancelled,
+    Leave,
+    Rejoin,
+}
+
+fn rebalancing(error_code: ErrorCode) -> bool {
+    matches!(
+        error_code,
+        ErrorCode::RebalanceInProgress | ErrorCode::IllegalGeneration | ErrorCode::UnknownMemberId
+    )
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Consumer {
+    pub(crate) id: u32,
+    pub(crate) client: Client,
+    pub(crate) group_id: String,
+    pub(crate) topic: String,
+    pub(crate) topic_id: [u8; 16],
+    pub(crate) partitions: i32,
+    pub(crate) session_timeout: Duration,
+    pub(crate) rebalance_interval: Duration,
+    pub(crate) ledger: Arc<Mutex<Ledger>>,
+    pub(crate) producing: CancellationToken,
+    pub(crate) token: CancellationToken,
+}
+
+impl Consumer {
+    fn session_timeout_ms(&self) -> i32 {
+        i32::try_from(self.session_timeout.as_millis()).unwrap_or(i32::MAX)
+    }
+
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn join(&self, member_id: &mut String) -> Result<Membership> {
+        loop {
+            let response = self
+                .client
+                .call(
+                    JoinGroupRequest::default()
+                        .group_id(self.group_id.clone())
+                        .session_timeout_ms(self.session_timeout_ms())
+                        .rebalance_timeout_ms(Some(self.session_timeout_ms()))
+                        .member_id(member_id.clone())
+                        .group_instance_id(None)
+                        .protocol_type(PROTOCOL_TYPE.into())
+                        .protocols(Some(
+                            [JoinGroupRequestProtocol::default()
+                                .name(PROTOCOL_NAME.into())
+                                .metadata(Bytes::new())]
+                            .into(),
+                        ))
+                        .reason(None),
+                )
+                .await?;
+
+            match ErrorCode::try_from(response.error_code)? {
+                ErrorCode::None => {
+                    member_id.clone_from(&response.member_id);
+
+                    return Ok(Membership {
+                        generation_id: response.generation_id,
+                        leader: response.leader == response.member_id,
+                        member_id: response.member_id,
+                        members: response
+                            .members
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|member| member.member_id)
+                            .collect(),
+                    });
+                }
+
+                ErrorCode::MemberIdRequired => member_id.clone_from(&response.member_id),
+
+                ErrorCode::UnknownMemberId => member_id.clear(),
+
+                error_code if rebalancing(error_code) => continue,
+
+                error_code => return Err(Error::Api(error_code)),
+            }
+        }
+    }
+
+    /// The partitions assigned to this member, or none when the group is rebalancing
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn sync(&self, membership: &Membership) -> Result<Option<Vec<i32>>> {
+        let assignments = membership
+            .leader
+            .then(|| assign(&membership.members[..], self.partitions))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(member_id, partitions)| {
+                SyncGroupRequestAssignment::default()
+                    .member_id(member_id)
+                    .assignment(encode(&partitions[..]))
+            })
+            .collect();
+
+        let response = self
+            .client
+            .call(
+                SyncGroupRequest::default()
+                    .group_id(self.group_id.clone())
+                    .generation_id(membership.generation_id)
+                    .member_id(membership.member_id.clone())
+                    .group_instance_id(None)
+                    .protocol_type(Some(PROTOCOL_TYPE.into()))
+                    .protocol_name(Some(PROTOCOL_NAME.into()))
+                    .assignments(Some(assignments)),
+            )
+            .await?;
+
+        match ErrorCode::try_from(response.error_code)? {
+            ErrorCode::None => Ok(Some(decode(response.assignment))),
+            error_code if rebalancing(error_code) => Ok(None),
+            error_code => Err(Error::Api(error_code)),
+        }
+    }
+
+    /// The position of each assigned partition from the offsets committed by the group
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn positions(&self, assigned: &[i32]) -> Result<BTreeMap<i32, i64>> {
+        let response = self
+            .client
+            .call(
+                OffsetFetchRequest::default()
+                    .group_id(Some(self.group_id.clone()))
+                    .topics(Some(
+                        [OffsetFetchRequestTopic::default()
+                            .name(self.topic.clone())
+                            .partition_indexes(Some(assigned.to_vec()))]
+                        .into(),
+                    ))
+                    .groups(Some(
+                        [OffsetFetchRequestGroup::default()
+                            .group_id(self.group_id.clone())
+                            .member_id(None)
+                            .member_epoch(Some(-1))
+                            .topics(Some(
+                                [OffsetFetchRequestTopics::default()
+                                    .name(self.topic.clone())
+                                    .partition_indexes(Some(assigned.to_vec()))]
+                                .into(),
+                            ))]
+                        .into(),
+                    ))
+                    .require_stable(Some(false)),
+            )
+            .await?;
+
+        let committed = response
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+            .map(|partition| (partition.partition_index, partition.committed_offset))
+            .chain(
+                response
+                    .groups
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flat_map(|group| group.topics.unwrap_or_default())
+                    .flat_map(|topic| topic.partitions.unwrap_or_default())
+                    .map(|partition| (partition.partition_index, partition.committed_offset)),
+            )
+            .collect::<BTreeMap<_, _>>();
+
+        Ok(assigned
+            .iter()
+            .map(|partition| {
+                (
+                    *partition,
+                    committed.get(partition).copied().unwrap_or(-1).max(0),
+                )
+            })
+            .collect())
+    }
+
+    /// Fetch from each position, entering every record into the ledger
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn fetch(&self, positions: &mut BTreeMap<i32, i64>) -> Result<()> {
+        let response = self
+            .client
+            .call(
+                FetchRequest::default()
+                    .cluster_id(Some("".into()))
+                    .replica_id(Some(-1))
+                    .replica_state(Some(ReplicaState::default()))
+                    .max_wait_ms(500)
+                    .min_bytes(1)
+                    .max_bytes(Some(1_048_576))
+                    .isolation_level(Some(0))
+                    .session_id(Some(-1))
+                    .session_epoch(Some(-1))
+                    .topics(Some(
+                        [FetchTopic::default()
+                            .topic(Some(self.topic.clone()))
+                            .topic_id(Some(self.topic_id))
+                            .partitions(Some(
+                                positions
+                                    .iter()
+                                    .map(|(partition, position)| {
+                                        FetchPartition::default()
+                                            .partition(*partition)
+                                            .fetch_offset(*position)
+                                            .log_start_offset(Some(-1))
+                                            .partition_max_bytes(262_144)
+                                    })
+                                    .collect(),
+                            ))]
+                        .into(),
+                    ))
+                    .forgotten_topics_data(Some([].into()))
+                    .rack_id(Some("".into())),
+            )
+            .await?;
+
+        for partition in response
+            .responses
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+        {
+            let error_code = ErrorCode::try_from(partition.error_code)?;
+
+            if error_code != ErrorCode::None {
+                return Err(Error::Api(error_code));
+            }
+
+            let Some(position) = positions.get_mut(&partition.partition_index) else {
+                continue;
+            };
+
+            let Some(frame) = partition.records else {
+                continue;
+            };
+
+            let mut previous = *position - 1;
+
+            for batch in inflated::Frame::try_from(frame)?.batches {
+                if BatchAttribute::try_from(batch.attributes)?.control {
+                    previous = previous.max(batch.base_offset + i64::from(batch.last_offset_delta));
+                    continue;
+                }
+
+                for record in batch.records {
+                    let offset = batch.base_offset + i64::from(record.offset_delta);
+
+                    if offset < *position {
+                        continue;
+                    }
+
+                    let mut ledger = self.ledger.lock()?;
+
+                    if offset <= previous {
+                        ledger.fetch_not_monotonic(
+                            self.id,
+                            partition.partition_index,
+                            previous,
+                            offset,
+                        );
+                    }
+
+                    ledger.consume(
+                        Location::new(partition.partition_index, offset),
+                        record.value.as_deref().and_then(Key::decode),
+                    );
+
+                    previous = offset;
+                }
+            }
+
+            *position = previous + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Commit the positions, returning false when the group is rebalancing
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn commit(
+        &self,
+        membership: &Membership,
+        positions: &BTreeMap<i32, i64>,
+    ) -> Result<bool> {
+        let response = self
+            .client
+            .call(
+                OffsetCommitRequest::default()
+                    .group_id(self.group_id.clone())
+                    .generation_id_or_member_epoch(Some(membership.generation_id))
+                    .member_id(Some(membership.member_id.clone()))
+                    .group_instance_id(None)
+                    .retention_time_ms(Some(-1))
+                    .topics(Some(
+                        [OffsetCommitRequestTopic::default()
+                            .name(self.topic.clone())
+                            .partitions(Some(
+                                positions
+                                    .iter()
+                                    .map(|(partition, position)| {
+                                        OffsetCommitRequestPartition::default()
+                                            .partition_index(*partition)
+                                            .committed_offset(*position)
+                                            .committed_leader_epoch(Some(-1))
+                                            .commit_timestamp(None)
+                                            .committed_metadata(Some("".into()))
+                                    })
+                                    .collect(),
+                            ))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        for partition in response
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+        {
+            match ErrorCode::try_from(partition.error_code)? {
+                ErrorCode::None => {
+                    if let Some(position) = positions.get(&partition.partition_index) {
+                        self.ledger
+                            .lock()?
+                            .commit(partition.partition_index, *position);
+                    }
+                }
+
+                error_code if rebalancing(error_code) => return Ok(false),
+
+                error_code => return Err(Error::Api(error_code)),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Heartbeat, returning false when the group is rebalancing
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn heartbeat(&self, membership: &Membership) -> Result<bool> {
+        let response = self
+            .client
+            .call(
+                HeartbeatRequest::default()
+                    .group_id(self.group_id.clone())
+                    .generation_id(membership.generation_id)
+                    .member_id(membership.member_id.clone())
+                    .group_instance_id(None),
+            )
+            .await?;
+
+        match ErrorCode::try_from(response.error_code)? {
+            ErrorCode::None => Ok(true),
+            error_code if rebalancing(error_code) => Ok(false),
+            error_code => Err(Error::Api(error_code)),
+        }
+    }
+
+    #[instrument(skip_all, fields(id = self.id))]
+    async fn leave(&self, member_id: &str) -> Result<()> {
+        let response = self
+            .client
+            .call(
+                LeaveGroupRequest::default()
+                    .group_id(self.group_id.clone())
+                    .member_id(Some(member_id.into()))
+                    .members(Some(
+                        [MemberIdentity::default()
+                            .member_id(member_id.into())
+                            .group_instance_id(None)
+                            .reason(Some("soak rebalance".into()))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        debug!(error_code = response.error_code);
+
+        Ok(())
+    }
+
+    /// Consume the assignment until cancelled, asked to leave, or the group rebalances
+    async fn consume(&self, membership: &Membership, assigned: &[i32]) -> Result<Outcome> {
+        let mut positions = self.positions(assigned).await?;
+        debug!(id = self.id, ?positions);
+
+        let heartbeat_interval = self.session_timeout / 3;
+        let mut heartbeat_at = Instant::now() + heartbeat_interval;
+
+        // stagger each consumer leaving the group
+        let leave_at = Instant::now()
+            + self
+                .rebalance_interval
+                .mul_f64(1.0 + f64::from(self.id) / 10.0);
+
+        loop {
+            if self.token.is_cancelled() {
+                return Ok(Outcome::Cancelled);
+            }
+
+            if !self.producing.is_cancelled() && Instant::now() >= leave_at {
+                return Ok(Outcome::Leave);
+            }
+
+            if positions.is_empty() {
+                sleep(Duration::from_millis(100)).await;
+            } else {
+                self.fetch(&mut positions).await?;
+
+                if !self.commit(membership, &positions).await? {
+                    return Ok(Outcome::Rejoin);
+                }
+            }
+
+            if Instant::now() >= heartbeat_at {
+                if !self.heartbeat(membership).await? {
+                    return Ok(Outcome::Rejoin);
+                }
+
+                heartbeat_at = Instant::now() + heartbeat_interval;
+            }
+        }
+    }
+
+    /// Join, sync and consume, leaving and rejoining the group until cancelled
+    pub(crate) async fn run(self) -> Result<()> {
+        let mut member_id = String::new();
+
+        loop {
+            let membership = self.join(&mut member_id).await?;
+            debug!(id = self.id, ?membership);
+
+            let Some(assigned) = self.sync(&membership).await? else {
+                continue;
+            };
+
+            self.ledger.lock()?.rebalanced();
+
+            match self.consume(&membership, &assigned[..]).await? {
+                Outcome::Rejoin => continue,
+
+                Outcome::Leave => {
+                    self.leave(&member_id).await?;
+                    member_id.clear();
+                }
+
+                Outcome::Cancelled => {
+                    return self.leave(&member_id).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin() {
+        let members = ["b".to_owned(), "a".to_owned()];
+
+        assert_eq!(
+            BTreeMap::from([("a".to_owned(), vec![0, 2]), ("b".to_owned(), vec![1])]),
+            assign(&members[..], 3)
+        );
+    }
+
+    #[test]
+    fn assignment_round_trip() {
+        assert_eq!(vec![0, 2, 5], decode(encode(&[0, 2, 5])));
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Invariants checked by the soak test
+//!
+//! Every acknowledged produce and every consumed record is entered into the
+//! ledger, which reports a [`Violation`] when:
+//!
+//! - an offset holds two different records;
+//! - a record is stored at two different offsets;
+//! - the base offsets of a producer, or the offsets fetched by a consumer, go backwards;
+//! - consumers don't catch up with the high watermark once producers have stopped;
+//! - an acknowledged record is never consumed.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::Serialize;
+
+/// Identifies a record by the producer that sent it and its sequence within that producer
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub(crate) struct Key {
+    pub(crate) producer: u32,
+    pub(crate) sequence: u64,
+}
+
+impl Key {
+    const SIZE: usize = size_of::<u32>() + size_of::<u64>();
+
+    pub(crate) fn new(producer: u32, sequence: u64) -> Self {
+        Self { producer, sequence }
+    }
+
+    /// Encode as a record value, padded with zeroes to at least `size` bytes
+    pub(crate) fn encode(&self, size: usize) -> Bytes {
+        let mut value = BytesMut::with_capacity(size.max(Self::SIZE));
+        value.put_u32(self.producer);
+        value.put_u64(self.sequence);
+        value.resize(size.max(Self::SIZE), 0);
+        value.freeze()
+    }
+
+    pub(crate) fn decode(value: &[u8]) -> Option<Self> {
+        let producer = value.get(..4)?.try_into().map(u32::from_be_bytes).ok()?;
+        let sequence = value
+            .get(4..Self::SIZE)?
+            .try_into()
+            .map(u64::from_be_bytes)
+            .ok()?;
+
+        Some(Self::new(producer, sequence))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub(crate) struct Location {
+    pub(crate) partition: i32,
+    pub(crate) offset: i64,
+}
+
+impl Location {
+    pub(crate) fn new(partition: i32, offset: i64) -> Self {
+        Self { partition, offset }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(tag = "violation", rename_all = "snake_case")]
+pub(crate) enum Violation {
+    /// An acknowledged record that was never consumed
+    Lost { location: Location, key: Key },
+
+    /// A record found at more than one offset
+    Duplicate {
+        key: Key,
+        first: Location,
+        second: Location,
+    },
+
+    /// An offset holding more than one record
+    Conflict {
+        location: Location,
+        expected: Key,
+        actual: Key,
+    },
+
+    /// A fetched record that was not produced by this soak test
+    Unrecognised { location: Location },
+
+    /// A produce acknowledged with a base offset at or before a previous produce
+    ProduceNotMonotonic {
+        producer: u32,
+        partition: i32,
+        previous: i64,
+        base_offset: i64,
+    },
+
+    /// A fetch returning an offset at or before an offset already fetched
+    FetchNotMonotonic {
+        consumer: u32,
+        partition: i32,
+        previous: i64,
+        offset: i64,
+    },
+
+    /// Consumers that did not catch up with the high watermark in time
+    LagNotConverged {
+        partition: i32,
+        high_watermark: i64,
+        committed: i64,
+    },
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub(crate) struct Diagnostics {
+    acknowledged: usize,
+    consumed: usize,
+    rebalances: u64,
+    committed: BTreeMap<i32, i64>,
+    high_watermarks: BTreeMap<i32, i64>,
+    violations: Vec<Violation>,
+}
+
+impl Diagnostics {
+    pub(crate) fn violations(&self) -> usize {
+        self.violations.len()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Ledger {
+    acknowledged: BTreeMap<Location, Key>,
+    consumed: BTreeMap<Location, Key>,
+    located: HashMap<Key, Location>,
+    produced: BTreeMap<(u32, i32), i64>,
+    committed: BTreeMap<i32, i64>,
+    rebalances: u64,
+    violations: Vec<Violation>,
+}
+
+impl Ledger {
+    /// Records acknowledged by the broker as stored from `base_offset` onwards
+    pub(crate) fn acknowledge(&mut self, partition: i32, base_offset: i64, keys: &[Key]) {
+        let Some(producer) = keys.first().map(|key| key.producer) else {
+            return;
+        };
+
+        if let Some(previous) = self
+            .produced
+            .insert((producer, partition), base_offset)
+            .filter(|previous| *previous >= base_offset)
+        {
+            self.violations.push(Violation::ProduceNotMonotonic {
+                producer,
+                partition,
+                previous,
+                base_offset,
+            });
+        }
+
+        for (offset, key) in (base_offset..).zip(keys) {
+            let location = Location::new(partition, offset);
+
+            if let Some(expected) = self
+                .acknowledged
+                .insert(location, *key)
+                .or(self.consumed.get(&location).copied())
+                .filter(|existing| existing != key)
+            {
+                self.violations.push(Violation::Conflict {
+                    location,
+                    expected,
+                    actual: *key,
+                });
+            }
+        }
+    }
+
+    /// A record fetched by a consumer
+    pub(crate) fn consume(&mut self, location: Location, key: Option<Key>) {
+        let Some(key) = key else {
+            self.violations.push(Violation::Unrecognised { location });
+            return;
+        };
+
+        if let Some(expected) = self
+            .acknowledged
+            .get(&location)
+            .or(self.consumed.get(&location))
+            .copied()
+            .filter(|existing| *existing != key)
+        {
+            self.violations.push(Violation::Conflict {
+                location,
+                expected,
+                actual: key,
+            });
+        }
+
+        if let Some(first) = self
+            .located
+            .insert(key, location)
+            .filter(|first| *first != location)
+        {
+            self.violations.push(Violation::Duplicate {
+                key,
+                first,
+                second: location,
+            });
+        }
+
+        _ = self.consumed.insert(location, key);
+    }
+
+    pub(crate) fn fetch_not_monotonic(
+        &mut self,
+        consumer: u32,
+        partition: i32,
+        previous: i64,
+        offset: i64,
+    ) {
+        self.violations.push(Violation::FetchNotMonotonic {
+            consumer,
+            partition,
+            previous,
+            offset,
+        });
+    }
+
+    pub(crate) fn commit(&mut self, partition: i32, offset: i64) {
+        _ = self.committed.insert(partition, offset);
+    }
+
+    pub(crate) fn rebalanced(&mut self) {
+        self.rebalances += 1;
+    }
+
+    pub(crate) fn has_violations(&self) -> bool {
+        !self.violations.is_empty()
+    }
+
+    /// Whether the committed offset of every partition has reached its high watermark
+    pub(crate) fn converged(&self, high_watermarks: &BTreeMap<i32, i64>) -> bool {
+        high_watermarks.iter().all(|(partition, high_watermark)| {
+            self.committed.get(partition).copied().unwrap_or_default() >= *high_watermark
+        })
+    }
+
+    pub(crate) fn lagging(&mut self, high_watermarks: &BTreeMap<i32, i64>) {
+        for (partition, high_watermark) in high_watermarks {
+            let committed = self.committed.get(partition).copied().unwrap_or_default();
+
+            if committed < *high_watermark {
+                self.violations.push(Violation::LagNotConverged {
+                    partition: *partition,
+                    high_watermark: *high_watermark,
+                    committed,
+                });
+            }
+        }
+    }
+
+    /// Every acknowledged record must have been consumed
+    pub(crate) fn lost(&mut self) {
+        let lost = self
+            .acknowledged
+            .iter()
+            .filter(|(location, _)| !self.consumed.contains_key(location))
+            .map(|(location, key)| Violation::Lost {
+                location: *location,
+                key: *key,
+            })
+            .collect::<Vec<_>>();
+
+        self.violations.extend(lost);
+    }
+
+    pub(crate) fn diagnostics(&self, high_watermarks: BTreeMap<i32, i64>) -> Diagnostics {
+        Diagnostics {
+            acknowledged: self.acknowledged.len(),
+            consumed: self.consumed.len(),
+            rebalances: self.rebalances,
+            committed: self.committed.clone(),
+            high_watermarks,
+            violations: self.violations.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_round_trip() {
+        let key = Key::new(3, 32_123);
+        let value = key.encode(1_024);
+
+        assert_eq!(1_024, value.len());
+        assert_eq!(Some(key), Key::decode(&value[..]));
+        assert_eq!(None, Key::decode(b"tansu"));
+    }
+
+    #[test]
+    fn acknowledged_and_consumed() {
+        let mut ledger = Ledger::default();
+
+        ledger.acknowledge(0, 0, &[Key::new(0, 0), Key::new(0, 1)]);
+        ledger.consume(Location::new(0, 0), Some(Key::new(0, 0)));
+        ledger.consume(Location::new(0, 1), Some(Key::new(0, 1)));
+
+        // consumed again after a rebalance
+        ledger.consume(Location::new(0, 1), Some(Key::new(0, 1)));
+
+        ledger.lost();
+        assert!(!ledger.has_violations());
+    }
+
+    #[test]
+    fn lost() {
+        let mut ledger = Ledger::default();
+
+        ledger.acknowledge(0, 0, &[Key::new(0, 0), Key::new(0, 1)]);
+        ledger.consume(Location::new(0, 0), Some(Key::new(0, 0)));
+        ledger.lost();
+
+        assert_eq!(
+            vec![Violation::Lost {
+                location: Location::new(0, 1),
+                key: Key::new(0, 1)
+            }],
+            ledger.violations
+        );
+    }
+
+    #[test]
+    fn conflict() {
+        let mut ledger = Ledger::default();
+
+        ledger.acknowledge(0, 0, &[Key::new(0, 0)]);
+        ledger.acknowledge(0, 0, &[Key::new(1, 0)]);
+
+        assert_eq!(
+            vec![Violation::Conflict {
+                location: Location::new(0, 0),
+                expected: Key::new(0, 0),
+                actual: Key::new(1, 0)
+            }],
+            ledger.violations
+        );
+    }
+
+    #[test]
+    fn duplicate() {
+        let mut ledger = Ledger::default();
+
+        ledger.consume(Location::new(0, 1), Some(Key::new(0, 0)));
+        ledger.consume(Location::new(0, 0), Some(Key::new(0, 0)));
+
+        assert_eq!(
+            vec![Violation::Duplicate {
+                key: Key::new(0, 0),
+                first: Location::new(0, 1),
+                second: Location::new(0, 0)
+            }],
+            ledger.violations
+        );
+    }
+
+    #[test]
+    fn produce_not_monotonic() {
+        let mut ledger = Ledger::default();
+
+        ledger.acknowledge(0, 5, &[Key::new(0, 0)]);
+        ledger.acknowledge(0, 5, &[Key::new(0, 1)]);
+
+        assert!(ledger.violations.contains(&Violation::ProduceNotMonotonic {
+            producer: 0,
+            partition: 0,
+            previous: 5,
+            base_offset: 5
+        }));
+    }
+
+    #[test]
+    fn lag() {
+        let mut ledger = Ledger::default();
+        ledger.commit(0, 10);
+
+        let high_watermarks = BTreeMap::from([(0, 10), (1, 0)]);
+        assert!(ledger.converged(&high_watermarks));
+
+        let high_watermarks = BTreeMap::from([(0, 10), (1, 3)]);
+        assert!(!ledger.converged(&high_watermarks));
+
+        ledger.lagging(&high_watermarks);
+        assert_eq!(
+            vec![Violation::LagNotConverged {
+                partition: 1,
+                high_watermark: 3,
+                committed: 0
+            }],
+            ledger.violations
+        );
+    }
+}
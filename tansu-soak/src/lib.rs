@@ -0,0 +1,523 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Soak test for release qualification
+//!
+//! Runs producers and a group of consumers against a broker for a long
+//! duration, with consumers periodically leaving and rejoining the group to
+//! force rebalances. Every acknowledged and consumed record is entered into a
+//! ledger checking that no offset is lost or duplicated, that offsets are
+//! monotonic, and that the consumers catch up with the high watermark once
+//! the producers have stopped. Diagnostics are written as JSON, with an error
+//! returned on any violation.
+
+use core::{
+    fmt::{self, Display},
+    result,
+};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write, stdout},
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tansu_client::{Client, ConnectionManager};
+use tansu_sans_io::{
+    ErrorCode, ListOffsetsRequest, MetadataRequest, NULL_TOPIC_ID,
+    list_offsets_request::{ListOffsetsPartition, ListOffsetsTopic},
+    metadata_request::MetadataRequestTopic,
+};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    task::JoinSet,
+    time::{Instant, interval, sleep},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    consumer::Consumer,
+    ledger::{Diagnostics, Ledger},
+    producer::Producer,
+};
+
+mod consumer;
+mod ledger;
+mod producer;
+
+pub type Result<T, E = Error> = result::Result<T, E>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    Api(ErrorCode),
+    Client(#[from] tansu_client::Error),
+    Io(Arc<io::Error>),
+    Json(#[from] serde_json::Error),
+    Poison,
+    Protocol(#[from] tansu_sans_io::Error),
+    UnknownTopic(String),
+    Violation(usize),
+}
+
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_value: PoisonError<T>) -> Self {
+        Self::Poison
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(Arc::new(value))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// How the soak test finished
+#[derive(Debug)]
+enum Outcome {
+    Converged,
+    Failed(Error),
+    Interrupted,
+    NotConverged,
+    Violated,
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Soak {
+    broker: Url,
+    topic: String,
+    group_id: String,
+    producers: u32,
+    consumers: u32,
+    batch_size: u32,
+    record_size: usize,
+    duration: Duration,
+    rebalance_interval: Duration,
+    session_timeout: Duration,
+    convergence_timeout: Duration,
+    diagnostics: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Builder<B, T> {
+    broker: B,
+    topic: T,
+    group_id: String,
+    producers: u32,
+    consumers: u32,
+    batch_size: u32,
+    record_size: usize,
+    duration: Duration,
+    rebalance_interval: Duration,
+    session_timeout: Duration,
+    convergence_timeout: Duration,
+    diagnostics: Option<PathBuf>,
+}
+
+impl Default for Builder<PhantomData<Url>, PhantomData<String>> {
+    fn default() -> Self {
+        Self {
+            broker: Default::default(),
+            topic: Default::default(),
+            group_id: env!("CARGO_PKG_NAME").into(),
+            producers: 1,
+            consumers: 3,
+            batch_size: 10,
+            record_size: 1024,
+            duration: Duration::from_secs(3_600),
+            rebalance_interval: Duration::from_secs(60),
+            session_timeout: Duration::from_secs(30),
+            convergence_timeout: Duration::from_secs(60),
+            diagnostics: None,
+        }
+    }
+}
+
+impl<B, T> Builder<B, T> {
+    pub fn broker(self, broker: impl Into<Url>) -> Builder<Url, T> {
+        Builder {
+            broker: broker.into(),
+            topic: self.topic,
+            group_id: self.group_id,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            record_size: self.record_size,
+            duration: self.duration,
+            rebalance_interval: self.rebalance_interval,
+            session_timeout: self.session_timeout,
+            convergence_timeout: self.convergence_timeout,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    pub fn topic(self, topic: impl Into<String>) -> Builder<B, String> {
+        Builder {
+            broker: self.broker,
+            topic: topic.into(),
+            group_id: self.group_id,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            record_size: self.record_size,
+            duration: self.duration,
+            rebalance_interval: self.rebalance_interval,
+            session_timeout: self.session_timeout,
+            convergence_timeout: self.convergence_timeout,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    pub fn group_id(self, group_id: impl Into<String>) -> Self {
+        Self {
+            group_id: group_id.into(),
+            ..self
+        }
+    }
+
+    pub fn producers(self, producers: u32) -> Self {
+        Self { producers, ..self }
+    }
+
+    pub fn consumers(self, consumers: u32) -> Self {
+        Self { consumers, ..self }
+    }
+
+    pub fn batch_size(self, batch_size: u32) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            ..self
+        }
+    }
+
+    pub fn record_size(self, record_size: usize) -> Self {
+        Self {
+            record_size,
+            ..self
+        }
+    }
+
+    pub fn duration(self, duration: Duration) -> Self {
+        Self { duration, ..self }
+    }
+
+    pub fn rebalance_interval(self, rebalance_interval: Duration) -> Self {
+        Self {
+            rebalance_interval,
+            ..self
+        }
+    }
+
+    pub fn session_timeout(self, session_timeout: Duration) -> Self {
+        Self {
+            session_timeout,
+            ..self
+        }
+    }
+
+    pub fn convergence_timeout(self, convergence_timeout: Duration) -> Self {
+        Self {
+            convergence_timeout,
+            ..self
+        }
+    }
+
+    pub fn diagnostics(self, diagnostics: Option<PathBuf>) -> Self {
+        Self {
+            diagnostics,
+            ..self
+        }
+    }
+}
+
+impl Builder<Url, String> {
+    pub fn build(self) -> Soak {
+        Soak {
+            broker: self.broker,
+            topic: self.topic,
+            group_id: self.group_id,
+            producers: self.producers,
+            consumers: self.consumers,
+            batch_size: self.batch_size,
+            record_size: self.record_size,
+            duration: self.duration,
+            rebalance_interval: self.rebalance_interval,
+            session_timeout: self.session_timeout,
+            convergence_timeout: self.convergence_timeout,
+            diagnostics: self.diagnostics,
+        }
+    }
+}
+
+impl Soak {
+    pub fn builder() -> Builder<PhantomData<Url>, PhantomData<String>> {
+        Builder::default()
+    }
+
+    /// The topic id and number of partitions of the topic
+    async fn describe(&self, client: &Client) -> Result<([u8; 16], i32)> {
+        let response = client
+            .call(
+                MetadataRequest::default()
+                    .allow_auto_topic_creation(Some(false))
+                    .include_cluster_authorized_operations(Some(false))
+                    .include_topic_authorized_operations(Some(false))
+                    .topics(Some(
+                        [MetadataRequestTopic::default()
+                            .name(Some(self.topic.clone()))
+                            .topic_id(Some(NULL_TOPIC_ID))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        response
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .find(|topic| {
+                topic.error_code == i16::from(ErrorCode::None)
+                    && topic.name.as_deref() == Some(self.topic.as_str())
+            })
+            .and_then(|topic| {
+                topic.topic_id.map(|topic_id| {
+                    (
+                        topic_id,
+                        topic
+                            .partitions
+                            .map_or(0, |partitions| partitions.len() as i32),
+                    )
+                })
+            })
+            .ok_or(Error::UnknownTopic(self.topic.clone()))
+    }
+
+    /// The high watermark of each partition of the topic
+    async fn high_watermarks(
+        &self,
+        client: &Client,
+        partitions: i32,
+    ) -> Result<BTreeMap<i32, i64>> {
+        let response = client
+            .call(
+                ListOffsetsRequest::default()
+                    .replica_id(-1)
+                    .isolation_level(Some(0))
+                    .topics(Some(
+                        [ListOffsetsTopic::default()
+                            .name(self.topic.clone())
+                            .partitions(Some(
+                                (0..partitions)
+                                    .map(|partition_index| {
+                                        ListOffsetsPartition::default()
+                                            .partition_index(partition_index)
+                                            .current_leader_epoch(Some(-1))
+                                            .timestamp(-1)
+                                            .max_num_offsets(None)
+                                    })
+                                    .collect(),
+                            ))]
+                        .into(),
+                    )),
+            )
+            .await?;
+
+        response
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partitions.unwrap_or_default())
+            .map(|partition| {
+                ErrorCode::try_from(partition.error_code)
+                    .map_err(Into::into)
+                    .and_then(|error_code| {
+                        if error_code == ErrorCode::None {
+                            Ok((
+                                partition.partition_index,
+                                partition.offset.unwrap_or_default(),
+                            ))
+                        } else {
+                            Err(Error::Api(error_code))
+                        }
+                    })
+            })
+            .collect()
+    }
+
+    fn dump(&self, diagnostics: &Diagnostics) -> Result<()> {
+        if let Some(ref path) = self.diagnostics {
+            File::create(path).map_err(Into::into).and_then(|file| {
+                serde_json::to_writer_pretty(file, diagnostics).map_err(Into::into)
+            })
+        } else {
+            let mut stdout = stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, diagnostics)?;
+            writeln!(stdout).map_err(Into::into)
+        }
+    }
+
+    pub async fn main(self) -> Result<ErrorCode> {
+        let token = CancellationToken::new();
+        let producing = CancellationToken::new();
+
+        let mut interrupt_signal = signal(SignalKind::interrupt())?;
+        let mut terminate_signal = signal(SignalKind::terminate())?;
+
+        let client = ConnectionManager::builder(self.broker.clone())
+            .client_id(Some(env!("CARGO_PKG_NAME").into()))
+            .build()
+            .await
+            .inspect(|pool| debug!(?pool))
+            .map(Client::new)?;
+
+        let (topic_id, partitions) = self.describe(&client).await?;
+        debug!(topic = self.topic, partitions);
+
+        let ledger = Arc::new(Mutex::new(Ledger::default()));
+
+        let mut set = JoinSet::new();
+
+        for id in 0..self.producers {
+            let producer = Producer {
+                id,
+                client: client.clone(),
+                topic: self.topic.clone(),
+                partitions,
+                batch_size: self.batch_size,
+                record_size: self.record_size,
+                ledger: ledger.clone(),
+                producing: producing.clone(),
+            };
+
+            _ = set.spawn(producer.run());
+        }
+
+        for id in 0..self.consumers {
+            let consumer = Consumer {
+                id,
+                client: client.clone(),
+                group_id: self.group_id.clone(),
+                topic: self.topic.clone(),
+                topic_id,
+                partitions,
+                session_timeout: self.session_timeout,
+                rebalance_interval: self.rebalance_interval,
+                ledger: ledger.clone(),
+                producing: producing.clone(),
+                token: token.clone(),
+            };
+
+            _ = set.spawn(consumer.run());
+        }
+
+        let producers_until = sleep(self.duration);
+        tokio::pin!(producers_until);
+
+        let mut converge_until = None;
+        let mut high_watermarks = BTreeMap::new();
+        let mut check = interval(Duration::from_secs(1));
+
+        let outcome = loop {
+            tokio::select! {
+                _ = &mut producers_until, if !producing.is_cancelled() => {
+                    debug!("producers stopping");
+                    producing.cancel();
+                    converge_until = Some(Instant::now() + self.convergence_timeout);
+                }
+
+                _ = check.tick() => {
+                    if ledger.lock()?.has_violations() {
+                        break Outcome::Violated;
+                    }
+
+                    let Some(converge_until) = converge_until else {
+                        continue;
+                    };
+
+                    high_watermarks = match self.high_watermarks(&client, partitions).await {
+                        Ok(high_watermarks) => high_watermarks,
+                        Err(err) => break Outcome::Failed(err),
+                    };
+
+                    if ledger.lock()?.converged(&high_watermarks) {
+                        break Outcome::Converged;
+                    }
+
+                    if Instant::now() >= converge_until {
+                        break Outcome::NotConverged;
+                    }
+                }
+
+                Some(joined) = set.join_next() => {
+                    match joined {
+                        Ok(Err(err)) => break Outcome::Failed(err),
+                        Err(err) => warn!(?err),
+                        Ok(Ok(())) => (),
+                    }
+                }
+
+                interrupt = interrupt_signal.recv() => {
+                    debug!(?interrupt);
+                    break Outcome::Interrupted;
+                }
+
+                terminate = terminate_signal.recv() => {
+                    debug!(?terminate);
+                    break Outcome::Interrupted;
+                }
+            }
+        };
+
+        debug!(?outcome);
+
+        producing.cancel();
+        token.cancel();
+
+        while let Some(joined) = set.join_next().await {
+            debug!(?joined);
+        }
+
+        let diagnostics = {
+            let mut ledger = ledger.lock()?;
+
+            match outcome {
+                Outcome::Converged => ledger.lost(),
+                Outcome::NotConverged => ledger.lagging(&high_watermarks),
+                Outcome::Failed(_) | Outcome::Interrupted | Outcome::Violated => (),
+            }
+
+            ledger.diagnostics(high_watermarks)
+        };
+
+        self.dump(&diagnostics)?;
+
+        if let Outcome::Failed(err) = outcome {
+            Err(err)
+        } else if diagnostics.violations() > 0 {
+            Err(Error::Violation(diagnostics.violations()))
+        } else {
+            Ok(ErrorCode::None)
+        }
+    }
+}
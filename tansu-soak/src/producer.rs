@@ -0,0 +1,131 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Producers sending records identified by their producer and sequence
+
+use std::sync::{Arc, Mutex};
+
+use tansu_client::Client;
+use tansu_sans_io::{
+    ErrorCode, ProduceRequest,
+    produce_request::{PartitionProduceData, TopicProduceData},
+    record::{Record, deflated, inflated},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    Result,
+    ledger::{Key, Ledger},
+};
+
+#[derive(Clone, Debug)]
+pub(crate) struct Producer {
+    pub(crate) id: u32,
+    pub(crate) client: Client,
+    pub(crate) topic: String,
+    pub(crate) partitions: i32,
+    pub(crate) batch_size: u32,
+    pub(crate) record_size: usize,
+    pub(crate) ledger: Arc<Mutex<Ledger>>,
+    pub(crate) producing: CancellationToken,
+}
+
+impl Producer {
+    fn frame(&self, keys: &[Key]) -> Result<deflated::Frame> {
+        keys.iter()
+            .zip(0..)
+            .fold(inflated::Batch::builder(), |batch, (key, offset_delta)| {
+                batch.record(
+                    Record::builder()
+                        .value(Some(key.encode(self.record_size)))
+                        .offset_delta(offset_delta),
+                )
+            })
+            .last_offset_delta(i32::try_from(keys.len()).unwrap_or(i32::MAX) - 1)
+            .build()
+            .map(|batch| inflated::Frame {
+                batches: vec![batch],
+            })
+            .and_then(deflated::Frame::try_from)
+            .map_err(Into::into)
+    }
+
+    /// Produce a batch, returning the base offset when acknowledged by the broker
+    #[instrument(skip(self, keys), fields(id = self.id))]
+    async fn produce(&self, partition: i32, keys: &[Key]) -> Result<Option<i64>> {
+        let req = ProduceRequest::default()
+            .acks(-1)
+            .timeout_ms(5_000)
+            .topic_data(Some(
+                [TopicProduceData::default()
+                    .name(self.topic.clone())
+                    .partition_data(Some(
+                        [PartitionProduceData::default()
+                            .index(partition)
+                            .records(Some(self.frame(keys)?))]
+                        .into(),
+                    ))]
+                .into(),
+            ));
+
+        let response = self.client.call(req).await?;
+
+        Ok(response
+            .responses
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+            .find(|response| response.index == partition)
+            .and_then(|response| {
+                ErrorCode::try_from(response.error_code)
+                    .inspect_err(|err| warn!(partition, ?err))
+                    .ok()
+                    .filter(|error_code| {
+                        debug!(partition, ?error_code, base_offset = response.base_offset);
+                        *error_code == ErrorCode::None
+                    })
+                    .map(|_| response.base_offset)
+            }))
+    }
+
+    /// Produce batches round robin over every partition until producing stops
+    pub(crate) async fn run(self) -> Result<()> {
+        let mut sequence = 0;
+
+        for partition in (0..self.partitions).cycle() {
+            let keys = (sequence..)
+                .take(self.batch_size as usize)
+                .map(|sequence| Key::new(self.id, sequence))
+                .collect::<Vec<_>>();
+
+            sequence += u64::from(self.batch_size);
+
+            tokio::select! {
+                cancelled = self.producing.cancelled() => {
+                    debug!(?cancelled);
+                    break;
+                }
+
+                base_offset = self.produce(partition, &keys[..]) => {
+                    if let Some(base_offset) = base_offset? {
+                        self.ledger.lock()?.acknowledge(partition, base_offset, &keys[..]);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consumer group state in the `__consumer_offsets` format
+//!
+//! Kafka writes consumer group state to the compacted `__consumer_offsets`
+//! topic: an `OffsetCommit` record for the committed offset of each
+//! partition, and a `GroupMetadata` record for each group. [`export`] encodes
+//! the groups in storage as a batch of those records, so that tooling parsing
+//! that format (forensics, migration, etc.) can be used with Tansu.
+//!
+//! Tansu does not record the client id or host of a member, which are
+//! exported as empty strings.
+
+use std::{collections::BTreeMap, time::SystemTime};
+
+use bytes::{BufMut as _, Bytes, BytesMut};
+use tansu_sans_io::{
+    record::{Record, deflated, inflated},
+    to_timestamp,
+};
+use tracing::{debug, instrument};
+
+use crate::{GroupDetail, GroupDetailResponse, Result, Storage, Topition};
+
+/// The key version of an `OffsetCommit` record
+const OFFSET_COMMIT_KEY_VERSION: i16 = 1;
+
+/// The value version of an `OffsetCommit` record
+const OFFSET_COMMIT_VALUE_VERSION: i16 = 3;
+
+/// The key version of a `GroupMetadata` record
+const GROUP_METADATA_KEY_VERSION: i16 = 2;
+
+/// The value version of a `GroupMetadata` record
+const GROUP_METADATA_VALUE_VERSION: i16 = 3;
+
+fn put_string(encoded: &mut BytesMut, s: &str) -> Result<()> {
+    encoded.put_i16(i16::try_from(s.len())?);
+    encoded.put(s.as_bytes());
+    Ok(())
+}
+
+fn put_nullable_string(encoded: &mut BytesMut, s: Option<&str>) -> Result<()> {
+    match s {
+        Some(s) => put_string(encoded, s),
+        None => {
+            encoded.put_i16(-1);
+            Ok(())
+        }
+    }
+}
+
+fn put_bytes(encoded: &mut BytesMut, b: &[u8]) -> Result<()> {
+    encoded.put_i32(i32::try_from(b.len())?);
+    encoded.put(b);
+    Ok(())
+}
+
+/// The key of an `OffsetCommit` record for a group and topition
+pub fn offset_commit_key(group_id: &str, topition: &Topition) -> Result<Bytes> {
+    let mut encoded = BytesMut::new();
+    encoded.put_i16(OFFSET_COMMIT_KEY_VERSION);
+    put_string(&mut encoded, group_id)?;
+    put_string(&mut encoded, topition.topic())?;
+    encoded.put_i32(topition.partition());
+    Ok(encoded.freeze())
+}
+
+/// The value of an `OffsetCommit` record, without a leader epoch or metadata
+pub fn offset_commit_value(offset: i64, commit_timestamp: i64) -> Result<Bytes> {
+    let mut encoded = BytesMut::new();
+    encoded.put_i16(OFFSET_COMMIT_VALUE_VERSION);
+    encoded.put_i64(offset);
+    encoded.put_i32(-1);
+    put_string(&mut encoded, "")?;
+    encoded.put_i64(commit_timestamp);
+    Ok(encoded.freeze())
+}
+
+/// The key of a `GroupMetadata` record for a group
+pub fn group_metadata_key(group_id: &str) -> Result<Bytes> {
+    let mut encoded = BytesMut::new();
+    encoded.put_i16(GROUP_METADATA_KEY_VERSION);
+    put_string(&mut encoded, group_id)?;
+    Ok(encoded.freeze())
+}
+
+/// The value of a `GroupMetadata` record, using the inception of the group as
+/// the timestamp of its current state
+pub fn group_metadata_value(detail: &GroupDetail) -> Result<Bytes> {
+    let assignments = detail.state.assignments();
+
+    let mut encoded = BytesMut::new();
+    encoded.put_i16(GROUP_METADATA_VALUE_VERSION);
+    put_string(
+        &mut encoded,
+        detail.state.protocol_type().as_deref().unwrap_or_default(),
+    )?;
+    encoded.put_i32(detail.generation_id);
+    put_nullable_string(&mut encoded, detail.state.protocol_name().as_deref())?;
+    put_nullable_string(&mut encoded, detail.state.leader().as_deref())?;
+    encoded.put_i64(to_timestamp(&detail.inception)?);
+
+    encoded.put_i32(i32::try_from(detail.members.len())?);
+
+    for (member_id, member) in &detail.members {
+        put_string(&mut encoded, member_id)?;
+        put_nullable_string(
+            &mut encoded,
+            member.join_response.group_instance_id.as_deref(),
+        )?;
+        put_string(&mut encoded, "")?;
+        put_string(&mut encoded, "")?;
+        encoded.put_i32(
+            detail
+                .rebalance_timeout_ms
+                .unwrap_or(detail.session_timeout_ms),
+        );
+        encoded.put_i32(detail.session_timeout_ms);
+        put_bytes(&mut encoded, &member.join_response.metadata)?;
+        put_bytes(
+            &mut encoded,
+            assignments
+                .get(member_id)
+                .map(|assignment| &assignment[..])
+                .unwrap_or_default(),
+        )?;
+    }
+
+    Ok(encoded.freeze())
+}
+
+/// The `OffsetCommit` records followed by the `GroupMetadata` record of a group
+fn group_records(
+    group_id: &str,
+    detail: &GroupDetail,
+    offsets: &BTreeMap<Topition, i64>,
+    commit_timestamp: i64,
+) -> Result<Vec<(Bytes, Bytes)>> {
+    let mut records = offsets
+        .iter()
+        .map(|(topition, offset)| {
+            offset_commit_key(group_id, topition).and_then(|key| {
+                offset_commit_value(*offset, commit_timestamp).map(|value| (key, value))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    records.push((group_metadata_key(group_id)?, group_metadata_value(detail)?));
+
+    Ok(records)
+}
+
+/// Export the state of some (or all) groups as a record batch in the
+/// `__consumer_offsets` format, empty when there are no groups
+#[instrument(skip(storage))]
+pub async fn export<S>(storage: &S, group_ids: Option<&[String]>) -> Result<Bytes>
+where
+    S: Storage,
+{
+    let group_ids = match group_ids {
+        Some(group_ids) => group_ids.to_vec(),
+        None => storage
+            .list_groups(None)
+            .await?
+            .into_iter()
+            .map(|group| group.group_id)
+            .collect(),
+    };
+
+    let commit_timestamp = to_timestamp(&SystemTime::now())?;
+
+    let mut records = vec![];
+
+    for group in storage.describe_groups(Some(&group_ids[..]), false).await? {
+        let GroupDetailResponse::Found(ref detail) = group.response else {
+            debug!(?group);
+            continue;
+        };
+
+        let offsets = storage.committed_offset_topitions(&group.name).await?;
+
+        records.append(&mut group_records(
+            &group.name,
+            detail,
+            &offsets,
+            commit_timestamp,
+        )?);
+    }
+
+    if records.is_empty() {
+        return Ok(Bytes::new());
+    }
+
+    let mut batch = inflated::Batch::builder()
+        .base_timestamp(commit_timestamp)
+        .max_timestamp(commit_timestamp);
+
+    for (offset_delta, (key, value)) in records.into_iter().enumerate() {
+        let offset_delta = i32::try_from(offset_delta)?;
+
+        batch = batch
+            .record(
+                Record::builder()
+                    .offset_delta(offset_delta)
+                    .key(key.into())
+                    .value(value.into()),
+            )
+            .last_offset_delta(offset_delta);
+    }
+
+    batch
+        .build()
+        .and_then(deflated::Batch::try_from)
+        .map(Bytes::from)
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use bytes::Buf as _;
+    use tansu_sans_io::join_group_response::JoinGroupResponseMember;
+
+    use super::*;
+    use crate::{GroupMember, GroupState};
+
+    fn get_string(encoded: &mut Bytes) -> Option<String> {
+        let length = encoded.get_i16();
+
+        usize::try_from(length)
+            .ok()
+            .map(|length| String::from_utf8(encoded.split_to(length).to_vec()).expect("utf8"))
+    }
+
+    fn get_bytes(encoded: &mut Bytes) -> Bytes {
+        let length = encoded.get_i32();
+        encoded.split_to(usize::try_from(length).expect("length"))
+    }
+
+    #[test]
+    fn offset_commit() -> Result<()> {
+        let mut key = offset_commit_key("abc", &Topition::new("pqr", 3))?;
+        assert_eq!(OFFSET_COMMIT_KEY_VERSION, key.get_i16());
+        assert_eq!(Some("abc".into()), get_string(&mut key));
+        assert_eq!(Some("pqr".into()), get_string(&mut key));
+        assert_eq!(3, key.get_i32());
+        assert!(key.is_empty());
+
+        let mut value = offset_commit_value(12_321, 1_700_000_000_000)?;
+        assert_eq!(OFFSET_COMMIT_VALUE_VERSION, value.get_i16());
+        assert_eq!(12_321, value.get_i64());
+        assert_eq!(-1, value.get_i32());
+        assert_eq!(Some("".into()), get_string(&mut value));
+        assert_eq!(1_700_000_000_000, value.get_i64());
+        assert!(value.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn group_metadata() -> Result<()> {
+        let detail = GroupDetail {
+            session_timeout_ms: 45_000,
+            rebalance_timeout_ms: Some(300_000),
+            members: BTreeMap::from([(
+                "m1".into(),
+                GroupMember {
+                    join_response: JoinGroupResponseMember::default()
+                        .member_id("m1".into())
+                        .group_instance_id(None)
+                        .metadata(Bytes::from_static(b"subscription")),
+                    last_contact: None,
+                },
+            )]),
+            generation_id: 5,
+            skip_assignment: Some(false),
+            inception: UNIX_EPOCH,
+            state: GroupState::Formed {
+                protocol_type: "consumer".into(),
+                protocol_name: "range".into(),
+                leader: "m1".into(),
+                assignments: BTreeMap::from([("m1".into(), Bytes::from_static(b"assignment"))]),
+            },
+            consumer: None,
+            share: None,
+        };
+
+        let mut key = group_metadata_key("abc")?;
+        assert_eq!(GROUP_METADATA_KEY_VERSION, key.get_i16());
+        assert_eq!(Some("abc".into()), get_string(&mut key));
+        assert!(key.is_empty());
+
+        let mut value = group_metadata_value(&detail)?;
+        assert_eq!(GROUP_METADATA_VALUE_VERSION, value.get_i16());
+        assert_eq!(Some("consumer".into()), get_string(&mut value));
+        assert_eq!(5, value.get_i32());
+        assert_eq!(Some("range".into()), get_string(&mut value));
+        assert_eq!(Some("m1".into()), get_string(&mut value));
+        assert_eq!(0, value.get_i64());
+        assert_eq!(1, value.get_i32());
+
+        assert_eq!(Some("m1".into()), get_string(&mut value));
+        assert_eq!(None, get_string(&mut value));
+        assert_eq!(Some("".into()), get_string(&mut value));
+        assert_eq!(Some("".into()), get_string(&mut value));
+        assert_eq!(300_000, value.get_i32());
+        assert_eq!(45_000, value.get_i32());
+        assert_eq!(Bytes::from_static(b"subscription"), get_bytes(&mut value));
+        assert_eq!(Bytes::from_static(b"assignment"), get_bytes(&mut value));
+        assert!(value.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+create table if not exists acl (
+    id integer primary key autoincrement,
+    cluster int references cluster (id) on delete cascade not null,
+    resource_type int not null,
+    resource_name text not null,
+    pattern_type int not null,
+    principal text not null,
+    host text not null,
+    operation int not null,
+    permission_type int not null,
+    created_at datetime default current_timestamp not null,
+    unique (cluster, resource_type, resource_name, pattern_type, principal, host, operation, permission_type)
+);
@@ -25,7 +25,7 @@ use std::{
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::{
-    StreamExt,
+    StreamExt, future,
     stream::{BoxStream, TryStreamExt},
 };
 use metadata::Cache;
@@ -33,6 +33,7 @@ use object_store::{
     Attribute, AttributeValue, Attributes, CopyOptions, DynObjectStore, GetOptions, GetResult,
     ListResult, MultipartUpload, ObjectMeta, ObjectStore, ObjectStoreExt, PutMode,
     PutMultipartOptions, PutOptions, PutPayload, PutResult, UpdateVersion, path::Path,
+    signer::Signer,
 };
 use opentelemetry::{
     KeyValue,
@@ -56,11 +57,12 @@ use tansu_sans_io::{
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
+    fetch_response::AbortedTransaction,
     incremental_alter_configs_request::{AlterConfigsResource, AlterableConfig},
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     list_groups_response::ListedGroup,
     metadata_response::{MetadataResponseBroker, MetadataResponsePartition, MetadataResponseTopic},
-    record::{Record, deflated, inflated},
+    record::{deflated, inflated},
     txn_offset_commit_response::{TxnOffsetCommitResponsePartition, TxnOffsetCommitResponseTopic},
 };
 use tansu_schema::{
@@ -71,14 +73,25 @@ use tracing::{debug, error, instrument, warn};
 use url::Url;
 use uuid::Uuid;
 
+#[cfg(feature = "dynamodb")]
+mod dynamodb;
 mod metadata;
 mod opticon;
+mod segment;
+mod tiered;
+
+#[cfg(feature = "dynamodb")]
+pub(crate) use dynamodb::DynamoDb;
+pub(crate) use segment::{DEFAULT_SEGMENT_BYTES, Segments};
+pub(crate) use tiered::{DEFAULT_LOCAL_RETENTION, Tiered};
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, ListOffsetResponse, METER, MetadataResponse,
-    NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse, Result, Storage,
-    TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest,
-    TxnState, UpdateError, Version,
+    AclBinding, BrokerRegistrationRequest, ClientQuota, ClientQuotaEntity, CoordinatorLease,
+    DirectRead, Error, FinalizedFeatures, GroupDetail, ListOffsetResponse, METER, MetadataResponse,
+    NamedGroupDetail, OffsetCommitRequest, OffsetStage, OffsetTranslation, PreflightCheck,
+    ProducerIdResponse, Result, ScramCredential, ScramMechanism, Storage, TopicId, Topition,
+    TopitionUsage, TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnDescription,
+    TxnOffsetCommitRequest, TxnState, UpdateError, Usage, Version, next_sequence,
 };
 
 const APPLICATION_JSON: &str = "application/json";
@@ -92,8 +105,14 @@ pub struct DynoStore {
     lake: Option<House>,
     watermarks: Arc<Mutex<BTreeMap<Topition, OptiCon<Watermark>>>>,
     meta: OptiCon<Meta>,
+    users: OptiCon<Users>,
+    acls: OptiCon<Acls>,
+    client_quotas: OptiCon<ClientQuotas>,
+    features: OptiCon<FinalizedFeatures>,
+    leases: OptiCon<Leases>,
 
     object_store: Arc<DynObjectStore>,
+    signer: Option<Arc<dyn Signer>>,
 }
 
 type Group = String;
@@ -109,6 +128,8 @@ struct Meta {
     producers: BTreeMap<ProducerId, ProducerDetail>,
     topics: BTreeMap<Topic, TopicMetadata>,
     transactions: BTreeMap<String, Txn>,
+    #[serde(default)]
+    aborted: BTreeMap<Topic, BTreeMap<Partition, Vec<TxnAborted>>>,
 }
 
 impl OptiCon<Meta> {
@@ -117,6 +138,64 @@ impl OptiCon<Meta> {
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct Users {
+    scram: BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>,
+}
+
+impl OptiCon<Users> {
+    fn new(cluster: &str) -> Self {
+        Self::path(format!("clusters/{cluster}/users.json"))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct Acls {
+    bindings: BTreeSet<AclBinding>,
+}
+
+impl OptiCon<Acls> {
+    fn new(cluster: &str) -> Self {
+        Self::path(format!("clusters/{cluster}/acls.json"))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+struct ClientQuotas {
+    quotas: Vec<ClientQuota>,
+}
+
+impl OptiCon<ClientQuotas> {
+    fn new(cluster: &str) -> Self {
+        Self::path(format!("clusters/{cluster}/client_quotas.json"))
+    }
+}
+
+impl OptiCon<FinalizedFeatures> {
+    fn new(cluster: &str) -> Self {
+        Self::path(format!("clusters/{cluster}/features.json"))
+    }
+}
+
+/// The coordinator leases of groups (key type 0) and transactional ids (key
+/// type 1), shared by every broker using this object store
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct Leases {
+    keys: BTreeMap<i8, BTreeMap<String, Lease>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct Lease {
+    lease: CoordinatorLease,
+    expires_at: SystemTime,
+}
+
+impl OptiCon<Leases> {
+    fn new(cluster: &str) -> Self {
+        Self::path(format!("clusters/{cluster}/leases.json"))
+    }
+}
+
 impl Meta {
     fn produced(
         &self,
@@ -305,6 +384,17 @@ struct TxnProduceOffset {
     offset_end: Offset,
 }
 
+/// The offsets produced to a topition by an aborted transaction, kept after
+/// the transaction has ended for read committed consumers to skip
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+struct TxnAborted {
+    producer: ProducerId,
+    offset_start: Offset,
+    offset_end: Offset,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 struct TxnCommitOffset {
     committed_offset: Offset,
@@ -323,6 +413,9 @@ struct Watermark {
     low: Option<i64>,
     high: Option<i64>,
     timestamps: Option<BTreeMap<i64, i64>>,
+
+    #[serde(default)]
+    usage: Usage,
 }
 
 impl OptiCon<Watermark> {
@@ -334,6 +427,20 @@ impl OptiCon<Watermark> {
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct OffsetTranslations {
+    checkpoints: BTreeMap<Offset, Offset>,
+}
+
+impl OptiCon<OffsetTranslations> {
+    fn new(cluster: &str, topition: &Topition) -> Self {
+        Self::path(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/offset-translation.json",
+            cluster, topition.topic, topition.partition,
+        ))
+    }
+}
+
 fn json_content_type() -> Attributes {
     let mut attributes = Attributes::new();
     _ = attributes.insert(
@@ -355,10 +462,16 @@ impl DynoStore {
 
             watermarks: Arc::new(Mutex::new(BTreeMap::new())),
             meta: OptiCon::<Meta>::new(cluster),
+            users: OptiCon::<Users>::new(cluster),
+            acls: OptiCon::<Acls>::new(cluster),
+            client_quotas: OptiCon::<ClientQuotas>::new(cluster),
+            features: OptiCon::<FinalizedFeatures>::new(cluster),
+            leases: OptiCon::<Leases>::new(cluster),
             object_store: Arc::new(Cache::new(
                 Metron::new(object_store, cluster),
                 Duration::from_millis(5_000),
             )),
+            signer: None,
         }
     }
 
@@ -377,6 +490,11 @@ impl DynoStore {
         Self { lake, ..self }
     }
 
+    /// Sign URLs for consumers reading batches directly from the object store
+    pub fn signer(self, signer: Option<Arc<dyn Signer>>) -> Self {
+        Self { signer, ..self }
+    }
+
     async fn topic_metadata(&self, topic: &TopicId) -> Result<Option<TopicMetadata>> {
         debug!(?topic);
 
@@ -401,7 +519,95 @@ impl DynoStore {
     }
 
     fn decode(&self, encoded: Bytes) -> Result<deflated::Batch> {
-        deflated::Batch::try_from(encoded).map_err(Into::into)
+        deflated::Batch::from_encoded(encoded).map_err(Into::into)
+    }
+
+    /// The encoded batches of a topition from an offset, with the offset of each
+    async fn fetch_stored(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation_level: IsolationLevel,
+    ) -> Result<Vec<(i64, Bytes)>> {
+        let high_watermark = self.offset_stage(topition).await.map(|offset_stage| {
+            if isolation_level == IsolationLevel::ReadCommitted {
+                offset_stage.last_stable
+            } else {
+                offset_stage.high_watermark
+            }
+        })?;
+
+        debug!(high_watermark);
+
+        let mut offsets = BTreeSet::new();
+
+        if offset < high_watermark {
+            let location = Path::from(format!(
+                "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+                self.cluster, topition.topic, topition.partition
+            ));
+
+            let mut list_stream = self.object_store.list(Some(&location));
+
+            while let Some(meta) = list_stream
+                .next()
+                .await
+                .inspect(|meta| debug!(?meta))
+                .transpose()
+                .inspect_err(|error| error!(?error, ?topition, ?offset, ?min_bytes, ?max_bytes))
+                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?
+            {
+                let Some(offset) = meta.location.parts().next_back() else {
+                    continue;
+                };
+
+                let offset = i64::from_str(&offset.as_ref()[0..20])?;
+                debug!(offset);
+
+                if offset < high_watermark {
+                    _ = offsets.insert(offset);
+                }
+            }
+        }
+
+        let mut batches = vec![];
+
+        let mut bytes = max_bytes as u64;
+
+        for offset in offsets.split_off(&offset) {
+            debug!(?offset);
+
+            let location = Path::from(format!(
+                "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+                self.cluster, topition.topic, topition.partition, offset,
+            ));
+
+            let get_result = self
+                .object_store
+                .get(&location)
+                .await
+                .inspect_err(|error| error!(?error, ?topition, ?offset, ?min_bytes, ?max_bytes))
+                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?;
+
+            let size = get_result.meta.size;
+
+            let encoded = get_result
+                .bytes()
+                .await
+                .inspect_err(|error| error!(?error, %location))
+                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?;
+            batches.push((offset, encoded));
+
+            if size > bytes {
+                break;
+            } else {
+                bytes = bytes.saturating_sub(size);
+            }
+        }
+
+        Ok(batches)
     }
 
     async fn get<V>(&self, location: &Path) -> Result<(V, Version)>
@@ -603,6 +809,54 @@ impl Storage for DynoStore {
         }
     }
 
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        let current = match self
+            .meta
+            .with_mut(&self.object_store, |meta| {
+                let Some(metadata) = meta.topics.get_mut(topic) else {
+                    return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+                };
+
+                if count <= metadata.topic.num_partitions {
+                    return Err(Error::Api(ErrorCode::InvalidPartitions));
+                }
+
+                let current = metadata.topic.num_partitions;
+                metadata.topic.num_partitions = count;
+                Ok(current)
+            })
+            .await
+        {
+            Ok(current) => current,
+            Err(Error::Api(error_code)) => return Ok(error_code),
+            Err(otherwise) => return Err(otherwise),
+        };
+
+        debug!(topic, current, count);
+
+        for partition in current..count {
+            let topition = Topition::new(topic, partition);
+
+            let watermark = self.watermarks.lock().map(|mut locked| {
+                locked
+                    .entry(topition.to_owned())
+                    .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), &topition))
+                    .to_owned()
+            })?;
+
+            watermark
+                .with_mut(&self.object_store, |watermark| {
+                    _ = watermark.high.take();
+                    _ = watermark.low.take();
+
+                    Ok(())
+                })
+                .await?;
+        }
+
+        Ok(ErrorCode::None)
+    }
+
     async fn delete_records(
         &self,
         _topics: &[DeleteRecordsTopic],
@@ -615,6 +869,7 @@ impl Storage for DynoStore {
             self.meta
                 .with_mut(&self.object_store, |meta| {
                     _ = meta.topics.remove(metadata.topic.name.as_str());
+                    _ = meta.aborted.remove(metadata.topic.name.as_str());
                     Ok(())
                 })
                 .await?;
@@ -739,6 +994,10 @@ impl Storage for DynoStore {
                     .to_owned()
             })?;
 
+            let usage = inflated::Batch::try_from(&deflated)
+                .map(|inflated| Usage::of(&inflated))
+                .inspect_err(|err| debug!(?err))?;
+
             let offset = watermark
                 .with_mut(&self.object_store, |watermark| {
                     debug!(?watermark);
@@ -750,6 +1009,7 @@ impl Storage for DynoStore {
                     );
 
                     watermark.timestamps = None;
+                    watermark.usage = watermark.usage.add(usage);
 
                     debug!(?watermark);
 
@@ -834,7 +1094,7 @@ impl Storage for DynoStore {
                         sequence => {
                             debug!(?sequence, delta = deflated.last_offset_delta + 1);
 
-                            *sequence += deflated.last_offset_delta + 1;
+                            *sequence = next_sequence(*sequence, deflated.last_offset_delta + 1);
                             Ok(())
                         }
                     }
@@ -866,6 +1126,10 @@ impl Storage for DynoStore {
                     .to_owned()
             })?;
 
+            let usage = inflated::Batch::try_from(&deflated)
+                .map(|inflated| Usage::of(&inflated))
+                .inspect_err(|err| debug!(?err))?;
+
             let offset = watermark
                 .with_mut(&self.object_store, |watermark| {
                     debug!(?watermark);
@@ -877,6 +1141,7 @@ impl Storage for DynoStore {
                     );
 
                     watermark.timestamps = None;
+                    watermark.usage = watermark.usage.add(usage);
 
                     debug!(?watermark);
 
@@ -956,7 +1221,13 @@ impl Storage for DynoStore {
                 self.cluster, topition.topic, topition.partition, offset,
             ));
 
-            let payload = self.encode(deflated).inspect_err(|err| debug!(?err))?;
+            // stored with its offset, so that it is fetched without being rebased
+            let payload = self
+                .encode(deflated::Batch {
+                    base_offset: offset,
+                    ..deflated
+                })
+                .inspect_err(|err| debug!(?err))?;
 
             _ = self
                 .object_store
@@ -977,6 +1248,42 @@ impl Storage for DynoStore {
         }
     }
 
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        let watermark = self.watermarks.lock().map(|mut locked| {
+            locked
+                .entry(topition.to_owned())
+                .or_insert_with(|| OptiCon::<Watermark>::new(self.cluster.as_str(), topition))
+                .to_owned()
+        })?;
+
+        let high = watermark
+            .with_mut(&self.object_store, |watermark| {
+                let high = watermark.high.unwrap_or_default();
+
+                if batch.base_offset > high {
+                    debug!(?topition, high, base_offset = batch.base_offset);
+
+                    watermark.low = watermark.low.or(Some(batch.base_offset));
+                    watermark.high = Some(batch.base_offset);
+                    watermark.timestamps = None;
+
+                    Ok(batch.base_offset)
+                } else {
+                    Ok(high)
+                }
+            })
+            .await
+            .inspect_err(|err| error!(?err, ?topition))?;
+
+        if batch.base_offset < high {
+            debug!(?topition, base_offset = batch.base_offset, high);
+            return Ok(batch.base_offset);
+        }
+
+        self.produce(None, topition, batch.without_producer()?)
+            .await
+    }
+
     async fn fetch(
         &self,
         topition: &'_ Topition,
@@ -985,120 +1292,68 @@ impl Storage for DynoStore {
         max_bytes: u32,
         isolation_level: IsolationLevel,
     ) -> Result<Vec<deflated::Batch>> {
-        let high_watermark = self.offset_stage(topition).await.map(|offset_stage| {
-            if isolation_level == IsolationLevel::ReadCommitted {
-                offset_stage.last_stable
-            } else {
-                offset_stage.high_watermark
-            }
-        })?;
+        self.fetch_stored(topition, offset, min_bytes, max_bytes, isolation_level)
+            .await?
+            .into_iter()
+            .map(|(offset, encoded)| {
+                self.decode(encoded).map(|mut batch| {
+                    batch.base_offset = offset;
+                    batch
+                })
+            })
+            .collect()
+    }
 
-        debug!(high_watermark);
+    async fn fetch_encoded(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation_level: IsolationLevel,
+    ) -> Result<Vec<Bytes>> {
+        self.fetch_stored(topition, offset, min_bytes, max_bytes, isolation_level)
+            .await?
+            .into_iter()
+            .map(|(offset, encoded)| {
+                deflated::Batch::rebase_encoded(encoded, offset).map_err(Into::into)
+            })
+            .collect()
+    }
 
-        let mut offsets = BTreeSet::new();
+    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage> {
+        let stable = self
+            .meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .transactions
+                    .values()
+                    .flat_map(|txn| {
+                        debug!(?txn);
 
-        if offset < high_watermark {
-            let location = Path::from(format!(
-                "clusters/{}/topics/{}/partitions/{:0>10}/records/",
-                self.cluster, topition.topic, topition.partition
-            ));
+                        txn.epochs
+                            .values()
+                            .filter(|detail| {
+                                detail.state.is_some_and(|state| {
+                                    state != TxnState::Committed && state != TxnState::Aborted
+                                })
+                            })
+                            .map(BTreeMap::<Topition, Offset>::from)
+                            .collect::<Vec<_>>()
+                    })
+                    .reduce(|mut acc, e| {
+                        debug!(?acc, ?e);
 
-            let mut list_stream = self.object_store.list(Some(&location));
-
-            while let Some(meta) = list_stream
-                .next()
-                .await
-                .inspect(|meta| debug!(?meta))
-                .transpose()
-                .inspect_err(|error| error!(?error, ?topition, ?offset, ?min_bytes, ?max_bytes))
-                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?
-            {
-                let Some(offset) = meta.location.parts().next_back() else {
-                    continue;
-                };
-
-                let offset = i64::from_str(&offset.as_ref()[0..20])?;
-                debug!(offset);
-
-                if offset < high_watermark {
-                    _ = offsets.insert(offset);
-                }
-            }
-        }
-
-        let mut batches = vec![];
-
-        let mut bytes = max_bytes as u64;
-
-        for offset in offsets.split_off(&offset) {
-            debug!(?offset);
-
-            let location = Path::from(format!(
-                "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
-                self.cluster, topition.topic, topition.partition, offset,
-            ));
-
-            let get_result = self
-                .object_store
-                .get(&location)
-                .await
-                .inspect_err(|error| error!(?error, ?topition, ?offset, ?min_bytes, ?max_bytes))
-                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?;
-
-            let size = get_result.meta.size;
-
-            let mut batch = get_result
-                .bytes()
-                .await
-                .inspect_err(|error| error!(?error, %location))
-                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))
-                .and_then(|encoded| self.decode(encoded))?;
-            batch.base_offset = offset;
-            batches.push(batch);
-
-            if size > bytes {
-                break;
-            } else {
-                bytes = bytes.saturating_sub(size);
-            }
-        }
-
-        Ok(batches)
-    }
-
-    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage> {
-        let stable = self
-            .meta
-            .with(&self.object_store, |meta| {
-                Ok(meta
-                    .transactions
-                    .values()
-                    .flat_map(|txn| {
-                        debug!(?txn);
-
-                        txn.epochs
-                            .values()
-                            .filter(|detail| {
-                                detail.state.is_some_and(|state| {
-                                    state != TxnState::Committed && state != TxnState::Aborted
-                                })
-                            })
-                            .map(BTreeMap::<Topition, Offset>::from)
-                            .collect::<Vec<_>>()
-                    })
-                    .reduce(|mut acc, e| {
-                        debug!(?acc, ?e);
-
-                        for (topition, offset_start) in e.iter() {
-                            _ = acc
-                                .entry(topition.to_owned())
-                                .and_modify(|existing_offset_start| {
-                                    if *existing_offset_start > *offset_start {
-                                        *existing_offset_start = *offset_start
-                                    }
-                                })
-                                .or_insert(*offset_start);
-                        }
+                        for (topition, offset_start) in e.iter() {
+                            _ = acc
+                                .entry(topition.to_owned())
+                                .and_modify(|existing_offset_start| {
+                                    if *existing_offset_start > *offset_start {
+                                        *existing_offset_start = *offset_start
+                                    }
+                                })
+                                .or_insert(*offset_start);
+                        }
 
                         acc
                     })
@@ -1644,8 +1899,10 @@ impl Storage for DynoStore {
                                         .name(config.name.clone())
                                         .value(config.value.clone())
                                         .read_only(false)
-                                        .is_default(None)
-                                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                                        .is_default(Some(false))
+                                        .config_source(Some(
+                                            ConfigSource::DynamicTopicConfig.into(),
+                                        ))
                                         .is_sensitive(false)
                                         .synonyms(Some([].into()))
                                         .config_type(Some(ConfigType::String.into()))
@@ -1662,7 +1919,7 @@ impl Storage for DynoStore {
                     .resource_name(name.into())
                     .configs(Some(vec![]))),
 
-                Err(_) => todo!(),
+                Err(error) => Err(error),
             },
 
             _ => Ok(DescribeConfigsResult::default()
@@ -2412,29 +2669,15 @@ impl Storage for DynoStore {
         for topition in produced {
             debug!(?topition);
 
-            let control_batch: Bytes = if committed {
-                ControlBatch::default().commit().try_into()?
+            let control = if committed {
+                ControlBatch::default().commit()
             } else {
-                ControlBatch::default().abort().try_into()?
+                ControlBatch::default().abort()
             };
 
-            let end_transaction_marker: Bytes = EndTransactionMarker::default().try_into()?;
-
-            let batch = inflated::Batch::builder()
-                .record(
-                    Record::builder()
-                        .key(control_batch.into())
-                        .value(end_transaction_marker.into()),
-                )
-                .attributes(
-                    BatchAttribute::default()
-                        .control(true)
-                        .transaction(true)
-                        .into(),
-                )
+            let batch = inflated::Batch::control(control, EndTransactionMarker::default())?
                 .producer_id(producer_id)
                 .producer_epoch(producer_epoch)
-                .base_sequence(-1)
                 .build()
                 .and_then(TryInto::try_into)
                 .inspect(|deflated| debug!(?deflated))?;
@@ -2525,6 +2768,25 @@ impl Storage for DynoStore {
 
                                 Some(TxnState::PrepareAbort) => {
                                     _ = txn_detail.state.replace(TxnState::Aborted);
+
+                                    for (topic, partitions) in &txn_detail.produces {
+                                        for (partition, offset_range) in partitions {
+                                            let Some(offset_range) = offset_range else {
+                                                continue;
+                                            };
+
+                                            meta.aborted
+                                                .entry(topic.to_owned())
+                                                .or_default()
+                                                .entry(*partition)
+                                                .or_default()
+                                                .push(TxnAborted {
+                                                    producer: txn_id.producer_id,
+                                                    offset_start: offset_range.offset_start,
+                                                    offset_end: offset_range.offset_end,
+                                                });
+                                        }
+                                    }
                                 }
 
                                 otherwise => {
@@ -2592,6 +2854,454 @@ impl Storage for DynoStore {
         Ok(ErrorCode::None)
     }
 
+    #[instrument(skip(self, credential))]
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        self.users
+            .with_mut(&self.object_store, |users| {
+                _ = users
+                    .scram
+                    .entry(username.to_owned())
+                    .or_default()
+                    .insert(mechanism, credential.clone());
+
+                Ok(())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        self.users
+            .with_mut(&self.object_store, |users| {
+                let Some(credentials) = users.scram.get_mut(username) else {
+                    return Ok(ErrorCode::ResourceNotFound);
+                };
+
+                if credentials.remove(&mechanism).is_none() {
+                    return Ok(ErrorCode::ResourceNotFound);
+                }
+
+                if credentials.is_empty() {
+                    _ = users.scram.remove(username);
+                }
+
+                Ok(ErrorCode::None)
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        self.users
+            .with(&self.object_store, |users| {
+                Ok(users
+                    .scram
+                    .iter()
+                    .filter(|(username, _)| {
+                        usernames.is_none_or(|usernames| usernames.contains(username))
+                    })
+                    .map(|(username, credentials)| (username.to_owned(), credentials.to_owned()))
+                    .collect())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        OptiCon::<OffsetTranslations>::new(&self.cluster, topition)
+            .with_mut(&self.object_store, |translations| {
+                _ = translations
+                    .checkpoints
+                    .insert(translation.upstream, translation.local);
+
+                Ok(())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        OptiCon::<OffsetTranslations>::new(&self.cluster, topition)
+            .with(&self.object_store, |translations| {
+                Ok(translations
+                    .checkpoints
+                    .range(..=upstream)
+                    .next_back()
+                    .map(|(upstream, local)| OffsetTranslation::new(*upstream, *local)))
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        let topitions = self
+            .meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .topics
+                    .iter()
+                    .filter(|(name, _)| topics.is_none_or(|topics| topics.contains(name)))
+                    .flat_map(|(name, metadata)| {
+                        (0..metadata.topic.num_partitions)
+                            .map(|partition| Topition::new(name.to_owned(), partition))
+                    })
+                    .collect::<Vec<_>>())
+            })
+            .await?;
+
+        let mut usage = vec![];
+
+        for topition in topitions {
+            let watermark = self.watermarks.lock().map(|mut locked| {
+                locked
+                    .entry(topition.to_owned())
+                    .or_insert_with(|| OptiCon::<Watermark>::new(self.cluster.as_str(), &topition))
+                    .to_owned()
+            })?;
+
+            usage.push(TopitionUsage {
+                usage: watermark
+                    .with(&self.object_store, |watermark| Ok(watermark.usage))
+                    .await?,
+                topition,
+            });
+        }
+
+        Ok(usage)
+    }
+
+    #[instrument(skip(self))]
+    async fn direct_reads(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        limit: usize,
+        expires_in: Duration,
+    ) -> Result<Vec<DirectRead>> {
+        let Some(signer) = self.signer.as_ref() else {
+            return Ok(vec![]);
+        };
+
+        let last_stable = self.offset_stage(topition).await?.last_stable;
+
+        let location = Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+            self.cluster, topition.topic, topition.partition
+        ));
+
+        let mut sealed = BTreeMap::new();
+        let mut list_stream = self.object_store.list(Some(&location));
+
+        while let Some(meta) = list_stream.next().await.transpose()? {
+            let Some(base_offset) = meta.location.parts().next_back() else {
+                continue;
+            };
+
+            let base_offset = i64::from_str(&base_offset.as_ref()[0..20])?;
+
+            if base_offset < last_stable {
+                _ = sealed.insert(base_offset, (meta.location, meta.size));
+            }
+        }
+
+        let start = sealed
+            .range(..=offset)
+            .next_back()
+            .map_or(offset, |(base_offset, _)| *base_offset);
+
+        let mut direct_reads = vec![];
+
+        for (base_offset, (location, bytes)) in sealed.range(start..).take(limit) {
+            let url = signer
+                .signed_url(http::Method::GET, location, expires_in)
+                .await?;
+
+            debug!(?topition, base_offset, bytes, %url);
+
+            direct_reads.push(DirectRead {
+                base_offset: *base_offset,
+                bytes: *bytes,
+                url,
+            });
+        }
+
+        Ok(direct_reads)
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        let prefix = Path::from(format!(
+            "clusters/{}/groups/consumers/{}/offsets/",
+            self.cluster, group_id,
+        ));
+
+        let expired = self
+            .object_store
+            .list(Some(&prefix))
+            .try_filter(|meta| {
+                future::ready(
+                    SystemTime::from(meta.last_modified)
+                        .elapsed()
+                        .is_ok_and(|elapsed| elapsed > retention),
+                )
+            })
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut topitions = vec![];
+
+        for meta in expired {
+            let Some(topic): Option<String> = meta
+                .location
+                .parts()
+                .nth(6)
+                .map(|topic| topic.as_ref().into())
+            else {
+                continue;
+            };
+
+            let Some(partition) = meta
+                .location
+                .parts()
+                .nth(8)
+                .map(|partition| i32::from_str(&partition.as_ref()[0..10]))
+                .transpose()?
+            else {
+                continue;
+            };
+
+            self.object_store.delete(&meta.location).await?;
+
+            topitions.push(Topition::new(topic, partition));
+        }
+
+        Ok(topitions).inspect(|expired| debug!(cluster = self.cluster, group_id, ?expired))
+    }
+
+    #[instrument(skip(self))]
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        self.meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .aborted
+                    .get(topition.topic())
+                    .and_then(|partitions| partitions.get(&topition.partition()))
+                    .map(|aborted| {
+                        aborted
+                            .iter()
+                            .filter(|aborted| {
+                                aborted.offset_end >= first_offset
+                                    && aborted.offset_start <= last_offset
+                            })
+                            .map(|aborted| {
+                                AbortedTransaction::default()
+                                    .producer_id(aborted.producer)
+                                    .first_offset(aborted.offset_start)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn describe_txns(
+        &self,
+        transactional_ids: Option<&[String]>,
+    ) -> Result<Vec<TxnDescription>> {
+        self.meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .transactions
+                    .iter()
+                    .filter(|(transactional_id, _)| {
+                        transactional_ids.is_none_or(|ids| ids.contains(transactional_id))
+                    })
+                    .filter_map(|(transactional_id, txn)| {
+                        // the latest epoch of each transactional id
+                        txn.epochs
+                            .last_key_value()
+                            .map(|(epoch, detail)| TxnDescription {
+                                transactional_id: transactional_id.clone(),
+                                producer_id: txn.producer,
+                                producer_epoch: *epoch,
+                                state: detail
+                                    .state
+                                    .or(detail.started_at.and(Some(TxnState::Begin))),
+                                timeout_ms: detail.transaction_timeout_ms,
+                                started_at: detail.started_at,
+                                topitions: detail
+                                    .produces
+                                    .iter()
+                                    .flat_map(|(topic, partitions)| {
+                                        partitions.keys().map(|partition| {
+                                            Topition::new(topic.clone(), *partition)
+                                        })
+                                    })
+                                    .collect(),
+                            })
+                    })
+                    .collect())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        self.acls
+            .with_mut(&self.object_store, |acls| {
+                _ = acls.bindings.insert(binding.to_owned());
+                Ok(())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        self.acls
+            .with_mut(&self.object_store, |acls| {
+                Ok(if acls.bindings.remove(binding) {
+                    ErrorCode::None
+                } else {
+                    ErrorCode::ResourceNotFound
+                })
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        self.acls
+            .with(&self.object_store, |acls| {
+                Ok(acls.bindings.iter().cloned().collect())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        self.client_quotas
+            .with_mut(&self.object_store, |client_quotas| {
+                ClientQuota::alter(&mut client_quotas.quotas, entity, key, value);
+                Ok(())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        self.client_quotas
+            .with(&self.object_store, |client_quotas| {
+                Ok(client_quotas.quotas.clone())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        self.features
+            .with_mut(&self.object_store, |features| {
+                features.update(name, level);
+                Ok(())
+            })
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        self.features
+            .with(&self.object_store, |features| Ok(features.clone()))
+            .await
+    }
+
+    async fn coordinator_lease(&self, key_type: i8, key: &str) -> Result<CoordinatorLease> {
+        let host = self.advertised_listener.host_str().unwrap_or("0.0.0.0");
+        let port = i32::from(self.advertised_listener.port().unwrap_or(9092));
+
+        self.leases
+            .with_mut(&self.object_store, |leases| {
+                let now = SystemTime::now();
+
+                let held = CoordinatorLease {
+                    node_id: self.node,
+                    host: host.into(),
+                    port,
+                    epoch: 0,
+                };
+
+                match leases
+                    .keys
+                    .entry(key_type)
+                    .or_default()
+                    .entry(key.to_owned())
+                {
+                    Entry::Vacant(vacant) => Ok(vacant
+                        .insert(Lease {
+                            lease: held,
+                            expires_at: now + CoordinatorLease::EXPIRES_IN,
+                        })
+                        .lease
+                        .clone()),
+
+                    Entry::Occupied(mut occupied) => {
+                        let lease = occupied.get_mut();
+
+                        // renewed by its holder, or moved once it has expired,
+                        // fencing the previous holder with a new epoch
+                        if lease.lease.node_id == self.node || lease.expires_at < now {
+                            let epoch = if lease.lease.node_id == self.node {
+                                lease.lease.epoch
+                            } else {
+                                lease.lease.epoch + 1
+                            };
+
+                            lease.lease = CoordinatorLease { epoch, ..held };
+                            lease.expires_at = now + CoordinatorLease::EXPIRES_IN;
+                        }
+
+                        Ok(lease.lease.clone())
+                    }
+                }
+            })
+            .await
+            .inspect(|lease| debug!(cluster = self.cluster, key_type, key, ?lease))
+    }
+
     async fn maintain(&self, _now: SystemTime) -> Result<()> {
         if let Some(ref lake) = self.lake {
             return lake
@@ -2605,6 +3315,157 @@ impl Storage for DynoStore {
         Ok(())
     }
 
+    #[instrument(skip(self))]
+    async fn compact(&self, topition: &Topition, end_offset: i64) -> Result<u64> {
+        let end_offset = end_offset.min(self.offset_stage(topition).await?.last_stable);
+
+        let prefix = Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+            self.cluster, topition.topic, topition.partition
+        ));
+
+        let mut offsets = BTreeSet::new();
+        let mut list_stream = self.object_store.list(Some(&prefix));
+
+        while let Some(meta) = list_stream.next().await.transpose()? {
+            let Some(base_offset) = meta.location.parts().next_back() else {
+                continue;
+            };
+
+            let base_offset = i64::from_str(&base_offset.as_ref()[0..20])?;
+
+            if base_offset < end_offset {
+                _ = offsets.insert(base_offset);
+            }
+        }
+
+        // the keys of newer batches, superseding those in older batches
+        let mut head = BTreeSet::new();
+        let mut removed = 0;
+
+        for offset in offsets.into_iter().rev() {
+            let location = Path::from(format!(
+                "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+                self.cluster, topition.topic, topition.partition, offset,
+            ));
+
+            let mut batch = self
+                .object_store
+                .get(&location)
+                .await?
+                .bytes()
+                .await
+                .map_err(Into::into)
+                .and_then(|encoded| self.decode(encoded))
+                .and_then(|deflated| inflated::Batch::try_from(deflated).map_err(Into::into))?;
+
+            // transaction markers are never compacted
+            if batch.is_control() {
+                continue;
+            }
+
+            batch.base_offset = offset;
+
+            let keys = batch.keys();
+            let compaction = batch.compact(&head)?;
+            head.extend(keys);
+
+            if compaction.records == 0 {
+                continue;
+            }
+
+            debug!(offset, records = compaction.records);
+
+            let payload = deflated::Batch::try_from(compaction.batch)
+                .map_err(Into::into)
+                .and_then(|deflated| self.encode(deflated))?;
+
+            _ = self
+                .object_store
+                .put_opts(
+                    &location,
+                    payload,
+                    PutOptions {
+                        mode: PutMode::Overwrite,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+
+            removed += compaction.records as u64;
+        }
+
+        Ok(removed)
+    }
+
+    #[instrument(skip_all)]
+    async fn preflight(&self) -> Result<Vec<PreflightCheck>> {
+        const NAME: &str = "object store conditional writes";
+        const REMEDY: &str = "use an object store supporting conditional writes \
+            (for an S3 compatible store, set AWS_CONDITIONAL_PUT=etag)";
+
+        // every update to metadata relies on creating an object only when it
+        // is absent, and replacing it only when it is unchanged
+        let location = Path::from(format!(
+            "clusters/{}/preflight/{}.json",
+            self.cluster,
+            Uuid::now_v7()
+        ));
+
+        let create = PutOptions {
+            mode: PutMode::Create,
+            ..Default::default()
+        };
+
+        let created = self
+            .object_store
+            .put_opts(&location, PutPayload::from_static(b"{}"), create.clone())
+            .await?;
+
+        let check = match self
+            .object_store
+            .put_opts(&location, PutPayload::from_static(b"{}"), create)
+            .await
+        {
+            Err(object_store::Error::AlreadyExists { .. })
+            | Err(object_store::Error::Precondition { .. }) => {
+                match self
+                    .object_store
+                    .put_opts(
+                        &location,
+                        PutPayload::from_static(b"{}"),
+                        PutOptions {
+                            mode: PutMode::Update(UpdateVersion::from(created)),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                {
+                    Ok(_) => PreflightCheck::passed(NAME, "create and update are conditional"),
+                    Err(error) => {
+                        PreflightCheck::failed(NAME, format!("conditional update: {error}"), REMEDY)
+                    }
+                }
+            }
+
+            Ok(_) => PreflightCheck::failed(NAME, "create replaced an existing object", REMEDY),
+
+            Err(error) => {
+                PreflightCheck::failed(NAME, format!("conditional create: {error}"), REMEDY)
+            }
+        };
+
+        _ = self
+            .object_store
+            .delete(&location)
+            .await
+            .inspect_err(|error| warn!(%location, ?error));
+
+        debug!(?check);
+
+        Ok(vec![check])
+    }
+
     async fn cluster_id(&self) -> Result<String> {
         Ok(self.cluster.clone())
     }
@@ -2903,6 +3764,100 @@ where
 mod tests {
 
     use super::*;
+    use tansu_sans_io::record::Record;
+
+    #[tokio::test]
+    async fn coordinator_lease() -> Result<()> {
+        let object_store = Arc::new(object_store::memory::InMemory::new());
+
+        let first = DynoStore::new("tansu", 111, object_store.clone())
+            .advertised_listener(Url::parse("tcp://first:9092")?);
+
+        let second = DynoStore::new("tansu", 222, object_store)
+            .advertised_listener(Url::parse("tcp://second:9092")?);
+
+        let lease = first.coordinator_lease(0, "abc").await?;
+        assert_eq!(111, lease.node_id);
+        assert_eq!("first", lease.host);
+        assert_eq!(0, lease.epoch);
+
+        // held by the first broker until it expires
+        assert_eq!(lease, second.coordinator_lease(0, "abc").await?);
+
+        // a transactional id with the same name is leased separately
+        let txn = second.coordinator_lease(1, "abc").await?;
+        assert_eq!(222, txn.node_id);
+        assert_eq!(0, txn.epoch);
+
+        // expire the lease of the first broker, which moves to the second
+        second
+            .leases
+            .with_mut(&second.object_store, |leases| {
+                for lease in leases.keys.values_mut().flat_map(BTreeMap::values_mut) {
+                    lease.expires_at = SystemTime::UNIX_EPOCH;
+                }
+
+                Ok(())
+            })
+            .await?;
+
+        let moved = second.coordinator_lease(0, "abc").await?;
+        assert_eq!(222, moved.node_id);
+        assert_eq!(1, moved.epoch);
+
+        assert_eq!(moved, first.coordinator_lease(0, "abc").await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_expired_offsets() -> Result<()> {
+        let storage = DynoStore::new("tansu", 111, object_store::memory::InMemory::new());
+
+        let topic = "abc";
+
+        _ = storage
+            .create_topic(
+                CreatableTopic::default()
+                    .name(topic.into())
+                    .num_partitions(1)
+                    .replication_factor(1)
+                    .assignments(Some([].into()))
+                    .configs(Some([].into())),
+                false,
+            )
+            .await?;
+
+        let topition = Topition::new(topic, 0);
+
+        _ = storage
+            .offset_commit(
+                "pqr",
+                None,
+                &[(topition.clone(), OffsetCommitRequest::default().offset(6))],
+            )
+            .await?;
+
+        assert!(
+            storage
+                .delete_expired_offsets("pqr", Duration::from_secs(3_600))
+                .await?
+                .is_empty()
+        );
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(
+            vec![topition],
+            storage
+                .delete_expired_offsets("pqr", Duration::from_millis(1))
+                .await?
+        );
+
+        assert!(storage.committed_offset_topitions("pqr").await?.is_empty());
+
+        Ok(())
+    }
 
     #[test]
     fn range_check() {
@@ -2947,4 +3902,84 @@ mod tests {
 
         Ok(())
     }
+
+    #[derive(Debug)]
+    struct UnsignedUrl;
+
+    #[async_trait]
+    impl Signer for UnsignedUrl {
+        async fn signed_url(
+            &self,
+            _method: http::Method,
+            path: &Path,
+            _expires_in: Duration,
+        ) -> object_store::Result<Url> {
+            Ok(Url::parse("s3://tansu/")
+                .and_then(|url| url.join(path.as_ref()))
+                .expect("url"))
+        }
+    }
+
+    #[tokio::test]
+    async fn direct_reads() -> Result<()> {
+        let storage = DynoStore::new("tansu", 111, object_store::memory::InMemory::new())
+            .signer(Some(Arc::new(UnsignedUrl)));
+
+        let topic = "abc";
+
+        _ = storage
+            .create_topic(
+                CreatableTopic::default()
+                    .name(topic.into())
+                    .num_partitions(1)
+                    .replication_factor(1)
+                    .assignments(Some([].into()))
+                    .configs(Some([].into())),
+                false,
+            )
+            .await?;
+
+        let topition = Topition::new(topic, 0);
+
+        for records in [2, 3, 1] {
+            let mut batch = inflated::Batch::builder();
+
+            for _ in 0..records {
+                batch = batch.record(Record::builder().value(Bytes::from_static(b"pqr").into()));
+            }
+
+            _ = storage
+                .produce(None, &topition, batch.build().and_then(TryInto::try_into)?)
+                .await?;
+        }
+
+        let direct_reads = storage
+            .direct_reads(&topition, 3, 10, Duration::from_secs(60))
+            .await?;
+
+        assert_eq!(
+            vec![2, 5],
+            direct_reads
+                .iter()
+                .map(|direct_read| direct_read.base_offset)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(
+            direct_reads[0]
+                .url
+                .path()
+                .ends_with("/records/00000000000000000002.batch")
+        );
+
+        assert_eq!(
+            1,
+            storage
+                .direct_reads(&topition, 0, 1, Duration::from_secs(60))
+                .await?
+                .len()
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,955 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! DynamoDB object store
+//!
+//! Each object is an item in a DynamoDB table keyed by its path, with a
+//! version (also its e-tag) that is replaced on every write. The conditional
+//! writes used by the [`DynoStore`](super::DynoStore) to create and compare
+//! and swap watermarks, offsets and metadata are condition expressions on
+//! that version, with every read being strongly consistent.
+//!
+//! The table has a string partition key named `kind` and a string sort key
+//! named `path`:
+//!
+//! ```shell
+//! aws dynamodb create-table \
+//!     --table-name tansu \
+//!     --attribute-definitions \
+//!         AttributeName=kind,AttributeType=S \
+//!         AttributeName=path,AttributeType=S \
+//!     --key-schema \
+//!         AttributeName=kind,KeyType=HASH \
+//!         AttributeName=path,KeyType=RANGE \
+//!     --billing-mode PAY_PER_REQUEST
+//! ```
+//!
+//! The kind of an object is the first four segments of its directory, e.g.,
+//! `clusters/tansu/topics/abc` for the batches of a topic, or
+//! `clusters/tansu/groups/consumers` for every consumer group. Listing the
+//! topics and groups of a cluster is then a query of a single kind, with
+//! only a listing of fewer than four segments scanning the table.
+//!
+//! An item is limited to 400KB by DynamoDB. A larger object is split into
+//! chunks, each an item keyed by the path and version of the object, that
+//! are written in the same transaction as the object. A transaction is
+//! limited to 4MB, which limits the size of a produced record batch.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{self, Debug, Display},
+    ops::Range,
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    Client,
+    error::{ProvideErrorMetadata, SdkError},
+    operation::transact_write_items::TransactWriteItemsError,
+    primitives::Blob,
+    types::{AttributeValue, Put, ReturnValue, TransactWriteItem},
+};
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures::{
+    StreamExt, TryStreamExt,
+    stream::{self, BoxStream},
+};
+use object_store::{
+    CopyMode, CopyOptions, GetOptions, GetRange, GetResult, GetResultPayload, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOptions, PutOptions, PutPayload,
+    PutResult, path::Path,
+};
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+
+const STORE: &str = "DynamoDB";
+
+const KIND: &str = "kind";
+const PATH: &str = "path";
+const PAYLOAD: &str = "payload";
+const CHUNKS: &str = "chunks";
+const VERSION: &str = "version";
+const LAST_MODIFIED: &str = "last_modified";
+const SIZE: &str = "size";
+
+/// The segments of a directory forming the kind (partition key) of an object
+const KIND_SEGMENTS: usize = 4;
+
+/// The largest payload held by a single item, leaving room for its key and
+/// other attributes within the 400KB item limit
+const CHUNK_BYTES: usize = 350 * 1024;
+
+/// The largest object written in a single transaction, within its 4MB limit
+const MAX_OBJECT_BYTES: usize = 10 * CHUNK_BYTES;
+
+type Item = HashMap<String, AttributeValue>;
+
+fn generic<E>(error: E) -> object_store::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    object_store::Error::Generic {
+        store: STORE,
+        source: Box::new(error),
+    }
+}
+
+fn malformed(location: &str, attribute: &str) -> object_store::Error {
+    object_store::Error::Generic {
+        store: STORE,
+        source: format!("{location} has a missing or malformed {attribute}").into(),
+    }
+}
+
+fn not_found(location: &Path) -> object_store::Error {
+    object_store::Error::NotFound {
+        path: location.to_string(),
+        source: format!("{location} not found").into(),
+    }
+}
+
+fn is_conditional_check_failed<E, R>(error: &SdkError<E, R>) -> bool
+where
+    E: ProvideErrorMetadata,
+{
+    error
+        .as_service_error()
+        .and_then(ProvideErrorMetadata::code)
+        .is_some_and(|code| code == "ConditionalCheckFailedException")
+}
+
+fn is_transaction_condition_failed<R>(error: &SdkError<TransactWriteItemsError, R>) -> bool {
+    error.as_service_error().is_some_and(|error| {
+        matches!(
+            error,
+            TransactWriteItemsError::TransactionCanceledException(cancelled)
+                if cancelled
+                    .cancellation_reasons()
+                    .iter()
+                    .any(|reason| reason.code() == Some("ConditionalCheckFailed"))
+        )
+    })
+}
+
+/// A put that failed its condition, with the object either already existing
+/// or at another version
+fn rejected<E>(location: &Path, mode: &PutMode, error: E) -> object_store::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    debug!(%location, ?mode, "conditional check failed");
+
+    if matches!(mode, PutMode::Create) {
+        object_store::Error::AlreadyExists {
+            path: location.to_string(),
+            source: Box::new(error),
+        }
+    } else {
+        object_store::Error::Precondition {
+            path: location.to_string(),
+            source: Box::new(error),
+        }
+    }
+}
+
+/// The kind (partition key) of the objects in a directory, being its first
+/// [`KIND_SEGMENTS`] segments
+fn kind_of_directory<'a>(segments: impl Iterator<Item = &'a str>) -> String {
+    let kind = segments
+        .take(KIND_SEGMENTS)
+        .collect::<Vec<_>>()
+        .join(Path::DELIMITER);
+
+    if kind.is_empty() {
+        Path::DELIMITER.into()
+    } else {
+        kind
+    }
+}
+
+/// The kind (partition key) of an object
+fn kind(location: &Path) -> String {
+    let segments = location.as_ref().split(Path::DELIMITER).collect::<Vec<_>>();
+    kind_of_directory(segments[..segments.len() - 1].iter().copied())
+}
+
+/// The kind of the objects listed under a prefix, when they share one
+fn list_kind(prefix: Option<&Path>) -> Option<String> {
+    prefix
+        .map(|prefix| prefix.as_ref())
+        .filter(|prefix| prefix.split(Path::DELIMITER).count() >= KIND_SEGMENTS)
+        .map(|prefix| kind_of_directory(prefix.split(Path::DELIMITER)))
+}
+
+/// The kind (partition key) of the chunks of a version of an object
+fn chunk_kind(location: &Path, version: &str) -> String {
+    format!("{location}#{version}")
+}
+
+/// The key of an object
+fn key(location: &Path) -> Item {
+    Item::from([
+        (KIND.into(), AttributeValue::S(kind(location))),
+        (PATH.into(), AttributeValue::S(location.to_string())),
+    ])
+}
+
+/// The key of a chunk of a version of an object
+fn chunk_key(location: &Path, version: &str, index: usize) -> Item {
+    Item::from([
+        (
+            KIND.into(),
+            AttributeValue::S(chunk_kind(location, version)),
+        ),
+        (PATH.into(), AttributeValue::S(format!("{index:0>5}"))),
+    ])
+}
+
+/// The number of chunks holding the payload of an item, if any
+fn chunks(item: &Item) -> Option<usize> {
+    item.get(CHUNKS)
+        .and_then(|chunks| chunks.as_n().ok())
+        .and_then(|chunks| chunks.parse().ok())
+}
+
+/// The condition of a put, as an expression with its attribute names and values
+#[derive(Clone, Debug, Default)]
+struct Condition {
+    expression: Option<String>,
+    names: Option<HashMap<String, String>>,
+    values: Option<Item>,
+}
+
+impl Condition {
+    fn new(location: &Path, mode: &PutMode) -> Result<Self, object_store::Error> {
+        match mode {
+            PutMode::Overwrite => Ok(Self::default()),
+
+            PutMode::Create => Ok(Self {
+                expression: Some("attribute_not_exists(#path)".into()),
+                names: Some(HashMap::from([("#path".into(), PATH.into())])),
+                values: None,
+            }),
+
+            PutMode::Update(update) => update
+                .e_tag
+                .as_ref()
+                .or(update.version.as_ref())
+                .cloned()
+                .ok_or_else(|| object_store::Error::Generic {
+                    store: STORE,
+                    source: format!("{location}: update without a version").into(),
+                })
+                .map(|expected| Self {
+                    expression: Some("#version = :version".into()),
+                    names: Some(HashMap::from([("#version".into(), VERSION.into())])),
+                    values: Some(HashMap::from([(
+                        ":version".into(),
+                        AttributeValue::S(expected),
+                    )])),
+                }),
+        }
+    }
+}
+
+/// The key of the items listed under a prefix, which (as with other object
+/// stores) is a directory rather than a prefix of a file name
+fn list_prefix(prefix: Option<&Path>) -> String {
+    prefix
+        .map(|prefix| prefix.as_ref())
+        .filter(|prefix| !prefix.is_empty())
+        .map_or_else(String::new, |prefix| format!("{prefix}{}", Path::DELIMITER))
+}
+
+/// The object meta of an item, which may omit the payload
+fn meta(item: &Item) -> Result<ObjectMeta, object_store::Error> {
+    let location = item
+        .get(PATH)
+        .and_then(|path| path.as_s().ok())
+        .ok_or_else(|| malformed("item", PATH))?;
+
+    let version = item
+        .get(VERSION)
+        .and_then(|version| version.as_s().ok())
+        .cloned()
+        .ok_or_else(|| malformed(location, VERSION))?;
+
+    let last_modified = item
+        .get(LAST_MODIFIED)
+        .and_then(|last_modified| last_modified.as_n().ok())
+        .and_then(|last_modified| last_modified.parse().ok())
+        .and_then(DateTime::<Utc>::from_timestamp_millis)
+        .ok_or_else(|| malformed(location, LAST_MODIFIED))?;
+
+    let size = item
+        .get(SIZE)
+        .and_then(|size| size.as_n().ok())
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(|| malformed(location, SIZE))?;
+
+    Ok(ObjectMeta {
+        location: Path::from(location.as_str()),
+        last_modified,
+        size,
+        e_tag: Some(version.clone()),
+        version: Some(version),
+    })
+}
+
+/// The byte range of an object requested by a get
+fn range(requested: Option<&GetRange>, size: u64) -> Result<Range<u64>, object_store::Error> {
+    let range = match requested {
+        None => 0..size,
+        Some(GetRange::Bounded(range)) => range.start..range.end.min(size),
+        Some(GetRange::Offset(offset)) => *offset..size,
+        Some(GetRange::Suffix(suffix)) => size.saturating_sub(*suffix)..size,
+    };
+
+    if range.start < range.end || (range.start == 0 && size == 0) {
+        Ok(range)
+    } else {
+        Err(object_store::Error::Generic {
+            store: STORE,
+            source: format!("range: {requested:?} is not satisfiable for size: {size}").into(),
+        })
+    }
+}
+
+/// The objects and common prefixes of a listing, one level below a prefix
+fn delimited(prefix: Option<&Path>, objects: Vec<ObjectMeta>) -> ListResult {
+    let within = list_prefix(prefix);
+
+    let mut common_prefixes = BTreeSet::new();
+    let mut listed = vec![];
+
+    for meta in objects {
+        let Some(remaining) = meta.location.as_ref().strip_prefix(within.as_str()) else {
+            continue;
+        };
+
+        match remaining.split_once(Path::DELIMITER) {
+            Some((directory, _)) => {
+                _ = common_prefixes.insert(Path::from(format!("{within}{directory}")));
+            }
+
+            None => listed.push(meta),
+        }
+    }
+
+    ListResult {
+        common_prefixes: common_prefixes.into_iter().collect(),
+        objects: listed,
+    }
+}
+
+/// An object store with each object held as an item in a DynamoDB table
+#[derive(Clone)]
+pub(crate) struct DynamoDb {
+    client: Client,
+    table: String,
+}
+
+impl Debug for DynamoDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamoDb")
+            .field("table", &self.table)
+            .finish()
+    }
+}
+
+impl Display for DynamoDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DynamoDb({})", self.table)
+    }
+}
+
+impl DynamoDb {
+    /// A table using the AWS configuration of the environment, with
+    /// `AWS_ENDPOINT_URL` selecting an alternative (e.g., local) endpoint
+    pub(crate) async fn from_env(table: impl Into<String>) -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+
+        Self {
+            client: Client::new(&config),
+            table: table.into(),
+        }
+    }
+
+    async fn item(&self, location: &Path) -> Result<Item, object_store::Error> {
+        self.client
+            .get_item()
+            .table_name(&self.table)
+            .set_key(Some(key(location)))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(generic)?
+            .item
+            .ok_or_else(|| not_found(location))
+    }
+
+    /// The version and chunks of an object, when it exists
+    async fn chunked(&self, location: &Path) -> Result<Option<Item>, object_store::Error> {
+        self.client
+            .get_item()
+            .table_name(&self.table)
+            .set_key(Some(key(location)))
+            .consistent_read(true)
+            .projection_expression("#version, #chunks")
+            .expression_attribute_names("#version", VERSION)
+            .expression_attribute_names("#chunks", CHUNKS)
+            .send()
+            .await
+            .map(|output| output.item)
+            .map_err(generic)
+    }
+
+    /// The payload of an object held in chunks
+    async fn payload(
+        &self,
+        location: &Path,
+        version: &str,
+        chunks: usize,
+    ) -> Result<Bytes, object_store::Error> {
+        let items = self
+            .client
+            .query()
+            .table_name(&self.table)
+            .consistent_read(true)
+            .key_condition_expression("#kind = :kind")
+            .expression_attribute_names("#kind", KIND)
+            .expression_attribute_values(":kind", AttributeValue::S(chunk_kind(location, version)))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await
+            .map_err(generic)?;
+
+        // the chunks of a version replaced while being read have been deleted
+        if items.len() != chunks {
+            return Err(object_store::Error::Generic {
+                store: STORE,
+                source: format!(
+                    "{location}: {} of {chunks} chunks of version: {version}",
+                    items.len()
+                )
+                .into(),
+            });
+        }
+
+        items
+            .iter()
+            .try_fold(BytesMut::new(), |mut payload, item| {
+                item.get(PAYLOAD)
+                    .and_then(|chunk| chunk.as_b().ok())
+                    .map(|chunk| {
+                        payload.extend_from_slice(chunk.as_ref());
+                        payload
+                    })
+                    .ok_or_else(|| malformed(location.as_ref(), PAYLOAD))
+            })
+            .map(BytesMut::freeze)
+    }
+
+    /// Delete the chunks of a previous version of an object, which are no
+    /// longer referenced once the object is replaced or deleted
+    async fn delete_chunks(&self, location: &Path, previous: &Item) {
+        let (Some(chunks), Some(version)) = (
+            chunks(previous),
+            previous
+                .get(VERSION)
+                .and_then(|version| version.as_s().ok()),
+        ) else {
+            return;
+        };
+
+        for index in 0..chunks {
+            if let Err(error) = self
+                .client
+                .delete_item()
+                .table_name(&self.table)
+                .set_key(Some(chunk_key(location, version, index)))
+                .send()
+                .await
+            {
+                warn!(%location, version, index, ?error);
+            }
+        }
+    }
+
+    /// Put an object held in a single item, returning the item replaced
+    async fn put_item(
+        &self,
+        location: &Path,
+        item: Item,
+        mode: &PutMode,
+    ) -> Result<Option<Item>, object_store::Error> {
+        let condition = Condition::new(location, mode)?;
+
+        self.client
+            .put_item()
+            .table_name(&self.table)
+            .set_item(Some(item))
+            .set_condition_expression(condition.expression)
+            .set_expression_attribute_names(condition.names)
+            .set_expression_attribute_values(condition.values)
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map(|output| output.attributes)
+            .map_err(|error| {
+                if is_conditional_check_failed(&error) {
+                    rejected(location, mode, error)
+                } else {
+                    generic(error)
+                }
+            })
+    }
+
+    /// Put an object with its payload in chunks, written in one transaction,
+    /// returning the item replaced
+    async fn put_chunked(
+        &self,
+        location: &Path,
+        item: Item,
+        version: &str,
+        payload: &Bytes,
+        mode: &PutMode,
+    ) -> Result<Option<Item>, object_store::Error> {
+        if payload.len() > MAX_OBJECT_BYTES {
+            return Err(object_store::Error::Generic {
+                store: STORE,
+                source: format!(
+                    "{location}: {} bytes exceeds the maximum object size of {MAX_OBJECT_BYTES} bytes",
+                    payload.len()
+                )
+                .into(),
+            });
+        }
+
+        let previous = if matches!(mode, PutMode::Create) {
+            None
+        } else {
+            self.chunked(location).await?
+        };
+
+        let condition = Condition::new(location, mode)?;
+
+        let object = Put::builder()
+            .table_name(&self.table)
+            .set_item(Some(item))
+            .set_condition_expression(condition.expression)
+            .set_expression_attribute_names(condition.names)
+            .set_expression_attribute_values(condition.values)
+            .build()
+            .map(|put| TransactWriteItem::builder().put(put).build())
+            .map_err(generic)?;
+
+        let chunks = payload
+            .chunks(CHUNK_BYTES)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut item = chunk_key(location, version, index);
+                _ = item.insert(PAYLOAD.into(), AttributeValue::B(Blob::new(chunk)));
+
+                Put::builder()
+                    .table_name(&self.table)
+                    .set_item(Some(item))
+                    .build()
+                    .map(|put| TransactWriteItem::builder().put(put).build())
+                    .map_err(generic)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.client
+            .transact_write_items()
+            .set_transact_items(Some([vec![object], chunks].concat()))
+            .send()
+            .await
+            .map(|_| previous)
+            .map_err(|error| {
+                if is_transaction_condition_failed(&error) {
+                    rejected(location, mode, error)
+                } else {
+                    generic(error)
+                }
+            })
+    }
+
+    /// The items under a prefix, without their payload, querying the kind of
+    /// the prefix when it has one, otherwise scanning the table
+    fn items(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'static, Result<Item, object_store::Error>> {
+        let client = self.client.clone();
+        let table = self.table.clone();
+        let kind = list_kind(prefix);
+        let prefix = list_prefix(prefix);
+
+        stream::try_unfold(
+            (Some(None), client, table, kind, prefix),
+            |(exclusive_start_key, client, table, kind, prefix)| async move {
+                let Some(exclusive_start_key) = exclusive_start_key else {
+                    return Ok(None);
+                };
+
+                let (items, last_evaluated_key) = if let Some(ref kind) = kind {
+                    client
+                        .query()
+                        .table_name(&table)
+                        .consistent_read(true)
+                        .projection_expression("#path, #version, #last_modified, #size")
+                        .key_condition_expression("#kind = :kind and begins_with(#path, :prefix)")
+                        .expression_attribute_names("#kind", KIND)
+                        .expression_attribute_names("#path", PATH)
+                        .expression_attribute_names("#version", VERSION)
+                        .expression_attribute_names("#last_modified", LAST_MODIFIED)
+                        .expression_attribute_names("#size", SIZE)
+                        .expression_attribute_values(":kind", AttributeValue::S(kind.clone()))
+                        .expression_attribute_values(":prefix", AttributeValue::S(prefix.clone()))
+                        .set_exclusive_start_key(exclusive_start_key)
+                        .send()
+                        .await
+                        .map(|output| (output.items, output.last_evaluated_key))
+                        .map_err(generic)?
+                } else {
+                    client
+                        .scan()
+                        .table_name(&table)
+                        .consistent_read(true)
+                        .projection_expression("#path, #version, #last_modified, #size")
+                        .expression_attribute_names("#path", PATH)
+                        .expression_attribute_names("#version", VERSION)
+                        .expression_attribute_names("#last_modified", LAST_MODIFIED)
+                        .expression_attribute_names("#size", SIZE)
+                        .filter_expression(
+                            "begins_with(#path, :prefix) and attribute_exists(#size)",
+                        )
+                        .expression_attribute_values(":prefix", AttributeValue::S(prefix.clone()))
+                        .set_exclusive_start_key(exclusive_start_key)
+                        .send()
+                        .await
+                        .map(|output| (output.items, output.last_evaluated_key))
+                        .map_err(generic)?
+                };
+
+                Ok(Some((
+                    stream::iter(items.unwrap_or_default().into_iter().map(Ok)),
+                    (last_evaluated_key.map(Some), client, table, kind, prefix),
+                )))
+            },
+        )
+        .try_flatten()
+        .boxed()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for DynamoDb {
+    #[instrument(skip_all, fields(%location, mode = ?opts.mode))]
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult, object_store::Error> {
+        let payload = Bytes::from(payload);
+        let version = Uuid::now_v7().to_string();
+        let last_modified = DateTime::<Utc>::from(SystemTime::now()).timestamp_millis();
+
+        let mut item = key(location);
+        _ = item.insert(SIZE.into(), AttributeValue::N(payload.len().to_string()));
+        _ = item.insert(VERSION.into(), AttributeValue::S(version.clone()));
+        _ = item.insert(
+            LAST_MODIFIED.into(),
+            AttributeValue::N(last_modified.to_string()),
+        );
+
+        let previous = if payload.len() > CHUNK_BYTES {
+            _ = item.insert(
+                CHUNKS.into(),
+                AttributeValue::N(payload.len().div_ceil(CHUNK_BYTES).to_string()),
+            );
+
+            self.put_chunked(location, item, &version, &payload, &opts.mode)
+                .await?
+        } else {
+            _ = item.insert(PAYLOAD.into(), AttributeValue::B(Blob::new(payload)));
+
+            self.put_item(location, item, &opts.mode).await?
+        };
+
+        if let Some(previous) = previous {
+            self.delete_chunks(location, &previous).await;
+        }
+
+        Ok(PutResult {
+            e_tag: Some(version.clone()),
+            version: Some(version),
+        })
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        _opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>, object_store::Error> {
+        Err(object_store::Error::Generic {
+            store: STORE,
+            source: format!("{location}: multipart upload is not supported").into(),
+        })
+    }
+
+    #[instrument(skip_all, fields(%location, if_none_match = options.if_none_match))]
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> Result<GetResult, object_store::Error> {
+        let item = self.item(location).await?;
+        let meta = meta(&item)?;
+
+        options.check_preconditions(&meta)?;
+
+        let range = range(options.range.as_ref(), meta.size)?;
+
+        let payload = if options.head {
+            Bytes::new()
+        } else if let Some(chunks) = chunks(&item) {
+            let version = meta.version.as_deref().unwrap_or_default();
+
+            self.payload(location, version, chunks)
+                .await?
+                .slice(range.start as usize..range.end as usize)
+        } else {
+            item.get(PAYLOAD)
+                .and_then(|payload| payload.as_b().ok())
+                .map(|payload| Bytes::copy_from_slice(payload.as_ref()))
+                .ok_or_else(|| malformed(location.as_ref(), PAYLOAD))?
+                .slice(range.start as usize..range.end as usize)
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async move { Ok(payload) }).boxed()),
+            meta,
+            range,
+            attributes: Default::default(),
+        })
+    }
+
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path, object_store::Error>>,
+    ) -> BoxStream<'static, Result<Path, object_store::Error>> {
+        let store = self.clone();
+
+        locations
+            .and_then(move |location| {
+                let store = store.clone();
+
+                async move {
+                    let previous = store
+                        .client
+                        .delete_item()
+                        .table_name(&store.table)
+                        .set_key(Some(key(&location)))
+                        .return_values(ReturnValue::AllOld)
+                        .send()
+                        .await
+                        .map(|output| output.attributes)
+                        .map_err(generic)?;
+
+                    if let Some(previous) = previous {
+                        store.delete_chunks(&location, &previous).await;
+                    }
+
+                    Ok(location)
+                }
+            })
+            .boxed()
+    }
+
+    fn list(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'static, Result<ObjectMeta, object_store::Error>> {
+        self.items(prefix)
+            .and_then(|item| futures::future::ready(meta(&item)))
+            .boxed()
+    }
+
+    async fn list_with_delimiter(
+        &self,
+        prefix: Option<&Path>,
+    ) -> Result<ListResult, object_store::Error> {
+        self.list(prefix)
+            .try_collect::<Vec<_>>()
+            .await
+            .map(|objects| delimited(prefix, objects))
+    }
+
+    async fn copy_opts(
+        &self,
+        from: &Path,
+        to: &Path,
+        opts: CopyOptions,
+    ) -> Result<(), object_store::Error> {
+        let payload = self
+            .get_opts(from, GetOptions::default())
+            .await?
+            .bytes()
+            .await?;
+
+        let mode = match opts.mode {
+            CopyMode::Overwrite => PutMode::Overwrite,
+            CopyMode::Create => PutMode::Create,
+        };
+
+        self.put_opts(
+            to,
+            PutPayload::from(payload),
+            PutOptions {
+                mode,
+                ..Default::default()
+            },
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(location: &str) -> ObjectMeta {
+        ObjectMeta {
+            location: Path::from(location),
+            last_modified: DateTime::<Utc>::from(SystemTime::UNIX_EPOCH),
+            size: 0,
+            e_tag: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn kind_is_the_leading_segments_of_the_directory() {
+        assert_eq!("/", kind(&Path::from("meta.json")));
+        assert_eq!(
+            "clusters/tansu",
+            kind(&Path::from("clusters/tansu/meta.json"))
+        );
+
+        assert_eq!(
+            "clusters/tansu/topics/abc",
+            kind(&Path::from(
+                "clusters/tansu/topics/abc/partitions/0000000000/records/0.batch"
+            ))
+        );
+
+        assert_eq!(
+            "clusters/tansu/groups/consumers",
+            kind(&Path::from(
+                "clusters/tansu/groups/consumers/xyz/offsets/abc/partitions/0000000000.json"
+            ))
+        );
+    }
+
+    #[test]
+    fn list_kind_of_prefix() {
+        assert_eq!(None, list_kind(None));
+        assert_eq!(None, list_kind(Some(&Path::from("clusters/tansu"))));
+
+        assert_eq!(
+            Some("clusters/tansu/groups/consumers".into()),
+            list_kind(Some(&Path::from("clusters/tansu/groups/consumers/")))
+        );
+
+        let location =
+            Path::from("clusters/tansu/topics/abc/partitions/0000000000/records/0.batch");
+
+        assert_eq!(
+            Some(kind(&location)),
+            list_kind(Some(&Path::from(
+                "clusters/tansu/topics/abc/partitions/0000000000/records/"
+            )))
+        );
+    }
+
+    #[test]
+    fn chunks_are_keyed_by_version() {
+        let location = Path::from("clusters/tansu/meta.json");
+
+        assert_eq!(
+            Some(&AttributeValue::S("clusters/tansu/meta.json#abc".into())),
+            chunk_key(&location, "abc", 3).get(KIND)
+        );
+
+        assert_eq!(
+            Some(&AttributeValue::S("00003".into())),
+            chunk_key(&location, "abc", 3).get(PATH)
+        );
+    }
+
+    #[test]
+    fn prefix_is_a_directory() {
+        assert_eq!("", list_prefix(None));
+        assert_eq!("clusters/", list_prefix(Some(&Path::from("clusters"))));
+    }
+
+    #[test]
+    fn list_one_level() {
+        let listed = delimited(
+            Some(&Path::from("clusters/tansu")),
+            vec![
+                object("clusters/tansu/meta.json"),
+                object("clusters/tansu/topics/abc/records/0.batch"),
+                object("clusters/tansu/topics/pqr/records/0.batch"),
+                object("clusters/tansu/watermarks/abc/0.json"),
+            ],
+        );
+
+        assert_eq!(
+            vec![
+                Path::from("clusters/tansu/topics"),
+                Path::from("clusters/tansu/watermarks")
+            ],
+            listed.common_prefixes
+        );
+
+        assert_eq!(
+            vec![Path::from("clusters/tansu/meta.json")],
+            listed
+                .objects
+                .into_iter()
+                .map(|meta| meta.location)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn get_range() {
+        assert_eq!(0..10, range(None, 10).unwrap());
+        assert_eq!(2..5, range(Some(&GetRange::Bounded(2..5)), 10).unwrap());
+        assert_eq!(8..10, range(Some(&GetRange::Bounded(8..15)), 10).unwrap());
+        assert_eq!(4..10, range(Some(&GetRange::Offset(4)), 10).unwrap());
+        assert_eq!(7..10, range(Some(&GetRange::Suffix(3)), 10).unwrap());
+        assert_eq!(0..0, range(None, 0).unwrap());
+        assert!(range(Some(&GetRange::Offset(12)), 10).is_err());
+    }
+}
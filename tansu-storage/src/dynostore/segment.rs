@@ -0,0 +1,1062 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local filesystem segments
+//!
+//! Record batches are appended to Kafka style segments on local disk, with
+//! a directory for each topition under `segments/{cluster}/{topic}-{partition}`
+//! holding:
+//!
+//! - `{base_offset}.log`: the record batches in their wire format, with the
+//!   base offset of each batch set to its offset in the partition.
+//! - `{base_offset}.index`: the relative offset and position (each a big
+//!   endian `i32`) of every batch in the log.
+//! - `{base_offset}.timeindex`: the max timestamp (a big endian `i64`) and
+//!   relative offset (a big endian `i32`) of each batch increasing the max
+//!   timestamp of the segment.
+//!
+//! The active segment is rolled once it reaches `segment.bytes`. On startup,
+//! the batches of each segment are recovered from its log, truncating any
+//! partially written batch. A segment is removed once all of its batches are
+//! deleted, with a compacted batch appended to the active segment.
+//!
+//! Everything other than record batches (watermarks, offsets, metadata) are
+//! files on local disk, with conditional updates serialized by this store,
+//! which must be the only writer.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug, Display},
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures::{
+    StreamExt, TryStreamExt,
+    stream::{self, BoxStream},
+};
+use object_store::{
+    Attributes, CopyMode, CopyOptions, GetOptions, GetRange, GetResult, GetResultPayload,
+    ListResult, MultipartUpload, ObjectMeta, ObjectStore, ObjectStoreExt, PutMode,
+    PutMultipartOptions, PutOptions, PutPayload, PutResult, path::Path,
+};
+use tracing::{debug, instrument, warn};
+
+const STORE: &str = "Segments";
+
+/// Record batches are appended to segments
+const BATCH: &str = "batch";
+
+const LOG: &str = "log";
+const INDEX: &str = "index";
+const TIME_INDEX: &str = "timeindex";
+
+/// Segments are rolled at 1GiB by default, as with Kafka
+pub(crate) const DEFAULT_SEGMENT_BYTES: u64 = 1_073_741_824;
+
+/// The base offset and batch length of a record batch
+const BATCH_OVERHEAD: usize = 12;
+
+/// The wire format of a record batch up to and including its max timestamp
+const BATCH_HEADER: usize = 43;
+
+/// The position of the max timestamp within a record batch
+const MAX_TIMESTAMP: Range<usize> = 35..43;
+
+fn generic<E>(error: E) -> object_store::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    object_store::Error::Generic {
+        store: STORE,
+        source: Box::new(error),
+    }
+}
+
+fn not_found(location: &Path) -> object_store::Error {
+    object_store::Error::NotFound {
+        path: location.to_string(),
+        source: format!("{location} not found").into(),
+    }
+}
+
+fn is_not_found(error: &object_store::Error) -> bool {
+    matches!(error, object_store::Error::NotFound { .. })
+}
+
+/// The topition of a record batch
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Topition {
+    cluster: String,
+    topic: String,
+    partition: i32,
+}
+
+impl Topition {
+    /// The topition and offset of a record batch location
+    fn parse(location: &Path) -> Option<(Self, i64)> {
+        if location.extension() != Some(BATCH) {
+            return None;
+        }
+
+        let parts = location.parts().collect::<Vec<_>>();
+
+        match parts.iter().map(AsRef::as_ref).collect::<Vec<&str>>()[..] {
+            [
+                "clusters",
+                cluster,
+                "topics",
+                topic,
+                "partitions",
+                partition,
+                "records",
+                batch,
+            ] => Some((
+                Self {
+                    cluster: cluster.to_owned(),
+                    topic: topic.to_owned(),
+                    partition: partition.parse().ok()?,
+                },
+                batch.strip_suffix(".batch")?.parse().ok()?,
+            )),
+
+            _ => None,
+        }
+    }
+
+    fn location(&self, offset: i64) -> Path {
+        Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+            self.cluster, self.topic, self.partition, offset,
+        ))
+    }
+
+    fn directory(&self, root: &std::path::Path) -> PathBuf {
+        root.join("segments")
+            .join(&self.cluster)
+            .join(format!("{}-{}", self.topic, self.partition))
+    }
+}
+
+/// A record batch within a segment
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Entry {
+    segment: i64,
+    position: u64,
+    size: u64,
+    last_modified: DateTime<Utc>,
+}
+
+/// The size and max timestamp of a segment
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Segment {
+    size: u64,
+    max_timestamp: i64,
+}
+
+/// The segments of a topition
+#[derive(Debug)]
+struct Log {
+    directory: PathBuf,
+    segment_bytes: u64,
+    segments: BTreeMap<i64, Segment>,
+    entries: BTreeMap<i64, Entry>,
+}
+
+impl Log {
+    fn new(directory: PathBuf, segment_bytes: u64) -> Self {
+        Self {
+            directory,
+            segment_bytes,
+            segments: BTreeMap::new(),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn file(&self, base_offset: i64, extension: &str) -> PathBuf {
+        self.directory
+            .join(format!("{base_offset:0>20}"))
+            .with_extension(extension)
+    }
+
+    /// Open the segments of an existing topition directory
+    fn open(directory: PathBuf, segment_bytes: u64) -> io::Result<Self> {
+        let mut log = Self::new(directory, segment_bytes);
+
+        let mut base_offsets = BTreeSet::new();
+
+        for dir_entry in fs::read_dir(&log.directory)? {
+            let path = dir_entry?.path();
+
+            if path.extension().is_some_and(|extension| extension == LOG)
+                && let Some(base_offset) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok())
+            {
+                _ = base_offsets.insert(base_offset);
+            }
+        }
+
+        for base_offset in base_offsets {
+            log.recover(base_offset)?;
+        }
+
+        Ok(log)
+    }
+
+    /// Recover the batches of a segment from its log, truncating a partially
+    /// written batch
+    fn recover(&mut self, base_offset: i64) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.file(base_offset, LOG))?;
+
+        let metadata = file.metadata()?;
+        let last_modified = metadata
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+
+        let length = metadata.len();
+        let mut segment = Segment::default();
+        let mut header = [0u8; BATCH_HEADER];
+
+        while segment.size < length {
+            let position = segment.size;
+
+            let size = file
+                .seek(SeekFrom::Start(position))
+                .and_then(|_| file.read_exact(&mut header))
+                .ok()
+                .map(|()| {
+                    let offset = i64::from_be_bytes(header[0..8].try_into().unwrap_or_default());
+                    let batch_length =
+                        i32::from_be_bytes(header[8..12].try_into().unwrap_or_default());
+                    (offset, batch_length)
+                })
+                .and_then(|(offset, batch_length)| {
+                    u64::try_from(batch_length)
+                        .ok()
+                        .map(|batch_length| (offset, batch_length + BATCH_OVERHEAD as u64))
+                })
+                .filter(|(_, size)| position + size <= length);
+
+            let Some((offset, size)) = size else {
+                warn!(?self.directory, base_offset, position, length, "truncating");
+                file.set_len(position)?;
+                break;
+            };
+
+            segment.size += size;
+            segment.max_timestamp = segment.max_timestamp.max(i64::from_be_bytes(
+                header[MAX_TIMESTAMP].try_into().unwrap_or_default(),
+            ));
+
+            // a compacted batch replaces any earlier copy
+            _ = self.entries.insert(
+                offset,
+                Entry {
+                    segment: base_offset,
+                    position,
+                    size,
+                    last_modified,
+                },
+            );
+        }
+
+        debug!(?self.directory, base_offset, ?segment);
+
+        _ = self.segments.insert(base_offset, segment);
+
+        Ok(())
+    }
+
+    /// Append a batch to the active segment, rolling to a new segment when
+    /// the active segment is full
+    fn append(&mut self, offset: i64, batch: Bytes) -> io::Result<Entry> {
+        if batch.len() < BATCH_HEADER {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("batch of {} bytes is too short", batch.len()),
+            ));
+        }
+
+        let active = self
+            .segments
+            .last_key_value()
+            .filter(|(base_offset, segment)| {
+                segment.size < self.segment_bytes || **base_offset >= offset
+            })
+            .map(|(base_offset, _)| *base_offset);
+
+        let base_offset = match active {
+            Some(base_offset) => base_offset,
+
+            None => {
+                fs::create_dir_all(&self.directory)?;
+                _ = self.segments.insert(offset, Segment::default());
+                offset
+            }
+        };
+
+        let mut encoded = BytesMut::from(batch);
+        encoded[0..8].copy_from_slice(&offset.to_be_bytes());
+
+        let max_timestamp =
+            i64::from_be_bytes(encoded[MAX_TIMESTAMP].try_into().unwrap_or_default());
+
+        let relative = i32::try_from(offset - base_offset)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+
+        let segment = self.segments.entry(base_offset).or_default();
+        let position = segment.size;
+
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.file(base_offset, LOG))?;
+        log.write_all(&encoded[..])?;
+
+        let mut index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.file(base_offset, INDEX))?;
+        index.write_all(&relative.to_be_bytes())?;
+        index.write_all(&i32::try_from(position).unwrap_or(i32::MAX).to_be_bytes())?;
+
+        if max_timestamp > segment.max_timestamp {
+            let mut time_index = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.file(base_offset, TIME_INDEX))?;
+            time_index.write_all(&max_timestamp.to_be_bytes())?;
+            time_index.write_all(&relative.to_be_bytes())?;
+
+            segment.max_timestamp = max_timestamp;
+        }
+
+        let size = encoded.len() as u64;
+        segment.size += size;
+
+        let entry = Entry {
+            segment: base_offset,
+            position,
+            size,
+            last_modified: Utc::now(),
+        };
+
+        _ = self.entries.insert(offset, entry);
+
+        Ok(entry)
+    }
+
+    fn read(&self, entry: &Entry) -> io::Result<Bytes> {
+        let mut file = File::open(self.file(entry.segment, LOG))?;
+        _ = file.seek(SeekFrom::Start(entry.position))?;
+
+        let mut batch = vec![0; usize::try_from(entry.size).unwrap_or_default()];
+        file.read_exact(&mut batch)?;
+
+        Ok(Bytes::from(batch))
+    }
+
+    /// Remove a batch, removing its segment when no other batches remain
+    fn remove(&mut self, offset: i64) -> io::Result<()> {
+        let Some(removed) = self.entries.remove(&offset) else {
+            return Ok(());
+        };
+
+        if self
+            .entries
+            .values()
+            .any(|entry| entry.segment == removed.segment)
+        {
+            return Ok(());
+        }
+
+        _ = self.segments.remove(&removed.segment);
+
+        for extension in [LOG, INDEX, TIME_INDEX] {
+            match fs::remove_file(self.file(removed.segment, extension)) {
+                Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+                _ => (),
+            }
+        }
+
+        if self.segments.is_empty() {
+            _ = fs::remove_dir(&self.directory).inspect_err(|err| debug!(?err));
+        }
+
+        Ok(())
+    }
+}
+
+type Logs = Arc<Mutex<BTreeMap<Topition, Arc<Mutex<Log>>>>>;
+
+/// Run blocking file I/O on a log
+async fn blocking<F, T>(log: Arc<Mutex<Log>>, f: F) -> Result<T, object_store::Error>
+where
+    F: FnOnce(&mut Log) -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        log.lock()
+            .map_err(|poison| io::Error::other(poison.to_string()))
+            .and_then(|mut log| f(&mut log))
+    })
+    .await
+    .map_err(generic)?
+    .map_err(generic)
+}
+
+/// The byte range of a batch requested by a get
+fn range(requested: Option<&GetRange>, size: u64) -> Result<Range<u64>, object_store::Error> {
+    let range = match requested {
+        None => 0..size,
+        Some(GetRange::Bounded(range)) => range.start..range.end.min(size),
+        Some(GetRange::Offset(offset)) => *offset..size,
+        Some(GetRange::Suffix(suffix)) => size.saturating_sub(*suffix)..size,
+    };
+
+    if range.start < range.end || (range.start == 0 && size == 0) {
+        Ok(range)
+    } else {
+        Err(object_store::Error::Generic {
+            store: STORE,
+            source: format!("range: {requested:?} is not satisfiable for size: {size}").into(),
+        })
+    }
+}
+
+/// Record batches appended to segments on local disk, with every other
+/// object held by the inner store
+pub(crate) struct Segments<S> {
+    root: PathBuf,
+    segment_bytes: u64,
+    logs: Logs,
+    inner: Arc<S>,
+    updates: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<S> Debug for Segments<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(stringify!(Segments))
+            .field("root", &self.root)
+            .field("segment_bytes", &self.segment_bytes)
+            .finish()
+    }
+}
+
+impl<S> Display for Segments<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{STORE}({})", self.root.display())
+    }
+}
+
+impl<S> Segments<S>
+where
+    S: ObjectStore,
+{
+    /// Segments under a root directory, opening the segments of every
+    /// existing topition
+    pub(crate) fn new(
+        root: impl Into<PathBuf>,
+        inner: S,
+        segment_bytes: u64,
+    ) -> Result<Self, object_store::Error> {
+        let root = root.into();
+        let mut logs = BTreeMap::new();
+
+        let clusters = root.join("segments");
+
+        if clusters.is_dir() {
+            for cluster in fs::read_dir(&clusters).map_err(generic)? {
+                let cluster = cluster.map_err(generic)?.path();
+
+                let Some(cluster_name) = cluster.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+
+                for directory in fs::read_dir(&cluster).map_err(generic)? {
+                    let directory = directory.map_err(generic)?.path();
+
+                    let Some((topic, partition)) = directory
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| name.rsplit_once('-'))
+                        .and_then(|(topic, partition)| {
+                            partition
+                                .parse()
+                                .ok()
+                                .map(|partition| (topic.to_owned(), partition))
+                        })
+                    else {
+                        continue;
+                    };
+
+                    let log = Log::open(directory, segment_bytes).map_err(generic)?;
+
+                    _ = logs.insert(
+                        Topition {
+                            cluster: cluster_name.to_owned(),
+                            topic,
+                            partition,
+                        },
+                        Arc::new(Mutex::new(log)),
+                    );
+                }
+            }
+        }
+
+        Ok(Self {
+            root,
+            segment_bytes,
+            logs: Arc::new(Mutex::new(logs)),
+            inner: Arc::new(inner),
+            updates: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    fn log(&self, topition: &Topition) -> Result<Arc<Mutex<Log>>, object_store::Error> {
+        self.logs
+            .lock()
+            .map(|mut logs| {
+                logs.entry(topition.to_owned())
+                    .or_insert_with(|| {
+                        Arc::new(Mutex::new(Log::new(
+                            topition.directory(&self.root),
+                            self.segment_bytes,
+                        )))
+                    })
+                    .clone()
+            })
+            .map_err(|poison| generic(io::Error::other(poison.to_string())))
+    }
+
+    fn entry(&self, topition: &Topition, offset: i64) -> Option<Entry> {
+        self.logs
+            .lock()
+            .ok()
+            .and_then(|logs| logs.get(topition).cloned())
+            .and_then(|log| {
+                log.lock()
+                    .ok()
+                    .and_then(|log| log.entries.get(&offset).copied())
+            })
+    }
+
+    /// The object meta of every batch under a prefix
+    fn batches(&self, prefix: Option<&Path>) -> Vec<ObjectMeta> {
+        let logs = self
+            .logs
+            .lock()
+            .map(|logs| {
+                logs.iter()
+                    .map(|(topition, log)| (topition.to_owned(), log.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut batches = vec![];
+
+        for (topition, log) in logs {
+            let Ok(log) = log.lock() else {
+                continue;
+            };
+
+            for (offset, entry) in &log.entries {
+                let location = topition.location(*offset);
+
+                if prefix.is_none_or(|prefix| location.prefix_matches(prefix)) {
+                    batches.push(meta(location, entry));
+                }
+            }
+        }
+
+        batches
+    }
+
+    /// A conditional update of an object held by the inner store, which has
+    /// no conditional updates of its own
+    async fn update(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult, object_store::Error> {
+        // attributes (e.g., the content type) are not held on local disk
+        let opts = PutOptions {
+            attributes: Attributes::new(),
+            ..opts
+        };
+
+        let PutMode::Update(ref version) = opts.mode else {
+            return self.inner.put_opts(location, payload, opts).await;
+        };
+
+        let _guard = self.updates.lock().await;
+
+        let current = match self.inner.head(location).await {
+            Ok(meta) => meta,
+
+            Err(error) if is_not_found(&error) => {
+                return Err(object_store::Error::Precondition {
+                    path: location.to_string(),
+                    source: format!("{location} not found").into(),
+                });
+            }
+
+            Err(error) => return Err(error),
+        };
+
+        if current.e_tag != version.e_tag {
+            return Err(object_store::Error::Precondition {
+                path: location.to_string(),
+                source: format!("{location} has changed: {:?}", current.e_tag).into(),
+            });
+        }
+
+        self.inner
+            .put_opts(
+                location,
+                payload,
+                PutOptions {
+                    mode: PutMode::Overwrite,
+                    ..opts
+                },
+            )
+            .await
+    }
+}
+
+fn meta(location: Path, entry: &Entry) -> ObjectMeta {
+    ObjectMeta {
+        location,
+        last_modified: entry.last_modified,
+        size: entry.size,
+        e_tag: Some(format!("{}-{}", entry.segment, entry.position)),
+        version: None,
+    }
+}
+
+#[async_trait]
+impl<S> ObjectStore for Segments<S>
+where
+    S: ObjectStore,
+{
+    #[instrument(skip_all, fields(%location))]
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult, object_store::Error> {
+        let Some((topition, offset)) = Topition::parse(location) else {
+            return self.update(location, payload, opts).await;
+        };
+
+        match opts.mode {
+            PutMode::Create if self.entry(&topition, offset).is_some() => {
+                return Err(object_store::Error::AlreadyExists {
+                    path: location.to_string(),
+                    source: format!("{location} already exists").into(),
+                });
+            }
+
+            PutMode::Create | PutMode::Overwrite => (),
+
+            PutMode::Update(_) => return Err(object_store::Error::NotImplemented),
+        }
+
+        let entry = blocking(self.log(&topition)?, move |log| {
+            log.append(offset, Bytes::from(payload))
+        })
+        .await?;
+
+        Ok(PutResult {
+            e_tag: Some(format!("{}-{}", entry.segment, entry.position)),
+            version: None,
+        })
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>, object_store::Error> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    #[instrument(skip_all, fields(%location))]
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> Result<GetResult, object_store::Error> {
+        let Some((topition, offset)) = Topition::parse(location) else {
+            return self.inner.get_opts(location, options).await;
+        };
+
+        let entry = self
+            .entry(&topition, offset)
+            .ok_or_else(|| not_found(location))?;
+
+        let meta = meta(location.to_owned(), &entry);
+        options.check_preconditions(&meta)?;
+
+        let range = range(options.range.as_ref(), meta.size)?;
+
+        let payload = if options.head {
+            Bytes::new()
+        } else {
+            blocking(self.log(&topition)?, move |log| log.read(&entry))
+                .await?
+                .slice(range.start as usize..range.end as usize)
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async move { Ok(payload) }).boxed()),
+            meta,
+            range,
+            attributes: Default::default(),
+        })
+    }
+
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path, object_store::Error>>,
+    ) -> BoxStream<'static, Result<Path, object_store::Error>> {
+        let logs = self.logs.clone();
+        let inner = self.inner.clone();
+
+        locations
+            .and_then(move |location| {
+                let logs = logs.clone();
+                let inner = inner.clone();
+
+                async move {
+                    let Some((topition, offset)) = Topition::parse(&location) else {
+                        return match inner.delete(&location).await {
+                            Err(error) if !is_not_found(&error) => Err(error),
+                            _ => Ok(location),
+                        };
+                    };
+
+                    let log = logs
+                        .lock()
+                        .ok()
+                        .and_then(|logs| logs.get(&topition).cloned());
+
+                    if let Some(log) = log {
+                        blocking(log, move |log| log.remove(offset)).await?;
+                    }
+
+                    Ok(location)
+                }
+            })
+            .boxed()
+    }
+
+    fn list(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'static, Result<ObjectMeta, object_store::Error>> {
+        let batches = self.batches(prefix);
+
+        self.inner
+            .list(prefix)
+            .chain(stream::iter(batches.into_iter().map(Ok)))
+            .boxed()
+    }
+
+    async fn list_with_delimiter(
+        &self,
+        prefix: Option<&Path>,
+    ) -> Result<ListResult, object_store::Error> {
+        let listed = self.inner.list_with_delimiter(prefix).await?;
+
+        let within = prefix
+            .map(|prefix| prefix.as_ref())
+            .filter(|prefix| !prefix.is_empty())
+            .map_or_else(String::new, |prefix| format!("{prefix}{}", Path::DELIMITER));
+
+        let mut common_prefixes = listed.common_prefixes.into_iter().collect::<BTreeSet<_>>();
+        let mut objects = listed.objects;
+
+        for meta in self.batches(prefix) {
+            let Some(remaining) = meta.location.as_ref().strip_prefix(within.as_str()) else {
+                continue;
+            };
+
+            match remaining.split_once(Path::DELIMITER) {
+                Some((directory, _)) => {
+                    _ = common_prefixes.insert(Path::from(format!("{within}{directory}")));
+                }
+
+                None => objects.push(meta),
+            }
+        }
+
+        Ok(ListResult {
+            common_prefixes: common_prefixes.into_iter().collect(),
+            objects,
+        })
+    }
+
+    async fn copy_opts(
+        &self,
+        from: &Path,
+        to: &Path,
+        opts: CopyOptions,
+    ) -> Result<(), object_store::Error> {
+        if Topition::parse(from).is_none() && Topition::parse(to).is_none() {
+            return self.inner.copy_opts(from, to, opts).await;
+        }
+
+        let payload = self
+            .get_opts(from, GetOptions::default())
+            .await?
+            .bytes()
+            .await?;
+
+        let mode = match opts.mode {
+            CopyMode::Overwrite => PutMode::Overwrite,
+            CopyMode::Create => PutMode::Create,
+        };
+
+        self.put_opts(
+            to,
+            PutPayload::from(payload),
+            PutOptions {
+                mode,
+                ..Default::default()
+            },
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::{UpdateVersion, local::LocalFileSystem};
+    use tansu_sans_io::record::{Record, deflated, inflated};
+
+    use super::*;
+
+    const LOCATION: &str = "clusters/tansu/topics/abc/partitions/0000000000/records";
+
+    fn batch(value: &'static [u8], max_timestamp: i64) -> Bytes {
+        inflated::Batch::builder()
+            .record(Record::builder().value(Some(Bytes::from_static(value))))
+            .max_timestamp(max_timestamp)
+            .build()
+            .and_then(deflated::Batch::try_from)
+            .map(Bytes::from)
+            .expect("batch")
+    }
+
+    fn segments(root: &std::path::Path, segment_bytes: u64) -> Segments<LocalFileSystem> {
+        Segments::new(
+            root,
+            LocalFileSystem::new_with_prefix(root).expect("local"),
+            segment_bytes,
+        )
+        .expect("segments")
+    }
+
+    #[tokio::test]
+    async fn appended_to_segments() -> Result<(), object_store::Error> {
+        let root = tempfile::tempdir().map_err(generic)?;
+        let segments = segments(root.path(), 1);
+
+        for (offset, value) in [(0, b"abc"), (1, b"pqr"), (2, b"xyz")] {
+            _ = segments
+                .put(
+                    &Path::from(format!("{LOCATION}/{offset:0>20}.batch")),
+                    batch(value, 1_000 + offset).into(),
+                )
+                .await?;
+        }
+
+        let directory = root.path().join("segments/tansu/abc-0");
+
+        // every segment is full after its first batch
+        for base_offset in 0..3 {
+            for extension in [LOG, INDEX, TIME_INDEX] {
+                assert!(
+                    directory
+                        .join(format!("{base_offset:0>20}.{extension}"))
+                        .is_file()
+                );
+            }
+        }
+
+        let location = Path::from(format!("{LOCATION}/{:0>20}.batch", 1));
+        let fetched = segments.get(&location).await?.bytes().await?;
+        assert_eq!(
+            1,
+            deflated::Batch::try_from(fetched.clone())
+                .expect("batch")
+                .base_offset
+        );
+        assert_eq!(batch(b"pqr", 1_001).slice(8..), fetched.slice(8..));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn recovered_on_restart() -> Result<(), object_store::Error> {
+        let root = tempfile::tempdir().map_err(generic)?;
+
+        {
+            let segments = segments(root.path(), DEFAULT_SEGMENT_BYTES);
+
+            for offset in 0..3 {
+                _ = segments
+                    .put_opts(
+                        &Path::from(format!("{LOCATION}/{offset:0>20}.batch")),
+                        batch(b"abc", 1_000).into(),
+                        PutOptions {
+                            mode: PutMode::Create,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+            }
+        }
+
+        // a batch partially written before a restart
+        let log = root
+            .path()
+            .join(format!("segments/tansu/abc-0/{:0>20}.{LOG}", 0));
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .map_err(generic)?;
+        file.write_all(&batch(b"pqr", 1_000)[..20])
+            .map_err(generic)?;
+        drop(file);
+
+        let segments = segments(root.path(), DEFAULT_SEGMENT_BYTES);
+
+        let listed = segments
+            .list(Some(&Path::from(LOCATION)))
+            .map_ok(|meta| meta.location)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(
+            (0..3)
+                .map(|offset| Path::from(format!("{LOCATION}/{offset:0>20}.batch")))
+                .collect::<Vec<_>>(),
+            listed
+        );
+
+        assert_eq!(
+            3 * batch(b"abc", 1_000).len() as u64,
+            fs::metadata(&log).map_err(generic)?.len()
+        );
+
+        assert!(matches!(
+            segments
+                .put_opts(
+                    &Path::from(format!("{LOCATION}/{:0>20}.batch", 2)),
+                    batch(b"abc", 1_000).into(),
+                    PutOptions {
+                        mode: PutMode::Create,
+                        ..Default::default()
+                    },
+                )
+                .await,
+            Err(object_store::Error::AlreadyExists { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn segment_removed_once_empty() -> Result<(), object_store::Error> {
+        let root = tempfile::tempdir().map_err(generic)?;
+        let segments = segments(root.path(), DEFAULT_SEGMENT_BYTES);
+
+        let locations = (0..2)
+            .map(|offset| Path::from(format!("{LOCATION}/{offset:0>20}.batch")))
+            .collect::<Vec<_>>();
+
+        for location in &locations {
+            _ = segments.put(location, batch(b"abc", 1_000).into()).await?;
+        }
+
+        segments.delete(&locations[0]).await?;
+        assert!(segments.get(&locations[0]).await.is_err());
+        assert!(segments.get(&locations[1]).await.is_ok());
+        assert!(root.path().join("segments/tansu/abc-0").is_dir());
+
+        segments.delete(&locations[1]).await?;
+        assert!(!root.path().join("segments/tansu/abc-0").exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conditional_update() -> Result<(), object_store::Error> {
+        let root = tempfile::tempdir().map_err(generic)?;
+        let segments = segments(root.path(), DEFAULT_SEGMENT_BYTES);
+
+        let location = Path::from("clusters/tansu/meta.json");
+
+        let created = segments
+            .put_opts(
+                &location,
+                Bytes::from_static(b"{}").into(),
+                PutOptions {
+                    mode: PutMode::Create,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let update = PutOptions {
+            mode: PutMode::Update(UpdateVersion::from(created)),
+            ..Default::default()
+        };
+
+        _ = segments
+            .put_opts(
+                &location,
+                Bytes::from_static(b"{\"a\":1}").into(),
+                update.clone(),
+            )
+            .await?;
+
+        assert!(matches!(
+            segments
+                .put_opts(&location, Bytes::from_static(b"{\"a\":2}").into(), update)
+                .await,
+            Err(object_store::Error::Precondition { .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,459 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tiered object storage
+//!
+//! Record batches are written to a hot tier (local disk) for low latency
+//! fetches, and are asynchronously uploaded to a cold tier (S3). A batch is
+//! sealed once written, and remains in the hot tier for the local retention
+//! after which, once present in the cold tier, it is evicted from the hot
+//! tier. Batches are read from whichever tier holds them.
+//!
+//! Everything other than record batches (watermarks, offsets, metadata),
+//! depends on the conditional writes of the cold tier, and is not tiered.
+//!
+//! A batch not uploaded before a restart is uploaded once it reaches the
+//! local retention.
+
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Debug, Display},
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{
+    StreamExt, TryStreamExt,
+    stream::{self, BoxStream},
+};
+use object_store::{
+    CopyOptions, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    ObjectStoreExt, PutMultipartOptions, PutOptions, PutPayload, PutResult, path::Path,
+};
+use opentelemetry::{KeyValue, metrics::Counter};
+use tokio::{sync::mpsc, time};
+use tracing::{debug, instrument, warn};
+
+use crate::dynostore::object_store_error_name;
+
+use super::METER;
+
+/// Record batches are written to the hot tier
+const BATCH: &str = "batch";
+
+/// Record batches remain in the hot tier for an hour by default
+pub(crate) const DEFAULT_LOCAL_RETENTION: Duration = Duration::from_secs(3_600);
+
+/// The longest interval between sweeps of the hot tier
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+static UPLOADS: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_tiered_uploads")
+        .with_description("Record batches uploaded to the cold tier")
+        .build()
+});
+
+static EVICTIONS: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_tiered_evictions")
+        .with_description("Record batches evicted from the hot tier")
+        .build()
+});
+
+static READS: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_tiered_reads")
+        .with_description("Record batches read from each tier")
+        .build()
+});
+
+fn is_hot(location: &Path) -> bool {
+    location.extension() == Some(BATCH)
+}
+
+fn is_not_found(error: &object_store::Error) -> bool {
+    matches!(error, object_store::Error::NotFound { .. })
+}
+
+/// A hot tier with a cold tier, uploading record batches from hot to cold
+pub(crate) struct Tiered<H, C> {
+    hot: Arc<H>,
+    cold: Arc<C>,
+    uploads: mpsc::UnboundedSender<Path>,
+}
+
+impl<H, C> Debug for Tiered<H, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tiered").finish()
+    }
+}
+
+impl<H, C> Display for Tiered<H, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tiered").finish()
+    }
+}
+
+impl<H, C> Tiered<H, C>
+where
+    H: ObjectStore,
+    C: ObjectStore,
+{
+    /// Record batches remain in the hot tier for the local retention
+    /// (`local.retention.ms`) before being evicted
+    pub(crate) fn new(hot: H, cold: C, local_retention: Duration) -> Self {
+        let hot = Arc::new(hot);
+        let cold = Arc::new(cold);
+
+        let (uploads, pending) = mpsc::unbounded_channel();
+
+        _ = tokio::spawn(Uploader::new(hot.clone(), cold.clone(), local_retention).serve(pending));
+
+        Self { hot, cold, uploads }
+    }
+}
+
+/// Uploads record batches written to the hot tier, evicting those beyond
+/// the local retention
+#[derive(Debug)]
+struct Uploader<H, C> {
+    hot: Arc<H>,
+    cold: Arc<C>,
+    local_retention: Duration,
+}
+
+impl<H, C> Uploader<H, C>
+where
+    H: ObjectStore,
+    C: ObjectStore,
+{
+    fn new(hot: Arc<H>, cold: Arc<C>, local_retention: Duration) -> Self {
+        Self {
+            hot,
+            cold,
+            local_retention,
+        }
+    }
+
+    /// Upload each pending batch, until the tiered store is dropped
+    async fn serve(self, mut pending: mpsc::UnboundedReceiver<Path>) {
+        let mut interval = time::interval(
+            self.local_retention
+                .clamp(Duration::from_secs(1), SWEEP_INTERVAL),
+        );
+
+        loop {
+            tokio::select! {
+                location = pending.recv() => {
+                    let Some(location) = location else {
+                        break;
+                    };
+
+                    _ = self
+                        .upload(&location)
+                        .await
+                        .inspect_err(|err| warn!(%location, ?err));
+                }
+
+                _ = interval.tick() => {
+                    _ = self
+                        .sweep()
+                        .await
+                        .inspect(|evicted| debug!(evicted))
+                        .inspect_err(|err| warn!(?err));
+                }
+            }
+        }
+    }
+
+    #[instrument(skip(self), fields(%location))]
+    async fn upload(&self, location: &Path) -> Result<(), object_store::Error> {
+        let payload = match self.hot.get(location).await {
+            Ok(get_result) => get_result.bytes().await.map(PutPayload::from)?,
+
+            // deleted or evicted before being uploaded
+            Err(error) if is_not_found(&error) => return Ok(()),
+
+            Err(error) => return Err(error),
+        };
+
+        _ = self.cold.put(location, payload).await?;
+
+        UPLOADS.add(1, &[]);
+
+        Ok(())
+    }
+
+    /// Evict the batches in the hot tier beyond the local retention that are
+    /// present in the cold tier, uploading those that are not
+    #[instrument(skip(self))]
+    async fn sweep(&self) -> Result<u64, object_store::Error> {
+        let Some(cutoff) = SystemTime::now()
+            .checked_sub(self.local_retention)
+            .map(DateTime::<Utc>::from)
+        else {
+            return Ok(0);
+        };
+
+        let expired = self
+            .hot
+            .list(None)
+            .try_filter(|meta| futures::future::ready(meta.last_modified <= cutoff))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut evicted = 0;
+
+        for meta in expired {
+            match self.cold.head(&meta.location).await {
+                Ok(_) => {
+                    self.hot.delete(&meta.location).await?;
+
+                    EVICTIONS.add(1, &[]);
+                    evicted += 1;
+                }
+
+                Err(error) if is_not_found(&error) => self.upload(&meta.location).await?,
+
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(evicted)
+    }
+}
+
+#[async_trait]
+impl<H, C> ObjectStore for Tiered<H, C>
+where
+    H: ObjectStore,
+    C: ObjectStore,
+{
+    #[instrument(skip_all, fields(%location))]
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult, object_store::Error> {
+        if !is_hot(location) {
+            return self.cold.put_opts(location, payload, opts).await;
+        }
+
+        let put_result = self.hot.put_opts(location, payload, opts).await?;
+
+        if self.uploads.send(location.to_owned()).is_err() {
+            warn!(%location, "uploader stopped");
+        }
+
+        Ok(put_result)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOptions,
+    ) -> Result<Box<dyn MultipartUpload>, object_store::Error> {
+        self.cold.put_multipart_opts(location, opts).await
+    }
+
+    #[instrument(skip_all, fields(%location))]
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> Result<GetResult, object_store::Error> {
+        if !is_hot(location) {
+            return self.cold.get_opts(location, options).await;
+        }
+
+        match self.hot.get_opts(location, options.clone()).await {
+            Err(error) if is_not_found(&error) => {
+                READS.add(1, &[KeyValue::new("tier", "cold")]);
+                self.cold.get_opts(location, options).await
+            }
+
+            otherwise => {
+                READS.add(1, &[KeyValue::new("tier", "hot")]);
+                otherwise
+            }
+        }
+    }
+
+    fn delete_stream(
+        &self,
+        locations: BoxStream<'static, Result<Path, object_store::Error>>,
+    ) -> BoxStream<'static, Result<Path, object_store::Error>> {
+        let hot = self.hot.clone();
+        let cold = self.cold.clone();
+
+        locations
+            .and_then(move |location| {
+                let hot = hot.clone();
+                let cold = cold.clone();
+
+                async move {
+                    if is_hot(&location) {
+                        match hot.delete(&location).await {
+                            Err(error) if !is_not_found(&error) => {
+                                debug!(%location, error = object_store_error_name(&error));
+                                return Err(error);
+                            }
+
+                            _ => (),
+                        }
+                    }
+
+                    match cold.delete(&location).await {
+                        Err(error) if !is_not_found(&error) => Err(error),
+                        _ => Ok(location),
+                    }
+                }
+            })
+            .boxed()
+    }
+
+    fn list(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'static, Result<ObjectMeta, object_store::Error>> {
+        let seen = Arc::new(Mutex::new(BTreeSet::new()));
+
+        // a batch in both tiers is listed once
+        stream::iter([self.hot.list(prefix), self.cold.list(prefix)])
+            .flatten()
+            .try_filter(move |meta| {
+                futures::future::ready(
+                    seen.lock()
+                        .map(|mut seen| seen.insert(meta.location.clone()))
+                        .unwrap_or(true),
+                )
+            })
+            .boxed()
+    }
+
+    async fn list_with_delimiter(
+        &self,
+        prefix: Option<&Path>,
+    ) -> Result<ListResult, object_store::Error> {
+        let hot = self.hot.list_with_delimiter(prefix).await?;
+        let cold = self.cold.list_with_delimiter(prefix).await?;
+
+        let common_prefixes = hot
+            .common_prefixes
+            .into_iter()
+            .chain(cold.common_prefixes)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut seen = BTreeSet::new();
+
+        let objects = hot
+            .objects
+            .into_iter()
+            .chain(cold.objects)
+            .filter(|meta| seen.insert(meta.location.clone()))
+            .collect();
+
+        Ok(ListResult {
+            common_prefixes,
+            objects,
+        })
+    }
+
+    async fn copy_opts(
+        &self,
+        from: &Path,
+        to: &Path,
+        opts: CopyOptions,
+    ) -> Result<(), object_store::Error> {
+        self.cold.copy_opts(from, to, opts).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use tokio::time::sleep;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn batch_uploaded() -> Result<(), object_store::Error> {
+        let tiered = Tiered::new(InMemory::new(), InMemory::new(), Duration::from_secs(3_600));
+
+        let batch = Path::from("clusters/tansu/topics/abc/records/00000000000000000000.batch");
+        let meta = Path::from("clusters/tansu/meta.json");
+
+        _ = tiered
+            .put(&batch, Bytes::from_static(b"pqr").into())
+            .await?;
+        _ = tiered.put(&meta, Bytes::from_static(b"{}").into()).await?;
+
+        assert!(tiered.hot.head(&batch).await.is_ok());
+        assert!(tiered.hot.head(&meta).await.is_err());
+        assert!(tiered.cold.head(&meta).await.is_ok());
+
+        sleep(Duration::from_millis(100)).await;
+        assert!(tiered.cold.head(&batch).await.is_ok());
+
+        assert_eq!(2, tiered.list(None).try_collect::<Vec<_>>().await?.len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_from_cold() -> Result<(), object_store::Error> {
+        let tiered = Tiered::new(InMemory::new(), InMemory::new(), Duration::from_secs(3_600));
+
+        let batch = Path::from("clusters/tansu/topics/abc/records/00000000000000000000.batch");
+
+        _ = tiered
+            .cold
+            .put(&batch, Bytes::from_static(b"pqr").into())
+            .await?;
+
+        assert_eq!(
+            Bytes::from_static(b"pqr"),
+            tiered.get(&batch).await?.bytes().await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn evict_after_local_retention() -> Result<(), object_store::Error> {
+        let hot = Arc::new(InMemory::new());
+        let cold = Arc::new(InMemory::new());
+
+        let batch = Path::from("clusters/tansu/topics/abc/records/00000000000000000000.batch");
+        _ = hot.put(&batch, Bytes::from_static(b"pqr").into()).await?;
+
+        let uploader = Uploader::new(hot.clone(), cold.clone(), Duration::ZERO);
+
+        // not yet in the cold tier, so uploaded rather than evicted
+        assert_eq!(0, uploader.sweep().await?);
+        assert!(cold.head(&batch).await.is_ok());
+
+        assert_eq!(1, uploader.sweep().await?);
+        assert!(hot.head(&batch).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage invariants
+//!
+//! In debug builds (with `debug_assertions`) the [`StorageContainer`](crate::StorageContainer)
+//! checks these invariants around each produce, replicate and fetch, panicking
+//! on the first violation, so that a storage engine corrupting a topition is
+//! caught where it happens rather than by a confused consumer much later:
+//!
+//! - the CRC of each produced, replicated or fetched batch matches its content
+//! - a produced batch is assigned an offset at or after the high watermark
+//!   observed before it was produced, with the high watermark afterwards
+//!   beyond its last record
+//! - the log start offset is at or before the last stable offset, which is at
+//!   or before the high watermark
+//! - fetched batches are in ascending offset order
+//!
+//! Release builds check nothing.
+
+use std::fmt;
+
+use tansu_sans_io::record::deflated::Batch;
+use tracing::error;
+
+use crate::{OffsetStage, Storage, Topition};
+
+/// A broken invariant
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Violation {
+    Crc {
+        base_offset: i64,
+        crc: u32,
+        computed: Option<u32>,
+    },
+
+    Offset {
+        offset: i64,
+        high_watermark: i64,
+    },
+
+    HighWatermark {
+        last_offset: i64,
+        high_watermark: i64,
+    },
+
+    Stage {
+        log_start: i64,
+        last_stable: i64,
+        high_watermark: i64,
+    },
+
+    Order {
+        previous: i64,
+        base_offset: i64,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crc {
+                base_offset,
+                crc,
+                computed,
+            } => write!(
+                f,
+                "batch at {base_offset} has crc: {crc}, computed: {computed:?}"
+            ),
+
+            Self::Offset {
+                offset,
+                high_watermark,
+            } => write!(
+                f,
+                "produced at {offset}, before the high watermark: {high_watermark}"
+            ),
+
+            Self::HighWatermark {
+                last_offset,
+                high_watermark,
+            } => write!(
+                f,
+                "produced up to {last_offset}, beyond the high watermark: {high_watermark}"
+            ),
+
+            Self::Stage {
+                log_start,
+                last_stable,
+                high_watermark,
+            } => write!(
+                f,
+                "log start: {log_start}, last stable: {last_stable}, high watermark: {high_watermark}"
+            ),
+
+            Self::Order {
+                previous,
+                base_offset,
+            } => write!(f, "batch at {base_offset} fetched after {previous}"),
+        }
+    }
+}
+
+fn crc(batch: &Batch) -> Result<(), Violation> {
+    let computed = batch.computed_crc().ok();
+
+    if computed == Some(batch.crc) {
+        Ok(())
+    } else {
+        Err(Violation::Crc {
+            base_offset: batch.base_offset,
+            crc: batch.crc,
+            computed,
+        })
+    }
+}
+
+fn stage(stage: &OffsetStage) -> Result<(), Violation> {
+    if stage.log_start <= stage.last_stable && stage.last_stable <= stage.high_watermark {
+        Ok(())
+    } else {
+        Err(Violation::Stage {
+            log_start: stage.log_start,
+            last_stable: stage.last_stable,
+            high_watermark: stage.high_watermark,
+        })
+    }
+}
+
+fn produced(
+    before: &OffsetStage,
+    offset: i64,
+    record_count: u32,
+    after: &OffsetStage,
+) -> Result<(), Violation> {
+    if offset < before.high_watermark {
+        return Err(Violation::Offset {
+            offset,
+            high_watermark: before.high_watermark,
+        });
+    }
+
+    let last_offset = offset + i64::from(record_count) - 1;
+
+    if after.high_watermark <= last_offset {
+        return Err(Violation::HighWatermark {
+            last_offset,
+            high_watermark: after.high_watermark,
+        });
+    }
+
+    stage(after)
+}
+
+fn fetched(batches: &[Batch]) -> Result<(), Violation> {
+    let mut previous = None;
+
+    for batch in batches.iter().filter(|batch| batch.record_count > 0) {
+        crc(batch)?;
+
+        if let Some(previous) = previous.filter(|previous| batch.base_offset <= *previous) {
+            return Err(Violation::Order {
+                previous,
+                base_offset: batch.base_offset,
+            });
+        }
+
+        previous = Some(batch.base_offset);
+    }
+
+    Ok(())
+}
+
+fn enforce(topition: &Topition, checked: Result<(), Violation>) {
+    if let Err(violation) = checked {
+        error!(?topition, %violation);
+        panic!("storage invariant violated on {topition:?}: {violation}");
+    }
+}
+
+/// The state of a topition before a batch is produced to it
+#[derive(Clone, Debug)]
+pub(crate) struct Produce {
+    record_count: u32,
+    before: Option<OffsetStage>,
+}
+
+impl Produce {
+    pub(crate) async fn before<S>(storage: &S, topition: &Topition, batch: &Batch) -> Self
+    where
+        S: Storage,
+    {
+        enforce(topition, crc(batch));
+
+        let before = storage.offset_stage(topition).await.ok();
+
+        if let Some(ref before) = before {
+            enforce(topition, stage(before));
+        }
+
+        Self {
+            record_count: batch.record_count,
+            before,
+        }
+    }
+
+    pub(crate) async fn after<S>(self, storage: &S, topition: &Topition, offset: i64)
+    where
+        S: Storage,
+    {
+        if let (Some(before), Ok(after)) = (self.before, storage.offset_stage(topition).await) {
+            enforce(
+                topition,
+                produced(&before, offset, self.record_count, &after),
+            );
+        }
+    }
+}
+
+pub(crate) async fn replicated<S>(storage: &S, topition: &Topition, batch: &Batch)
+where
+    S: Storage,
+{
+    enforce(topition, crc(batch));
+
+    if let Ok(after) = storage.offset_stage(topition).await {
+        enforce(topition, stage(&after));
+    }
+}
+
+pub(crate) fn fetch(topition: &Topition, batches: &[Batch]) {
+    enforce(topition, fetched(batches));
+}
+
+#[cfg(test)]
+mod tests {
+    use tansu_sans_io::record::{Record, inflated};
+
+    use super::*;
+
+    fn offset_stage(log_start: i64, last_stable: i64, high_watermark: i64) -> OffsetStage {
+        OffsetStage {
+            last_stable,
+            high_watermark,
+            log_start,
+        }
+    }
+
+    fn batch(base_offset: i64) -> Batch {
+        inflated::Batch::builder()
+            .base_offset(base_offset)
+            .record(Record::builder().value(Some(bytes::Bytes::from_static(b"pqr"))))
+            .build()
+            .and_then(Batch::try_from)
+            .unwrap()
+    }
+
+    #[test]
+    fn produce() {
+        let before = offset_stage(0, 5, 5);
+
+        assert!(produced(&before, 5, 3, &offset_stage(0, 8, 8)).is_ok());
+
+        assert_eq!(
+            Err(Violation::Offset {
+                offset: 4,
+                high_watermark: 5
+            }),
+            produced(&before, 4, 3, &offset_stage(0, 8, 8))
+        );
+
+        assert_eq!(
+            Err(Violation::HighWatermark {
+                last_offset: 7,
+                high_watermark: 7
+            }),
+            produced(&before, 5, 3, &offset_stage(0, 7, 7))
+        );
+
+        assert!(stage(&offset_stage(0, 8, 8)).is_ok());
+        assert!(stage(&offset_stage(9, 8, 8)).is_err());
+        assert!(stage(&offset_stage(0, 9, 8)).is_err());
+    }
+
+    #[test]
+    fn batch_crc() {
+        let mut batch = batch(0);
+        assert!(crc(&batch).is_ok());
+
+        batch.crc = batch.crc.wrapping_add(1);
+        assert!(matches!(crc(&batch), Err(Violation::Crc { .. })));
+    }
+
+    #[test]
+    fn fetch_order() {
+        assert!(fetched(&[batch(0), batch(1)]).is_ok());
+
+        assert_eq!(
+            Err(Violation::Order {
+                previous: 1,
+                base_offset: 0
+            }),
+            fetched(&[batch(1), batch(0)])
+        );
+    }
+}
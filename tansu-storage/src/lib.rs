@@ -56,6 +56,48 @@
 //! # }
 //! ```
 //!
+//! ## Local Filesystem
+//!
+//! Record batches are appended to Kafka style segments (a log, offset index
+//! and time index) on local disk, rolled at `segment.bytes`:
+//!
+//! ```no_run
+//! # use tansu_storage::{Error, StorageContainer};
+//! # use url::Url;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! let storage = StorageContainer::builder()
+//!     .cluster_id("tansu")
+//!     .node_id(111)
+//!     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+//!     .storage(Url::parse("file:///var/lib/tansu?segment.bytes=104857600")?)
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## DynamoDB
+//!
+//! With the `dynamodb` feature, each object is held as an item in a DynamoDB
+//! table, using conditional writes on the watermarks:
+//!
+//! ```no_run
+//! # use tansu_storage::{Error, StorageContainer};
+//! # use url::Url;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! let storage = StorageContainer::builder()
+//!     .cluster_id("tansu")
+//!     .node_id(111)
+//!     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+//!     .storage(Url::parse("dynamodb://tansu")?)
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## PostgreSQL
 //!
 //! ```no_run
@@ -112,21 +154,28 @@
 //!
 
 use async_trait::async_trait;
-use bytes::{Bytes, TryGetError};
+use bytes::{BufMut as _, Bytes, BytesMut, TryGetError};
 
 #[cfg(any(feature = "libsql", feature = "postgres"))]
 use deadpool::managed::PoolError;
 #[cfg(feature = "dynostore")]
-use dynostore::DynoStore;
+use dynostore::{DEFAULT_LOCAL_RETENTION, DEFAULT_SEGMENT_BYTES, DynoStore, Segments, Tiered};
+
+#[cfg(feature = "dynamodb")]
+use dynostore::DynamoDb;
 
 use glob::{GlobError, PatternError};
+use hmac::{Hmac, Mac as _};
 
 #[cfg(feature = "dynostore")]
-use object_store::memory::InMemory;
+use object_store::{local::LocalFileSystem, memory::InMemory};
 
 #[cfg(feature = "dynostore")]
 use object_store::aws::{AmazonS3Builder, S3ConditionalPut};
 
+#[cfg(feature = "dynostore")]
+use object_store::{azure::MicrosoftAzureBuilder, gcp::GoogleCloudStorageBuilder};
+
 use opentelemetry::{
     InstrumentationScope, KeyValue, global,
     metrics::{Counter, Meter},
@@ -138,11 +187,12 @@ use pg::Postgres;
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256, Sha512};
 #[cfg(any(feature = "libsql", feature = "postgres"))]
 use std::error;
 use std::{
     array::TryFromSliceError,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     ffi::OsString,
     fmt::{self, Debug, Display, Formatter},
     fs::DirEntry,
@@ -156,7 +206,8 @@ use std::{
     time::{Duration, SystemTime, SystemTimeError},
 };
 use tansu_sans_io::{
-    Body, ConfigResource, ErrorCode, IsolationLevel, ListOffset, NULL_TOPIC_ID,
+    AclOperation, AclPatternType, AclPermissionType, AclResourceType, Body, ConfigResource,
+    ErrorCode, IsolationLevel, ListOffset, NULL_TOPIC_ID,
     add_partitions_to_txn_request::{
         AddPartitionsToTxnRequest, AddPartitionsToTxnTopic, AddPartitionsToTxnTransaction,
     },
@@ -173,6 +224,7 @@ use tansu_sans_io::{
     describe_topic_partitions_request::{Cursor, TopicRequest},
     describe_topic_partitions_response::DescribeTopicPartitionsResponseTopic,
     fetch_request::FetchTopic,
+    fetch_response::AbortedTransaction,
     incremental_alter_configs_request::AlterConfigsResource,
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     join_group_response::JoinGroupResponseMember,
@@ -180,14 +232,14 @@ use tansu_sans_io::{
     metadata_request::MetadataRequestTopic,
     metadata_response::{MetadataResponseBroker, MetadataResponseTopic},
     offset_commit_request::OffsetCommitRequestPartition,
-    record::deflated,
+    record::{deflated, inflated},
     to_system_time, to_timestamp,
     txn_offset_commit_request::TxnOffsetCommitRequestTopic,
     txn_offset_commit_response::TxnOffsetCommitResponseTopic,
 };
 use tansu_schema::{Registry, lake::House};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 use tracing_subscriber::filter::ParseError;
 use url::Url;
 use uuid::Uuid;
@@ -195,29 +247,54 @@ use uuid::Uuid;
 #[cfg(feature = "dynostore")]
 mod dynostore;
 
+mod config;
+pub mod consumer_offsets;
+
+#[cfg(debug_assertions)]
+mod invariant;
+
 mod null;
 
 #[cfg(feature = "postgres")]
 mod pg;
 
+mod produced;
 mod proxy;
+pub mod purgatory;
+mod routed;
 mod service;
 
 pub use service::{
-    ChannelRequestLayer, ChannelRequestService, ConsumerGroupDescribeService, CreateTopicsService,
-    DeleteGroupsService, DeleteRecordsService, DeleteTopicsService, DescribeClusterService,
-    DescribeConfigsService, DescribeGroupsService, DescribeTopicPartitionsService, FetchService,
-    FindCoordinatorService, GetTelemetrySubscriptionsService, IncrementalAlterConfigsService,
-    InitProducerIdService, ListGroupsService, ListOffsetsService,
-    ListPartitionReassignmentsService, MetadataService, ProduceService, Request,
-    RequestChannelService, RequestLayer, RequestReceiver, RequestSender, RequestService,
-    RequestStorageService, Response, TxnAddOffsetsService, TxnAddPartitionService,
-    TxnOffsetCommitService, bounded_channel,
+    AlterClientQuotasService, AlterUserScramCredentialsService, ChannelRequestLayer,
+    ChannelRequestService, ConsumerGroupDescribeService, CreateAclsService,
+    CreatePartitionsService, CreateTopicsService, DeleteAclsService, DeleteGroupsService,
+    DeleteRecordsService, DeleteTopicsService, DescribeAclsService, DescribeClientQuotasService,
+    DescribeClusterService, DescribeConfigsService, DescribeGroupsService, DescribeLogDirsService,
+    DescribeQuorumService, DescribeTopicPartitionsService, DescribeTransactionsService,
+    DescribeUserScramCredentialsService, ElectLeadersService, FetchService, FindCoordinatorService,
+    GetTelemetrySubscriptionsService, IncrementalAlterConfigsService, InitProducerIdService,
+    ListGroupsService, ListOffsetsService, ListPartitionReassignmentsService,
+    ListTransactionsService, MetadataService, ProduceService, Request, RequestChannelService,
+    RequestLayer, RequestReceiver, RequestSender, RequestService, RequestStorageService, Response,
+    TxnAddOffsetsService, TxnAddPartitionService, TxnOffsetCommitService, UpdateFeaturesService,
+    bounded_channel,
 };
 
 #[cfg(feature = "slatedb")]
 pub mod slate;
 
+mod secret;
+mod tag;
+
+pub use routed::{Routed, STORAGE_TIER};
+pub use secret::Secret;
+pub use tag::Tag;
+
+pub use config::{
+    ConfigOrigin, ConfigSynonym, InheritedConfig, InvalidConfig, TopicConfig, inheritance,
+    is_sensitive, validate_topic_config,
+};
+
 #[cfg(any(feature = "libsql", feature = "postgres", feature = "turso"))]
 pub(crate) mod sql;
 
@@ -248,6 +325,7 @@ pub enum Error {
     },
 
     Glob(Arc<GlobError>),
+    InvalidKeyLength(#[from] hmac::digest::InvalidLength),
     Io(Arc<io::Error>),
     KafkaSansIo(#[from] tansu_sans_io::Error),
     LessThanBaseOffset {
@@ -328,6 +406,11 @@ pub enum Error {
 
     UnknownCacheKey(String),
 
+    UnresolvedSecret(String),
+
+    /// An operation that this storage engine does not support
+    Unsupported(&'static str),
+
     UnsupportedStorageUrl(Url),
     UnexpectedAddPartitionsToTxnRequest(Box<AddPartitionsToTxnRequest>),
     Url(#[from] url::ParseError),
@@ -826,6 +909,235 @@ pub struct GroupMember {
     pub last_contact: Option<SystemTime>,
 }
 
+/// The partitions of each topic (by name) assigned to a consumer group member
+pub type ConsumerGroupAssignment = BTreeMap<String, BTreeSet<i32>>;
+
+/// Consumer Group Member
+///
+/// A member of a group using the consumer rebalance protocol (KIP-848).
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ConsumerGroupMember {
+    pub member_epoch: i32,
+    pub instance_id: Option<String>,
+    pub rack_id: Option<String>,
+    pub client_id: String,
+    pub client_host: String,
+    pub rebalance_timeout_ms: i32,
+    pub subscribed_topic_names: Vec<String>,
+    pub server_assignor: Option<String>,
+    /// The partitions this member has been told to own
+    pub assignment: ConsumerGroupAssignment,
+    /// The partitions this member has been told to release, that it has yet to
+    /// confirm as released
+    pub revoking: ConsumerGroupAssignment,
+    pub target_assignment: ConsumerGroupAssignment,
+    pub last_contact: Option<SystemTime>,
+}
+
+impl ConsumerGroupMember {
+    /// Whether this member owns its target assignment
+    pub fn is_reconciled(&self) -> bool {
+        self.revoking.is_empty() && self.assignment == self.target_assignment
+    }
+
+    /// The subscribed topics of this member encoded as a (version 0)
+    /// `ConsumerProtocolSubscription`
+    pub fn subscription_metadata(&self) -> Bytes {
+        let mut encoded = BytesMut::new();
+
+        encoded.put_i16(0);
+        encoded.put_i32(self.subscribed_topic_names.len() as i32);
+
+        for topic in &self.subscribed_topic_names {
+            put_string(&mut encoded, topic);
+        }
+
+        encoded.put_i32(-1);
+        encoded.freeze()
+    }
+
+    /// The assignment of this member encoded as a (version 0)
+    /// `ConsumerProtocolAssignment`
+    pub fn assignment_metadata(&self) -> Bytes {
+        let mut encoded = BytesMut::new();
+
+        encoded.put_i16(0);
+        encoded.put_i32(self.assignment.len() as i32);
+
+        for (topic, partitions) in &self.assignment {
+            put_string(&mut encoded, topic);
+            encoded.put_i32(partitions.len() as i32);
+
+            for partition in partitions {
+                encoded.put_i32(*partition);
+            }
+        }
+
+        encoded.put_i32(-1);
+        encoded.freeze()
+    }
+}
+
+fn put_string(encoded: &mut BytesMut, s: &str) {
+    encoded.put_i16(s.len() as i16);
+    encoded.put(s.as_bytes());
+}
+
+/// Consumer Group
+///
+/// The state of a group using the consumer rebalance protocol (KIP-848), with
+/// the target assignment computed by the coordinator whenever the group epoch
+/// is advanced by a change in membership or subscription.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ConsumerGroup {
+    pub group_epoch: i32,
+    pub assignment_epoch: i32,
+    pub assignor_name: String,
+    /// The id of each subscribed topic
+    pub topics: BTreeMap<String, Uuid>,
+    pub members: BTreeMap<String, ConsumerGroupMember>,
+}
+
+impl From<&ConsumerGroup> for ConsumerGroupState {
+    fn from(value: &ConsumerGroup) -> Self {
+        if value.members.is_empty() {
+            Self::Empty
+        } else if value.group_epoch > value.assignment_epoch {
+            Self::Assigning
+        } else if value
+            .members
+            .values()
+            .any(|member| !member.is_reconciled() || member.member_epoch != value.assignment_epoch)
+        {
+            Self::Reconciling
+        } else {
+            Self::Stable
+        }
+    }
+}
+
+impl ConsumerGroup {
+    fn described_assignment(
+        &self,
+        assignment: &ConsumerGroupAssignment,
+    ) -> consumer_group_describe_response::Assignment {
+        consumer_group_describe_response::Assignment::default().topic_partitions(Some(
+            assignment
+                .iter()
+                .map(|(topic, partitions)| {
+                    consumer_group_describe_response::TopicPartitions::default()
+                        .topic_id(
+                            self.topics
+                                .get(topic)
+                                .copied()
+                                .unwrap_or_default()
+                                .into_bytes(),
+                        )
+                        .topic_name(topic.into())
+                        .partitions(Some(partitions.iter().copied().collect()))
+                })
+                .collect(),
+        ))
+    }
+
+    fn described_members(&self) -> Vec<consumer_group_describe_response::Member> {
+        self.members
+            .iter()
+            .map(|(member_id, member)| {
+                consumer_group_describe_response::Member::default()
+                    .member_id(member_id.into())
+                    .instance_id(member.instance_id.clone())
+                    .rack_id(member.rack_id.clone())
+                    .member_epoch(member.member_epoch)
+                    .client_id(member.client_id.clone())
+                    .client_host(member.client_host.clone())
+                    .subscribed_topic_names(Some(member.subscribed_topic_names.clone()))
+                    .subscribed_topic_regex(None)
+                    .assignment(self.described_assignment(&member.assignment))
+                    .target_assignment(self.described_assignment(&member.target_assignment))
+            })
+            .collect()
+    }
+}
+
+/// The delivery state of an in-flight record of a share group (KIP-932)
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum ShareDeliveryState {
+    /// Available for delivery to a member of the share group
+    #[default]
+    Available,
+    /// Delivered to a member, locked until acknowledged, released or the lock expires
+    Acquired,
+    /// Processed successfully by a member
+    Acknowledged,
+    /// No longer available for delivery, being rejected or delivered too many times
+    Archived,
+}
+
+/// An in-flight record of a share group
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ShareRecord {
+    pub state: ShareDeliveryState,
+    pub delivery_count: i16,
+    /// The member holding the acquisition lock on this record
+    pub member_id: Option<String>,
+    pub lock_expiry: Option<SystemTime>,
+}
+
+/// Share Partition
+///
+/// The in-flight records of a topic partition consumed by a share group. Records
+/// before the start offset have all been acknowledged or archived, and records
+/// from the end offset have yet to be delivered.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SharePartition {
+    pub start_offset: i64,
+    pub end_offset: i64,
+    pub records: BTreeMap<i64, ShareRecord>,
+}
+
+/// Share Group Member
+///
+/// A member of a share group (KIP-932).
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ShareGroupMember {
+    pub member_epoch: i32,
+    pub rack_id: Option<String>,
+    pub client_id: String,
+    pub client_host: String,
+    pub subscribed_topic_names: Vec<String>,
+    pub assignment: ConsumerGroupAssignment,
+    pub last_contact: Option<SystemTime>,
+}
+
+/// Share Group
+///
+/// The state of a share group (KIP-932), with members cooperatively consuming
+/// records that are acquired, and then acknowledged, one at a time rather
+/// than by committing an offset. A partition may be assigned to more than one
+/// member.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ShareGroup {
+    pub group_epoch: i32,
+    /// The id of each subscribed topic
+    pub topics: BTreeMap<String, Uuid>,
+    pub members: BTreeMap<String, ShareGroupMember>,
+    /// The in-flight records of each partition of a topic (by name)
+    pub partitions: BTreeMap<String, BTreeMap<i32, SharePartition>>,
+}
+
+impl From<&ShareGroup> for ConsumerGroupState {
+    fn from(value: &ShareGroup) -> Self {
+        if value.members.is_empty() {
+            Self::Empty
+        } else {
+            Self::Stable
+        }
+    }
+}
+
 /// Group State
 ///
 /// A group is either in the process of [`GroupState::Forming`] or has [`GroupState::Formed`].
@@ -927,6 +1239,10 @@ pub struct GroupDetail {
     pub skip_assignment: Option<bool>,
     pub inception: SystemTime,
     pub state: GroupState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consumer: Option<ConsumerGroup>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share: Option<ShareGroup>,
 }
 
 impl Default for GroupDetail {
@@ -939,6 +1255,8 @@ impl Default for GroupDetail {
             skip_assignment: Some(false),
             inception: SystemTime::now(),
             state: GroupState::default(),
+            consumer: None,
+            share: None,
         }
     }
 }
@@ -946,6 +1264,15 @@ impl Default for GroupDetail {
 impl From<&GroupDetail> for ConsumerGroupState {
     fn from(value: &GroupDetail) -> Self {
         match value {
+            GroupDetail {
+                consumer: Some(consumer),
+                ..
+            } => Self::from(consumer),
+
+            GroupDetail {
+                share: Some(share), ..
+            } => Self::from(share),
+
             GroupDetail { members, .. } if members.is_empty() => Self::Empty,
 
             GroupDetail {
@@ -1023,6 +1350,24 @@ impl NamedGroupDetail {
 impl From<&NamedGroupDetail> for consumer_group_describe_response::DescribedGroup {
     fn from(value: &NamedGroupDetail) -> Self {
         match value {
+            NamedGroupDetail {
+                name,
+                response:
+                    GroupDetailResponse::Found(GroupDetail {
+                        consumer: Some(consumer),
+                        ..
+                    }),
+            } => Self::default()
+                .error_code(ErrorCode::None.into())
+                .error_message(Some(ErrorCode::None.to_string()))
+                .group_id(name.into())
+                .group_state(ConsumerGroupState::from(consumer).to_string())
+                .group_epoch(consumer.group_epoch)
+                .assignment_epoch(consumer.assignment_epoch)
+                .assignor_name(consumer.assignor_name.clone())
+                .members(Some(consumer.described_members()))
+                .authorized_operations(-1),
+
             NamedGroupDetail {
                 name,
                 response: GroupDetailResponse::Found(group_detail),
@@ -1066,23 +1411,59 @@ impl From<&NamedGroupDetail> for consumer_group_describe_response::DescribedGrou
 impl From<&NamedGroupDetail> for describe_groups_response::DescribedGroup {
     fn from(value: &NamedGroupDetail) -> Self {
         match value {
+            NamedGroupDetail {
+                name,
+                response:
+                    GroupDetailResponse::Found(GroupDetail {
+                        consumer: Some(consumer),
+                        ..
+                    }),
+            } => {
+                let members = consumer
+                    .members
+                    .iter()
+                    .map(|(member_id, member)| {
+                        describe_groups_response::DescribedGroupMember::default()
+                            .member_id(member_id.into())
+                            .group_instance_id(member.instance_id.clone())
+                            .client_id(member.client_id.clone())
+                            .client_host(member.client_host.clone())
+                            .member_metadata(member.subscription_metadata())
+                            .member_assignment(member.assignment_metadata())
+                    })
+                    .collect::<Vec<_>>();
+
+                Self::default()
+                    .error_code(ErrorCode::None.into())
+                    .group_id(name.clone())
+                    .group_state(ConsumerGroupState::from(consumer).to_string())
+                    .protocol_type("consumer".into())
+                    .protocol_data(consumer.assignor_name.clone())
+                    .members(Some(members))
+                    .authorized_operations(Some(-1))
+            }
+
             NamedGroupDetail {
                 name,
                 response: GroupDetailResponse::Found(group_detail),
             } => {
                 let group_state = ConsumerGroupState::from(group_detail).to_string();
 
+                let assignments = group_detail.state.assignments();
+
                 let members = group_detail
                     .members
-                    .keys()
-                    .map(|member_id| {
+                    .iter()
+                    .map(|(member_id, member)| {
                         describe_groups_response::DescribedGroupMember::default()
                             .member_id(member_id.into())
-                            .group_instance_id(None)
+                            .group_instance_id(member.join_response.group_instance_id.clone())
                             .client_id("".into())
                             .client_host("".into())
-                            .member_metadata(Bytes::new())
-                            .member_assignment(Bytes::new())
+                            .member_metadata(member.join_response.metadata.clone())
+                            .member_assignment(
+                                assignments.get(member_id).cloned().unwrap_or_default(),
+                            )
                     })
                     .collect::<Vec<_>>();
 
@@ -1091,7 +1472,7 @@ impl From<&NamedGroupDetail> for describe_groups_response::DescribedGroup {
                     .group_id(name.clone())
                     .group_state(group_state)
                     .protocol_type(group_detail.state.protocol_type().unwrap_or_default())
-                    .protocol_data("".into())
+                    .protocol_data(group_detail.state.protocol_name().unwrap_or_default())
                     .members(Some(members))
                     .authorized_operations(Some(-1))
             }
@@ -1150,6 +1531,16 @@ pub struct ProducerIdResponse {
     pub epoch: i16,
 }
 
+/// The sequence following a batch of records from a sequence, wrapping to
+/// zero after `i32::MAX` as the sequences of an idempotent producer do
+pub(crate) fn next_sequence(sequence: i32, records: i32) -> i32 {
+    if sequence > i32::MAX - records {
+        records - (i32::MAX - sequence) - 1
+    } else {
+        sequence + records
+    }
+}
+
 impl Default for ProducerIdResponse {
     fn default() -> Self {
         Self {
@@ -1303,99 +1694,704 @@ impl From<TxnState> for String {
     }
 }
 
-/// Storage
+/// Transaction Description
 ///
-/// The Core storage abstraction. All storage engines implement this type.
-#[async_trait]
-pub trait Storage: Clone + Debug + Send + Sync + 'static {
-    /// On startup a broker will register with storage.
-    async fn register_broker(&self, broker_registration: BrokerRegistrationRequest) -> Result<()>;
+/// The current state of a transactional id as known by its coordinator, with
+/// the topitions added to an ongoing transaction.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct TxnDescription {
+    pub transactional_id: String,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub state: Option<TxnState>,
+    pub timeout_ms: i32,
+    pub started_at: Option<SystemTime>,
+    pub topitions: Vec<Topition>,
+}
 
-    /// Create a topic on this storage.
-    async fn create_topic(&self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid>;
+impl TxnDescription {
+    /// Transaction states as named by the Kafka protocol
+    pub const STATES: [&str; 6] = [
+        "Empty",
+        "Ongoing",
+        "PrepareCommit",
+        "PrepareAbort",
+        "CompleteCommit",
+        "CompleteAbort",
+    ];
+
+    /// The state of this transaction as named by the Kafka protocol
+    pub fn transaction_state(&self) -> &'static str {
+        match self.state {
+            None => "Empty",
+            Some(TxnState::Begin) => "Ongoing",
+            Some(TxnState::PrepareCommit) => "PrepareCommit",
+            Some(TxnState::PrepareAbort) => "PrepareAbort",
+            Some(TxnState::Committed) => "CompleteCommit",
+            Some(TxnState::Aborted) => "CompleteAbort",
+        }
+    }
+}
 
-    /// Incrementally alter a resource on this storage.
-    async fn incremental_alter_resource(
-        &self,
-        resource: AlterConfigsResource,
-    ) -> Result<AlterConfigsResourceResponse>;
+/// SCRAM Mechanism
+///
+/// With the Kafka mechanism type of `1` for `SCRAM-SHA-256` and `2` for `SCRAM-SHA-512`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ScramMechanism {
+    Sha256,
+    Sha512,
+}
 
-    /// Delete records on this storage.
-    async fn delete_records(
-        &self,
-        topics: &[DeleteRecordsTopic],
-    ) -> Result<Vec<DeleteRecordsTopicResult>>;
+impl ScramMechanism {
+    pub const MIN_ITERATIONS: i32 = 4_096;
+    pub const MAX_ITERATIONS: i32 = 16_384;
 
-    /// Delete a topic from this storage.
-    async fn delete_topic(&self, topic: &TopicId) -> Result<ErrorCode>;
+    /// The SASL mechanism name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "SCRAM-SHA-256",
+            Self::Sha512 => "SCRAM-SHA-512",
+        }
+    }
 
-    /// Query the brokers registered with this storage.
-    async fn brokers(&self) -> Result<Vec<DescribeClusterBroker>>;
+    pub fn hash(&self, data: &[u8]) -> Bytes {
+        match self {
+            Self::Sha256 => Bytes::from(Sha256::digest(data).to_vec()),
+            Self::Sha512 => Bytes::from(Sha512::digest(data).to_vec()),
+        }
+    }
 
-    /// Produce a deflated batch to this storage.
-    async fn produce(
-        &self,
-        transaction_id: Option<&str>,
-        topition: &Topition,
-        batch: deflated::Batch,
-    ) -> Result<i64>;
+    pub fn hmac(&self, key: &[u8], data: &[u8]) -> Result<Bytes> {
+        match self {
+            Self::Sha256 => Hmac::<Sha256>::new_from_slice(key).map(|mut mac| {
+                mac.update(data);
+                Bytes::from(mac.finalize().into_bytes().to_vec())
+            }),
 
-    /// Fetch deflated batches from storage.
-    async fn fetch(
-        &self,
-        topition: &'_ Topition,
-        offset: i64,
-        min_bytes: u32,
-        max_bytes: u32,
-        isolation: IsolationLevel,
-    ) -> Result<Vec<deflated::Batch>>;
+            Self::Sha512 => Hmac::<Sha512>::new_from_slice(key).map(|mut mac| {
+                mac.update(data);
+                Bytes::from(mac.finalize().into_bytes().to_vec())
+            }),
+        }
+        .map_err(Into::into)
+    }
 
-    /// Query the offset stage for a topic partition.
-    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage>;
+    /// The `Hi(password, salt, iterations)` salted password of RFC 5802
+    pub fn salted_password(&self, password: &[u8], salt: &[u8], iterations: i32) -> Result<Bytes> {
+        let mut u = self.hmac(password, &[salt, &1u32.to_be_bytes()[..]].concat())?;
+        let mut salted = u.to_vec();
 
-    /// Query the offsets for one or more topic partitions.
-    async fn list_offsets(
-        &self,
-        isolation_level: IsolationLevel,
-        offsets: &[(Topition, ListOffset)],
-    ) -> Result<Vec<(Topition, ListOffsetResponse)>>;
+        for _ in 1..iterations {
+            u = self.hmac(password, &u[..])?;
 
-    /// Commit offsets for one or more topic partitions in a consumer group.
-    async fn offset_commit(
-        &self,
-        group_id: &str,
-        retention_time_ms: Option<Duration>,
-        offsets: &[(Topition, OffsetCommitRequest)],
-    ) -> Result<Vec<(Topition, ErrorCode)>>;
+            salted
+                .iter_mut()
+                .zip(u.iter())
+                .for_each(|(salted, u)| *salted ^= u);
+        }
 
-    /// Fetch committed offsets for one or more topic partitions in a consumer group.
-    async fn offset_fetch(
+        Ok(Bytes::from(salted))
+    }
+
+    /// The credential stored by the server for a salted password
+    pub fn credential(
         &self,
-        group_id: Option<&str>,
-        topics: &[Topition],
-        require_stable: Option<bool>,
-    ) -> Result<BTreeMap<Topition, i64>>;
+        salt: Bytes,
+        iterations: i32,
+        salted_password: &[u8],
+    ) -> Result<ScramCredential> {
+        let client_key = self.hmac(salted_password, b"Client Key")?;
+        let server_key = self.hmac(salted_password, b"Server Key")?;
+
+        Ok(ScramCredential {
+            salt,
+            iterations,
+            stored_key: self.hash(&client_key[..]),
+            server_key,
+        })
+    }
+}
 
-    /// Fetch all committed offsets in a consumer group.
-    async fn committed_offset_topitions(&self, group_id: &str) -> Result<BTreeMap<Topition, i64>>;
+impl TryFrom<i8> for ScramMechanism {
+    type Error = Error;
 
-    /// Query broker and topic metadata.
-    async fn metadata(&self, topics: Option<&[TopicId]>) -> Result<MetadataResponse>;
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Sha256),
+            2 => Ok(Self::Sha512),
+            _otherwise => Err(Error::Api(ErrorCode::UnsupportedSaslMechanism)),
+        }
+    }
+}
 
-    /// Query the configuration of a resource in this storage.
-    async fn describe_config(
-        &self,
-        name: &str,
-        resource: ConfigResource,
-        keys: Option<&[String]>,
-    ) -> Result<DescribeConfigsResult>;
+impl From<ScramMechanism> for i8 {
+    fn from(value: ScramMechanism) -> Self {
+        match value {
+            ScramMechanism::Sha256 => 1,
+            ScramMechanism::Sha512 => 2,
+        }
+    }
+}
 
-    /// Query available groups optionally with a state filter.
-    async fn list_groups(&self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>>;
+impl FromStr for ScramMechanism {
+    type Err = Error;
 
-    /// Delete one or more groups from storage.
-    async fn delete_groups(
-        &self,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SCRAM-SHA-256" => Ok(Self::Sha256),
+            "SCRAM-SHA-512" => Ok(Self::Sha512),
+            _otherwise => Err(Error::Api(ErrorCode::UnsupportedSaslMechanism)),
+        }
+    }
+}
+
+/// SCRAM Credential
+///
+/// The salt, iterations, stored and server keys of a user, without the password.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ScramCredential {
+    pub salt: Bytes,
+    pub iterations: i32,
+    pub stored_key: Bytes,
+    pub server_key: Bytes,
+}
+
+/// Offset Translation
+///
+/// A checkpoint mapping an upstream offset of a mirrored topition to the
+/// local offset of the same record.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub struct OffsetTranslation {
+    pub upstream: i64,
+    pub local: i64,
+}
+
+impl OffsetTranslation {
+    pub fn new(upstream: i64, local: i64) -> Self {
+        Self { upstream, local }
+    }
+
+    /// Translate an upstream offset at or after this checkpoint into a local offset.
+    pub fn translate(&self, upstream: i64) -> i64 {
+        self.local + (upstream - self.upstream)
+    }
+}
+
+/// Usage
+///
+/// The records and bytes (of keys and values) stored by a topition, with the
+/// timestamps of its oldest and newest records. Usage is maintained by each
+/// storage engine as records are produced and deleted, rather than by scanning
+/// the stored records.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub struct Usage {
+    pub records: i64,
+    pub bytes: i64,
+    pub oldest_timestamp: Option<i64>,
+    pub newest_timestamp: Option<i64>,
+}
+
+impl Usage {
+    /// The usage of the records in a batch
+    pub fn of(batch: &inflated::Batch) -> Self {
+        batch.records.iter().fold(Self::default(), |usage, record| {
+            let timestamp = batch.base_timestamp + record.timestamp_delta;
+
+            usage.add(Self {
+                records: 1,
+                bytes: [record.key.as_ref(), record.value.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|bytes| bytes.len() as i64)
+                    .sum(),
+                oldest_timestamp: Some(timestamp),
+                newest_timestamp: Some(timestamp),
+            })
+        })
+    }
+
+    /// Combine with the usage of subsequently produced records
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            records: self.records + other.records,
+            bytes: self.bytes + other.bytes,
+            oldest_timestamp: match (self.oldest_timestamp, other.oldest_timestamp) {
+                (Some(this), Some(other)) => Some(this.min(other)),
+                (this, other) => this.or(other),
+            },
+            newest_timestamp: self.newest_timestamp.max(other.newest_timestamp),
+        }
+    }
+}
+
+/// Topition Usage
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct TopitionUsage {
+    pub topition: Topition,
+
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+/// Topition Size
+///
+/// The bytes stored by a topition.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct TopitionSize {
+    pub topition: Topition,
+    pub bytes: i64,
+}
+
+/// Direct Read
+///
+/// A presigned URL reading a sealed batch of a topition directly from object
+/// storage, bypassing the broker, until the URL expires.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct DirectRead {
+    pub base_offset: i64,
+    pub bytes: u64,
+    pub url: Url,
+}
+
+/// Coordinator Lease
+///
+/// The broker coordinating a group or transactional id. The epoch increases
+/// each time the lease moves to another broker, fencing the previous holder.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct CoordinatorLease {
+    pub node_id: i32,
+    pub host: String,
+    pub port: i32,
+    pub epoch: i32,
+}
+
+impl CoordinatorLease {
+    /// How long a lease is held without being renewed, after which another
+    /// broker may acquire it
+    pub const EXPIRES_IN: Duration = Duration::from_secs(30);
+}
+
+/// ACL Binding
+///
+/// Allows or denies a principal, connecting from a host, an operation on the
+/// resources matching a literal or prefixed name.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct AclBinding {
+    pub resource_type: AclResourceType,
+    pub resource_name: String,
+    pub pattern_type: AclPatternType,
+    pub principal: String,
+    pub host: String,
+    pub operation: AclOperation,
+    pub permission_type: AclPermissionType,
+}
+
+impl AclBinding {
+    /// The wildcard matching any resource name, principal (as `User:*`) or host.
+    pub const WILDCARD: &str = "*";
+
+    /// Whether the resource pattern of this binding applies to a named resource.
+    pub fn applies_to(&self, resource_type: AclResourceType, resource_name: &str) -> bool {
+        self.resource_type == resource_type
+            && match self.pattern_type {
+                AclPatternType::Literal => {
+                    self.resource_name == resource_name || self.resource_name == Self::WILDCARD
+                }
+                AclPatternType::Prefixed => resource_name.starts_with(&self.resource_name),
+                _otherwise => false,
+            }
+    }
+}
+
+/// ACL Binding Filter
+///
+/// Matches ACL bindings in `DescribeAcls` and `DeleteAcls`, with `None` or
+/// `Any` matching everything, and a `Match` pattern type matching every
+/// binding that applies to the named resource.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct AclBindingFilter {
+    pub resource_type: AclResourceType,
+    pub resource_name: Option<String>,
+    pub pattern_type: AclPatternType,
+    pub principal: Option<String>,
+    pub host: Option<String>,
+    pub operation: AclOperation,
+    pub permission_type: AclPermissionType,
+}
+
+impl AclBindingFilter {
+    pub fn matches(&self, binding: &AclBinding) -> bool {
+        let resource_type = self.resource_type == AclResourceType::Any
+            || self.resource_type == binding.resource_type;
+
+        let resource = match (self.pattern_type, self.resource_name.as_deref()) {
+            (AclPatternType::Any, None) | (AclPatternType::Match, None) => true,
+
+            (AclPatternType::Any, Some(name)) => binding.resource_name == name,
+
+            (AclPatternType::Match, Some(name)) => binding.applies_to(binding.resource_type, name),
+
+            (pattern_type, name) => {
+                pattern_type == binding.pattern_type
+                    && name.is_none_or(|name| binding.resource_name == name)
+            }
+        };
+
+        let principal = self
+            .principal
+            .as_ref()
+            .is_none_or(|principal| *principal == binding.principal);
+
+        let host = self.host.as_ref().is_none_or(|host| *host == binding.host);
+
+        let operation = self.operation == AclOperation::Any || self.operation == binding.operation;
+
+        let permission_type = self.permission_type == AclPermissionType::Any
+            || self.permission_type == binding.permission_type;
+
+        resource_type && resource && principal && host && operation && permission_type
+    }
+}
+
+/// Client Quota Entity
+///
+/// The user, client id or both that a client quota applies to, keyed by
+/// entity type, with a `None` name being the default entity of that type.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ClientQuotaEntity(pub BTreeMap<String, Option<String>>);
+
+impl ClientQuotaEntity {
+    pub const USER: &str = "user";
+    pub const CLIENT_ID: &str = "client-id";
+    pub const IP: &str = "ip";
+}
+
+impl FromIterator<(String, Option<String>)> for ClientQuotaEntity {
+    fn from_iter<T: IntoIterator<Item = (String, Option<String>)>>(iter: T) -> Self {
+        Self(BTreeMap::from_iter(iter))
+    }
+}
+
+/// Client Quota
+///
+/// The value of a quota, such as `producer_byte_rate` or `consumer_byte_rate`,
+/// for an entity.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct ClientQuota {
+    pub entity: ClientQuotaEntity,
+    pub key: String,
+    pub value: f64,
+}
+
+impl ClientQuota {
+    /// Set the value of a quota within quotas, or remove it with `None`.
+    pub fn alter(
+        quotas: &mut Vec<ClientQuota>,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) {
+        let existing = quotas
+            .iter()
+            .position(|quota| quota.entity == *entity && quota.key == key);
+
+        match (existing, value) {
+            (Some(index), Some(value)) => quotas[index].value = value,
+
+            (Some(index), None) => _ = quotas.remove(index),
+
+            (None, Some(value)) => quotas.push(ClientQuota {
+                entity: entity.to_owned(),
+                key: key.to_owned(),
+                value,
+            }),
+
+            (None, None) => (),
+        }
+    }
+}
+
+/// How a client quota filter component matches the name of an entity
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ClientQuotaMatch {
+    /// The entity with this name
+    Exact(String),
+
+    /// The default entity
+    #[default]
+    Default,
+
+    /// Any entity of the type, including the default
+    Specified,
+}
+
+/// A component of a client quota filter matching an entity type
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ClientQuotaFilterComponent {
+    pub entity_type: String,
+    pub match_type: ClientQuotaMatch,
+}
+
+/// Client Quota Filter
+///
+/// Matches the entities of `DescribeClientQuotas`, with each component
+/// matching an entity type. A strict filter excludes entities having
+/// any other entity type.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ClientQuotaFilter {
+    pub components: Vec<ClientQuotaFilterComponent>,
+    pub strict: bool,
+}
+
+impl ClientQuotaFilter {
+    pub fn matches(&self, entity: &ClientQuotaEntity) -> bool {
+        let components = self.components.iter().all(|component| {
+            entity.0.get(&component.entity_type).is_some_and(|name| {
+                match (&component.match_type, name) {
+                    (ClientQuotaMatch::Exact(expected), Some(name)) => expected == name,
+                    (ClientQuotaMatch::Default, None) => true,
+                    (ClientQuotaMatch::Specified, _) => true,
+                    _otherwise => false,
+                }
+            })
+        });
+
+        components && (!self.strict || entity.0.len() == self.components.len())
+    }
+}
+
+/// Finalized Features
+///
+/// The finalized level of each feature of a cluster (KIP-584), maintained
+/// with `UpdateFeatures` and reported by `ApiVersions`. The epoch increases
+/// with every update, being 0 until a feature is first updated. A supported
+/// feature that has not been updated has its default level.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct FinalizedFeatures {
+    pub epoch: i64,
+    pub levels: BTreeMap<String, i16>,
+}
+
+/// A feature supported by this broker, with its range of levels
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SupportedFeature {
+    pub name: &'static str,
+    pub min_level: i16,
+    pub max_level: i16,
+    pub default_level: i16,
+}
+
+impl FinalizedFeatures {
+    /// The consumer group protocol of KIP-848, enabled at level 1
+    pub const GROUP_VERSION: &str = "group.version";
+
+    /// Share groups of KIP-932, enabled at level 1
+    pub const SHARE_VERSION: &str = "share.version";
+
+    pub const SUPPORTED: [SupportedFeature; 2] = [
+        SupportedFeature {
+            name: Self::GROUP_VERSION,
+            min_level: 0,
+            max_level: 1,
+            default_level: 1,
+        },
+        SupportedFeature {
+            name: Self::SHARE_VERSION,
+            min_level: 0,
+            max_level: 1,
+            default_level: 1,
+        },
+    ];
+
+    /// The supported feature with this name
+    pub fn supported(name: &str) -> Option<SupportedFeature> {
+        Self::SUPPORTED
+            .into_iter()
+            .find(|supported| supported.name == name)
+    }
+
+    /// The finalized level of a feature, or its default level
+    pub fn level(&self, name: &str) -> i16 {
+        self.levels
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| Self::supported(name).map_or(0, |supported| supported.default_level))
+    }
+
+    /// The finalized level of every supported feature
+    pub fn finalized(&self) -> BTreeMap<String, i16> {
+        Self::SUPPORTED
+            .into_iter()
+            .map(|supported| (supported.name.to_owned(), self.level(supported.name)))
+            .chain(self.levels.clone())
+            .collect()
+    }
+
+    /// Set the finalized level of a feature, incrementing the epoch
+    pub fn update(&mut self, name: &str, level: i16) {
+        _ = self.levels.insert(name.to_owned(), level);
+        self.epoch += 1;
+    }
+}
+
+/// Finalized features from the name, level and number of updates of each
+/// feature, with the epoch being the total number of updates
+impl FromIterator<(String, i16, i64)> for FinalizedFeatures {
+    fn from_iter<T: IntoIterator<Item = (String, i16, i64)>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::default(), |mut features, (name, level, updates)| {
+                _ = features.levels.insert(name, level);
+                features.epoch += updates;
+                features
+            })
+    }
+}
+
+/// Storage
+///
+/// The Core storage abstraction. All storage engines implement this type.
+#[async_trait]
+pub trait Storage: Clone + Debug + Send + Sync + 'static {
+    /// On startup a broker will register with storage.
+    async fn register_broker(&self, broker_registration: BrokerRegistrationRequest) -> Result<()>;
+
+    /// Create a topic on this storage.
+    async fn create_topic(&self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid>;
+
+    /// Grow the number of partitions of an existing topic to count.
+    ///
+    /// Returns [`ErrorCode::UnknownTopicOrPartition`] for an unknown topic,
+    /// and [`ErrorCode::InvalidPartitions`] when count does not exceed the
+    /// current number of partitions.
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode>;
+
+    /// Incrementally alter a resource on this storage.
+    async fn incremental_alter_resource(
+        &self,
+        resource: AlterConfigsResource,
+    ) -> Result<AlterConfigsResourceResponse>;
+
+    /// Delete records on this storage.
+    async fn delete_records(
+        &self,
+        topics: &[DeleteRecordsTopic],
+    ) -> Result<Vec<DeleteRecordsTopicResult>>;
+
+    /// Delete a topic from this storage.
+    async fn delete_topic(&self, topic: &TopicId) -> Result<ErrorCode>;
+
+    /// Query the brokers registered with this storage.
+    async fn brokers(&self) -> Result<Vec<DescribeClusterBroker>>;
+
+    /// Produce a deflated batch to this storage.
+    async fn produce(
+        &self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        batch: deflated::Batch,
+    ) -> Result<i64>;
+
+    /// Append a batch replicated from an upstream cluster, preserving its base offset.
+    ///
+    /// A batch below the high watermark has already been replicated and is ignored. The
+    /// default appends a batch at the high watermark, rejecting a gap in the upstream offsets
+    /// with [`ErrorCode::OffsetOutOfRange`]: engines able to advance their high watermark
+    /// should override this method.
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        let high_watermark = self.offset_stage(topition).await?.high_watermark();
+
+        if batch.base_offset < high_watermark {
+            debug!(?topition, base_offset = batch.base_offset, high_watermark);
+            return Ok(batch.base_offset);
+        }
+
+        if batch.base_offset > high_watermark {
+            warn!(?topition, base_offset = batch.base_offset, high_watermark);
+            return Err(Error::Api(ErrorCode::OffsetOutOfRange));
+        }
+
+        self.produce(None, topition, batch.without_producer()?)
+            .await
+    }
+
+    /// Fetch deflated batches from storage.
+    async fn fetch(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<deflated::Batch>>;
+
+    /// Fetch batches as their encoded bytes, ready to be written to a
+    /// fetch response without being decoded.
+    ///
+    /// A storage engine holding encoded batches returns them as stored.
+    /// Storage holding records rather than batches returns
+    /// [`Error::Unsupported`], with batches then fetched by [`Storage::fetch`].
+    async fn fetch_encoded(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<Bytes>> {
+        let _ = (topition, offset, min_bytes, max_bytes, isolation);
+        Err(Error::Unsupported("fetch_encoded"))
+    }
+
+    /// Query the offset stage for a topic partition.
+    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage>;
+
+    /// Query the offsets for one or more topic partitions.
+    async fn list_offsets(
+        &self,
+        isolation_level: IsolationLevel,
+        offsets: &[(Topition, ListOffset)],
+    ) -> Result<Vec<(Topition, ListOffsetResponse)>>;
+
+    /// Commit offsets for one or more topic partitions in a consumer group.
+    async fn offset_commit(
+        &self,
+        group_id: &str,
+        retention_time_ms: Option<Duration>,
+        offsets: &[(Topition, OffsetCommitRequest)],
+    ) -> Result<Vec<(Topition, ErrorCode)>>;
+
+    /// Fetch committed offsets for one or more topic partitions in a consumer group.
+    async fn offset_fetch(
+        &self,
+        group_id: Option<&str>,
+        topics: &[Topition],
+        require_stable: Option<bool>,
+    ) -> Result<BTreeMap<Topition, i64>>;
+
+    /// Fetch all committed offsets in a consumer group.
+    async fn committed_offset_topitions(&self, group_id: &str) -> Result<BTreeMap<Topition, i64>>;
+
+    /// Query broker and topic metadata.
+    async fn metadata(&self, topics: Option<&[TopicId]>) -> Result<MetadataResponse>;
+
+    /// Query the configuration of a resource in this storage.
+    async fn describe_config(
+        &self,
+        name: &str,
+        resource: ConfigResource,
+        keys: Option<&[String]>,
+    ) -> Result<DescribeConfigsResult>;
+
+    /// Query available groups optionally with a state filter.
+    async fn list_groups(&self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>>;
+
+    /// Delete one or more groups from storage.
+    async fn delete_groups(
+        &self,
         group_ids: Option<&[String]>,
     ) -> Result<Vec<DeletableGroupResult>>;
 
@@ -1461,19 +2457,255 @@ pub trait Storage: Clone + Debug + Send + Sync + 'static {
         committed: bool,
     ) -> Result<ErrorCode>;
 
-    /// Run periodic maintenance on this storage.
-    async fn maintain(&self, _now: SystemTime) -> Result<()> {
-        Ok(())
-    }
+    /// Insert or update the SCRAM credential of a user for a mechanism.
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()>;
 
-    async fn cluster_id(&self) -> Result<String>;
+    /// Delete the SCRAM credential of a user for a mechanism.
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode>;
 
-    async fn node(&self) -> Result<i32>;
+    /// The SCRAM credentials of users, or of all users when none are specified.
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>>;
 
-    async fn advertised_listener(&self) -> Result<Url>;
+    /// Whether this storage holds the SCRAM credentials of users.
+    fn scram_credentials(&self) -> bool {
+        true
+    }
 
-    async fn ping(&self) -> Result<()>;
-}
+    /// The SCRAM credential of a user for a mechanism.
+    async fn user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<Option<ScramCredential>> {
+        self.user_scram_credentials(Some(&[username.to_owned()]))
+            .await
+            .map(|mut users| {
+                users
+                    .remove(username)
+                    .and_then(|mut credentials| credentials.remove(&mechanism))
+            })
+    }
+
+    /// Checkpoint the translation of an upstream offset of a mirrored topition.
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()>;
+
+    /// The latest checkpoint at or before an upstream offset of a mirrored topition.
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>>;
+
+    /// The usage of each topition, optionally restricted to these topics.
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>>;
+
+    /// The size of each topition, optionally restricted to these topics.
+    async fn topition_size(&self, topics: Option<&[String]>) -> Result<Vec<TopitionSize>> {
+        self.topition_usage(topics).await.map(|usage| {
+            usage
+                .into_iter()
+                .map(|TopitionUsage { topition, usage }| TopitionSize {
+                    topition,
+                    bytes: usage.bytes,
+                })
+                .collect()
+        })
+    }
+
+    /// Presigned URLs for up to limit sealed batches of a topition, starting
+    /// with the batch containing offset. Storage without an object store has
+    /// no direct reads, leaving the consumer to fetch from the broker.
+    async fn direct_reads(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        limit: usize,
+        expires_in: Duration,
+    ) -> Result<Vec<DirectRead>> {
+        let _ = (topition, offset, limit, expires_in);
+        Ok(vec![])
+    }
+
+    /// Delete the committed offsets of a group that were last committed longer
+    /// ago than the retention, returning their topitions. Storage without the
+    /// time of each commit returns [`Error::Unsupported`].
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        let _ = (group_id, retention);
+        Err(Error::Unsupported("delete_expired_offsets"))
+    }
+
+    /// Describe the transactions of this cluster, or only those of some
+    /// transactional ids. Storage without queryable transaction state returns
+    /// [`Error::Unsupported`].
+    async fn describe_txns(
+        &self,
+        transactional_ids: Option<&[String]>,
+    ) -> Result<Vec<TxnDescription>> {
+        let _ = transactional_ids;
+        Err(Error::Unsupported("describe_txns"))
+    }
+
+    /// The transactions aborted on a topition with records between two
+    /// offsets (inclusive), for read committed consumers to skip. Storage
+    /// without queryable transaction state returns [`Error::Unsupported`].
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        let _ = (topition, first_offset, last_offset);
+        Err(Error::Unsupported("aborted_txns"))
+    }
+
+    /// The coordinator lease of a group (key type 0) or transactional id (key
+    /// type 1). The lease is acquired or renewed by this broker when it is
+    /// unheld, has expired or is already held by this broker. Storage that
+    /// cannot be shared between brokers (a local database, or a single writer
+    /// of an object store) leases every key to this broker.
+    async fn coordinator_lease(&self, key_type: i8, key: &str) -> Result<CoordinatorLease> {
+        let _ = (key_type, key);
+
+        let listener = self.advertised_listener().await?;
+
+        Ok(CoordinatorLease {
+            node_id: self.node().await?,
+            host: listener.host_str().unwrap_or("localhost").into(),
+            port: i32::from(listener.port().unwrap_or(9092)),
+            epoch: 0,
+        })
+    }
+
+    /// Create an ACL binding, which is unchanged when it already exists.
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()>;
+
+    /// Delete an ACL binding.
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode>;
+
+    /// All ACL bindings of this cluster.
+    async fn acls(&self) -> Result<Vec<AclBinding>>;
+
+    /// The ACL bindings matching a filter.
+    async fn describe_acls(&self, filter: &AclBindingFilter) -> Result<Vec<AclBinding>> {
+        self.acls().await.map(|acls| {
+            acls.into_iter()
+                .filter(|binding| filter.matches(binding))
+                .collect()
+        })
+    }
+
+    /// Set the value of a client quota, or remove it with `None`.
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()>;
+
+    /// All client quotas of this cluster.
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>>;
+
+    /// The client quotas of the entities matching a filter.
+    async fn describe_client_quotas(&self, filter: &ClientQuotaFilter) -> Result<Vec<ClientQuota>> {
+        self.client_quotas().await.map(|quotas| {
+            quotas
+                .into_iter()
+                .filter(|quota| filter.matches(&quota.entity))
+                .collect()
+        })
+    }
+
+    /// Set the finalized level of a feature.
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()>;
+
+    /// The finalized features of this cluster.
+    async fn finalized_features(&self) -> Result<FinalizedFeatures>;
+
+    /// Run periodic maintenance on this storage.
+    async fn maintain(&self, _now: SystemTime) -> Result<()> {
+        Ok(())
+    }
+
+    /// Compact the records of a topition below an offset, retaining only the
+    /// latest record of each key, returning the number of records removed.
+    /// Storage compacting during its maintenance removes nothing, while
+    /// storage without compaction returns [`Error::Unsupported`].
+    async fn compact(&self, topition: &Topition, end_offset: i64) -> Result<u64> {
+        let _ = (topition, end_offset);
+        Err(Error::Unsupported("compact"))
+    }
+
+    /// Verify the conditions this storage depends on before the broker
+    /// accepts requests, such as an applied schema. Storage without any such
+    /// conditions has no checks.
+    async fn preflight(&self) -> Result<Vec<PreflightCheck>> {
+        Ok(vec![])
+    }
+
+    async fn cluster_id(&self) -> Result<String>;
+
+    async fn node(&self) -> Result<i32>;
+
+    async fn advertised_listener(&self) -> Result<Url>;
+
+    async fn ping(&self) -> Result<()>;
+}
+
+/// Preflight Check
+///
+/// A condition of storage verified before the broker accepts requests, with a
+/// remedy for an operator when it has failed.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct PreflightCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub remedy: Option<String>,
+}
+
+impl PreflightCheck {
+    pub fn passed(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: true,
+            detail: detail.into(),
+            remedy: None,
+        }
+    }
+
+    pub fn failed(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        remedy: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            passed: false,
+            detail: detail.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+}
 
 /// Conditional Update Errors
 #[derive(Clone, Debug, thiserror::Error)]
@@ -1538,6 +2770,8 @@ impl<T> From<tokio_postgres::error::Error> for UpdateError<T> {
 pub enum StorageContainer {
     Null(null::Engine),
 
+    Routed(Routed),
+
     #[cfg(feature = "postgres")]
     Postgres(Postgres),
 
@@ -1559,6 +2793,11 @@ impl Debug for StorageContainer {
         match self {
             Self::Null(_) => f.debug_tuple(stringify!(StorageContainer::Null)).finish(),
 
+            Self::Routed(routed) => f
+                .debug_tuple(stringify!(StorageContainer::Routed))
+                .field(routed)
+                .finish(),
+
             #[cfg(feature = "postgres")]
             Self::Postgres(_) => f
                 .debug_tuple(stringify!(StorageContainer::Postgres))
@@ -1596,6 +2835,7 @@ pub struct Builder<N, C, A, S> {
     storage: S,
     schema_registry: Option<Registry>,
     lake_house: Option<House>,
+    tiers: BTreeMap<String, Url>,
 
     cancellation: CancellationToken,
 }
@@ -1612,6 +2852,7 @@ impl<N, C, A, S> Builder<N, C, A, S> {
             storage: self.storage,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            tiers: self.tiers,
             cancellation: self.cancellation,
         }
     }
@@ -1624,6 +2865,7 @@ impl<N, C, A, S> Builder<N, C, A, S> {
             storage: self.storage,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            tiers: self.tiers,
             cancellation: self.cancellation,
         }
     }
@@ -1636,6 +2878,7 @@ impl<N, C, A, S> Builder<N, C, A, S> {
             storage: self.storage,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            tiers: self.tiers,
             cancellation: self.cancellation,
         }
     }
@@ -1650,6 +2893,7 @@ impl<N, C, A, S> Builder<N, C, A, S> {
             storage,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            tiers: self.tiers,
             cancellation: self.cancellation,
         }
     }
@@ -1673,6 +2917,16 @@ impl<N, C, A, S> Builder<N, C, A, S> {
         Self { lake_house, ..self }
     }
 
+    /// Add a storage tier, holding the topics created with a
+    /// [`STORAGE_TIER`] config of this name.
+    pub fn storage_tier(mut self, name: impl Into<String>, storage: Url) -> Self {
+        let name = name.into();
+        debug!(name, %storage);
+
+        _ = self.tiers.insert(name, storage);
+        self
+    }
+
     pub fn cancellation(self, cancellation: CancellationToken) -> Self {
         Self {
             cancellation,
@@ -1682,7 +2936,24 @@ impl<N, C, A, S> Builder<N, C, A, S> {
 }
 
 impl Builder<i32, String, Url, Url> {
-    pub async fn build(self) -> Result<StorageContainer> {
+    pub async fn build(mut self) -> Result<StorageContainer> {
+        let mut tiers = BTreeMap::new();
+
+        for (name, storage) in std::mem::take(&mut self.tiers) {
+            let tier = Builder {
+                node_id: self.node_id,
+                cluster_id: self.cluster_id.clone(),
+                advertised_listener: self.advertised_listener.clone(),
+                storage,
+                schema_registry: self.schema_registry.clone(),
+                lake_house: self.lake_house.clone(),
+                tiers: BTreeMap::new(),
+                cancellation: self.cancellation.clone(),
+            };
+
+            _ = tiers.insert(name, Box::pin(tier.build()).await?);
+        }
+
         let storage = match self.storage.scheme() {
             #[cfg(feature = "postgres")]
             "postgres" | "postgresql" => Postgres::builder(self.storage.to_string().as_str())
@@ -1709,10 +2980,56 @@ impl Builder<i32, String, Url, Url> {
                     .with_conditional_put(S3ConditionalPut::ETagMatch)
                     .build()
                     .map(|object_store| {
+                        let signer = Arc::new(object_store.clone());
+
+                        DynoStore::new(self.cluster_id.as_str(), self.node_id, object_store)
+                            .advertised_listener(self.advertised_listener.clone())
+                            .schemas(self.schema_registry)
+                            .lake(self.lake_house.clone())
+                            .signer(Some(signer))
+                    })
+                    .map(StorageContainer::DynoStore)
+                    .map_err(Into::into)
+            }
+
+            // Azure Blob Storage, with conditional puts using the ETag: az://container/
+            #[cfg(feature = "dynostore")]
+            "az" => {
+                let container_name = self.storage.host_str().unwrap_or("tansu");
+
+                MicrosoftAzureBuilder::from_env()
+                    .with_container_name(container_name)
+                    .build()
+                    .map(|object_store| {
+                        let signer = Arc::new(object_store.clone());
+
+                        DynoStore::new(self.cluster_id.as_str(), self.node_id, object_store)
+                            .advertised_listener(self.advertised_listener.clone())
+                            .schemas(self.schema_registry)
+                            .lake(self.lake_house.clone())
+                            .signer(Some(signer))
+                    })
+                    .map(StorageContainer::DynoStore)
+                    .map_err(Into::into)
+            }
+
+            // Google Cloud Storage, with conditional puts using the object
+            // generation: gs://bucket/
+            #[cfg(feature = "dynostore")]
+            "gs" => {
+                let bucket_name = self.storage.host_str().unwrap_or("tansu");
+
+                GoogleCloudStorageBuilder::from_env()
+                    .with_bucket_name(bucket_name)
+                    .build()
+                    .map(|object_store| {
+                        let signer = Arc::new(object_store.clone());
+
                         DynoStore::new(self.cluster_id.as_str(), self.node_id, object_store)
                             .advertised_listener(self.advertised_listener.clone())
                             .schemas(self.schema_registry)
                             .lake(self.lake_house.clone())
+                            .signer(Some(signer))
                     })
                     .map(StorageContainer::DynoStore)
                     .map_err(Into::into)
@@ -1726,8 +3043,96 @@ impl Builder<i32, String, Url, Url> {
                     .lake(self.lake_house.clone()),
             )),
 
+            // Kafka style segments on local disk: file:///var/lib/tansu
+            #[cfg(feature = "dynostore")]
+            "file" => {
+                let segment_bytes = self
+                    .storage
+                    .query_pairs()
+                    .find(|(k, _)| k == "segment.bytes")
+                    .map_or(Ok(DEFAULT_SEGMENT_BYTES), |(_, v)| v.parse())?;
+
+                tokio::fs::create_dir_all(self.storage.path()).await?;
+
+                LocalFileSystem::new_with_prefix(self.storage.path())
+                    .and_then(|local| Segments::new(self.storage.path(), local, segment_bytes))
+                    .map(|object_store| {
+                        DynoStore::new(self.cluster_id.as_str(), self.node_id, object_store)
+                            .advertised_listener(self.advertised_listener.clone())
+                            .schemas(self.schema_registry)
+                            .lake(self.lake_house.clone())
+                    })
+                    .map(StorageContainer::DynoStore)
+                    .map_err(Into::into)
+            }
+
+            #[cfg(feature = "dynostore")]
+            "tiered" => {
+                let bucket_name = self.storage.host_str().unwrap_or("tansu");
+
+                let local_retention = self
+                    .storage
+                    .query_pairs()
+                    .find(|(k, _)| k == "local.retention.ms")
+                    .map_or(Ok(DEFAULT_LOCAL_RETENTION), |(_, v)| {
+                        v.parse().map(Duration::from_millis)
+                    })?;
+
+                tokio::fs::create_dir_all(self.storage.path()).await?;
+                let hot = LocalFileSystem::new_with_prefix(self.storage.path())?
+                    .with_automatic_cleanup(true);
+
+                if bucket_name == "memory" {
+                    Ok(StorageContainer::DynoStore(
+                        DynoStore::new(
+                            self.cluster_id.as_str(),
+                            self.node_id,
+                            Tiered::new(hot, InMemory::new(), local_retention),
+                        )
+                        .advertised_listener(self.advertised_listener.clone())
+                        .schemas(self.schema_registry)
+                        .lake(self.lake_house.clone()),
+                    ))
+                } else {
+                    AmazonS3Builder::from_env()
+                        .with_bucket_name(bucket_name)
+                        .with_conditional_put(S3ConditionalPut::ETagMatch)
+                        .build()
+                        .map(|cold| {
+                            DynoStore::new(
+                                self.cluster_id.as_str(),
+                                self.node_id,
+                                Tiered::new(hot, cold, local_retention),
+                            )
+                            .advertised_listener(self.advertised_listener.clone())
+                            .schemas(self.schema_registry)
+                            .lake(self.lake_house.clone())
+                        })
+                        .map(StorageContainer::DynoStore)
+                        .map_err(Into::into)
+                }
+            }
+
+            #[cfg(feature = "dynamodb")]
+            "dynamodb" => Ok(StorageContainer::DynoStore(
+                DynoStore::new(
+                    self.cluster_id.as_str(),
+                    self.node_id,
+                    DynamoDb::from_env(self.storage.host_str().unwrap_or("tansu")).await,
+                )
+                .advertised_listener(self.advertised_listener.clone())
+                .schemas(self.schema_registry)
+                .lake(self.lake_house.clone()),
+            )),
+
+            #[cfg(not(feature = "dynamodb"))]
+            "dynamodb" => Err(Error::FeatureNotEnabled {
+                feature: "dynamodb".into(),
+                message: self.storage.to_string(),
+            }),
+
             #[cfg(not(feature = "dynostore"))]
-            "s3" | "memory" => Err(Error::FeatureNotEnabled {
+            "s3" | "az" | "gs" | "memory" | "file" | "tiered" => Err(Error::FeatureNotEnabled {
                 feature: "dynostore".into(),
                 message: self.storage.to_string(),
             }),
@@ -1760,23 +3165,32 @@ impl Builder<i32, String, Url, Url> {
                         AmazonS3Builder as SlateS3Builder,
                         S3ConditionalPut as SlateS3ConditionalPut,
                     },
+                    local::LocalFileSystem as SlateLocalFileSystem,
                     memory::InMemory as SlateInMemory,
                 };
 
-                let host = self.storage.host_str().unwrap_or("tansu");
                 let db_path = format!("tansu-{}.slatedb", self.cluster_id);
 
-                // Support memory backend for testing: slatedb://memory
-                let object_store: Arc<dyn SlateObjectStore> = if host == "memory" {
-                    Arc::new(SlateInMemory::new())
-                } else {
+                let object_store: Arc<dyn SlateObjectStore> = match self.storage.host_str() {
+                    // Embedded on local disk: slatedb:///var/lib/tansu
+                    None | Some("") => {
+                        tokio::fs::create_dir_all(self.storage.path()).await?;
+
+                        SlateLocalFileSystem::new_with_prefix(self.storage.path())
+                            .map(Arc::new)
+                            .map_err(|e| Error::Message(e.to_string()))?
+                    }
+
+                    // Support memory backend for testing: slatedb://memory
+                    Some("memory") => Arc::new(SlateInMemory::new()),
+
                     // Use S3 backend with host as bucket name
-                    SlateS3Builder::from_env()
-                        .with_bucket_name(host)
+                    Some(bucket_name) => SlateS3Builder::from_env()
+                        .with_bucket_name(bucket_name)
                         .with_conditional_put(SlateS3ConditionalPut::ETagMatch)
                         .build()
                         .map(Arc::new)
-                        .map_err(|e| Error::Message(e.to_string()))?
+                        .map_err(|e| Error::Message(e.to_string()))?,
                 };
 
                 Db::open(db_path, object_store)
@@ -1850,7 +3264,12 @@ impl Builder<i32, String, Url, Url> {
         }?;
 
         storage.ping().await?;
-        Ok(storage)
+
+        if tiers.is_empty() {
+            Ok(storage)
+        } else {
+            Ok(StorageContainer::Routed(Routed::new(storage, tiers)))
+        }
     }
 }
 
@@ -1890,16 +3309,1012 @@ impl Storage for StorageContainer {
             #[cfg(feature = "libsql")]
             Self::Lite(engine) => engine.register_broker(broker_registration),
 
-            Self::Null(engine) => engine.register_broker(broker_registration),
+            Self::Null(engine) => engine.register_broker(broker_registration),
+
+            Self::Routed(engine) => engine.register_broker(broker_registration),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.register_broker(broker_registration),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.register_broker(broker_registration),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.register_broker(broker_registration),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn incremental_alter_resource(
+        &self,
+        resource: AlterConfigsResource,
+    ) -> Result<AlterConfigsResourceResponse> {
+        let attributes = [KeyValue::new("method", "incremental_alter_resource")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.incremental_alter_resource(resource),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.incremental_alter_resource(resource),
+
+            Self::Null(engine) => engine.incremental_alter_resource(resource),
+
+            Self::Routed(engine) => engine.incremental_alter_resource(resource),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.incremental_alter_resource(resource),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.incremental_alter_resource(resource),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.incremental_alter_resource(resource),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn create_topic(&self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid> {
+        let attributes = [KeyValue::new("method", "create_topic")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.create_topic(topic, validate_only),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.create_topic(topic, validate_only),
+
+            Self::Null(engine) => engine.create_topic(topic, validate_only),
+
+            Self::Routed(engine) => engine.create_topic(topic, validate_only),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.create_topic(topic, validate_only),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.create_topic(topic, validate_only),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.create_topic(topic, validate_only),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        let attributes = [KeyValue::new("method", "add_partitions")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.add_partitions(topic, count),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.add_partitions(topic, count),
+
+            Self::Null(engine) => engine.add_partitions(topic, count),
+
+            Self::Routed(engine) => engine.add_partitions(topic, count),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.add_partitions(topic, count),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.add_partitions(topic, count),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.add_partitions(topic, count),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_records(
+        &self,
+        topics: &[DeleteRecordsTopic],
+    ) -> Result<Vec<DeleteRecordsTopicResult>> {
+        let attributes = [KeyValue::new("method", "delete_records")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.delete_records(topics),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.delete_records(topics),
+
+            Self::Null(engine) => engine.delete_records(topics),
+
+            Self::Routed(engine) => engine.delete_records(topics),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.delete_records(topics),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.delete_records(topics),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.delete_records(topics),
+        }
+        .await
+        .inspect(|results| {
+            for topic in results {
+                for partition in topic.partitions.as_deref().unwrap_or_default() {
+                    if partition.error_code == i16::from(ErrorCode::None) {
+                        produced::notify(
+                            &Topition::new(topic.name.as_str(), partition.partition_index),
+                            partition.low_watermark,
+                        );
+                    }
+                }
+            }
+        })
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_topic(&self, topic: &TopicId) -> Result<ErrorCode> {
+        let attributes = [KeyValue::new("method", "delete_topic")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.delete_topic(topic),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.delete_topic(topic),
+
+            Self::Null(engine) => engine.delete_topic(topic),
+
+            Self::Routed(engine) => engine.delete_topic(topic),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.delete_topic(topic),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.delete_topic(topic),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.delete_topic(topic),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn brokers(&self) -> Result<Vec<DescribeClusterBroker>> {
+        let attributes = [KeyValue::new("method", "brokers")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.brokers(),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.brokers(),
+
+            Self::Null(engine) => engine.brokers(),
+
+            Self::Routed(engine) => engine.brokers(),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.brokers(),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.brokers(),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.brokers(),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn produce(
+        &self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        batch: deflated::Batch,
+    ) -> Result<i64> {
+        let attributes = [KeyValue::new("method", "produce")];
+
+        #[cfg(debug_assertions)]
+        let checked = if matches!(self, Self::Null(_) | Self::Routed(_)) {
+            None
+        } else {
+            Some(invariant::Produce::before(self, topition, &batch).await)
+        };
+
+        let produced = match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.produce(transaction_id, topition, batch),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.produce(transaction_id, topition, batch),
+
+            Self::Null(engine) => engine.produce(transaction_id, topition, batch),
+
+            Self::Routed(engine) => engine.produce(transaction_id, topition, batch),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.produce(transaction_id, topition, batch),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.produce(transaction_id, topition, batch),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.produce(transaction_id, topition, batch),
+        }
+        .await
+        .inspect(|offset| produced::notify(topition, *offset))
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        });
+
+        #[cfg(debug_assertions)]
+        if let (Some(checked), Ok(offset)) = (checked, produced.as_ref()) {
+            checked.after(self, topition, *offset).await;
+        }
+
+        produced
+    }
+
+    #[instrument(skip_all)]
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        let attributes = [KeyValue::new("method", "replicate")];
+
+        #[cfg(debug_assertions)]
+        let checked = (!matches!(self, Self::Null(_) | Self::Routed(_))).then(|| batch.clone());
+
+        let replicated = match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.replicate(topition, batch),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.replicate(topition, batch),
+
+            Self::Null(engine) => engine.replicate(topition, batch),
+
+            Self::Routed(engine) => engine.replicate(topition, batch),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.replicate(topition, batch),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.replicate(topition, batch),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.replicate(topition, batch),
+        }
+        .await
+        .inspect(|offset| produced::notify(topition, *offset))
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        });
+
+        #[cfg(debug_assertions)]
+        if let (Some(batch), Ok(_)) = (checked, replicated.as_ref()) {
+            invariant::replicated(self, topition, &batch).await;
+        }
+
+        replicated
+    }
+
+    #[instrument(skip_all)]
+    async fn fetch(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<deflated::Batch>> {
+        let attributes = [KeyValue::new("method", "fetch")];
+
+        let fetched = match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => {
+                engine.fetch(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+
+            Self::Null(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+
+            Self::Routed(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => {
+                engine.fetch(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        });
+
+        #[cfg(debug_assertions)]
+        if let Ok(batches) = fetched.as_ref() {
+            invariant::fetch(topition, batches);
+        }
+
+        fetched
+    }
+
+    #[instrument(skip_all)]
+    async fn fetch_encoded(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<Bytes>> {
+        let attributes = [KeyValue::new("method", "fetch_encoded")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            Self::Null(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            Self::Routed(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => {
+                engine.fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            }
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage> {
+        let attributes = [KeyValue::new("method", "offset_stage")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.offset_stage(topition),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.offset_stage(topition),
+
+            Self::Null(engine) => engine.offset_stage(topition),
+
+            Self::Routed(engine) => engine.offset_stage(topition),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.offset_stage(topition),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.offset_stage(topition),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.offset_stage(topition),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn list_offsets(
+        &self,
+        isolation_level: IsolationLevel,
+        offsets: &[(Topition, ListOffset)],
+    ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
+        let attributes = [KeyValue::new("method", "list_offsets")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.list_offsets(isolation_level, offsets),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.list_offsets(isolation_level, offsets),
+
+            Self::Null(engine) => engine.list_offsets(isolation_level, offsets),
+
+            Self::Routed(engine) => engine.list_offsets(isolation_level, offsets),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.list_offsets(isolation_level, offsets),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.list_offsets(isolation_level, offsets),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.list_offsets(isolation_level, offsets),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn offset_commit(
+        &self,
+        group_id: &str,
+        retention_time_ms: Option<Duration>,
+        offsets: &[(Topition, OffsetCommitRequest)],
+    ) -> Result<Vec<(Topition, ErrorCode)>> {
+        let attributes = [KeyValue::new("method", "offset_commit")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+
+            Self::Null(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+
+            Self::Routed(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn committed_offset_topitions(&self, group_id: &str) -> Result<BTreeMap<Topition, i64>> {
+        let attributes = [KeyValue::new("method", "committed_offset_topitions")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.committed_offset_topitions(group_id),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.committed_offset_topitions(group_id),
+
+            Self::Null(engine) => engine.committed_offset_topitions(group_id),
+
+            Self::Routed(engine) => engine.committed_offset_topitions(group_id),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.committed_offset_topitions(group_id),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.committed_offset_topitions(group_id),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.committed_offset_topitions(group_id),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn offset_fetch(
+        &self,
+        group_id: Option<&str>,
+        topics: &[Topition],
+        require_stable: Option<bool>,
+    ) -> Result<BTreeMap<Topition, i64>> {
+        let attributes = [KeyValue::new("method", "offset_fetch")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.offset_fetch(group_id, topics, require_stable),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.offset_fetch(group_id, topics, require_stable),
+
+            Self::Null(engine) => engine.offset_fetch(group_id, topics, require_stable),
+
+            Self::Routed(engine) => engine.offset_fetch(group_id, topics, require_stable),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.offset_fetch(group_id, topics, require_stable),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.offset_fetch(group_id, topics, require_stable),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.offset_fetch(group_id, topics, require_stable),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn metadata(&self, topics: Option<&[TopicId]>) -> Result<MetadataResponse> {
+        let attributes = [KeyValue::new("method", "metadata")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.metadata(topics),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.metadata(topics),
+
+            Self::Null(engine) => engine.metadata(topics),
+
+            Self::Routed(engine) => engine.metadata(topics),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.metadata(topics),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.metadata(topics),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.metadata(topics),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn describe_config(
+        &self,
+        name: &str,
+        resource: ConfigResource,
+        keys: Option<&[String]>,
+    ) -> Result<DescribeConfigsResult> {
+        let attributes = [KeyValue::new("method", "describe_config")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.describe_config(name, resource, keys),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.describe_config(name, resource, keys),
+
+            Self::Null(engine) => engine.describe_config(name, resource, keys),
+
+            Self::Routed(engine) => engine.describe_config(name, resource, keys),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.describe_config(name, resource, keys),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.describe_config(name, resource, keys),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.describe_config(name, resource, keys),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn describe_topic_partitions(
+        &self,
+        topics: Option<&[TopicId]>,
+        partition_limit: i32,
+        cursor: Option<Topition>,
+    ) -> Result<Vec<DescribeTopicPartitionsResponseTopic>> {
+        let attributes = [KeyValue::new("method", "describe_topic_partitions")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => {
+                engine.describe_topic_partitions(topics, partition_limit, cursor)
+            }
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.describe_topic_partitions(topics, partition_limit, cursor),
+
+            Self::Null(engine) => engine.describe_topic_partitions(topics, partition_limit, cursor),
+
+            Self::Routed(engine) => {
+                engine.describe_topic_partitions(topics, partition_limit, cursor)
+            }
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => {
+                engine.describe_topic_partitions(topics, partition_limit, cursor)
+            }
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => {
+                engine.describe_topic_partitions(topics, partition_limit, cursor)
+            }
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => {
+                engine.describe_topic_partitions(topics, partition_limit, cursor)
+            }
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn list_groups(&self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>> {
+        let attributes = [KeyValue::new("method", "list_groups")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.list_groups(states_filter),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.list_groups(states_filter),
+
+            Self::Null(engine) => engine.list_groups(states_filter),
+
+            Self::Routed(engine) => engine.list_groups(states_filter),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.list_groups(states_filter),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.list_groups(states_filter),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.list_groups(states_filter),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_groups(
+        &self,
+        group_ids: Option<&[String]>,
+    ) -> Result<Vec<DeletableGroupResult>> {
+        let attributes = [KeyValue::new("method", "delete_groups")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.delete_groups(group_ids),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.delete_groups(group_ids),
+
+            Self::Null(engine) => engine.delete_groups(group_ids),
+
+            Self::Routed(engine) => engine.delete_groups(group_ids),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.delete_groups(group_ids),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.delete_groups(group_ids),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.delete_groups(group_ids),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn describe_groups(
+        &self,
+        group_ids: Option<&[String]>,
+        include_authorized_operations: bool,
+    ) -> Result<Vec<NamedGroupDetail>> {
+        let attributes = [KeyValue::new("method", "describe_groups")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => {
+                engine.describe_groups(group_ids, include_authorized_operations)
+            }
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+
+            Self::Null(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+
+            Self::Routed(engine) => {
+                engine.describe_groups(group_ids, include_authorized_operations)
+            }
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => {
+                engine.describe_groups(group_ids, include_authorized_operations)
+            }
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn update_group(
+        &self,
+        group_id: &str,
+        detail: GroupDetail,
+        version: Option<Version>,
+    ) -> Result<Version, UpdateError<GroupDetail>> {
+        let attributes = [KeyValue::new("method", "update_group")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.update_group(group_id, detail, version),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.update_group(group_id, detail, version),
+
+            Self::Null(engine) => engine.update_group(group_id, detail, version),
+
+            Self::Routed(engine) => engine.update_group(group_id, detail, version),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.update_group(group_id, detail, version),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.update_group(group_id, detail, version),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.update_group(group_id, detail, version),
+        }
+        .await
+        .inspect(|_| purgatory::complete(&purgatory::Key::Group(group_id.to_owned())))
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn init_producer(
+        &self,
+        transaction_id: Option<&str>,
+        transaction_timeout_ms: i32,
+        producer_id: Option<i64>,
+        producer_epoch: Option<i16>,
+    ) -> Result<ProducerIdResponse> {
+        let attributes = [KeyValue::new("method", "init_producer")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+
+            Self::Null(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+
+            Self::Routed(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+
+            #[cfg(feature = "slatedb")]
+            Self::Slate(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+        }
+        .await
+        .inspect(|_| {
+            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
+        })
+        .inspect_err(|_| {
+            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn txn_add_offsets(
+        &self,
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        group_id: &str,
+    ) -> Result<ErrorCode> {
+        let attributes = [KeyValue::new("method", "txn_add_offsets")];
+
+        match self {
+            #[cfg(feature = "dynostore")]
+            Self::DynoStore(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
+
+            #[cfg(feature = "libsql")]
+            Self::Lite(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
+
+            Self::Null(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
+
+            Self::Routed(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.register_broker(broker_registration),
+            Self::Postgres(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.register_broker(broker_registration),
+            Self::Slate(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.register_broker(broker_registration),
+            Self::Turso(engine) => {
+                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            }
         }
         .await
         .inspect(|_| {
@@ -1911,29 +4326,31 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn incremental_alter_resource(
+    async fn txn_add_partitions(
         &self,
-        resource: AlterConfigsResource,
-    ) -> Result<AlterConfigsResourceResponse> {
-        let attributes = [KeyValue::new("method", "incremental_alter_resource")];
+        partitions: TxnAddPartitionsRequest,
+    ) -> Result<TxnAddPartitionsResponse> {
+        let attributes = [KeyValue::new("method", "txn_add_partitions")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.incremental_alter_resource(resource),
+            Self::DynoStore(engine) => engine.txn_add_partitions(partitions),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.incremental_alter_resource(resource),
+            Self::Lite(engine) => engine.txn_add_partitions(partitions),
 
-            Self::Null(engine) => engine.incremental_alter_resource(resource),
+            Self::Null(engine) => engine.txn_add_partitions(partitions),
+
+            Self::Routed(engine) => engine.txn_add_partitions(partitions),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.incremental_alter_resource(resource),
+            Self::Postgres(engine) => engine.txn_add_partitions(partitions),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.incremental_alter_resource(resource),
+            Self::Slate(engine) => engine.txn_add_partitions(partitions),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.incremental_alter_resource(resource),
+            Self::Turso(engine) => engine.txn_add_partitions(partitions),
         }
         .await
         .inspect(|_| {
@@ -1945,26 +4362,31 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn create_topic(&self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid> {
-        let attributes = [KeyValue::new("method", "create_topic")];
+    async fn txn_offset_commit(
+        &self,
+        offsets: TxnOffsetCommitRequest,
+    ) -> Result<Vec<TxnOffsetCommitResponseTopic>> {
+        let attributes = [KeyValue::new("method", "txn_offset_commit")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.create_topic(topic, validate_only),
+            Self::DynoStore(engine) => engine.txn_offset_commit(offsets),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.create_topic(topic, validate_only),
+            Self::Lite(engine) => engine.txn_offset_commit(offsets),
 
-            Self::Null(engine) => engine.create_topic(topic, validate_only),
+            Self::Null(engine) => engine.txn_offset_commit(offsets),
 
-            #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.create_topic(topic, validate_only),
+            Self::Routed(engine) => engine.txn_offset_commit(offsets),
 
-            #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.create_topic(topic, validate_only),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(engine) => engine.txn_offset_commit(offsets),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.create_topic(topic, validate_only),
+            Self::Slate(engine) => engine.txn_offset_commit(offsets),
+
+            #[cfg(feature = "turso")]
+            Self::Turso(engine) => engine.txn_offset_commit(offsets),
         }
         .await
         .inspect(|_| {
@@ -1976,29 +4398,48 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn delete_records(
+    async fn txn_end(
         &self,
-        topics: &[DeleteRecordsTopic],
-    ) -> Result<Vec<DeleteRecordsTopicResult>> {
-        let attributes = [KeyValue::new("method", "delete_records")];
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        committed: bool,
+    ) -> Result<ErrorCode> {
+        let attributes = [KeyValue::new("method", "txn_end")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.delete_records(topics),
+            Self::DynoStore(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.delete_records(topics),
+            Self::Lite(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
 
-            Self::Null(engine) => engine.delete_records(topics),
+            Self::Null(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
+
+            Self::Routed(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.delete_records(topics),
+            Self::Postgres(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.delete_records(topics),
+            Self::Slate(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.delete_records(topics),
+            Self::Turso(engine) => {
+                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
+            }
         }
         .await
         .inspect(|_| {
@@ -2010,26 +4451,47 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn delete_topic(&self, topic: &TopicId) -> Result<ErrorCode> {
-        let attributes = [KeyValue::new("method", "delete_topic")];
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        let attributes = [KeyValue::new("method", "upsert_user_scram_credential")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.delete_topic(topic),
+            Self::DynoStore(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.delete_topic(topic),
+            Self::Lite(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
 
-            Self::Null(engine) => engine.delete_topic(topic),
+            Self::Null(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
+
+            Self::Routed(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.delete_topic(topic),
+            Self::Postgres(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.delete_topic(topic),
+            Self::Slate(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.delete_topic(topic),
+            Self::Turso(engine) => {
+                engine.upsert_user_scram_credential(username, mechanism, credential)
+            }
         }
         .await
         .inspect(|_| {
@@ -2041,26 +4503,32 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn brokers(&self) -> Result<Vec<DescribeClusterBroker>> {
-        let attributes = [KeyValue::new("method", "brokers")];
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        let attributes = [KeyValue::new("method", "delete_user_scram_credential")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.brokers(),
+            Self::DynoStore(engine) => engine.delete_user_scram_credential(username, mechanism),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.brokers(),
+            Self::Lite(engine) => engine.delete_user_scram_credential(username, mechanism),
 
-            Self::Null(engine) => engine.brokers(),
+            Self::Null(engine) => engine.delete_user_scram_credential(username, mechanism),
+
+            Self::Routed(engine) => engine.delete_user_scram_credential(username, mechanism),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.brokers(),
+            Self::Postgres(engine) => engine.delete_user_scram_credential(username, mechanism),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.brokers(),
+            Self::Slate(engine) => engine.delete_user_scram_credential(username, mechanism),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.brokers(),
+            Self::Turso(engine) => engine.delete_user_scram_credential(username, mechanism),
         }
         .await
         .inspect(|_| {
@@ -2072,31 +4540,31 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn produce(
+    async fn user_scram_credentials(
         &self,
-        transaction_id: Option<&str>,
-        topition: &Topition,
-        batch: deflated::Batch,
-    ) -> Result<i64> {
-        let attributes = [KeyValue::new("method", "produce")];
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        let attributes = [KeyValue::new("method", "user_scram_credentials")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.produce(transaction_id, topition, batch),
+            Self::DynoStore(engine) => engine.user_scram_credentials(usernames),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.produce(transaction_id, topition, batch),
+            Self::Lite(engine) => engine.user_scram_credentials(usernames),
 
-            Self::Null(engine) => engine.produce(transaction_id, topition, batch),
+            Self::Null(engine) => engine.user_scram_credentials(usernames),
+
+            Self::Routed(engine) => engine.user_scram_credentials(usernames),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.produce(transaction_id, topition, batch),
+            Self::Postgres(engine) => engine.user_scram_credentials(usernames),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.produce(transaction_id, topition, batch),
+            Self::Slate(engine) => engine.user_scram_credentials(usernames),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.produce(transaction_id, topition, batch),
+            Self::Turso(engine) => engine.user_scram_credentials(usernames),
         }
         .await
         .inspect(|_| {
@@ -2107,69 +4575,56 @@ impl Storage for StorageContainer {
         })
     }
 
-    #[instrument(skip_all)]
-    async fn fetch(
-        &self,
-        topition: &'_ Topition,
-        offset: i64,
-        min_bytes: u32,
-        max_bytes: u32,
-        isolation: IsolationLevel,
-    ) -> Result<Vec<deflated::Batch>> {
-        let attributes = [KeyValue::new("method", "fetch")];
-
+    fn scram_credentials(&self) -> bool {
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => {
-                engine.fetch(topition, offset, min_bytes, max_bytes, isolation)
-            }
+            Self::DynoStore(engine) => engine.scram_credentials(),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+            Self::Lite(engine) => engine.scram_credentials(),
 
-            Self::Null(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+            Self::Null(engine) => engine.scram_credentials(),
+
+            Self::Routed(engine) => engine.scram_credentials(),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => {
-                engine.fetch(topition, offset, min_bytes, max_bytes, isolation)
-            }
+            Self::Postgres(engine) => engine.scram_credentials(),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+            Self::Slate(engine) => engine.scram_credentials(),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.fetch(topition, offset, min_bytes, max_bytes, isolation),
+            Self::Turso(engine) => engine.scram_credentials(),
         }
-        .await
-        .inspect(|_| {
-            STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
-        })
-        .inspect_err(|_| {
-            STORAGE_CONTAINER_ERRORS.add(1, &attributes);
-        })
     }
 
     #[instrument(skip_all)]
-    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage> {
-        let attributes = [KeyValue::new("method", "offset_stage")];
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        let attributes = [KeyValue::new("method", "checkpoint_offset_translation")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.offset_stage(topition),
+            Self::DynoStore(engine) => engine.checkpoint_offset_translation(topition, translation),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.offset_stage(topition),
+            Self::Lite(engine) => engine.checkpoint_offset_translation(topition, translation),
 
-            Self::Null(engine) => engine.offset_stage(topition),
+            Self::Null(engine) => engine.checkpoint_offset_translation(topition, translation),
+
+            Self::Routed(engine) => engine.checkpoint_offset_translation(topition, translation),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.offset_stage(topition),
+            Self::Postgres(engine) => engine.checkpoint_offset_translation(topition, translation),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.offset_stage(topition),
+            Self::Slate(engine) => engine.checkpoint_offset_translation(topition, translation),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.offset_stage(topition),
+            Self::Turso(engine) => engine.checkpoint_offset_translation(topition, translation),
         }
         .await
         .inspect(|_| {
@@ -2181,30 +4636,32 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn list_offsets(
+    async fn offset_translation(
         &self,
-        isolation_level: IsolationLevel,
-        offsets: &[(Topition, ListOffset)],
-    ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
-        let attributes = [KeyValue::new("method", "list_offsets")];
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        let attributes = [KeyValue::new("method", "offset_translation")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.list_offsets(isolation_level, offsets),
+            Self::DynoStore(engine) => engine.offset_translation(topition, upstream),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.list_offsets(isolation_level, offsets),
+            Self::Lite(engine) => engine.offset_translation(topition, upstream),
 
-            Self::Null(engine) => engine.list_offsets(isolation_level, offsets),
+            Self::Null(engine) => engine.offset_translation(topition, upstream),
+
+            Self::Routed(engine) => engine.offset_translation(topition, upstream),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.list_offsets(isolation_level, offsets),
+            Self::Postgres(engine) => engine.offset_translation(topition, upstream),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.list_offsets(isolation_level, offsets),
+            Self::Slate(engine) => engine.offset_translation(topition, upstream),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.list_offsets(isolation_level, offsets),
+            Self::Turso(engine) => engine.offset_translation(topition, upstream),
         }
         .await
         .inspect(|_| {
@@ -2216,31 +4673,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn offset_commit(
-        &self,
-        group_id: &str,
-        retention_time_ms: Option<Duration>,
-        offsets: &[(Topition, OffsetCommitRequest)],
-    ) -> Result<Vec<(Topition, ErrorCode)>> {
-        let attributes = [KeyValue::new("method", "offset_commit")];
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        let attributes = [KeyValue::new("method", "topition_usage")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+            Self::DynoStore(engine) => engine.topition_usage(topics),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+            Self::Lite(engine) => engine.topition_usage(topics),
 
-            Self::Null(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+            Self::Null(engine) => engine.topition_usage(topics),
+
+            Self::Routed(engine) => engine.topition_usage(topics),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+            Self::Postgres(engine) => engine.topition_usage(topics),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+            Self::Slate(engine) => engine.topition_usage(topics),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.offset_commit(group_id, retention_time_ms, offsets),
+            Self::Turso(engine) => engine.topition_usage(topics),
         }
         .await
         .inspect(|_| {
@@ -2252,26 +4706,34 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn committed_offset_topitions(&self, group_id: &str) -> Result<BTreeMap<Topition, i64>> {
-        let attributes = [KeyValue::new("method", "committed_offset_topitions")];
+    async fn direct_reads(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        limit: usize,
+        expires_in: Duration,
+    ) -> Result<Vec<DirectRead>> {
+        let attributes = [KeyValue::new("method", "direct_reads")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.committed_offset_topitions(group_id),
+            Self::DynoStore(engine) => engine.direct_reads(topition, offset, limit, expires_in),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.committed_offset_topitions(group_id),
+            Self::Lite(engine) => engine.direct_reads(topition, offset, limit, expires_in),
 
-            Self::Null(engine) => engine.committed_offset_topitions(group_id),
+            Self::Null(engine) => engine.direct_reads(topition, offset, limit, expires_in),
+
+            Self::Routed(engine) => engine.direct_reads(topition, offset, limit, expires_in),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.committed_offset_topitions(group_id),
+            Self::Postgres(engine) => engine.direct_reads(topition, offset, limit, expires_in),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.committed_offset_topitions(group_id),
+            Self::Slate(engine) => engine.direct_reads(topition, offset, limit, expires_in),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.committed_offset_topitions(group_id),
+            Self::Turso(engine) => engine.direct_reads(topition, offset, limit, expires_in),
         }
         .await
         .inspect(|_| {
@@ -2283,31 +4745,32 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn offset_fetch(
+    async fn delete_expired_offsets(
         &self,
-        group_id: Option<&str>,
-        topics: &[Topition],
-        require_stable: Option<bool>,
-    ) -> Result<BTreeMap<Topition, i64>> {
-        let attributes = [KeyValue::new("method", "offset_fetch")];
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        let attributes = [KeyValue::new("method", "delete_expired_offsets")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.offset_fetch(group_id, topics, require_stable),
+            Self::DynoStore(engine) => engine.delete_expired_offsets(group_id, retention),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.offset_fetch(group_id, topics, require_stable),
+            Self::Lite(engine) => engine.delete_expired_offsets(group_id, retention),
 
-            Self::Null(engine) => engine.offset_fetch(group_id, topics, require_stable),
+            Self::Null(engine) => engine.delete_expired_offsets(group_id, retention),
+
+            Self::Routed(engine) => engine.delete_expired_offsets(group_id, retention),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.offset_fetch(group_id, topics, require_stable),
+            Self::Postgres(engine) => engine.delete_expired_offsets(group_id, retention),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.offset_fetch(group_id, topics, require_stable),
+            Self::Slate(engine) => engine.delete_expired_offsets(group_id, retention),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.offset_fetch(group_id, topics, require_stable),
+            Self::Turso(engine) => engine.delete_expired_offsets(group_id, retention),
         }
         .await
         .inspect(|_| {
@@ -2319,26 +4782,31 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn metadata(&self, topics: Option<&[TopicId]>) -> Result<MetadataResponse> {
-        let attributes = [KeyValue::new("method", "metadata")];
+    async fn describe_txns(
+        &self,
+        transactional_ids: Option<&[String]>,
+    ) -> Result<Vec<TxnDescription>> {
+        let attributes = [KeyValue::new("method", "describe_txns")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.metadata(topics),
+            Self::DynoStore(engine) => engine.describe_txns(transactional_ids),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.metadata(topics),
+            Self::Lite(engine) => engine.describe_txns(transactional_ids),
 
-            Self::Null(engine) => engine.metadata(topics),
+            Self::Null(engine) => engine.describe_txns(transactional_ids),
+
+            Self::Routed(engine) => engine.describe_txns(transactional_ids),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.metadata(topics),
+            Self::Postgres(engine) => engine.describe_txns(transactional_ids),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.metadata(topics),
+            Self::Slate(engine) => engine.describe_txns(transactional_ids),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.metadata(topics),
+            Self::Turso(engine) => engine.describe_txns(transactional_ids),
         }
         .await
         .inspect(|_| {
@@ -2350,31 +4818,33 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn describe_config(
+    async fn aborted_txns(
         &self,
-        name: &str,
-        resource: ConfigResource,
-        keys: Option<&[String]>,
-    ) -> Result<DescribeConfigsResult> {
-        let attributes = [KeyValue::new("method", "describe_config")];
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        let attributes = [KeyValue::new("method", "aborted_txns")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.describe_config(name, resource, keys),
+            Self::DynoStore(engine) => engine.aborted_txns(topition, first_offset, last_offset),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.describe_config(name, resource, keys),
+            Self::Lite(engine) => engine.aborted_txns(topition, first_offset, last_offset),
 
-            Self::Null(engine) => engine.describe_config(name, resource, keys),
+            Self::Null(engine) => engine.aborted_txns(topition, first_offset, last_offset),
+
+            Self::Routed(engine) => engine.aborted_txns(topition, first_offset, last_offset),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.describe_config(name, resource, keys),
+            Self::Postgres(engine) => engine.aborted_txns(topition, first_offset, last_offset),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.describe_config(name, resource, keys),
+            Self::Slate(engine) => engine.aborted_txns(topition, first_offset, last_offset),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.describe_config(name, resource, keys),
+            Self::Turso(engine) => engine.aborted_txns(topition, first_offset, last_offset),
         }
         .await
         .inspect(|_| {
@@ -2386,39 +4856,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn describe_topic_partitions(
-        &self,
-        topics: Option<&[TopicId]>,
-        partition_limit: i32,
-        cursor: Option<Topition>,
-    ) -> Result<Vec<DescribeTopicPartitionsResponseTopic>> {
-        let attributes = [KeyValue::new("method", "describe_topic_partitions")];
+    async fn coordinator_lease(&self, key_type: i8, key: &str) -> Result<CoordinatorLease> {
+        let attributes = [KeyValue::new("method", "coordinator_lease")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => {
-                engine.describe_topic_partitions(topics, partition_limit, cursor)
-            }
+            Self::DynoStore(engine) => engine.coordinator_lease(key_type, key),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.describe_topic_partitions(topics, partition_limit, cursor),
+            Self::Lite(engine) => engine.coordinator_lease(key_type, key),
 
-            Self::Null(engine) => engine.describe_topic_partitions(topics, partition_limit, cursor),
+            Self::Null(engine) => engine.coordinator_lease(key_type, key),
+
+            Self::Routed(engine) => engine.coordinator_lease(key_type, key),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => {
-                engine.describe_topic_partitions(topics, partition_limit, cursor)
-            }
+            Self::Postgres(engine) => engine.coordinator_lease(key_type, key),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => {
-                engine.describe_topic_partitions(topics, partition_limit, cursor)
-            }
+            Self::Slate(engine) => engine.coordinator_lease(key_type, key),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => {
-                engine.describe_topic_partitions(topics, partition_limit, cursor)
-            }
+            Self::Turso(engine) => engine.coordinator_lease(key_type, key),
         }
         .await
         .inspect(|_| {
@@ -2430,26 +4889,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn list_groups(&self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>> {
-        let attributes = [KeyValue::new("method", "list_groups")];
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        let attributes = [KeyValue::new("method", "create_acl")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.list_groups(states_filter),
+            Self::DynoStore(engine) => engine.create_acl(binding),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.list_groups(states_filter),
+            Self::Lite(engine) => engine.create_acl(binding),
 
-            Self::Null(engine) => engine.list_groups(states_filter),
+            Self::Null(engine) => engine.create_acl(binding),
+
+            Self::Routed(engine) => engine.create_acl(binding),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.list_groups(states_filter),
+            Self::Postgres(engine) => engine.create_acl(binding),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.list_groups(states_filter),
+            Self::Slate(engine) => engine.create_acl(binding),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.list_groups(states_filter),
+            Self::Turso(engine) => engine.create_acl(binding),
         }
         .await
         .inspect(|_| {
@@ -2461,29 +4922,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn delete_groups(
-        &self,
-        group_ids: Option<&[String]>,
-    ) -> Result<Vec<DeletableGroupResult>> {
-        let attributes = [KeyValue::new("method", "delete_groups")];
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        let attributes = [KeyValue::new("method", "delete_acl")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.delete_groups(group_ids),
+            Self::DynoStore(engine) => engine.delete_acl(binding),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.delete_groups(group_ids),
+            Self::Lite(engine) => engine.delete_acl(binding),
 
-            Self::Null(engine) => engine.delete_groups(group_ids),
+            Self::Null(engine) => engine.delete_acl(binding),
+
+            Self::Routed(engine) => engine.delete_acl(binding),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.delete_groups(group_ids),
+            Self::Postgres(engine) => engine.delete_acl(binding),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.delete_groups(group_ids),
+            Self::Slate(engine) => engine.delete_acl(binding),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.delete_groups(group_ids),
+            Self::Turso(engine) => engine.delete_acl(binding),
         }
         .await
         .inspect(|_| {
@@ -2495,34 +4955,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn describe_groups(
-        &self,
-        group_ids: Option<&[String]>,
-        include_authorized_operations: bool,
-    ) -> Result<Vec<NamedGroupDetail>> {
-        let attributes = [KeyValue::new("method", "describe_groups")];
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        let attributes = [KeyValue::new("method", "acls")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => {
-                engine.describe_groups(group_ids, include_authorized_operations)
-            }
+            Self::DynoStore(engine) => engine.acls(),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+            Self::Lite(engine) => engine.acls(),
 
-            Self::Null(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+            Self::Null(engine) => engine.acls(),
+
+            Self::Routed(engine) => engine.acls(),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => {
-                engine.describe_groups(group_ids, include_authorized_operations)
-            }
+            Self::Postgres(engine) => engine.acls(),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+            Self::Slate(engine) => engine.acls(),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.describe_groups(group_ids, include_authorized_operations),
+            Self::Turso(engine) => engine.acls(),
         }
         .await
         .inspect(|_| {
@@ -2534,31 +4988,33 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn update_group(
+    async fn alter_client_quota(
         &self,
-        group_id: &str,
-        detail: GroupDetail,
-        version: Option<Version>,
-    ) -> Result<Version, UpdateError<GroupDetail>> {
-        let attributes = [KeyValue::new("method", "update_group")];
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        let attributes = [KeyValue::new("method", "alter_client_quota")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.update_group(group_id, detail, version),
+            Self::DynoStore(engine) => engine.alter_client_quota(entity, key, value),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.update_group(group_id, detail, version),
+            Self::Lite(engine) => engine.alter_client_quota(entity, key, value),
 
-            Self::Null(engine) => engine.update_group(group_id, detail, version),
+            Self::Null(engine) => engine.alter_client_quota(entity, key, value),
+
+            Self::Routed(engine) => engine.alter_client_quota(entity, key, value),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.update_group(group_id, detail, version),
+            Self::Postgres(engine) => engine.alter_client_quota(entity, key, value),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.update_group(group_id, detail, version),
+            Self::Slate(engine) => engine.alter_client_quota(entity, key, value),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.update_group(group_id, detail, version),
+            Self::Turso(engine) => engine.alter_client_quota(entity, key, value),
         }
         .await
         .inspect(|_| {
@@ -2570,62 +5026,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn init_producer(
-        &self,
-        transaction_id: Option<&str>,
-        transaction_timeout_ms: i32,
-        producer_id: Option<i64>,
-        producer_epoch: Option<i16>,
-    ) -> Result<ProducerIdResponse> {
-        let attributes = [KeyValue::new("method", "init_producer")];
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        let attributes = [KeyValue::new("method", "client_quotas")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.init_producer(
-                transaction_id,
-                transaction_timeout_ms,
-                producer_id,
-                producer_epoch,
-            ),
+            Self::DynoStore(engine) => engine.client_quotas(),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.init_producer(
-                transaction_id,
-                transaction_timeout_ms,
-                producer_id,
-                producer_epoch,
-            ),
+            Self::Lite(engine) => engine.client_quotas(),
 
-            Self::Null(engine) => engine.init_producer(
-                transaction_id,
-                transaction_timeout_ms,
-                producer_id,
-                producer_epoch,
-            ),
+            Self::Null(engine) => engine.client_quotas(),
+
+            Self::Routed(engine) => engine.client_quotas(),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.init_producer(
-                transaction_id,
-                transaction_timeout_ms,
-                producer_id,
-                producer_epoch,
-            ),
+            Self::Postgres(engine) => engine.client_quotas(),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.init_producer(
-                transaction_id,
-                transaction_timeout_ms,
-                producer_id,
-                producer_epoch,
-            ),
+            Self::Slate(engine) => engine.client_quotas(),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.init_producer(
-                transaction_id,
-                transaction_timeout_ms,
-                producer_id,
-                producer_epoch,
-            ),
+            Self::Turso(engine) => engine.client_quotas(),
         }
         .await
         .inspect(|_| {
@@ -2637,44 +5059,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn txn_add_offsets(
-        &self,
-        transaction_id: &str,
-        producer_id: i64,
-        producer_epoch: i16,
-        group_id: &str,
-    ) -> Result<ErrorCode> {
-        let attributes = [KeyValue::new("method", "txn_add_offsets")];
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        let attributes = [KeyValue::new("method", "update_feature")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => {
-                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
-            }
+            Self::DynoStore(engine) => engine.update_feature(name, level),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => {
-                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
-            }
+            Self::Lite(engine) => engine.update_feature(name, level),
 
-            Self::Null(engine) => {
-                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
-            }
+            Self::Null(engine) => engine.update_feature(name, level),
+
+            Self::Routed(engine) => engine.update_feature(name, level),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => {
-                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
-            }
+            Self::Postgres(engine) => engine.update_feature(name, level),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => {
-                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
-            }
+            Self::Slate(engine) => engine.update_feature(name, level),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => {
-                engine.txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
-            }
+            Self::Turso(engine) => engine.update_feature(name, level),
         }
         .await
         .inspect(|_| {
@@ -2686,29 +5092,28 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn txn_add_partitions(
-        &self,
-        partitions: TxnAddPartitionsRequest,
-    ) -> Result<TxnAddPartitionsResponse> {
-        let attributes = [KeyValue::new("method", "txn_add_partitions")];
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        let attributes = [KeyValue::new("method", "finalized_features")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.txn_add_partitions(partitions),
+            Self::DynoStore(engine) => engine.finalized_features(),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.txn_add_partitions(partitions),
+            Self::Lite(engine) => engine.finalized_features(),
 
-            Self::Null(engine) => engine.txn_add_partitions(partitions),
+            Self::Null(engine) => engine.finalized_features(),
+
+            Self::Routed(engine) => engine.finalized_features(),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.txn_add_partitions(partitions),
+            Self::Postgres(engine) => engine.finalized_features(),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.txn_add_partitions(partitions),
+            Self::Slate(engine) => engine.finalized_features(),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.txn_add_partitions(partitions),
+            Self::Turso(engine) => engine.finalized_features(),
         }
         .await
         .inspect(|_| {
@@ -2720,78 +5125,63 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn txn_offset_commit(
-        &self,
-        offsets: TxnOffsetCommitRequest,
-    ) -> Result<Vec<TxnOffsetCommitResponseTopic>> {
-        let attributes = [KeyValue::new("method", "txn_offset_commit")];
+    async fn maintain(&self, now: SystemTime) -> Result<()> {
+        let attributes = [KeyValue::new("method", "maintain")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.txn_offset_commit(offsets),
+            Self::DynoStore(engine) => engine.maintain(now),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.txn_offset_commit(offsets),
+            Self::Lite(engine) => engine.maintain(now),
 
-            Self::Null(engine) => engine.txn_offset_commit(offsets),
+            Self::Null(engine) => engine.maintain(now),
+
+            Self::Routed(engine) => engine.maintain(now),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.txn_offset_commit(offsets),
+            Self::Postgres(engine) => engine.maintain(now),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.txn_offset_commit(offsets),
+            Self::Slate(engine) => engine.maintain(now),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.txn_offset_commit(offsets),
+            Self::Turso(engine) => engine.maintain(now),
         }
         .await
-        .inspect(|_| {
+        .inspect(|maintain| {
+            debug!(?maintain);
             STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
         })
-        .inspect_err(|_| {
+        .inspect_err(|err| {
+            debug!(?err);
             STORAGE_CONTAINER_ERRORS.add(1, &attributes);
         })
     }
 
     #[instrument(skip_all)]
-    async fn txn_end(
-        &self,
-        transaction_id: &str,
-        producer_id: i64,
-        producer_epoch: i16,
-        committed: bool,
-    ) -> Result<ErrorCode> {
-        let attributes = [KeyValue::new("method", "txn_end")];
+    async fn compact(&self, topition: &Topition, end_offset: i64) -> Result<u64> {
+        let attributes = [KeyValue::new("method", "compact")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => {
-                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
-            }
+            Self::DynoStore(engine) => engine.compact(topition, end_offset),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => {
-                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
-            }
+            Self::Lite(engine) => engine.compact(topition, end_offset),
 
-            Self::Null(engine) => {
-                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
-            }
+            Self::Null(engine) => engine.compact(topition, end_offset),
+
+            Self::Routed(engine) => engine.compact(topition, end_offset),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => {
-                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
-            }
+            Self::Postgres(engine) => engine.compact(topition, end_offset),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => {
-                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
-            }
+            Self::Slate(engine) => engine.compact(topition, end_offset),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => {
-                engine.txn_end(transaction_id, producer_id, producer_epoch, committed)
-            }
+            Self::Turso(engine) => engine.compact(topition, end_offset),
         }
         .await
         .inspect(|_| {
@@ -2803,34 +5193,34 @@ impl Storage for StorageContainer {
     }
 
     #[instrument(skip_all)]
-    async fn maintain(&self, now: SystemTime) -> Result<()> {
-        let attributes = [KeyValue::new("method", "maintain")];
+    async fn preflight(&self) -> Result<Vec<PreflightCheck>> {
+        let attributes = [KeyValue::new("method", "preflight")];
 
         match self {
             #[cfg(feature = "dynostore")]
-            Self::DynoStore(engine) => engine.maintain(now),
+            Self::DynoStore(engine) => engine.preflight(),
 
             #[cfg(feature = "libsql")]
-            Self::Lite(engine) => engine.maintain(now),
+            Self::Lite(engine) => engine.preflight(),
 
-            Self::Null(engine) => engine.maintain(now),
+            Self::Null(engine) => engine.preflight(),
+
+            Self::Routed(engine) => engine.preflight(),
 
             #[cfg(feature = "postgres")]
-            Self::Postgres(engine) => engine.maintain(now),
+            Self::Postgres(engine) => engine.preflight(),
 
             #[cfg(feature = "slatedb")]
-            Self::Slate(engine) => engine.maintain(now),
+            Self::Slate(engine) => engine.preflight(),
 
             #[cfg(feature = "turso")]
-            Self::Turso(engine) => engine.maintain(now),
+            Self::Turso(engine) => engine.preflight(),
         }
         .await
-        .inspect(|maintain| {
-            debug!(?maintain);
+        .inspect(|_| {
             STORAGE_CONTAINER_REQUESTS.add(1, &attributes);
         })
-        .inspect_err(|err| {
-            debug!(?err);
+        .inspect_err(|_| {
             STORAGE_CONTAINER_ERRORS.add(1, &attributes);
         })
     }
@@ -2846,6 +5236,8 @@ impl Storage for StorageContainer {
 
             Self::Null(engine) => engine.cluster_id().await,
 
+            Self::Routed(engine) => engine.cluster_id().await,
+
             #[cfg(feature = "postgres")]
             Self::Postgres(engine) => engine.cluster_id().await,
 
@@ -2868,6 +5260,8 @@ impl Storage for StorageContainer {
 
             Self::Null(engine) => engine.node().await,
 
+            Self::Routed(engine) => engine.node().await,
+
             #[cfg(feature = "postgres")]
             Self::Postgres(engine) => engine.node().await,
 
@@ -2890,6 +5284,8 @@ impl Storage for StorageContainer {
 
             Self::Null(engine) => engine.advertised_listener().await,
 
+            Self::Routed(engine) => engine.advertised_listener().await,
+
             #[cfg(feature = "postgres")]
             Self::Postgres(engine) => engine.advertised_listener().await,
 
@@ -2914,6 +5310,8 @@ impl Storage for StorageContainer {
 
             Self::Null(engine) => engine.ping(),
 
+            Self::Routed(engine) => engine.ping(),
+
             #[cfg(feature = "postgres")]
             Self::Postgres(engine) => engine.ping(),
 
@@ -2945,6 +5343,14 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn sequence_wraps_after_max() {
+        assert_eq!(3, next_sequence(0, 3));
+        assert_eq!(i32::MAX, next_sequence(i32::MAX - 3, 3));
+        assert_eq!(0, next_sequence(i32::MAX, 1));
+        assert_eq!(1, next_sequence(i32::MAX - 1, 3));
+    }
+
     #[test]
     fn topic_with_dashes_in_name() -> Result<()> {
         let topition = Topition::from_str("test-topic-0000000-eFC79C8-2147483647")?;
@@ -2952,4 +5358,91 @@ mod tests {
         assert_eq!(i32::MAX, topition.partition());
         Ok(())
     }
+
+    #[test]
+    fn usage_add() {
+        let produced = Usage {
+            records: 3,
+            bytes: 96,
+            oldest_timestamp: Some(1_000),
+            newest_timestamp: Some(2_000),
+        };
+
+        assert_eq!(produced, Usage::default().add(produced));
+
+        assert_eq!(
+            Usage {
+                records: 5,
+                bytes: 128,
+                oldest_timestamp: Some(500),
+                newest_timestamp: Some(2_000),
+            },
+            produced.add(Usage {
+                records: 2,
+                bytes: 32,
+                oldest_timestamp: Some(500),
+                newest_timestamp: Some(1_500),
+            })
+        );
+    }
+
+    #[test]
+    fn described_group_members() {
+        let metadata = Bytes::from_static(b"subscription");
+        let assignment = Bytes::from_static(b"assignment");
+
+        let detail = NamedGroupDetail::found(
+            "abc".into(),
+            GroupDetail {
+                members: BTreeMap::from([(
+                    "m1".into(),
+                    GroupMember {
+                        join_response: JoinGroupResponseMember::default()
+                            .member_id("m1".into())
+                            .group_instance_id(Some("i1".into()))
+                            .metadata(metadata.clone()),
+                        last_contact: None,
+                    },
+                )]),
+                state: GroupState::Formed {
+                    protocol_type: "consumer".into(),
+                    protocol_name: "range".into(),
+                    leader: "m1".into(),
+                    assignments: BTreeMap::from([("m1".into(), assignment.clone())]),
+                },
+                ..Default::default()
+            },
+        );
+
+        let described = describe_groups_response::DescribedGroup::from(&detail);
+        assert_eq!("range", described.protocol_data);
+
+        let members = described.members.unwrap_or_default();
+        assert_eq!(1, members.len());
+        assert_eq!(Some("i1"), members[0].group_instance_id.as_deref());
+        assert_eq!(metadata, members[0].member_metadata);
+        assert_eq!(assignment, members[0].member_assignment);
+    }
+
+    #[test]
+    fn consumer_protocol_metadata() {
+        let member = ConsumerGroupMember {
+            subscribed_topic_names: vec!["t".into()],
+            assignment: BTreeMap::from([("t".into(), BTreeSet::from([0, 1]))]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Bytes::from_static(&[0, 0, 0, 0, 0, 1, 0, 1, b't', 255, 255, 255, 255]),
+            member.subscription_metadata()
+        );
+
+        assert_eq!(
+            Bytes::from_static(&[
+                0, 0, 0, 0, 0, 1, 0, 1, b't', 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 255, 255, 255,
+                255
+            ]),
+            member.assignment_metadata()
+        );
+    }
 }
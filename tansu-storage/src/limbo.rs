@@ -25,10 +25,12 @@ use std::{
 };
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, ListOffsetRequest, ListOffsetResponse, METER,
-    MetadataResponse, NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse,
-    Result, Storage, TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
-    TxnOffsetCommitRequest, TxnState, UpdateError, Version,
+    AclBinding, BrokerRegistrationRequest, ClientQuota, ClientQuotaEntity, Error,
+    FinalizedFeatures, GroupDetail, ListOffsetRequest, ListOffsetResponse, METER, MetadataResponse,
+    NamedGroupDetail, OffsetCommitRequest, OffsetStage, OffsetTranslation, ProducerIdResponse,
+    Result, ScramCredential, ScramMechanism, Storage, TopicId, Topition, TopitionUsage,
+    TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest, TxnState,
+    UpdateError, Usage, Version,
     sql::{Cache, default_hash, idempotent_sequence_check, remove_comments},
 };
 use async_trait::async_trait;
@@ -41,8 +43,9 @@ use opentelemetry::{
 use rand::{rng, seq::SliceRandom as _};
 use regex::Regex;
 use tansu_sans_io::{
-    BatchAttribute, ConfigResource, ConfigSource, ConfigType, ControlBatch, EndTransactionMarker,
-    ErrorCode, IsolationLevel, NULL_TOPIC_ID, OpType,
+    AclOperation, AclPatternType, AclPermissionType, AclResourceType, BatchAttribute,
+    ConfigResource, ConfigSource, ConfigType, ControlBatch, EndTransactionMarker, ErrorCode,
+    IsolationLevel, NULL_TOPIC_ID, OpType,
     add_partitions_to_txn_response::{
         AddPartitionsToTxnPartitionResult, AddPartitionsToTxnTopicResult,
     },
@@ -55,6 +58,7 @@ use tansu_sans_io::{
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
+    fetch_response::AbortedTransaction,
     incremental_alter_configs_request::AlterConfigsResource,
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     list_groups_response::ListedGroup,
@@ -413,9 +417,9 @@ impl Engine {
                 sequence,
             );
 
-            let increment = idempotent_sequence_check(&current_epoch, &sequence, deflated)?;
+            let next = idempotent_sequence_check(&current_epoch, &sequence, deflated)?;
 
-            debug!(increment);
+            debug!(next);
 
             assert_eq!(
                 1,
@@ -428,7 +432,7 @@ impl Engine {
                         topition.partition(),
                         deflated.producer_id,
                         deflated.producer_epoch,
-                        increment,
+                        next,
                     ),
                 )
                 .await?
@@ -605,6 +609,26 @@ impl Engine {
             .inspect(|n| debug!(?n))
             .inspect_err(|err| error!(?err))?;
 
+        let usage = Usage::of(&inflated);
+
+        _ = self
+            .prepare_execute(
+                tx,
+                &sql_lookup("topition_usage_upsert.sql")?,
+                (
+                    self.cluster.as_str(),
+                    topic,
+                    partition,
+                    usage.records,
+                    usage.bytes,
+                    usage.oldest_timestamp,
+                    usage.newest_timestamp,
+                ),
+            )
+            .await
+            .inspect(|n| debug!(?n))
+            .inspect_err(|err| error!(?err))?;
+
         if !attributes.control
             && let Some(ref lake) = self.lake
         {
@@ -671,28 +695,15 @@ impl Engine {
 
             debug!(?topition);
 
-            let control_batch: Bytes = if committed {
-                ControlBatch::default().commit().try_into()?
+            let control = if committed {
+                ControlBatch::default().commit()
             } else {
-                ControlBatch::default().abort().try_into()?
+                ControlBatch::default().abort()
             };
-            let end_transaction_marker: Bytes = EndTransactionMarker::default().try_into()?;
 
-            let batch = inflated::Batch::builder()
-                .record(
-                    Record::builder()
-                        .key(control_batch.into())
-                        .value(end_transaction_marker.into()),
-                )
-                .attributes(
-                    BatchAttribute::default()
-                        .control(true)
-                        .transaction(true)
-                        .into(),
-                )
+            let batch = inflated::Batch::control(control, EndTransactionMarker::default())?
                 .producer_id(producer_id)
                 .producer_epoch(producer_epoch)
-                .base_sequence(-1)
                 .build()
                 .and_then(TryInto::try_into)
                 .inspect(|deflated| debug!(?deflated))?;
@@ -780,6 +791,20 @@ impl Engine {
             for txn in txns {
                 debug!(?txn);
 
+                if txn.status == TxnState::PrepareAbort {
+                    _ = tx
+                        .execute(
+                            &sql_lookup("txn_aborted_insert_from_txn.sql")?,
+                            (
+                                self.cluster.as_str(),
+                                txn.name.as_str(),
+                                txn.producer_id,
+                                txn.producer_epoch,
+                            ),
+                        )
+                        .await?;
+                }
+
                 _ = tx
                     .execute(
                         &sql_lookup("txn_produce_offset_delete_by_txn.sql")?,
@@ -969,12 +994,22 @@ impl<C, N, L, D> Builder<C, N, L, D> {
 static DDL: LazyLock<Cache> = LazyLock::new(|| {
     let mapping = [
         ("010-cluster.sql", include_sql!("ddl/010-cluster.sql")),
+        ("020-acl.sql", include_sql!("ddl/020-acl.sql")),
+        (
+            "020-client-quota.sql",
+            include_sql!("ddl/020-client-quota.sql"),
+        ),
+        ("020-feature.sql", include_sql!("ddl/020-feature.sql")),
         (
             "020-consumer-group.sql",
             include_sql!("ddl/020-consumer-group.sql"),
         ),
         ("020-producer.sql", include_sql!("ddl/020-producer.sql")),
         ("020-topic.sql", include_sql!("ddl/020-topic.sql")),
+        (
+            "020-user-scram-credential.sql",
+            include_sql!("ddl/020-user-scram-credential.sql"),
+        ),
         (
             "030-consumer-group-detail.sql",
             include_sql!("ddl/030-consumer-group-detail.sql"),
@@ -994,11 +1029,23 @@ static DDL: LazyLock<Cache> = LazyLock::new(|| {
             include_sql!("ddl/040-consumer-offset.sql"),
         ),
         ("040-header.sql", include_sql!("ddl/040-header.sql")),
+        (
+            "040-offset-translation.sql",
+            include_sql!("ddl/040-offset-translation.sql"),
+        ),
         (
             "040-producer-detail.sql",
             include_sql!("ddl/040-producer-detail.sql"),
         ),
         ("040-record.sql", include_sql!("ddl/040-record.sql")),
+        (
+            "040-topition-usage.sql",
+            include_sql!("ddl/040-topition-usage.sql"),
+        ),
+        (
+            "040-txn-aborted.sql",
+            include_sql!("ddl/040-txn-aborted.sql"),
+        ),
         ("040-txn-detail.sql", include_sql!("ddl/040-txn-detail.sql")),
         ("040-watermark.sql", include_sql!("ddl/040-watermark.sql")),
         (
@@ -1207,6 +1254,74 @@ impl Storage for Engine {
         tx.commit().await.map_err(Into::into).and(Ok(uuid))
     }
 
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        debug!(cluster = self.cluster, topic, count);
+
+        let mut connection = self.connection().await.inspect_err(|err| error!(?err))?;
+
+        let tx = connection.transaction().await?;
+
+        let Some(row) = self
+            .prepare_query_opt(
+                &tx,
+                &sql_lookup("topic_select_name.sql")?,
+                (self.cluster.as_str(), topic),
+            )
+            .await?
+        else {
+            return Ok(ErrorCode::UnknownTopicOrPartition);
+        };
+
+        let current = row
+            .get_value(3)
+            .map_err(Into::into)
+            .and_then(|value| {
+                value
+                    .as_integer()
+                    .copied()
+                    .map(|value| value as i32)
+                    .ok_or(Error::UnexpectedValue(value))
+            })
+            .inspect_err(|err| error!(?err))?;
+
+        if count <= current {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        if self
+            .prepare_execute(
+                &tx,
+                &sql_lookup("topic_update_partitions.sql")?,
+                (self.cluster.as_str(), topic, count),
+            )
+            .await?
+            == 0
+        {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        for partition in current..count {
+            let params = (self.cluster.as_str(), topic, partition);
+
+            _ = self
+                .prepare_query_one(&tx, &sql_lookup("topition_insert.sql")?, params)
+                .await
+                .map(|row| row.get_value(0))
+                .inspect(|topition| debug!(?topition))?;
+
+            _ = self
+                .prepare_query_one(&tx, &sql_lookup("watermark_insert.sql")?, params)
+                .await
+                .map(|row| row.get_value(0))
+                .inspect(|watermark| debug!(?watermark))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(Into::into)
+            .and(Ok(ErrorCode::None))
+    }
+
     async fn delete_records(
         &self,
         topics: &[DeleteRecordsTopic],
@@ -1255,6 +1370,7 @@ impl Storage for Engine {
             "consumer_offset_delete_by_topic.sql",
             "topic_configuration_delete_by_topic.sql",
             "watermark_delete_by_topic.sql",
+            "topition_usage_delete_by_topic.sql",
             "header_delete_by_topic.sql",
             "record_delete_by_topic.sql",
             "txn_offset_commit_tp_delete_by_topic.sql",
@@ -1393,6 +1509,54 @@ impl Storage for Engine {
         tx.commit().await.map_err(Into::into).and(Ok(high))
     }
 
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        debug!(
+            cluster = self.cluster,
+            ?topition,
+            base_offset = batch.base_offset
+        );
+
+        let mut connection = self.connection().await?;
+        let tx = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .await?;
+
+        let (low, high) = self.watermark_select_for_update(topition, &tx).await?;
+        let high = high.unwrap_or_default();
+
+        if batch.base_offset < high {
+            debug!(?topition, base_offset = batch.base_offset, high);
+            return Ok(batch.base_offset);
+        }
+
+        if batch.base_offset > high {
+            // a gap in the upstream offsets, advancing the high watermark so
+            // that the batch retains its base offset
+            debug!(?topition, base_offset = batch.base_offset, high);
+
+            _ = self
+                .prepare_execute(
+                    &tx,
+                    &sql_lookup("watermark_update.sql")?,
+                    (
+                        self.cluster.as_str(),
+                        topition.topic(),
+                        topition.partition(),
+                        low.unwrap_or(batch.base_offset),
+                        batch.base_offset,
+                    ),
+                )
+                .await
+                .inspect_err(|err| error!(?err))?;
+        }
+
+        let offset = self
+            .produce_in_tx(None, topition, batch.without_producer()?, &tx)
+            .await?;
+
+        tx.commit().await.map_err(Into::into).and(Ok(offset))
+    }
+
     async fn fetch(
         &self,
         topition: &Topition,
@@ -1884,6 +2048,122 @@ impl Storage for Engine {
         Ok(results)
     }
 
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        debug!(cluster = self.cluster, ?topition, first_offset, last_offset);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                &sql_lookup("txn_aborted_select.sql")?,
+                (
+                    self.cluster.as_str(),
+                    topition.topic(),
+                    topition.partition(),
+                    first_offset,
+                    last_offset,
+                ),
+            )
+            .await?;
+
+        let integer = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let mut aborted = vec![];
+
+        while let Some(row) = rows.next().await? {
+            aborted.push(
+                AbortedTransaction::default()
+                    .producer_id(row.get_value(0).map_err(Into::into).and_then(integer)?)
+                    .first_offset(row.get_value(1).map_err(Into::into).and_then(integer)?),
+            );
+        }
+
+        Ok(aborted)
+    }
+
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        debug!(cluster = self.cluster, group_id, ?retention);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                &sql_lookup("lite/consumer_offset_last_updated.sql")?,
+                (self.cluster.as_str(), group_id),
+            )
+            .await?;
+
+        let text = |value: Value| {
+            value
+                .as_text()
+                .cloned()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let integer = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let mut expired = vec![];
+
+        while let Some(row) = rows.next().await? {
+            let last_updated = row.get_value(3)?;
+
+            if LiteTimestamp::try_from(last_updated.clone())?
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed > retention)
+            {
+                expired.push((
+                    row.get_value(0).map_err(Into::into).and_then(integer)?,
+                    Topition::new(
+                        row.get_value(1).map_err(Into::into).and_then(text)?,
+                        row.get_value(2)
+                            .map_err(Into::into)
+                            .and_then(integer)
+                            .and_then(|partition| i32::try_from(partition).map_err(Into::into))?,
+                    ),
+                    last_updated,
+                ));
+            }
+        }
+
+        let mut deleted = vec![];
+
+        // an offset committed again since it was read is no longer expired
+        for (id, topition, last_updated) in expired {
+            if self
+                .prepare_execute(
+                    &c,
+                    &sql_lookup("lite/consumer_offset_delete_unchanged.sql")?,
+                    (id, last_updated),
+                )
+                .await?
+                > 0
+            {
+                deleted.push(topition);
+            }
+        }
+
+        Ok(deleted).inspect(|deleted| debug!(group_id, ?deleted))
+    }
+
     async fn offset_fetch(
         &self,
         group_id: Option<&str>,
@@ -2513,8 +2793,8 @@ impl Storage for Engine {
                         .name(name)
                         .value(value)
                         .read_only(false)
-                        .is_default(None)
-                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                        .is_default(Some(false))
+                        .config_source(Some(ConfigSource::DynamicTopicConfig.into()))
                         .is_sensitive(false)
                         .synonyms(Some([].into()))
                         .config_type(Some(ConfigType::String.into()))
@@ -3626,6 +3906,497 @@ impl Storage for Engine {
         Ok(error_code)
     }
 
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        debug!(cluster = self.cluster, username, ?mechanism);
+
+        let c = self.connection().await?;
+
+        self.prepare_execute(
+            &c,
+            &sql_lookup("user_scram_credential_upsert.sql")?,
+            (
+                self.cluster.as_str(),
+                username,
+                i32::from(i8::from(mechanism)),
+                credential.salt.to_vec(),
+                credential.iterations,
+                credential.stored_key.to_vec(),
+                credential.server_key.to_vec(),
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .and(Ok(()))
+    }
+
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        debug!(cluster = self.cluster, username, ?mechanism);
+
+        let c = self.connection().await?;
+
+        self.prepare_query_opt(
+            &c,
+            &sql_lookup("user_scram_credential_delete.sql")?,
+            (
+                self.cluster.as_str(),
+                username,
+                i32::from(i8::from(mechanism)),
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .map(|row| {
+            if row.is_some() {
+                ErrorCode::None
+            } else {
+                ErrorCode::ResourceNotFound
+            }
+        })
+    }
+
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        debug!(cluster = self.cluster, ?usernames);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                &sql_lookup("user_scram_credential_select.sql")?,
+                &[self.cluster.as_str()],
+            )
+            .await?;
+
+        let blob = |value: Value| {
+            value
+                .as_blob()
+                .map(|blob| Bytes::copy_from_slice(blob))
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let integer = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let mut users = BTreeMap::<String, BTreeMap<ScramMechanism, ScramCredential>>::new();
+
+        while let Some(row) = rows.next().await? {
+            let username = row.get_value(0).map_err(Into::into).and_then(|value| {
+                value
+                    .as_text()
+                    .cloned()
+                    .ok_or(Error::UnexpectedValue(value))
+            })?;
+
+            if usernames.is_some_and(|usernames| !usernames.contains(&username)) {
+                continue;
+            }
+
+            let mechanism = row
+                .get_value(1)
+                .map_err(Into::into)
+                .and_then(integer)
+                .and_then(|mechanism| i8::try_from(mechanism).map_err(Into::into))
+                .and_then(ScramMechanism::try_from)?;
+
+            let credential = ScramCredential {
+                salt: row.get_value(2).map_err(Into::into).and_then(blob)?,
+                iterations: row
+                    .get_value(3)
+                    .map_err(Into::into)
+                    .and_then(integer)
+                    .and_then(|iterations| i32::try_from(iterations).map_err(Into::into))?,
+                stored_key: row.get_value(4).map_err(Into::into).and_then(blob)?,
+                server_key: row.get_value(5).map_err(Into::into).and_then(blob)?,
+            };
+
+            _ = users
+                .entry(username)
+                .or_default()
+                .insert(mechanism, credential);
+        }
+
+        Ok(users)
+    }
+
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        debug!(cluster = self.cluster, ?topition, ?translation);
+
+        let c = self.connection().await?;
+
+        self.prepare_execute(
+            &c,
+            &sql_lookup("offset_translation_upsert.sql")?,
+            (
+                self.cluster.as_str(),
+                topition.topic(),
+                topition.partition(),
+                translation.upstream,
+                translation.local,
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .and(Ok(()))
+    }
+
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        debug!(cluster = self.cluster, ?topition, upstream);
+
+        let c = self.connection().await?;
+
+        let integer = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        self.prepare_query_opt(
+            &c,
+            &sql_lookup("offset_translation_select.sql")?,
+            (
+                self.cluster.as_str(),
+                topition.topic(),
+                topition.partition(),
+                upstream,
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))?
+        .map(|row| {
+            Ok(OffsetTranslation::new(
+                row.get_value(0).map_err(Into::into).and_then(integer)?,
+                row.get_value(1).map_err(Into::into).and_then(integer)?,
+            ))
+        })
+        .transpose()
+    }
+
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        debug!(cluster = self.cluster, ?topics);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                &sql_lookup("topition_usage_select.sql")?,
+                &[self.cluster.as_str()],
+            )
+            .await?;
+
+        let text = |value: Value| {
+            value
+                .as_text()
+                .cloned()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let integer = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let mut usage = vec![];
+
+        while let Some(row) = rows.next().await? {
+            let topic = row.get_value(0).map_err(Into::into).and_then(text)?;
+
+            if topics.is_some_and(|topics| !topics.contains(&topic)) {
+                continue;
+            }
+
+            usage.push(TopitionUsage {
+                topition: Topition::new(
+                    topic,
+                    row.get_value(1)
+                        .map_err(Into::into)
+                        .and_then(integer)
+                        .and_then(|partition| i32::try_from(partition).map_err(Into::into))?,
+                ),
+                usage: Usage {
+                    records: row.get_value(2).map_err(Into::into).and_then(integer)?,
+                    bytes: row.get_value(3).map_err(Into::into).and_then(integer)?,
+                    oldest_timestamp: row.get_value(4).map(|value| value.as_integer().copied())?,
+                    newest_timestamp: row.get_value(5).map(|value| value.as_integer().copied())?,
+                },
+            })
+        }
+
+        Ok(usage)
+    }
+
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        debug!(cluster = self.cluster, ?binding);
+
+        let c = self.connection().await?;
+
+        self.prepare_execute(
+            &c,
+            &sql_lookup("acl_insert.sql")?,
+            (
+                self.cluster.as_str(),
+                i32::from(i8::from(binding.resource_type)),
+                binding.resource_name.as_str(),
+                i32::from(i8::from(binding.pattern_type)),
+                binding.principal.as_str(),
+                binding.host.as_str(),
+                i32::from(i8::from(binding.operation)),
+                i32::from(i8::from(binding.permission_type)),
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .and(Ok(()))
+    }
+
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        debug!(cluster = self.cluster, ?binding);
+
+        let c = self.connection().await?;
+
+        self.prepare_query_opt(
+            &c,
+            &sql_lookup("acl_delete.sql")?,
+            (
+                self.cluster.as_str(),
+                i32::from(i8::from(binding.resource_type)),
+                binding.resource_name.as_str(),
+                i32::from(i8::from(binding.pattern_type)),
+                binding.principal.as_str(),
+                binding.host.as_str(),
+                i32::from(i8::from(binding.operation)),
+                i32::from(i8::from(binding.permission_type)),
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .map(|row| {
+            if row.is_some() {
+                ErrorCode::None
+            } else {
+                ErrorCode::ResourceNotFound
+            }
+        })
+    }
+
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(&sql_lookup("acl_select.sql")?, &[self.cluster.as_str()])
+            .await?;
+
+        let text = |value: Value| {
+            value
+                .as_text()
+                .cloned()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let code = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+                .and_then(|code| i8::try_from(code).map_err(Into::into))
+        };
+
+        let mut acls = vec![];
+
+        while let Some(row) = rows.next().await? {
+            acls.push(AclBinding {
+                resource_type: row
+                    .get_value(0)
+                    .map_err(Into::into)
+                    .and_then(code)
+                    .map(AclResourceType::from)?,
+                resource_name: row.get_value(1).map_err(Into::into).and_then(text)?,
+                pattern_type: row
+                    .get_value(2)
+                    .map_err(Into::into)
+                    .and_then(code)
+                    .map(AclPatternType::from)?,
+                principal: row.get_value(3).map_err(Into::into).and_then(text)?,
+                host: row.get_value(4).map_err(Into::into).and_then(text)?,
+                operation: row
+                    .get_value(5)
+                    .map_err(Into::into)
+                    .and_then(code)
+                    .map(AclOperation::from)?,
+                permission_type: row
+                    .get_value(6)
+                    .map_err(Into::into)
+                    .and_then(code)
+                    .map(AclPermissionType::from)?,
+            });
+        }
+
+        Ok(acls)
+    }
+
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        debug!(cluster = self.cluster, ?entity, key, ?value);
+
+        let entity = serde_json::to_string(entity)?;
+
+        let c = self.connection().await?;
+
+        if let Some(value) = value {
+            self.prepare_execute(
+                &c,
+                &sql_lookup("client_quota_upsert.sql")?,
+                (self.cluster.as_str(), entity.as_str(), key, value),
+            )
+            .await
+            .and(Ok(()))
+        } else {
+            self.prepare_query_opt(
+                &c,
+                &sql_lookup("client_quota_delete.sql")?,
+                (self.cluster.as_str(), entity.as_str(), key),
+            )
+            .await
+            .and(Ok(()))
+        }
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+    }
+
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                &sql_lookup("client_quota_select.sql")?,
+                &[self.cluster.as_str()],
+            )
+            .await?;
+
+        let text = |value: Value| {
+            value
+                .as_text()
+                .cloned()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let real = |value: Value| {
+            value
+                .as_real()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let mut quotas = vec![];
+
+        while let Some(row) = rows.next().await? {
+            quotas.push(ClientQuota {
+                entity: row
+                    .get_value(0)
+                    .map_err(Into::into)
+                    .and_then(text)
+                    .and_then(|entity| serde_json::from_str(&entity).map_err(Into::into))?,
+                key: row.get_value(1).map_err(Into::into).and_then(text)?,
+                value: row.get_value(2).map_err(Into::into).and_then(real)?,
+            });
+        }
+
+        Ok(quotas)
+    }
+
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        debug!(cluster = self.cluster, name, level);
+
+        let c = self.connection().await?;
+
+        self.prepare_execute(
+            &c,
+            &sql_lookup("feature_upsert.sql")?,
+            (self.cluster.as_str(), name, i64::from(level)),
+        )
+        .await
+        .and(Ok(()))
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+    }
+
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(&sql_lookup("feature_select.sql")?, &[self.cluster.as_str()])
+            .await?;
+
+        let text = |value: Value| {
+            value
+                .as_text()
+                .cloned()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let integer = |value: Value| {
+            value
+                .as_integer()
+                .copied()
+                .ok_or(Error::UnexpectedValue(value))
+        };
+
+        let mut features = vec![];
+
+        while let Some(row) = rows.next().await? {
+            features.push((
+                row.get_value(0).map_err(Into::into).and_then(text)?,
+                row.get_value(1)
+                    .map_err(Into::into)
+                    .and_then(integer)
+                    .and_then(|level| i16::try_from(level).map_err(Into::into))?,
+                row.get_value(2).map_err(Into::into).and_then(integer)?,
+            ));
+        }
+
+        Ok(features.into_iter().collect())
+    }
+
     async fn maintain(&self, _now: SystemTime) -> Result<()> {
         Ok(())
     }
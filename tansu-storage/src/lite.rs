@@ -25,11 +25,12 @@ use std::{
 };
 
 use crate::{
-    BrokerRegistrationRequest, ChannelRequestLayer, Error, GroupDetail, ListOffsetResponse, METER,
-    MetadataResponse, NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse,
-    RequestChannelService, RequestStorageService, Result, Storage, TopicId, Topition,
-    TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest, TxnState,
-    UpdateError, Version, bounded_channel,
+    AclBinding, BrokerRegistrationRequest, ChannelRequestLayer, ClientQuota, ClientQuotaEntity,
+    Error, FinalizedFeatures, GroupDetail, ListOffsetResponse, METER, MetadataResponse,
+    NamedGroupDetail, OffsetCommitRequest, OffsetStage, OffsetTranslation, ProducerIdResponse,
+    RequestChannelService, RequestStorageService, Result, ScramCredential, ScramMechanism, Storage,
+    TopicId, Topition, TopitionUsage, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
+    TxnOffsetCommitRequest, TxnState, UpdateError, Usage, Version, bounded_channel,
     sql::{Cache, default_hash, idempotent_sequence_check, remove_comments},
 };
 use async_trait::async_trait;
@@ -48,8 +49,9 @@ use rama::{Context, Layer as _, Service as _};
 use rand::{rng, seq::SliceRandom as _};
 use regex::Regex;
 use tansu_sans_io::{
-    BatchAttribute, ConfigResource, ConfigSource, ConfigType, ControlBatch, EndTransactionMarker,
-    ErrorCode, IsolationLevel, ListOffset, NULL_TOPIC_ID, OpType,
+    AclOperation, AclPatternType, AclPermissionType, AclResourceType, BatchAttribute,
+    ConfigResource, ConfigSource, ConfigType, ControlBatch, EndTransactionMarker, ErrorCode,
+    IsolationLevel, ListOffset, NULL_TOPIC_ID, OpType,
     add_partitions_to_txn_response::{
         AddPartitionsToTxnPartitionResult, AddPartitionsToTxnTopicResult,
     },
@@ -62,6 +64,7 @@ use tansu_sans_io::{
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
+    fetch_response::AbortedTransaction,
     incremental_alter_configs_request::AlterConfigsResource,
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     list_groups_response::ListedGroup,
@@ -586,9 +589,9 @@ impl Delegate {
                 sequence,
             );
 
-            let increment = idempotent_sequence_check(&current_epoch, &sequence, deflated)?;
+            let next = idempotent_sequence_check(&current_epoch, &sequence, deflated)?;
 
-            debug!(increment);
+            debug!(next);
 
             assert_eq!(
                 1,
@@ -601,7 +604,7 @@ impl Delegate {
                             topition.partition(),
                             deflated.producer_id,
                             deflated.producer_epoch,
-                            increment,
+                            next,
                         ),
                     )
                     .await?
@@ -814,6 +817,25 @@ impl Delegate {
             .inspect(|n| debug!(?n, after_watermark_update = elapsed_millis(start)))
             .inspect_err(|err| error!(?err))?;
 
+        let usage = Usage::of(&inflated);
+
+        _ = connection
+            .execute(
+                "topition_usage_upsert.sql",
+                (
+                    self.cluster.as_str(),
+                    topic,
+                    partition,
+                    usage.records,
+                    usage.bytes,
+                    usage.oldest_timestamp,
+                    usage.newest_timestamp,
+                ),
+            )
+            .await
+            .inspect(|n| debug!(?n, after_usage_upsert = elapsed_millis(start)))
+            .inspect_err(|err| error!(?err))?;
+
         if !attributes.control
             && let Some(ref lake) = self.lake
         {
@@ -875,28 +897,15 @@ impl Delegate {
 
             debug!(?topition);
 
-            let control_batch: Bytes = if committed {
-                ControlBatch::default().commit().try_into()?
+            let control = if committed {
+                ControlBatch::default().commit()
             } else {
-                ControlBatch::default().abort().try_into()?
+                ControlBatch::default().abort()
             };
-            let end_transaction_marker: Bytes = EndTransactionMarker::default().try_into()?;
 
-            let batch = inflated::Batch::builder()
-                .record(
-                    Record::builder()
-                        .key(control_batch.into())
-                        .value(end_transaction_marker.into()),
-                )
-                .attributes(
-                    BatchAttribute::default()
-                        .control(true)
-                        .transaction(true)
-                        .into(),
-                )
+            let batch = inflated::Batch::control(control, EndTransactionMarker::default())?
                 .producer_id(producer_id)
                 .producer_epoch(producer_epoch)
-                .base_sequence(-1)
                 .build()
                 .and_then(TryInto::try_into)
                 .inspect(|deflated| debug!(?deflated))?;
@@ -972,6 +981,20 @@ impl Delegate {
             for txn in txns {
                 debug!(?txn);
 
+                if txn.status == TxnState::PrepareAbort {
+                    _ = connection
+                        .execute(
+                            "txn_aborted_insert_from_txn.sql",
+                            (
+                                self.cluster.as_str(),
+                                txn.name.as_str(),
+                                txn.producer_id,
+                                txn.producer_epoch,
+                            ),
+                        )
+                        .await?;
+                }
+
                 _ = connection
                     .execute(
                         "txn_produce_offset_delete_by_txn.sql",
@@ -1087,6 +1110,34 @@ impl Delegate {
         Ok(ErrorCode::None)
     }
 
+    /// Release the usage of deleted records, given as `(topition, bytes)` rows,
+    /// returning the number of records deleted.
+    async fn release_usage(&self, connection: &PoolConnection, mut deleted: Rows) -> Result<u64> {
+        let mut released = BTreeMap::<i64, (i64, i64)>::new();
+
+        while let Some(row) = deleted.next().await? {
+            let (records, bytes) = released.entry(row.get::<i64>(0)?).or_default();
+            *records += 1;
+            *bytes += row.get::<i64>(1)?;
+        }
+
+        let mut total = 0;
+
+        for (topition, (records, bytes)) in released {
+            _ = connection
+                .execute(
+                    "lite/topition_usage_release.sql",
+                    (topition, records, bytes),
+                )
+                .await
+                .inspect_err(|err| error!(?topition, ?records, ?bytes, ?err))?;
+
+            total += records as u64;
+        }
+
+        Ok(total)
+    }
+
     #[instrument(skip(self), ret)]
     async fn policy_compact(&self) -> Result<u64> {
         let start = SystemTime::now();
@@ -1095,8 +1146,10 @@ impl Delegate {
         let tx = pc.transaction().await?;
 
         let compacted = pc
-            .execute("policy_compact.sql", [self.cluster.as_str()])
-            .await? as u64;
+            .query("policy_compact.sql", [self.cluster.as_str()])
+            .await?;
+
+        let compacted = self.release_usage(&pc, compacted).await?;
 
         tx.commit()
             .await
@@ -1132,11 +1185,13 @@ impl Delegate {
         let tx = pc.transaction().await?;
 
         let deleted = pc
-            .execute(
+            .query(
                 "lite/policy_delete.sql",
                 (self.cluster.as_str(), now, retention_ms),
             )
-            .await? as u64;
+            .await?;
+
+        let deleted = self.release_usage(&pc, deleted).await?;
 
         tx.commit()
             .await
@@ -1149,6 +1204,31 @@ impl Delegate {
                 )
             })
     }
+
+    #[instrument(skip(self), ret)]
+    async fn policy_retention_bytes(&self) -> Result<u64> {
+        let start = SystemTime::now();
+
+        let pc = self.connection().await?;
+        let tx = pc.transaction().await?;
+
+        let deleted = pc
+            .query("policy_retention_bytes.sql", [self.cluster.as_str()])
+            .await?;
+
+        let deleted = self.release_usage(&pc, deleted).await?;
+
+        tx.commit()
+            .await
+            .map_err(Into::into)
+            .and(Ok(deleted))
+            .inspect(|_| {
+                DELEGATE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "policy_retention_bytes")],
+                )
+            })
+    }
 }
 
 #[derive(Clone, Default, Debug)]
@@ -1236,12 +1316,22 @@ impl<C, N, L, D> Builder<C, N, L, D> {
 static DDL: LazyLock<Cache> = LazyLock::new(|| {
     let mapping = [
         ("010-cluster.sql", include_sql!("ddl/010-cluster.sql")),
+        ("020-acl.sql", include_sql!("ddl/020-acl.sql")),
+        (
+            "020-client-quota.sql",
+            include_sql!("ddl/020-client-quota.sql"),
+        ),
+        ("020-feature.sql", include_sql!("ddl/020-feature.sql")),
         (
             "020-consumer-group.sql",
             include_sql!("ddl/020-consumer-group.sql"),
         ),
         ("020-producer.sql", include_sql!("ddl/020-producer.sql")),
         ("020-topic.sql", include_sql!("ddl/020-topic.sql")),
+        (
+            "020-user-scram-credential.sql",
+            include_sql!("ddl/020-user-scram-credential.sql"),
+        ),
         (
             "030-consumer-group-detail.sql",
             include_sql!("ddl/030-consumer-group-detail.sql"),
@@ -1261,11 +1351,23 @@ static DDL: LazyLock<Cache> = LazyLock::new(|| {
             include_sql!("ddl/040-consumer-offset.sql"),
         ),
         ("040-header.sql", include_sql!("ddl/040-header.sql")),
+        (
+            "040-offset-translation.sql",
+            include_sql!("ddl/040-offset-translation.sql"),
+        ),
         (
             "040-producer-detail.sql",
             include_sql!("ddl/040-producer-detail.sql"),
         ),
         ("040-record.sql", include_sql!("ddl/040-record.sql")),
+        (
+            "040-topition-usage.sql",
+            include_sql!("ddl/040-topition-usage.sql"),
+        ),
+        (
+            "040-txn-aborted.sql",
+            include_sql!("ddl/040-txn-aborted.sql"),
+        ),
         ("040-txn-detail.sql", include_sql!("ddl/040-txn-detail.sql")),
         ("040-watermark.sql", include_sql!("ddl/040-watermark.sql")),
         (
@@ -1360,6 +1462,17 @@ impl Storage for Engine {
             })
     }
 
+    #[instrument(skip_all)]
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        let start = SystemTime::now();
+        self.inner.add_partitions(topic, count).await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "add_partitions")],
+            )
+        })
+    }
+
     #[instrument(skip_all)]
     async fn delete_records(
         &self,
@@ -1421,6 +1534,17 @@ impl Storage for Engine {
             })
     }
 
+    #[instrument(skip_all)]
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        let start = SystemTime::now();
+        self.inner.replicate(topition, batch).await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "replicate")],
+            )
+        })
+    }
+
     #[instrument(skip_all)]
     async fn fetch(
         &self,
@@ -1486,6 +1610,43 @@ impl Storage for Engine {
             })
     }
 
+    #[instrument(skip_all)]
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        let start = SystemTime::now();
+        self.inner
+            .aborted_txns(topition, first_offset, last_offset)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "aborted_txns")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        let start = SystemTime::now();
+        self.inner
+            .delete_expired_offsets(group_id, retention)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "delete_expired_offsets")],
+                )
+            })
+    }
+
     #[instrument(skip_all)]
     async fn offset_fetch(
         &self,
@@ -1730,6 +1891,190 @@ impl Storage for Engine {
             })
     }
 
+    #[instrument(skip_all)]
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        let start = SystemTime::now();
+        self.inner
+            .upsert_user_scram_credential(username, mechanism, credential)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "upsert_user_scram_credential")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        let start = SystemTime::now();
+        self.inner
+            .delete_user_scram_credential(username, mechanism)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "delete_user_scram_credential")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        let start = SystemTime::now();
+        self.inner
+            .user_scram_credentials(usernames)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "user_scram_credentials")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        let start = SystemTime::now();
+        self.inner
+            .checkpoint_offset_translation(topition, translation)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "checkpoint_offset_translation")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        let start = SystemTime::now();
+        self.inner
+            .offset_translation(topition, upstream)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "offset_translation")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        let start = SystemTime::now();
+        self.inner.topition_usage(topics).await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "topition_usage")],
+            )
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        let start = SystemTime::now();
+        self.inner.create_acl(binding).await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "create_acl")],
+            )
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        let start = SystemTime::now();
+        self.inner.delete_acl(binding).await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "delete_acl")],
+            )
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        let start = SystemTime::now();
+        self.inner.acls().await.inspect(|_| {
+            ENGINE_REQUEST_DURATION
+                .record(elapsed_millis(start), &[KeyValue::new("operation", "acls")])
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        let start = SystemTime::now();
+        self.inner
+            .alter_client_quota(entity, key, value)
+            .await
+            .inspect(|_| {
+                ENGINE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "alter_client_quota")],
+                )
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        let start = SystemTime::now();
+        self.inner.client_quotas().await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "client_quotas")],
+            )
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        let start = SystemTime::now();
+        self.inner.update_feature(name, level).await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "update_feature")],
+            )
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        let start = SystemTime::now();
+        self.inner.finalized_features().await.inspect(|_| {
+            ENGINE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "finalized_features")],
+            )
+        })
+    }
+
     #[instrument(skip_all)]
     async fn maintain(&self, now: SystemTime) -> Result<()> {
         let start = SystemTime::now();
@@ -1741,6 +2086,12 @@ impl Storage for Engine {
         })
     }
 
+    // compacted topics are compacted in full during maintenance
+    #[instrument(skip_all)]
+    async fn compact(&self, _topition: &Topition, _end_offset: i64) -> Result<u64> {
+        Ok(0)
+    }
+
     #[instrument(skip_all)]
     async fn cluster_id(&self) -> Result<String> {
         let start = SystemTime::now();
@@ -2004,7 +2355,62 @@ impl Storage for Delegate {
         })
     }
 
-    async fn delete_records(
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        let start = SystemTime::now();
+        debug!(cluster = self.cluster, topic, count);
+
+        let pc = self.connection().await?;
+        let tx = pc.transaction().await?;
+
+        let Some(current) = pc
+            .query_opt("topic_select_name.sql", (self.cluster.as_str(), topic))
+            .await?
+            .map(|row| row.get::<i32>(3))
+            .transpose()?
+        else {
+            return Ok(ErrorCode::UnknownTopicOrPartition);
+        };
+
+        if count <= current {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        if pc
+            .execute(
+                "topic_update_partitions.sql",
+                (self.cluster.as_str(), topic, count),
+            )
+            .await?
+            == 0
+        {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        for partition in current..count {
+            let params = (self.cluster.as_str(), topic, partition);
+
+            _ = pc
+                .query_opt("topition_insert.sql", params)
+                .await
+                .map(|row| row.map(|row| row.get_value(0)).transpose())
+                .inspect(|topition| debug!(?topition))?;
+
+            _ = pc
+                .query_opt("watermark_insert.sql", params)
+                .await
+                .map(|row| row.map(|row| row.get_value(0)).transpose())
+                .inspect(|watermark| debug!(?watermark))?;
+        }
+
+        pc.commit(tx).await.and(Ok(ErrorCode::None)).inspect(|_| {
+            DELEGATE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "add_partitions")],
+            )
+        })
+    }
+
+    async fn delete_records(
         &self,
         topics: &[DeleteRecordsTopic],
     ) -> Result<Vec<DeleteRecordsTopicResult>> {
@@ -2047,6 +2453,7 @@ impl Storage for Delegate {
             "consumer_offset_delete_by_topic.sql",
             "topic_configuration_delete_by_topic.sql",
             "watermark_delete_by_topic.sql",
+            "topition_usage_delete_by_topic.sql",
             "header_delete_by_topic.sql",
             "record_delete_by_topic.sql",
             "txn_offset_commit_tp_delete_by_topic.sql",
@@ -2211,6 +2618,57 @@ impl Storage for Delegate {
             })
     }
 
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        let start = SystemTime::now();
+
+        let pc = self.connection().await?;
+        let tx = pc.transaction().await?;
+
+        let (low, high) = self.watermark_select_for_update(topition, &pc).await?;
+        let high = high.unwrap_or_default();
+
+        if batch.base_offset < high {
+            debug!(?topition, base_offset = batch.base_offset, high);
+            return Ok(batch.base_offset);
+        }
+
+        if batch.base_offset > high {
+            // a gap in the upstream offsets, advancing the high watermark so
+            // that the batch retains its base offset
+            debug!(?topition, base_offset = batch.base_offset, high);
+
+            _ = pc
+                .execute(
+                    "watermark_update.sql",
+                    (
+                        self.cluster.as_str(),
+                        topition.topic(),
+                        topition.partition(),
+                        low.unwrap_or(batch.base_offset),
+                        batch.base_offset,
+                    ),
+                )
+                .await
+                .inspect_err(|err| error!(?err))?;
+        }
+
+        let offset = self
+            .produce_in_tx(None, topition, batch.without_producer()?, &pc)
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        pc.commit(tx)
+            .await
+            .and(Ok(offset))
+            .inspect_err(|err| error!(?err))
+            .inspect(|_| {
+                DELEGATE_REQUEST_DURATION.record(
+                    elapsed_millis(start),
+                    &[KeyValue::new("operation", "replicate")],
+                )
+            })
+    }
+
     async fn fetch(
         &self,
         topition: &Topition,
@@ -2650,6 +3108,107 @@ impl Storage for Delegate {
         })
     }
 
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?topition, first_offset, last_offset);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                "txn_aborted_select.sql",
+                (
+                    self.cluster.as_str(),
+                    topition.topic(),
+                    topition.partition(),
+                    first_offset,
+                    last_offset,
+                ),
+            )
+            .await?;
+
+        let mut aborted = vec![];
+
+        while let Some(row) = rows.next().await? {
+            aborted.push(
+                AbortedTransaction::default()
+                    .producer_id(row.get::<i64>(0)?)
+                    .first_offset(row.get::<i64>(1)?),
+            );
+        }
+
+        Ok(aborted).inspect(|_| {
+            DELEGATE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "aborted_txns")],
+            )
+        })
+    }
+
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, group_id, ?retention);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query(
+                "lite/consumer_offset_last_updated.sql",
+                (self.cluster.as_str(), group_id),
+            )
+            .await?;
+
+        let mut expired = vec![];
+
+        while let Some(row) = rows.next().await? {
+            let last_updated = row.get_value(3)?;
+
+            if LiteTimestamp::try_from(last_updated.clone())?
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed > retention)
+            {
+                expired.push((
+                    row.get::<i64>(0)?,
+                    Topition::new(row.get_str(1)?, row.get::<i32>(2)?),
+                    last_updated,
+                ));
+            }
+        }
+
+        let mut deleted = vec![];
+
+        // an offset committed again since it was read is no longer expired
+        for (id, topition, last_updated) in expired {
+            if c.execute(
+                "lite/consumer_offset_delete_unchanged.sql",
+                (id, last_updated),
+            )
+            .await?
+                > 0
+            {
+                deleted.push(topition);
+            }
+        }
+
+        Ok(deleted).inspect(|_| {
+            DELEGATE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "delete_expired_offsets")],
+            )
+        })
+    }
+
     async fn offset_fetch(
         &self,
         group_id: Option<&str>,
@@ -3185,8 +3744,8 @@ impl Storage for Delegate {
                         .name(name.to_owned())
                         .value(value)
                         .read_only(false)
-                        .is_default(None)
-                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                        .is_default(Some(false))
+                        .config_source(Some(ConfigSource::DynamicTopicConfig.into()))
                         .is_sensitive(false)
                         .synonyms(Some([].into()))
                         .config_type(Some(ConfigType::String.into()))
@@ -4208,12 +4767,478 @@ impl Storage for Delegate {
         })
     }
 
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, username, ?mechanism);
+
+        let c = self.connection().await?;
+
+        _ = c
+            .execute(
+                "user_scram_credential_upsert.sql",
+                (
+                    self.cluster.as_str(),
+                    username,
+                    i32::from(i8::from(mechanism)),
+                    credential.salt.to_vec(),
+                    credential.iterations,
+                    credential.stored_key.to_vec(),
+                    credential.server_key.to_vec(),
+                ),
+            )
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "upsert_user_scram_credential")],
+        );
+
+        Ok(())
+    }
+
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, username, ?mechanism);
+
+        let c = self.connection().await?;
+
+        c.query_opt(
+            "user_scram_credential_delete.sql",
+            (
+                self.cluster.as_str(),
+                username,
+                i32::from(i8::from(mechanism)),
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .map(|row| {
+            if row.is_some() {
+                ErrorCode::None
+            } else {
+                ErrorCode::ResourceNotFound
+            }
+        })
+        .inspect(|_| {
+            DELEGATE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "delete_user_scram_credential")],
+            )
+        })
+    }
+
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?usernames);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query("user_scram_credential_select.sql", [self.cluster.as_str()])
+            .await?;
+
+        let mut users = BTreeMap::<String, BTreeMap<ScramMechanism, ScramCredential>>::new();
+
+        while let Some(row) = rows.next().await? {
+            let username = row.get::<String>(0).inspect_err(|err| error!(?err))?;
+
+            if usernames.is_some_and(|usernames| !usernames.contains(&username)) {
+                continue;
+            }
+
+            let mechanism = row
+                .get::<i32>(1)
+                .map_err(Error::from)
+                .and_then(|mechanism| i8::try_from(mechanism).map_err(Into::into))
+                .and_then(ScramMechanism::try_from)?;
+
+            let credential = ScramCredential {
+                salt: row.get::<Vec<u8>>(2).map(Bytes::from)?,
+                iterations: row.get::<i32>(3)?,
+                stored_key: row.get::<Vec<u8>>(4).map(Bytes::from)?,
+                server_key: row.get::<Vec<u8>>(5).map(Bytes::from)?,
+            };
+
+            _ = users
+                .entry(username)
+                .or_default()
+                .insert(mechanism, credential);
+        }
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "user_scram_credentials")],
+        );
+
+        Ok(users)
+    }
+
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?topition, ?translation);
+
+        let c = self.connection().await?;
+
+        _ = c
+            .execute(
+                "offset_translation_upsert.sql",
+                (
+                    self.cluster.as_str(),
+                    topition.topic(),
+                    topition.partition(),
+                    translation.upstream,
+                    translation.local,
+                ),
+            )
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "checkpoint_offset_translation")],
+        );
+
+        Ok(())
+    }
+
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?topition, upstream);
+
+        let c = self.connection().await?;
+
+        let translation = c
+            .query_opt(
+                "offset_translation_select.sql",
+                (
+                    self.cluster.as_str(),
+                    topition.topic(),
+                    topition.partition(),
+                    upstream,
+                ),
+            )
+            .await
+            .inspect_err(|err| error!(?err))?
+            .map(|row| {
+                Ok::<_, Error>(OffsetTranslation::new(
+                    row.get::<i64>(0)?,
+                    row.get::<i64>(1)?,
+                ))
+            })
+            .transpose()?;
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "offset_translation")],
+        );
+
+        Ok(translation)
+    }
+
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?topics);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query("topition_usage_select.sql", [self.cluster.as_str()])
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        let mut usage = vec![];
+
+        while let Some(row) = rows.next().await? {
+            let topic = row.get::<String>(0)?;
+
+            if topics.is_some_and(|topics| !topics.contains(&topic)) {
+                continue;
+            }
+
+            usage.push(TopitionUsage {
+                topition: Topition::new(topic, row.get::<i32>(1)?),
+                usage: Usage {
+                    records: row.get::<i64>(2)?,
+                    bytes: row.get::<i64>(3)?,
+                    oldest_timestamp: row.get::<Option<i64>>(4)?,
+                    newest_timestamp: row.get::<Option<i64>>(5)?,
+                },
+            })
+        }
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "topition_usage")],
+        );
+
+        Ok(usage)
+    }
+
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?binding);
+
+        let c = self.connection().await?;
+
+        _ = c
+            .execute(
+                "acl_insert.sql",
+                (
+                    self.cluster.as_str(),
+                    i32::from(i8::from(binding.resource_type)),
+                    binding.resource_name.as_str(),
+                    i32::from(i8::from(binding.pattern_type)),
+                    binding.principal.as_str(),
+                    binding.host.as_str(),
+                    i32::from(i8::from(binding.operation)),
+                    i32::from(i8::from(binding.permission_type)),
+                ),
+            )
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "create_acl")],
+        );
+
+        Ok(())
+    }
+
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?binding);
+
+        let c = self.connection().await?;
+
+        c.query_opt(
+            "acl_delete.sql",
+            (
+                self.cluster.as_str(),
+                i32::from(i8::from(binding.resource_type)),
+                binding.resource_name.as_str(),
+                i32::from(i8::from(binding.pattern_type)),
+                binding.principal.as_str(),
+                binding.host.as_str(),
+                i32::from(i8::from(binding.operation)),
+                i32::from(i8::from(binding.permission_type)),
+            ),
+        )
+        .await
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+        .map(|row| {
+            if row.is_some() {
+                ErrorCode::None
+            } else {
+                ErrorCode::ResourceNotFound
+            }
+        })
+        .inspect(|_| {
+            DELEGATE_REQUEST_DURATION.record(
+                elapsed_millis(start),
+                &[KeyValue::new("operation", "delete_acl")],
+            )
+        })
+    }
+
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        let mut rows = c.query("acl_select.sql", [self.cluster.as_str()]).await?;
+
+        let code = |row: &Row, idx: i32| {
+            row.get::<i32>(idx)
+                .map_err(Error::from)
+                .and_then(|code| i8::try_from(code).map_err(Into::into))
+        };
+
+        let mut acls = vec![];
+
+        while let Some(row) = rows.next().await? {
+            acls.push(AclBinding {
+                resource_type: code(&row, 0).map(AclResourceType::from)?,
+                resource_name: row.get::<String>(1)?,
+                pattern_type: code(&row, 2).map(AclPatternType::from)?,
+                principal: row.get::<String>(3)?,
+                host: row.get::<String>(4)?,
+                operation: code(&row, 5).map(AclOperation::from)?,
+                permission_type: code(&row, 6).map(AclPermissionType::from)?,
+            });
+        }
+
+        DELEGATE_REQUEST_DURATION
+            .record(elapsed_millis(start), &[KeyValue::new("operation", "acls")]);
+
+        Ok(acls)
+    }
+
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, ?entity, key, ?value);
+
+        let entity = serde_json::to_string(entity)?;
+
+        let c = self.connection().await?;
+
+        if let Some(value) = value {
+            c.execute(
+                "client_quota_upsert.sql",
+                (self.cluster.as_str(), entity.as_str(), key, value),
+            )
+            .await
+            .and(Ok(()))
+        } else {
+            c.query_opt(
+                "client_quota_delete.sql",
+                (self.cluster.as_str(), entity.as_str(), key),
+            )
+            .await
+            .and(Ok(()))
+        }
+        .inspect_err(|err| error!(?err))?;
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "alter_client_quota")],
+        );
+
+        Ok(())
+    }
+
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query("client_quota_select.sql", [self.cluster.as_str()])
+            .await?;
+
+        let mut quotas = vec![];
+
+        while let Some(row) = rows.next().await? {
+            quotas.push(ClientQuota {
+                entity: row
+                    .get::<String>(0)
+                    .map_err(Error::from)
+                    .and_then(|entity| serde_json::from_str(&entity).map_err(Into::into))?,
+                key: row.get::<String>(1)?,
+                value: row.get::<f64>(2)?,
+            });
+        }
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "client_quotas")],
+        );
+
+        Ok(quotas)
+    }
+
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster, name, level);
+
+        let c = self.connection().await?;
+
+        _ = c
+            .execute(
+                "feature_upsert.sql",
+                (self.cluster.as_str(), name, i32::from(level)),
+            )
+            .await
+            .inspect_err(|err| error!(?err))?;
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "update_feature")],
+        );
+
+        Ok(())
+    }
+
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        let start = SystemTime::now();
+
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        let mut rows = c
+            .query("feature_select.sql", [self.cluster.as_str()])
+            .await?;
+
+        let mut features = vec![];
+
+        while let Some(row) = rows.next().await? {
+            features.push((
+                row.get::<String>(0)?,
+                i16::try_from(row.get::<i32>(1)?)?,
+                row.get::<i64>(2)?,
+            ));
+        }
+
+        DELEGATE_REQUEST_DURATION.record(
+            elapsed_millis(start),
+            &[KeyValue::new("operation", "finalized_features")],
+        );
+
+        Ok(features.into_iter().collect())
+    }
+
     async fn maintain(&self, now: SystemTime) -> Result<()> {
         let start = SystemTime::now();
 
         let deleted = self.policy_delete(now).await?;
         debug!(deleted);
 
+        let excess = self.policy_retention_bytes().await?;
+        debug!(excess);
+
         let compacted = self.policy_compact().await?;
         debug!(compacted);
 
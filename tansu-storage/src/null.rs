@@ -19,6 +19,7 @@ use std::{
 };
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use tansu_sans_io::{
     ConfigResource, ErrorCode, IsolationLevel, ListOffset, NULL_TOPIC_ID,
     add_partitions_to_txn_response::{AddPartitionsToTxnResult, AddPartitionsToTxnTopicResult},
@@ -31,6 +32,7 @@ use tansu_sans_io::{
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
+    fetch_response::AbortedTransaction,
     incremental_alter_configs_request::AlterConfigsResource,
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     list_groups_response::ListedGroup,
@@ -43,10 +45,12 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, GroupDetailResponse, ListOffsetResponse,
-    MetadataResponse, NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse,
-    Result, Storage, TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
-    TxnOffsetCommitRequest, UpdateError, Version,
+    AclBinding, BrokerRegistrationRequest, ClientQuota, ClientQuotaEntity, Error,
+    FinalizedFeatures, GroupDetail, GroupDetailResponse, ListOffsetResponse, MetadataResponse,
+    NamedGroupDetail, OffsetCommitRequest, OffsetStage, OffsetTranslation, ProducerIdResponse,
+    Result, ScramCredential, ScramMechanism, Storage, TopicId, Topition, TopitionUsage,
+    TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnDescription, TxnOffsetCommitRequest,
+    UpdateError, Version,
 };
 
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -122,6 +126,23 @@ impl Storage for Engine {
             })
     }
 
+    #[instrument(skip_all)]
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        self.topics.lock().map_err(Into::into).map(|mut topics| {
+            topics
+                .iter_mut()
+                .find(|existing| existing.name == topic)
+                .map_or(ErrorCode::UnknownTopicOrPartition, |existing| {
+                    if count > existing.num_partitions {
+                        existing.num_partitions = count;
+                        ErrorCode::None
+                    } else {
+                        ErrorCode::InvalidPartitions
+                    }
+                })
+        })
+    }
+
     #[instrument(skip_all)]
     async fn delete_records(
         &self,
@@ -172,6 +193,18 @@ impl Storage for Engine {
         Ok([].into())
     }
 
+    #[instrument(skip_all)]
+    async fn fetch_encoded(
+        &self,
+        _topition: &Topition,
+        _offset: i64,
+        _min_bytes: u32,
+        _max_bytes: u32,
+        _isolation_level: IsolationLevel,
+    ) -> Result<Vec<Bytes>> {
+        Ok([].into())
+    }
+
     #[instrument(skip_all)]
     async fn offset_stage(&self, _topition: &Topition) -> Result<OffsetStage> {
         Ok(OffsetStage::default())
@@ -488,11 +521,149 @@ impl Storage for Engine {
         Ok(ErrorCode::None)
     }
 
+    #[instrument(skip_all)]
+    async fn upsert_user_scram_credential(
+        &self,
+        _username: &str,
+        _mechanism: ScramMechanism,
+        _credential: ScramCredential,
+    ) -> Result<()> {
+        Err(Error::FeatureNotEnabled {
+            feature: FEATURE.into(),
+            message: MESSAGE.into(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_user_scram_credential(
+        &self,
+        _username: &str,
+        _mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        Ok(ErrorCode::ResourceNotFound)
+    }
+
+    #[instrument(skip_all)]
+    async fn user_scram_credentials(
+        &self,
+        _usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        Ok(BTreeMap::new())
+    }
+
+    fn scram_credentials(&self) -> bool {
+        false
+    }
+
+    #[instrument(skip_all)]
+    async fn checkpoint_offset_translation(
+        &self,
+        _topition: &Topition,
+        _translation: OffsetTranslation,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn offset_translation(
+        &self,
+        _topition: &Topition,
+        _upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        Ok(None)
+    }
+
+    #[instrument(skip_all)]
+    async fn topition_usage(&self, _topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        Ok(vec![])
+    }
+
+    #[instrument(skip_all)]
+    async fn aborted_txns(
+        &self,
+        _topition: &Topition,
+        _first_offset: i64,
+        _last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        Ok(vec![])
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_expired_offsets(
+        &self,
+        _group_id: &str,
+        _retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        Ok(vec![])
+    }
+
+    #[instrument(skip_all)]
+    async fn describe_txns(
+        &self,
+        _transactional_ids: Option<&[String]>,
+    ) -> Result<Vec<TxnDescription>> {
+        Ok(vec![])
+    }
+
+    #[instrument(skip_all)]
+    async fn create_acl(&self, _binding: &AclBinding) -> Result<()> {
+        Err(Error::FeatureNotEnabled {
+            feature: FEATURE.into(),
+            message: MESSAGE.into(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_acl(&self, _binding: &AclBinding) -> Result<ErrorCode> {
+        Ok(ErrorCode::ResourceNotFound)
+    }
+
+    #[instrument(skip_all)]
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        Ok(vec![])
+    }
+
+    #[instrument(skip_all)]
+    async fn alter_client_quota(
+        &self,
+        _entity: &ClientQuotaEntity,
+        _key: &str,
+        _value: Option<f64>,
+    ) -> Result<()> {
+        Err(Error::FeatureNotEnabled {
+            feature: FEATURE.into(),
+            message: MESSAGE.into(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        Ok(vec![])
+    }
+
+    #[instrument(skip_all)]
+    async fn update_feature(&self, _name: &str, _level: i16) -> Result<()> {
+        Err(Error::FeatureNotEnabled {
+            feature: FEATURE.into(),
+            message: MESSAGE.into(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        Ok(FinalizedFeatures::default())
+    }
+
     #[instrument(skip_all)]
     async fn maintain(&self, _now: SystemTime) -> Result<()> {
         Ok(())
     }
 
+    #[instrument(skip_all)]
+    async fn compact(&self, _topition: &Topition, _end_offset: i64) -> Result<u64> {
+        Ok(0)
+    }
+
     #[instrument(skip_all)]
     async fn cluster_id(&self) -> Result<String> {
         Ok(self.cluster.clone())
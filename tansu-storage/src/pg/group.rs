@@ -0,0 +1,295 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Group commit
+//!
+//! Concurrent produce calls to the same topition are queued, with a single
+//! committer per topition appending every queued batch in one transaction.
+//! Each batch is appended within its own savepoint, so that a batch failing
+//! (e.g., a duplicate sequence from an idempotent producer) is rolled back
+//! without failing the remainder of its group.
+//!
+//! A committer runs while its topition has queued batches, taking up to
+//! [`MAX_GROUP`] at a time. With many small producers, one commit (and
+//! write ahead log flush) is shared by many batches, rather than each batch
+//! waiting for its own. A lone producer has a group of one, appended without
+//! a savepoint as before.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use opentelemetry::{KeyValue, metrics::Histogram};
+use tansu_sans_io::{ErrorCode, record::deflated};
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+
+use crate::{Error, METER, Result, Tag, Topition};
+
+use super::{Postgres, failover};
+
+/// The most batches appended by one transaction
+pub(super) const MAX_GROUP: usize = 64;
+
+static GROUP_COMMIT_BATCHES: LazyLock<Histogram<u64>> = LazyLock::new(|| {
+    METER
+        .u64_histogram("tansu_sql_group_commit_batches")
+        .with_description("The number of batches appended by each group commit")
+        .build()
+});
+
+/// A batch waiting to be appended by the committer of its topition
+#[derive(Debug)]
+pub(super) struct Pending {
+    transaction_id: Option<String>,
+    deflated: deflated::Batch,
+    reply: oneshot::Sender<Result<i64>>,
+}
+
+/// The batches queued by topition, with a topition present while its
+/// committer is running
+#[derive(Clone, Debug, Default)]
+pub(super) struct Queue {
+    queued: Arc<Mutex<BTreeMap<Topition, Vec<Pending>>>>,
+}
+
+impl Queue {
+    /// Queue a batch, returning true when the caller must start a committer
+    /// for the topition
+    fn enqueue(&self, topition: &Topition, pending: Pending) -> Result<bool> {
+        let mut queued = self.queued.lock()?;
+
+        if let Some(waiting) = queued.get_mut(topition) {
+            waiting.push(pending);
+            Ok(false)
+        } else {
+            _ = queued.insert(topition.to_owned(), vec![pending]);
+            Ok(true)
+        }
+    }
+
+    /// Take the next group of a topition, with an empty group stopping the
+    /// committer of the topition
+    fn take(&self, topition: &Topition) -> Result<Vec<Pending>> {
+        let mut queued = self.queued.lock()?;
+
+        let Some(waiting) = queued.get_mut(topition) else {
+            return Ok(vec![]);
+        };
+
+        if waiting.is_empty() {
+            _ = queued.remove(topition);
+            return Ok(vec![]);
+        }
+
+        let group = waiting.len().min(MAX_GROUP);
+        Ok(waiting.drain(..group).collect())
+    }
+}
+
+impl Postgres {
+    /// Append a batch with those produced concurrently to the same topition,
+    /// returning its offset once their transaction has committed
+    pub(super) async fn group_produce(
+        &self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        deflated: deflated::Batch,
+    ) -> Result<i64> {
+        let (reply, committed) = oneshot::channel();
+
+        let pending = Pending {
+            transaction_id: transaction_id.map(ToOwned::to_owned),
+            deflated,
+            reply,
+        };
+
+        if self.group.enqueue(topition, pending)? {
+            let committer = self.clone();
+            let topition = topition.to_owned();
+            let tag = Tag::with_topic(topition.topic());
+
+            _ = tokio::spawn(tag.scope(async move { committer.commit(&topition).await }));
+        }
+
+        committed
+            .await
+            .inspect_err(|err| error!(?topition, ?err))
+            .unwrap_or(Err(Error::Api(ErrorCode::UnknownServerError)))
+    }
+
+    /// Commit the groups of a topition until none remain
+    async fn commit(&self, topition: &Topition) {
+        loop {
+            let group = match self.group.take(topition) {
+                Ok(group) if group.is_empty() => break,
+                Ok(group) => group,
+                Err(err) => {
+                    error!(?topition, ?err);
+                    break;
+                }
+            };
+
+            GROUP_COMMIT_BATCHES.record(
+                group.len() as u64,
+                &[KeyValue::new("cluster_id", self.cluster.clone())],
+            );
+
+            let (batches, replies): (Vec<_>, Vec<_>) = group
+                .into_iter()
+                .map(|pending| ((pending.transaction_id, pending.deflated), pending.reply))
+                .unzip();
+
+            match self.produce_group(topition, batches).await {
+                Ok(produced) => {
+                    for (reply, high) in replies.into_iter().zip(produced) {
+                        _ = reply.send(high);
+                    }
+                }
+
+                Err(err) => {
+                    for reply in replies {
+                        _ = reply.send(Err(err.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Append a group of batches in one transaction, each within its own
+    /// savepoint when there is more than one
+    async fn produce_group(
+        &self,
+        topition: &Topition,
+        batches: Vec<(Option<String>, deflated::Batch)>,
+    ) -> Result<Vec<Result<i64>>> {
+        debug!(cluster = self.cluster, ?topition, batches = batches.len());
+
+        let retriable = self.retriable(ErrorCode::NotLeaderOrFollower);
+
+        let mut c = self.connection().await.map_err(&retriable)?;
+
+        let mut tx = self.begin(&mut c).await.map_err(&retriable)?;
+
+        let single = batches.len() == 1;
+        let mut produced = Vec::with_capacity(batches.len());
+
+        for (transaction_id, deflated) in batches {
+            if single {
+                produced.push(Ok(self
+                    .produce_in_tx(transaction_id.as_deref(), topition, deflated, &tx)
+                    .await
+                    .map_err(&retriable)?));
+
+                continue;
+            }
+
+            let savepoint = tx
+                .savepoint("produce")
+                .await
+                .map_err(Error::from)
+                .map_err(&retriable)?;
+
+            match self
+                .produce_in_tx(transaction_id.as_deref(), topition, deflated, &savepoint)
+                .await
+            {
+                Ok(high) => {
+                    savepoint
+                        .commit()
+                        .await
+                        .map_err(Error::from)
+                        .map_err(&retriable)?;
+
+                    produced.push(Ok(high));
+                }
+
+                Err(err) if failover(&err) => return Err(retriable(err)),
+
+                Err(err) => {
+                    debug!(?topition, ?err);
+
+                    savepoint
+                        .rollback()
+                        .await
+                        .map_err(Error::from)
+                        .map_err(&retriable)?;
+
+                    produced.push(Err(err));
+                }
+            }
+        }
+
+        tx.commit().await.map_err(Error::from).map_err(&retriable)?;
+
+        Ok(produced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending() -> (Pending, oneshot::Receiver<Result<i64>>) {
+        let (reply, receiver) = oneshot::channel();
+
+        (
+            Pending {
+                transaction_id: None,
+                deflated: deflated::Batch::default(),
+                reply,
+            },
+            receiver,
+        )
+    }
+
+    #[test]
+    fn one_committer_per_topition() -> Result<()> {
+        let queue = Queue::default();
+        let abc = Topition::new("abc", 0);
+        let pqr = Topition::new("pqr", 0);
+
+        assert!(queue.enqueue(&abc, pending().0)?);
+        assert!(!queue.enqueue(&abc, pending().0)?);
+        assert!(queue.enqueue(&pqr, pending().0)?);
+
+        assert_eq!(2, queue.take(&abc)?.len());
+
+        // abc has a running committer, until it takes an empty group
+        assert!(!queue.enqueue(&abc, pending().0)?);
+        assert_eq!(1, queue.take(&abc)?.len());
+        assert!(queue.take(&abc)?.is_empty());
+
+        assert!(queue.enqueue(&abc, pending().0)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn group_is_limited() -> Result<()> {
+        let queue = Queue::default();
+        let topition = Topition::new("abc", 0);
+
+        for _ in 0..MAX_GROUP + 1 {
+            _ = queue.enqueue(&topition, pending().0)?;
+        }
+
+        assert_eq!(MAX_GROUP, queue.take(&topition)?.len());
+        assert_eq!(1, queue.take(&topition)?.len());
+        assert!(queue.take(&topition)?.is_empty());
+
+        Ok(())
+    }
+}
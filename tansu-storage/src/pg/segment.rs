@@ -0,0 +1,258 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record segments
+//!
+//! The `record` and `header` tables are partitioned by topition, with each
+//! topition sub-partitioned by range of offset into segments of
+//! [`SEGMENT_OFFSETS`] (`record_{topition}_{segment}`). The segments of a
+//! topition are created when its topic is created, with the next segment of
+//! each topition created ahead during maintenance. The segments holding the
+//! offsets of a batch not already created are created before the batch is
+//! appended, with the segments known to exist cached.
+//!
+//! Segments are created by `tansu_record_segment` in
+//! `etc/initdb.d/020-record-segment.sql`, which also migrates an existing
+//! database.
+//!
+//! A closed segment (below the high watermark) of a topic with a `delete`
+//! cleanup policy is dropped once its newest record is older than the
+//! retention of the topic, rather than deleting each record. Large topics
+//! have an index per segment, rather than one index for every record.
+//!
+//! A topition with records already in the default partition, from before
+//! segments, continues to use the default partition.
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use deadpool_postgres::Transaction;
+use tokio_postgres::error::SqlState;
+use tracing::{debug, error, instrument};
+
+use crate::Result;
+
+use super::Postgres;
+
+/// The number of offsets held by each segment of a topition
+pub(super) const SEGMENT_OFFSETS: i64 = 1_048_576;
+
+/// The segments of each topition known to exist, as `(topition, segment)`
+#[derive(Clone, Debug, Default)]
+pub(super) struct Segments {
+    created: Arc<Mutex<BTreeSet<(i32, i64)>>>,
+}
+
+impl Segments {
+    /// The segments holding the offsets from first to last not known to exist
+    fn missing(&self, topition: i32, first: i64, last: i64) -> Result<Vec<i64>> {
+        let created = self.created.lock()?;
+
+        Ok((first / SEGMENT_OFFSETS..=last / SEGMENT_OFFSETS)
+            .filter(|segment| !created.contains(&(topition, *segment)))
+            .collect())
+    }
+
+    fn created(&self, topition: i32, segment: i64) -> Result<()> {
+        self.created
+            .lock()
+            .map(|mut created| _ = created.insert((topition, segment)))
+            .map_err(Into::into)
+    }
+
+    fn forget(&self, topition: i32) -> Result<()> {
+        self.created
+            .lock()
+            .map(|mut created| created.retain(|(created, _)| *created != topition))
+            .map_err(Into::into)
+    }
+
+    fn dropped(&self, topition: i32, segment: i64) -> Result<()> {
+        self.created
+            .lock()
+            .map(|mut created| _ = created.remove(&(topition, segment)))
+            .map_err(Into::into)
+    }
+}
+
+impl Postgres {
+    /// Create the segments of a topition holding the offsets from first to last
+    ///
+    /// The segments are created within the transaction appending the batch,
+    /// so that a connection is not needed while holding another. Should that
+    /// transaction roll back, the segments are forgotten by [`Self::segment_missing`].
+    pub(super) async fn record_segments(
+        &self,
+        tx: &Transaction<'_>,
+        topition: i32,
+        first: i64,
+        last: i64,
+    ) -> Result<u64> {
+        let mut created = 0;
+
+        for segment in self.segments.missing(topition, first, last)? {
+            let partitioned = self
+                .tx_prepare_query_one(
+                    tx,
+                    "record_segment_create.sql",
+                    &[&topition, &segment, &SEGMENT_OFFSETS],
+                )
+                .await
+                .and_then(|row| row.try_get::<_, bool>(0).map_err(Into::into))
+                .inspect_err(|err| error!(topition, segment, ?err))?;
+
+            debug!(topition, segment, partitioned);
+
+            self.segments.created(topition, segment)?;
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    /// Create the segment holding the high watermark of each topition (of a
+    /// topic, or of the cluster) and the segment following it, each in their
+    /// own transaction, returning the number of segments created
+    #[instrument(skip(self), ret)]
+    pub(super) async fn record_segments_ahead(&self, topic: Option<&str>) -> Result<u64> {
+        let topitions = {
+            let c = self.connection().await?;
+
+            self.prepare_query(&c, "record_segment_ahead.sql", &[&self.cluster, &topic])
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.try_get::<_, i32>(0)?, row.try_get::<_, i64>(1)?)))
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let mut created = 0;
+
+        for (topition, high) in topitions {
+            if self
+                .segments
+                .missing(topition, high, high + SEGMENT_OFFSETS)?
+                .is_empty()
+            {
+                continue;
+            }
+
+            let mut c = self.connection().await?;
+            let tx = self.begin(&mut c).await?;
+
+            created += self
+                .record_segments(&tx, topition, high, high + SEGMENT_OFFSETS)
+                .await?;
+
+            tx.commit().await?;
+        }
+
+        Ok(created)
+    }
+
+    /// Forget the segments of a topition after a record could not be placed
+    /// in any partition, being created by a transaction that rolled back
+    pub(super) fn segment_missing(&self, topition: i32, error: &tokio_postgres::Error) {
+        if error.code() == Some(&SqlState::CHECK_VIOLATION) {
+            debug!(topition, ?error);
+            _ = self.segments.forget(topition);
+        }
+    }
+
+    /// Drop the expired segments of topics with a delete cleanup policy,
+    /// returning the number of records dropped
+    #[instrument(skip(self), ret)]
+    pub(super) async fn policy_segment_drop(&self, now: SystemTime) -> Result<u64> {
+        let retention_secs = i32::try_from(Duration::from_hours(7 * 24).as_secs())?;
+
+        let expired = {
+            let c = self.connection().await?;
+
+            self.prepare_query(
+                &c,
+                "record_segment_expired.sql",
+                &[&self.cluster, &now, &retention_secs, &SEGMENT_OFFSETS],
+            )
+            .await?
+        };
+
+        let mut dropped = 0;
+
+        for row in expired {
+            let topition = row.try_get::<_, i32>(0)?;
+            let segment = row.try_get::<_, i64>(1)?;
+
+            let mut c = self.connection().await?;
+            let tx = self.begin(&mut c).await?;
+
+            let row = self
+                .tx_prepare_query_one(&tx, "record_segment_drop.sql", &[&topition, &segment])
+                .await?;
+
+            let records = row.try_get::<_, i64>(0)?;
+            let bytes = row.try_get::<_, i64>(1)?;
+
+            debug!(topition, segment, records, bytes);
+
+            _ = self
+                .tx_prepare_execute(
+                    &tx,
+                    "topition_usage_release.sql",
+                    &[&topition, &records, &bytes],
+                )
+                .await?;
+
+            tx.commit().await?;
+
+            self.segments.dropped(topition, segment)?;
+            dropped += u64::try_from(records)?;
+        }
+
+        Ok(dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_segments() -> Result<()> {
+        let segments = Segments::default();
+
+        assert_eq!(vec![0], segments.missing(1, 0, 0)?);
+        assert_eq!(
+            vec![0, 1],
+            segments.missing(1, SEGMENT_OFFSETS - 1, SEGMENT_OFFSETS)?
+        );
+
+        segments.created(1, 0)?;
+        assert!(segments.missing(1, 0, SEGMENT_OFFSETS - 1)?.is_empty());
+        assert_eq!(vec![0], segments.missing(2, 0, 0)?);
+
+        segments.dropped(1, 0)?;
+        assert_eq!(vec![0], segments.missing(1, 0, 0)?);
+
+        segments.created(1, 0)?;
+        segments.created(1, 1)?;
+        segments.created(2, 0)?;
+        segments.forget(1)?;
+        assert_eq!(vec![0, 1], segments.missing(1, 0, SEGMENT_OFFSETS)?);
+        assert!(segments.missing(2, 0, 0)?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Produce notifications
+//!
+//! A delayed operation in the [`purgatory`](crate::purgatory) watching a
+//! topition is woken when its watermarks change through this broker. Each
+//! watched topition has a [`watch`] channel, signalled with the offset of
+//! each batch produced (or replicated) to it, and with its low watermark once
+//! records are deleted. A channel exists only while an operation is watching
+//! its topition.
+
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, Mutex},
+};
+
+use tokio::sync::watch;
+
+use crate::{Result, Topition};
+
+static WATCHED: LazyLock<Mutex<BTreeMap<Topition, watch::Sender<i64>>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Signal a batch produced to a topition at an offset, or its low watermark
+/// after deleting records
+pub(crate) fn notify(topition: &Topition, offset: i64) {
+    let Ok(mut watched) = WATCHED.lock() else {
+        return;
+    };
+
+    if let Some(sender) = watched.get(topition) {
+        if sender.receiver_count() == 0 {
+            _ = watched.remove(topition);
+        } else {
+            _ = sender.send_replace(offset);
+        }
+    }
+}
+
+/// Wake the operations watching a topition, without an offset
+pub(crate) fn wake(topition: &Topition) {
+    let Ok(watched) = WATCHED.lock() else {
+        return;
+    };
+
+    if let Some(sender) = watched.get(topition) {
+        sender.send_modify(|_| ());
+    }
+}
+
+/// Watch a topition, before checking its watermarks, so that a batch produced
+/// after that check is not missed
+pub(crate) fn subscribe(topition: &Topition) -> Result<watch::Receiver<i64>> {
+    WATCHED
+        .lock()
+        .map(|mut watched| {
+            watched
+                .entry(topition.clone())
+                .or_insert_with(|| watch::channel(-1).0)
+                .subscribe()
+        })
+        .map_err(Into::into)
+}
+
+/// Stop watching a topition, after dropping its receiver, removing the channel
+/// once no operation is watching it
+pub(crate) fn release(topition: &Topition) {
+    let Ok(mut watched) = WATCHED.lock() else {
+        return;
+    };
+
+    if watched
+        .get(topition)
+        .is_some_and(|sender| sender.receiver_count() == 0)
+    {
+        _ = watched.remove(topition);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn wake_on_produce() -> Result<()> {
+        let topition = Topition::new("wake_on_produce", 0);
+
+        let mut receiver = subscribe(&topition)?;
+
+        // produced between the check and the wait
+        notify(&topition, 32);
+
+        assert!(
+            time::timeout(Duration::from_secs(5), receiver.changed())
+                .await
+                .is_ok_and(|changed| changed.is_ok())
+        );
+        assert_eq!(32, *receiver.borrow_and_update());
+
+        notify(&Topition::new("wake_on_produce", 1), 6);
+        assert!(
+            time::timeout(Duration::from_millis(10), receiver.changed())
+                .await
+                .is_err()
+        );
+
+        wake(&topition);
+        assert!(
+            time::timeout(Duration::from_secs(5), receiver.changed())
+                .await
+                .is_ok_and(|changed| changed.is_ok())
+        );
+        assert_eq!(32, *receiver.borrow_and_update());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unwatched_topition_is_removed() -> Result<()> {
+        let topition = Topition::new("unwatched_topition_is_removed", 0);
+
+        let first = subscribe(&topition)?;
+        let second = subscribe(&topition)?;
+
+        drop(first);
+        release(&topition);
+        assert!(WATCHED.lock()?.contains_key(&topition));
+
+        drop(second);
+        release(&topition);
+        assert!(!WATCHED.lock()?.contains_key(&topition));
+
+        Ok(())
+    }
+}
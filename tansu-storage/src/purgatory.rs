@@ -0,0 +1,312 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Purgatory
+//!
+//! Operations delayed until they can complete or expire: a long-poll fetch
+//! waiting for records (`max.wait.ms`), a member joining a group waiting for
+//! the rebalance delay, and records posted to the admin listener lingering
+//! before their batch is produced.
+//!
+//! A [`Delayed`] operation watches one or more [`Key`]s, being woken when any
+//! of them is completed: a topition when its watermarks change (produce,
+//! replicate or delete records), a group when its state is updated, or a
+//! lingering batch when it is full. Expiry uses the timer wheel of the tokio
+//! runtime.
+//!
+//! Produce (including `acks=all`) and delete records are not delayed: every
+//! storage engine holds a single copy, written before it responds, so their
+//! watermarks have already moved when the response is made.
+//!
+//! Keys are only completed by changes made through this broker. An operation
+//! may also poll storage, noticing changes made through another broker.
+//! Each watched key has a [`watch`] channel, existing only while an operation
+//! is watching it: a delayed operation stops watching its keys when it
+//! expires or is dropped. A topition is watched through the notifications of
+//! each batch produced to it.
+
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use futures::future::select_all;
+use tokio::{
+    sync::watch,
+    time::{self, Instant},
+};
+use tracing::debug;
+
+use crate::{Result, Topition, produced};
+
+static WATCHED: LazyLock<Mutex<BTreeMap<Key, watch::Sender<u64>>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// A key watched by delayed operations
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Key {
+    /// The watermarks of a topition
+    Topition(Topition),
+
+    /// The state of a group
+    Group(String),
+
+    /// The batch lingering for a topition
+    Linger(Topition),
+}
+
+/// Complete a key, waking the operations watching it
+pub fn complete(key: &Key) {
+    if let Key::Topition(topition) = key {
+        produced::wake(topition);
+        return;
+    }
+
+    let Ok(mut watched) = WATCHED.lock() else {
+        return;
+    };
+
+    if let Some(sender) = watched.get(key) {
+        if sender.receiver_count() == 0 {
+            _ = watched.remove(key);
+        } else {
+            sender.send_modify(|completions| *completions = completions.wrapping_add(1));
+        }
+    }
+}
+
+/// Stop watching a key, after dropping its receiver, removing the channel once
+/// no operation is watching it
+fn unwatch(key: &Key) {
+    if let Key::Topition(topition) = key {
+        produced::release(topition);
+        return;
+    }
+
+    let Ok(mut watched) = WATCHED.lock() else {
+        return;
+    };
+
+    if watched
+        .get(key)
+        .is_some_and(|sender| sender.receiver_count() == 0)
+    {
+        _ = watched.remove(key);
+    }
+}
+
+/// A receiver of the changes to a watched key
+#[derive(Debug)]
+enum Receiver {
+    /// The offsets produced to a topition
+    Produced(watch::Receiver<i64>),
+
+    /// The completions of a group or lingering batch
+    Completed(watch::Receiver<u64>),
+}
+
+impl Receiver {
+    async fn changed(&mut self) -> Result<(), watch::error::RecvError> {
+        match self {
+            Self::Produced(receiver) => receiver.changed().await,
+            Self::Completed(receiver) => receiver.changed().await,
+        }
+    }
+}
+
+/// Why a delayed operation was woken
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Wake {
+    /// A watched key was completed
+    Completed(Key),
+
+    /// The poll interval elapsed
+    Poll,
+
+    /// The operation expired
+    Expired,
+}
+
+/// An operation delayed until a watched key is completed, or it expires
+#[derive(Debug)]
+pub struct Delayed {
+    deadline: Instant,
+    receivers: BTreeMap<Key, Receiver>,
+}
+
+impl Delayed {
+    /// An operation expiring after the timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+            receivers: BTreeMap::new(),
+        }
+    }
+
+    /// Watch a key, before checking whether the operation can complete, so
+    /// that a completion after that check is not missed
+    pub fn watch(&mut self, key: Key) -> Result<()> {
+        if !self.receivers.contains_key(&key) {
+            let receiver = if let Key::Topition(topition) = &key {
+                produced::subscribe(topition).map(Receiver::Produced)?
+            } else {
+                WATCHED
+                    .lock()
+                    .map(|mut watched| {
+                        watched
+                            .entry(key.clone())
+                            .or_insert_with(|| watch::channel(0).0)
+                            .subscribe()
+                    })
+                    .map(Receiver::Completed)?
+            };
+
+            _ = self.receivers.insert(key, receiver);
+        }
+
+        Ok(())
+    }
+
+    /// The time remaining before this operation expires
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// Wait until a watched key is completed, polling after an interval, or
+    /// until this operation expires, no longer watching its keys
+    pub async fn wait(&mut self, poll: Option<Duration>) -> Wake {
+        let remaining = self.remaining();
+
+        let (timeout, timed_out) = match poll {
+            Some(poll) if poll < remaining => (poll, Wake::Poll),
+            _ => (remaining, Wake::Expired),
+        };
+
+        let wake = if self.receivers.is_empty() {
+            time::sleep(timeout).await;
+            timed_out
+        } else {
+            time::timeout(
+                timeout,
+                select_all(self.receivers.iter_mut().map(|(key, receiver)| {
+                    Box::pin(async move { (key, receiver.changed().await) })
+                })),
+            )
+            .await
+            .map_or(timed_out, |((key, changed), _, _)| {
+                debug!(?key, ?changed);
+                Wake::Completed(key.clone())
+            })
+        };
+
+        if wake == Wake::Expired {
+            self.unwatch();
+        }
+
+        wake
+    }
+
+    /// Stop watching every key, dropping each receiver before its channel is
+    /// checked for other operations still watching it
+    fn unwatch(&mut self) {
+        let keys = std::mem::take(&mut self.receivers)
+            .into_keys()
+            .collect::<Vec<_>>();
+
+        for key in &keys {
+            unwatch(key);
+        }
+    }
+}
+
+impl Drop for Delayed {
+    fn drop(&mut self) {
+        self.unwatch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn completed_or_expired() -> Result<()> {
+        let topition = Key::Topition(Topition::new("completed_or_expired", 0));
+
+        let mut delayed = Delayed::new(Duration::from_secs(5));
+        delayed.watch(topition.clone())?;
+
+        assert_eq!(
+            Wake::Poll,
+            delayed.wait(Some(Duration::from_millis(10))).await
+        );
+
+        // completed between the check and the wait
+        complete(&topition);
+        assert_eq!(Wake::Completed(topition), delayed.wait(None).await);
+
+        complete(&Key::Group("completed_or_expired".into()));
+        assert_eq!(
+            Wake::Poll,
+            delayed.wait(Some(Duration::from_millis(10))).await
+        );
+
+        let mut delayed = Delayed::new(Duration::from_millis(10));
+        assert_eq!(
+            Wake::Expired,
+            delayed.wait(Some(Duration::from_secs(5))).await
+        );
+        assert!(delayed.is_expired());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expired_key_is_removed() -> Result<()> {
+        let key = Key::Group("expired_key_is_removed".into());
+
+        let mut delayed = Delayed::new(Duration::from_millis(10));
+        delayed.watch(key.clone())?;
+        assert!(WATCHED.lock()?.contains_key(&key));
+
+        assert_eq!(Wake::Expired, delayed.wait(None).await);
+        assert!(!WATCHED.lock()?.contains_key(&key));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unwatched_key_is_removed() -> Result<()> {
+        let key = Key::Linger(Topition::new("unwatched_key_is_removed", 0));
+
+        let mut first = Delayed::new(Duration::from_secs(5));
+        first.watch(key.clone())?;
+
+        let mut second = Delayed::new(Duration::from_secs(5));
+        second.watch(key.clone())?;
+
+        drop(first);
+        assert!(WATCHED.lock()?.contains_key(&key));
+
+        drop(second);
+        assert!(!WATCHED.lock()?.contains_key(&key));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,799 @@
+// Copyright ⓒ 2024-2026 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage tiers
+//!
+//! A broker may place topics on different storage engines: for example, hot
+//! topics on Postgres with archival topics on an S3 only tier. The tier of a
+//! topic is named by its [`STORAGE_TIER`] config when the topic is created,
+//! with a topic without a tier remaining on the primary storage.
+//!
+//! The primary storage is the source of truth for metadata: every topic is
+//! created on the primary, with a topic on a tier also created on that tier
+//! holding its records. Groups, transactions, ACLs, quotas, features and
+//! credentials are held only by the primary. The tier of a topic cannot be
+//! altered once created.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tansu_sans_io::{
+    ConfigResource, ErrorCode, IsolationLevel, ListOffset, create_topics_request::CreatableTopic,
+    delete_groups_response::DeletableGroupResult, delete_records_request::DeleteRecordsTopic,
+    delete_records_response::DeleteRecordsTopicResult,
+    describe_cluster_response::DescribeClusterBroker,
+    describe_configs_response::DescribeConfigsResult,
+    describe_topic_partitions_response::DescribeTopicPartitionsResponseTopic,
+    fetch_response::AbortedTransaction, incremental_alter_configs_request::AlterConfigsResource,
+    incremental_alter_configs_response::AlterConfigsResourceResponse,
+    list_groups_response::ListedGroup, record::deflated,
+    txn_offset_commit_response::TxnOffsetCommitResponseTopic,
+};
+use tracing::{debug, instrument, warn};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    AclBinding, BrokerRegistrationRequest, ClientQuota, ClientQuotaEntity, CoordinatorLease,
+    DirectRead, Error, FinalizedFeatures, GroupDetail, ListOffsetResponse, MetadataResponse,
+    NamedGroupDetail, OffsetCommitRequest, OffsetStage, OffsetTranslation, PreflightCheck,
+    ProducerIdResponse, Result, ScramCredential, ScramMechanism, Storage, StorageContainer,
+    TopicId, Topition, TopitionUsage, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
+    TxnDescription, TxnOffsetCommitRequest, UpdateError, Version,
+};
+
+/// The topic config naming the storage tier of a topic
+pub const STORAGE_TIER: &str = "tansu.storage.tier";
+
+/// The storage tier named by the configs of a new topic
+fn tier_of(topic: &CreatableTopic) -> Option<&str> {
+    topic
+        .configs
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .find(|config| config.name == STORAGE_TIER)
+        .and_then(|config| config.value.as_deref())
+}
+
+/// Whether a resource alters the storage tier of a topic
+fn alters_tier(resource: &AlterConfigsResource) -> bool {
+    ConfigResource::from(resource.resource_type) == ConfigResource::Topic
+        && resource
+            .configs
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .any(|config| config.name == STORAGE_TIER)
+}
+
+/// Storage routing each topic to the primary or its storage tier
+#[derive(Clone, Debug)]
+pub struct Routed {
+    primary: Arc<StorageContainer>,
+    tiers: Arc<BTreeMap<String, StorageContainer>>,
+
+    /// The tier of each topic, `None` being the primary
+    assigned: Arc<Mutex<BTreeMap<String, Option<String>>>>,
+}
+
+impl Routed {
+    pub fn new(primary: StorageContainer, tiers: BTreeMap<String, StorageContainer>) -> Self {
+        Self {
+            primary: Arc::new(primary),
+            tiers: Arc::new(tiers),
+            assigned: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// The storage of a tier, or the primary
+    fn container(&self, tier: Option<&str>) -> Result<&StorageContainer> {
+        tier.map_or(Ok(self.primary.as_ref()), |tier| {
+            self.tiers.get(tier).ok_or_else(|| {
+                warn!(tier, "unknown storage tier");
+                Error::Api(ErrorCode::KafkaStorageError)
+            })
+        })
+    }
+
+    /// The tier of a topic, from its config on the primary
+    async fn tier(&self, topic: &str) -> Result<Option<String>> {
+        if let Some(tier) = self.assigned.lock()?.get(topic) {
+            return Ok(tier.clone());
+        }
+
+        let described = self
+            .primary
+            .describe_config(topic, ConfigResource::Topic, Some(&[STORAGE_TIER.into()]))
+            .await?;
+
+        let tier = described
+            .configs
+            .unwrap_or_default()
+            .into_iter()
+            .find(|config| config.name == STORAGE_TIER)
+            .and_then(|config| config.value);
+
+        // an unknown topic may yet be created with a tier
+        if described.error_code == i16::from(ErrorCode::None) {
+            _ = self.assigned.lock()?.insert(topic.to_owned(), tier.clone());
+        }
+
+        Ok(tier)
+    }
+
+    /// The storage holding the records of a topic
+    async fn storage(&self, topic: &str) -> Result<&StorageContainer> {
+        self.tier(topic)
+            .await
+            .and_then(|tier| self.container(tier.as_deref()))
+    }
+
+    /// Group items by the storage holding the records of their topic
+    async fn by_storage<'a, T>(
+        &self,
+        items: &'a [T],
+        topic: impl Fn(&T) -> &str,
+    ) -> Result<Vec<(&StorageContainer, Vec<&'a T>)>> {
+        let mut grouped = BTreeMap::<Option<String>, Vec<&T>>::new();
+
+        for item in items {
+            grouped
+                .entry(self.tier(topic(item)).await?)
+                .or_default()
+                .push(item);
+        }
+
+        grouped
+            .into_iter()
+            .map(|(tier, items)| {
+                self.container(tier.as_deref())
+                    .map(|storage| (storage, items))
+            })
+            .collect()
+    }
+
+    /// The name of a topic, resolving an id using the primary
+    async fn topic_name(&self, topic: &TopicId) -> Result<Option<String>> {
+        match topic {
+            TopicId::Name(name) => Ok(Some(name.clone())),
+            TopicId::Id(_) => self
+                .primary
+                .metadata(Some(&[topic.clone()]))
+                .await
+                .map(|metadata| {
+                    metadata
+                        .topics()
+                        .first()
+                        .and_then(|topic| topic.name.clone())
+                }),
+        }
+    }
+
+    fn forget(&self, topic: &str) -> Result<()> {
+        self.assigned
+            .lock()
+            .map(|mut assigned| _ = assigned.remove(topic))
+            .map_err(Into::into)
+    }
+}
+
+#[async_trait]
+impl Storage for Routed {
+    async fn register_broker(&self, broker_registration: BrokerRegistrationRequest) -> Result<()> {
+        self.primary.register_broker(broker_registration).await
+    }
+
+    #[instrument(skip_all)]
+    async fn create_topic(&self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid> {
+        let Some(tier) = tier_of(&topic).map(ToOwned::to_owned) else {
+            return self.primary.create_topic(topic, validate_only).await;
+        };
+
+        let Some(storage) = self.tiers.get(&tier) else {
+            debug!(tier, "unknown storage tier");
+            return Err(Error::Api(ErrorCode::InvalidConfig));
+        };
+
+        let name = topic.name.clone();
+        let id = self
+            .primary
+            .create_topic(topic.clone(), validate_only)
+            .await?;
+
+        if let Err(err) = storage.create_topic(topic, validate_only).await {
+            warn!(name, tier, ?err);
+
+            if !validate_only {
+                _ = self.primary.delete_topic(&TopicId::Name(name)).await?;
+            }
+
+            return Err(err);
+        }
+
+        if !validate_only {
+            _ = self.assigned.lock()?.insert(name, Some(tier));
+        }
+
+        Ok(id)
+    }
+
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        let error_code = self.primary.add_partitions(topic, count).await?;
+
+        if error_code != ErrorCode::None {
+            return Ok(error_code);
+        }
+
+        match self.tier(topic).await? {
+            Some(tier) => {
+                self.container(Some(&tier))?
+                    .add_partitions(topic, count)
+                    .await
+            }
+            None => Ok(error_code),
+        }
+    }
+
+    async fn incremental_alter_resource(
+        &self,
+        resource: AlterConfigsResource,
+    ) -> Result<AlterConfigsResourceResponse> {
+        if alters_tier(&resource) {
+            return Ok(AlterConfigsResourceResponse::default()
+                .error_code(ErrorCode::InvalidConfig.into())
+                .error_message(Some(format!("{STORAGE_TIER} cannot be altered")))
+                .resource_type(resource.resource_type)
+                .resource_name(resource.resource_name));
+        }
+
+        let tier = if ConfigResource::from(resource.resource_type) == ConfigResource::Topic {
+            self.tier(&resource.resource_name).await?
+        } else {
+            None
+        };
+
+        let response = self
+            .primary
+            .incremental_alter_resource(resource.clone())
+            .await?;
+
+        if let Some(tier) = tier
+            && response.error_code == i16::from(ErrorCode::None)
+        {
+            // keep the configs held by the tier consistent with the primary
+            let altered = self
+                .container(Some(&tier))?
+                .incremental_alter_resource(resource)
+                .await?;
+
+            if altered.error_code != i16::from(ErrorCode::None) {
+                warn!(tier, ?altered);
+            }
+        }
+
+        Ok(response)
+    }
+
+    async fn delete_records(
+        &self,
+        topics: &[DeleteRecordsTopic],
+    ) -> Result<Vec<DeleteRecordsTopicResult>> {
+        let mut results = vec![];
+
+        for (storage, topics) in self.by_storage(topics, |topic| &topic.name).await? {
+            let topics = topics.into_iter().cloned().collect::<Vec<_>>();
+            results.extend(storage.delete_records(&topics).await?);
+        }
+
+        Ok(results)
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_topic(&self, topic: &TopicId) -> Result<ErrorCode> {
+        let Some(name) = self.topic_name(topic).await? else {
+            return self.primary.delete_topic(topic).await;
+        };
+
+        if let Some(tier) = self.tier(&name).await? {
+            let error_code = self
+                .container(Some(&tier))?
+                .delete_topic(&TopicId::Name(name.clone()))
+                .await?;
+
+            debug!(name, tier, ?error_code);
+        }
+
+        self.forget(&name)?;
+        self.primary.delete_topic(topic).await
+    }
+
+    async fn brokers(&self) -> Result<Vec<DescribeClusterBroker>> {
+        self.primary.brokers().await
+    }
+
+    async fn produce(
+        &self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        batch: deflated::Batch,
+    ) -> Result<i64> {
+        let Some(tier) = self.tier(topition.topic()).await? else {
+            return self.primary.produce(transaction_id, topition, batch).await;
+        };
+
+        // producer state and transactions are held only by the primary
+        if transaction_id.is_some() || batch.is_transactional() {
+            debug!(?topition, tier, "transactional produce to a storage tier");
+            return Err(Error::Api(ErrorCode::InvalidTxnState));
+        }
+
+        self.container(Some(&tier))?
+            .produce(None, topition, batch.without_producer()?)
+            .await
+    }
+
+    async fn replicate(&self, topition: &Topition, batch: deflated::Batch) -> Result<i64> {
+        self.storage(topition.topic())
+            .await?
+            .replicate(topition, batch)
+            .await
+    }
+
+    async fn fetch(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<deflated::Batch>> {
+        self.storage(topition.topic())
+            .await?
+            .fetch(topition, offset, min_bytes, max_bytes, isolation)
+            .await
+    }
+
+    async fn fetch_encoded(
+        &self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<Bytes>> {
+        self.storage(topition.topic())
+            .await?
+            .fetch_encoded(topition, offset, min_bytes, max_bytes, isolation)
+            .await
+    }
+
+    async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage> {
+        self.storage(topition.topic())
+            .await?
+            .offset_stage(topition)
+            .await
+    }
+
+    async fn list_offsets(
+        &self,
+        isolation_level: IsolationLevel,
+        offsets: &[(Topition, ListOffset)],
+    ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
+        let mut responses = vec![];
+
+        for (storage, offsets) in self
+            .by_storage(offsets, |(topition, _)| topition.topic())
+            .await?
+        {
+            let offsets = offsets.into_iter().cloned().collect::<Vec<_>>();
+            responses.extend(storage.list_offsets(isolation_level, &offsets).await?);
+        }
+
+        Ok(responses)
+    }
+
+    async fn offset_commit(
+        &self,
+        group_id: &str,
+        retention_time_ms: Option<Duration>,
+        offsets: &[(Topition, OffsetCommitRequest)],
+    ) -> Result<Vec<(Topition, ErrorCode)>> {
+        self.primary
+            .offset_commit(group_id, retention_time_ms, offsets)
+            .await
+    }
+
+    async fn offset_fetch(
+        &self,
+        group_id: Option<&str>,
+        topics: &[Topition],
+        require_stable: Option<bool>,
+    ) -> Result<BTreeMap<Topition, i64>> {
+        self.primary
+            .offset_fetch(group_id, topics, require_stable)
+            .await
+    }
+
+    async fn committed_offset_topitions(&self, group_id: &str) -> Result<BTreeMap<Topition, i64>> {
+        self.primary.committed_offset_topitions(group_id).await
+    }
+
+    async fn metadata(&self, topics: Option<&[TopicId]>) -> Result<MetadataResponse> {
+        self.primary.metadata(topics).await
+    }
+
+    async fn describe_config(
+        &self,
+        name: &str,
+        resource: ConfigResource,
+        keys: Option<&[String]>,
+    ) -> Result<DescribeConfigsResult> {
+        self.primary.describe_config(name, resource, keys).await
+    }
+
+    async fn list_groups(&self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>> {
+        self.primary.list_groups(states_filter).await
+    }
+
+    async fn delete_groups(
+        &self,
+        group_ids: Option<&[String]>,
+    ) -> Result<Vec<DeletableGroupResult>> {
+        self.primary.delete_groups(group_ids).await
+    }
+
+    async fn describe_groups(
+        &self,
+        group_ids: Option<&[String]>,
+        include_authorized_operations: bool,
+    ) -> Result<Vec<NamedGroupDetail>> {
+        self.primary
+            .describe_groups(group_ids, include_authorized_operations)
+            .await
+    }
+
+    async fn describe_topic_partitions(
+        &self,
+        topics: Option<&[TopicId]>,
+        partition_limit: i32,
+        cursor: Option<Topition>,
+    ) -> Result<Vec<DescribeTopicPartitionsResponseTopic>> {
+        self.primary
+            .describe_topic_partitions(topics, partition_limit, cursor)
+            .await
+    }
+
+    async fn update_group(
+        &self,
+        group_id: &str,
+        detail: GroupDetail,
+        version: Option<Version>,
+    ) -> Result<Version, UpdateError<GroupDetail>> {
+        self.primary.update_group(group_id, detail, version).await
+    }
+
+    async fn init_producer(
+        &self,
+        transaction_id: Option<&str>,
+        transaction_timeout_ms: i32,
+        producer_id: Option<i64>,
+        producer_epoch: Option<i16>,
+    ) -> Result<ProducerIdResponse> {
+        self.primary
+            .init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            )
+            .await
+    }
+
+    async fn txn_add_offsets(
+        &self,
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        group_id: &str,
+    ) -> Result<ErrorCode> {
+        self.primary
+            .txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id)
+            .await
+    }
+
+    async fn txn_add_partitions(
+        &self,
+        partitions: TxnAddPartitionsRequest,
+    ) -> Result<TxnAddPartitionsResponse> {
+        self.primary.txn_add_partitions(partitions).await
+    }
+
+    async fn txn_offset_commit(
+        &self,
+        offsets: TxnOffsetCommitRequest,
+    ) -> Result<Vec<TxnOffsetCommitResponseTopic>> {
+        self.primary.txn_offset_commit(offsets).await
+    }
+
+    async fn txn_end(
+        &self,
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        committed: bool,
+    ) -> Result<ErrorCode> {
+        self.primary
+            .txn_end(transaction_id, producer_id, producer_epoch, committed)
+            .await
+    }
+
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        self.primary
+            .upsert_user_scram_credential(username, mechanism, credential)
+            .await
+    }
+
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        self.primary
+            .delete_user_scram_credential(username, mechanism)
+            .await
+    }
+
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        self.primary.user_scram_credentials(usernames).await
+    }
+
+    fn scram_credentials(&self) -> bool {
+        self.primary.scram_credentials()
+    }
+
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        self.primary
+            .checkpoint_offset_translation(topition, translation)
+            .await
+    }
+
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        self.primary.offset_translation(topition, upstream).await
+    }
+
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        let mut usage = vec![];
+
+        for tier in [None]
+            .into_iter()
+            .chain(self.tiers.keys().map(|tier| Some(tier.as_str())))
+        {
+            for topition_usage in self.container(tier)?.topition_usage(topics).await? {
+                // only the storage holding its records has the usage of a topic
+                if self.tier(topition_usage.topition.topic()).await?.as_deref() == tier {
+                    usage.push(topition_usage);
+                }
+            }
+        }
+
+        Ok(usage)
+    }
+
+    async fn direct_reads(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        limit: usize,
+        expires_in: Duration,
+    ) -> Result<Vec<DirectRead>> {
+        self.storage(topition.topic())
+            .await?
+            .direct_reads(topition, offset, limit, expires_in)
+            .await
+    }
+
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        self.primary
+            .delete_expired_offsets(group_id, retention)
+            .await
+    }
+
+    async fn describe_txns(
+        &self,
+        transactional_ids: Option<&[String]>,
+    ) -> Result<Vec<TxnDescription>> {
+        self.primary.describe_txns(transactional_ids).await
+    }
+
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        self.storage(topition.topic())
+            .await?
+            .aborted_txns(topition, first_offset, last_offset)
+            .await
+    }
+
+    async fn coordinator_lease(&self, key_type: i8, key: &str) -> Result<CoordinatorLease> {
+        self.primary.coordinator_lease(key_type, key).await
+    }
+
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        self.primary.create_acl(binding).await
+    }
+
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        self.primary.delete_acl(binding).await
+    }
+
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        self.primary.acls().await
+    }
+
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        self.primary.alter_client_quota(entity, key, value).await
+    }
+
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        self.primary.client_quotas().await
+    }
+
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        self.primary.update_feature(name, level).await
+    }
+
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        self.primary.finalized_features().await
+    }
+
+    async fn maintain(&self, now: SystemTime) -> Result<()> {
+        self.primary.maintain(now).await?;
+
+        for (tier, storage) in self.tiers.iter() {
+            storage
+                .maintain(now)
+                .await
+                .inspect_err(|err| warn!(tier, ?err))?;
+        }
+
+        Ok(())
+    }
+
+    async fn compact(&self, topition: &Topition, end_offset: i64) -> Result<u64> {
+        self.storage(topition.topic())
+            .await?
+            .compact(topition, end_offset)
+            .await
+    }
+
+    async fn preflight(&self) -> Result<Vec<PreflightCheck>> {
+        let mut checks = self.primary.preflight().await?;
+
+        for storage in self.tiers.values() {
+            checks.extend(storage.preflight().await?);
+        }
+
+        Ok(checks)
+    }
+
+    async fn cluster_id(&self) -> Result<String> {
+        self.primary.cluster_id().await
+    }
+
+    async fn node(&self) -> Result<i32> {
+        self.primary.node().await
+    }
+
+    async fn advertised_listener(&self) -> Result<Url> {
+        self.primary.advertised_listener().await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.primary.ping().await?;
+
+        for storage in self.tiers.values() {
+            storage.ping().await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tansu_sans_io::{
+        OpType, create_topics_request::CreatableTopicConfig,
+        incremental_alter_configs_request::AlterableConfig,
+    };
+
+    use super::*;
+
+    #[test]
+    fn tier_of_topic() {
+        let topic = CreatableTopic::default()
+            .name("archive".into())
+            .num_partitions(1)
+            .replication_factor(1)
+            .configs(Some(vec![
+                CreatableTopicConfig::default()
+                    .name("cleanup.policy".into())
+                    .value(Some("delete".into())),
+                CreatableTopicConfig::default()
+                    .name(STORAGE_TIER.into())
+                    .value(Some("cold".into())),
+            ]));
+
+        assert_eq!(Some("cold"), tier_of(&topic));
+        assert_eq!(None, tier_of(&topic.configs(None)));
+    }
+
+    #[test]
+    fn tier_is_not_altered() {
+        let resource = |resource: ConfigResource, name: &str| {
+            AlterConfigsResource::default()
+                .resource_type(resource.into())
+                .resource_name("archive".into())
+                .configs(Some(vec![
+                    AlterableConfig::default()
+                        .name(name.into())
+                        .config_operation(OpType::Set.into())
+                        .value(Some("hot".into())),
+                ]))
+        };
+
+        assert!(alters_tier(&resource(ConfigResource::Topic, STORAGE_TIER)));
+        assert!(!alters_tier(&resource(
+            ConfigResource::Topic,
+            "retention.ms"
+        )));
+        assert!(!alters_tier(&resource(
+            ConfigResource::Broker,
+            STORAGE_TIER
+        )));
+    }
+}
@@ -12,15 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod alter_client_quotas;
+mod alter_user_scram_credentials;
 mod consumer_group_describe;
+mod create_acls;
+mod create_partitions;
 mod create_topics;
+mod delete_acls;
 mod delete_groups;
 mod delete_records;
 mod delete_topics;
+mod describe_acls;
+mod describe_client_quotas;
 mod describe_cluster;
 mod describe_configs;
 mod describe_groups;
+mod describe_log_dirs;
+mod describe_quorum;
 mod describe_topic_partitions;
+mod describe_transactions;
+mod describe_user_scram_credentials;
+mod elect_leaders;
 mod fetch;
 mod find_coordinator;
 mod get_telemetry_subscriptions;
@@ -29,9 +41,11 @@ mod init_producer_id;
 mod list_groups;
 mod list_offsets;
 mod list_partition_reassignments;
+mod list_transactions;
 mod metadata;
 mod produce;
 mod txn;
+mod update_features;
 
 use std::{
     collections::BTreeMap,
@@ -40,16 +54,28 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+pub use alter_client_quotas::AlterClientQuotasService;
+pub use alter_user_scram_credentials::AlterUserScramCredentialsService;
 use async_trait::async_trait;
 pub use consumer_group_describe::ConsumerGroupDescribeService;
+pub use create_acls::CreateAclsService;
+pub use create_partitions::CreatePartitionsService;
 pub use create_topics::CreateTopicsService;
+pub use delete_acls::DeleteAclsService;
 pub use delete_groups::DeleteGroupsService;
 pub use delete_records::DeleteRecordsService;
 pub use delete_topics::DeleteTopicsService;
+pub use describe_acls::DescribeAclsService;
+pub use describe_client_quotas::DescribeClientQuotasService;
 pub use describe_cluster::DescribeClusterService;
 pub use describe_configs::DescribeConfigsService;
 pub use describe_groups::DescribeGroupsService;
+pub use describe_log_dirs::DescribeLogDirsService;
+pub use describe_quorum::DescribeQuorumService;
 pub use describe_topic_partitions::DescribeTopicPartitionsService;
+pub use describe_transactions::DescribeTransactionsService;
+pub use describe_user_scram_credentials::DescribeUserScramCredentialsService;
+pub use elect_leaders::ElectLeadersService;
 pub use fetch::FetchService;
 pub use find_coordinator::FindCoordinatorService;
 pub use get_telemetry_subscriptions::GetTelemetrySubscriptionsService;
@@ -58,6 +84,7 @@ pub use init_producer_id::InitProducerIdService;
 pub use list_groups::ListGroupsService;
 pub use list_offsets::ListOffsetsService;
 pub use list_partition_reassignments::ListPartitionReassignmentsService;
+pub use list_transactions::ListTransactionsService;
 pub use metadata::MetadataService;
 use opentelemetry::{
     KeyValue,
@@ -72,7 +99,7 @@ use tansu_sans_io::{
     describe_cluster_response::DescribeClusterBroker,
     describe_configs_response::DescribeConfigsResult,
     describe_topic_partitions_response::DescribeTopicPartitionsResponseTopic,
-    incremental_alter_configs_request::AlterConfigsResource,
+    fetch_response::AbortedTransaction, incremental_alter_configs_request::AlterConfigsResource,
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     list_groups_response::ListedGroup, record::deflated,
     txn_offset_commit_response::TxnOffsetCommitResponseTopic,
@@ -86,24 +113,36 @@ use tracing::{debug, error, instrument};
 pub use txn::add_offsets::AddOffsetsService as TxnAddOffsetsService;
 pub use txn::add_partitions::AddPartitionService as TxnAddPartitionService;
 pub use txn::offset_commit::OffsetCommitService as TxnOffsetCommitService;
+pub use update_features::UpdateFeaturesService;
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, ListOffsetResponse, METER, MetadataResponse,
-    NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse, Result, Storage,
-    TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest,
-    UpdateError, Version,
+    AclBinding, BrokerRegistrationRequest, ClientQuota, ClientQuotaEntity, Error,
+    FinalizedFeatures, GroupDetail, ListOffsetResponse, METER, MetadataResponse, NamedGroupDetail,
+    OffsetCommitRequest, OffsetStage, OffsetTranslation, ProducerIdResponse, Result,
+    ScramCredential, ScramMechanism, Storage, TopicId, Topition, TopitionUsage,
+    TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest, UpdateError,
+    Version,
 };
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Request {
+    AbortedTxns {
+        topition: Topition,
+        first_offset: i64,
+        last_offset: i64,
+    },
     RegisterBroker(BrokerRegistrationRequest),
     IncrementalAlterResource(AlterConfigsResource),
     CreateTopic {
         topic: CreatableTopic,
         validate_only: bool,
     },
+    AddPartitions {
+        topic: String,
+        count: i32,
+    },
     DeleteRecords(Vec<DeleteRecordsTopic>),
     DeleteTopic(TopicId),
     Brokers,
@@ -130,6 +169,10 @@ pub enum Request {
         offsets: Vec<(Topition, OffsetCommitRequest)>,
     },
     CommittedOffsetTopitions(String),
+    DeleteExpiredOffsets {
+        group_id: String,
+        retention: Duration,
+    },
     OffsetFetch {
         group_id: Option<String>,
         topics: Vec<Topition>,
@@ -177,6 +220,40 @@ pub enum Request {
         producer_epoch: i16,
         committed: bool,
     },
+    UpsertUserScramCredential {
+        username: String,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    },
+    DeleteUserScramCredential {
+        username: String,
+        mechanism: ScramMechanism,
+    },
+    UserScramCredentials(Option<Vec<String>>),
+    CheckpointOffsetTranslation {
+        topition: Topition,
+        translation: OffsetTranslation,
+    },
+    OffsetTranslation {
+        topition: Topition,
+        upstream: i64,
+    },
+    TopitionUsage(Option<Vec<String>>),
+    CreateAcl(AclBinding),
+    DeleteAcl(AclBinding),
+    Acls,
+    AlterClientQuota {
+        entity: ClientQuotaEntity,
+        key: String,
+        // bits of the f64 value, keeping this request Eq, Hash and Ord
+        value: Option<u64>,
+    },
+    ClientQuotas,
+    UpdateFeature {
+        name: String,
+        level: i16,
+    },
+    FinalizedFeatures,
     Maintain(SystemTime),
     ClusterId,
     Node,
@@ -187,18 +264,29 @@ pub enum Request {
 impl Display for Request {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            Self::AbortedTxns { .. } => f.write_str("AbortedTxns"),
+            Self::Acls => f.write_str("Acls"),
+            Self::AddPartitions { .. } => f.write_str("AddPartitions"),
             Self::AdvertisedListener => f.write_str("AdvertisedListener"),
+            Self::AlterClientQuota { .. } => f.write_str("AlterClientQuota"),
             Self::Brokers => f.write_str("Brokers"),
+            Self::CheckpointOffsetTranslation { .. } => f.write_str("CheckpointOffsetTranslation"),
+            Self::ClientQuotas => f.write_str("ClientQuotas"),
             Self::ClusterId => f.write_str("ClusterId"),
             Self::CommittedOffsetTopitions(_) => f.write_str("CommittedOffsetTopitions"),
+            Self::CreateAcl(_) => f.write_str("CreateAcl"),
             Self::CreateTopic { .. } => f.write_str("CreateTopic"),
+            Self::DeleteAcl(_) => f.write_str("DeleteAcl"),
+            Self::DeleteExpiredOffsets { .. } => f.write_str("DeleteExpiredOffsets"),
             Self::DeleteGroups(_) => f.write_str("DeleteGroups"),
             Self::DeleteRecords(_) => f.write_str("DeleteRecords"),
             Self::DeleteTopic(_) => f.write_str("DeleteTopic"),
+            Self::DeleteUserScramCredential { .. } => f.write_str("DeleteUserScramCredential"),
             Self::DescribeConfig { .. } => f.write_str("DescribeConfig"),
             Self::DescribeGroups { .. } => f.write_str("DescribeGroups"),
             Self::DescribeTopicPartitions { .. } => f.write_str("DescribeTopicPartitions"),
             Self::Fetch { .. } => f.write_str("Fetch"),
+            Self::FinalizedFeatures => f.write_str("FinalizedFeatures"),
             Self::IncrementalAlterResource(_) => f.write_str("IncrementalAlterResource"),
             Self::InitProducer { .. } => f.write_str("InitProducer"),
             Self::ListGroups(_) => f.write_str("ListGroups"),
@@ -209,13 +297,18 @@ impl Display for Request {
             Self::OffsetCommit { .. } => f.write_str("OffsetCommit"),
             Self::OffsetFetch { .. } => f.write_str("OffsetFetch"),
             Self::OffsetStage(_) => f.write_str("OffsetStage"),
+            Self::OffsetTranslation { .. } => f.write_str("OffsetTranslation"),
             Self::Produce { .. } => f.write_str("Produce"),
             Self::RegisterBroker(_) => f.write_str("RegisterBroker"),
+            Self::TopitionUsage(_) => f.write_str("TopitionUsage"),
             Self::TxnAddOffsets { .. } => f.write_str("TxnAddOffsets"),
             Self::TxnAddPartitions(_) => f.write_str("TxnAddPartitions"),
             Self::TxnEnd { .. } => f.write_str("TxnEnd"),
             Self::TxnOffsetCommit(_) => f.write_str("TxnOffsetCommit"),
+            Self::UpdateFeature { .. } => f.write_str("UpdateFeature"),
             Self::UpdateGroup { .. } => f.write_str("UpdateGroup"),
+            Self::UpsertUserScramCredential { .. } => f.write_str("UpsertUserScramCredential"),
+            Self::UserScramCredentials(_) => f.write_str("UserScramCredentials"),
             Self::Ping => f.write_str("Ping"),
         }
     }
@@ -223,9 +316,11 @@ impl Display for Request {
 
 #[derive(Clone, Debug)]
 pub enum Response {
+    AbortedTxns(Result<Vec<AbortedTransaction>>),
     RegisterBroker(Result<()>),
     IncrementalAlterResponse(Result<AlterConfigsResourceResponse>),
     CreateTopic(Result<Uuid>),
+    AddPartitions(Result<ErrorCode>),
     DeleteRecords(Result<Vec<DeleteRecordsTopicResult>>),
     DeleteTopic(Result<ErrorCode>),
     Brokers(Result<Vec<DescribeClusterBroker>>),
@@ -235,6 +330,7 @@ pub enum Response {
     ListOffsets(Result<Vec<(Topition, ListOffsetResponse)>>),
     OffsetCommit(Result<Vec<(Topition, ErrorCode)>>),
     CommittedOffsetTopitions(Result<BTreeMap<Topition, i64>>),
+    DeleteExpiredOffsets(Result<Vec<Topition>>),
     OffsetFetch(Result<BTreeMap<Topition, i64>>),
     Metadata(Result<MetadataResponse>),
     DescribeConfig(Result<DescribeConfigsResult>),
@@ -248,6 +344,19 @@ pub enum Response {
     TxnAddPartitions(Result<TxnAddPartitionsResponse>),
     TxnOffsetCommit(Result<Vec<TxnOffsetCommitResponseTopic>>),
     TxnEnd(Result<ErrorCode>),
+    UpsertUserScramCredential(Result<()>),
+    DeleteUserScramCredential(Result<ErrorCode>),
+    UserScramCredentials(Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>>),
+    CheckpointOffsetTranslation(Result<()>),
+    OffsetTranslation(Result<Option<OffsetTranslation>>),
+    TopitionUsage(Result<Vec<TopitionUsage>>),
+    CreateAcl(Result<()>),
+    DeleteAcl(Result<ErrorCode>),
+    Acls(Result<Vec<AclBinding>>),
+    AlterClientQuota(Result<()>),
+    ClientQuotas(Result<Vec<ClientQuota>>),
+    UpdateFeature(Result<()>),
+    FinalizedFeatures(Result<FinalizedFeatures>),
     Maintain(Result<()>),
     ClusterId(Result<String>),
     Node(Result<i32>),
@@ -507,6 +616,26 @@ impl Storage for RequestChannelService {
         .map_err(Into::into)
     }
 
+    #[instrument(skip_all)]
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        self.serve(
+            Context::default(),
+            Request::AddPartitions {
+                topic: topic.to_owned(),
+                count,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::AddPartitions(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
     #[instrument(skip_all)]
     async fn delete_records(
         &self,
@@ -707,6 +836,56 @@ impl Storage for RequestChannelService {
         .map_err(Into::into)
     }
 
+    #[instrument(skip_all)]
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        self.serve(
+            Context::default(),
+            Request::AbortedTxns {
+                topition: topition.to_owned(),
+                first_offset,
+                last_offset,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::AbortedTxns(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_expired_offsets(
+        &self,
+        group_id: &str,
+        retention: Duration,
+    ) -> Result<Vec<Topition>> {
+        self.serve(
+            Context::default(),
+            Request::DeleteExpiredOffsets {
+                group_id: group_id.to_owned(),
+                retention,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::DeleteExpiredOffsets(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
     #[instrument(skip_all)]
     async fn offset_fetch(
         &self,
@@ -1023,6 +1202,261 @@ impl Storage for RequestChannelService {
         .map_err(Into::into)
     }
 
+    #[instrument(skip_all)]
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        let username = username.to_string();
+
+        self.serve(
+            Context::default(),
+            Request::UpsertUserScramCredential {
+                username,
+                mechanism,
+                credential,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::UpsertUserScramCredential(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        let username = username.to_string();
+
+        self.serve(
+            Context::default(),
+            Request::DeleteUserScramCredential {
+                username,
+                mechanism,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::DeleteUserScramCredential(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        let usernames = usernames.map(Vec::from);
+
+        self.serve(Context::default(), Request::UserScramCredentials(usernames))
+            .await
+            .and_then(|response| {
+                if let Response::UserScramCredentials(inner) = response {
+                    inner.map_err(Into::into)
+                } else {
+                    Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        let topition = topition.to_owned();
+
+        self.serve(
+            Context::default(),
+            Request::CheckpointOffsetTranslation {
+                topition,
+                translation,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::CheckpointOffsetTranslation(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        let topition = topition.to_owned();
+
+        self.serve(
+            Context::default(),
+            Request::OffsetTranslation { topition, upstream },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::OffsetTranslation(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        self.serve(
+            Context::default(),
+            Request::TopitionUsage(topics.map(|topics| topics.to_vec())),
+        )
+        .await
+        .and_then(|response| {
+            if let Response::TopitionUsage(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        self.serve(Context::default(), Request::CreateAcl(binding.to_owned()))
+            .await
+            .and_then(|response| {
+                if let Response::CreateAcl(inner) = response {
+                    inner.map_err(Into::into)
+                } else {
+                    Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        self.serve(Context::default(), Request::DeleteAcl(binding.to_owned()))
+            .await
+            .and_then(|response| {
+                if let Response::DeleteAcl(inner) = response {
+                    inner.map_err(Into::into)
+                } else {
+                    Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        self.serve(Context::default(), Request::Acls)
+            .await
+            .and_then(|response| {
+                if let Response::Acls(inner) = response {
+                    inner.map_err(Into::into)
+                } else {
+                    Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        self.serve(
+            Context::default(),
+            Request::AlterClientQuota {
+                entity: entity.to_owned(),
+                key: key.to_owned(),
+                value: value.map(f64::to_bits),
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::AlterClientQuota(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        self.serve(Context::default(), Request::ClientQuotas)
+            .await
+            .and_then(|response| {
+                if let Response::ClientQuotas(inner) = response {
+                    inner.map_err(Into::into)
+                } else {
+                    Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+                }
+            })
+            .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        self.serve(
+            Context::default(),
+            Request::UpdateFeature {
+                name: name.to_owned(),
+                level,
+            },
+        )
+        .await
+        .and_then(|response| {
+            if let Response::UpdateFeature(inner) = response {
+                inner.map_err(Into::into)
+            } else {
+                Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+            }
+        })
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all)]
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        self.serve(Context::default(), Request::FinalizedFeatures)
+            .await
+            .and_then(|response| {
+                if let Response::FinalizedFeatures(inner) = response {
+                    inner.map_err(Into::into)
+                } else {
+                    Err(Error::UnexpectedServiceResponse(Box::new(response)).into())
+                }
+            })
+            .map_err(Into::into)
+    }
+
     #[instrument(skip_all)]
     async fn maintain(&self, now: SystemTime) -> Result<()> {
         self.serve(Context::default(), Request::Maintain(now))
@@ -1201,6 +1635,9 @@ where
             } => Ok(Response::CreateTopic(
                 self.storage.create_topic(topic, validate_only).await,
             )),
+            Request::AddPartitions { topic, count } => Ok(Response::AddPartitions(
+                self.storage.add_partitions(&topic, count).await,
+            )),
             Request::DeleteRecords(delete_records_topics) => Ok(Response::DeleteRecords(
                 self.storage
                     .delete_records(&delete_records_topics[..])
@@ -1253,6 +1690,23 @@ where
             Request::CommittedOffsetTopitions(group_id) => Ok(Response::CommittedOffsetTopitions(
                 self.storage.committed_offset_topitions(&group_id).await,
             )),
+            Request::AbortedTxns {
+                topition,
+                first_offset,
+                last_offset,
+            } => Ok(Response::AbortedTxns(
+                self.storage
+                    .aborted_txns(&topition, first_offset, last_offset)
+                    .await,
+            )),
+            Request::DeleteExpiredOffsets {
+                group_id,
+                retention,
+            } => Ok(Response::DeleteExpiredOffsets(
+                self.storage
+                    .delete_expired_offsets(&group_id, retention)
+                    .await,
+            )),
             Request::OffsetFetch {
                 group_id,
                 topics,
@@ -1351,6 +1805,61 @@ where
                     .txn_end(&transaction_id, producer_id, producer_epoch, committed)
                     .await,
             )),
+            Request::UpsertUserScramCredential {
+                username,
+                mechanism,
+                credential,
+            } => Ok(Response::UpsertUserScramCredential(
+                self.storage
+                    .upsert_user_scram_credential(&username, mechanism, credential)
+                    .await,
+            )),
+            Request::DeleteUserScramCredential {
+                username,
+                mechanism,
+            } => Ok(Response::DeleteUserScramCredential(
+                self.storage
+                    .delete_user_scram_credential(&username, mechanism)
+                    .await,
+            )),
+            Request::UserScramCredentials(usernames) => Ok(Response::UserScramCredentials(
+                self.storage
+                    .user_scram_credentials(usernames.as_deref())
+                    .await,
+            )),
+            Request::CheckpointOffsetTranslation {
+                topition,
+                translation,
+            } => Ok(Response::CheckpointOffsetTranslation(
+                self.storage
+                    .checkpoint_offset_translation(&topition, translation)
+                    .await,
+            )),
+            Request::OffsetTranslation { topition, upstream } => Ok(Response::OffsetTranslation(
+                self.storage.offset_translation(&topition, upstream).await,
+            )),
+            Request::TopitionUsage(topics) => Ok(Response::TopitionUsage(
+                self.storage.topition_usage(topics.as_deref()).await,
+            )),
+            Request::CreateAcl(binding) => {
+                Ok(Response::CreateAcl(self.storage.create_acl(&binding).await))
+            }
+            Request::DeleteAcl(binding) => {
+                Ok(Response::DeleteAcl(self.storage.delete_acl(&binding).await))
+            }
+            Request::Acls => Ok(Response::Acls(self.storage.acls().await)),
+            Request::AlterClientQuota { entity, key, value } => Ok(Response::AlterClientQuota(
+                self.storage
+                    .alter_client_quota(&entity, &key, value.map(f64::from_bits))
+                    .await,
+            )),
+            Request::ClientQuotas => Ok(Response::ClientQuotas(self.storage.client_quotas().await)),
+            Request::UpdateFeature { name, level } => Ok(Response::UpdateFeature(
+                self.storage.update_feature(&name, level).await,
+            )),
+            Request::FinalizedFeatures => Ok(Response::FinalizedFeatures(
+                self.storage.finalized_features().await,
+            )),
             Request::Maintain(now) => Ok(Response::Maintain(self.storage.maintain(now).await)),
             Request::ClusterId => Ok(Response::ClusterId(self.storage.cluster_id().await)),
             Request::Node => Ok(Response::Node(self.storage.node().await)),
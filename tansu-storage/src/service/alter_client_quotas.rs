@@ -0,0 +1,215 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    AlterClientQuotasRequest, AlterClientQuotasResponse, ApiKey, ErrorCode,
+    alter_client_quotas_request::{EntityData, OpData},
+    alter_client_quotas_response,
+};
+use tracing::{debug, instrument};
+
+use crate::{ClientQuotaEntity, Error, Result, Storage};
+
+/// Quotas applying to a user, client id or both
+const CLIENT_KEYS: [&str; 4] = [
+    "consumer_byte_rate",
+    "controller_mutation_rate",
+    "producer_byte_rate",
+    "request_percentage",
+];
+
+/// Quotas applying to an IP address
+const IP_KEYS: [&str; 1] = ["connection_creation_rate"];
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`AlterClientQuotasRequest`] returning [`AlterClientQuotasResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     AlterClientQuotasRequest, ErrorCode,
+///     alter_client_quotas_request::{EntityData, OpData},
+/// };
+/// use tansu_storage::{AlterClientQuotasService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(AlterClientQuotasService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         AlterClientQuotasRequest::default()
+///             .entries(Some(
+///                 [EntryData::default()
+///                     .entity(Some(
+///                         [EntityData::default()
+///                             .entity_type("user".into())
+///                             .entity_name(Some("alice".into()))]
+///                         .into(),
+///                     ))
+///                     .ops(Some(
+///                         [OpData::default()
+///                             .key("producer_byte_rate".into())
+///                             .value(1_048_576.0)
+///                             .remove(false)]
+///                         .into(),
+///                     ))]
+///                 .into(),
+///             ))
+///             .validate_only(false),
+///     )
+///     .await?;
+///
+/// let entries = response.entries.unwrap_or_default();
+/// assert_eq!(1, entries.len());
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(entries[0].error_code)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct AlterClientQuotasService;
+
+impl ApiKey for AlterClientQuotasService {
+    const KEY: i16 = AlterClientQuotasRequest::KEY;
+}
+
+/// The entity of an entry, or why it is invalid
+fn entity(entity: &[EntityData]) -> Result<ClientQuotaEntity, &'static str> {
+    if entity.is_empty() {
+        return Err("empty entity");
+    }
+
+    let mut quota_entity = ClientQuotaEntity::default();
+
+    for data in entity {
+        if ![
+            ClientQuotaEntity::USER,
+            ClientQuotaEntity::CLIENT_ID,
+            ClientQuotaEntity::IP,
+        ]
+        .contains(&data.entity_type.as_str())
+        {
+            return Err("invalid entity type");
+        }
+
+        if quota_entity
+            .0
+            .insert(data.entity_type.clone(), data.entity_name.clone())
+            .is_some()
+        {
+            return Err("duplicate entity type");
+        }
+    }
+
+    if quota_entity.0.contains_key(ClientQuotaEntity::IP) && quota_entity.0.len() > 1 {
+        return Err("ip cannot be combined with other entity types");
+    }
+
+    Ok(quota_entity)
+}
+
+/// Validate the operations on an entity
+fn ops(entity: &ClientQuotaEntity, ops: &[OpData]) -> Result<(), &'static str> {
+    let keys = if entity.0.contains_key(ClientQuotaEntity::IP) {
+        &IP_KEYS[..]
+    } else {
+        &CLIENT_KEYS[..]
+    };
+
+    for op in ops {
+        if !keys.contains(&op.key.as_str()) {
+            return Err("invalid quota key for entity");
+        }
+
+        if !op.remove && !(op.value.is_finite() && op.value > 0.0) {
+            return Err("quota value must be positive");
+        }
+    }
+
+    Ok(())
+}
+
+impl<G> Service<G, AlterClientQuotasRequest> for AlterClientQuotasService
+where
+    G: Storage,
+{
+    type Response = AlterClientQuotasResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: AlterClientQuotasRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut entries = vec![];
+
+        for entry in req.entries.unwrap_or_default() {
+            let data = entry.entity.unwrap_or_default();
+            let op_data = entry.ops.unwrap_or_default();
+
+            let validated =
+                entity(&data).and_then(|entity| ops(&entity, &op_data).map(|()| entity));
+
+            let (error_code, error_message) = match validated {
+                Ok(entity) => {
+                    if !req.validate_only {
+                        for op in &op_data {
+                            ctx.state()
+                                .alter_client_quota(
+                                    &entity,
+                                    &op.key,
+                                    (!op.remove).then_some(op.value),
+                                )
+                                .await
+                                .inspect(|()| debug!(?entity, ?op))?;
+                        }
+                    }
+
+                    (ErrorCode::None, None)
+                }
+
+                Err(message) => (ErrorCode::InvalidRequest, Some(message.into())),
+            };
+
+            entries.push(
+                alter_client_quotas_response::EntryData::default()
+                    .error_code(error_code.into())
+                    .error_message(error_message)
+                    .entity(Some(
+                        data.into_iter()
+                            .map(|data| {
+                                alter_client_quotas_response::EntityData::default()
+                                    .entity_type(data.entity_type)
+                                    .entity_name(data.entity_name)
+                            })
+                            .collect(),
+                    )),
+            );
+        }
+
+        Ok(AlterClientQuotasResponse::default()
+            .throttle_time_ms(0)
+            .entries(Some(entries)))
+    }
+}
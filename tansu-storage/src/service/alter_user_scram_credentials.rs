@@ -0,0 +1,240 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::{BTreeMap, BTreeSet};
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    AlterUserScramCredentialsRequest, AlterUserScramCredentialsResponse, ApiKey, ErrorCode,
+    alter_user_scram_credentials_response::AlterUserScramCredentialsResult,
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, ScramMechanism, Storage};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`AlterUserScramCredentialsRequest`] returning [`AlterUserScramCredentialsResponse`].
+/// ```
+/// use bytes::Bytes;
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     AlterUserScramCredentialsRequest, ErrorCode,
+///     alter_user_scram_credentials_request::ScramCredentialUpsertion,
+/// };
+/// use tansu_storage::{AlterUserScramCredentialsService, Error, ScramMechanism, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(AlterUserScramCredentialsService);
+///
+/// let mechanism = ScramMechanism::Sha256;
+/// let salt = Bytes::from_static(b"pepper");
+/// let iterations = ScramMechanism::MIN_ITERATIONS;
+/// let salted_password = mechanism.salted_password(b"secret", &salt[..], iterations)?;
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         AlterUserScramCredentialsRequest::default()
+///             .deletions(Some([].into()))
+///             .upsertions(Some(
+///                 [ScramCredentialUpsertion::default()
+///                     .name("alice".into())
+///                     .mechanism(mechanism.into())
+///                     .iterations(iterations)
+///                     .salt(salt)
+///                     .salted_password(salted_password)]
+///                 .into(),
+///             )),
+///     )
+///     .await?;
+///
+/// let results = response.results.unwrap_or_default();
+/// assert_eq!(1, results.len());
+/// assert_eq!("alice", results[0].user.as_str());
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct AlterUserScramCredentialsService;
+
+impl ApiKey for AlterUserScramCredentialsService {
+    const KEY: i16 = AlterUserScramCredentialsRequest::KEY;
+}
+
+type Outcome = (ErrorCode, Option<String>);
+
+/// Record the first error for a user, with later errors being ignored
+fn reject(
+    outcomes: &mut BTreeMap<String, Outcome>,
+    user: &str,
+    error_code: ErrorCode,
+    message: &str,
+) {
+    let outcome = outcomes
+        .entry(user.to_owned())
+        .or_insert((ErrorCode::None, None));
+
+    if outcome.0 == ErrorCode::None {
+        *outcome = (error_code, Some(message.into()));
+    }
+}
+
+impl<G> Service<G, AlterUserScramCredentialsRequest> for AlterUserScramCredentialsService
+where
+    G: Storage,
+{
+    type Response = AlterUserScramCredentialsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: AlterUserScramCredentialsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let deletions = req.deletions.unwrap_or_default();
+        let upsertions = req.upsertions.unwrap_or_default();
+
+        let mut outcomes = BTreeMap::new();
+        let mut altered = BTreeSet::new();
+
+        let users = deletions
+            .iter()
+            .map(|deletion| (deletion.name.as_str(), deletion.mechanism))
+            .chain(
+                upsertions
+                    .iter()
+                    .map(|upsertion| (upsertion.name.as_str(), upsertion.mechanism)),
+            )
+            .collect::<Vec<_>>();
+
+        for (user, mechanism) in users {
+            _ = outcomes
+                .entry(user.to_owned())
+                .or_insert((ErrorCode::None, None));
+
+            if user.is_empty() {
+                reject(
+                    &mut outcomes,
+                    user,
+                    ErrorCode::UnacceptableCredential,
+                    "empty user name",
+                );
+            }
+
+            if ScramMechanism::try_from(mechanism).is_err() {
+                reject(
+                    &mut outcomes,
+                    user,
+                    ErrorCode::UnsupportedSaslMechanism,
+                    "unknown SCRAM mechanism",
+                );
+            }
+
+            if !altered.insert((user.to_owned(), mechanism)) {
+                reject(
+                    &mut outcomes,
+                    user,
+                    ErrorCode::DuplicateResource,
+                    "a user credential cannot be altered twice in the same request",
+                );
+            }
+        }
+
+        for upsertion in &upsertions {
+            if !(ScramMechanism::MIN_ITERATIONS..=ScramMechanism::MAX_ITERATIONS)
+                .contains(&upsertion.iterations)
+            {
+                reject(
+                    &mut outcomes,
+                    upsertion.name.as_str(),
+                    ErrorCode::UnacceptableCredential,
+                    "iterations outside of the acceptable range",
+                );
+            }
+        }
+
+        for deletion in deletions {
+            if outcomes
+                .get(&deletion.name)
+                .is_some_and(|(error_code, _)| *error_code != ErrorCode::None)
+            {
+                continue;
+            }
+
+            let mechanism = ScramMechanism::try_from(deletion.mechanism)?;
+
+            let error_code = ctx
+                .state()
+                .delete_user_scram_credential(deletion.name.as_str(), mechanism)
+                .await?;
+
+            debug!(user = deletion.name, ?mechanism, ?error_code);
+
+            if error_code != ErrorCode::None {
+                reject(
+                    &mut outcomes,
+                    deletion.name.as_str(),
+                    error_code,
+                    "user credential does not exist",
+                );
+            }
+        }
+
+        for upsertion in upsertions {
+            if outcomes
+                .get(&upsertion.name)
+                .is_some_and(|(error_code, _)| *error_code != ErrorCode::None)
+            {
+                continue;
+            }
+
+            let mechanism = ScramMechanism::try_from(upsertion.mechanism)?;
+
+            let credential = mechanism.credential(
+                upsertion.salt,
+                upsertion.iterations,
+                &upsertion.salted_password[..],
+            )?;
+
+            ctx.state()
+                .upsert_user_scram_credential(upsertion.name.as_str(), mechanism, credential)
+                .await
+                .inspect(|()| debug!(user = upsertion.name, ?mechanism))?;
+        }
+
+        Ok(AlterUserScramCredentialsResponse::default()
+            .throttle_time_ms(0)
+            .results(Some(
+                outcomes
+                    .into_iter()
+                    .map(|(user, (error_code, error_message))| {
+                        AlterUserScramCredentialsResult::default()
+                            .user(user)
+                            .error_code(error_code.into())
+                            .error_message(error_message)
+                    })
+                    .collect(),
+            )))
+    }
+}
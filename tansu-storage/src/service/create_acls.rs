@@ -0,0 +1,171 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    AclOperation, AclPatternType, AclPermissionType, AclResourceType, ApiKey, CreateAclsRequest,
+    CreateAclsResponse, ErrorCode, create_acls_request::AclCreation,
+    create_acls_response::AclCreationResult,
+};
+use tracing::{debug, instrument};
+
+use crate::{AclBinding, Error, Result, Storage};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`CreateAclsRequest`] returning [`CreateAclsResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     AclOperation, AclPatternType, AclPermissionType, AclResourceType, CreateAclsRequest,
+///     ErrorCode, create_acls_request::AclCreation,
+/// };
+/// use tansu_storage::{CreateAclsService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(CreateAclsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         CreateAclsRequest::default().creations(Some(
+///             [AclCreation::default()
+///                 .resource_type(AclResourceType::Topic.into())
+///                 .resource_name("orders".into())
+///                 .resource_pattern_type(Some(AclPatternType::Literal.into()))
+///                 .principal("User:alice".into())
+///                 .host("*".into())
+///                 .operation(AclOperation::Read.into())
+///                 .permission_type(AclPermissionType::Allow.into())]
+///             .into(),
+///         )),
+///     )
+///     .await?;
+///
+/// let results = response.results.unwrap_or_default();
+/// assert_eq!(1, results.len());
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CreateAclsService;
+
+impl ApiKey for CreateAclsService {
+    const KEY: i16 = CreateAclsRequest::KEY;
+}
+
+/// The binding of a creation, or why it cannot be created
+fn binding(creation: AclCreation) -> Result<AclBinding, &'static str> {
+    let binding = AclBinding {
+        resource_type: AclResourceType::from(creation.resource_type),
+        resource_name: creation.resource_name,
+        pattern_type: creation
+            .resource_pattern_type
+            .map_or(AclPatternType::Literal, AclPatternType::from),
+        principal: creation.principal,
+        host: creation.host,
+        operation: AclOperation::from(creation.operation),
+        permission_type: AclPermissionType::from(creation.permission_type),
+    };
+
+    if matches!(
+        binding.resource_type,
+        AclResourceType::Unknown | AclResourceType::Any
+    ) {
+        return Err("invalid resource type");
+    }
+
+    if !matches!(
+        binding.pattern_type,
+        AclPatternType::Literal | AclPatternType::Prefixed
+    ) {
+        return Err("pattern type must be literal or prefixed");
+    }
+
+    if binding.resource_name.is_empty() {
+        return Err("empty resource name");
+    }
+
+    if !binding.principal.starts_with("User:") {
+        return Err("principal must be of the form User:<name>");
+    }
+
+    if binding.host.is_empty() {
+        return Err("empty host");
+    }
+
+    if matches!(binding.operation, AclOperation::Unknown | AclOperation::Any) {
+        return Err("invalid operation");
+    }
+
+    if !matches!(
+        binding.permission_type,
+        AclPermissionType::Allow | AclPermissionType::Deny
+    ) {
+        return Err("permission type must be allow or deny");
+    }
+
+    Ok(binding)
+}
+
+impl<G> Service<G, CreateAclsRequest> for CreateAclsService
+where
+    G: Storage,
+{
+    type Response = CreateAclsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: CreateAclsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut results = vec![];
+
+        for creation in req.creations.unwrap_or_default() {
+            let result = match binding(creation) {
+                Ok(binding) => {
+                    ctx.state()
+                        .create_acl(&binding)
+                        .await
+                        .inspect(|()| debug!(?binding))?;
+
+                    AclCreationResult::default()
+                        .error_code(ErrorCode::None.into())
+                        .error_message(None)
+                }
+
+                Err(message) => AclCreationResult::default()
+                    .error_code(ErrorCode::InvalidRequest.into())
+                    .error_message(Some(message.into())),
+            };
+
+            results.push(result);
+        }
+
+        Ok(CreateAclsResponse::default()
+            .throttle_time_ms(0)
+            .results(Some(results)))
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, CreatePartitionsRequest, CreatePartitionsResponse, ErrorCode,
+    create_partitions_response::CreatePartitionsTopicResult,
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, Storage, TopicId};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`CreatePartitionsRequest`] returning [`CreatePartitionsResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{CreatePartitionsRequest, ErrorCode,
+///     create_partitions_request::CreatePartitionsTopic,
+///     create_topics_request::CreatableTopic};
+/// use tansu_storage::{CreatePartitionsService, Error, Storage, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let name = "abcba";
+///
+/// _ = storage
+///     .create_topic(
+///         CreatableTopic::default()
+///             .name(name.into())
+///             .num_partitions(1)
+///             .replication_factor(3)
+///             .assignments(Some([].into()))
+///             .configs(Some([].into())),
+///         false,
+///     )
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(CreatePartitionsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         CreatePartitionsRequest::default()
+///             .topics(Some(vec![
+///                 CreatePartitionsTopic::default()
+///                     .name(name.into())
+///                     .count(3)
+///                     .assignments(None),
+///             ]))
+///             .timeout_ms(5_000)
+///             .validate_only(false),
+///     )
+///     .await?;
+///
+/// let results = response.results.unwrap_or_default();
+///
+/// assert_eq!(1, results.len());
+/// assert_eq!(name, results[0].name.as_str());
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CreatePartitionsService;
+
+impl ApiKey for CreatePartitionsService {
+    const KEY: i16 = CreatePartitionsRequest::KEY;
+}
+
+impl CreatePartitionsService {
+    /// Check that a topic exists and count would grow its partitions, without altering storage
+    async fn validate<G>(storage: &G, topic: &str, count: i32) -> Result<ErrorCode>
+    where
+        G: Storage,
+    {
+        let metadata = storage
+            .metadata(Some(&[TopicId::Name(topic.into())]))
+            .await?;
+
+        let Some(existing) = metadata
+            .topics()
+            .iter()
+            .find(|existing| existing.name.as_deref() == Some(topic))
+        else {
+            return Ok(ErrorCode::UnknownTopicOrPartition);
+        };
+
+        let error_code = ErrorCode::try_from(existing.error_code)?;
+        if error_code != ErrorCode::None {
+            return Ok(error_code);
+        }
+
+        let current = existing
+            .partitions
+            .as_ref()
+            .map_or(0, |partitions| partitions.len()) as i32;
+
+        Ok(if count > current {
+            ErrorCode::None
+        } else {
+            ErrorCode::InvalidPartitions
+        })
+    }
+}
+
+impl<G> Service<G, CreatePartitionsRequest> for CreatePartitionsService
+where
+    G: Storage,
+{
+    type Response = CreatePartitionsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: CreatePartitionsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut results = vec![];
+
+        for topic in req.topics.unwrap_or_default() {
+            let outcome = if req.validate_only {
+                Self::validate(ctx.state(), &topic.name, topic.count).await
+            } else {
+                ctx.state().add_partitions(&topic.name, topic.count).await
+            };
+
+            let (error_code, error_message) = match outcome {
+                Ok(ErrorCode::None) => (ErrorCode::None, None),
+
+                Ok(error_code) | Err(Error::Api(error_code)) => {
+                    (error_code, Some(error_code.to_string()))
+                }
+
+                Err(error) => {
+                    debug!(?error);
+                    (ErrorCode::UnknownServerError, None)
+                }
+            };
+
+            results.push(
+                CreatePartitionsTopicResult::default()
+                    .name(topic.name)
+                    .error_code(error_code.into())
+                    .error_message(error_message),
+            );
+        }
+
+        Ok(CreatePartitionsResponse::default()
+            .throttle_time_ms(0)
+            .results(Some(results)))
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    AclOperation, AclPatternType, AclPermissionType, AclResourceType, ApiKey, DeleteAclsRequest,
+    DeleteAclsResponse, ErrorCode,
+    delete_acls_request::DeleteAclsFilter,
+    delete_acls_response::{DeleteAclsFilterResult, DeleteAclsMatchingAcl},
+};
+use tracing::{debug, instrument};
+
+use crate::{AclBindingFilter, Error, Result, Storage};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DeleteAclsRequest`] returning [`DeleteAclsResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     AclOperation, AclPatternType, AclPermissionType, AclResourceType, DeleteAclsRequest,
+///     ErrorCode, delete_acls_request::DeleteAclsFilter,
+/// };
+/// use tansu_storage::{DeleteAclsService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DeleteAclsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         DeleteAclsRequest::default().filters(Some(
+///             [DeleteAclsFilter::default()
+///                 .resource_type_filter(AclResourceType::Topic.into())
+///                 .resource_name_filter(Some("orders".into()))
+///                 .pattern_type_filter(Some(AclPatternType::Literal.into()))
+///                 .principal_filter(None)
+///                 .host_filter(None)
+///                 .operation(AclOperation::Any.into())
+///                 .permission_type(AclPermissionType::Any.into())]
+///             .into(),
+///         )),
+///     )
+///     .await?;
+///
+/// let results = response.filter_results.unwrap_or_default();
+/// assert_eq!(1, results.len());
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+/// assert!(results[0].matching_acls.as_deref().unwrap_or_default().is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DeleteAclsService;
+
+impl ApiKey for DeleteAclsService {
+    const KEY: i16 = DeleteAclsRequest::KEY;
+}
+
+impl From<DeleteAclsFilter> for AclBindingFilter {
+    fn from(filter: DeleteAclsFilter) -> Self {
+        Self {
+            resource_type: AclResourceType::from(filter.resource_type_filter),
+            resource_name: filter.resource_name_filter,
+            pattern_type: filter
+                .pattern_type_filter
+                .map_or(AclPatternType::Literal, AclPatternType::from),
+            principal: filter.principal_filter,
+            host: filter.host_filter,
+            operation: AclOperation::from(filter.operation),
+            permission_type: AclPermissionType::from(filter.permission_type),
+        }
+    }
+}
+
+fn filter_result(error_code: ErrorCode) -> DeleteAclsFilterResult {
+    DeleteAclsFilterResult::default()
+        .error_code(error_code.into())
+        .error_message(None)
+        .matching_acls(Some([].into()))
+}
+
+impl<G> Service<G, DeleteAclsRequest> for DeleteAclsService
+where
+    G: Storage,
+{
+    type Response = DeleteAclsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DeleteAclsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut filter_results = vec![];
+
+        for filter in req.filters.unwrap_or_default() {
+            let filter = AclBindingFilter::from(filter);
+
+            if filter.resource_type == AclResourceType::Unknown
+                || filter.pattern_type == AclPatternType::Unknown
+                || filter.operation == AclOperation::Unknown
+                || filter.permission_type == AclPermissionType::Unknown
+            {
+                filter_results.push(
+                    filter_result(ErrorCode::InvalidRequest)
+                        .error_message(Some("filter has an unknown type".into())),
+                );
+                continue;
+            }
+
+            let mut matching_acls = vec![];
+
+            for binding in ctx.state().describe_acls(&filter).await? {
+                let error_code = ctx.state().delete_acl(&binding).await?;
+
+                debug!(?binding, ?error_code);
+
+                matching_acls.push(
+                    DeleteAclsMatchingAcl::default()
+                        .error_code(error_code.into())
+                        .error_message(None)
+                        .resource_type(binding.resource_type.into())
+                        .resource_name(binding.resource_name)
+                        .pattern_type(Some(binding.pattern_type.into()))
+                        .principal(binding.principal)
+                        .host(binding.host)
+                        .operation(binding.operation.into())
+                        .permission_type(binding.permission_type.into()),
+                );
+            }
+
+            filter_results.push(filter_result(ErrorCode::None).matching_acls(Some(matching_acls)));
+        }
+
+        Ok(DeleteAclsResponse::default()
+            .throttle_time_ms(0)
+            .filter_results(Some(filter_results)))
+    }
+}
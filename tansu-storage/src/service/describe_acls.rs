@@ -0,0 +1,142 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    AclOperation, AclPatternType, AclPermissionType, AclResourceType, ApiKey, DescribeAclsRequest,
+    DescribeAclsResponse, ErrorCode,
+    describe_acls_response::{AclDescription, DescribeAclsResource},
+};
+use tracing::instrument;
+
+use crate::{AclBindingFilter, Error, Result, Storage};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeAclsRequest`] returning [`DescribeAclsResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     AclOperation, AclPatternType, AclPermissionType, AclResourceType, DescribeAclsRequest,
+///     ErrorCode,
+/// };
+/// use tansu_storage::{DescribeAclsService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DescribeAclsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         DescribeAclsRequest::default()
+///             .resource_type_filter(AclResourceType::Any.into())
+///             .resource_name_filter(None)
+///             .pattern_type_filter(Some(AclPatternType::Any.into()))
+///             .principal_filter(None)
+///             .host_filter(None)
+///             .operation(AclOperation::Any.into())
+///             .permission_type(AclPermissionType::Any.into()),
+///     )
+///     .await?;
+///
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(response.error_code)?);
+/// assert!(response.resources.unwrap_or_default().is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescribeAclsService;
+
+impl ApiKey for DescribeAclsService {
+    const KEY: i16 = DescribeAclsRequest::KEY;
+}
+
+impl From<DescribeAclsRequest> for AclBindingFilter {
+    fn from(req: DescribeAclsRequest) -> Self {
+        Self {
+            resource_type: AclResourceType::from(req.resource_type_filter),
+            resource_name: req.resource_name_filter,
+            pattern_type: req
+                .pattern_type_filter
+                .map_or(AclPatternType::Literal, AclPatternType::from),
+            principal: req.principal_filter,
+            host: req.host_filter,
+            operation: AclOperation::from(req.operation),
+            permission_type: AclPermissionType::from(req.permission_type),
+        }
+    }
+}
+
+impl<G> Service<G, DescribeAclsRequest> for DescribeAclsService
+where
+    G: Storage,
+{
+    type Response = DescribeAclsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DescribeAclsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let filter = AclBindingFilter::from(req);
+
+        let mut resources = BTreeMap::<_, Vec<AclDescription>>::new();
+
+        for binding in ctx.state().describe_acls(&filter).await? {
+            resources
+                .entry((
+                    binding.resource_type,
+                    binding.resource_name,
+                    binding.pattern_type,
+                ))
+                .or_default()
+                .push(
+                    AclDescription::default()
+                        .principal(binding.principal)
+                        .host(binding.host)
+                        .operation(binding.operation.into())
+                        .permission_type(binding.permission_type.into()),
+                );
+        }
+
+        Ok(DescribeAclsResponse::default()
+            .throttle_time_ms(0)
+            .error_code(ErrorCode::None.into())
+            .error_message(None)
+            .resources(Some(
+                resources
+                    .into_iter()
+                    .map(|((resource_type, resource_name, pattern_type), acls)| {
+                        DescribeAclsResource::default()
+                            .resource_type(resource_type.into())
+                            .resource_name(resource_name)
+                            .pattern_type(Some(pattern_type.into()))
+                            .acls(Some(acls))
+                    })
+                    .collect(),
+            )))
+    }
+}
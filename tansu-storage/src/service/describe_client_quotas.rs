@@ -0,0 +1,173 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, DescribeClientQuotasRequest, DescribeClientQuotasResponse, ErrorCode,
+    describe_client_quotas_request::ComponentData,
+    describe_client_quotas_response::{EntityData, EntryData, ValueData},
+};
+use tracing::{debug, instrument};
+
+use crate::{
+    ClientQuotaFilter, ClientQuotaFilterComponent, ClientQuotaMatch, Error, Result, Storage,
+};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeClientQuotasRequest`] returning [`DescribeClientQuotasResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     DescribeClientQuotasRequest, ErrorCode, describe_client_quotas_request::ComponentData,
+/// };
+/// use tansu_storage::{DescribeClientQuotasService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DescribeClientQuotasService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         DescribeClientQuotasRequest::default()
+///             .components(Some(
+///                 [ComponentData::default()
+///                     .entity_type("user".into())
+///                     .match_type(2)
+///                     .r#match(None)]
+///                 .into(),
+///             ))
+///             .strict(false),
+///     )
+///     .await?;
+///
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(response.error_code)?);
+/// assert!(response.entries.unwrap_or_default().is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescribeClientQuotasService;
+
+impl ApiKey for DescribeClientQuotasService {
+    const KEY: i16 = DescribeClientQuotasRequest::KEY;
+}
+
+impl TryFrom<ComponentData> for ClientQuotaFilterComponent {
+    type Error = &'static str;
+
+    fn try_from(component: ComponentData) -> Result<Self, Self::Error> {
+        let match_type = match (component.match_type, component.r#match) {
+            (0, Some(name)) => Ok(ClientQuotaMatch::Exact(name)),
+            (1, _) => Ok(ClientQuotaMatch::Default),
+            (2, _) => Ok(ClientQuotaMatch::Specified),
+            (0, None) => Err("exact match without a name"),
+            _otherwise => Err("invalid match type"),
+        }?;
+
+        Ok(Self {
+            entity_type: component.entity_type,
+            match_type,
+        })
+    }
+}
+
+impl TryFrom<DescribeClientQuotasRequest> for ClientQuotaFilter {
+    type Error = &'static str;
+
+    fn try_from(req: DescribeClientQuotasRequest) -> Result<Self, Self::Error> {
+        req.components
+            .unwrap_or_default()
+            .into_iter()
+            .map(ClientQuotaFilterComponent::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|components| Self {
+                components,
+                strict: req.strict,
+            })
+    }
+}
+
+impl<G> Service<G, DescribeClientQuotasRequest> for DescribeClientQuotasService
+where
+    G: Storage,
+{
+    type Response = DescribeClientQuotasResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DescribeClientQuotasRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let filter = match ClientQuotaFilter::try_from(req) {
+            Ok(filter) => filter,
+
+            Err(message) => {
+                debug!(message);
+
+                return Ok(DescribeClientQuotasResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::InvalidRequest.into())
+                    .error_message(Some(message.into()))
+                    .entries(None));
+            }
+        };
+
+        let mut entries = BTreeMap::<_, Vec<ValueData>>::new();
+
+        for quota in ctx.state().describe_client_quotas(&filter).await? {
+            entries
+                .entry(quota.entity)
+                .or_default()
+                .push(ValueData::default().key(quota.key).value(quota.value));
+        }
+
+        Ok(DescribeClientQuotasResponse::default()
+            .throttle_time_ms(0)
+            .error_code(ErrorCode::None.into())
+            .error_message(None)
+            .entries(Some(
+                entries
+                    .into_iter()
+                    .map(|(entity, values)| {
+                        EntryData::default()
+                            .entity(Some(
+                                entity
+                                    .0
+                                    .into_iter()
+                                    .map(|(entity_type, entity_name)| {
+                                        EntityData::default()
+                                            .entity_type(entity_type)
+                                            .entity_name(entity_name)
+                                    })
+                                    .collect(),
+                            ))
+                            .values(Some(values))
+                    })
+                    .collect(),
+            )))
+    }
+}
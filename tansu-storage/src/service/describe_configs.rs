@@ -13,12 +13,29 @@
 // limitations under the License.
 
 use rama::{Context, Service};
-use tansu_sans_io::{ApiKey, ConfigResource, DescribeConfigsRequest, DescribeConfigsResponse};
+use tansu_sans_io::{
+    ApiKey, ConfigResource, DescribeConfigsRequest, DescribeConfigsResponse,
+    describe_configs_response::DescribeConfigsResourceResult,
+};
 use tracing::{error, instrument};
 
-use crate::{Error, Result, Storage};
+use crate::{Error, Result, Storage, is_sensitive};
+
+/// Never describe the value of a sensitive config, or any of its synonyms
+fn redact(config: &mut DescribeConfigsResourceResult) {
+    if is_sensitive(&config.name) {
+        config.value = None;
+        config.is_sensitive = true;
+
+        for synonym in config.synonyms.iter_mut().flatten() {
+            synonym.value = None;
+        }
+    }
+}
 
 /// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeConfigsRequest`] returning [`DescribeConfigsResponse`].
+///
+/// The value of a sensitive config is never described.
 /// ```
 /// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
 /// use tansu_sans_io::{ConfigResource, DescribeConfigsRequest,
@@ -84,16 +101,19 @@ where
         let mut results = vec![];
 
         for resource in req.resources.unwrap_or_default() {
-            results.push(
-                ctx.state()
-                    .describe_config(
-                        resource.resource_name.as_str(),
-                        ConfigResource::from(resource.resource_type),
-                        resource.configuration_keys.as_deref(),
-                    )
-                    .await
-                    .inspect_err(|err| error!(?err))?,
-            );
+            let mut result = ctx
+                .state()
+                .describe_config(
+                    resource.resource_name.as_str(),
+                    ConfigResource::from(resource.resource_type),
+                    resource.configuration_keys.as_deref(),
+                )
+                .await
+                .inspect_err(|err| error!(?err))?;
+
+            result.configs.iter_mut().flatten().for_each(redact);
+
+            results.push(result);
         }
 
         Ok(DescribeConfigsResponse::default().results(Some(results)))
@@ -0,0 +1,162 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, DescribeLogDirsRequest, DescribeLogDirsResponse, ErrorCode,
+    describe_log_dirs_response::{
+        DescribeLogDirsPartition, DescribeLogDirsResult, DescribeLogDirsTopic,
+    },
+};
+use tracing::{error, instrument};
+
+use crate::{Error, Result, Storage};
+
+/// The single logical log directory holding every partition
+const LOG_DIR: &str = "tansu";
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeLogDirsRequest`] returning [`DescribeLogDirsResponse`].
+///
+/// Partitions are described in a single log directory, with the size being
+/// the key and value bytes currently stored by the partition. The offset lag
+/// is that of the log end offset behind the high watermark, which is always
+/// zero for the single copy of each partition. Should the sizes be unavailable from storage, the log directory is described with a
+/// storage error rather than failing the request, so that admin tools listing
+/// the log directories of each broker carry on.
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{DescribeLogDirsRequest, ErrorCode};
+/// use tansu_storage::{DescribeLogDirsService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DescribeLogDirsService);
+///
+/// let response = service
+///     .serve(Context::default(), DescribeLogDirsRequest::default())
+///     .await?;
+///
+/// let results = response.results.unwrap_or_default();
+/// assert_eq!(1, results.len());
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescribeLogDirsService;
+
+impl ApiKey for DescribeLogDirsService {
+    const KEY: i16 = DescribeLogDirsRequest::KEY;
+}
+
+impl<G> Service<G, DescribeLogDirsRequest> for DescribeLogDirsService
+where
+    G: Storage,
+{
+    type Response = DescribeLogDirsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DescribeLogDirsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let requested = req.topics.map(|topics| {
+            topics
+                .into_iter()
+                .map(|topic| (topic.topic, topic.partitions.unwrap_or_default()))
+                .collect::<BTreeMap<_, _>>()
+        });
+
+        let topics = requested
+            .as_ref()
+            .map(|requested| requested.keys().cloned().collect::<Vec<_>>());
+
+        let mut partitions = BTreeMap::<String, Vec<DescribeLogDirsPartition>>::new();
+
+        let sizes = match ctx.state().topition_size(topics.as_deref()).await {
+            Ok(sizes) => sizes,
+
+            Err(err) => {
+                error!(?err);
+
+                return Ok(DescribeLogDirsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(Some(ErrorCode::None.into()))
+                    .results(Some(vec![
+                        DescribeLogDirsResult::default()
+                            .error_code(ErrorCode::KafkaStorageError.into())
+                            .log_dir(LOG_DIR.into())
+                            .topics(Some([].into()))
+                            .total_bytes(Some(-1))
+                            .usable_bytes(Some(-1)),
+                    ])));
+            }
+        };
+
+        for size in sizes {
+            let topic = size.topition.topic();
+            let partition = size.topition.partition();
+
+            if requested.as_ref().is_some_and(|requested| {
+                requested
+                    .get(topic)
+                    .is_none_or(|partitions| !partitions.contains(&partition))
+            }) {
+                continue;
+            }
+
+            partitions.entry(topic.to_owned()).or_default().push(
+                DescribeLogDirsPartition::default()
+                    .partition_index(partition)
+                    .partition_size(size.bytes)
+                    .offset_lag(0)
+                    .is_future_key(false),
+            );
+        }
+
+        let topics = partitions
+            .into_iter()
+            .map(|(name, partitions)| {
+                DescribeLogDirsTopic::default()
+                    .name(name)
+                    .partitions(Some(partitions))
+            })
+            .collect();
+
+        Ok(DescribeLogDirsResponse::default()
+            .throttle_time_ms(0)
+            .error_code(Some(ErrorCode::None.into()))
+            .results(Some(vec![
+                DescribeLogDirsResult::default()
+                    .error_code(ErrorCode::None.into())
+                    .log_dir(LOG_DIR.into())
+                    .topics(Some(topics))
+                    .total_bytes(Some(-1))
+                    .usable_bytes(Some(-1)),
+            ])))
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, DescribeQuorumRequest, DescribeQuorumResponse, ErrorCode,
+    describe_cluster_response::DescribeClusterBroker,
+    describe_quorum_response::{Listener, Node, PartitionData, ReplicaState, TopicData},
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, Storage};
+
+/// The topic of the metadata quorum
+const CLUSTER_METADATA: &str = "__cluster_metadata";
+
+/// The name of the listener of each voter
+const LISTENER: &str = "PLAINTEXT";
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeQuorumRequest`] returning [`DescribeQuorumResponse`].
+///
+/// Tansu has no metadata quorum, with the metadata of the cluster held by the
+/// storage engine. The quorum is described as the brokers of the cluster
+/// being its voters, led by the controller, so that admin tools describing
+/// the quorum (e.g., `kafka-metadata-quorum`) find a healthy one.
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     DescribeQuorumRequest, ErrorCode,
+///     describe_quorum_request::{PartitionData, TopicData},
+/// };
+/// use tansu_storage::{DescribeQuorumService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// const NODE_ID: i32 = 111;
+///
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(NODE_ID)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DescribeQuorumService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         DescribeQuorumRequest::default().topics(Some(
+///             [TopicData::default()
+///                 .topic_name("__cluster_metadata".into())
+///                 .partitions(Some([PartitionData::default().partition_index(0)].into()))]
+///             .into(),
+///         )),
+///     )
+///     .await?;
+///
+/// let topics = response.topics.unwrap_or_default();
+/// let partitions = topics[0].partitions.as_deref().unwrap_or_default();
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(partitions[0].error_code)?);
+/// assert_eq!(NODE_ID, partitions[0].leader_id);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescribeQuorumService;
+
+impl ApiKey for DescribeQuorumService {
+    const KEY: i16 = DescribeQuorumRequest::KEY;
+}
+
+fn voter(broker: &DescribeClusterBroker) -> ReplicaState {
+    ReplicaState::default()
+        .replica_id(broker.broker_id)
+        .replica_directory_id(Some([0; 16]))
+        .log_end_offset(0)
+        .last_fetch_timestamp(Some(-1))
+        .last_caught_up_timestamp(Some(-1))
+}
+
+impl<G> Service<G, DescribeQuorumRequest> for DescribeQuorumService
+where
+    G: Storage,
+{
+    type Response = DescribeQuorumResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DescribeQuorumRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let brokers = ctx.state().brokers().await?;
+        debug!(?brokers);
+
+        let leader_id = brokers.first().map_or(-1, |broker| broker.broker_id);
+
+        let topics = req
+            .topics
+            .unwrap_or_default()
+            .into_iter()
+            .map(|topic| {
+                let partitions = topic
+                    .partitions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|partition| {
+                        let error_code = if topic.topic_name == CLUSTER_METADATA
+                            && partition.partition_index == 0
+                        {
+                            ErrorCode::None
+                        } else {
+                            ErrorCode::UnknownTopicOrPartition
+                        };
+
+                        PartitionData::default()
+                            .partition_index(partition.partition_index)
+                            .error_code(error_code.into())
+                            .error_message(None)
+                            .leader_id(leader_id)
+                            .leader_epoch(0)
+                            .high_watermark(0)
+                            .current_voters(Some(brokers.iter().map(voter).collect()))
+                            .observers(Some([].into()))
+                    })
+                    .collect();
+
+                TopicData::default()
+                    .topic_name(topic.topic_name)
+                    .partitions(Some(partitions))
+            })
+            .collect();
+
+        let nodes = brokers
+            .iter()
+            .map(|broker| {
+                Node::default().node_id(broker.broker_id).listeners(Some(
+                    [Listener::default()
+                        .name(LISTENER.into())
+                        .host(broker.host.clone())
+                        .port(u16::try_from(broker.port).unwrap_or_default())]
+                    .into(),
+                ))
+            })
+            .collect();
+
+        Ok(DescribeQuorumResponse::default()
+            .error_code(ErrorCode::None.into())
+            .error_message(None)
+            .topics(Some(topics))
+            .nodes(Some(nodes)))
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::BTreeMap;
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, DescribeTransactionsRequest, DescribeTransactionsResponse, ErrorCode,
+    describe_transactions_response::{TopicData, TransactionState},
+    to_timestamp,
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, Storage, TxnDescription};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeTransactionsRequest`] returning [`DescribeTransactionsResponse`].
+///
+/// A transactional id without any transaction state in storage is reported
+/// with [`ErrorCode::TransactionalIdNotFound`], with every transactional id
+/// reported with [`ErrorCode::UnsupportedVersion`] by storage that cannot
+/// describe transactions.
+/// ```
+/// use rama::{Context, Layer as _, Service, layer::MapStateLayer};
+/// use tansu_sans_io::{DescribeTransactionsRequest, ErrorCode};
+/// use tansu_storage::{DescribeTransactionsService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DescribeTransactionsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         DescribeTransactionsRequest::default().transactional_ids(Some(["abcba".into()].into())),
+///     )
+///     .await?;
+///
+/// let states = response.transaction_states.unwrap_or_default();
+/// assert_eq!(1, states.len());
+/// assert_eq!("abcba", states[0].transactional_id);
+/// assert_eq!(
+///     ErrorCode::TransactionalIdNotFound,
+///     ErrorCode::try_from(states[0].error_code)?
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescribeTransactionsService;
+
+impl ApiKey for DescribeTransactionsService {
+    const KEY: i16 = DescribeTransactionsRequest::KEY;
+}
+
+impl DescribeTransactionsService {
+    fn failed(transactional_id: String, error_code: ErrorCode) -> TransactionState {
+        TransactionState::default()
+            .error_code(error_code.into())
+            .transactional_id(transactional_id)
+            .transaction_state("".into())
+            .transaction_timeout_ms(0)
+            .transaction_start_time_ms(-1)
+            .producer_id(-1)
+            .producer_epoch(-1)
+            .topics(Some([].into()))
+    }
+
+    fn described(description: TxnDescription) -> Result<TransactionState> {
+        let transaction_start_time_ms = description
+            .started_at
+            .as_ref()
+            .map_or(Ok(-1), to_timestamp)?;
+
+        let topics = description
+            .topitions
+            .iter()
+            .fold(BTreeMap::<&str, Vec<i32>>::new(), |mut topics, topition| {
+                topics
+                    .entry(topition.topic())
+                    .or_default()
+                    .push(topition.partition());
+                topics
+            })
+            .into_iter()
+            .map(|(topic, partitions)| {
+                TopicData::default()
+                    .topic(topic.into())
+                    .partitions(Some(partitions))
+            })
+            .collect();
+
+        Ok(TransactionState::default()
+            .error_code(ErrorCode::None.into())
+            .transaction_state(description.transaction_state().into())
+            .transaction_timeout_ms(description.timeout_ms)
+            .transaction_start_time_ms(transaction_start_time_ms)
+            .producer_id(description.producer_id)
+            .producer_epoch(description.producer_epoch)
+            .topics(Some(topics))
+            .transactional_id(description.transactional_id))
+    }
+}
+
+impl<G> Service<G, DescribeTransactionsRequest> for DescribeTransactionsService
+where
+    G: Storage,
+{
+    type Response = DescribeTransactionsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DescribeTransactionsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let transactional_ids = req.transactional_ids.unwrap_or_default();
+
+        let mut descriptions = match ctx
+            .state()
+            .describe_txns(Some(&transactional_ids[..]))
+            .await
+        {
+            Ok(descriptions) => descriptions
+                .into_iter()
+                .map(|description| (description.transactional_id.clone(), description))
+                .collect::<BTreeMap<_, _>>(),
+
+            Err(Error::Unsupported(operation)) => {
+                debug!(operation);
+
+                return Ok(DescribeTransactionsResponse::default()
+                    .throttle_time_ms(0)
+                    .transaction_states(Some(
+                        transactional_ids
+                            .into_iter()
+                            .map(|transactional_id| {
+                                Self::failed(transactional_id, ErrorCode::UnsupportedVersion)
+                            })
+                            .collect(),
+                    )));
+            }
+
+            Err(err) => return Err(err),
+        };
+
+        debug!(?descriptions);
+
+        let mut transaction_states = vec![];
+
+        for transactional_id in transactional_ids {
+            transaction_states.push(descriptions.remove(&transactional_id).map_or_else(
+                || {
+                    Ok(Self::failed(
+                        transactional_id,
+                        ErrorCode::TransactionalIdNotFound,
+                    ))
+                },
+                Self::described,
+            )?);
+        }
+
+        Ok(DescribeTransactionsResponse::default()
+            .throttle_time_ms(0)
+            .transaction_states(Some(transaction_states)))
+    }
+}
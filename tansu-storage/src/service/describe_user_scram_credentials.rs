@@ -0,0 +1,156 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::{BTreeMap, BTreeSet};
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, DescribeUserScramCredentialsRequest, DescribeUserScramCredentialsResponse, ErrorCode,
+    describe_user_scram_credentials_response::{
+        CredentialInfo, DescribeUserScramCredentialsResult,
+    },
+};
+use tracing::instrument;
+
+use crate::{Error, Result, ScramCredential, ScramMechanism, Storage};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`DescribeUserScramCredentialsRequest`] returning [`DescribeUserScramCredentialsResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     DescribeUserScramCredentialsRequest, ErrorCode,
+///     describe_user_scram_credentials_request::UserName,
+/// };
+/// use tansu_storage::{DescribeUserScramCredentialsService, Error, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(DescribeUserScramCredentialsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         DescribeUserScramCredentialsRequest::default()
+///             .users(Some([UserName::default().name("alice".into())].into())),
+///     )
+///     .await?;
+///
+/// let results = response.results.unwrap_or_default();
+/// assert_eq!(1, results.len());
+/// assert_eq!(
+///     ErrorCode::ResourceNotFound,
+///     ErrorCode::try_from(results[0].error_code)?
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescribeUserScramCredentialsService;
+
+impl ApiKey for DescribeUserScramCredentialsService {
+    const KEY: i16 = DescribeUserScramCredentialsRequest::KEY;
+}
+
+fn result(user: String, error_code: ErrorCode) -> DescribeUserScramCredentialsResult {
+    DescribeUserScramCredentialsResult::default()
+        .user(user)
+        .error_code(error_code.into())
+        .error_message(None)
+        .credential_infos(Some([].into()))
+}
+
+fn described(
+    user: String,
+    mechanisms: BTreeMap<ScramMechanism, ScramCredential>,
+) -> DescribeUserScramCredentialsResult {
+    result(user, ErrorCode::None).credential_infos(Some(
+        mechanisms
+            .into_iter()
+            .map(|(mechanism, credential)| {
+                CredentialInfo::default()
+                    .mechanism(mechanism.into())
+                    .iterations(credential.iterations)
+            })
+            .collect(),
+    ))
+}
+
+impl<G> Service<G, DescribeUserScramCredentialsRequest> for DescribeUserScramCredentialsService
+where
+    G: Storage,
+{
+    type Response = DescribeUserScramCredentialsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: DescribeUserScramCredentialsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let requested = req
+            .users
+            .filter(|users| !users.is_empty())
+            .map(|users| users.into_iter().map(|user| user.name).collect::<Vec<_>>());
+
+        let mut credentials = ctx
+            .state()
+            .user_scram_credentials(requested.as_deref())
+            .await?;
+
+        let results = if let Some(requested) = requested {
+            let mut seen = BTreeSet::new();
+            let duplicates = requested
+                .iter()
+                .filter(|user| !seen.insert(user.as_str()))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+
+            requested
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|user| {
+                    if duplicates.contains(&user) {
+                        result(user, ErrorCode::DuplicateResource)
+                    } else if let Some(mechanisms) = credentials.remove(&user) {
+                        described(user, mechanisms)
+                    } else {
+                        result(user, ErrorCode::ResourceNotFound)
+                            .error_message(Some("user credential does not exist".into()))
+                    }
+                })
+                .collect()
+        } else {
+            credentials
+                .into_iter()
+                .map(|(user, mechanisms)| described(user, mechanisms))
+                .collect()
+        };
+
+        Ok(DescribeUserScramCredentialsResponse::default()
+            .throttle_time_ms(0)
+            .error_code(ErrorCode::None.into())
+            .error_message(None)
+            .results(Some(results)))
+    }
+}
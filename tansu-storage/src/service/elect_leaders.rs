@@ -0,0 +1,197 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, ElectLeadersRequest, ElectLeadersResponse, ErrorCode,
+    elect_leaders_response::{PartitionResult, ReplicaElectionResult},
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, Storage, TopicId};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`ElectLeadersRequest`] returning [`ElectLeadersResponse`].
+///
+/// Every partition is led by the broker that it is served from, which is
+/// also its preferred leader, so an existing partition never needs an
+/// election. A request for all partitions (with null topic partitions) has no
+/// elections to report.
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{ElectLeadersRequest, ErrorCode,
+///     create_topics_request::CreatableTopic,
+///     elect_leaders_request::TopicPartitions};
+/// use tansu_storage::{ElectLeadersService, Error, Storage, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let name = "abcba";
+///
+/// _ = storage
+///     .create_topic(
+///         CreatableTopic::default()
+///             .name(name.into())
+///             .num_partitions(3)
+///             .replication_factor(3)
+///             .assignments(Some([].into()))
+///             .configs(Some([].into())),
+///         false,
+///     )
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(ElectLeadersService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         ElectLeadersRequest::default()
+///             .election_type(Some(0))
+///             .topic_partitions(Some(vec![
+///                 TopicPartitions::default()
+///                     .topic(name.into())
+///                     .partitions(Some(vec![0, 5])),
+///             ]))
+///             .timeout_ms(5_000),
+///     )
+///     .await?;
+///
+/// let results = response.replica_election_results.unwrap_or_default();
+/// assert_eq!(1, results.len());
+///
+/// let partitions = results[0].partition_result.as_deref().unwrap_or_default();
+/// assert_eq!(2, partitions.len());
+/// assert_eq!(
+///     ErrorCode::ElectionNotNeeded,
+///     ErrorCode::try_from(partitions[0].error_code)?
+/// );
+/// assert_eq!(
+///     ErrorCode::UnknownTopicOrPartition,
+///     ErrorCode::try_from(partitions[1].error_code)?
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ElectLeadersService;
+
+impl ApiKey for ElectLeadersService {
+    const KEY: i16 = ElectLeadersRequest::KEY;
+}
+
+impl ElectLeadersService {
+    fn partition_result(partition_id: i32, error_code: ErrorCode) -> PartitionResult {
+        PartitionResult::default()
+            .partition_id(partition_id)
+            .error_code(error_code.into())
+            .error_message(Some(error_code.to_string()))
+    }
+}
+
+impl<G> Service<G, ElectLeadersRequest> for ElectLeadersService
+where
+    G: Storage,
+{
+    type Response = ElectLeadersResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: ElectLeadersRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(topic_partitions) = req.topic_partitions else {
+            return Ok(ElectLeadersResponse::default()
+                .throttle_time_ms(0)
+                .error_code(Some(ErrorCode::None.into()))
+                .replica_election_results(Some([].into())));
+        };
+
+        let topics = topic_partitions
+            .iter()
+            .map(|topic| TopicId::Name(topic.topic.as_str().into()))
+            .collect::<Vec<_>>();
+
+        let existing = ctx
+            .state()
+            .metadata(Some(&topics[..]))
+            .await?
+            .topics()
+            .iter()
+            .filter(|topic| topic.error_code == i16::from(ErrorCode::None))
+            .filter_map(|topic| {
+                topic.name.clone().map(|name| {
+                    (
+                        name,
+                        topic
+                            .partitions
+                            .as_deref()
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|partition| partition.partition_index)
+                            .collect::<BTreeSet<_>>(),
+                    )
+                })
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        debug!(?existing);
+
+        let mut results = vec![];
+
+        for topic in topic_partitions {
+            let partitions = existing.get(&topic.topic);
+
+            results.push(
+                ReplicaElectionResult::default()
+                    .partition_result(Some(
+                        topic
+                            .partitions
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|partition_id| {
+                                Self::partition_result(
+                                    partition_id,
+                                    if partitions.is_some_and(|partitions| {
+                                        partitions.contains(&partition_id)
+                                    }) {
+                                        ErrorCode::ElectionNotNeeded
+                                    } else {
+                                        ErrorCode::UnknownTopicOrPartition
+                                    },
+                                )
+                            })
+                            .collect(),
+                    ))
+                    .topic(topic.topic),
+            );
+        }
+
+        Ok(ElectLeadersResponse::default()
+            .throttle_time_ms(0)
+            .error_code(Some(ErrorCode::None.into()))
+            .replica_election_results(Some(results)))
+    }
+}
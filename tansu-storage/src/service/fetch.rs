@@ -12,22 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use rama::{Context, Service};
 use tansu_sans_io::{
     ApiKey, ErrorCode, FetchRequest, FetchResponse, IsolationLevel,
     fetch_request::{FetchPartition, FetchTopic},
     fetch_response::{
-        EpochEndOffset, FetchableTopicResponse, LeaderIdAndEpoch, PartitionData, SnapshotId,
+        EpochEndOffset, FetchHint, FetchableTopicResponse, LeaderIdAndEpoch, PartitionData,
+        SnapshotId,
     },
-    metadata_response::MetadataResponseTopic,
+    metadata_response::{MetadataResponseBroker, MetadataResponsePartition, MetadataResponseTopic},
     record::deflated::{Batch, Frame},
 };
-use tokio::time::sleep;
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, instrument, warn};
 
-use crate::{Error, Result, Storage, Topition};
+use crate::{
+    Error, Result, Storage, Topition,
+    purgatory::{Delayed, Key},
+};
 
 /// A [`Service`] using [`Storage`] as [`Context`] taking [`FetchRequest`] returning [`FetchResponse`].
 /// ```
@@ -84,7 +87,7 @@ use crate::{Error, Result, Storage, Topition};
 ///
 /// let fetch = {
 ///     let storage = storage.clone();
-///     MapStateLayer::new(|_| storage).into_layer(FetchService)
+///     MapStateLayer::new(|_| storage).into_layer(FetchService::default())
 /// };
 ///
 /// let partition = 0;
@@ -117,14 +120,120 @@ use crate::{Error, Result, Storage, Topition};
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct FetchService;
+///
+/// A long-poll fetch without data is answered with an empty response no later than
+/// `safety_margin` before the client `request_timeout`, rather than letting the client
+/// time out and reconnect. The fetch is woken as soon as a batch is produced through this
+/// broker to any of its topitions, otherwise storage is polled in case of a batch produced
+/// through another broker.
+///
+/// A `read_committed` fetch includes the transactions aborted within the
+/// fetched records, so that the consumer skips their records. Storage that
+/// cannot describe aborted transactions answers a `read_committed` fetch with
+/// `UnsupportedVersion` and no records.
+///
+/// A client in a different rack to this broker is directed to an in-sync
+/// replica in its own rack (`client.rack`) with a preferred read replica.
+///
+/// With `hints` enabled, each partition of a flexible (v12+) response carries
+/// a `FetchHint` tagged field with the end offset of the partition and the
+/// lag of the consumer after this fetch. Clients unaware of the tag ignore it.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FetchService {
+    request_timeout: Duration,
+    safety_margin: Duration,
+    rack: Option<String>,
+    hints: bool,
+}
+
+impl Default for FetchService {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_millis(30_000),
+            safety_margin: Duration::from_millis(500),
+            rack: None,
+            hints: false,
+        }
+    }
+}
 
 impl ApiKey for FetchService {
     const KEY: i16 = FetchRequest::KEY;
 }
 
 impl FetchService {
+    /// The client request timeout, defaulting to 30s (`request.timeout.ms`)
+    pub fn request_timeout(self, request_timeout: Duration) -> Self {
+        Self {
+            request_timeout,
+            ..self
+        }
+    }
+
+    /// Respond this long before the client request timeout, defaulting to 500ms
+    pub fn safety_margin(self, safety_margin: Duration) -> Self {
+        Self {
+            safety_margin,
+            ..self
+        }
+    }
+
+    /// The rack of this broker (`broker.rack`)
+    pub fn rack(self, rack: Option<String>) -> Self {
+        Self { rack, ..self }
+    }
+
+    /// Include the end offset and consumer lag of each partition as a tagged field
+    pub fn hints(self, hints: bool) -> Self {
+        Self { hints, ..self }
+    }
+
+    /// Whether the end offset and consumer lag of each partition are included
+    pub fn has_hints(&self) -> bool {
+        self.hints
+    }
+
+    /// An in-sync replica of a partition in the rack of the client, or -1
+    /// when the client should continue to fetch from this broker
+    fn preferred_read_replica(
+        &self,
+        rack_id: Option<&str>,
+        brokers: &[MetadataResponseBroker],
+        partition: Option<&MetadataResponsePartition>,
+    ) -> i32 {
+        const NO_PREFERRED_READ_REPLICA: i32 = -1;
+
+        let Some(rack_id) = rack_id.filter(|rack_id| !rack_id.is_empty()) else {
+            return NO_PREFERRED_READ_REPLICA;
+        };
+
+        if self.rack.as_deref() == Some(rack_id) {
+            return NO_PREFERRED_READ_REPLICA;
+        }
+
+        partition
+            .and_then(|partition| {
+                partition
+                    .isr_nodes
+                    .as_deref()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|node_id| **node_id != partition.leader_id)
+                    .find(|node_id| {
+                        brokers.iter().any(|broker| {
+                            broker.node_id == **node_id && broker.rack.as_deref() == Some(rack_id)
+                        })
+                    })
+                    .copied()
+            })
+            .unwrap_or(NO_PREFERRED_READ_REPLICA)
+    }
+
+    /// The maximum time spent waiting for data before responding
+    fn deadline(&self, max_wait: Duration) -> Duration {
+        max_wait.min(self.request_timeout.saturating_sub(self.safety_margin))
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn fetch_partition<G>(
         &self,
@@ -135,6 +244,8 @@ impl FetchService {
         isolation: IsolationLevel,
         topic: &str,
         fetch_partition: &FetchPartition,
+        preferred_read_replica: i32,
+        delayed: &mut Delayed,
     ) -> Result<PartitionData>
     where
         G: Storage,
@@ -149,6 +260,7 @@ impl FetchService {
 
         let partition_index = fetch_partition.partition;
         let tp = Topition::new(topic, partition_index);
+        delayed.watch(Key::Topition(tp.clone()))?;
 
         let mut batches = Vec::new();
 
@@ -191,17 +303,59 @@ impl FetchService {
             .await
             .inspect_err(|error| error!(?error, ?tp))?;
 
+        let mut error_code = ErrorCode::None;
+
+        let aborted_transactions =
+            if isolation == IsolationLevel::ReadCommitted && !batches.is_empty() {
+                match ctx
+                    .state()
+                    .aborted_txns(&tp, fetch_partition.fetch_offset, offset - 1)
+                    .await
+                {
+                    Ok(aborted_transactions) => aborted_transactions,
+
+                    // records are withheld rather than given to a read
+                    // committed consumer that cannot skip aborted transactions
+                    Err(Error::Unsupported(operation)) => {
+                        warn!(operation, ?tp);
+                        error_code = ErrorCode::UnsupportedVersion;
+                        batches.clear();
+                        vec![]
+                    }
+
+                    Err(error) => {
+                        error!(?error, ?tp);
+                        return Err(error);
+                    }
+                }
+            } else {
+                vec![]
+            };
+
+        let fetch_hint = self.hints.then(|| {
+            let end_offset = if isolation == IsolationLevel::ReadCommitted {
+                offset_stage.last_stable()
+            } else {
+                offset_stage.high_watermark()
+            };
+
+            FetchHint::default()
+                .end_offset(end_offset)
+                .lag(end_offset.saturating_sub(offset).max(0))
+        });
+
         Ok(PartitionData::default()
             .partition_index(partition_index)
-            .error_code(ErrorCode::None.into())
+            .error_code(error_code.into())
             .high_watermark(offset_stage.high_watermark())
             .last_stable_offset(Some(offset_stage.last_stable()))
             .log_start_offset(Some(offset_stage.log_start()))
             .diverging_epoch(None)
             .current_leader(None)
             .snapshot_id(None)
-            .aborted_transactions(Some([].into()))
-            .preferred_read_replica(Some(-1))
+            .fetch_hint(fetch_hint)
+            .aborted_transactions(Some(aborted_transactions))
+            .preferred_read_replica(Some(preferred_read_replica))
             .records(if batches.is_empty() {
                 None
             } else {
@@ -247,8 +401,9 @@ impl FetchService {
         min_bytes: u32,
         max_bytes: &mut u32,
         isolation: IsolationLevel,
+        rack_id: Option<&str>,
         fetch: &FetchTopic,
-        _is_first: bool,
+        delayed: &mut Delayed,
     ) -> Result<FetchableTopicResponse>
     where
         G: Storage,
@@ -260,12 +415,23 @@ impl FetchService {
         if let Some(MetadataResponseTopic {
             topic_id,
             name: Some(name),
+            partitions: metadata_partitions,
             ..
         }) = metadata.topics().first()
         {
             let mut partitions = Vec::new();
 
             for fetch_partition in fetch.partitions.as_ref().unwrap_or(&Vec::new()) {
+                let preferred_read_replica = self.preferred_read_replica(
+                    rack_id,
+                    metadata.brokers(),
+                    metadata_partitions
+                        .as_deref()
+                        .unwrap_or_default()
+                        .iter()
+                        .find(|partition| partition.partition_index == fetch_partition.partition),
+                );
+
                 let partition = self
                     .fetch_partition(
                         ctx.clone(),
@@ -275,6 +441,8 @@ impl FetchService {
                         isolation,
                         name,
                         fetch_partition,
+                        preferred_read_replica,
+                        delayed,
                     )
                     .await?;
 
@@ -297,6 +465,7 @@ impl FetchService {
         min_bytes: u32,
         max_bytes: &mut u32,
         isolation: IsolationLevel,
+        rack_id: Option<&str>,
         topics: &[FetchTopic],
     ) -> Result<Vec<FetchableTopicResponse>>
     where
@@ -307,60 +476,50 @@ impl FetchService {
         if topics.is_empty() {
             Ok(vec![])
         } else {
-            let start = Instant::now();
-            let mut responses = vec![];
-            let mut iteration = 0;
-            let mut elapsed = Duration::from_millis(0);
-            let mut bytes = 0;
+            let max_wait = self.deadline(max_wait);
+            debug!(?max_wait, ?self.request_timeout, ?self.safety_margin);
 
-            while elapsed <= max_wait && bytes <= min_bytes {
-                debug!(?elapsed, ?max_wait, ?bytes, ?min_bytes);
+            let budget = *max_bytes;
+            let mut delayed = Delayed::new(max_wait);
 
-                let enumerate = topics.iter().enumerate();
-                responses.clear();
+            loop {
+                *max_bytes = budget;
 
-                for (i, fetch) in enumerate {
-                    let fetch_response = self
-                        .fetch_topic(
+                let mut responses = vec![];
+
+                for fetch in topics {
+                    responses.push(
+                        self.fetch_topic(
                             ctx.clone(),
                             max_wait,
                             min_bytes,
                             max_bytes,
                             isolation,
+                            rack_id,
                             fetch,
-                            i == 0,
+                            &mut delayed,
                         )
-                        .await?;
-
-                    responses.push(fetch_response);
+                        .await?,
+                    );
                 }
 
-                bytes += u32::try_from(responses.byte_size())?;
+                let bytes = responses.byte_size();
+                let remaining = delayed.remaining();
 
-                let now = Instant::now();
-                elapsed = now.duration_since(start);
-                let remaining = max_wait.saturating_sub(elapsed);
+                debug!(?max_wait, ?remaining, ?bytes, ?min_bytes);
 
-                debug!(
-                    ?iteration,
-                    ?max_wait,
-                    ?elapsed,
-                    ?remaining,
-                    ?bytes,
-                    ?min_bytes
-                );
+                if bytes >= u64::from(min_bytes) || remaining.is_zero() {
+                    return Ok(responses);
+                }
 
-                sleep(if remaining.as_millis() >= 250 {
-                    remaining / 2
-                } else {
-                    remaining
-                })
-                .await;
+                // woken by a batch produced through this broker, polling for
+                // a batch produced through another
+                let wake = delayed
+                    .wait((remaining.as_millis() >= 250).then(|| remaining / 2))
+                    .await;
 
-                iteration += 1;
+                debug!(?wake);
             }
-
-            Ok(responses)
         }
     }
 }
@@ -401,6 +560,7 @@ where
                 min_bytes,
                 &mut max_bytes,
                 isolation_level,
+                req.rack_id.as_deref(),
                 topics.as_ref(),
             )
             .await?
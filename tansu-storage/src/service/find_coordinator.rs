@@ -17,11 +17,14 @@ use tansu_sans_io::{
     ApiKey, ErrorCode, FindCoordinatorRequest, FindCoordinatorResponse,
     find_coordinator_response::Coordinator,
 };
-use tracing::instrument;
+use tracing::{debug, instrument};
 
 use crate::{Error, Result, Storage};
 
 /// A [`Service`] using [`Storage`] as [`Context`] taking [`FindCoordinatorRequest`] returning [`FindCoordinatorResponse`].
+///
+/// The coordinator of each key is the broker holding its coordinator lease,
+/// which is acquired by this broker when the lease is unheld or has expired.
 /// ```
 /// use rama::{Context, Layer as _, Service, layer::MapStateLayer};
 /// use tansu_sans_io::{ErrorCode, FindCoordinatorRequest};
@@ -89,31 +92,44 @@ where
         ctx: Context<G>,
         req: FindCoordinatorRequest,
     ) -> Result<Self::Response, Self::Error> {
-        let node_id = ctx.state().node().await?;
+        let key_type = req.key_type.unwrap_or_default();
+
+        let lease = if let Some(ref key) = req.key {
+            Some(ctx.state().coordinator_lease(key_type, key).await?)
+        } else {
+            None
+        };
+
+        let coordinators = if let Some(keys) = req.coordinator_keys {
+            let mut coordinators = vec![];
+
+            for key in keys {
+                let lease = ctx.state().coordinator_lease(key_type, &key).await?;
+                debug!(key, ?lease);
+
+                coordinators.push(
+                    Coordinator::default()
+                        .key(key)
+                        .node_id(lease.node_id)
+                        .host(lease.host)
+                        .port(lease.port)
+                        .error_code(ErrorCode::None.into())
+                        .error_message(None),
+                );
+            }
 
-        let listener = ctx.state().advertised_listener().await?;
-        let host = listener.host_str().unwrap_or("localhost");
-        let port = i32::from(listener.port().unwrap_or(9092));
+            Some(coordinators)
+        } else {
+            None
+        };
 
         Ok(FindCoordinatorResponse::default()
             .throttle_time_ms(Some(0))
             .error_code(Some(ErrorCode::None.into()))
             .error_message(Some("NONE".into()))
-            .node_id(Some(node_id))
-            .host(Some(host.into()))
-            .port(Some(port))
-            .coordinators(req.coordinator_keys.map(|keys| {
-                keys.iter()
-                    .map(|key| {
-                        Coordinator::default()
-                            .key(key.to_string())
-                            .node_id(node_id)
-                            .host(host.into())
-                            .port(port)
-                            .error_code(ErrorCode::None.into())
-                            .error_message(None)
-                    })
-                    .collect()
-            })))
+            .node_id(lease.as_ref().map(|lease| lease.node_id))
+            .host(lease.as_ref().map(|lease| lease.host.clone()))
+            .port(lease.as_ref().map(|lease| lease.port))
+            .coordinators(coordinators))
     }
 }
@@ -13,10 +13,14 @@
 // limitations under the License.
 
 use rama::{Context, Service};
-use tansu_sans_io::{ApiKey, IncrementalAlterConfigsRequest, IncrementalAlterConfigsResponse};
-use tracing::instrument;
+use tansu_sans_io::{
+    ApiKey, ConfigResource, ErrorCode, IncrementalAlterConfigsRequest,
+    IncrementalAlterConfigsResponse, incremental_alter_configs_request::AlterConfigsResource,
+    incremental_alter_configs_response::AlterConfigsResourceResponse,
+};
+use tracing::{debug, instrument};
 
-use crate::{Error, Result, Storage};
+use crate::{Error, InvalidConfig, Result, Storage, validate_topic_config};
 
 /// A [`Service`] using [`Storage`] as [`Context`] taking [`IncrementalAlterConfigsRequest`] returning [`IncrementalAlterConfigsResponse`].
 /// ```
@@ -73,8 +77,8 @@ use crate::{Error, Result, Storage};
 ///     ErrorCode::try_from(response.topics.unwrap_or_default()[0].error_code)?
 /// );
 ///
-/// let config_name = "x.y.z";
-/// let config_value = "pqr";
+/// let config_name = "retention.ms";
+/// let config_value = "86400000";
 ///
 /// let describe_configs = {
 ///     let storage = storage.clone();
@@ -156,6 +160,26 @@ impl ApiKey for IncrementalAlterConfigsService {
     const KEY: i16 = IncrementalAlterConfigsRequest::KEY;
 }
 
+/// Validate the configs of a resource against the topic config catalog
+fn validate(resource: &AlterConfigsResource) -> Result<(), InvalidConfig> {
+    if ConfigResource::from(resource.resource_type) != ConfigResource::Topic {
+        return Ok(());
+    }
+
+    resource
+        .configs
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .try_for_each(|config| {
+            validate_topic_config(
+                config.name.as_str(),
+                config.config_operation,
+                config.value.as_deref(),
+            )
+        })
+}
+
 impl<G> Service<G, IncrementalAlterConfigsRequest> for IncrementalAlterConfigsService
 where
     G: Storage,
@@ -172,7 +196,27 @@ where
         let mut responses = vec![];
 
         for resource in req.resources.unwrap_or_default() {
-            responses.push(ctx.state().incremental_alter_resource(resource).await?);
+            let response = match validate(&resource) {
+                Err(invalid) => {
+                    debug!(resource_name = resource.resource_name, %invalid);
+
+                    AlterConfigsResourceResponse::default()
+                        .error_code(invalid.error_code().into())
+                        .error_message(Some(invalid.to_string()))
+                        .resource_type(resource.resource_type)
+                        .resource_name(resource.resource_name)
+                }
+
+                Ok(()) if req.validate_only => AlterConfigsResourceResponse::default()
+                    .error_code(ErrorCode::None.into())
+                    .error_message(None)
+                    .resource_type(resource.resource_type)
+                    .resource_name(resource.resource_name),
+
+                Ok(()) => ctx.state().incremental_alter_resource(resource).await?,
+            };
+
+            responses.push(response);
         }
 
         Ok(IncrementalAlterConfigsResponse::default()
@@ -0,0 +1,148 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::time::{Duration, SystemTime};
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, ErrorCode, ListTransactionsRequest, ListTransactionsResponse,
+    list_transactions_response::TransactionState,
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, Result, Storage, TxnDescription};
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`ListTransactionsRequest`] returning [`ListTransactionsResponse`].
+///
+/// Transactions are filtered by state, producer id and (from version 1) those
+/// that have been running for longer than the duration filter. State filters
+/// that are not transaction states are returned as unknown. Storage that
+/// cannot describe transactions responds with [`ErrorCode::UnsupportedVersion`].
+/// ```
+/// use rama::{Context, Layer as _, Service, layer::MapStateLayer};
+/// use tansu_sans_io::{ErrorCode, ListTransactionsRequest};
+/// use tansu_storage::{Error, ListTransactionsService, StorageContainer};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage).into_layer(ListTransactionsService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         ListTransactionsRequest::default()
+///             .state_filters(Some(["Ongoing".into(), "Pending".into()].into()))
+///             .producer_id_filters(Some([].into())),
+///     )
+///     .await?;
+///
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(response.error_code)?);
+/// assert_eq!(Some(vec!["Pending".into()]), response.unknown_state_filters);
+/// assert_eq!(Some([].into()), response.transaction_states);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ListTransactionsService;
+
+impl ApiKey for ListTransactionsService {
+    const KEY: i16 = ListTransactionsRequest::KEY;
+}
+
+impl<G> Service<G, ListTransactionsRequest> for ListTransactionsService
+where
+    G: Storage,
+{
+    type Response = ListTransactionsResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: ListTransactionsRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let state_filters = req.state_filters.unwrap_or_default();
+        let producer_id_filters = req.producer_id_filters.unwrap_or_default();
+
+        let running_since = req
+            .duration_filter
+            .and_then(|duration| u64::try_from(duration).ok())
+            .map(Duration::from_millis)
+            .and_then(|duration| SystemTime::now().checked_sub(duration));
+
+        let (known, unknown): (Vec<_>, Vec<_>) = state_filters
+            .into_iter()
+            .partition(|state| TxnDescription::STATES.contains(&state.as_str()));
+
+        debug!(?known, ?unknown, ?producer_id_filters, ?running_since);
+
+        let descriptions = match ctx.state().describe_txns(None).await {
+            Ok(descriptions) => descriptions,
+
+            Err(Error::Unsupported(operation)) => {
+                debug!(operation);
+
+                return Ok(ListTransactionsResponse::default()
+                    .throttle_time_ms(0)
+                    .error_code(ErrorCode::UnsupportedVersion.into())
+                    .unknown_state_filters(Some(unknown))
+                    .transaction_states(Some([].into())));
+            }
+
+            Err(err) => return Err(err),
+        };
+
+        let transaction_states = descriptions
+            .into_iter()
+            .filter(|description| {
+                known.is_empty()
+                    || known
+                        .iter()
+                        .any(|state| state == description.transaction_state())
+            })
+            .filter(|description| {
+                producer_id_filters.is_empty()
+                    || producer_id_filters.contains(&description.producer_id)
+            })
+            .filter(|description| {
+                running_since.is_none_or(|running_since| {
+                    description
+                        .started_at
+                        .is_some_and(|started_at| started_at <= running_since)
+                })
+            })
+            .map(|description| {
+                TransactionState::default()
+                    .producer_id(description.producer_id)
+                    .transaction_state(description.transaction_state().into())
+                    .transactional_id(description.transactional_id)
+            })
+            .collect();
+
+        Ok(ListTransactionsResponse::default()
+            .throttle_time_ms(0)
+            .error_code(ErrorCode::None.into())
+            .unknown_state_filters(Some(unknown))
+            .transaction_states(Some(transaction_states)))
+    }
+}
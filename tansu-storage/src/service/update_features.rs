@@ -0,0 +1,194 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rama::{Context, Service};
+use tansu_sans_io::{
+    ApiKey, ErrorCode, UpdateFeaturesRequest, UpdateFeaturesResponse,
+    update_features_request::FeatureUpdateKey, update_features_response::UpdatableFeatureResult,
+};
+use tracing::{debug, instrument};
+
+use crate::{Error, FinalizedFeatures, Result, Storage};
+
+/// An upgrade only, rejecting a lower level
+const UPGRADE: i8 = 1;
+
+/// A [`Service`] using [`Storage`] as [`Context`] taking [`UpdateFeaturesRequest`] returning [`UpdateFeaturesResponse`].
+/// ```
+/// use rama::{Context, Layer, Service as _, layer::MapStateLayer};
+/// use tansu_sans_io::{
+///     ErrorCode, UpdateFeaturesRequest, update_features_request::FeatureUpdateKey,
+/// };
+/// use tansu_storage::{Error, FinalizedFeatures, Storage as _, StorageContainer, UpdateFeaturesService};
+/// use url::Url;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let storage = StorageContainer::builder()
+///     .cluster_id("tansu")
+///     .node_id(111)
+///     .advertised_listener(Url::parse("tcp://localhost:9092")?)
+///     .storage(Url::parse("memory://tansu/")?)
+///     .build()
+///     .await?;
+///
+/// let service = MapStateLayer::new(|_| storage.clone()).into_layer(UpdateFeaturesService);
+///
+/// let response = service
+///     .serve(
+///         Context::default(),
+///         UpdateFeaturesRequest::default()
+///             .timeout_ms(60_000)
+///             .feature_updates(Some(
+///                 [FeatureUpdateKey::default()
+///                     .feature(FinalizedFeatures::SHARE_VERSION.into())
+///                     .max_version_level(0)
+///                     .upgrade_type(Some(2))]
+///                 .into(),
+///             ))
+///             .validate_only(Some(false)),
+///     )
+///     .await?;
+///
+/// let results = response.results.unwrap_or_default();
+/// assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+///
+/// let features = storage.finalized_features().await?;
+/// assert_eq!(0, features.level(FinalizedFeatures::SHARE_VERSION));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct UpdateFeaturesService;
+
+impl ApiKey for UpdateFeaturesService {
+    const KEY: i16 = UpdateFeaturesRequest::KEY;
+}
+
+/// The level of a feature update, or why it is invalid
+fn level(finalized: &FinalizedFeatures, update: &FeatureUpdateKey) -> Result<i16, String> {
+    let Some(supported) = FinalizedFeatures::supported(&update.feature) else {
+        return Err(format!("unsupported feature: {}", update.feature));
+    };
+
+    // a level below 1 removes the feature, which is its lowest level here
+    let level = update.max_version_level.max(supported.min_level);
+
+    if level > supported.max_level {
+        return Err(format!(
+            "{} supports levels {} to {}",
+            supported.name, supported.min_level, supported.max_level
+        ));
+    }
+
+    let downgrade = update
+        .upgrade_type
+        .map_or(update.allow_downgrade.unwrap_or_default(), |upgrade_type| {
+            upgrade_type != UPGRADE
+        });
+
+    if !downgrade && level < finalized.level(&update.feature) {
+        return Err(format!("{} downgrade not allowed", supported.name));
+    }
+
+    Ok(level)
+}
+
+impl<G> Service<G, UpdateFeaturesRequest> for UpdateFeaturesService
+where
+    G: Storage,
+{
+    type Response = UpdateFeaturesResponse;
+    type Error = Error;
+
+    #[instrument(skip(ctx, req))]
+    async fn serve(
+        &self,
+        ctx: Context<G>,
+        req: UpdateFeaturesRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let finalized = ctx.state().finalized_features().await?;
+        let validate_only = req.validate_only.unwrap_or_default();
+
+        let mut results = vec![];
+
+        for update in req.feature_updates.unwrap_or_default() {
+            let (error_code, error_message) = match level(&finalized, &update) {
+                Ok(level) => {
+                    if !validate_only {
+                        ctx.state()
+                            .update_feature(&update.feature, level)
+                            .await
+                            .inspect(|()| debug!(update.feature, level))?;
+                    }
+
+                    (ErrorCode::None, None)
+                }
+
+                Err(message) => (ErrorCode::InvalidUpdateVersion, Some(message)),
+            };
+
+            results.push(
+                UpdatableFeatureResult::default()
+                    .feature(update.feature)
+                    .error_code(error_code.into())
+                    .error_message(error_message),
+            );
+        }
+
+        Ok(UpdateFeaturesResponse::default()
+            .throttle_time_ms(0)
+            .error_code(ErrorCode::None.into())
+            .error_message(None)
+            .results(Some(results)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(feature: &str, level: i16, upgrade_type: i8) -> FeatureUpdateKey {
+        FeatureUpdateKey::default()
+            .feature(feature.into())
+            .max_version_level(level)
+            .upgrade_type(Some(upgrade_type))
+    }
+
+    #[test]
+    fn feature_levels() {
+        let finalized = FinalizedFeatures::default();
+
+        assert_eq!(
+            Ok(1),
+            level(&finalized, &update(FinalizedFeatures::GROUP_VERSION, 1, 1))
+        );
+
+        assert!(level(&finalized, &update(FinalizedFeatures::GROUP_VERSION, 2, 1)).is_err());
+        assert!(level(&finalized, &update("unknown.version", 1, 1)).is_err());
+
+        // disabling a feature enabled by default is a downgrade
+        assert!(
+            level(
+                &finalized,
+                &update(FinalizedFeatures::SHARE_VERSION, 0, UPGRADE)
+            )
+            .is_err()
+        );
+        assert_eq!(
+            Ok(0),
+            level(&finalized, &update(FinalizedFeatures::SHARE_VERSION, -1, 2))
+        );
+    }
+}
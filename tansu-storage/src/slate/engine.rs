@@ -52,6 +52,7 @@
 //! | `b` | `b/{topic:uuid}/{partition:be32}/{offset:be64}` | Batch | Sequential read by offset |
 //! | `c` | `c/{group}/{topic}/{partition:be32}` | OffsetCommitValue | Per-group offset lookup |
 //! | `g` | `g/{group_id}` | GroupDetailVersion | Group state management |
+//! | `u` | `u/{topic:uuid}/{partition:be32}` | Usage | Stored records and bytes per partition |
 //! | `w` | `w/{topic:uuid}/{partition:be32}` | Watermark | Watermark per partition |
 //!
 //! ### Key Ordering Example
@@ -80,16 +81,15 @@
 use std::{cmp::Ordering, fmt, sync::Arc};
 
 use bytes::Bytes;
-use serde::Deserialize;
 use slatedb::Db;
-use tansu_sans_io::{ErrorCode, de::BatchDecoder, record::deflated::Batch};
+use tansu_sans_io::{ErrorCode, IsolationLevel, record::deflated::Batch};
 use tansu_schema::{Registry, lake::House};
 use tracing::debug;
 use url::Url;
 
-use crate::{Error, Result, TopicId};
+use crate::{Error, Result, Storage as _, TopicId, Topition};
 
-use super::types::{TopicMetadata, Topics, Transactions};
+use super::types::{BatchKey, BatchKeyPrefix, TopicMetadata, Topics, Transactions};
 
 /// SlateDB Storage Engine
 ///
@@ -211,6 +211,12 @@ impl Engine {
     // - The main challenge is that a transaction can enlist multiple topics,
     //   which could be in different partitions.
 
+    /// Key for storing all ACL bindings.
+    pub(super) const ACLS: &[u8] = b"acls.pc.bin";
+    /// Key for storing all client quotas.
+    pub(super) const CLIENT_QUOTAS: &[u8] = b"client_quotas.pc.bin";
+    /// Key for storing the finalized features.
+    pub(super) const FEATURES: &[u8] = b"features.pc.bin";
     /// Key for storing all broker registrations.
     pub(super) const BROKERS: &[u8] = b"brokers.pc.bin";
     /// Key for storing all producer states (idempotent/transactional).
@@ -219,6 +225,8 @@ impl Engine {
     pub(super) const TOPICS: &[u8] = b"topics.pc.bin";
     /// Key for storing all transaction states.
     pub(super) const TRANSACTIONS: &[u8] = b"transactions.pc.bin";
+    /// Key for storing all user SCRAM credentials.
+    pub(super) const USERS: &[u8] = b"users.pc.bin";
 
     /// Create a new Engine with the simple constructor (without optional features)
     pub fn new(cluster: &str, node: i32, advertised_listener: Url, db: Arc<Db>) -> Self {
@@ -238,8 +246,94 @@ impl Engine {
     }
 
     pub(super) fn decode(&self, encoded: Bytes) -> Result<Batch> {
-        let decoder = BatchDecoder::new(encoded);
-        Batch::deserialize(decoder).map_err(Into::into)
+        Batch::from_encoded(encoded).map_err(Into::into)
+    }
+
+    /// The encoded batches of a topition from an offset, with the offset of each
+    pub(super) async fn fetch_stored(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation_level: IsolationLevel,
+    ) -> Result<Vec<(i64, Bytes)>> {
+        // Get the high watermark based on isolation level
+        let offset_stage = self.offset_stage(topition).await?;
+        let high_watermark = if isolation_level == IsolationLevel::ReadCommitted {
+            offset_stage.last_stable
+        } else {
+            offset_stage.high_watermark
+        };
+
+        debug!(
+            ?isolation_level,
+            high_watermark, offset, min_bytes, max_bytes
+        );
+
+        let topics = self
+            .db
+            .get(Self::TOPICS)
+            .await
+            .map_err(Error::from)
+            .and_then(|topics| {
+                topics.map_or(Ok(Topics::default()), |encoded| {
+                    postcard::from_bytes(&encoded[..]).map_err(Into::into)
+                })
+            })?;
+
+        let Some(metadata) = topics.get(&topition.topic[..]) else {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        };
+
+        if topition.partition < 0 || topition.partition >= metadata.topic.num_partitions {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        }
+
+        let prefix = postcard::to_stdvec(&BatchKeyPrefix::new(metadata.id, topition.partition))?;
+
+        let mut i = {
+            let from = postcard::to_stdvec(&BatchKey::scan_from(
+                metadata.id,
+                topition.partition,
+                offset,
+            ))?;
+
+            self.db.scan(from..).await?
+        };
+
+        let mut batches = vec![];
+        let mut total_bytes: usize = 0;
+        let min_bytes = min_bytes as usize;
+        let max_bytes = max_bytes as usize;
+
+        while let Some(kv) = i.next().await? {
+            // Check if the key still belongs to the same topic/partition
+            if !kv.key.starts_with(&prefix) {
+                break;
+            }
+
+            let size = kv.value.len();
+
+            let key: BatchKey = postcard::from_bytes(&kv.key)?;
+
+            // Stop if we've reached the high watermark (respecting isolation level)
+            if key.offset >= high_watermark {
+                break;
+            }
+
+            batches.push((key.offset, kv.value));
+            total_bytes += size;
+
+            // Stop if we've exceeded max_bytes (unless we haven't reached min_bytes yet)
+            if total_bytes >= max_bytes
+                || (total_bytes >= min_bytes && size > (max_bytes - total_bytes))
+            {
+                break;
+            }
+        }
+
+        Ok(batches)
     }
 
     pub(super) async fn load_metadata<T>(
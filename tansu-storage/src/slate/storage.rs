@@ -15,7 +15,7 @@
 //! Storage trait implementation for SlateDB Engine
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     iter,
     sync::Arc,
     time::{Duration, SystemTime},
@@ -39,11 +39,12 @@ use tansu_sans_io::{
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
+    fetch_response::AbortedTransaction,
     incremental_alter_configs_request::AlterConfigsResource,
     incremental_alter_configs_response::AlterConfigsResourceResponse,
     list_groups_response::ListedGroup,
     metadata_response::{MetadataResponseBroker, MetadataResponsePartition, MetadataResponseTopic},
-    record::{Record, deflated::Batch, inflated::Batch as InflatedBatch},
+    record::{deflated::Batch, inflated::Batch as InflatedBatch},
     to_system_time,
     txn_offset_commit_response::{TxnOffsetCommitResponsePartition, TxnOffsetCommitResponseTopic},
 };
@@ -52,17 +53,20 @@ use tracing::debug;
 use uuid::Uuid;
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, ListOffsetResponse, MetadataResponse,
-    NULL_TOPIC_ID, NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse, Result,
-    Storage, TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
-    TxnOffsetCommitRequest, TxnState, UpdateError, Version,
+    AclBinding, BrokerRegistrationRequest, ClientQuota, ClientQuotaEntity, Error,
+    FinalizedFeatures, GroupDetail, ListOffsetResponse, MetadataResponse, NULL_TOPIC_ID,
+    NamedGroupDetail, OffsetCommitRequest, OffsetStage, OffsetTranslation, ProducerIdResponse,
+    Result, ScramCredential, ScramMechanism, Storage, TopicId, Topition, TopitionUsage,
+    TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnDescription, TxnOffsetCommitRequest,
+    TxnState, UpdateError, Usage, Version, next_sequence,
 };
 
 use super::engine::Engine;
 use super::types::{
-    BatchKey, BatchKeyPrefix, BrokerInfo, Brokers, GroupDetailVersion, GroupKey, GroupKeyPrefix,
-    OffsetCommitKey, OffsetCommitKeyPrefix, OffsetCommitValue, Producers, TopicMetadata, Topics,
-    Transactions, Txn, TxnCommitOffset, TxnDetail, TxnProduceOffset, Watermark, WatermarkKey,
+    Acls, BatchKey, BatchKeyPrefix, BrokerInfo, Brokers, ClientQuotas, GroupDetailVersion,
+    GroupKey, GroupKeyPrefix, OffsetCommitKey, OffsetCommitKeyPrefix, OffsetCommitValue,
+    OffsetTranslationKey, OffsetTranslations, Producers, TopicMetadata, Topics, Transactions, Txn,
+    TxnCommitOffset, TxnDetail, TxnProduceOffset, UsageKey, Users, Watermark, WatermarkKey,
 };
 
 #[async_trait]
@@ -184,6 +188,32 @@ impl Storage for Engine {
         tx.commit().await.map_err(Error::from).and(Ok(id))
     }
 
+    async fn add_partitions(&self, topic: &str, count: i32) -> Result<ErrorCode> {
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut topics: Topics = self.load_metadata(&tx, Self::TOPICS).await?;
+
+        let Some(metadata) = topics.get_mut(topic) else {
+            return Ok(ErrorCode::UnknownTopicOrPartition);
+        };
+
+        if count <= metadata.topic.num_partitions {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        metadata.topic.num_partitions = count;
+        self.save_metadata(&tx, Self::TOPICS, &topics)?;
+
+        tx.commit()
+            .await
+            .map_err(Error::from)
+            .and(Ok(ErrorCode::None))
+    }
+
     /// Delete records up to a specified offset.
     ///
     /// Physically deletes batch data below the specified offset and updates
@@ -347,11 +377,18 @@ impl Storage for Engine {
             }
         }
 
-        // 2. Delete all watermarks for this topic
+        // 2. Delete all watermarks, offset translations and usage for this topic
         for partition in 0..topic_metadata.topic.num_partitions {
             let watermark_key =
                 postcard::to_stdvec(&WatermarkKey::new(topic_metadata.id, partition))?;
             tx.delete(&watermark_key)?;
+
+            let offset_translation_key =
+                postcard::to_stdvec(&OffsetTranslationKey::new(topic_metadata.id, partition))?;
+            tx.delete(&offset_translation_key)?;
+
+            let usage_key = postcard::to_stdvec(&UsageKey::new(topic_metadata.id, partition))?;
+            tx.delete(&usage_key)?;
         }
 
         // 3. Delete consumer offsets for this topic (scan all groups)
@@ -482,189 +519,14 @@ impl Storage for Engine {
         topition: &Topition,
         deflated: Batch,
     ) -> Result<i64> {
-        let tx = self
-            .db
-            .begin(slatedb::IsolationLevel::SerializableSnapshot)
-            .await
-            .inspect_err(|err| debug!(?err))?;
-
-        let topics: Topics = self.load_metadata(&tx, Self::TOPICS).await?;
-
-        let Some(metadata) = topics.get(&topition.topic[..]) else {
-            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
-        };
-
-        if topition.partition < 0 || topition.partition >= metadata.topic.num_partitions {
-            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
-        }
-
-        // Schema validation (if schemas registry is configured)
-        if let Some(ref schemas) = self.schemas {
-            let inflated = InflatedBatch::try_from(deflated.clone())?;
-            let attributes = BatchAttribute::try_from(inflated.attributes)?;
-
-            // Only validate non-control batches
-            if !attributes.control {
-                schemas.validate(topition.topic(), &inflated).await?;
-            }
-        }
-
-        // Idempotent message check
-        if deflated.is_idempotent() {
-            // NOTE: Contention Hotspot
-            // Loading all producers to check/update sequence numbers prevents high-throughput idempotent production.
-            // This map needs to be sharded or converted to per-producer keys.
-            let mut producers: Producers = self.load_metadata(&tx, Self::PRODUCERS).await?;
-
-            let Some(producer_detail) = producers.get_mut(&deflated.producer_id) else {
-                return Err(Error::Api(ErrorCode::UnknownProducerId));
-            };
-
-            // Get current epoch for this producer
-            let Some(current_epoch) = producer_detail.sequences.last_key_value().map(|(e, _)| *e)
-            else {
-                return Err(Error::Api(ErrorCode::UnknownProducerId));
-            };
-
-            // Get current sequence for this topic/partition
-            let current_sequence = producer_detail
-                .sequences
-                .get(&deflated.producer_epoch)
-                .and_then(|topics| topics.get(&topition.topic))
-                .and_then(|partitions| partitions.get(&topition.partition))
-                .copied()
-                .unwrap_or(0);
-
-            debug!(
-                producer_id = deflated.producer_id,
-                producer_epoch = deflated.producer_epoch,
-                current_epoch,
-                current_sequence,
-                base_sequence = deflated.base_sequence,
-            );
-
-            // Check sequence validity
-            let increment =
-                Self::idempotent_sequence_check(&current_epoch, &current_sequence, &deflated)?;
-
-            // Update sequence
-            _ = producer_detail
-                .sequences
-                .entry(deflated.producer_epoch)
-                .or_default()
-                .entry(topition.topic.clone())
-                .or_default()
-                .insert(topition.partition, current_sequence + increment);
+        self.append(transaction_id, topition, deflated, None).await
+    }
 
-            // Save updated producers
-            self.save_metadata(&tx, Self::PRODUCERS, &producers)?;
-        }
+    async fn replicate(&self, topition: &Topition, batch: Batch) -> Result<i64> {
+        let base_offset = batch.base_offset;
 
-        let mut watermark = tx
-            .get(postcard::to_stdvec(&WatermarkKey::new(
-                metadata.id,
-                topition.partition,
-            ))?)
+        self.append(None, topition, batch.without_producer()?, Some(base_offset))
             .await
-            .map_err(Error::from)
-            .and_then(|watermark| {
-                watermark.map_or(Ok(Watermark::default()), |encoded| {
-                    postcard::from_bytes(&encoded[..]).map_err(Into::into)
-                })
-            })?;
-
-        let offset = watermark.high.unwrap_or_default();
-        let offset_end = offset + deflated.last_offset_delta as i64;
-
-        // Handle transactional produce - update transaction state with offset range
-        if let Some(transaction_id) = transaction_id {
-            let mut transactions: Transactions =
-                self.load_metadata(&tx, Self::TRANSACTIONS).await?;
-
-            if let Some(txn) = transactions.get_mut(transaction_id)
-                && let Some(txn_detail) = txn.epochs.get_mut(&deflated.producer_epoch)
-            {
-                // Get or create the partition entry in produces map
-                let partition_entry = txn_detail
-                    .produces
-                    .entry(topition.topic.clone())
-                    .or_default()
-                    .entry(topition.partition)
-                    .or_insert(None);
-
-                // Update offset range - keep original offset_start if already set
-                if let Some(existing) = partition_entry {
-                    // Just update offset_end
-                    existing.offset_end = offset_end;
-                } else {
-                    // First produce to this partition - set both start and end
-                    *partition_entry = Some(TxnProduceOffset {
-                        offset_start: offset,
-                        offset_end,
-                    });
-                }
-
-                self.save_metadata(&tx, Self::TRANSACTIONS, &transactions)?;
-            }
-        }
-
-        watermark.high = watermark
-            .high
-            .map_or(Some(deflated.last_offset_delta as i64 + 1i64), |high| {
-                Some(high + deflated.last_offset_delta as i64 + 1i64)
-            });
-
-        _ = watermark
-            .timestamps
-            .get_or_insert_default()
-            .insert(deflated.base_timestamp, offset);
-
-        debug!(?watermark);
-
-        let encoded = {
-            let mut writer = BytesMut::new().writer();
-            let mut encoder = Encoder::new(&mut writer);
-            deflated.serialize(&mut encoder)?;
-
-            Bytes::from(writer.into_inner())
-        };
-
-        let batch_key =
-            postcard::to_stdvec(&BatchKey::new(metadata.id, topition.partition, offset))?;
-
-        tx.put(batch_key, &encoded[..])?;
-
-        // Also save the updated watermark
-        let watermark_key =
-            postcard::to_stdvec(&WatermarkKey::new(metadata.id, topition.partition))?;
-        let watermark_value = postcard::to_stdvec(&watermark)?;
-        tx.put(watermark_key, watermark_value)?;
-
-        // Store to data lake if configured
-        if let Some(ref lake) = self.lake {
-            let inflated = InflatedBatch::try_from(deflated.clone())?;
-            let attributes = BatchAttribute::try_from(inflated.attributes)?;
-
-            if !attributes.control {
-                // TODO: Optimization - Avoid synchronous call
-                // Loading config and writing to lake synchronously inside the transaction critical path
-                // increases latency and lock holding time. Consider moving this to an async background task.
-                let config = self
-                    .describe_config(topition.topic(), ConfigResource::Topic, None)
-                    .await?;
-
-                lake.store(
-                    topition.topic(),
-                    topition.partition(),
-                    offset,
-                    &inflated,
-                    config,
-                )
-                .await?;
-            }
-        }
-
-        tx.commit().await.map_err(Error::from).and(Ok(offset))
     }
 
     async fn fetch(
@@ -675,87 +537,31 @@ impl Storage for Engine {
         max_bytes: u32,
         isolation_level: IsolationLevel,
     ) -> Result<Vec<Batch>> {
-        // Get the high watermark based on isolation level
-        let offset_stage = self.offset_stage(topition).await?;
-        let high_watermark = if isolation_level == IsolationLevel::ReadCommitted {
-            offset_stage.last_stable
-        } else {
-            offset_stage.high_watermark
-        };
-
-        debug!(
-            ?isolation_level,
-            high_watermark, offset, min_bytes, max_bytes
-        );
-
-        let topics = self
-            .db
-            .get(Self::TOPICS)
-            .await
-            .map_err(Error::from)
-            .and_then(|topics| {
-                topics.map_or(Ok(Topics::default()), |encoded| {
-                    postcard::from_bytes(&encoded[..]).map_err(Into::into)
+        self.fetch_stored(topition, offset, min_bytes, max_bytes, isolation_level)
+            .await?
+            .into_iter()
+            .map(|(offset, encoded)| {
+                self.decode(encoded).map(|mut batch| {
+                    batch.base_offset = offset;
+                    batch
                 })
-            })?;
-
-        let Some(metadata) = topics.get(&topition.topic[..]) else {
-            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
-        };
-
-        if topition.partition < 0 || topition.partition >= metadata.topic.num_partitions {
-            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
-        }
-
-        let prefix = postcard::to_stdvec(&BatchKeyPrefix::new(metadata.id, topition.partition))?;
-
-        let mut i = {
-            let from = postcard::to_stdvec(&BatchKey::scan_from(
-                metadata.id,
-                topition.partition,
-                offset,
-            ))?;
-
-            self.db.scan(from..).await?
-        };
-
-        let mut batches = vec![];
-        let mut total_bytes: usize = 0;
-        let min_bytes = min_bytes as usize;
-        let max_bytes = max_bytes as usize;
-
-        while let Some(kv) = i.next().await? {
-            // Check if the key still belongs to the same topic/partition
-            if !kv.key.starts_with(&prefix) {
-                break;
-            }
-
-            let size = kv.value.len();
-
-            let key: BatchKey = postcard::from_bytes(&kv.key)?;
-
-            // Stop if we've reached the high watermark (respecting isolation level)
-            if key.offset >= high_watermark {
-                break;
-            }
-
-            // TODO: Performance - Avoid full decode
-            // We decode the entire batch just to check size limits or return it.
-            // For scanning/filtering, we should only decode the header or use a lightweight check.
-            let mut batch = self.decode(kv.value)?;
-            batch.base_offset = key.offset;
-            batches.push(batch);
-            total_bytes += size;
-
-            // Stop if we've exceeded max_bytes (unless we haven't reached min_bytes yet)
-            if total_bytes >= max_bytes
-                || (total_bytes >= min_bytes && size > (max_bytes - total_bytes))
-            {
-                break;
-            }
-        }
+            })
+            .collect()
+    }
 
-        Ok(batches)
+    async fn fetch_encoded(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation_level: IsolationLevel,
+    ) -> Result<Vec<Bytes>> {
+        self.fetch_stored(topition, offset, min_bytes, max_bytes, isolation_level)
+            .await?
+            .into_iter()
+            .map(|(offset, encoded)| Batch::rebase_encoded(encoded, offset).map_err(Into::into))
+            .collect()
     }
 
     async fn offset_stage(&self, topition: &Topition) -> Result<OffsetStage> {
@@ -1225,8 +1031,10 @@ impl Storage for Engine {
                                         .name(config.name.clone())
                                         .value(config.value.clone())
                                         .read_only(false)
-                                        .is_default(None)
-                                        .config_source(Some(ConfigSource::DefaultConfig.into()))
+                                        .is_default(Some(false))
+                                        .config_source(Some(
+                                            ConfigSource::DynamicTopicConfig.into(),
+                                        ))
                                         .is_sensitive(false)
                                         .synonyms(Some([].into()))
                                         .config_type(Some(ConfigResource::Topic.into()))
@@ -1453,15 +1261,8 @@ impl Storage for Engine {
     async fn describe_groups(
         &self,
         group_ids: Option<&[String]>,
-        include_authorized_operations: bool,
+        _include_authorized_operations: bool,
     ) -> Result<Vec<NamedGroupDetail>> {
-        // TODO: Implement include_authorized_operations
-        // Should return ACL-based authorized operations for each group
-        if include_authorized_operations {
-            tracing::warn!(
-                "describe_groups authorized_operations is not implemented, returning empty"
-            );
-        }
         let mut results = vec![];
 
         if let Some(group_ids) = group_ids {
@@ -1592,28 +1393,14 @@ impl Storage for Engine {
                             };
 
                             // Create abort marker batch
-                            let control_batch: Bytes =
-                                ControlBatch::default().abort().try_into()?;
-                            let end_transaction_marker: Bytes =
-                                EndTransactionMarker::default().try_into()?;
-
-                            let batch: Batch = InflatedBatch::builder()
-                                .record(
-                                    Record::builder()
-                                        .key(control_batch.into())
-                                        .value(end_transaction_marker.into()),
-                                )
-                                .attributes(
-                                    BatchAttribute::default()
-                                        .control(true)
-                                        .transaction(true)
-                                        .into(),
-                                )
-                                .producer_id(producer_id)
-                                .producer_epoch(old_epoch)
-                                .base_sequence(-1)
-                                .build()
-                                .and_then(TryInto::try_into)?;
+                            let batch: Batch = InflatedBatch::control(
+                                ControlBatch::default().abort(),
+                                EndTransactionMarker::default(),
+                            )?
+                            .producer_id(producer_id)
+                            .producer_epoch(old_epoch)
+                            .build()
+                            .and_then(TryInto::try_into)?;
 
                             // Get current watermark and increment it
                             let watermark_key =
@@ -2154,30 +1941,18 @@ impl Storage for Engine {
                 let topition = Topition::new(topic_name.clone(), *partition);
 
                 // Create the control batch marker
-                let control_batch: Bytes = if committed {
-                    ControlBatch::default().commit().try_into()?
+                let control = if committed {
+                    ControlBatch::default().commit()
                 } else {
-                    ControlBatch::default().abort().try_into()?
+                    ControlBatch::default().abort()
                 };
-                let end_transaction_marker: Bytes = EndTransactionMarker::default().try_into()?;
-
-                let batch: Batch = InflatedBatch::builder()
-                    .record(
-                        Record::builder()
-                            .key(control_batch.into())
-                            .value(end_transaction_marker.into()),
-                    )
-                    .attributes(
-                        BatchAttribute::default()
-                            .control(true)
-                            .transaction(true)
-                            .into(),
-                    )
-                    .producer_id(producer_id)
-                    .producer_epoch(producer_epoch)
-                    .base_sequence(-1)
-                    .build()
-                    .and_then(TryInto::try_into)?;
+
+                let batch: Batch =
+                    InflatedBatch::control(control, EndTransactionMarker::default())?
+                        .producer_id(producer_id)
+                        .producer_epoch(producer_epoch)
+                        .build()
+                        .and_then(TryInto::try_into)?;
 
                 // Get current watermark and increment it
                 let watermark_key =
@@ -2352,7 +2127,503 @@ impl Storage for Engine {
         Ok(ErrorCode::None)
     }
 
-    /// Maintenance callback for periodic cleanup operations.
+    /// Insert or update the SCRAM credential of a user.
+    async fn upsert_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+        credential: ScramCredential,
+    ) -> Result<()> {
+        debug!(username, ?mechanism);
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut users: Users = self.load_metadata(&tx, Self::USERS).await?;
+
+        _ = users
+            .entry(username.to_owned())
+            .or_default()
+            .insert(mechanism, credential);
+
+        self.save_metadata(&tx, Self::USERS, &users)?;
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// Delete the SCRAM credential of a user.
+    async fn delete_user_scram_credential(
+        &self,
+        username: &str,
+        mechanism: ScramMechanism,
+    ) -> Result<ErrorCode> {
+        debug!(username, ?mechanism);
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut users: Users = self.load_metadata(&tx, Self::USERS).await?;
+
+        let Some(credentials) = users.get_mut(username) else {
+            tx.rollback();
+            return Ok(ErrorCode::ResourceNotFound);
+        };
+
+        if credentials.remove(&mechanism).is_none() {
+            tx.rollback();
+            return Ok(ErrorCode::ResourceNotFound);
+        }
+
+        if credentials.is_empty() {
+            _ = users.remove(username);
+        }
+
+        self.save_metadata(&tx, Self::USERS, &users)?;
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(ErrorCode::None)
+    }
+
+    /// The SCRAM credentials of users, or all users when none are specified.
+    async fn user_scram_credentials(
+        &self,
+        usernames: Option<&[String]>,
+    ) -> Result<BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>> {
+        let users: Users = self
+            .db
+            .get(Self::USERS)
+            .await
+            .map_err(Error::from)
+            .and_then(|users| {
+                users.map_or(Ok(Users::default()), |encoded| {
+                    postcard::from_bytes(&encoded[..]).map_err(Into::into)
+                })
+            })?;
+
+        Ok(users
+            .into_iter()
+            .filter(|(username, _)| usernames.is_none_or(|usernames| usernames.contains(username)))
+            .collect())
+    }
+
+    /// Checkpoint the translation of an upstream offset of a mirrored partition.
+    async fn checkpoint_offset_translation(
+        &self,
+        topition: &Topition,
+        translation: OffsetTranslation,
+    ) -> Result<()> {
+        debug!(?topition, ?translation);
+
+        let topics = self.get_topics().await?;
+
+        let Some(metadata) = topics.get(&topition.topic[..]) else {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        };
+
+        let key = postcard::to_stdvec(&OffsetTranslationKey::new(metadata.id, topition.partition))?;
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut translations =
+            tx.get(&key)
+                .await
+                .map_err(Error::from)
+                .and_then(|translations| {
+                    translations.map_or(Ok(OffsetTranslations::default()), |encoded| {
+                        postcard::from_bytes(&encoded[..]).map_err(Into::into)
+                    })
+                })?;
+
+        _ = translations.insert(translation.upstream, translation.local);
+
+        tx.put(&key, postcard::to_stdvec(&translations)?)?;
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// The latest checkpoint at or before an upstream offset of a mirrored partition.
+    async fn offset_translation(
+        &self,
+        topition: &Topition,
+        upstream: i64,
+    ) -> Result<Option<OffsetTranslation>> {
+        let topics = self.get_topics().await?;
+
+        let Some(metadata) = topics.get(&topition.topic[..]) else {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        };
+
+        let key = postcard::to_stdvec(&OffsetTranslationKey::new(metadata.id, topition.partition))?;
+
+        self.db
+            .get(&key)
+            .await
+            .map_err(Error::from)
+            .and_then(|translations| {
+                translations.map_or(Ok(OffsetTranslations::default()), |encoded| {
+                    postcard::from_bytes::<OffsetTranslations>(&encoded[..]).map_err(Into::into)
+                })
+            })
+            .map(|translations| {
+                translations
+                    .range(..=upstream)
+                    .next_back()
+                    .map(|(upstream, local)| OffsetTranslation::new(*upstream, *local))
+            })
+    }
+
+    async fn topition_usage(&self, topics: Option<&[String]>) -> Result<Vec<TopitionUsage>> {
+        let mut usage = vec![];
+
+        for (name, metadata) in self
+            .get_topics()
+            .await?
+            .iter()
+            .filter(|(name, _)| topics.is_none_or(|topics| topics.contains(name)))
+        {
+            for partition in 0..metadata.topic.num_partitions {
+                let key = postcard::to_stdvec(&UsageKey::new(metadata.id, partition))?;
+
+                usage.push(TopitionUsage {
+                    topition: Topition::new(name.to_owned(), partition),
+                    usage: self
+                        .db
+                        .get(&key)
+                        .await
+                        .map_err(Error::from)
+                        .and_then(|usage| {
+                            usage.map_or(Ok(Usage::default()), |encoded| {
+                                postcard::from_bytes::<Usage>(&encoded[..]).map_err(Into::into)
+                            })
+                        })?,
+                });
+            }
+        }
+
+        Ok(usage)
+    }
+
+    /// Describe the transactions of this cluster, or only those of some
+    /// transactional ids, from the latest epoch of each transactional id.
+    async fn describe_txns(
+        &self,
+        transactional_ids: Option<&[String]>,
+    ) -> Result<Vec<TxnDescription>> {
+        Ok(self
+            .get_transactions()
+            .await?
+            .iter()
+            .filter(|(transactional_id, _)| {
+                transactional_ids.is_none_or(|ids| ids.contains(transactional_id))
+            })
+            .filter_map(|(transactional_id, txn)| {
+                txn.epochs
+                    .last_key_value()
+                    .map(|(epoch, detail)| TxnDescription {
+                        transactional_id: transactional_id.clone(),
+                        producer_id: txn.producer,
+                        producer_epoch: *epoch,
+                        state: detail
+                            .state
+                            .or(detail.started_at.and(Some(TxnState::Begin))),
+                        timeout_ms: detail.transaction_timeout_ms,
+                        started_at: detail.started_at,
+                        topitions: detail
+                            .produces
+                            .iter()
+                            .flat_map(|(topic, partitions)| {
+                                partitions
+                                    .keys()
+                                    .map(|partition| Topition::new(topic.clone(), *partition))
+                            })
+                            .collect(),
+                    })
+            })
+            .collect())
+    }
+
+    /// The aborted transactions with records produced to a topition between
+    /// two offsets, from the epochs kept for each transactional id.
+    async fn aborted_txns(
+        &self,
+        topition: &Topition,
+        first_offset: i64,
+        last_offset: i64,
+    ) -> Result<Vec<AbortedTransaction>> {
+        let mut aborted = self
+            .get_transactions()
+            .await?
+            .values()
+            .flat_map(|txn| {
+                txn.epochs
+                    .values()
+                    .filter(|detail| detail.state == Some(TxnState::Aborted))
+                    .filter_map(|detail| {
+                        detail
+                            .produces
+                            .get(topition.topic())
+                            .and_then(|partitions| partitions.get(&topition.partition()))
+                            .copied()
+                            .flatten()
+                    })
+                    .filter(|range| {
+                        range.offset_end >= first_offset && range.offset_start <= last_offset
+                    })
+                    .map(|range| (range.offset_start, txn.producer))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        aborted.sort_unstable();
+
+        Ok(aborted
+            .into_iter()
+            .map(|(first_offset, producer_id)| {
+                AbortedTransaction::default()
+                    .producer_id(producer_id)
+                    .first_offset(first_offset)
+            })
+            .collect())
+    }
+
+    /// Create an ACL binding, which is unchanged when it already exists.
+    async fn create_acl(&self, binding: &AclBinding) -> Result<()> {
+        debug!(?binding);
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut acls: Acls = self.load_metadata(&tx, Self::ACLS).await?;
+
+        if !acls.insert(binding.to_owned()) {
+            tx.rollback();
+            return Ok(());
+        }
+
+        self.save_metadata(&tx, Self::ACLS, &acls)?;
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// Delete an ACL binding.
+    async fn delete_acl(&self, binding: &AclBinding) -> Result<ErrorCode> {
+        debug!(?binding);
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut acls: Acls = self.load_metadata(&tx, Self::ACLS).await?;
+
+        if !acls.remove(binding) {
+            tx.rollback();
+            return Ok(ErrorCode::ResourceNotFound);
+        }
+
+        self.save_metadata(&tx, Self::ACLS, &acls)?;
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(ErrorCode::None)
+    }
+
+    /// All ACL bindings of this cluster.
+    async fn acls(&self) -> Result<Vec<AclBinding>> {
+        self.db
+            .get(Self::ACLS)
+            .await
+            .map_err(Error::from)
+            .and_then(|acls| {
+                acls.map_or(Ok(Acls::default()), |encoded| {
+                    postcard::from_bytes::<Acls>(&encoded[..]).map_err(Into::into)
+                })
+            })
+            .map(|acls| acls.into_iter().collect())
+    }
+
+    /// Set the value of a client quota, or remove it with `None`.
+    async fn alter_client_quota(
+        &self,
+        entity: &ClientQuotaEntity,
+        key: &str,
+        value: Option<f64>,
+    ) -> Result<()> {
+        debug!(?entity, key, ?value);
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut quotas: ClientQuotas = self.load_metadata(&tx, Self::CLIENT_QUOTAS).await?;
+
+        ClientQuota::alter(&mut quotas, entity, key, value);
+
+        self.save_metadata(&tx, Self::CLIENT_QUOTAS, &quotas)?;
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// All client quotas of this cluster.
+    async fn client_quotas(&self) -> Result<Vec<ClientQuota>> {
+        self.db
+            .get(Self::CLIENT_QUOTAS)
+            .await
+            .map_err(Error::from)
+            .and_then(|quotas| {
+                quotas.map_or(Ok(ClientQuotas::default()), |encoded| {
+                    postcard::from_bytes::<ClientQuotas>(&encoded[..]).map_err(Into::into)
+                })
+            })
+    }
+
+    /// Set the finalized level of a feature.
+    async fn update_feature(&self, name: &str, level: i16) -> Result<()> {
+        debug!(name, level);
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let mut features: FinalizedFeatures = self.load_metadata(&tx, Self::FEATURES).await?;
+
+        features.update(name, level);
+
+        self.save_metadata(&tx, Self::FEATURES, &features)?;
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// The finalized features of this cluster.
+    async fn finalized_features(&self) -> Result<FinalizedFeatures> {
+        self.db
+            .get(Self::FEATURES)
+            .await
+            .map_err(Error::from)
+            .and_then(|features| {
+                features.map_or(Ok(FinalizedFeatures::default()), |encoded| {
+                    postcard::from_bytes::<FinalizedFeatures>(&encoded[..]).map_err(Into::into)
+                })
+            })
+    }
+
+    /// Compact the batches of a topition below an offset, newest first, so
+    /// that the keys of newer batches supersede those in older batches.
+    async fn compact(&self, topition: &Topition, end_offset: i64) -> Result<u64> {
+        let end_offset = end_offset.min(self.offset_stage(topition).await?.last_stable);
+
+        let topics = self.get_topics().await?;
+
+        let Some(metadata) = topics.get(&topition.topic[..]) else {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        };
+
+        let prefix = postcard::to_stdvec(&BatchKeyPrefix::new(metadata.id, topition.partition))?;
+
+        let mut stored = vec![];
+
+        {
+            let from =
+                postcard::to_stdvec(&BatchKey::scan_from(metadata.id, topition.partition, 0))?;
+
+            let mut i = self.db.scan(from..).await?;
+
+            while let Some(kv) = i.next().await? {
+                if !kv.key.starts_with(&prefix) {
+                    break;
+                }
+
+                let key: BatchKey = postcard::from_bytes(&kv.key)?;
+
+                if key.offset >= end_offset {
+                    break;
+                }
+
+                stored.push((key.offset, kv.value));
+            }
+        }
+
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        // the keys of newer batches, superseding those in older batches
+        let mut head = BTreeSet::new();
+        let mut removed = 0;
+
+        for (offset, encoded) in stored.into_iter().rev() {
+            let mut batch = self
+                .decode(encoded)
+                .and_then(|deflated| InflatedBatch::try_from(deflated).map_err(Into::into))?;
+
+            // transaction markers are never compacted
+            if batch.is_control() {
+                continue;
+            }
+
+            batch.base_offset = offset;
+
+            let keys = batch.keys();
+            let compaction = batch.compact(&head)?;
+            head.extend(keys);
+
+            if compaction.records == 0 {
+                continue;
+            }
+
+            debug!(offset, records = compaction.records);
+
+            let encoded = {
+                let mut writer = BytesMut::new().writer();
+                let mut encoder = Encoder::new(&mut writer);
+                Batch::try_from(compaction.batch)?.serialize(&mut encoder)?;
+                Bytes::from(writer.into_inner())
+            };
+
+            tx.put(
+                postcard::to_stdvec(&BatchKey::new(metadata.id, topition.partition, offset))?,
+                &encoded[..],
+            )?;
+
+            removed += compaction.records as u64;
+        }
+
+        tx.commit().await.map_err(Error::from)?;
+
+        Ok(removed)
+    }
+
+    /// Maintenance callback for periodic cleanup operations.
     ///
     /// Runs lake maintenance if configured. This aligns with PG's maintain
     /// implementation.
@@ -2382,3 +2653,237 @@ impl Storage for Engine {
         Ok(())
     }
 }
+
+impl Engine {
+    /// Append a batch at the high watermark, or at the base offset of a
+    /// replicated batch, advancing the high watermark over any gap in the
+    /// upstream offsets
+    async fn append(
+        &self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        deflated: Batch,
+        replicated: Option<i64>,
+    ) -> Result<i64> {
+        let tx = self
+            .db
+            .begin(slatedb::IsolationLevel::SerializableSnapshot)
+            .await
+            .inspect_err(|err| debug!(?err))?;
+
+        let topics: Topics = self.load_metadata(&tx, Self::TOPICS).await?;
+
+        let Some(metadata) = topics.get(&topition.topic[..]) else {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        };
+
+        if topition.partition < 0 || topition.partition >= metadata.topic.num_partitions {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        }
+
+        // Schema validation (if schemas registry is configured)
+        if let Some(ref schemas) = self.schemas {
+            let inflated = InflatedBatch::try_from(deflated.clone())?;
+            let attributes = BatchAttribute::try_from(inflated.attributes)?;
+
+            // Only validate non-control batches
+            if !attributes.control {
+                schemas.validate(topition.topic(), &inflated).await?;
+            }
+        }
+
+        // Idempotent message check
+        if deflated.is_idempotent() {
+            // NOTE: Contention Hotspot
+            // Loading all producers to check/update sequence numbers prevents high-throughput idempotent production.
+            // This map needs to be sharded or converted to per-producer keys.
+            let mut producers: Producers = self.load_metadata(&tx, Self::PRODUCERS).await?;
+
+            let Some(producer_detail) = producers.get_mut(&deflated.producer_id) else {
+                return Err(Error::Api(ErrorCode::UnknownProducerId));
+            };
+
+            // Get current epoch for this producer
+            let Some(current_epoch) = producer_detail.sequences.last_key_value().map(|(e, _)| *e)
+            else {
+                return Err(Error::Api(ErrorCode::UnknownProducerId));
+            };
+
+            // Get current sequence for this topic/partition
+            let current_sequence = producer_detail
+                .sequences
+                .get(&deflated.producer_epoch)
+                .and_then(|topics| topics.get(&topition.topic))
+                .and_then(|partitions| partitions.get(&topition.partition))
+                .copied()
+                .unwrap_or(0);
+
+            debug!(
+                producer_id = deflated.producer_id,
+                producer_epoch = deflated.producer_epoch,
+                current_epoch,
+                current_sequence,
+                base_sequence = deflated.base_sequence,
+            );
+
+            // Check sequence validity
+            let increment =
+                Self::idempotent_sequence_check(&current_epoch, &current_sequence, &deflated)?;
+
+            // Update sequence
+            _ = producer_detail
+                .sequences
+                .entry(deflated.producer_epoch)
+                .or_default()
+                .entry(topition.topic.clone())
+                .or_default()
+                .insert(
+                    topition.partition,
+                    next_sequence(current_sequence, increment),
+                );
+
+            // Save updated producers
+            self.save_metadata(&tx, Self::PRODUCERS, &producers)?;
+        }
+
+        let mut watermark = tx
+            .get(postcard::to_stdvec(&WatermarkKey::new(
+                metadata.id,
+                topition.partition,
+            ))?)
+            .await
+            .map_err(Error::from)
+            .and_then(|watermark| {
+                watermark.map_or(Ok(Watermark::default()), |encoded| {
+                    postcard::from_bytes(&encoded[..]).map_err(Into::into)
+                })
+            })?;
+
+        if let Some(base_offset) = replicated {
+            let high = watermark.high.unwrap_or_default();
+
+            if base_offset < high {
+                debug!(?topition, base_offset, high);
+                return Ok(base_offset);
+            }
+
+            if base_offset > high {
+                debug!(?topition, base_offset, high);
+                watermark.low = watermark.low.or(Some(base_offset));
+                watermark.high = Some(base_offset);
+            }
+        }
+
+        let offset = watermark.high.unwrap_or_default();
+        let offset_end = offset + deflated.last_offset_delta as i64;
+
+        // Handle transactional produce - update transaction state with offset range
+        if let Some(transaction_id) = transaction_id {
+            let mut transactions: Transactions =
+                self.load_metadata(&tx, Self::TRANSACTIONS).await?;
+
+            if let Some(txn) = transactions.get_mut(transaction_id)
+                && let Some(txn_detail) = txn.epochs.get_mut(&deflated.producer_epoch)
+            {
+                // Get or create the partition entry in produces map
+                let partition_entry = txn_detail
+                    .produces
+                    .entry(topition.topic.clone())
+                    .or_default()
+                    .entry(topition.partition)
+                    .or_insert(None);
+
+                // Update offset range - keep original offset_start if already set
+                if let Some(existing) = partition_entry {
+                    // Just update offset_end
+                    existing.offset_end = offset_end;
+                } else {
+                    // First produce to this partition - set both start and end
+                    *partition_entry = Some(TxnProduceOffset {
+                        offset_start: offset,
+                        offset_end,
+                    });
+                }
+
+                self.save_metadata(&tx, Self::TRANSACTIONS, &transactions)?;
+            }
+        }
+
+        watermark.high = watermark
+            .high
+            .map_or(Some(deflated.last_offset_delta as i64 + 1i64), |high| {
+                Some(high + deflated.last_offset_delta as i64 + 1i64)
+            });
+
+        _ = watermark
+            .timestamps
+            .get_or_insert_default()
+            .insert(deflated.base_timestamp, offset);
+
+        debug!(?watermark);
+
+        // stored with its offset, so that it is fetched without being rebased
+        let encoded = {
+            let mut writer = BytesMut::new().writer();
+            let mut encoder = Encoder::new(&mut writer);
+            Batch {
+                base_offset: offset,
+                ..deflated.clone()
+            }
+            .serialize(&mut encoder)?;
+
+            Bytes::from(writer.into_inner())
+        };
+
+        let batch_key =
+            postcard::to_stdvec(&BatchKey::new(metadata.id, topition.partition, offset))?;
+
+        tx.put(batch_key, &encoded[..])?;
+
+        // Also save the updated watermark
+        let watermark_key =
+            postcard::to_stdvec(&WatermarkKey::new(metadata.id, topition.partition))?;
+        let watermark_value = postcard::to_stdvec(&watermark)?;
+        tx.put(watermark_key, watermark_value)?;
+
+        // Maintain the usage of the partition
+        let usage_key = postcard::to_stdvec(&UsageKey::new(metadata.id, topition.partition))?;
+        let usage = tx
+            .get(&usage_key)
+            .await
+            .map_err(Error::from)
+            .and_then(|usage| {
+                usage.map_or(Ok(Usage::default()), |encoded| {
+                    postcard::from_bytes::<Usage>(&encoded[..]).map_err(Into::into)
+                })
+            })?
+            .add(InflatedBatch::try_from(&deflated).map(|inflated| Usage::of(&inflated))?);
+        tx.put(usage_key, postcard::to_stdvec(&usage)?)?;
+
+        // Store to data lake if configured
+        if let Some(ref lake) = self.lake {
+            let inflated = InflatedBatch::try_from(deflated.clone())?;
+            let attributes = BatchAttribute::try_from(inflated.attributes)?;
+
+            if !attributes.control {
+                // TODO: Optimization - Avoid synchronous call
+                // Loading config and writing to lake synchronously inside the transaction critical path
+                // increases latency and lock holding time. Consider moving this to an async background task.
+                let config = self
+                    .describe_config(topition.topic(), ConfigResource::Topic, None)
+                    .await?;
+
+                lake.store(
+                    topition.topic(),
+                    topition.partition(),
+                    offset,
+                    &inflated,
+                    config,
+                )
+                .await?;
+            }
+        }
+
+        tx.commit().await.map_err(Error::from).and(Ok(offset))
+    }
+}
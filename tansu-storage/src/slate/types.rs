@@ -29,6 +29,8 @@
 //! | `b/` | Batch data | `b/{topic_uuid}/{partition:be32}/{offset:be64}` |
 //! | `c/` | Consumer group commits | `c/{group}/{topic}/{partition:be32}` |
 //! | `g/` | Group state | `g/{group_id}` |
+//! | `t/` | Offset translations | `t/{topic_uuid}/{partition:be32}` |
+//! | `u/` | Usage | `u/{topic_uuid}/{partition:be32}` |
 //! | `w/` | Watermarks | `w/{topic_uuid}/{partition:be32}` |
 //!
 //! ## Design Principles
@@ -44,13 +46,18 @@
 //! - Bloom filters can efficiently skip unrelated key types
 //! - Range scans for a partition only touch relevant SSTable blocks
 
-use std::{collections::BTreeMap, time::SystemTime};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::SystemTime,
+};
 
 use serde::{Deserialize, Serialize};
 use tansu_sans_io::create_topics_request::CreatableTopic;
 use uuid::Uuid;
 
-use crate::{GroupDetail, TxnState, Version};
+use crate::{
+    AclBinding, ClientQuota, GroupDetail, ScramCredential, ScramMechanism, TxnState, Version,
+};
 
 // Type aliases
 pub(super) type Group = String;
@@ -66,6 +73,10 @@ pub(super) type Topics = BTreeMap<Topic, TopicMetadata>;
 pub(super) type Producers = BTreeMap<ProducerId, ProducerDetail>;
 pub(super) type Brokers = BTreeMap<i32, BrokerInfo>;
 pub(super) type Transactions = BTreeMap<String, Txn>;
+pub(super) type Users = BTreeMap<String, BTreeMap<ScramMechanism, ScramCredential>>;
+pub(super) type OffsetTranslations = BTreeMap<Offset, Offset>;
+pub(super) type Acls = BTreeSet<AclBinding>;
+pub(super) type ClientQuotas = Vec<ClientQuota>;
 
 /// Transaction produce offset range
 #[derive(
@@ -133,6 +144,55 @@ impl WatermarkKey {
     }
 }
 
+/// Key for offset translation storage: `t/{topic_uuid}/{partition:be32}`
+///
+/// The upstream to local offset checkpoints of a mirrored partition.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub(super) struct OffsetTranslationKey {
+    /// Type prefix for LSM-tree grouping
+    pub prefix: char,
+    /// Topic UUID (16 bytes, fixed)
+    pub topic: Uuid,
+    /// Partition number (big-endian for correct ordering)
+    #[serde(with = "postcard::fixint::be")]
+    pub partition: Partition,
+}
+
+impl OffsetTranslationKey {
+    pub(super) fn new(topic: Uuid, partition: Partition) -> Self {
+        Self {
+            prefix: 't',
+            topic,
+            partition,
+        }
+    }
+}
+
+/// Key for usage storage: `u/{topic_uuid}/{partition:be32}`
+///
+/// The records, bytes and timestamps stored by a partition, maintained as
+/// batches are produced.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub(super) struct UsageKey {
+    /// Type prefix for LSM-tree grouping
+    pub prefix: char,
+    /// Topic UUID (16 bytes, fixed)
+    pub topic: Uuid,
+    /// Partition number (big-endian for correct ordering)
+    #[serde(with = "postcard::fixint::be")]
+    pub partition: Partition,
+}
+
+impl UsageKey {
+    pub(super) fn new(topic: Uuid, partition: Partition) -> Self {
+        Self {
+            prefix: 'u',
+            topic,
+            partition,
+        }
+    }
+}
+
 /// Group detail with version
 #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub(super) struct GroupDetailVersion {
@@ -0,0 +1,32 @@
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+delete from acl
+where
+
+acl.cluster in (
+    select c.id
+    from cluster c
+    where c.name = $1
+)
+
+and acl.resource_type = $2
+and acl.resource_name = $3
+and acl.pattern_type = $4
+and acl.principal = $5
+and acl.host = $6
+and acl.operation = $7
+and acl.permission_type = $8
+
+returning acl.id;
@@ -0,0 +1,26 @@
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+select a.resource_type, a.resource_name, a.pattern_type, a.principal, a.host, a.operation, a.permission_type
+
+from
+
+cluster c
+join acl a on a.cluster = c.id
+
+where
+
+c.name = $1
+
+order by a.resource_type, a.resource_name, a.pattern_type, a.principal, a.host, a.operation, a.permission_type;
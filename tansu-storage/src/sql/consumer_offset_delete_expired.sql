@@ -0,0 +1,30 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+delete from consumer_offset co
+
+using cluster c, consumer_group cg, topic t, topition tp
+
+where
+
+c.name = $1
+and cg.cluster = c.id
+and cg.name = $2
+and co.consumer_group = cg.id
+and tp.id = co.topition
+and t.id = tp.topic
+and co.last_updated < current_timestamp - ($3::bigint * interval '1 millisecond')
+
+returning t.name, tp.partition;
@@ -33,4 +33,5 @@ do update set
 committed_offset = excluded.committed_offset,
 leader_epoch = excluded.leader_epoch,
 timestamp = excluded.timestamp,
+last_updated = excluded.last_updated,
 metadata = excluded.metadata;
@@ -0,0 +1,43 @@
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- acquire or renew the lease when it is unheld, has expired, or is already
+-- held by this node, incrementing the epoch when the lease moves
+--
+insert into coordinator_lease
+(cluster, key_type, key, node, host, port, epoch, expires_at)
+
+select
+c.id, $2, $3, $4, $5, $6, 0,
+current_timestamp + ($7::bigint * interval '1 millisecond')
+
+from cluster c
+
+where c.name = $1
+
+on conflict (cluster, key_type, key)
+
+do update set
+node = excluded.node,
+host = excluded.host,
+port = excluded.port,
+epoch = case
+    when coordinator_lease.node = excluded.node then coordinator_lease.epoch
+    else coordinator_lease.epoch + 1
+end,
+expires_at = excluded.expires_at,
+last_updated = excluded.last_updated
+
+where coordinator_lease.node = excluded.node
+or coordinator_lease.expires_at < current_timestamp;
@@ -16,7 +16,7 @@ use tansu_sans_io::{ErrorCode, record::deflated};
 use tracing::debug;
 use uuid::Uuid;
 
-use crate::{Error, Result};
+use crate::{Error, Result, next_sequence};
 use std::{
     cmp::Ordering,
     hash::{DefaultHasher, Hash, Hasher as _},
@@ -63,6 +63,29 @@ macro_rules! include_sql {
 pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
     let mapping = [
         ("maintain-vacuum.sql", include_sql!("maintain-vacuum.sql")),
+        ("acl_delete.sql", include_sql!("acl_delete.sql")),
+        ("acl_insert.sql", include_sql!("acl_insert.sql")),
+        ("acl_select.sql", include_sql!("acl_select.sql")),
+        (
+            "client_quota_delete.sql",
+            include_sql!("client_quota_delete.sql"),
+        ),
+        (
+            "client_quota_select.sql",
+            include_sql!("client_quota_select.sql"),
+        ),
+        (
+            "client_quota_upsert.sql",
+            include_sql!("client_quota_upsert.sql"),
+        ),
+        (
+            "coordinator_lease_select.sql",
+            include_sql!("coordinator_lease_select.sql"),
+        ),
+        (
+            "coordinator_lease_upsert.sql",
+            include_sql!("coordinator_lease_upsert.sql"),
+        ),
         (
             "consumer_group_delete.sql",
             include_sql!("consumer_group_delete.sql"),
@@ -95,6 +118,10 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
             "consumer_offset_delete_by_cg.sql",
             include_sql!("consumer_offset_delete_by_cg.sql"),
         ),
+        (
+            "consumer_offset_delete_expired.sql",
+            include_sql!("consumer_offset_delete_expired.sql"),
+        ),
         (
             "consumer_offset_delete_by_topic.sql",
             include_sql!("consumer_offset_delete_by_topic.sql"),
@@ -115,6 +142,8 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
             "consumer_offset_select.sql",
             include_sql!("consumer_offset_select.sql"),
         ),
+        ("feature_select.sql", include_sql!("feature_select.sql")),
+        ("feature_upsert.sql", include_sql!("feature_upsert.sql")),
         ("header_copy.sql", include_sql!("header_copy.sql")),
         (
             "header_delete_by_topic.sql",
@@ -138,16 +167,40 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
             "list_latest_offset_uncommitted.sql",
             include_sql!("list_latest_offset_uncommitted.sql"),
         ),
+        (
+            "lite/consumer_offset_delete_unchanged.sql",
+            include_sql!("../lite/consumer_offset_delete_unchanged.sql"),
+        ),
+        (
+            "lite/consumer_offset_last_updated.sql",
+            include_sql!("../lite/consumer_offset_last_updated.sql"),
+        ),
         (
             "lite/policy_delete.sql",
             include_sql!("../lite/policy_delete.sql"),
         ),
+        (
+            "lite/topition_usage_release.sql",
+            include_sql!("../lite/topition_usage_release.sql"),
+        ),
         (
             "lite/vacuum_into.sql",
             include_sql!("../lite/vacuum_into.sql"),
         ),
+        (
+            "offset_translation_select.sql",
+            include_sql!("offset_translation_select.sql"),
+        ),
+        (
+            "offset_translation_upsert.sql",
+            include_sql!("offset_translation_upsert.sql"),
+        ),
         ("policy_compact.sql", include_sql!("policy_compact.sql")),
         ("policy_delete.sql", include_sql!("policy_delete.sql")),
+        (
+            "policy_retention_bytes.sql",
+            include_sql!("policy_retention_bytes.sql"),
+        ),
         ("ping.sql", "select 1 + 1".to_string()),
         (
             "producer_detail_delete_by_topic.sql",
@@ -190,7 +243,27 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
         ("record_fetch.sql", include_sql!("record_fetch.sql")),
         ("record_fetch_pg.sql", include_sql!("record_fetch_pg.sql")),
         ("record_insert.sql", include_sql!("record_insert.sql")),
+        (
+            "record_segment_ahead.sql",
+            include_sql!("record_segment_ahead.sql"),
+        ),
+        (
+            "record_segment_create.sql",
+            include_sql!("record_segment_create.sql"),
+        ),
+        (
+            "record_segment_drop.sql",
+            include_sql!("record_segment_drop.sql"),
+        ),
+        (
+            "record_segment_expired.sql",
+            include_sql!("record_segment_expired.sql"),
+        ),
         ("register_broker.sql", include_sql!("register_broker.sql")),
+        (
+            "schema_table_missing.sql",
+            include_sql!("schema_table_missing.sql"),
+        ),
         ("topic_by_cluster.sql", include_sql!("topic_by_cluster.sql")),
         ("topic_by_uuid.sql", include_sql!("topic_by_uuid.sql")),
         (
@@ -220,6 +293,10 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
             "topic_select_uuid.sql",
             include_sql!("topic_select_uuid.sql"),
         ),
+        (
+            "topic_update_partitions.sql",
+            include_sql!("topic_update_partitions.sql"),
+        ),
         (
             "topition_delete_by_topic.sql",
             include_sql!("topition_delete_by_topic.sql"),
@@ -230,6 +307,32 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
             "topition_select_id.sql",
             include_sql!("topition_select_id.sql"),
         ),
+        (
+            "topition_usage_delete_by_topic.sql",
+            include_sql!("topition_usage_delete_by_topic.sql"),
+        ),
+        (
+            "topition_usage_release.sql",
+            include_sql!("topition_usage_release.sql"),
+        ),
+        (
+            "topition_usage_select.sql",
+            include_sql!("topition_usage_select.sql"),
+        ),
+        (
+            "topition_usage_upsert.sql",
+            include_sql!("topition_usage_upsert.sql"),
+        ),
+        ("transaction_tag.sql", include_sql!("transaction_tag.sql")),
+        (
+            "txn_aborted_insert_from_txn.sql",
+            include_sql!("txn_aborted_insert_from_txn.sql"),
+        ),
+        (
+            "txn_aborted_select.sql",
+            include_sql!("txn_aborted_select.sql"),
+        ),
+        ("txn_describe.sql", include_sql!("txn_describe.sql")),
         (
             "txn_detail_insert.sql",
             include_sql!("txn_detail_insert.sql"),
@@ -328,6 +431,18 @@ pub(crate) static SQL: LazyLock<Cache> = LazyLock::new(|| {
             "txn_topition_select.sql",
             include_sql!("txn_topition_select.sql"),
         ),
+        (
+            "user_scram_credential_delete.sql",
+            include_sql!("user_scram_credential_delete.sql"),
+        ),
+        (
+            "user_scram_credential_select.sql",
+            include_sql!("user_scram_credential_select.sql"),
+        ),
+        (
+            "user_scram_credential_upsert.sql",
+            include_sql!("user_scram_credential_upsert.sql"),
+        ),
         (
             "watermark_delete_by_topic.sql",
             include_sql!("watermark_delete_by_topic.sql"),
@@ -386,7 +501,7 @@ pub(crate) fn idempotent_sequence_check(
 ) -> Result<i32> {
     match producer_epoch.cmp(&deflated.producer_epoch) {
         Ordering::Equal => match sequence.cmp(&deflated.base_sequence) {
-            Ordering::Equal => Ok(deflated.last_offset_delta + 1),
+            Ordering::Equal => Ok(next_sequence(*sequence, deflated.last_offset_delta + 1)),
 
             Ordering::Greater => {
                 debug!(?sequence, ?deflated.base_sequence);
@@ -417,6 +532,7 @@ where
 #[cfg(test)]
 mod tests {
     use crate::{Error, Result};
+    use tansu_sans_io::{ErrorCode, record::deflated};
     use tracing::subscriber::DefaultGuard;
     use tracing_subscriber::EnvFilter;
 
@@ -464,4 +580,61 @@ mod tests {
 
         Ok(())
     }
+
+    fn batch(base_sequence: i32, records: i32) -> deflated::Batch {
+        deflated::Batch {
+            producer_epoch: 0,
+            base_sequence,
+            last_offset_delta: records - 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn idempotent_sequence_check() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        assert_eq!(3, super::idempotent_sequence_check(&0, &0, &batch(0, 3))?);
+
+        assert!(matches!(
+            super::idempotent_sequence_check(&0, &3, &batch(0, 3)),
+            Err(Error::Api(ErrorCode::DuplicateSequenceNumber))
+        ));
+
+        assert!(matches!(
+            super::idempotent_sequence_check(&0, &3, &batch(5, 1)),
+            Err(Error::Api(ErrorCode::OutOfOrderSequenceNumber))
+        ));
+
+        assert!(matches!(
+            super::idempotent_sequence_check(&1, &3, &batch(3, 1)),
+            Err(Error::Api(ErrorCode::ProducerFenced))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn idempotent_sequence_wraps_after_max() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        assert_eq!(
+            i32::MAX,
+            super::idempotent_sequence_check(&0, &(i32::MAX - 3), &batch(i32::MAX - 3, 3))?
+        );
+
+        assert_eq!(
+            0,
+            super::idempotent_sequence_check(&0, &i32::MAX, &batch(i32::MAX, 1))?
+        );
+
+        assert_eq!(
+            1,
+            super::idempotent_sequence_check(&0, &(i32::MAX - 1), &batch(i32::MAX - 1, 3))?
+        );
+
+        assert_eq!(2, super::idempotent_sequence_check(&0, &1, &batch(1, 1))?);
+
+        Ok(())
+    }
 }
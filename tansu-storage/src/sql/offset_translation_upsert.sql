@@ -0,0 +1,34 @@
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+insert into offset_translation (topition, upstream_offset, local_offset)
+
+select tp.id, $4, $5
+
+from
+
+cluster c
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+
+where c.name = $1
+and t.name = $2
+and tp.partition = $3
+
+on conflict (topition, upstream_offset)
+
+do update set
+
+local_offset = excluded.local_offset,
+last_updated = excluded.last_updated;
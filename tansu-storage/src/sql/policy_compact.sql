@@ -39,4 +39,5 @@ compaction as (
 )
 
 delete from record
-where (record.topition, record.offset_id) in (select * from compaction);
+where (record.topition, record.offset_id) in (select * from compaction)
+returning record.topition, cast(coalesce(length(record.k), 0) + coalesce(length(record.v), 0) as bigint);
@@ -49,4 +49,5 @@ ancient as (
 )
 
 delete from record
-where (record.topition, record.offset_id) in (select * from ancient);
+where (record.topition, record.offset_id) in (select * from ancient)
+returning record.topition, cast(coalesce(length(record.k), 0) + coalesce(length(record.v), 0) as bigint);
@@ -0,0 +1,61 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+
+-- delete the oldest records of each topition with a delete cleanup policy
+-- until the records retained are within its retention.bytes
+with
+
+deletion as (
+    select tp.id as topition
+    from topition tp
+    join topic t on t.id = tp.topic
+    join cluster c on t.cluster = c.id
+    join topic_configuration tc on tc.topic = t.id
+    where c.name = $1
+    and tc.name = 'cleanup.policy'
+    and tc.value like '%delete%'
+),
+
+retention as (
+    select tp.id as topition, cast(tc.value as bigint) as bytes
+    from topition tp
+    join topic t on t.id = tp.topic
+    join cluster c on t.cluster = c.id
+    join topic_configuration tc on tc.topic = t.id
+    where c.name = $1
+    and tc.name = 'retention.bytes'
+    and cast(tc.value as bigint) >= 0
+),
+
+retained as (
+    select
+    r.topition,
+    r.offset_id,
+    sum(coalesce(length(r.k), 0) + coalesce(length(r.v), 0))
+        over (partition by r.topition order by r.offset_id desc) as bytes
+    from record r
+    join deletion del on del.topition = r.topition
+    join retention ret on ret.topition = r.topition
+),
+
+excess as (
+    select rtd.topition, rtd.offset_id
+    from retained rtd
+    join retention ret on ret.topition = rtd.topition
+    where rtd.bytes > ret.bytes
+)
+
+delete from record
+where (record.topition, record.offset_id) in (select * from excess)
+returning record.topition, cast(coalesce(length(record.k), 0) + coalesce(length(record.v), 0) as bigint);
@@ -37,5 +37,5 @@ on conflict (producer_epoch, topition)
 
 do update set
 
-sequence = producer_detail.sequence + $6,
+sequence = excluded.sequence,
 last_updated = excluded.last_updated
@@ -0,0 +1,24 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- the high watermark of each topition of a cluster, or of a single topic
+select tp.id, coalesce(w.high, 0)
+from cluster c
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+left join watermark w on w.topition = tp.id
+where c.name = $1
+and ($2::text is null or t.name = $2)
+order by tp.id;
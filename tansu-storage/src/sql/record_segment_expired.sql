@@ -0,0 +1,66 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+with
+
+deletion as (
+    select tp.id as topition
+    from topition tp
+    join topic t on t.id = tp.topic
+    join cluster c on t.cluster = c.id
+    join topic_configuration tc on tc.topic = t.id
+    where c.name = $1
+    and tc.name = 'cleanup.policy'
+    and tc.value like '%delete%'
+),
+
+retention as (
+    select tp.id as topition, tc.value
+    from topition tp
+    join topic t on t.id = tp.topic
+    join cluster c on t.cluster = c.id
+    left join topic_configuration tc on tc.topic = t.id
+    where c.name = $1
+    and tc.name = 'retention.ms'
+),
+
+segment as (
+    select del.topition, cast(substring(s.relname from '_(\d+)$') as bigint) as segment
+    from deletion del
+    join pg_class p on p.relname = 'record_' || del.topition
+    join pg_inherits i on i.inhparent = p.oid
+    join pg_class s on s.oid = i.inhrelid
+)
+
+-- a segment is expired once closed (below the high watermark), with its newest
+-- record older than the retention of the topic, or no records at all
+select s.topition, s.segment
+from segment s
+join watermark w on w.topition = s.topition
+left join retention ret on ret.topition = s.topition
+where (s.segment + 1) * $4 <= w.high
+and coalesce(
+    (
+        select (extract(epoch from cast($2 as timestamp)) - extract(epoch from r.timestamp)) > coalesce(cast(ret.value as integer) / 1000, $3)
+        from record r
+        where r.topition = s.topition
+        and r.offset_id >= s.segment * $4
+        and r.offset_id < (s.segment + 1) * $4
+        order by r.offset_id desc
+        limit 1
+    ),
+    true
+)
+order by s.topition, s.segment;
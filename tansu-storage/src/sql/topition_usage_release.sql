@@ -0,0 +1,36 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- release the usage of records deleted from a topition, with the oldest
+-- timestamp of the remaining records found by offset
+update topition_usage
+
+set
+
+records = records - $2,
+bytes = bytes - $3,
+
+oldest_timestamp = (
+    select cast(extract(epoch from r.timestamp) * 1000 as bigint)
+    from record r
+    where r.topition = topition_usage.topition
+    order by r.offset_id
+    limit 1
+),
+
+newest_timestamp = case when records > $2 then newest_timestamp end,
+last_updated = current_timestamp
+
+where topition = $1;
@@ -0,0 +1,51 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+insert into topition_usage (topition, records, bytes, oldest_timestamp, newest_timestamp)
+
+select tp.id, $4, $5, $6, $7
+
+from
+
+cluster c
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+
+where c.name = $1
+and t.name = $2
+and tp.partition = $3
+
+on conflict (topition)
+
+do update set
+
+records = topition_usage.records + excluded.records,
+bytes = topition_usage.bytes + excluded.bytes,
+
+oldest_timestamp = case
+    when topition_usage.oldest_timestamp is null
+    or excluded.oldest_timestamp < topition_usage.oldest_timestamp
+    then excluded.oldest_timestamp
+    else topition_usage.oldest_timestamp
+end,
+
+newest_timestamp = case
+    when topition_usage.newest_timestamp is null
+    or excluded.newest_timestamp > topition_usage.newest_timestamp
+    then excluded.newest_timestamp
+    else topition_usage.newest_timestamp
+end,
+
+last_updated = current_timestamp;
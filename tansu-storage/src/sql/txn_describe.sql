@@ -0,0 +1,45 @@
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+-- http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+
+-- the current (latest) detail of each transaction with the topitions
+-- added to it, optionally restricted to some transactional ids
+--
+select
+txn.name,
+p.id,
+pe.epoch,
+txn_d.transaction_timeout_ms,
+txn_d.started_at,
+txn_d.status,
+t.name,
+tp.partition
+
+from cluster c
+join txn on txn.cluster = c.id
+join producer p on p.id = txn.producer
+join txn_detail txn_d on txn_d."transaction" = txn.id
+join producer_epoch pe on pe.id = txn_d.producer_epoch
+left join txn_topition txn_tp on txn_tp.txn_detail = txn_d.id
+left join topition tp on tp.id = txn_tp.topition
+left join topic t on t.id = tp.topic
+
+where c.name = $1
+and ($2::text[] is null or txn.name = any($2::text[]))
+and txn_d.id = (
+    select max(latest.id)
+    from txn_detail latest
+    where latest."transaction" = txn.id
+)
+
+order by txn.name, t.name, tp.partition;
@@ -0,0 +1,139 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage operation tags
+//!
+//! A [`Tag`] describes the broker operation on whose behalf storage is being
+//! used, such as the API key of the protocol request and the topic involved.
+//! The tag is held in a task local for the duration of a request, so that
+//! storage may annotate the work it does: each Postgres transaction appends
+//! the tag to its `application_name`, so that slow queries in
+//! `pg_stat_activity` and the Postgres logs can be attributed to the request
+//! that issued them, without changing the text of any prepared statement.
+
+use std::fmt::{self, Display, Formatter};
+
+use url::form_urlencoded::byte_serialize;
+
+tokio::task_local! {
+    static TAG: Tag;
+}
+
+/// The broker operation using storage
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Tag {
+    api_key: Option<i16>,
+    topic: Option<String>,
+}
+
+impl Tag {
+    pub fn api_key(self, api_key: Option<i16>) -> Self {
+        Self { api_key, ..self }
+    }
+
+    pub fn topic(self, topic: Option<String>) -> Self {
+        Self { topic, ..self }
+    }
+
+    /// The tag of the current task, if any
+    pub fn current() -> Option<Self> {
+        TAG.try_with(Clone::clone).ok()
+    }
+
+    /// The tag of the current task, updated with a topic
+    pub fn with_topic(topic: &str) -> Self {
+        Self::current()
+            .unwrap_or_default()
+            .topic(Some(topic.to_owned()))
+    }
+
+    /// Run a future with this tag as the tag of the current task
+    pub async fn scope<F>(self, f: F) -> F::Output
+    where
+        F: Future,
+    {
+        TAG.scope(self, f).await
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.api_key.is_none() && self.topic.is_none()
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+
+        if let Some(api_key) = self.api_key {
+            write!(f, "api_key='{api_key}'")?;
+            separator = ",";
+        }
+
+        if let Some(ref topic) = self.topic {
+            write!(
+                f,
+                "{separator}topic='{}'",
+                byte_serialize(topic.as_bytes()).collect::<String>()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert!(Tag::default().is_empty());
+        assert_eq!("", Tag::default().to_string());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            "api_key='0',topic='orders'",
+            Tag::default()
+                .api_key(Some(0))
+                .topic(Some("orders".into()))
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn display_is_escaped() {
+        assert_eq!(
+            "topic='a*%2F'",
+            Tag::default().topic(Some("a*/".into())).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn scoped() {
+        assert_eq!(None, Tag::current());
+
+        let tag = Tag::default().api_key(Some(1));
+
+        assert_eq!(
+            Some(tag.clone().topic(Some("orders".into()))),
+            tag.clone()
+                .scope(async { Some(Tag::with_topic("orders")) })
+                .await
+        );
+
+        assert_eq!(None, Tag::current());
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::{Error, init_tracing};
+use tansu_sans_io::{AclOperation, AclPatternType, AclPermissionType, AclResourceType, ErrorCode};
+use tansu_storage::{AclBinding, AclBindingFilter, Storage as _, StorageContainer};
+use url::Url;
+
+mod common;
+
+fn binding(
+    resource_name: &str,
+    pattern_type: AclPatternType,
+    principal: &str,
+    operation: AclOperation,
+) -> AclBinding {
+    AclBinding {
+        resource_type: AclResourceType::Topic,
+        resource_name: resource_name.into(),
+        pattern_type,
+        principal: principal.into(),
+        host: AclBinding::WILDCARD.into(),
+        operation,
+        permission_type: AclPermissionType::Allow,
+    }
+}
+
+fn any() -> AclBindingFilter {
+    AclBindingFilter {
+        resource_type: AclResourceType::Any,
+        pattern_type: AclPatternType::Any,
+        operation: AclOperation::Any,
+        permission_type: AclPermissionType::Any,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn create_describe_delete() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let storage = StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(111)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(Url::parse("memory://tansu/")?)
+        .build()
+        .await?;
+
+    assert!(storage.acls().await?.is_empty());
+
+    let literal = binding(
+        "orders",
+        AclPatternType::Literal,
+        "User:alice",
+        AclOperation::Read,
+    );
+
+    let prefixed = binding(
+        "ord",
+        AclPatternType::Prefixed,
+        "User:bob",
+        AclOperation::Write,
+    );
+
+    let other = binding(
+        "payments",
+        AclPatternType::Literal,
+        "User:alice",
+        AclOperation::Read,
+    );
+
+    for acl in [&literal, &prefixed, &other, &literal] {
+        storage.create_acl(acl).await?;
+    }
+
+    assert_eq!(3, storage.acls().await?.len());
+
+    let alice = storage
+        .describe_acls(&AclBindingFilter {
+            principal: Some("User:alice".into()),
+            ..any()
+        })
+        .await?;
+    assert_eq!(2, alice.len());
+
+    let orders = storage
+        .describe_acls(&AclBindingFilter {
+            resource_name: Some("orders".into()),
+            pattern_type: AclPatternType::Match,
+            ..any()
+        })
+        .await?;
+    assert_eq!(2, orders.len());
+    assert!(orders.contains(&literal));
+    assert!(orders.contains(&prefixed));
+
+    let prefixes = storage
+        .describe_acls(&AclBindingFilter {
+            pattern_type: AclPatternType::Prefixed,
+            ..any()
+        })
+        .await?;
+    assert_eq!(vec![prefixed.clone()], prefixes);
+
+    assert_eq!(ErrorCode::None, storage.delete_acl(&literal).await?);
+    assert_eq!(
+        ErrorCode::ResourceNotFound,
+        storage.delete_acl(&literal).await?
+    );
+
+    let remaining = storage.acls().await?;
+    assert_eq!(2, remaining.len());
+    assert!(!remaining.contains(&literal));
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::{Error, init_tracing};
+use tansu_storage::{
+    ClientQuota, ClientQuotaEntity, ClientQuotaFilter, ClientQuotaFilterComponent,
+    ClientQuotaMatch, Storage as _, StorageContainer,
+};
+use url::Url;
+
+mod common;
+
+const PRODUCER_BYTE_RATE: &str = "producer_byte_rate";
+const CONSUMER_BYTE_RATE: &str = "consumer_byte_rate";
+
+fn entity(components: &[(&str, Option<&str>)]) -> ClientQuotaEntity {
+    components
+        .iter()
+        .map(|(entity_type, name)| ((*entity_type).into(), name.map(Into::into)))
+        .collect()
+}
+
+fn filter(components: &[(&str, ClientQuotaMatch)], strict: bool) -> ClientQuotaFilter {
+    ClientQuotaFilter {
+        components: components
+            .iter()
+            .map(|(entity_type, match_type)| ClientQuotaFilterComponent {
+                entity_type: (*entity_type).into(),
+                match_type: match_type.clone(),
+            })
+            .collect(),
+        strict,
+    }
+}
+
+#[tokio::test]
+async fn alter_describe() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let storage = StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(111)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(Url::parse("memory://tansu/")?)
+        .build()
+        .await?;
+
+    assert!(storage.client_quotas().await?.is_empty());
+
+    let alice = entity(&[(ClientQuotaEntity::USER, Some("alice"))]);
+    let default_user = entity(&[(ClientQuotaEntity::USER, None)]);
+    let alice_producer = entity(&[
+        (ClientQuotaEntity::USER, Some("alice")),
+        (ClientQuotaEntity::CLIENT_ID, Some("producer")),
+    ]);
+
+    storage
+        .alter_client_quota(&alice, PRODUCER_BYTE_RATE, Some(1_024.0))
+        .await?;
+    storage
+        .alter_client_quota(&alice, CONSUMER_BYTE_RATE, Some(2_048.0))
+        .await?;
+    storage
+        .alter_client_quota(&default_user, PRODUCER_BYTE_RATE, Some(512.0))
+        .await?;
+    storage
+        .alter_client_quota(&alice_producer, PRODUCER_BYTE_RATE, Some(256.0))
+        .await?;
+
+    assert_eq!(4, storage.client_quotas().await?.len());
+
+    // replacing an existing value
+    storage
+        .alter_client_quota(&alice, PRODUCER_BYTE_RATE, Some(4_096.0))
+        .await?;
+
+    let mut exact = storage
+        .describe_client_quotas(&filter(
+            &[(
+                ClientQuotaEntity::USER,
+                ClientQuotaMatch::Exact("alice".into()),
+            )],
+            true,
+        ))
+        .await?;
+    exact.sort_by(|a, b| a.key.cmp(&b.key));
+
+    assert_eq!(
+        vec![
+            ClientQuota {
+                entity: alice.clone(),
+                key: CONSUMER_BYTE_RATE.into(),
+                value: 2_048.0,
+            },
+            ClientQuota {
+                entity: alice.clone(),
+                key: PRODUCER_BYTE_RATE.into(),
+                value: 4_096.0,
+            },
+        ],
+        exact
+    );
+
+    assert_eq!(
+        vec![ClientQuota {
+            entity: default_user.clone(),
+            key: PRODUCER_BYTE_RATE.into(),
+            value: 512.0,
+        }],
+        storage
+            .describe_client_quotas(&filter(
+                &[(ClientQuotaEntity::USER, ClientQuotaMatch::Default)],
+                false,
+            ))
+            .await?
+    );
+
+    // not strict, includes the user and client id entity
+    assert_eq!(
+        4,
+        storage
+            .describe_client_quotas(&filter(
+                &[(ClientQuotaEntity::USER, ClientQuotaMatch::Specified)],
+                false,
+            ))
+            .await?
+            .len()
+    );
+
+    assert_eq!(
+        3,
+        storage
+            .describe_client_quotas(&filter(
+                &[(ClientQuotaEntity::USER, ClientQuotaMatch::Specified)],
+                true,
+            ))
+            .await?
+            .len()
+    );
+
+    // removing a value
+    storage
+        .alter_client_quota(&alice, CONSUMER_BYTE_RATE, None)
+        .await?;
+
+    // removing a value that doesn't exist
+    storage
+        .alter_client_quota(&alice, CONSUMER_BYTE_RATE, None)
+        .await?;
+
+    assert_eq!(3, storage.client_quotas().await?.len());
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Azure Blob Storage (`az://`) and Google Cloud Storage (`gs://`), run
+//! against azurite and fake-gcs with: `just test-cloud-storage`
+
+#![cfg(feature = "dynostore")]
+
+use crate::common::{Error, init_tracing};
+use bytes::Bytes;
+use tansu_sans_io::{
+    create_topics_request::CreatableTopic,
+    record::{Record, deflated, inflated},
+};
+use tansu_storage::{Storage as _, StorageContainer, Topition};
+use url::Url;
+use uuid::Uuid;
+
+mod common;
+
+fn batch(value: &'static [u8]) -> Result<deflated::Batch, Error> {
+    inflated::Batch::builder()
+        .record(Record::builder().value(Some(Bytes::from_static(value))))
+        .build()
+        .and_then(deflated::Batch::try_from)
+        .map_err(Into::into)
+}
+
+/// Brokers sharing a cluster produce to the same topition, with each
+/// watermark update conditional on the version last read by that broker
+async fn watermark_updates(url: Url) -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let cluster_id = format!("tansu-{}", Uuid::new_v4());
+
+    let mut brokers = vec![];
+
+    for node_id in [111, 112] {
+        brokers.push(
+            StorageContainer::builder()
+                .cluster_id(cluster_id.as_str())
+                .node_id(node_id)
+                .advertised_listener(Url::parse("tcp://localhost:9092")?)
+                .storage(url.clone())
+                .build()
+                .await?,
+        );
+    }
+
+    let topic = "abc";
+    let topition = Topition::new(topic, 0);
+
+    _ = brokers[0]
+        .create_topic(
+            CreatableTopic::default()
+                .name(topic.into())
+                .num_partitions(1)
+                .replication_factor(1)
+                .assignments(Some([].into()))
+                .configs(Some([].into())),
+            false,
+        )
+        .await?;
+
+    let mut offsets = vec![];
+
+    for broker in brokers.iter().chain(brokers.iter()) {
+        offsets.push(broker.produce(None, &topition, batch(b"pqr")?).await?);
+    }
+
+    assert_eq!(vec![0, 1, 2, 3], offsets);
+    assert_eq!(
+        4,
+        brokers[1].offset_stage(&topition).await?.high_watermark()
+    );
+
+    Ok(())
+}
+
+#[ignore = "requires azurite with AZURE_STORAGE_USE_EMULATOR=true"]
+#[tokio::test]
+async fn azure_watermark_updates() -> Result<(), Error> {
+    watermark_updates(Url::parse("az://tansu/")?).await
+}
+
+#[ignore = "requires fake-gcs with GOOGLE_SERVICE_ACCOUNT=etc/fake-gcs.json"]
+#[tokio::test]
+async fn gcs_watermark_updates() -> Result<(), Error> {
+    watermark_updates(Url::parse("gs://tansu/")?).await
+}
@@ -0,0 +1,76 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "slatedb")]
+
+use crate::common::{Error, init_tracing};
+use bytes::Bytes;
+use tansu_sans_io::{
+    create_topics_request::CreatableTopic,
+    record::{Record, deflated, inflated},
+};
+use tansu_storage::{Storage as _, StorageContainer, Topition};
+use url::Url;
+
+mod common;
+
+async fn storage(url: &Url) -> Result<StorageContainer, Error> {
+    StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(111)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(url.clone())
+        .build()
+        .await
+        .map_err(Into::into)
+}
+
+#[tokio::test]
+async fn records_survive_restart() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    let url = Url::parse(&format!("slatedb://{}", dir.path().display()))?;
+
+    let topic = "abc";
+    let topition = Topition::new(topic, 0);
+
+    {
+        let storage = storage(&url).await?;
+
+        _ = storage
+            .create_topic(
+                CreatableTopic::default()
+                    .name(topic.into())
+                    .num_partitions(1)
+                    .replication_factor(1)
+                    .assignments(Some([].into()))
+                    .configs(Some([].into())),
+                false,
+            )
+            .await?;
+
+        let batch = inflated::Batch::builder()
+            .record(Record::builder().value(Some(Bytes::from_static(b"pqr"))))
+            .build()
+            .and_then(deflated::Batch::try_from)?;
+
+        assert_eq!(0, storage.produce(None, &topition, batch).await?);
+    }
+
+    let storage = storage(&url).await?;
+    assert_eq!(1, storage.offset_stage(&topition).await?.high_watermark());
+
+    Ok(())
+}
@@ -15,6 +15,8 @@
 mod common;
 
 mod doctest_template {
+    use std::time::{Duration, Instant};
+
     use rama::{Context, Layer as _, Service as _, layer::MapStateLayer};
     use tansu_sans_io::{
         CreateTopicsRequest, ErrorCode, FetchRequest,
@@ -72,7 +74,7 @@ mod doctest_template {
 
         let fetch = {
             let storage = storage.clone();
-            MapStateLayer::new(|_| storage).into_layer(FetchService)
+            MapStateLayer::new(|_| storage).into_layer(FetchService::default())
         };
 
         let partition = 0;
@@ -105,4 +107,90 @@ mod doctest_template {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn answered_at_deadline() -> Result<(), Error> {
+        let _guard = init_tracing()?;
+
+        let storage = StorageContainer::builder()
+            .cluster_id("tansu")
+            .node_id(111)
+            .advertised_listener(Url::parse("tcp://localhost:9092")?)
+            .storage(Url::parse("memory://tansu/")?)
+            .build()
+            .await?;
+
+        let name = "answered_at_deadline";
+
+        let create_topic = {
+            let storage = storage.clone();
+            MapStateLayer::new(|_| storage).into_layer(CreateTopicsService)
+        };
+
+        let response = create_topic
+            .serve(
+                Context::default(),
+                CreateTopicsRequest::default()
+                    .topics(Some(vec![
+                        CreatableTopic::default()
+                            .name(name.into())
+                            .num_partitions(1)
+                            .replication_factor(1)
+                            .assignments(Some([].into()))
+                            .configs(Some([].into())),
+                    ]))
+                    .validate_only(Some(false)),
+            )
+            .await?;
+
+        let topics = response.topics.unwrap_or_default();
+        assert_eq!(ErrorCode::None, ErrorCode::try_from(topics[0].error_code)?);
+
+        // a client request timeout well within the max wait of the fetch
+        let request_timeout = Duration::from_millis(600);
+        let safety_margin = Duration::from_millis(100);
+
+        let fetch = {
+            let storage = storage.clone();
+            MapStateLayer::new(|_| storage).into_layer(
+                FetchService::default()
+                    .request_timeout(request_timeout)
+                    .safety_margin(safety_margin),
+            )
+        };
+
+        let start = Instant::now();
+
+        let response = fetch
+            .serve(
+                Context::default(),
+                FetchRequest::default()
+                    .topics(Some(
+                        [FetchTopic::default()
+                            .topic(Some(name.into()))
+                            .partitions(Some([FetchPartition::default().partition(0)].into()))]
+                        .into(),
+                    ))
+                    .min_bytes(1)
+                    .max_bytes(Some(1_024))
+                    .max_wait_ms(30_000),
+            )
+            .await?;
+
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= request_timeout - safety_margin, "{elapsed:?}");
+        assert!(elapsed < request_timeout, "{elapsed:?}");
+
+        let topics = response.responses.as_deref().unwrap_or_default();
+        assert_eq!(1, topics.len());
+        let partitions = topics[0].partitions.as_deref().unwrap_or_default();
+        assert_eq!(1, partitions.len());
+        assert_eq!(
+            ErrorCode::None,
+            ErrorCode::try_from(partitions[0].error_code)?
+        );
+
+        Ok(())
+    }
 }
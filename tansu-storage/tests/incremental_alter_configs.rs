@@ -69,8 +69,8 @@ async fn req() -> Result<(), Error> {
         ErrorCode::try_from(response.topics.unwrap_or_default()[0].error_code)?
     );
 
-    let config_name = "x.y.z";
-    let config_value = "pqr";
+    let config_name = "retention.ms";
+    let config_value = "86400000";
 
     let describe_configs = {
         let storage = storage.clone();
@@ -145,3 +145,159 @@ async fn req() -> Result<(), Error> {
 
     Ok(())
 }
+
+async fn alter(
+    resource_name: &str,
+    config_name: &str,
+    config_value: &str,
+    validate_only: bool,
+) -> Result<(ErrorCode, Option<String>, Option<String>), Error> {
+    let storage = StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(111)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(Url::parse("memory://tansu/")?)
+        .build()
+        .await?;
+
+    let response = MapStateLayer::new(|_| storage.clone())
+        .into_layer(CreateTopicsService)
+        .serve(
+            Context::default(),
+            CreateTopicsRequest::default().topics(Some(
+                [CreatableTopic::default()
+                    .name(resource_name.into())
+                    .num_partitions(1)
+                    .replication_factor(1)]
+                .into(),
+            )),
+        )
+        .await?;
+
+    assert_eq!(
+        ErrorCode::None,
+        ErrorCode::try_from(response.topics.unwrap_or_default()[0].error_code)?
+    );
+
+    let response = MapStateLayer::new(|_| storage.clone())
+        .into_layer(IncrementalAlterConfigsService)
+        .serve(
+            Context::default(),
+            IncrementalAlterConfigsRequest::default()
+                .resources(Some(
+                    [AlterConfigsResource::default()
+                        .resource_name(resource_name.into())
+                        .resource_type(ConfigResource::Topic.into())
+                        .configs(Some(
+                            [AlterableConfig::default()
+                                .config_operation(OpType::Set.into())
+                                .name(config_name.into())
+                                .value(Some(config_value.into()))]
+                            .into(),
+                        ))]
+                    .into(),
+                ))
+                .validate_only(validate_only),
+        )
+        .await?;
+
+    let responses = response.responses.unwrap_or_default();
+    assert_eq!(1, responses.len());
+
+    let response = MapStateLayer::new(|_| storage)
+        .into_layer(DescribeConfigsService)
+        .serve(
+            Context::default(),
+            DescribeConfigsRequest::default()
+                .include_documentation(Some(false))
+                .include_synonyms(Some(false))
+                .resources(Some(
+                    [DescribeConfigsResource::default()
+                        .resource_name(resource_name.into())
+                        .resource_type(ConfigResource::Topic.into())
+                        .configuration_keys(Some([config_name.into()].into()))]
+                    .into(),
+                )),
+        )
+        .await?;
+
+    let value = response
+        .results
+        .unwrap_or_default()
+        .first()
+        .and_then(|result| result.configs.as_deref())
+        .and_then(|configs| configs.first())
+        .and_then(|config| config.value.clone());
+
+    Ok((
+        ErrorCode::try_from(responses[0].error_code)?,
+        responses[0].error_message.clone(),
+        value,
+    ))
+}
+
+#[tokio::test]
+async fn unknown_config() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    assert_eq!(
+        (
+            ErrorCode::InvalidConfig,
+            Some("Unknown topic config name: x.y.z".into()),
+            None
+        ),
+        alter("abcba", "x.y.z", "pqr", false).await?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn out_of_range() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    assert_eq!(
+        (
+            ErrorCode::InvalidConfig,
+            Some(
+                "Invalid value 2 for configuration min.cleanable.dirty.ratio: \
+                 Value must be no more than 1"
+                    .into()
+            ),
+            None
+        ),
+        alter("abcba", "min.cleanable.dirty.ratio", "2", false).await?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn validate_only() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    assert_eq!(
+        (ErrorCode::None, None, None),
+        alter("abcba", "retention.ms", "86400000", true).await?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sensitive_is_write_only() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let (error_code, _, value) = alter(
+        "abcba",
+        "tansu.link.upstream",
+        "secret://env/TANSU_SECRET_UPSTREAM_URL",
+        false,
+    )
+    .await?;
+
+    assert_eq!(ErrorCode::None, error_code);
+    assert_eq!(None, value);
+
+    Ok(())
+}
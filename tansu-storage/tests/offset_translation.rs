@@ -0,0 +1,69 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::{Error, init_tracing};
+use tansu_storage::{OffsetTranslation, Storage as _, StorageContainer, Topition};
+use url::Url;
+
+mod common;
+
+#[tokio::test]
+async fn checkpoint_and_translate() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let storage = StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(111)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(Url::parse("memory://tansu/")?)
+        .build()
+        .await?;
+
+    let topition = Topition::new("abc", 0);
+
+    assert_eq!(None, storage.offset_translation(&topition, 1_000).await?);
+
+    storage
+        .checkpoint_offset_translation(&topition, OffsetTranslation::new(100, 0))
+        .await?;
+
+    storage
+        .checkpoint_offset_translation(&topition, OffsetTranslation::new(250, 120))
+        .await?;
+
+    assert_eq!(None, storage.offset_translation(&topition, 99).await?);
+
+    let checkpoint = storage.offset_translation(&topition, 100).await?;
+    assert_eq!(Some(OffsetTranslation::new(100, 0)), checkpoint);
+    assert_eq!(
+        Some(42),
+        checkpoint.map(|checkpoint| checkpoint.translate(142))
+    );
+
+    let checkpoint = storage.offset_translation(&topition, 300).await?;
+    assert_eq!(Some(OffsetTranslation::new(250, 120)), checkpoint);
+    assert_eq!(
+        Some(170),
+        checkpoint.map(|checkpoint| checkpoint.translate(300))
+    );
+
+    assert_eq!(
+        None,
+        storage
+            .offset_translation(&Topition::new("abc", 1), 300)
+            .await?
+    );
+
+    Ok(())
+}
@@ -31,7 +31,7 @@ use tansu_sans_io::{
 };
 use tansu_storage::{
     CreateTopicsService, DeleteTopicsService, InitProducerIdService, ListOffsetsService,
-    ProduceService, StorageContainer,
+    ProduceService, ProducerIdResponse, Storage as _, StorageContainer, Topition,
 };
 use tracing::debug;
 use url::Url;
@@ -945,3 +945,321 @@ mod doctest_template {
         Ok(())
     }
 }
+
+async fn replicated_storage(storage: &str) -> Result<StorageContainer, Error> {
+    const HOST: &str = "localhost";
+    const PORT: i32 = 9092;
+    const NODE_ID: i32 = 111;
+
+    StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(NODE_ID)
+        .advertised_listener(Url::parse(&format!("tcp://{HOST}:{PORT}"))?)
+        .storage(Url::parse(storage)?)
+        .build()
+        .await
+        .map_err(Into::into)
+}
+
+async fn replicated_topic(storage: &StorageContainer) -> Result<Topition, Error> {
+    let create_topic = {
+        let storage = storage.clone();
+        MapStateLayer::new(|_| storage).into_layer(CreateTopicsService)
+    };
+
+    let name = rng()
+        .sample_iter(&Alphanumeric)
+        .take(15)
+        .map(char::from)
+        .collect::<String>();
+
+    let response = create_topic
+        .serve(
+            Context::default(),
+            CreateTopicsRequest::default()
+                .validate_only(Some(false))
+                .topics(Some(
+                    [CreatableTopic::default()
+                        .name(name.clone())
+                        .num_partitions(1)
+                        .replication_factor(1)
+                        .assignments(Some([].into()))
+                        .configs(Some([].into()))]
+                    .into(),
+                )),
+        )
+        .await?;
+
+    let topics = response.topics.as_deref().unwrap_or_default();
+    assert_eq!(1, topics.len());
+    assert_eq!(ErrorCode::None, ErrorCode::try_from(topics[0].error_code)?);
+
+    Ok(Topition::new(name, 0))
+}
+
+fn upstream(base_offset: i64, values: &[&'static [u8]]) -> Result<deflated::Batch, Error> {
+    (0..)
+        .zip(values)
+        .fold(
+            inflated::Batch::builder()
+                .base_offset(base_offset)
+                .producer_id(54_345)
+                .producer_epoch(1)
+                .base_sequence(0),
+            |builder, (delta, value)| {
+                builder.record(
+                    Record::builder()
+                        .offset_delta(delta)
+                        .value(Bytes::from_static(value).into()),
+                )
+            },
+        )
+        .build()
+        .and_then(deflated::Batch::try_from)
+        .map_err(Into::into)
+}
+
+/// Batches replicated either side of a gap in the upstream offsets, as left
+/// by compaction or by transaction markers, retain their upstream offsets
+async fn replicate_upstream_gap(storage: StorageContainer) -> Result<(), Error> {
+    let topition = replicated_topic(&storage).await?;
+
+    assert_eq!(
+        100,
+        storage
+            .replicate(&topition, upstream(100, &[b"lorem", b"ipsum"])?)
+            .await?
+    );
+    assert_eq!(102, storage.offset_stage(&topition).await?.high_watermark());
+
+    assert_eq!(
+        110,
+        storage
+            .replicate(&topition, upstream(110, &[b"dolor"])?)
+            .await?
+    );
+    assert_eq!(111, storage.offset_stage(&topition).await?.high_watermark());
+
+    let fetched = storage
+        .fetch(&topition, 102, 1, 1_024, IsolationLevel::ReadUncommitted)
+        .await?;
+    assert_eq!(1, fetched.len());
+    assert_eq!(110, fetched[0].base_offset);
+
+    let fetched = storage
+        .fetch(&topition, 100, 1, 1_024, IsolationLevel::ReadUncommitted)
+        .await?;
+    assert_eq!(100, fetched[0].base_offset);
+    assert_eq!(2, fetched[0].record_count);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replicate_upstream_gap_memory() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+    replicate_upstream_gap(replicated_storage("memory://tansu/").await?).await
+}
+
+#[cfg(feature = "slatedb")]
+#[tokio::test]
+async fn replicate_upstream_gap_slatedb() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    replicate_upstream_gap(
+        replicated_storage(&format!("slatedb://{}", dir.path().display())).await?,
+    )
+    .await
+}
+
+#[cfg(feature = "libsql")]
+#[tokio::test]
+async fn replicate_upstream_gap_libsql() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    replicate_upstream_gap(
+        replicated_storage(&format!("sqlite://{}/tansu.db", dir.path().display())).await?,
+    )
+    .await
+}
+
+#[cfg(feature = "turso")]
+#[tokio::test]
+async fn replicate_upstream_gap_turso() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    replicate_upstream_gap(
+        replicated_storage(&format!("turso://{}/tansu.db", dir.path().display())).await?,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn replicate_preserves_upstream_offsets() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let storage = replicated_storage("memory://tansu/").await?;
+
+    let topition = replicated_topic(&storage).await?;
+
+    let upstream = upstream(32_123, &[b"lorem", b"ipsum"])?;
+
+    assert_eq!(
+        32_123,
+        storage.replicate(&topition, upstream.clone()).await?
+    );
+
+    let offset_stage = storage.offset_stage(&topition).await?;
+    assert_eq!(32_125, offset_stage.high_watermark());
+
+    // replicating the same batch again is ignored
+    assert_eq!(32_123, storage.replicate(&topition, upstream).await?);
+    assert_eq!(
+        32_125,
+        storage.offset_stage(&topition).await?.high_watermark()
+    );
+
+    let fetched = storage
+        .fetch(&topition, 32_123, 1, 1_024, IsolationLevel::ReadUncommitted)
+        .await?;
+    assert_eq!(1, fetched.len());
+    assert_eq!(32_123, fetched[0].base_offset);
+    assert_eq!(2, fetched[0].record_count);
+
+    Ok(())
+}
+
+fn sequenced(
+    producer: &ProducerIdResponse,
+    base_sequence: i32,
+    values: &[&'static [u8]],
+) -> Result<deflated::Batch, Error> {
+    (0..)
+        .zip(values)
+        .fold(
+            inflated::Batch::builder()
+                .producer_id(producer.id)
+                .producer_epoch(producer.epoch)
+                .base_sequence(base_sequence),
+            |builder, (delta, value)| {
+                builder.record(
+                    Record::builder()
+                        .offset_delta(delta)
+                        .value(Bytes::from_static(value).into()),
+                )
+            },
+        )
+        .build()
+        .and_then(deflated::Batch::try_from)
+        .map_err(Into::into)
+}
+
+/// The sequence of an idempotent producer advances by the records of each
+/// batch, with a replayed batch rejected as a duplicate and a gap rejected as
+/// out of order
+async fn idempotent_sequence(storage: StorageContainer) -> Result<(), Error> {
+    let topition = replicated_topic(&storage).await?;
+
+    let producer = storage.init_producer(None, 0, Some(-1), Some(-1)).await?;
+
+    assert_eq!(
+        0,
+        storage
+            .produce(None, &topition, sequenced(&producer, 0, &[b"a", b"b"])?)
+            .await?
+    );
+
+    assert_eq!(
+        2,
+        storage
+            .produce(
+                None,
+                &topition,
+                sequenced(&producer, 2, &[b"c", b"d", b"e"])?
+            )
+            .await?
+    );
+
+    assert_eq!(
+        5,
+        storage
+            .produce(None, &topition, sequenced(&producer, 5, &[b"f"])?)
+            .await?
+    );
+
+    assert!(matches!(
+        storage
+            .produce(
+                None,
+                &topition,
+                sequenced(&producer, 2, &[b"c", b"d", b"e"])?
+            )
+            .await,
+        Err(tansu_storage::Error::Api(
+            ErrorCode::DuplicateSequenceNumber
+        ))
+    ));
+
+    assert!(matches!(
+        storage
+            .produce(None, &topition, sequenced(&producer, 8, &[b"g"])?)
+            .await,
+        Err(tansu_storage::Error::Api(
+            ErrorCode::OutOfOrderSequenceNumber
+        ))
+    ));
+
+    assert_eq!(6, storage.offset_stage(&topition).await?.high_watermark());
+
+    assert_eq!(
+        6,
+        storage
+            .produce(None, &topition, sequenced(&producer, 6, &[b"g"])?)
+            .await?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn idempotent_sequence_memory() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+    idempotent_sequence(replicated_storage("memory://tansu/").await?).await
+}
+
+#[cfg(feature = "slatedb")]
+#[tokio::test]
+async fn idempotent_sequence_slatedb() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    idempotent_sequence(replicated_storage(&format!("slatedb://{}", dir.path().display())).await?)
+        .await
+}
+
+#[cfg(feature = "libsql")]
+#[tokio::test]
+async fn idempotent_sequence_libsql() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    idempotent_sequence(
+        replicated_storage(&format!("sqlite://{}/tansu.db", dir.path().display())).await?,
+    )
+    .await
+}
+
+#[cfg(feature = "turso")]
+#[tokio::test]
+async fn idempotent_sequence_turso() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let dir = tempfile::tempdir()?;
+    idempotent_sequence(
+        replicated_storage(&format!("turso://{}/tansu.db", dir.path().display())).await?,
+    )
+    .await
+}
@@ -0,0 +1,180 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::{Error, init_tracing};
+use bytes::Bytes;
+use rama::{Context, Layer as _, Service, layer::MapStateLayer};
+use tansu_sans_io::{
+    AlterUserScramCredentialsRequest, DescribeUserScramCredentialsRequest, ErrorCode,
+    alter_user_scram_credentials_request::{ScramCredentialDeletion, ScramCredentialUpsertion},
+    describe_user_scram_credentials_request::UserName,
+};
+use tansu_storage::{
+    AlterUserScramCredentialsService, DescribeUserScramCredentialsService, ScramMechanism,
+    Storage as _, StorageContainer,
+};
+use url::Url;
+
+mod common;
+
+fn upsertion(
+    name: &str,
+    mechanism: ScramMechanism,
+    iterations: i32,
+) -> Result<ScramCredentialUpsertion, Error> {
+    let salt = Bytes::from_static(b"pepper");
+
+    mechanism
+        .salted_password(b"secret", &salt[..], iterations)
+        .map(|salted_password| {
+            ScramCredentialUpsertion::default()
+                .name(name.into())
+                .mechanism(mechanism.into())
+                .iterations(iterations)
+                .salt(salt)
+                .salted_password(salted_password)
+        })
+        .map_err(Into::into)
+}
+
+#[tokio::test]
+async fn alter_describe_delete() -> Result<(), Error> {
+    let _guard = init_tracing()?;
+
+    let storage = StorageContainer::builder()
+        .cluster_id("tansu")
+        .node_id(111)
+        .advertised_listener(Url::parse("tcp://localhost:9092")?)
+        .storage(Url::parse("memory://tansu/")?)
+        .build()
+        .await?;
+
+    let alter = {
+        let storage = storage.clone();
+        MapStateLayer::new(|_| storage).into_layer(AlterUserScramCredentialsService)
+    };
+
+    let describe = {
+        let storage = storage.clone();
+        MapStateLayer::new(|_| storage).into_layer(DescribeUserScramCredentialsService)
+    };
+
+    let response = alter
+        .serve(
+            Context::default(),
+            AlterUserScramCredentialsRequest::default()
+                .deletions(Some([].into()))
+                .upsertions(Some(
+                    [
+                        upsertion("alice", ScramMechanism::Sha256, 4_096)?,
+                        upsertion("alice", ScramMechanism::Sha512, 8_192)?,
+                        upsertion("bob", ScramMechanism::Sha256, 1_024)?,
+                    ]
+                    .into(),
+                )),
+        )
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(2, results.len());
+    assert_eq!("alice", results[0].user.as_str());
+    assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+    assert_eq!("bob", results[1].user.as_str());
+    assert_eq!(
+        ErrorCode::UnacceptableCredential,
+        ErrorCode::try_from(results[1].error_code)?
+    );
+
+    let credential = storage
+        .user_scram_credential("alice", ScramMechanism::Sha512)
+        .await?
+        .expect("credential");
+    assert_eq!(8_192, credential.iterations);
+    assert_eq!(Bytes::from_static(b"pepper"), credential.salt);
+
+    let response = describe
+        .serve(
+            Context::default(),
+            DescribeUserScramCredentialsRequest::default().users(Some(
+                [
+                    UserName::default().name("alice".into()),
+                    UserName::default().name("bob".into()),
+                ]
+                .into(),
+            )),
+        )
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(2, results.len());
+    assert_eq!("alice", results[0].user.as_str());
+    assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+
+    let infos = results[0].credential_infos.as_deref().unwrap_or_default();
+    assert_eq!(2, infos.len());
+    assert_eq!(i8::from(ScramMechanism::Sha256), infos[0].mechanism);
+    assert_eq!(4_096, infos[0].iterations);
+    assert_eq!(i8::from(ScramMechanism::Sha512), infos[1].mechanism);
+    assert_eq!(8_192, infos[1].iterations);
+
+    assert_eq!("bob", results[1].user.as_str());
+    assert_eq!(
+        ErrorCode::ResourceNotFound,
+        ErrorCode::try_from(results[1].error_code)?
+    );
+
+    let response = alter
+        .serve(
+            Context::default(),
+            AlterUserScramCredentialsRequest::default()
+                .deletions(Some(
+                    [
+                        ScramCredentialDeletion::default()
+                            .name("alice".into())
+                            .mechanism(ScramMechanism::Sha256.into()),
+                        ScramCredentialDeletion::default()
+                            .name("bob".into())
+                            .mechanism(ScramMechanism::Sha256.into()),
+                    ]
+                    .into(),
+                ))
+                .upsertions(Some([].into())),
+        )
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(2, results.len());
+    assert_eq!(ErrorCode::None, ErrorCode::try_from(results[0].error_code)?);
+    assert_eq!(
+        ErrorCode::ResourceNotFound,
+        ErrorCode::try_from(results[1].error_code)?
+    );
+
+    let response = describe
+        .serve(
+            Context::default(),
+            DescribeUserScramCredentialsRequest::default().users(None),
+        )
+        .await?;
+
+    let results = response.results.unwrap_or_default();
+    assert_eq!(1, results.len());
+    assert_eq!("alice", results[0].user.as_str());
+
+    let infos = results[0].credential_infos.as_deref().unwrap_or_default();
+    assert_eq!(1, infos.len());
+    assert_eq!(i8::from(ScramMechanism::Sha512), infos[0].mechanism);
+
+    Ok(())
+}
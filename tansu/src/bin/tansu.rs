@@ -40,6 +40,10 @@ async fn main() -> Result<ErrorCode> {
             _ => error!("{}", error_code),
         })
         .inspect_err(|err| match err {
+            tansu_cli::Error::Bench(error) => match error {
+                tansu_bench::Error::Client(_) => error!("{}", CLIENT_ERROR_MESSAGE),
+                _ => error!("Unknown error occurred during command: {}", error),
+            },
             tansu_cli::Error::Cat(error) => match &**error {
                 tansu_cat::Error::Client(_) => error!("{}", CLIENT_ERROR_MESSAGE),
                 _ => error!("Unknown error occurred during command: {}", error),
@@ -56,6 +60,13 @@ async fn main() -> Result<ErrorCode> {
                 tansu_proxy::Error::Client(_) => error!("{}", CLIENT_ERROR_MESSAGE),
                 _ => error!("Unknown error occurred during command: {}", error),
             },
+            tansu_cli::Error::Soak(error) => match error {
+                tansu_soak::Error::Client(_) => error!("{}", CLIENT_ERROR_MESSAGE),
+                tansu_soak::Error::Violation(violations) => {
+                    error!("Soak test failed with {violations} invariant violation(s)")
+                }
+                _ => error!("Unknown error occurred during command: {}", error),
+            },
             tansu_cli::Error::Topic(error) => match error {
                 tansu_topic::Error::Client(_) => error!("{}", CLIENT_ERROR_MESSAGE),
                 _ => error!("Unknown error occurred during command: {}", error),